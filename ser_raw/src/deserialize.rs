@@ -0,0 +1,93 @@
+use crate::Deserializer;
+
+/// Trait for types which can be deserialized from output of
+/// [`PureCopySerializer`].
+///
+/// Counterpart to [`Serialize`]. Usually implemented with the derive macro.
+///
+/// # With derive macro
+///
+/// ```
+/// use ser_raw::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Foo {
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
+/// }
+/// ```
+///
+/// # Manual implementation
+///
+/// [`Deserialize`] has only one method: [`deserialize_data`].
+///
+/// By the time [`deserialize_data`] is called, the value itself has already
+/// been copied out of the input, bit for bit. But any pointers it contains
+/// still hold the addresses they had at time of serialization, so are not
+/// valid. [`deserialize_data`] must read the data the value owns *outside of
+/// its own memory allocation* from the deserializer, and replace every pointer
+/// with one to that freshly read data.
+///
+/// It must read that data in exactly the same order as [`serialize_data`]
+/// wrote it.
+///
+/// The invalid pointers must be replaced with [`std::ptr::write`] rather than
+/// assignment, so the invalid values are not dropped.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{Deserialize, Deserializer};
+///
+/// struct Foo {
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
+/// }
+///
+/// impl<D> Deserialize<D> for Foo
+/// where D: Deserializer
+/// {
+///     unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+///         self.smalls.deserialize_data(deserializer);
+///         self.bigs.deserialize_data(deserializer);
+///     }
+/// }
+/// ```
+///
+/// [`deserialize_data`]: Deserialize::deserialize_data
+/// [`serialize_data`]: crate::Serialize::serialize_data
+/// [`Serialize`]: crate::Serialize
+/// [`PureCopySerializer`]: crate::PureCopySerializer
+pub trait Deserialize<De: Deserializer> {
+	/// Deserialize data owned by this value, outside value's own memory
+	/// allocation.
+	///
+	/// See [`Deserialize`] trait for more details.
+	///
+	/// # Safety
+	///
+	/// `self` must be a bitwise copy of a value which was serialized, read from
+	/// the deserializer's input. Pointers within it are not valid, and must not
+	/// be dereferenced or dropped.
+	unsafe fn deserialize_data(&mut self, deserializer: &mut De);
+}
+
+/// Trait for implementing an equivalent of [`Deserialize`] on foreign types for
+/// which it's not possible to implement [`Deserialize`] directly due to orphan
+/// rules. Counterpart to [`SerializeWith`].
+///
+/// The same type used with `#[ser_with]` should implement both
+/// [`SerializeWith`] and [`DeserializeWith`].
+///
+/// [`SerializeWith`]: crate::SerializeWith
+pub trait DeserializeWith<T, De: Deserializer> {
+	/// Deserialize data owned by this value, outside value's own memory
+	/// allocation.
+	///
+	/// See [`Deserialize`] trait for more details.
+	///
+	/// # Safety
+	///
+	/// Same requirements as [`Deserialize::deserialize_data`].
+	unsafe fn deserialize_data_with(value: &mut T, deserializer: &mut De);
+}
@@ -0,0 +1,38 @@
+use num_bigint::{BigInt, BigUint};
+
+use crate::{
+	serialize_impls::{bigint::bigint_data_offset, ptrs::SameSizeAndAlignment},
+	Deserialize, Deserializer,
+};
+
+// `BigUint` is just a wrapper around a `Vec<usize>`.
+// See `Serialize` implementation.
+impl<D> Deserialize<D> for BigUint
+where D: Deserializer
+{
+	#[inline]
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		#[allow(clippy::let_unit_value)]
+		let _ = SameSizeAndAlignment::<BigUint, Vec<usize>>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
+
+		let vec = &mut *(self as *mut BigUint as *mut Vec<usize>);
+		vec.deserialize_data(deserializer);
+	}
+}
+
+// `BigInt` is defined as `BigInt { sign: Sign, data: BigUint }`.
+// See `Serialize` implementation.
+impl<D> Deserialize<D> for BigInt
+where D: Deserializer
+{
+	#[inline]
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		#[allow(clippy::let_unit_value)]
+		let _ = SameSizeAndAlignment::<BigInt, (u8, BigUint)>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
+
+		let data_offset = bigint_data_offset();
+		let ptr = self as *mut BigInt as *mut u8;
+		let biguint = &mut *(ptr.add(data_offset) as *mut BigUint);
+		biguint.deserialize_data(deserializer);
+	}
+}
@@ -0,0 +1,97 @@
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+	hash::{BuildHasher, Hash},
+	ptr,
+};
+
+use super::ptrs::read_vec;
+use crate::{serialize_impls::collections::MapEntry, Deserialize, Deserializer};
+
+impl<K, V, D> Deserialize<D> for MapEntry<K, V>
+where
+	D: Deserializer,
+	K: Deserialize<D>,
+	V: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		self.key.deserialize_data(deserializer);
+		self.value.deserialize_data(deserializer);
+	}
+}
+
+impl<K, V, H, D> Deserialize<D> for HashMap<K, V, H>
+where
+	D: Deserializer,
+	K: Deserialize<D> + Eq + Hash,
+	V: Deserialize<D>,
+	H: BuildHasher + Default,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let entries = read_entries::<MapEntry<K, V>, D>(deserializer);
+		let map = entries
+			.into_iter()
+			.map(|entry| (entry.key, entry.value))
+			.collect();
+		ptr::write(self, map);
+	}
+}
+
+impl<K, V, D> Deserialize<D> for BTreeMap<K, V>
+where
+	D: Deserializer,
+	K: Deserialize<D> + Ord,
+	V: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let entries = read_entries::<MapEntry<K, V>, D>(deserializer);
+		let map = entries
+			.into_iter()
+			.map(|entry| (entry.key, entry.value))
+			.collect();
+		ptr::write(self, map);
+	}
+}
+
+impl<T, H, D> Deserialize<D> for HashSet<T, H>
+where
+	D: Deserializer,
+	T: Deserialize<D> + Eq + Hash,
+	H: BuildHasher + Default,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let set = read_entries::<T, D>(deserializer).into_iter().collect();
+		ptr::write(self, set);
+	}
+}
+
+impl<T, D> Deserialize<D> for BTreeSet<T>
+where
+	D: Deserializer,
+	T: Deserialize<D> + Ord,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let set = read_entries::<T, D>(deserializer).into_iter().collect();
+		ptr::write(self, set);
+	}
+}
+
+impl<T, D> Deserialize<D> for VecDeque<T>
+where
+	D: Deserializer,
+	T: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		// Contents were written contiguously, in same format as a `Vec`.
+		// `VecDeque::len` only reads `len` field, without dereferencing pointer.
+		let vec = read_vec(self.len(), deserializer);
+		ptr::write(self, VecDeque::from(vec));
+	}
+}
+
+/// Read entries of a map or set.
+///
+/// Entries were written as a `Box<[T]>`, followed by the entries themselves.
+#[inline]
+unsafe fn read_entries<T: Deserialize<D>, D: Deserializer>(deserializer: &mut D) -> Vec<T> {
+	deserializer.deserialize_value::<Box<[T]>>().into_vec()
+}
@@ -0,0 +1,31 @@
+use std::ptr;
+
+use compact_str::CompactString;
+
+use crate::{Deserialize, Deserializer};
+
+// Inline strings are entirely within the `CompactString`. Content of heap
+// strings was written to output same as a `String`'s.
+// See `Serialize` implementation.
+impl<D> Deserialize<D> for CompactString
+where D: Deserializer
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		if !self.is_heap_allocated() {
+			return;
+		}
+
+		// Heap pointer is not valid, but length is.
+		// Empty strings have nothing written to output.
+		let len = self.len();
+		let mut bytes = Vec::<u8>::new();
+		if len > 0 {
+			bytes.reserve_exact(len);
+			deserializer.read_raw_slice(bytes.as_mut_ptr(), len);
+			bytes.set_len(len);
+		}
+
+		let s = String::from_utf8(bytes).expect("Invalid UTF-8 in `CompactString`");
+		ptr::write(self, CompactString::from(s));
+	}
+}
@@ -0,0 +1,13 @@
+use crate::{Deserialize, Deserializer};
+
+impl<T, D> Deserialize<D> for Option<T>
+where
+	D: Deserializer,
+	T: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		if let Some(value) = self {
+			value.deserialize_data(deserializer);
+		}
+	}
+}
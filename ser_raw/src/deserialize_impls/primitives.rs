@@ -0,0 +1,65 @@
+use std::{cmp, num, sync::atomic, time::Duration};
+
+use crate::{Deserialize, Deserializer};
+
+macro_rules! impl_primitive {
+	($ty:ty) => {
+		impl<D: Deserializer> Deserialize<D> for $ty {
+			#[inline(always)]
+			unsafe fn deserialize_data(&mut self, _deserializer: &mut D) {}
+		}
+	};
+}
+
+impl_primitive!(u8);
+impl_primitive!(u16);
+impl_primitive!(u32);
+impl_primitive!(u64);
+impl_primitive!(u128);
+impl_primitive!(usize);
+
+impl_primitive!(i8);
+impl_primitive!(i16);
+impl_primitive!(i32);
+impl_primitive!(i64);
+impl_primitive!(i128);
+impl_primitive!(isize);
+
+impl_primitive!(num::NonZeroU8);
+impl_primitive!(num::NonZeroU16);
+impl_primitive!(num::NonZeroU32);
+impl_primitive!(num::NonZeroU64);
+impl_primitive!(num::NonZeroU128);
+impl_primitive!(num::NonZeroUsize);
+
+impl_primitive!(num::NonZeroI8);
+impl_primitive!(num::NonZeroI16);
+impl_primitive!(num::NonZeroI32);
+impl_primitive!(num::NonZeroI64);
+impl_primitive!(num::NonZeroI128);
+impl_primitive!(num::NonZeroIsize);
+
+impl_primitive!(f32);
+impl_primitive!(f64);
+
+impl_primitive!(bool);
+impl_primitive!(char);
+
+impl_primitive!(());
+
+impl_primitive!(cmp::Ordering);
+impl_primitive!(Duration);
+
+impl_primitive!(atomic::AtomicU8);
+impl_primitive!(atomic::AtomicU16);
+impl_primitive!(atomic::AtomicU32);
+impl_primitive!(atomic::AtomicU64);
+impl_primitive!(atomic::AtomicUsize);
+
+impl_primitive!(atomic::AtomicI8);
+impl_primitive!(atomic::AtomicI16);
+impl_primitive!(atomic::AtomicI32);
+impl_primitive!(atomic::AtomicI64);
+impl_primitive!(atomic::AtomicIsize);
+
+impl_primitive!(atomic::AtomicBool);
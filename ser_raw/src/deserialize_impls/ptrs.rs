@@ -0,0 +1,191 @@
+use std::{
+	borrow::Cow,
+	mem::{self, MaybeUninit},
+	ptr,
+};
+#[cfg(unix)]
+use std::{
+	ffi::OsString,
+	os::unix::ffi::OsStringExt,
+	path::{Path, PathBuf},
+};
+
+use crate::{serialize_impls::ptrs::BOXED_SLICE_LEN_OFFSET, Deserialize, Deserializer};
+
+impl<T, D> Deserialize<D> for Box<T>
+where
+	D: Deserializer,
+	T: Deserialize<D> + Sized,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		// ZSTs are not written to output. A `Box` containing a ZST holds a dangling
+		// pointer, which is valid anywhere, so nothing to do.
+		if mem::size_of::<T>() == 0 {
+			return;
+		}
+
+		// Read boxed value.
+		// If deserializing boxed value's data panics, `MaybeUninit` ensures it
+		// isn't dropped. Only the allocation is freed.
+		let mut boxed = Box::new(MaybeUninit::<T>::uninit());
+		deserializer.read_raw(boxed.as_mut_ptr());
+		(*boxed.as_mut_ptr()).deserialize_data(deserializer);
+
+		let boxed = Box::from_raw(Box::into_raw(boxed) as *mut T);
+		ptr::write(self, boxed);
+	}
+}
+
+impl<T, D> Deserialize<D> for Vec<T>
+where
+	D: Deserializer,
+	T: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let vec = read_vec(self.len(), deserializer);
+		ptr::write(self, vec);
+	}
+}
+
+impl<D> Deserialize<D> for String
+where D: Deserializer
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let len = self.len();
+
+		// Empty strings have nothing written to output
+		let mut bytes = Vec::<u8>::new();
+		if len > 0 {
+			bytes.reserve_exact(len);
+			deserializer.read_raw_slice(bytes.as_mut_ptr(), len);
+			bytes.set_len(len);
+		}
+
+		let s = String::from_utf8(bytes).expect("Invalid UTF-8 in `String`");
+		ptr::write(self, s);
+	}
+}
+
+impl<T, D> Deserialize<D> for Box<[T]>
+where
+	D: Deserializer,
+	T: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		// Pointer is not valid, so get length directly from fat pointer
+		let vec = read_vec(boxed_slice_len(self), deserializer);
+		ptr::write(self, vec.into_boxed_slice());
+	}
+}
+
+impl<D> Deserialize<D> for Box<str>
+where D: Deserializer
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let bytes = read_boxed_bytes(self, deserializer);
+		let s = String::from_utf8(bytes).expect("Invalid UTF-8 in `Box<str>`");
+		ptr::write(self, s.into_boxed_str());
+	}
+}
+
+#[cfg(unix)]
+impl<D> Deserialize<D> for Box<Path>
+where D: Deserializer
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let bytes = read_boxed_bytes(self, deserializer);
+		let path = PathBuf::from(OsString::from_vec(bytes));
+		ptr::write(self, path.into_boxed_path());
+	}
+}
+
+/// Deserialized `Cow`s are always `Cow::Owned`, as there is nothing with a
+/// suitable lifetime for `Cow::Borrowed` to borrow from.
+impl<'a, D> Deserialize<D> for Cow<'a, str>
+where D: Deserializer
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let s = match self {
+			Cow::Borrowed(s) => {
+				let bytes = read_boxed_bytes(s, deserializer);
+				String::from_utf8(bytes).expect("Invalid UTF-8 in `Cow<str>`")
+			}
+			Cow::Owned(s) => {
+				s.deserialize_data(deserializer);
+				return;
+			}
+		};
+		ptr::write(self, Cow::Owned(s));
+	}
+}
+
+/// Deserialized `Cow`s are always `Cow::Owned`, as there is nothing with a
+/// suitable lifetime for `Cow::Borrowed` to borrow from.
+impl<'a, T, D> Deserialize<D> for Cow<'a, [T]>
+where
+	D: Deserializer,
+	T: Deserialize<D> + Clone,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		let vec = match self {
+			// Pointer is not valid, so get length directly from fat pointer
+			Cow::Borrowed(slice) => read_vec(boxed_slice_len(slice), deserializer),
+			Cow::Owned(vec) => {
+				vec.deserialize_data(deserializer);
+				return;
+			}
+		};
+		ptr::write(self, Cow::Owned(vec));
+	}
+}
+
+/// Read `len` x `T` from input into a `Vec<T>`, and deserialize their data.
+///
+/// Same format is used for contents of `Vec<T>`, `Box<[T]>` and `VecDeque<T>`.
+#[inline]
+pub(crate) unsafe fn read_vec<T: Deserialize<D>, D: Deserializer>(
+	len: usize,
+	deserializer: &mut D,
+) -> Vec<T> {
+	// ZSTs have no contents written to output.
+	// Empty slices have nothing written either.
+	let mut vec = Vec::<T>::new();
+	if mem::size_of::<T>() == 0 || len == 0 {
+		vec.set_len(len);
+		return vec;
+	}
+
+	// Read contents.
+	// Length is not set until all elements have been deserialized,
+	// so they aren't dropped if deserializing one of them panics.
+	vec.reserve_exact(len);
+	let ptr = vec.as_mut_ptr();
+	deserializer.read_raw_slice(ptr, len);
+	for index in 0..len {
+		(*ptr.add(index)).deserialize_data(deserializer);
+	}
+	vec.set_len(len);
+	vec
+}
+
+/// Get length from fat pointer of a boxed slice (`Box<[T]>`, `Box<str>` or
+/// `Box<Path>`) or slice reference, without dereferencing the pointer.
+#[inline]
+unsafe fn boxed_slice_len<B>(boxed: &B) -> usize {
+	*((boxed as *const B as *const u8).add(BOXED_SLICE_LEN_OFFSET) as *const usize)
+}
+
+/// Read content of a `Box<str>`, `Box<Path>` or `&str` from input.
+#[inline]
+unsafe fn read_boxed_bytes<B, D: Deserializer>(boxed: &B, deserializer: &mut D) -> Vec<u8> {
+	let len = boxed_slice_len(boxed);
+
+	// Empty content has nothing written to output
+	let mut bytes = Vec::<u8>::new();
+	if len > 0 {
+		bytes.reserve_exact(len);
+		deserializer.read_raw_slice(bytes.as_mut_ptr(), len);
+		bytes.set_len(len);
+	}
+	bytes
+}
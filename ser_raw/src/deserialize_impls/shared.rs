@@ -0,0 +1,239 @@
+use std::{
+	mem::{ManuallyDrop, MaybeUninit},
+	ptr,
+	rc::{self, Rc},
+	sync::{self, Arc},
+};
+
+use crate::{
+	deserializer::expect_shared_values,
+	serialize_impls::shared::{alloc_ptr, RcBox, DANGLING_WEAK_ADDR},
+	Deserialize, Deserializer,
+};
+
+/// Shared pointer type (`Rc<T>` or `Arc<T>`).
+trait SharedPtr<T>: Clone + 'static {
+	type Weak: Clone + 'static;
+
+	/// Create pointer to a new allocation, with value uninitialized.
+	///
+	/// # Safety
+	///
+	/// Value must be initialized via [`as_mut_ptr`](SharedPtr::as_mut_ptr) before
+	/// it's accessed, and before the last strong pointer to it is dropped.
+	unsafe fn new_uninit() -> Self;
+
+	/// Get mutable pointer to value.
+	fn as_mut_ptr(this: &Self) -> *mut T;
+
+	fn downgrade(this: &Self) -> Self::Weak;
+
+	fn upgrade(weak: &Self::Weak) -> Option<Self>;
+
+	fn dangling() -> Self::Weak;
+}
+
+impl<T: 'static> SharedPtr<T> for Rc<T> {
+	type Weak = rc::Weak<T>;
+
+	#[inline]
+	unsafe fn new_uninit() -> Self {
+		// `MaybeUninit<T>` has same size and alignment as `T`
+		let uninit = Rc::new(MaybeUninit::<T>::uninit());
+		Rc::from_raw(Rc::into_raw(uninit) as *const T)
+	}
+
+	#[inline]
+	fn as_mut_ptr(this: &Self) -> *mut T {
+		Rc::as_ptr(this) as *mut T
+	}
+
+	#[inline]
+	fn downgrade(this: &Self) -> rc::Weak<T> {
+		Rc::downgrade(this)
+	}
+
+	#[inline]
+	fn upgrade(weak: &rc::Weak<T>) -> Option<Self> {
+		weak.upgrade()
+	}
+
+	#[inline]
+	fn dangling() -> rc::Weak<T> {
+		rc::Weak::new()
+	}
+}
+
+impl<T: 'static> SharedPtr<T> for Arc<T> {
+	type Weak = sync::Weak<T>;
+
+	#[inline]
+	unsafe fn new_uninit() -> Self {
+		// `MaybeUninit<T>` has same size and alignment as `T`
+		let uninit = Arc::new(MaybeUninit::<T>::uninit());
+		Arc::from_raw(Arc::into_raw(uninit) as *const T)
+	}
+
+	#[inline]
+	fn as_mut_ptr(this: &Self) -> *mut T {
+		Arc::as_ptr(this) as *mut T
+	}
+
+	#[inline]
+	fn downgrade(this: &Self) -> sync::Weak<T> {
+		Arc::downgrade(this)
+	}
+
+	#[inline]
+	fn upgrade(weak: &sync::Weak<T>) -> Option<Self> {
+		weak.upgrade()
+	}
+
+	#[inline]
+	fn dangling() -> sync::Weak<T> {
+		sync::Weak::new()
+	}
+}
+
+/// `Weak` pointer to an allocation whose value is still being deserialized.
+///
+/// Stored in [`SharedValues`] in place of a strong pointer until value is
+/// initialized, so a strong pointer is not dropped if deserialization panics.
+///
+/// [`SharedValues`]: crate::deserializer::SharedValues
+struct Initializing<W>(W);
+
+/// Get shared pointer for allocation which was at memory address `addr` in
+/// original input, deserializing it if it's not been deserialized already.
+///
+/// If allocation is still being deserialized (a cycle), pointer to it is
+/// returned, but its value is uninitialized until deserializing it completes.
+///
+/// Returns `Err` containing a dangling `Weak` if allocation's value had been
+/// dropped before serialization.
+unsafe fn deserialize_shared<P, T, D>(addr: usize, deserializer: &mut D) -> Result<P, P::Weak>
+where
+	P: SharedPtr<T>,
+	T: Deserialize<D>,
+	D: Deserializer,
+{
+	// Allocation already deserialized, or being deserialized
+	if let Some(value) = expect_shared_values(deserializer).get(addr) {
+		if let Some(strong) = value.downcast_ref::<P>() {
+			return Ok(strong.clone());
+		}
+		if let Some(initializing) = value.downcast_ref::<Initializing<P::Weak>>() {
+			return Ok(P::upgrade(&initializing.0).unwrap());
+		}
+		let weak = value
+			.downcast_ref::<P::Weak>()
+			.expect("Shared pointers to same allocation have different types");
+		return Err(weak.clone());
+	}
+
+	// Read allocation.
+	// If its value was dropped before serialization, it's not followed by the
+	// value's data.
+	let mut alloc = MaybeUninit::<RcBox<T>>::uninit();
+	deserializer.read_raw(alloc.as_mut_ptr());
+	let alloc = alloc.as_mut_ptr();
+	if (*alloc).strong == 0 {
+		let weak = P::dangling();
+		expect_shared_values(deserializer).insert(addr, Box::new(weak.clone()));
+		return Err(weak);
+	}
+
+	// Create allocation before deserializing value, and record it, so any
+	// pointers back to this allocation encountered while deserializing the value
+	// can be created. This includes strong pointers, if input contained a strong
+	// reference cycle (which is leaked, as it was in input).
+	// Strong pointer is leaked if deserializing the value panics, rather than
+	// dropping an uninitialized value.
+	let strong = ManuallyDrop::new(P::new_uninit());
+	expect_shared_values(deserializer).insert(addr, Box::new(Initializing(P::downgrade(&strong))));
+	let value = ptr::addr_of_mut!((*alloc).value);
+	(*value).deserialize_data(deserializer);
+	ptr::copy_nonoverlapping(value, P::as_mut_ptr(&strong), 1);
+
+	// Value is initialized
+	let strong = ManuallyDrop::into_inner(strong);
+	expect_shared_values(deserializer).insert(addr, Box::new(strong.clone()));
+	Ok(strong)
+}
+
+/// Deserialize `Rc<T>` or `Arc<T>`.
+#[inline]
+unsafe fn deserialize_strong<P, T, D>(ptr: &mut P, deserializer: &mut D)
+where
+	P: SharedPtr<T>,
+	T: Deserialize<D>,
+	D: Deserializer,
+{
+	let addr = alloc_ptr::<P, T>(ptr) as usize;
+	// `Err` is only returned for an allocation whose value was dropped before
+	// serialization. A strong pointer to such an allocation can't exist.
+	let strong = deserialize_shared::<P, T, D>(addr, deserializer)
+		.unwrap_or_else(|_| unreachable!("Strong pointer to dropped value"));
+	ptr::write(ptr, strong);
+}
+
+/// Deserialize `rc::Weak<T>` or `sync::Weak<T>`.
+#[inline]
+unsafe fn deserialize_weak<P, T, D>(weak: &mut P::Weak, deserializer: &mut D)
+where
+	P: SharedPtr<T>,
+	T: Deserialize<D>,
+	D: Deserializer,
+{
+	let addr = alloc_ptr::<P::Weak, T>(weak) as usize;
+	let output = if addr == DANGLING_WEAK_ADDR {
+		// Dangling `Weak`s don't point to an allocation. Nothing was written.
+		P::dangling()
+	} else {
+		match deserialize_shared::<P, T, D>(addr, deserializer) {
+			Ok(strong) => P::downgrade(&strong),
+			Err(weak) => weak,
+		}
+	};
+	ptr::write(weak, output);
+}
+
+impl<T, D> Deserialize<D> for Rc<T>
+where
+	D: Deserializer,
+	T: Deserialize<D> + 'static,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		deserialize_strong::<Rc<T>, T, D>(self, deserializer);
+	}
+}
+
+impl<T, D> Deserialize<D> for rc::Weak<T>
+where
+	D: Deserializer,
+	T: Deserialize<D> + 'static,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		deserialize_weak::<Rc<T>, T, D>(self, deserializer);
+	}
+}
+
+impl<T, D> Deserialize<D> for Arc<T>
+where
+	D: Deserializer,
+	T: Deserialize<D> + 'static,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		deserialize_strong::<Arc<T>, T, D>(self, deserializer);
+	}
+}
+
+impl<T, D> Deserialize<D> for sync::Weak<T>
+where
+	D: Deserializer,
+	T: Deserialize<D> + 'static,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		deserialize_weak::<Arc<T>, T, D>(self, deserializer);
+	}
+}
@@ -0,0 +1,34 @@
+use std::{mem, ptr};
+
+use smallvec::{Array, SmallVec};
+
+use super::ptrs::read_vec;
+use crate::{Deserialize, Deserializer};
+
+// Inline contents are within the `SmallVec` itself, so only need their own data
+// deserialized. Heap contents were written same as a `Vec<T>`'s.
+// See `Serialize` implementation.
+impl<A, D> Deserialize<D> for SmallVec<A>
+where
+	A: Array,
+	A::Item: Deserialize<D>,
+	D: Deserializer,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		// ZSTs have no data written to output
+		if mem::size_of::<A::Item>() == 0 {
+			return;
+		}
+
+		if !self.spilled() {
+			for value in self.iter_mut() {
+				value.deserialize_data(deserializer);
+			}
+			return;
+		}
+
+		// Heap pointer is not valid, but length is
+		let vec = read_vec::<A::Item, D>(self.len(), deserializer);
+		ptr::write(self, SmallVec::from_vec(vec));
+	}
+}
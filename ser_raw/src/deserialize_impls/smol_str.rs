@@ -0,0 +1,38 @@
+use std::{mem::MaybeUninit, ptr};
+
+use smol_str::SmolStr;
+
+use crate::{
+	serialize_impls::{shared::RcBox, smol_str::HEAP_LEN_OFFSET},
+	Deserialize, Deserializer,
+};
+
+// Heap strings are written to output as reference counts, followed by the
+// string. Other strings need no further deserialization.
+// See `Serialize` implementation.
+impl<D> Deserialize<D> for SmolStr
+where D: Deserializer
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		if !self.is_heap_allocated() {
+			return;
+		}
+
+		// Pointer is not valid, so get length directly from `Arc<str>`'s fat pointer
+		let len = *((self as *const SmolStr as *const u8).add(HEAP_LEN_OFFSET) as *const usize);
+
+		// Skip reference counts
+		let mut rc_box = MaybeUninit::<RcBox<[u8; 0]>>::uninit();
+		deserializer.read_raw(rc_box.as_mut_ptr());
+
+		let mut bytes = Vec::<u8>::new();
+		if len > 0 {
+			bytes.reserve_exact(len);
+			deserializer.read_raw_slice(bytes.as_mut_ptr(), len);
+			bytes.set_len(len);
+		}
+
+		let s = String::from_utf8(bytes).expect("Invalid UTF-8 in `SmolStr`");
+		ptr::write(self, SmolStr::from(s));
+	}
+}
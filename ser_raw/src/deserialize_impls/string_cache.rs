@@ -0,0 +1,39 @@
+use std::{mem::MaybeUninit, ptr};
+
+use string_cache::{Atom, StaticAtomSet};
+
+use crate::{
+	serialize_impls::string_cache::{EntryBytes, DYNAMIC_TAG, ENTRY_STR_LEN_OFFSET, TAG_MASK},
+	Deserialize, Deserializer,
+};
+
+// Dynamic atoms are written to output as an `Entry`, followed by the string.
+// They're re-interned. Other atoms need no further deserialization.
+// See `Serialize` implementation.
+impl<Static, D> Deserialize<D> for Atom<Static>
+where
+	Static: StaticAtomSet,
+	D: Deserializer,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		if self.unsafe_data() & TAG_MASK != DYNAMIC_TAG {
+			return;
+		}
+
+		// Pointer is not valid. Get length from `Entry` in input.
+		let mut entry = MaybeUninit::<EntryBytes>::uninit();
+		deserializer.read_raw(entry.as_mut_ptr());
+		let len =
+			ptr::read_unaligned((entry.as_ptr() as *const u8).add(ENTRY_STR_LEN_OFFSET) as *const usize);
+
+		let mut bytes = Vec::<u8>::new();
+		if len > 0 {
+			bytes.reserve_exact(len);
+			deserializer.read_raw_slice(bytes.as_mut_ptr(), len);
+			bytes.set_len(len);
+		}
+
+		let s = String::from_utf8(bytes).expect("Invalid UTF-8 in `Atom`");
+		ptr::write(self, Atom::from(s));
+	}
+}
@@ -0,0 +1,40 @@
+use std::{
+	mem::{self, MaybeUninit},
+	ptr,
+};
+
+use thin_vec::ThinVec;
+
+use crate::{serialize_impls::thin_vec::ThinVecHeader, Deserialize, Deserializer};
+
+// Header is written to output, followed by contents.
+// See `Serialize` implementation.
+impl<T, D> Deserialize<D> for ThinVec<T>
+where
+	D: Deserializer,
+	T: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		// Pointer is not valid. Get length from header in input.
+		let mut header = MaybeUninit::<ThinVecHeader<T>>::uninit();
+		deserializer.read_raw(header.as_mut_ptr());
+		let len = (*header.as_ptr()).len;
+
+		// Read contents.
+		// Length is not set until all elements have been deserialized,
+		// so they aren't dropped if deserializing one of them panics.
+		let mut vec = ThinVec::<T>::with_capacity(len);
+		if len > 0 {
+			let ptr = vec.as_mut_ptr();
+			deserializer.read_raw_slice(ptr, len);
+			// ZSTs have no data written to output
+			if mem::size_of::<T>() > 0 {
+				for index in 0..len {
+					(*ptr.add(index)).deserialize_data(deserializer);
+				}
+			}
+			vec.set_len(len);
+		}
+		ptr::write(self, vec);
+	}
+}
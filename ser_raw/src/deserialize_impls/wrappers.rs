@@ -0,0 +1,94 @@
+use std::{
+	cell::{Cell, RefCell},
+	cmp::Reverse,
+	marker::PhantomData,
+	mem::ManuallyDrop,
+	num::Wrapping,
+	ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
+	sync::{Mutex, PoisonError, RwLock},
+};
+
+use crate::{Deserialize, Deserializer};
+
+impl<T, D> Deserialize<D> for PhantomData<T>
+where
+	D: Deserializer,
+	T: ?Sized,
+{
+	#[inline(always)]
+	unsafe fn deserialize_data(&mut self, _deserializer: &mut D) {}
+}
+
+impl<D> Deserialize<D> for RangeFull
+where D: Deserializer
+{
+	#[inline(always)]
+	unsafe fn deserialize_data(&mut self, _deserializer: &mut D) {}
+}
+
+/// Implement `Deserialize` for a wrapper type which contains a single value.
+/// `$get` is an expression getting a mutable reference to the wrapped value
+/// from `$value`.
+macro_rules! impl_wrapper {
+	($ty:ident, |$value:ident| $get:expr) => {
+		impl<T, D> Deserialize<D> for $ty<T>
+		where
+			D: Deserializer,
+			T: Deserialize<D>,
+		{
+			#[inline]
+			unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+				let $value = self;
+				let value: &mut T = $get;
+				value.deserialize_data(deserializer);
+			}
+		}
+	};
+}
+
+impl_wrapper!(ManuallyDrop, |value| &mut **value);
+impl_wrapper!(Wrapping, |value| &mut value.0);
+impl_wrapper!(Reverse, |value| &mut value.0);
+impl_wrapper!(Cell, |value| value.get_mut());
+// Serializer ensures borrow flag in input is unborrowed, and writes locks to
+// output unlocked and unpoisoned.
+impl_wrapper!(RefCell, |value| value.get_mut());
+impl_wrapper!(Mutex, |value| {
+	value.get_mut().unwrap_or_else(PoisonError::into_inner)
+});
+impl_wrapper!(RwLock, |value| {
+	value.get_mut().unwrap_or_else(PoisonError::into_inner)
+});
+impl_wrapper!(RangeFrom, |value| &mut value.start);
+impl_wrapper!(RangeTo, |value| &mut value.end);
+impl_wrapper!(RangeToInclusive, |value| &mut value.end);
+
+impl<T, D> Deserialize<D> for Range<T>
+where
+	D: Deserializer,
+	T: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		self.start.deserialize_data(deserializer);
+		self.end.deserialize_data(deserializer);
+	}
+}
+
+impl<T, D> Deserialize<D> for RangeInclusive<T>
+where
+	D: Deserializer,
+	T: Deserialize<D>,
+{
+	unsafe fn deserialize_data(&mut self, deserializer: &mut D) {
+		// `RangeInclusive` has no API for mutable access to `start` and `end`.
+		// Get pointers to them from their offsets, rather than rebuilding the range,
+		// to retain whether it's been exhausted.
+		let base = self as *const Self as usize;
+		let start_offset = self.start() as *const T as usize - base;
+		let end_offset = self.end() as *const T as usize - base;
+
+		let ptr = self as *mut Self as *mut u8;
+		(*(ptr.add(start_offset) as *mut T)).deserialize_data(deserializer);
+		(*(ptr.add(end_offset) as *mut T)).deserialize_data(deserializer);
+	}
+}
@@ -0,0 +1,127 @@
+use std::{any::Any, collections::HashMap, mem::MaybeUninit};
+
+use crate::Deserialize;
+
+/// Deserializers implement this trait.
+///
+/// A [`Deserializer`] reads values from serializer output in the same order
+/// they were written by [`Serialize`] implementations, following same
+/// alignment rules as the [`Storage`] which output was written to.
+///
+/// # Safety
+///
+/// [`read_raw_slice`] must write to `dest` a bit-for-bit copy of values of the
+/// requested type which were serialized. [`deserialize_value`] relies on this,
+/// so implementations must only accept input which was produced by a
+/// serializer (which is why e.g. [`PureCopyDeserializer::new`] is unsafe).
+///
+/// [`read_raw_slice`]: Deserializer::read_raw_slice
+/// [`deserialize_value`]: Deserializer::deserialize_value
+/// [`PureCopyDeserializer::new`]: crate::PureCopyDeserializer::new
+/// [`Serialize`]: crate::Serialize
+/// [`Storage`]: crate::storage::Storage
+pub unsafe trait Deserializer: Sized {
+	/// Deserialize a value and return it.
+	///
+	/// Reads the value itself, and then any data it owns.
+	#[inline]
+	fn deserialize_value<T: Deserialize<Self>>(&mut self) -> T {
+		let mut value = MaybeUninit::<T>::uninit();
+		unsafe {
+			self.read_raw(value.as_mut_ptr());
+			(*value.as_mut_ptr()).deserialize_data(self);
+			value.assume_init()
+		}
+	}
+
+	/// Deserialize a value, consuming the [`Deserializer`].
+	#[inline]
+	fn deserialize<T: Deserialize<Self>>(mut self) -> T {
+		self.deserialize_value()
+	}
+
+	/// Read a `T` from input, and write it to `dest`.
+	///
+	/// Value is copied bit for bit, so any pointers within it are not valid.
+	///
+	/// # Safety
+	///
+	/// * `dest` must be valid for writes of a `T` and correctly aligned.
+	/// * Next value in input must be a `T`.
+	#[inline]
+	unsafe fn read_raw<T>(&mut self, dest: *mut T) {
+		self.read_raw_slice(dest, 1);
+	}
+
+	/// Read a slice of `len` x `T` from input, and write it to `dest`.
+	///
+	/// Values are copied bit for bit, so any pointers within them are not valid.
+	///
+	/// # Safety
+	///
+	/// * `dest` must be valid for writes of `len` x `T` and correctly aligned.
+	/// * Next value in input must be a slice `&[T]` of length `len`.
+	unsafe fn read_raw_slice<T>(&mut self, dest: *mut T, len: usize);
+
+	/// Get current position in input.
+	fn pos(&self) -> usize;
+
+	/// Get mutable ref to record of shared values (e.g. `Rc`s) which have been
+	/// deserialized, if this deserializer keeps one.
+	///
+	/// Deserializers which don't keep a record cannot deserialize `Rc`s or
+	/// `Arc`s. Default implementation returns `None`.
+	#[inline]
+	fn shared_values(&mut self) -> Option<&mut SharedValues> {
+		None
+	}
+}
+
+/// A record of shared values (e.g. `Rc`s) which have been deserialized.
+///
+/// Maps memory address of each shared allocation in the original input to the
+/// `Rc` / `Arc` created for it, so all pointers to that allocation share the
+/// same `Rc` / `Arc` in output. While the allocation's value is being
+/// deserialized, a `Weak` is recorded instead.
+///
+/// Holds a strong reference to each value until deserializer is dropped, so
+/// values only reached via `Weak`s remain alive until deserialization is
+/// complete.
+#[derive(Default)]
+pub struct SharedValues {
+	values: HashMap<usize, Box<dyn Any>>,
+}
+
+impl SharedValues {
+	/// Create new empty [`SharedValues`].
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			values: HashMap::new(),
+		}
+	}
+
+	/// Get value recorded for allocation at original memory address `addr`.
+	#[inline]
+	pub fn get(&self, addr: usize) -> Option<&dyn Any> {
+		self.values.get(&addr).map(|value| &**value)
+	}
+
+	/// Record value for allocation at original memory address `addr`.
+	#[inline]
+	pub fn insert(&mut self, addr: usize, value: Box<dyn Any>) {
+		self.values.insert(addr, value);
+	}
+}
+
+/// Get deserializer's record of shared values.
+///
+/// # Panics
+///
+/// Panics if deserializer does not record shared values.
+#[inline]
+pub(crate) fn expect_shared_values<D: Deserializer>(deserializer: &mut D) -> &mut SharedValues {
+	deserializer
+		.shared_values()
+		.expect("Deserializer does not support shared pointers")
+}
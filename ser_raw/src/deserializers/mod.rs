@@ -0,0 +1,2 @@
+mod pure_copy;
+pub use pure_copy::PureCopyDeserializer;
@@ -0,0 +1,165 @@
+use std::{mem, ptr};
+
+use crate::{deserializer::SharedValues, util::align_up_to, Deserializer};
+
+/// Deserializer for output of [`PureCopySerializer`].
+///
+/// Reads values in the same order they were written by the serializer, and
+/// reconstructs owned values (`Box`, `Vec`, `String` etc) from them.
+///
+/// Const parameters must be the same as those of the [`PureCopySerializer`]
+/// which produced the input, as they determine where padding was inserted.
+/// See [`Storage`] for an explanation of the const parameters.
+///
+/// Input does not need to be aligned.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::{AlignedVec, ContiguousStorage},
+///     util::aligned_max_capacity,
+///     Deserialize, Deserializer, PureCopyDeserializer, PureCopySerializer, Serialize,
+///     Serializer,
+/// };
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Foo {
+///     boxed: Box<u8>,
+///     names: Vec<String>,
+/// }
+///
+/// let foo = Foo {
+///     boxed: Box::new(123),
+///     names: vec!["Alfred".to_string(), "Gertrude".to_string()],
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// let ser = PureCopySerializer::<16, 16, 8, MAX_CAPACITY, AlignedVec<16, 16, 8, MAX_CAPACITY>>::new();
+/// let storage = ser.serialize(&foo).1;
+///
+/// let de = unsafe {
+///     PureCopyDeserializer::<16, 16, 8, MAX_CAPACITY>::new(storage.as_slice())
+/// };
+/// let output: Foo = de.deserialize();
+/// assert_eq!(output, foo);
+/// ```
+///
+/// [`PureCopySerializer`]: crate::PureCopySerializer
+/// [`Storage`]: crate::storage::Storage
+pub struct PureCopyDeserializer<
+	'a,
+	const STORAGE_ALIGNMENT: usize,
+	const MAX_VALUE_ALIGNMENT: usize,
+	const VALUE_ALIGNMENT: usize,
+	const MAX_CAPACITY: usize,
+> {
+	bytes: &'a [u8],
+	pos: usize,
+	shared_values: SharedValues,
+}
+
+impl<'a, const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
+	PureCopyDeserializer<'a, SA, MVA, VA, MAX>
+{
+	/// Alignment of output buffer
+	pub const STORAGE_ALIGNMENT: usize = SA;
+
+	/// Maximum alignment of values being serialized
+	pub const MAX_VALUE_ALIGNMENT: usize = MVA;
+
+	/// Typical alignment of values being serialized
+	pub const VALUE_ALIGNMENT: usize = VA;
+
+	/// Maximum capacity of output buffer.
+	pub const MAX_CAPACITY: usize = MAX;
+
+	/// Create new [`PureCopyDeserializer`] to read from `bytes`.
+	///
+	/// # Safety
+	///
+	/// `bytes` must be output of a [`PureCopySerializer`] with the same const
+	/// parameters, and values must be deserialized with the same types, and in
+	/// the same order, as they were serialized.
+	///
+	/// [`PureCopySerializer`]: crate::PureCopySerializer
+	#[inline]
+	pub unsafe fn new(bytes: &'a [u8]) -> Self {
+		Self {
+			bytes,
+			pos: 0,
+			shared_values: SharedValues::new(),
+		}
+	}
+
+	/// Create new [`PureCopyDeserializer`] to read from `bytes`, starting at
+	/// position `pos`.
+	///
+	/// Use this to skip a [`Header`] at start of input. `pos` is the position
+	/// returned by [`header::check`].
+	///
+	/// # Safety
+	///
+	/// Same requirements as [`new`](PureCopyDeserializer::new). `pos` must be
+	/// the position of a value in `bytes`.
+	///
+	/// [`Header`]: crate::header::Header
+	/// [`header::check`]: crate::header::check
+	#[inline]
+	pub unsafe fn new_at(bytes: &'a [u8], pos: usize) -> Self {
+		Self {
+			bytes,
+			pos,
+			shared_values: SharedValues::new(),
+		}
+	}
+
+	/// Align position in input to `alignment`.
+	#[inline]
+	fn align(&mut self, alignment: usize) {
+		self.pos = align_up_to(self.pos, alignment);
+	}
+}
+
+// Reads follow the same rules as `Storage::push_slice`
+unsafe impl<'a, const SA: usize, const MVA: usize, const VA: usize, const MAX: usize> Deserializer
+	for PureCopyDeserializer<'a, SA, MVA, VA, MAX>
+{
+	#[inline]
+	unsafe fn read_raw_slice<T>(&mut self, dest: *mut T, len: usize) {
+		// ZSTs are not written to output
+		if mem::size_of::<T>() == 0 {
+			return;
+		}
+
+		// Skip padding inserted before value
+		if mem::align_of::<T>() > VA {
+			self.align(mem::align_of::<T>());
+		}
+
+		let size = mem::size_of::<T>()
+			.checked_mul(len)
+			.expect("Input is too short");
+		assert!(
+			self.pos <= self.bytes.len() && size <= self.bytes.len() - self.pos,
+			"Input is too short"
+		);
+		ptr::copy_nonoverlapping(self.bytes.as_ptr().add(self.pos), dest as *mut u8, size);
+		self.pos += size;
+
+		// Skip padding inserted after value
+		if mem::size_of::<T>() % VA > 0 {
+			self.align(VA);
+		}
+	}
+
+	#[inline]
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	#[inline]
+	fn shared_values(&mut self) -> Option<&mut SharedValues> {
+		Some(&mut self.shared_values)
+	}
+}
@@ -0,0 +1,209 @@
+//! Fallible serialization.
+//!
+//! By default, serialization panics if storage cannot grow to hold the output
+//! (it would exceed `MAX_CAPACITY`), and aborts if allocating memory fails.
+//! [`Serializer::try_serialize`] and [`Serializer::try_serialize_value`] return
+//! a [`SerializeError`] instead. Their `_with_budget` variants also limit size
+//! of output to a byte budget set at runtime.
+//!
+//! Failure is signalled from deep inside serialization by unwinding to the
+//! `try_*` entry point, so the `Serialize` implementation for every type does
+//! not need to check for errors, and serialization in the common case where it
+//! succeeds is exactly as fast as infallible serialization. This requires
+//! panics to unwind. If built with `panic = "abort"`, failure aborts the
+//! process. This applies to all the `try_*` entry points:
+//! [`Serializer::try_serialize`], [`Serializer::try_serialize_value`], their
+//! `_with_budget` variants, and [`Storage::try_with_capacity`].
+//!
+//! Serializing a `RefCell` which is borrowed, or a `Mutex` or `RwLock` which
+//! is locked, also fails, with [`SerializeError::Borrowed`] or
+//! [`SerializeError::Locked`].
+//!
+//! [`Storage`] implementations signal failure by calling
+//! [`SerializeError::fail`], and check budget with [`check_budget`] when
+//! growing. [`Storage::try_reserve`] returns errors without unwinding, and
+//! can be used to reserve capacity up front.
+//!
+//! # Example
+//!
+//! ```
+//! use ser_raw::{
+//!     fallible::SerializeError,
+//!     storage::{AlignedVec, Storage},
+//!     util::aligned_max_capacity,
+//!     PureCopySerializer, Serializer,
+//! };
+//!
+//! const MAX_CAPACITY: usize = aligned_max_capacity(16);
+//! type Ser = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+//!
+//! let input = vec![0u64; 1000];
+//!
+//! let result = Ser::new().try_serialize_with_budget(&input, 1024);
+//! assert_eq!(result.err(), Some(SerializeError::BudgetExceeded { budget: 1024 }));
+//!
+//! let (_, storage) = Ser::new().try_serialize(&input).unwrap();
+//! assert_eq!(storage.pos(), 24 + 8000);
+//! ```
+//!
+//! [`Serializer::try_serialize`]: crate::Serializer::try_serialize
+//! [`Serializer::try_serialize_value`]: crate::Serializer::try_serialize_value
+//! [`Storage`]: crate::storage::Storage
+//! [`Storage::try_reserve`]: crate::storage::Storage::try_reserve
+//! [`Storage::try_with_capacity`]: crate::storage::Storage::try_with_capacity
+
+use std::{
+	alloc::{self, Layout},
+	any,
+	cell::Cell,
+	error::Error,
+	fmt,
+	panic::{self, AssertUnwindSafe},
+};
+
+use crate::util::align_up_to;
+
+thread_local! {
+	/// Byte budget of fallible serialization in progress on this thread,
+	/// or `None` if none is in progress.
+	static BUDGET: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Error returned by fallible serialization methods e.g.
+/// [`try_serialize`](crate::Serializer::try_serialize), and by
+/// [`rollback`](crate::Serializer::rollback).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SerializeError {
+	/// Output would exceed storage's maximum capacity
+	CapacityExceeded { storage: &'static str },
+	/// Allocating memory for storage failed
+	AllocFailed { layout: Layout },
+	/// Output would exceed byte budget
+	BudgetExceeded { budget: usize },
+	/// A `RefCell` being serialized is borrowed
+	Borrowed,
+	/// A `Mutex` or `RwLock` being serialized is locked
+	Locked { lock: &'static str },
+	/// Output to be discarded by a rollback has already been written out, and
+	/// cannot be recalled
+	AlreadyWritten,
+}
+
+impl SerializeError {
+	/// Abort serialization with this error.
+	///
+	/// If called during fallible serialization (e.g.
+	/// [`try_serialize`](crate::Serializer::try_serialize)), unwinds to it, and
+	/// it returns this error.
+	///
+	/// Otherwise panics, or for [`AllocFailed`](SerializeError::AllocFailed),
+	/// calls [`handle_alloc_error`](alloc::handle_alloc_error), as `Vec` does.
+	#[cold]
+	pub fn fail(self) -> ! {
+		if in_progress() {
+			// `resume_unwind` does not invoke panic hook, so nothing is printed
+			panic::resume_unwind(Box::new(self));
+		}
+
+		match self {
+			Self::AllocFailed { layout } => alloc::handle_alloc_error(layout),
+			_ => panic!("{self}"),
+		}
+	}
+}
+
+impl fmt::Display for SerializeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::CapacityExceeded { storage } => {
+				write!(
+					f,
+					"Cannot grow {storage} further: {storage} capacity exceeded"
+				)
+			}
+			Self::AllocFailed { layout } => {
+				write!(f, "Memory allocation of {} bytes failed", layout.size())
+			}
+			Self::BudgetExceeded { budget } => write!(f, "Output exceeds budget of {budget} bytes"),
+			Self::Borrowed => write!(f, "Cannot serialize a `RefCell` which is borrowed"),
+			Self::Locked { lock } => write!(f, "Cannot serialize a `{lock}` which is locked"),
+			Self::AlreadyWritten => write!(f, "Cannot roll back output which has already been written"),
+		}
+	}
+}
+
+impl Error for SerializeError {}
+
+/// Get byte budget of fallible serialization in progress on current thread.
+///
+/// Returns `usize::MAX` if there is none, or it has no budget.
+#[inline]
+pub fn budget() -> usize {
+	BUDGET.with(|budget| budget.get()).unwrap_or(usize::MAX)
+}
+
+/// Check storage growing to `required` bytes would not exceed byte budget of
+/// fallible serialization in progress on current thread.
+///
+/// [`Storage`](crate::storage::Storage) implementations should call this
+/// before growing. Only needs to be called when storage grows, as fallible
+/// serialization also checks size of output once serialization is complete.
+#[inline]
+pub fn check_budget(required: usize) -> Result<(), SerializeError> {
+	let budget = budget();
+	if required > budget {
+		Err(SerializeError::BudgetExceeded { budget })
+	} else {
+		Ok(())
+	}
+}
+
+/// Limit capacity which storage is growing to, so it does not exceed byte
+/// budget (rounded up to a multiple of `alignment`).
+///
+/// Bytes required must already have been checked with [`check_budget`].
+/// `new_cap` must be a multiple of `alignment`, and `alignment` must be a power
+/// of 2.
+#[inline]
+pub(crate) fn limit_to_budget(new_cap: usize, alignment: usize) -> usize {
+	let budget = budget();
+	if new_cap <= budget {
+		new_cap
+	} else {
+		// Cannot overflow, and does not exceed `new_cap`, as `budget < new_cap` and
+		// `new_cap` is a multiple of `alignment`
+		align_up_to(budget, alignment)
+	}
+}
+
+/// Create [`SerializeError::CapacityExceeded`] error for storage `S`.
+pub(crate) fn capacity_exceeded<S>() -> SerializeError {
+	// Type name without path or generic params e.g. `AlignedVec`
+	let name = any::type_name::<S>();
+	let name = name.split('<').next().unwrap_or(name);
+	let name = name.rsplit("::").next().unwrap_or(name);
+	SerializeError::CapacityExceeded { storage: name }
+}
+
+/// Get whether fallible serialization is in progress on current thread.
+#[inline]
+pub(crate) fn in_progress() -> bool {
+	BUDGET.with(|budget| budget.get()).is_some()
+}
+
+/// Run `f`, converting any [`SerializeError`] raised within it with
+/// [`SerializeError::fail`] into an `Err`.
+///
+/// Other panics are propagated.
+pub(crate) fn catch<R, F: FnOnce() -> R>(budget: usize, f: F) -> Result<R, SerializeError> {
+	let outer = BUDGET.with(|cell| cell.replace(Some(budget)));
+	let result = panic::catch_unwind(AssertUnwindSafe(f));
+	BUDGET.with(|cell| cell.set(outer));
+
+	result.map_err(|payload| {
+		match payload.downcast::<SerializeError>() {
+			Ok(err) => *err,
+			Err(payload) => panic::resume_unwind(payload),
+		}
+	})
+}
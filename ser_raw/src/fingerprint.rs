@@ -0,0 +1,77 @@
+//! Layout fingerprints for types.
+//!
+//! A fingerprint is a 64-bit hash which describes a type's memory layout.
+//! It's recorded in the [`Header`] of serialized output, so a reader can refuse
+//! output which was produced from a different definition of the type.
+//!
+//! Every type implementing [`Serialize`] has a fingerprint
+//! ([`Serialize::FINGERPRINT`]). The derive macro computes it from the type's
+//! name, size and alignment, and the names and fingerprints of its fields.
+//! Types which don't define a fingerprint get one computed from just their
+//! size and alignment (see [`of_layout`]).
+//!
+//! Types which own data via a pointer (e.g. `Box<T>`, `Vec<T>`) include only
+//! the size and alignment of `T` in their fingerprint, not `T`'s own
+//! fingerprint. This is so recursive types (e.g. `struct Node { children:
+//! Vec<Node> }`) have a fingerprint which can be computed.
+//!
+//! Fingerprints are computed at compile time with the FNV-1a hash function.
+//!
+//! [`Header`]: crate::header::Header
+//! [`Serialize`]: crate::Serialize
+//! [`Serialize::FINGERPRINT`]: crate::Serialize::FINGERPRINT
+
+use std::mem;
+
+/// Initial hash value (FNV-1a 64-bit offset basis).
+pub const INITIAL: u64 = 0xcbf29ce484222325;
+
+const PRIME: u64 = 0x100000001b3;
+
+/// Add `bytes` to `hash`.
+pub const fn hash_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+	let mut index = 0;
+	while index < bytes.len() {
+		hash ^= bytes[index] as u64;
+		hash = hash.wrapping_mul(PRIME);
+		index += 1;
+	}
+	hash
+}
+
+/// Add a string to `hash`.
+///
+/// String's length is also hashed, so e.g. `"ab", "c"` hashes differently from
+/// `"a", "bc"`.
+pub const fn hash_str(hash: u64, str: &str) -> u64 {
+	let hash = hash_usize(hash, str.len());
+	hash_bytes(hash, str.as_bytes())
+}
+
+/// Add a `u64` to `hash`.
+pub const fn hash_u64(hash: u64, value: u64) -> u64 {
+	hash_bytes(hash, &value.to_le_bytes())
+}
+
+/// Add a `usize` to `hash`.
+///
+/// Hashed as a `u64`, so hash does not depend on pointer width.
+pub const fn hash_usize(hash: u64, value: usize) -> u64 {
+	hash_u64(hash, value as u64)
+}
+
+/// Get fingerprint of a type from only its size and alignment.
+///
+/// Used as the default for [`Serialize::FINGERPRINT`], and as the starting
+/// point for fingerprints computed by derive macro.
+///
+/// [`Serialize::FINGERPRINT`]: crate::Serialize::FINGERPRINT
+pub const fn of_layout<T>() -> u64 {
+	let hash = hash_usize(INITIAL, mem::size_of::<T>());
+	hash_usize(hash, mem::align_of::<T>())
+}
+
+/// Get fingerprint of a named type from its size and alignment.
+pub const fn of_named<T>(name: &str) -> u64 {
+	hash_str(of_layout::<T>(), name)
+}
@@ -0,0 +1,331 @@
+//! Self-describing header for serialized output.
+//!
+//! Serializers' output is only valid on a system with the same architecture
+//! as the one it was produced on, and when read with the same types, and the
+//! same serializer settings. By default, nothing in the output records these
+//! things, so a mismatch can't be detected.
+//!
+//! [`Serializer::serialize_with_header`] writes a [`Header`] at start of output
+//! which records all of this. [`check`] reads the header, and returns an error
+//! if output can't be read as the expected type, with the expected serializer.
+//!
+//! # Example
+//!
+//! ```
+//! use ser_raw::{
+//!     header,
+//!     storage::{AlignedVec, ContiguousStorage, RandomAccessStorage},
+//!     util::aligned_max_capacity,
+//!     CompleteSerializer, Serialize, Serializer,
+//! };
+//!
+//! #[derive(Serialize)]
+//! struct Foo {
+//!     names: Vec<String>,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Bar {
+//!     name: String,
+//! }
+//!
+//! const MAX_CAPACITY: usize = aligned_max_capacity(16);
+//! type Ser = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec<16, 16, 8, MAX_CAPACITY>>;
+//!
+//! let foo = Foo {
+//!     names: vec!["Alfred".to_string(), "Gertrude".to_string()],
+//! };
+//! let (_, storage) = Ser::new().serialize_with_header(&foo);
+//!
+//! // Header does not match a different type
+//! assert!(header::check::<Bar, Ser>(storage.as_slice()).is_err());
+//!
+//! // Header matches
+//! let pos = header::check::<Foo, Ser>(storage.as_slice()).unwrap();
+//! // This is safe because serialization and deserialization are performed
+//! // with same binary, and `storage` has not been mutated after serialization
+//! let foo_out: &Foo = unsafe { storage.read(pos) };
+//! assert_eq!(foo_out.names, foo.names);
+//! ```
+//!
+//! [`Serializer::serialize_with_header`]: crate::Serializer::serialize_with_header
+
+use std::{error::Error, fmt, mem, ptr};
+
+use crate::{storage::Storage, Serialize, Serializer};
+
+/// Magic number at start of [`Header`].
+pub const MAGIC: [u8; 8] = *b"SER_RAW\0";
+
+/// Version of header and output format.
+///
+/// Will be increased if format of [`Header`] or serializers' output changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Size of [`Header`] in bytes.
+pub const HEADER_SIZE: usize = mem::size_of::<Header>();
+
+/// Type of serializer which produced output.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SerializerKind {
+	/// [`PureCopySerializer`](crate::PureCopySerializer)-style serializer
+	PureCopy = 0,
+	/// [`PtrOffsetSerializer`](crate::PtrOffsetSerializer)-style serializer
+	PtrOffset = 1,
+	/// [`CompleteSerializer`](crate::CompleteSerializer)-style serializer
+	Complete = 2,
+	/// [`PortableSerializer`](crate::PortableSerializer)-style serializer
+	Portable = 3,
+}
+
+/// Endianness of system which produced output.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+	Little = 0,
+	Big = 1,
+}
+
+impl Endianness {
+	/// Endianness of this system.
+	#[cfg(target_endian = "little")]
+	pub const NATIVE: Endianness = Endianness::Little;
+	/// Endianness of this system.
+	#[cfg(target_endian = "big")]
+	pub const NATIVE: Endianness = Endianness::Big;
+}
+
+/// Header written at start of output by
+/// [`Serializer::serialize_with_header`].
+///
+/// All fields apart from `magic`, `ptr_width` and `endianness` are written in
+/// the native endianness of the system which produced output. All integers are
+/// fixed size, so header has same size and layout on all systems.
+///
+/// Output of portable serializers is an exception. Their header is always
+/// little-endian, `endianness` is always [`Endianness::Little`], and
+/// `ptr_width` is the size of offsets and lengths in output
+/// ([`Serializer::PORTABLE_OFFSET_SIZE`]). Fields of [`Header`] returned by
+/// [`Header::read`] are always in native endianness.
+///
+/// NB: `fingerprint` is computed from the native memory layouts of types, even
+/// for portable serializers. Types containing `usize`s or pointers have
+/// different fingerprints on 32-bit and 64-bit systems, so checking the header
+/// of portable output produced on a system with different pointer width will
+/// fail.
+///
+/// [`Serializer::serialize_with_header`]: crate::Serializer::serialize_with_header
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+	/// Magic number. Always [`MAGIC`].
+	pub magic: [u8; 8],
+	/// Format version. [`FORMAT_VERSION`] at time of serialization.
+	pub format_version: u32,
+	/// Type of serializer (as [`SerializerKind`])
+	pub serializer_kind: u8,
+	/// Size of pointers in bytes
+	pub ptr_width: u8,
+	/// Endianness (as [`Endianness`])
+	pub endianness: u8,
+	_padding: u8,
+	/// Storage's `STORAGE_ALIGNMENT` const parameter
+	pub storage_alignment: u64,
+	/// Storage's `MAX_VALUE_ALIGNMENT` const parameter
+	pub max_value_alignment: u64,
+	/// Storage's `VALUE_ALIGNMENT` const parameter
+	pub value_alignment: u64,
+	/// Storage's `MAX_CAPACITY` const parameter
+	pub max_capacity: u64,
+	/// Position of root value in output
+	pub root_pos: u64,
+	/// Fingerprint of root value's type
+	pub fingerprint: u64,
+}
+
+impl Header {
+	/// Create [`Header`] for root value of type `T`, serialized by serializer
+	/// `S` at position `root_pos`.
+	pub fn new<T: Serialize<S>, S: Serializer>(root_pos: usize) -> Self {
+		let (ptr_width, endianness) = match S::KIND {
+			SerializerKind::Portable => (S::PORTABLE_OFFSET_SIZE, Endianness::Little),
+			_ => (mem::size_of::<usize>(), Endianness::NATIVE),
+		};
+
+		Self {
+			magic: MAGIC,
+			format_version: FORMAT_VERSION,
+			serializer_kind: S::KIND as u8,
+			ptr_width: ptr_width as u8,
+			endianness: endianness as u8,
+			_padding: 0,
+			storage_alignment: S::Storage::STORAGE_ALIGNMENT as u64,
+			max_value_alignment: S::Storage::MAX_VALUE_ALIGNMENT as u64,
+			value_alignment: S::Storage::VALUE_ALIGNMENT as u64,
+			max_capacity: S::Storage::MAX_CAPACITY as u64,
+			root_pos: root_pos as u64,
+			fingerprint: T::FINGERPRINT,
+		}
+	}
+
+	/// Read [`Header`] from start of `bytes`.
+	///
+	/// Checks magic number, format version, and that output was produced on a
+	/// system with same pointer width and endianness as this one (unless output
+	/// is from a portable serializer). Does not check anything else.
+	///
+	/// `bytes` does not need to be aligned.
+	pub fn read(bytes: &[u8]) -> Result<Header, HeaderError> {
+		if bytes.len() < HEADER_SIZE {
+			return Err(HeaderError::TooShort);
+		}
+		// `Header` contains only integers, so any bit pattern is valid
+		let mut header = unsafe { ptr::read_unaligned(bytes.as_ptr() as *const Header) };
+
+		if header.magic != MAGIC {
+			return Err(HeaderError::InvalidMagic);
+		}
+		// Check endianness before format version, as version is endian-dependent.
+		// Output of portable serializers can be read on any system.
+		if header.serializer_kind == SerializerKind::Portable as u8 {
+			if header.endianness != Endianness::Little as u8 || ![4, 8].contains(&header.ptr_width) {
+				return Err(HeaderError::WrongArchitecture);
+			}
+			header = header.to_le();
+		} else if header.endianness != Endianness::NATIVE as u8
+			|| header.ptr_width as usize != mem::size_of::<usize>()
+		{
+			return Err(HeaderError::WrongArchitecture);
+		}
+		if header.format_version != FORMAT_VERSION {
+			return Err(HeaderError::UnsupportedVersion {
+				version: header.format_version,
+			});
+		}
+
+		Ok(header)
+	}
+
+	/// Check [`Header`] matches what would be produced by serializing a value of
+	/// type `T` with serializer `S`, and that root value is within output of
+	/// length `len`.
+	///
+	/// Returns position of root value.
+	pub fn check<T: Serialize<S>, S: Serializer>(&self, len: usize) -> Result<usize, HeaderError> {
+		let expected = Header::new::<T, S>(0);
+
+		// `ptr_width` can only differ for portable serializers with different
+		// offset size
+		if self.serializer_kind != expected.serializer_kind || self.ptr_width != expected.ptr_width {
+			return Err(HeaderError::WrongSerializer);
+		}
+		if self.storage_alignment != expected.storage_alignment
+			|| self.max_value_alignment != expected.max_value_alignment
+			|| self.value_alignment != expected.value_alignment
+			|| self.max_capacity != expected.max_capacity
+		{
+			return Err(HeaderError::WrongStorageParams);
+		}
+		if self.fingerprint != expected.fingerprint {
+			return Err(HeaderError::WrongFingerprint {
+				expected: expected.fingerprint,
+				found: self.fingerprint,
+			});
+		}
+
+		let root_pos = self.root_pos as usize;
+		if self.root_pos < HEADER_SIZE as u64
+			|| root_pos as u64 != self.root_pos
+			|| root_pos > len
+			|| len - root_pos < S::value_size::<T>()
+		{
+			return Err(HeaderError::RootPosOutOfBounds);
+		}
+
+		Ok(root_pos)
+	}
+
+	/// Convert multi-byte fields between native endianness and little-endian.
+	/// Conversion is the same in both directions.
+	pub(crate) fn to_le(self) -> Self {
+		Self {
+			format_version: self.format_version.to_le(),
+			storage_alignment: self.storage_alignment.to_le(),
+			max_value_alignment: self.max_value_alignment.to_le(),
+			value_alignment: self.value_alignment.to_le(),
+			max_capacity: self.max_capacity.to_le(),
+			root_pos: self.root_pos.to_le(),
+			fingerprint: self.fingerprint.to_le(),
+			..self
+		}
+	}
+}
+
+/// Read and check [`Header`] at start of `bytes`.
+///
+/// Returns an error if the output was produced on a system with different
+/// architecture (unless produced by a portable serializer), or was not produced
+/// by serializing a value of type `T` with serializer `S` (or one with the same
+/// configuration).
+///
+/// If header is valid, returns position of root value.
+pub fn check<T: Serialize<S>, S: Serializer>(bytes: &[u8]) -> Result<usize, HeaderError> {
+	Header::read(bytes)?.check::<T, S>(bytes.len())
+}
+
+/// Error returned when [`Header`] is invalid, or does not match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeaderError {
+	/// Input is too short to contain a header
+	TooShort,
+	/// Input does not start with [`MAGIC`]
+	InvalidMagic,
+	/// Output was produced by a different version of `ser_raw`
+	UnsupportedVersion { version: u32 },
+	/// Output was produced on a system with different pointer width or
+	/// endianness
+	WrongArchitecture,
+	/// Output was produced by a different type of serializer, or a portable
+	/// serializer with different offset size
+	WrongSerializer,
+	/// Output was produced by a serializer with different storage const
+	/// parameters
+	WrongStorageParams,
+	/// Output was produced from a different type, or a different definition of
+	/// the type
+	WrongFingerprint { expected: u64, found: u64 },
+	/// Root value position is out of bounds
+	RootPosOutOfBounds,
+}
+
+impl fmt::Display for HeaderError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::TooShort => write!(f, "Input is too short to contain header"),
+			Self::InvalidMagic => write!(f, "Input does not start with header"),
+			Self::UnsupportedVersion { version } => {
+				write!(f, "Unsupported format version {version}")
+			}
+			Self::WrongArchitecture => {
+				write!(
+					f,
+					"Output was produced on a system with different architecture"
+				)
+			}
+			Self::WrongSerializer => write!(f, "Output was produced by a different serializer"),
+			Self::WrongStorageParams => {
+				write!(f, "Output was produced with different storage parameters")
+			}
+			Self::WrongFingerprint { expected, found } => {
+				write!(
+					f,
+					"Type fingerprint mismatch (expected {expected:#018x}, found {found:#018x})"
+				)
+			}
+			Self::RootPosOutOfBounds => write!(f, "Root value position is out of bounds"),
+		}
+	}
+}
+
+impl Error for HeaderError {}
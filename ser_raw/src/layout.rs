@@ -0,0 +1,431 @@
+//! Type layout schemas.
+//!
+//! A [`Schema`] describes the memory layout of a type, and all the types it
+//! contains: size, alignment, offsets of fields, how enums' discriminants are
+//! encoded, and which fields are `Box`es, `Vec`s, `String`s, boxed slices or
+//! `Option`s.
+//!
+//! It contains everything external tooling (e.g. a deserializer written in
+//! another language) needs to decode output of any of the serializers in this
+//! crate. It can be exported as JSON with [`Schema::to_json`].
+//!
+//! Layouts are obtained from types implementing [`Layout`], which can be
+//! derived.
+//!
+//! # Example
+//!
+//! ```
+//! use ser_raw::{
+//!     layout::{Schema, TypeKind},
+//!     Layout, Serialize,
+//! };
+//!
+//! #[derive(Serialize, Layout)]
+//! #[repr(C)]
+//! struct Foo {
+//!     small: u8,
+//!     names: Vec<String>,
+//! }
+//!
+//! let schema = Schema::new::<Foo>();
+//! assert_eq!(schema.root(), "Foo");
+//!
+//! let foo = schema.get("Foo").unwrap();
+//! let TypeKind::Struct { fields } = &foo.kind else { panic!() };
+//! assert_eq!(fields[1].name, "names");
+//! assert_eq!(fields[1].offset, 8);
+//! assert_eq!(fields[1].type_name, "Vec<String>");
+//!
+//! let json = schema.to_json();
+//! assert!(json.starts_with(r#"{"root":"Foo","#));
+//! ```
+//!
+//! # Interpreting serializers' output
+//!
+//! Values are laid out in output exactly as described by the schema. Only
+//! pointers (in `Box`, `Vec`, `String`, `Box<[T]>` and `Box<str>`) differ
+//! between serializers:
+//!
+//! * [`PureCopySerializer`]: Pointers are meaningless. Pointees follow in
+//!   output in the order values are visited (depth first).
+//! * [`PtrOffsetSerializer`]: Pointers are replaced with position of pointee in
+//!   output.
+//! * [`CompleteSerializer`]: Pointers are valid pointers into output.
+//!
+//! [`PureCopySerializer`]: crate::PureCopySerializer
+//! [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+//! [`CompleteSerializer`]: crate::CompleteSerializer
+
+use std::{collections::HashMap, mem};
+
+use crate::{header::Endianness, niche::Niche};
+
+mod json;
+pub use json::JsonError;
+
+/// Trait for types which can describe their memory layout.
+///
+/// Usually implemented with the derive macro.
+///
+/// # With derive macro
+///
+/// ```
+/// use ser_raw::{Layout, Serialize};
+///
+/// #[derive(Serialize, Layout)]
+/// struct Foo {
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
+/// }
+/// ```
+///
+/// Enums with fields must have an explicit discriminant type e.g.
+/// `#[repr(u8)]` or `#[repr(C, u8)]`, as otherwise Rust gives no guarantees
+/// about where the discriminant is located.
+///
+/// # Manual implementation
+///
+/// [`NICHE`](Layout::NICHE) is required, as for
+/// [`Validate::NICHE`](crate::Validate::NICHE).
+///
+/// ```
+/// use ser_raw::{
+///     layout::{field_offset, Field, Schema, TypeKind},
+///     niche::Niche,
+///     Layout,
+/// };
+///
+/// #[repr(C)]
+/// struct Foo {
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
+/// }
+///
+/// impl Layout for Foo {
+///     const NICHE: Option<Niche> = <Vec<u8> as Layout>::NICHE;
+///
+///     fn type_name() -> String {
+///         "Foo".to_string()
+///     }
+///
+///     fn type_kind(schema: &mut Schema) -> TypeKind {
+///         let foo = std::mem::MaybeUninit::<Foo>::uninit();
+///         let ptr = foo.as_ptr();
+///         unsafe {
+///             TypeKind::Struct {
+///                 fields: vec![
+///                     Field::new(
+///                         "smalls",
+///                         field_offset(ptr, std::ptr::addr_of!((*ptr).smalls)),
+///                         schema.add::<Vec<u8>>(),
+///                     ),
+///                     Field::new(
+///                         "bigs",
+///                         field_offset(ptr, std::ptr::addr_of!((*ptr).bigs)),
+///                         schema.add::<Vec<u32>>(),
+///                     ),
+///                 ],
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub trait Layout {
+	/// Location of type's niche, used to describe layout of `Option`s of the
+	/// type. `None` if type has no niche. See [`niche`](crate::niche) module.
+	const NICHE: Option<Niche>;
+
+	/// Get name of type.
+	///
+	/// Must be unique among all types in a [`Schema`]. Generic types should
+	/// include names of their type parameters e.g. `Foo<u8>`.
+	fn type_name() -> String;
+
+	/// Describe layout of type.
+	///
+	/// Any types this type contains should be added to `schema` with
+	/// [`Schema::add`], which returns their names.
+	fn type_kind(schema: &mut Schema) -> TypeKind;
+}
+
+/// Trait for implementing an equivalent of [`Layout`] on foreign types for
+/// which it's not possible to implement [`Layout`] directly due to orphan
+/// rules. Counterpart to [`SerializeWith`].
+///
+/// The same type used with `#[ser_with]` should implement both
+/// [`SerializeWith`] and [`LayoutWith`].
+///
+/// [`SerializeWith`]: crate::SerializeWith
+pub trait LayoutWith<T> {
+	/// Location of niche of type `T`. See [`Layout::NICHE`].
+	const NICHE: Option<Niche>;
+
+	/// Get name of type `T`.
+	fn type_name_with() -> String;
+
+	/// Describe layout of type `T`.
+	fn type_kind_with(schema: &mut Schema) -> TypeKind;
+}
+
+/// Layout schema for a type, and all the types it contains.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schema {
+	root: String,
+	ptr_size: usize,
+	endianness: Endianness,
+	types: Vec<TypeDef>,
+	indexes: HashMap<String, usize>,
+}
+
+impl Schema {
+	/// Create [`Schema`] for type `T`.
+	pub fn new<T: Layout>() -> Self {
+		let mut schema = Self {
+			root: String::new(),
+			ptr_size: mem::size_of::<usize>(),
+			endianness: Endianness::NATIVE,
+			types: vec![],
+			indexes: HashMap::new(),
+		};
+		schema.root = schema.add::<T>();
+		schema
+	}
+
+	/// Get name of root type.
+	pub fn root(&self) -> &str {
+		&self.root
+	}
+
+	/// Get size of pointers (and `usize`) in bytes, on system which produced
+	/// schema.
+	pub fn ptr_size(&self) -> usize {
+		self.ptr_size
+	}
+
+	/// Get endianness of system which produced schema.
+	pub fn endianness(&self) -> Endianness {
+		self.endianness
+	}
+
+	/// Get all types in schema.
+	///
+	/// Types are in the order they were added. Root type is first.
+	pub fn types(&self) -> &[TypeDef] {
+		&self.types
+	}
+
+	/// Get definition of type with name `name`.
+	pub fn get(&self, name: &str) -> Option<&TypeDef> {
+		self.indexes.get(name).map(|&index| &self.types[index])
+	}
+
+	/// Add type `T` to schema (if it's not already), and get its name.
+	pub fn add<T: Layout>(&mut self) -> String {
+		self.add_type::<T>(T::type_name(), T::type_kind)
+	}
+
+	/// Add type `T` to schema (if it's not already) using `LayoutWith`
+	/// implementation `W`, and get its name.
+	pub fn add_with<W: LayoutWith<T>, T>(&mut self) -> String {
+		self.add_type::<T>(W::type_name_with(), W::type_kind_with)
+	}
+
+	fn add_type<T>(&mut self, name: String, type_kind: fn(&mut Schema) -> TypeKind) -> String {
+		if self.indexes.contains_key(&name) {
+			return name;
+		}
+
+		// Add type before getting its kind, so recursive types terminate.
+		// `TypeKind::Primitive` is a placeholder.
+		let index = self.types.len();
+		self.types.push(TypeDef {
+			name: name.clone(),
+			size: mem::size_of::<T>(),
+			align: mem::align_of::<T>(),
+			kind: TypeKind::Primitive,
+		});
+		self.indexes.insert(name.clone(), index);
+
+		self.types[index].kind = type_kind(self);
+		name
+	}
+
+	/// Export schema as JSON.
+	///
+	/// Output is an object with properties:
+	///
+	/// * `root`: Name of root type.
+	/// * `ptr_size`: Size of pointers in bytes.
+	/// * `endianness`: `"little"` or `"big"`.
+	/// * `types`: Array of type definitions, in same order as [`types`].
+	///
+	/// Each type definition has properties `name`, `size`, `align` and `kind`,
+	/// plus properties of the [`TypeKind`] (in snake case). `kind` is name of
+	/// the [`TypeKind`] variant in snake case. `Option`s' `none` bytes are
+	/// arrays of `[offset, byte]` pairs.
+	///
+	/// [`types`]: Schema::types
+	pub fn to_json(&self) -> String {
+		json::write(self)
+	}
+
+	/// Parse schema from JSON, in the format produced by
+	/// [`to_json`](Schema::to_json).
+	///
+	/// Schema may have been produced on a different system. Its pointer size and
+	/// endianness are available from [`ptr_size`](Schema::ptr_size) and
+	/// [`endianness`](Schema::endianness).
+	pub fn from_json(json: &str) -> Result<Self, JsonError> {
+		json::parse(json)
+	}
+}
+
+/// Definition of a type in a [`Schema`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeDef {
+	/// Name of type
+	pub name: String,
+	/// Size of type in bytes
+	pub size: usize,
+	/// Alignment of type in bytes
+	pub align: usize,
+	/// Description of type's layout
+	pub kind: TypeKind,
+}
+
+/// Description of a type's layout.
+///
+/// All offsets are in bytes, from start of the value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeKind {
+	/// Primitive type e.g. `u8`, `f64`, `bool`, `char`, `NonZeroU32`.
+	/// Type's name identifies which.
+	Primitive,
+	/// Struct or tuple. Tuples' fields are named `0`, `1` etc.
+	Struct { fields: Vec<Field> },
+	/// Enum.
+	///
+	/// Discriminant is stored in the first `tag_size` bytes, in native
+	/// endianness. For fieldless enums, `tag_size` is size of the enum.
+	Enum {
+		tag_size: usize,
+		variants: Vec<Variant>,
+	},
+	/// `Box<T>`. A pointer to `inner`.
+	Box { inner: String },
+	/// `Vec<T>`. Pointer, capacity and length are each `usize`.
+	Vec {
+		inner: String,
+		ptr_offset: usize,
+		cap_offset: usize,
+		len_offset: usize,
+	},
+	/// `String`. Pointer, capacity and length are each `usize`.
+	String {
+		ptr_offset: usize,
+		cap_offset: usize,
+		len_offset: usize,
+	},
+	/// `Box<[T]>`. Pointer and length are each `usize`.
+	BoxedSlice {
+		inner: String,
+		ptr_offset: usize,
+		len_offset: usize,
+	},
+	/// `Box<str>`. Pointer and length are each `usize`.
+	BoxedStr {
+		ptr_offset: usize,
+		len_offset: usize,
+	},
+	/// `Option<T>`.
+	///
+	/// Value is `None` if bytes at each offset in `none` are equal to the paired
+	/// byte. Otherwise it's `Some`, and the `T` is at `payload_offset`.
+	Option {
+		inner: String,
+		payload_offset: usize,
+		none: Vec<(usize, u8)>,
+	},
+	/// Array `[T; N]`.
+	Array { inner: String, len: usize },
+}
+
+/// Field of a struct, or of an enum variant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+	/// Name of field
+	pub name: String,
+	/// Offset of field in bytes, from start of struct or enum
+	pub offset: usize,
+	/// Name of field's type
+	pub type_name: String,
+}
+
+impl Field {
+	/// Create [`Field`].
+	pub fn new<N: Into<String>>(name: N, offset: usize, type_name: String) -> Self {
+		Self {
+			name: name.into(),
+			offset,
+			type_name,
+		}
+	}
+}
+
+/// Variant of an enum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variant {
+	/// Name of variant
+	pub name: String,
+	/// Discriminant value (unsigned)
+	pub discriminant: u64,
+	/// Fields of variant
+	pub fields: Vec<Field>,
+}
+
+impl Variant {
+	/// Create [`Variant`].
+	pub fn new<N: Into<String>>(name: N, discriminant: u64, fields: Vec<Field>) -> Self {
+		Self {
+			name: name.into(),
+			discriminant,
+			fields,
+		}
+	}
+}
+
+/// Get offset of a field from pointers to the containing value and the field.
+///
+/// Used with `MaybeUninit` and `addr_of!` to get offsets of fields without
+/// creating a value.
+pub fn field_offset<T, F>(ptr: *const T, field_ptr: *const F) -> usize {
+	field_ptr as usize - ptr as usize
+}
+
+/// Get discriminant value from bytes of a discriminant, in native endianness.
+///
+/// # Panics
+///
+/// Panics if discriminant is more than 8 bytes, and does not fit in a `u64`.
+pub fn discriminant_from_bytes(bytes: &[u8]) -> u64 {
+	let mut buf = [0u8; 8];
+	let (value, rest) = if bytes.len() > 8 {
+		#[cfg(target_endian = "little")]
+		let (value, rest) = bytes.split_at(8);
+		#[cfg(target_endian = "big")]
+		let (rest, value) = bytes.split_at(bytes.len() - 8);
+		(value, rest)
+	} else {
+		(bytes, &[][..])
+	};
+	assert!(
+		rest.iter().all(|&byte| byte == 0),
+		"Enum discriminant does not fit in a `u64`"
+	);
+
+	#[cfg(target_endian = "little")]
+	buf[..value.len()].copy_from_slice(value);
+	#[cfg(target_endian = "big")]
+	buf[8 - value.len()..].copy_from_slice(value);
+	u64::from_ne_bytes(buf)
+}
@@ -0,0 +1,657 @@
+//! Conversion of [`Schema`] to and from JSON.
+//!
+//! JSON format is described in [`Schema::to_json`].
+
+use std::{collections::HashMap, error::Error, fmt, fmt::Write};
+
+use super::{Field, Schema, TypeDef, TypeKind, Variant};
+use crate::header::Endianness;
+
+// Writing
+
+/// Write schema as JSON.
+pub(super) fn write(schema: &Schema) -> String {
+	let mut json = String::new();
+	json.push_str(r#"{"root":"#);
+	write_str(&mut json, &schema.root);
+	let endianness = match schema.endianness {
+		Endianness::Little => "little",
+		Endianness::Big => "big",
+	};
+	write!(
+		json,
+		r#","ptr_size":{},"endianness":"{}","types":["#,
+		schema.ptr_size, endianness
+	)
+	.unwrap();
+	for (index, type_def) in schema.types.iter().enumerate() {
+		if index > 0 {
+			json.push(',');
+		}
+		write_type_def(&mut json, type_def);
+	}
+	json.push_str("]}");
+	json
+}
+
+fn write_type_def(json: &mut String, type_def: &TypeDef) {
+	json.push_str(r#"{"name":"#);
+	write_str(json, &type_def.name);
+	write!(
+		json,
+		r#","size":{},"align":{},"kind":"#,
+		type_def.size, type_def.align
+	)
+	.unwrap();
+
+	match &type_def.kind {
+		TypeKind::Primitive => json.push_str(r#""primitive""#),
+		TypeKind::Struct { fields } => {
+			json.push_str(r#""struct","fields":"#);
+			write_fields(json, fields);
+		}
+		TypeKind::Enum { tag_size, variants } => {
+			write!(json, r#""enum","tag_size":{tag_size},"variants":["#).unwrap();
+			for (index, variant) in variants.iter().enumerate() {
+				if index > 0 {
+					json.push(',');
+				}
+				json.push_str(r#"{"name":"#);
+				write_str(json, &variant.name);
+				write!(
+					json,
+					r#","discriminant":{},"fields":"#,
+					variant.discriminant
+				)
+				.unwrap();
+				write_fields(json, &variant.fields);
+				json.push('}');
+			}
+			json.push(']');
+		}
+		TypeKind::Box { inner } => {
+			json.push_str(r#""box","inner":"#);
+			write_str(json, inner);
+		}
+		TypeKind::Vec {
+			inner,
+			ptr_offset,
+			cap_offset,
+			len_offset,
+		} => {
+			json.push_str(r#""vec","inner":"#);
+			write_str(json, inner);
+			write!(
+				json,
+				r#","ptr_offset":{ptr_offset},"cap_offset":{cap_offset},"len_offset":{len_offset}"#
+			)
+			.unwrap();
+		}
+		TypeKind::String {
+			ptr_offset,
+			cap_offset,
+			len_offset,
+		} => {
+			write!(
+				json,
+				r#""string","ptr_offset":{ptr_offset},"cap_offset":{cap_offset},"len_offset":{len_offset}"#
+			)
+			.unwrap();
+		}
+		TypeKind::BoxedSlice {
+			inner,
+			ptr_offset,
+			len_offset,
+		} => {
+			json.push_str(r#""boxed_slice","inner":"#);
+			write_str(json, inner);
+			write!(
+				json,
+				r#","ptr_offset":{ptr_offset},"len_offset":{len_offset}"#
+			)
+			.unwrap();
+		}
+		TypeKind::BoxedStr {
+			ptr_offset,
+			len_offset,
+		} => {
+			write!(
+				json,
+				r#""boxed_str","ptr_offset":{ptr_offset},"len_offset":{len_offset}"#
+			)
+			.unwrap();
+		}
+		TypeKind::Option {
+			inner,
+			payload_offset,
+			none,
+		} => {
+			json.push_str(r#""option","inner":"#);
+			write_str(json, inner);
+			write!(json, r#","payload_offset":{payload_offset},"none":["#).unwrap();
+			for (index, (offset, byte)) in none.iter().enumerate() {
+				if index > 0 {
+					json.push(',');
+				}
+				write!(json, "[{offset},{byte}]").unwrap();
+			}
+			json.push(']');
+		}
+		TypeKind::Array { inner, len } => {
+			json.push_str(r#""array","inner":"#);
+			write_str(json, inner);
+			write!(json, r#","len":{len}"#).unwrap();
+		}
+	}
+
+	json.push('}');
+}
+
+fn write_fields(json: &mut String, fields: &[Field]) {
+	json.push('[');
+	for (index, field) in fields.iter().enumerate() {
+		if index > 0 {
+			json.push(',');
+		}
+		json.push_str(r#"{"name":"#);
+		write_str(json, &field.name);
+		write!(json, r#","offset":{},"type":"#, field.offset).unwrap();
+		write_str(json, &field.type_name);
+		json.push('}');
+	}
+	json.push(']');
+}
+
+fn write_str(json: &mut String, str: &str) {
+	json.push('"');
+	for c in str.chars() {
+		match c {
+			'"' => json.push_str("\\\""),
+			'\\' => json.push_str("\\\\"),
+			c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+			c => json.push(c),
+		}
+	}
+	json.push('"');
+}
+
+// Parsing
+
+/// Parse schema from JSON.
+pub(super) fn parse(json: &str) -> Result<Schema, JsonError> {
+	let mut parser = Parser {
+		bytes: json.as_bytes(),
+		pos: 0,
+	};
+	let value = parser.parse_value()?;
+	parser.skip_whitespace();
+	if parser.pos != parser.bytes.len() {
+		return Err(JsonError::Syntax { pos: parser.pos });
+	}
+
+	let mut obj = value.into_object("schema")?;
+	let root = obj.take_str("root")?;
+	let ptr_size = obj.take_usize("ptr_size")?;
+	if ptr_size != 4 && ptr_size != 8 {
+		return Err(invalid("`ptr_size` must be 4 or 8"));
+	}
+	let endianness = match obj.take_str("endianness")?.as_str() {
+		"little" => Endianness::Little,
+		"big" => Endianness::Big,
+		_ => return Err(invalid("`endianness` must be \"little\" or \"big\"")),
+	};
+	let types = obj
+		.take_array("types")?
+		.into_iter()
+		.map(parse_type_def)
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut indexes = HashMap::new();
+	for (index, type_def) in types.iter().enumerate() {
+		if indexes.insert(type_def.name.clone(), index).is_some() {
+			return Err(invalid(format!("Duplicate type `{}`", type_def.name)));
+		}
+	}
+
+	let schema = Schema {
+		root,
+		ptr_size,
+		endianness,
+		types,
+		indexes,
+	};
+	check_type_refs(&schema)?;
+	Ok(schema)
+}
+
+fn parse_type_def(value: Value) -> Result<TypeDef, JsonError> {
+	let mut obj = value.into_object("type")?;
+	let name = obj.take_str("name")?;
+	let size = obj.take_usize("size")?;
+	let align = obj.take_usize("align")?;
+	let kind = match obj.take_str("kind")?.as_str() {
+		"primitive" => TypeKind::Primitive,
+		"struct" => {
+			TypeKind::Struct {
+				fields: parse_fields(obj.take_array("fields")?)?,
+			}
+		}
+		"enum" => {
+			TypeKind::Enum {
+				tag_size: obj.take_usize("tag_size")?,
+				variants: obj
+					.take_array("variants")?
+					.into_iter()
+					.map(|value| {
+						let mut obj = value.into_object("variant")?;
+						Ok(Variant {
+							name: obj.take_str("name")?,
+							discriminant: obj.take_u64("discriminant")?,
+							fields: parse_fields(obj.take_array("fields")?)?,
+						})
+					})
+					.collect::<Result<_, JsonError>>()?,
+			}
+		}
+		"box" => {
+			TypeKind::Box {
+				inner: obj.take_str("inner")?,
+			}
+		}
+		"vec" => {
+			TypeKind::Vec {
+				inner: obj.take_str("inner")?,
+				ptr_offset: obj.take_usize("ptr_offset")?,
+				cap_offset: obj.take_usize("cap_offset")?,
+				len_offset: obj.take_usize("len_offset")?,
+			}
+		}
+		"string" => {
+			TypeKind::String {
+				ptr_offset: obj.take_usize("ptr_offset")?,
+				cap_offset: obj.take_usize("cap_offset")?,
+				len_offset: obj.take_usize("len_offset")?,
+			}
+		}
+		"boxed_slice" => {
+			TypeKind::BoxedSlice {
+				inner: obj.take_str("inner")?,
+				ptr_offset: obj.take_usize("ptr_offset")?,
+				len_offset: obj.take_usize("len_offset")?,
+			}
+		}
+		"boxed_str" => {
+			TypeKind::BoxedStr {
+				ptr_offset: obj.take_usize("ptr_offset")?,
+				len_offset: obj.take_usize("len_offset")?,
+			}
+		}
+		"option" => {
+			TypeKind::Option {
+				inner: obj.take_str("inner")?,
+				payload_offset: obj.take_usize("payload_offset")?,
+				none: obj
+					.take_array("none")?
+					.into_iter()
+					.map(|value| {
+						let pair = value.into_array("`none` entry")?;
+						match &pair[..] {
+							[Value::Number(offset), Value::Number(byte)] if *byte <= u8::MAX as u64 => {
+								Ok((to_usize(*offset, "none")?, *byte as u8))
+							}
+							_ => Err(invalid("`none` entries must be `[offset, byte]` pairs")),
+						}
+					})
+					.collect::<Result<_, JsonError>>()?,
+			}
+		}
+		"array" => {
+			TypeKind::Array {
+				inner: obj.take_str("inner")?,
+				len: obj.take_usize("len")?,
+			}
+		}
+		kind => return Err(invalid(format!("Unknown type kind `{kind}`"))),
+	};
+
+	Ok(TypeDef {
+		name,
+		size,
+		align,
+		kind,
+	})
+}
+
+fn parse_fields(values: Vec<Value>) -> Result<Vec<Field>, JsonError> {
+	values
+		.into_iter()
+		.map(|value| {
+			let mut obj = value.into_object("field")?;
+			Ok(Field {
+				name: obj.take_str("name")?,
+				offset: obj.take_usize("offset")?,
+				type_name: obj.take_str("type")?,
+			})
+		})
+		.collect()
+}
+
+/// Check all types referred to are defined in schema.
+fn check_type_refs(schema: &Schema) -> Result<(), JsonError> {
+	let check = |name: &String| {
+		if schema.indexes.contains_key(name) {
+			Ok(())
+		} else {
+			Err(invalid(format!("Type `{name}` is not defined")))
+		}
+	};
+
+	check(&schema.root)?;
+	for type_def in &schema.types {
+		match &type_def.kind {
+			TypeKind::Primitive | TypeKind::String { .. } | TypeKind::BoxedStr { .. } => {}
+			TypeKind::Struct { fields } => {
+				fields
+					.iter()
+					.try_for_each(|field| check(&field.type_name))?;
+			}
+			TypeKind::Enum { variants, .. } => {
+				for variant in variants {
+					variant
+						.fields
+						.iter()
+						.try_for_each(|field| check(&field.type_name))?;
+				}
+			}
+			TypeKind::Box { inner }
+			| TypeKind::Vec { inner, .. }
+			| TypeKind::BoxedSlice { inner, .. }
+			| TypeKind::Option { inner, .. }
+			| TypeKind::Array { inner, .. } => check(inner)?,
+		}
+	}
+	Ok(())
+}
+
+fn invalid<M: Into<String>>(message: M) -> JsonError {
+	JsonError::InvalidSchema {
+		message: message.into(),
+	}
+}
+
+fn to_usize(value: u64, name: &str) -> Result<usize, JsonError> {
+	usize::try_from(value).map_err(|_| invalid(format!("`{name}` is too large")))
+}
+
+/// JSON value. Numbers must be non-negative integers, as that's all a schema
+/// contains.
+enum Value {
+	Null,
+	Bool,
+	Number(u64),
+	String(String),
+	Array(Vec<Value>),
+	Object(Object),
+}
+
+impl Value {
+	fn into_object(self, name: &str) -> Result<Object, JsonError> {
+		match self {
+			Value::Object(obj) => Ok(obj),
+			_ => Err(invalid(format!("Expected {name} to be an object"))),
+		}
+	}
+
+	fn into_array(self, name: &str) -> Result<Vec<Value>, JsonError> {
+		match self {
+			Value::Array(values) => Ok(values),
+			_ => Err(invalid(format!("Expected {name} to be an array"))),
+		}
+	}
+}
+
+struct Object {
+	props: HashMap<String, Value>,
+}
+
+impl Object {
+	fn take(&mut self, key: &str) -> Result<Value, JsonError> {
+		self
+			.props
+			.remove(key)
+			.ok_or_else(|| invalid(format!("Missing property `{key}`")))
+	}
+
+	fn take_str(&mut self, key: &str) -> Result<String, JsonError> {
+		match self.take(key)? {
+			Value::String(str) => Ok(str),
+			_ => Err(invalid(format!("Expected `{key}` to be a string"))),
+		}
+	}
+
+	fn take_u64(&mut self, key: &str) -> Result<u64, JsonError> {
+		match self.take(key)? {
+			Value::Number(num) => Ok(num),
+			_ => Err(invalid(format!("Expected `{key}` to be a number"))),
+		}
+	}
+
+	fn take_usize(&mut self, key: &str) -> Result<usize, JsonError> {
+		to_usize(self.take_u64(key)?, key)
+	}
+
+	fn take_array(&mut self, key: &str) -> Result<Vec<Value>, JsonError> {
+		self.take(key)?.into_array(&format!("`{key}`"))
+	}
+}
+
+struct Parser<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn parse_value(&mut self) -> Result<Value, JsonError> {
+		self.skip_whitespace();
+		match self.peek() {
+			Some(b'{') => self.parse_object(),
+			Some(b'[') => self.parse_array(),
+			Some(b'"') => Ok(Value::String(self.parse_str()?)),
+			Some(b'0'..=b'9') => self.parse_number(),
+			Some(b'n') => self.parse_literal("null", Value::Null),
+			Some(b't') => self.parse_literal("true", Value::Bool),
+			Some(b'f') => self.parse_literal("false", Value::Bool),
+			_ => Err(self.error()),
+		}
+	}
+
+	fn parse_object(&mut self) -> Result<Value, JsonError> {
+		self.pos += 1;
+		let mut props = HashMap::new();
+		self.skip_whitespace();
+		if self.peek() == Some(b'}') {
+			self.pos += 1;
+			return Ok(Value::Object(Object { props }));
+		}
+		loop {
+			self.skip_whitespace();
+			if self.peek() != Some(b'"') {
+				return Err(self.error());
+			}
+			let key = self.parse_str()?;
+			self.skip_whitespace();
+			self.expect(b':')?;
+			let value = self.parse_value()?;
+			props.insert(key, value);
+			self.skip_whitespace();
+			match self.next() {
+				Some(b',') => {}
+				Some(b'}') => return Ok(Value::Object(Object { props })),
+				_ => return Err(self.error_before()),
+			}
+		}
+	}
+
+	fn parse_array(&mut self) -> Result<Value, JsonError> {
+		self.pos += 1;
+		let mut values = vec![];
+		self.skip_whitespace();
+		if self.peek() == Some(b']') {
+			self.pos += 1;
+			return Ok(Value::Array(values));
+		}
+		loop {
+			values.push(self.parse_value()?);
+			self.skip_whitespace();
+			match self.next() {
+				Some(b',') => {}
+				Some(b']') => return Ok(Value::Array(values)),
+				_ => return Err(self.error_before()),
+			}
+		}
+	}
+
+	fn parse_str(&mut self) -> Result<String, JsonError> {
+		self.pos += 1;
+		let mut str = String::new();
+		loop {
+			let start = self.pos;
+			while !matches!(self.peek(), Some(b'"' | b'\\') | None) {
+				self.pos += 1;
+			}
+			// Input is a `&str`, and we only stop at ASCII chars, so this is valid UTF-8
+			str.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).unwrap());
+
+			match self.next() {
+				Some(b'"') => return Ok(str),
+				Some(b'\\') => {
+					let c = match self.next() {
+						Some(b'"') => '"',
+						Some(b'\\') => '\\',
+						Some(b'/') => '/',
+						Some(b'b') => '\u{8}',
+						Some(b'f') => '\u{c}',
+						Some(b'n') => '\n',
+						Some(b'r') => '\r',
+						Some(b't') => '\t',
+						Some(b'u') => self.parse_unicode_escape()?,
+						_ => return Err(self.error_before()),
+					};
+					str.push(c);
+				}
+				_ => return Err(self.error_before()),
+			}
+		}
+	}
+
+	fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+		let high = self.parse_hex4()?;
+		let code = if (0xd800..0xdc00).contains(&high) {
+			// Surrogate pair
+			self.expect(b'\\')?;
+			self.expect(b'u')?;
+			let low = self.parse_hex4()?;
+			if !(0xdc00..0xe000).contains(&low) {
+				return Err(self.error_before());
+			}
+			0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+		} else {
+			high
+		};
+		char::from_u32(code).ok_or_else(|| self.error_before())
+	}
+
+	fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+		let hex = self
+			.bytes
+			.get(self.pos..self.pos + 4)
+			.and_then(|hex| std::str::from_utf8(hex).ok())
+			.and_then(|hex| u32::from_str_radix(hex, 16).ok())
+			.ok_or_else(|| self.error())?;
+		self.pos += 4;
+		Ok(hex)
+	}
+
+	fn parse_number(&mut self) -> Result<Value, JsonError> {
+		let start = self.pos;
+		while matches!(self.peek(), Some(b'0'..=b'9')) {
+			self.pos += 1;
+		}
+		// Only non-negative integers are supported
+		if matches!(self.peek(), Some(b'.' | b'e' | b'E')) {
+			return Err(self.error());
+		}
+		let digits = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+		if digits.len() > 1 && digits.starts_with('0') {
+			return Err(JsonError::Syntax { pos: start });
+		}
+		digits
+			.parse()
+			.map(Value::Number)
+			.map_err(|_| JsonError::Syntax { pos: start })
+	}
+
+	fn parse_literal(&mut self, literal: &str, value: Value) -> Result<Value, JsonError> {
+		if self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+			self.pos += literal.len();
+			Ok(value)
+		} else {
+			Err(self.error())
+		}
+	}
+
+	fn skip_whitespace(&mut self) {
+		while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+			self.pos += 1;
+		}
+	}
+
+	fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+		if self.next() == Some(byte) {
+			Ok(())
+		} else {
+			Err(self.error_before())
+		}
+	}
+
+	fn peek(&self) -> Option<u8> {
+		self.bytes.get(self.pos).copied()
+	}
+
+	fn next(&mut self) -> Option<u8> {
+		let byte = self.peek();
+		self.pos += 1;
+		byte
+	}
+
+	fn error(&self) -> JsonError {
+		JsonError::Syntax { pos: self.pos }
+	}
+
+	/// Error at position of last byte consumed
+	fn error_before(&self) -> JsonError {
+		JsonError::Syntax {
+			pos: self.pos.saturating_sub(1).min(self.bytes.len()),
+		}
+	}
+}
+
+/// Error returned by [`Schema::from_json`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonError {
+	/// Input is not valid JSON. `pos` is byte offset of error in input.
+	Syntax { pos: usize },
+	/// Input is valid JSON, but is not a valid schema
+	InvalidSchema { message: String },
+}
+
+impl fmt::Display for JsonError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Syntax { pos } => write!(f, "Invalid JSON at position {pos}"),
+			Self::InvalidSchema { message } => write!(f, "Invalid schema: {message}"),
+		}
+	}
+}
+
+impl Error for JsonError {}
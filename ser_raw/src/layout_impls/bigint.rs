@@ -0,0 +1,83 @@
+use std::mem;
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+use crate::{
+	layout::{discriminant_from_bytes, Field, Schema, TypeKind, Variant},
+	niche::Niche,
+	serialize_impls::{bigint::bigint_data_offset, ptrs::VecOffsets},
+	Layout, Validate,
+};
+
+// `BigUint` is just a wrapper around a `Vec<usize>`.
+// See `Serialize` implementation.
+impl Layout for BigUint {
+	const NICHE: Option<Niche> = <BigUint as Validate>::NICHE;
+
+	fn type_name() -> String {
+		"BigUint".to_string()
+	}
+
+	fn type_kind(schema: &mut Schema) -> TypeKind {
+		TypeKind::Vec {
+			inner: schema.add::<usize>(),
+			ptr_offset: VecOffsets::<usize>::PTR_OFFSET,
+			cap_offset: VecOffsets::<usize>::OFFSETS_VEC.capacity(),
+			len_offset: VecOffsets::<usize>::OFFSETS_VEC.len(),
+		}
+	}
+}
+
+// `BigInt` is defined as `BigInt { sign: Sign, data: BigUint }`.
+// See `Serialize` implementation.
+impl Layout for BigInt {
+	const NICHE: Option<Niche> = <BigInt as Validate>::NICHE;
+
+	fn type_name() -> String {
+		"BigInt".to_string()
+	}
+
+	fn type_kind(schema: &mut Schema) -> TypeKind {
+		let data_offset = bigint_data_offset();
+		let sign_offset = if data_offset == 0 {
+			mem::size_of::<BigUint>()
+		} else {
+			0
+		};
+
+		TypeKind::Struct {
+			fields: vec![
+				Field::new("sign", sign_offset, schema.add::<Sign>()),
+				Field::new("data", data_offset, schema.add::<BigUint>()),
+			],
+		}
+	}
+}
+
+impl Layout for Sign {
+	const NICHE: Option<Niche> = Niche::tag(
+		1,
+		&[
+			Sign::Minus as i128,
+			Sign::NoSign as i128,
+			Sign::Plus as i128,
+		],
+	);
+
+	fn type_name() -> String {
+		"Sign".to_string()
+	}
+
+	fn type_kind(_schema: &mut Schema) -> TypeKind {
+		let variant =
+			|name: &str, sign: Sign| Variant::new(name, discriminant_from_bytes(&[sign as u8]), vec![]);
+		TypeKind::Enum {
+			tag_size: mem::size_of::<Sign>(),
+			variants: vec![
+				variant("Minus", Sign::Minus),
+				variant("NoSign", Sign::NoSign),
+				variant("Plus", Sign::Plus),
+			],
+		}
+	}
+}
@@ -0,0 +1,7 @@
+mod multiples;
+mod other;
+mod primitives;
+mod ptrs;
+
+#[cfg(feature = "num_bigint")]
+mod bigint;
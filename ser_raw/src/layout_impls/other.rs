@@ -0,0 +1,37 @@
+use std::marker::PhantomData;
+
+use crate::{
+	layout::{Schema, TypeKind},
+	niche::{Niche, OptionRepr},
+	Layout,
+};
+
+// Layout of `Option<T>` is determined at compile time, from `T`'s niche.
+// See `niche` module.
+impl<T> Layout for Option<T>
+where T: Layout
+{
+	const NICHE: Option<Niche> = OptionReprOf::<T>::REPR.niche;
+
+	fn type_name() -> String {
+		format!("Option<{}>", T::type_name())
+	}
+
+	fn type_kind(schema: &mut Schema) -> TypeKind {
+		let repr = OptionReprOf::<T>::REPR;
+		TypeKind::Option {
+			inner: schema.add::<T>(),
+			payload_offset: repr.payload_offset,
+			none: repr.none_bytes(),
+		}
+	}
+}
+
+struct OptionReprOf<T> {
+	_marker: PhantomData<T>,
+}
+
+impl<T: Layout> OptionReprOf<T> {
+	// Safe because evaluated in const context
+	const REPR: OptionRepr = unsafe { OptionRepr::new::<T>(T::NICHE) };
+}
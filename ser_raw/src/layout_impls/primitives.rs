@@ -0,0 +1,59 @@
+use std::num;
+
+use crate::{
+	layout::{Schema, TypeKind},
+	niche::Niche,
+	Layout, Validate,
+};
+
+macro_rules! impl_primitive {
+	($ty:ty, $name:literal) => {
+		impl Layout for $ty {
+			const NICHE: Option<Niche> = <$ty as Validate>::NICHE;
+
+			fn type_name() -> String {
+				$name.to_string()
+			}
+
+			fn type_kind(_schema: &mut Schema) -> TypeKind {
+				TypeKind::Primitive
+			}
+		}
+	};
+}
+
+impl_primitive!(u8, "u8");
+impl_primitive!(u16, "u16");
+impl_primitive!(u32, "u32");
+impl_primitive!(u64, "u64");
+impl_primitive!(u128, "u128");
+impl_primitive!(usize, "usize");
+
+impl_primitive!(i8, "i8");
+impl_primitive!(i16, "i16");
+impl_primitive!(i32, "i32");
+impl_primitive!(i64, "i64");
+impl_primitive!(i128, "i128");
+impl_primitive!(isize, "isize");
+
+impl_primitive!(f32, "f32");
+impl_primitive!(f64, "f64");
+
+impl_primitive!(bool, "bool");
+impl_primitive!(char, "char");
+
+impl_primitive!((), "()");
+
+impl_primitive!(num::NonZeroU8, "NonZeroU8");
+impl_primitive!(num::NonZeroU16, "NonZeroU16");
+impl_primitive!(num::NonZeroU32, "NonZeroU32");
+impl_primitive!(num::NonZeroU64, "NonZeroU64");
+impl_primitive!(num::NonZeroU128, "NonZeroU128");
+impl_primitive!(num::NonZeroUsize, "NonZeroUsize");
+
+impl_primitive!(num::NonZeroI8, "NonZeroI8");
+impl_primitive!(num::NonZeroI16, "NonZeroI16");
+impl_primitive!(num::NonZeroI32, "NonZeroI32");
+impl_primitive!(num::NonZeroI64, "NonZeroI64");
+impl_primitive!(num::NonZeroI128, "NonZeroI128");
+impl_primitive!(num::NonZeroIsize, "NonZeroIsize");
@@ -0,0 +1,92 @@
+use crate::{
+	layout::{Schema, TypeKind},
+	niche::Niche,
+	serialize_impls::ptrs::{
+		VecOffsets, BOXED_SLICE_LEN_OFFSET, BOXED_SLICE_PTR_OFFSET, OFFSETS_STRING, STRING_PTR_OFFSET,
+	},
+	Layout,
+};
+
+impl<T> Layout for Box<T>
+where T: Layout
+{
+	const NICHE: Option<Niche> = Niche::ptr(0);
+
+	fn type_name() -> String {
+		format!("Box<{}>", T::type_name())
+	}
+
+	fn type_kind(schema: &mut Schema) -> TypeKind {
+		TypeKind::Box {
+			inner: schema.add::<T>(),
+		}
+	}
+}
+
+impl<T> Layout for Vec<T>
+where T: Layout
+{
+	const NICHE: Option<Niche> = Niche::ptr(VecOffsets::<T>::PTR_OFFSET);
+
+	fn type_name() -> String {
+		format!("Vec<{}>", T::type_name())
+	}
+
+	fn type_kind(schema: &mut Schema) -> TypeKind {
+		TypeKind::Vec {
+			inner: schema.add::<T>(),
+			ptr_offset: VecOffsets::<T>::PTR_OFFSET,
+			cap_offset: VecOffsets::<T>::OFFSETS_VEC.capacity(),
+			len_offset: VecOffsets::<T>::OFFSETS_VEC.len(),
+		}
+	}
+}
+
+impl Layout for String {
+	const NICHE: Option<Niche> = Niche::ptr(STRING_PTR_OFFSET);
+
+	fn type_name() -> String {
+		"String".to_string()
+	}
+
+	fn type_kind(_schema: &mut Schema) -> TypeKind {
+		TypeKind::String {
+			ptr_offset: STRING_PTR_OFFSET,
+			cap_offset: OFFSETS_STRING.capacity(),
+			len_offset: OFFSETS_STRING.len(),
+		}
+	}
+}
+
+impl<T> Layout for Box<[T]>
+where T: Layout
+{
+	const NICHE: Option<Niche> = Niche::ptr(BOXED_SLICE_PTR_OFFSET);
+
+	fn type_name() -> String {
+		format!("Box<[{}]>", T::type_name())
+	}
+
+	fn type_kind(schema: &mut Schema) -> TypeKind {
+		TypeKind::BoxedSlice {
+			inner: schema.add::<T>(),
+			ptr_offset: BOXED_SLICE_PTR_OFFSET,
+			len_offset: BOXED_SLICE_LEN_OFFSET,
+		}
+	}
+}
+
+impl Layout for Box<str> {
+	const NICHE: Option<Niche> = Niche::ptr(BOXED_SLICE_PTR_OFFSET);
+
+	fn type_name() -> String {
+		"Box<str>".to_string()
+	}
+
+	fn type_kind(_schema: &mut Schema) -> TypeKind {
+		TypeKind::BoxedStr {
+			ptr_offset: BOXED_SLICE_PTR_OFFSET,
+			len_offset: BOXED_SLICE_LEN_OFFSET,
+		}
+	}
+}
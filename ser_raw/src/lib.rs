@@ -26,7 +26,7 @@
 //!
 //! # Serializers
 //!
-//! This crate provides 3 different serializers for different use cases. They
+//! This crate provides 4 different serializers for different use cases. They
 //! offer a range of options, between doing work during serialization, or during
 //! deserialization. They mostly differ in how they deal with pointers.
 //!
@@ -39,13 +39,19 @@
 //! the output.
 //!
 //! This allows lazy deserialization, and for a deserializer to traverse the
-//! tree of values in any order/direction.
+//! tree of values in any order/direction. [`PtrOffset32Serializer`] is a
+//! variant which writes offsets as `u32`s.
 //!
 //! [`CompleteSerializer`] replaces pointers in the input with valid pointers
 //! into the output, and makes other corrections to ensure output is a
 //! completely valid representation of the input. Input can be "rehydrated" just
 //! by casting a pointer to the start of the output buffer as a `&T`.
 //!
+//! [`PortableSerializer`] writes values in a fixed little-endian format, with
+//! pointers replaced by offsets, instead of copying Rust's native memory
+//! layouts. Its output is identical on all systems. See [`portable`] module
+//! for details of the format.
+//!
 //! # Custom serializers
 //!
 //! This crate provides an easy-to-use [derive
@@ -53,15 +59,84 @@
 //! [`Serializer`]s, based on any of the above.
 //!
 //! Serializers can also choose between different backing storage options.
-//! This crate provides only one at present - [`AlignedVec`] - but it's
-//! possible to create your own [`Storage`] implementation.
+//! This crate provides [`AlignedVec`], [`FixedVec`] which has fixed capacity
+//! and never moves, and `MmapStorage` which writes output directly to a
+//! memory-mapped file or to shared memory, for handing output to another
+//! process without copying. [`WriteStorage`] streams output to any
+//! `io::Write` as it's produced, and [`ArenaStorage`] allocates output from an
+//! arena allocator. It's also possible to create your own [`Storage`]
+//! implementation.
+//!
+//! # Pre-allocating output
+//!
+//! [`serialized_size`] calculates the exact size of output a serializer would
+//! produce for a value, with a dry run which only counts bytes (see
+//! [`SizeCountingSerializer`]). Use it to create a serializer with
+//! `with_capacity`, so its storage is allocated once and never has to grow.
+//!
+//! # Reusing serializers
+//!
+//! To serialize many values, each into its own output, a single serializer can
+//! be reused without allocating new memory for each one.
+//! [`serialize_in_place`](Serializer::serialize_in_place) resets the serializer
+//! and serializes a value without consuming the serializer, and
+//! [`reset`](Serializer::reset) clears all state from a previous serialization
+//! while retaining allocated memory. [`SerializerPool`] keeps a pool of
+//! pre-warmed serializers, for use across threads.
+//!
+//! # Fallible serialization
+//!
+//! By default, serialization panics if output would exceed storage's maximum
+//! capacity. [`try_serialize`](Serializer::try_serialize) and
+//! [`try_serialize_value`](Serializer::try_serialize_value) return a
+//! [`SerializeError`] instead, and can also limit output to a byte budget set
+//! at runtime. Errors are signalled by unwinding, so this requires
+//! `panic = "unwind"` (the default). See [`fallible`] module.
 //!
 //! # Serializable types
 //!
-//! Only owned, sized types are supported at present.
+//! Only owned types are supported at present.
 //!
 //! Support for serializing common Rust types (e.g. `u8`, `isize`, `NonZeroU32`,
-//! `Box`, `Vec`, `String`, `Option`) is included out of the box.
+//! `Box`, `Vec`, `String`, `Option`) is included out of the box. Unsized boxed
+//! types `Box<[T]>`, `Box<str>` and `Box<Path>` (Unix only) are also supported.
+//!
+//! Wrapper types `Cell`, `RefCell`, `Mutex`, `RwLock`, `ManuallyDrop`,
+//! `Wrapping` and `Reverse` are serialized as the value they contain, along
+//! with `PhantomData`, `Duration`, `Range*`, `cmp::Ordering` and atomics.
+//! `RefCell`s, `Mutex`es and `RwLock`s must not be borrowed / locked while
+//! being serialized - [`try_serialize`](Serializer::try_serialize) returns an
+//! error if they are. `Mutex`es and `RwLock`s cannot be serialized with
+//! [`PureCopySerializer`], and except on Linux, Android, FreeBSD, OpenBSD,
+//! DragonFly and Fuchsia, can only be serialized with a portable serializer.
+//! They are written to output unlocked and unpoisoned. `Cow<str>` and
+//! `Cow<[T]>` are supported too, and are always `Cow::Owned` once
+//! deserialized.
+//!
+//! `VecDeque`, `HashMap`, `HashSet`, `BTreeMap` and `BTreeSet` are supported
+//! too. Maps and sets are written as a slice of their entries, sorted by key
+//! (so keys must implement `Ord`), which can be searched with
+//! [`OffsetMap`](read::OffsetMap) without deserializing. The result is not a
+//! valid `HashMap` etc, so maps and sets cannot be serialized with
+//! [`CompleteSerializer`] - doing so is a compile-time error:
+//!
+//! ```compile_fail
+//! use std::collections::HashMap;
+//! use ser_raw::{
+//!     storage::AlignedVec, util::aligned_max_capacity, CompleteSerializer, Serializer,
+//! };
+//!
+//! const MAX_CAPACITY: usize = aligned_max_capacity(16);
+//! type Ser = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+//! Ser::new().serialize(&HashMap::<u32, u32>::new());
+//! ```
+//!
+//! `Rc`, `Arc` and their `Weak` counterparts are supported by serializers
+//! which track shared allocations (all serializers provided by this crate).
+//! Each shared allocation is written to output only once per value passed to
+//! [`serialize_value`](Serializer::serialize_value), however many references
+//! to it there are, so reference cycles through `Weak` pointers are fine. A
+//! leaked cycle of strong pointers is deserialized as a leaked cycle too.
 //!
 //! For your own types, implement the [`Serialize`] trait. Usually, you can use
 //! the [derive macro](ser_raw_derive::Serialize).
@@ -71,8 +146,8 @@
 //!
 //! #[derive(Serialize)]
 //! struct Foo {
-//! 	small: u8,
-//! 	vec: Vec<u32>
+//!     small: u8,
+//!     vec: Vec<u32>
 //! }
 //! ```
 //!
@@ -80,10 +155,32 @@
 //!
 //! # Deserializing
 //!
-//! No deserializers are provided at present.
+//! [`PureCopySerializer`]'s output can be deserialized with
+//! [`PureCopyDeserializer`], for types which implement [`Deserialize`].
+//! Usually, you can use the [derive macro](ser_raw_derive::Deserialize).
+//!
+//! ```
+//! use ser_raw::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Foo {
+//!     small: u8,
+//!     vec: Vec<u32>
+//! }
+//! ```
 //!
 //! [`CompleteSerializer`] doesn't require a deserializer anyway, as you can
-//! just cast a pointer to the output buffer to a `&T`.
+//! just cast a pointer to the output buffer to a `&T`. If the output may not be
+//! trustworthy, use [`validate()`] to check it's valid first. This requires the
+//! type to implement [`Validate`], which can also be
+//! [derived](ser_raw_derive::Validate).
+//!
+//! [`CompleteSerializer`]'s output is only valid at the memory address it was
+//! created at. To move it (e.g. write it to disk and read it back later), use
+//! the [`relocate`] module to fix pointers after it's moved.
+//!
+//! [`PtrOffsetSerializer`]'s output can be read lazily with the types in the
+//! [`read`] module, which follow offsets in the output on demand.
 //!
 //! # Warning
 //!
@@ -100,15 +197,42 @@
 //! binary. A mismatch will be very likely to cause memory unsafety and the
 //! dreaded *undefined behavior*.
 //!
-//!	For the primary use case for `ser_raw` - transfer of data within a single
+//! For the primary use case for `ser_raw` - transfer of data within a single
 //! system - these constraints are not a problem.
 //!
+//! To detect a mismatch, serialize with
+//! [`serialize_with_header`](Serializer::serialize_with_header), which writes a
+//! [`Header`](header::Header) at start of output recording the system
+//! architecture, serializer settings, and a fingerprint of the type's layout.
+//! Check it with [`header::check`] before reading the output.
+//!
+//! If output needs to be read on a different type of system, use
+//! [`PortableSerializer`] instead.
+//!
 //! # Features
 //!
-//! `derive` feature enables the [`Serialize`] derive macro. Enabled by default.
+//! `derive` feature enables the [`Serialize`], [`Deserialize`], [`Validate`]
+//! and [`Layout`] derive macros. Enabled by default.
+//!
+//! `num_bigint` feature enables serialization, deserialization, validation and
+//! layout schemas of [`num-bigint`]'s [`BigInt`] and [`BigUint`] types.
 //!
-//! `num_bigint` feature enables serialization of [`num-bigint`]'s [`BigInt`]
-//! and [`BigUint`] types.
+//! `smallvec`, `thin_vec`, `compact_str` and `smol_str` features enable
+//! serialization, deserialization and validation of [`SmallVec`],
+//! [`ThinVec`], [`CompactString`] and [`SmolStr`] respectively. Inline and heap
+//! representations are both supported. `smallvec` feature enables
+//! `smallvec`'s `union` feature. `thin-vec`'s `gecko-ffi` feature is not
+//! supported.
+//!
+//! `string_cache` feature does the same for [`string_cache`]'s [`Atom`].
+//! Dynamic atoms' strings live in a global interner, so a copy is written to
+//! output. In [`CompleteSerializer`]'s output, they are not interned, so
+//! compare unequal (`==`) to atoms outside output with the same string.
+//! `hstr` atoms are not supported.
+//!
+//! `mmap` feature enables `MmapStorage`, [`Storage`] backed by a memory-mapped
+//! file or shared memory, and `MmapReader` for mapping output read-only in
+//! another process. Unix only.
 //!
 //! # Future direction and motivation
 //!
@@ -121,8 +245,11 @@
 //! layouts, and write a codegen which uses that schema to generate a JavaScript
 //! serializer / deserializer which can deserialize `ser_raw`'s output.
 //!
-//! This is the main reason why there aren't deserializers implemented in Rust
-//! yet! I'm planning to be doing the deserialization in JavaScript.
+//! The [`layout`] module provides such a schema for types implementing
+//! [`Layout`] (which can be derived), and can export it as JSON.
+//!
+//! This is the main reason why there is only one deserializer implemented in
+//! Rust so far. I'm planning to be doing most deserialization in JavaScript.
 //!
 //! # Credits
 //!
@@ -136,6 +263,9 @@
 //!
 //! [`AlignedVec`]: storage::AlignedVec
 //! [`Storage`]: storage::Storage
+//! [`FixedVec`]: storage::FixedVec
+//! [`WriteStorage`]: storage::WriteStorage
+//! [`ArenaStorage`]: storage::ArenaStorage
 //! [SWC]: https://swc.rs/
 //! [napi-rs]: https://napi.rs/
 //! [serde JSON]: https://serde.rs/
@@ -146,10 +276,16 @@
 //! [`num-bigint`]: https://crates.io/crates/num-bigint
 //! [`BigInt`]: https://docs.rs/num-bigint/latest/num_bigint/struct.BigInt.html
 //! [`BigUint`]: https://docs.rs/num-bigint/latest/num_bigint/struct.BigUint.html
+//! [`SmallVec`]: https://docs.rs/smallvec/latest/smallvec/struct.SmallVec.html
+//! [`ThinVec`]: https://docs.rs/thin-vec/latest/thin_vec/struct.ThinVec.html
+//! [`CompactString`]: https://docs.rs/compact_str/latest/compact_str/struct.CompactString.html
+//! [`SmolStr`]: https://docs.rs/smol_str/latest/smol_str/struct.SmolStr.html
+//! [`string_cache`]: https://crates.io/crates/string_cache
+//! [`Atom`]: https://docs.rs/string_cache/latest/string_cache/struct.Atom.html
 
 // Derive macros
 #[cfg(feature = "derive")]
-pub use ser_raw_derive::Serialize;
+pub use ser_raw_derive::{Deserialize, Layout, Serialize, Validate};
 pub use ser_raw_derive_serializer::Serializer;
 
 // Export Serializers, Storage, traits, and utils
@@ -157,7 +293,14 @@ mod serializer;
 pub use serializer::Serializer;
 
 mod serializers;
-pub use serializers::{CompleteSerializer, PtrOffsetSerializer, PureCopySerializer};
+pub use serializers::{
+	serialized_size, serialized_size_with_header, CompleteSerializer, PortableSerializer,
+	PtrOffset32Serializer, PtrOffsetSerializer, PureCopySerializer, SizeCountingSerializer,
+};
+
+mod pool;
+pub use fallible::SerializeError;
+pub use pool::{PooledSerializer, SerializerPool};
 
 mod serializer_traits;
 pub mod ser_traits {
@@ -169,9 +312,37 @@ pub mod ser_traits {
 mod serialize;
 pub use serialize::{Serialize, SerializeWith};
 
+// Export Deserializers and traits
+mod deserializer;
+pub use deserializer::{Deserializer, SharedValues};
+
+mod deserializers;
+pub use deserializers::PureCopyDeserializer;
+
+mod deserialize;
+pub use deserialize::{Deserialize, DeserializeWith};
+
+// Export validation
+mod validate;
+// Export layout schemas
+pub use layout::{Layout, LayoutWith};
+pub use validate::{validate, Validate, ValidateError, ValidateWith, Validator};
+
+pub mod fallible;
+pub mod fingerprint;
+pub mod header;
+pub mod layout;
+pub mod niche;
+pub mod portable;
 pub mod pos;
+pub mod read;
+pub mod relocate;
 pub mod storage;
 pub mod util;
 
-// `Serialize` implementations for Rust internal types
+// `Serialize`, `Deserialize`, `Validate` and `Layout` implementations for
+// Rust internal types
+mod deserialize_impls;
+mod layout_impls;
 mod serialize_impls;
+mod validate_impls;
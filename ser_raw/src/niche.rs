@@ -0,0 +1,302 @@
+//! Niches in types' layouts, used to determine layout of `Option<T>`.
+//!
+//! Rust gives no guarantees about layout of `Option<T>`. If `Option<T>` is
+//! same size as `T`, `None` is represented by a "niche" in `T` - a bit pattern
+//! which is not a valid `T` (e.g. a null pointer for `Box`, 2 for `bool`).
+//! Otherwise, `Option<T>` has a tag before the `T`.
+//!
+//! Which bytes represent `None` can't be deduced at runtime without reading
+//! uninitialized memory, which is undefined behavior. So layout of `Option<T>`
+//! is only determined from a niche `T` states explicitly, and checked at
+//! compile time:
+//!
+//! 1. Every implementation of [`Validate`] and [`Layout`] states where its
+//!    type's niche is with [`Validate::NICHE`] / [`Layout::NICHE`], or that it
+//!    has none. There is no default.
+//! 2. `Option<T>` must be same size as `T` if, and only if, `T` states it has a
+//!    niche.
+//! 3. `Option<Option<T>>` must be same size as `T` if, and only if, the niche
+//!    `T` states has more than one invalid value.
+//! 4. The bytes of `None` are read from the stated niche in const evaluation.
+//!    Const evaluation refuses to read uninitialized bytes, and `None` only
+//!    initializes the niche Rust actually uses, so if a type's niche is stated
+//!    wrongly, reading `None` fails.
+//!
+//! If any of these checks fail, using `Option<T>` is a compile-time error, not
+//! undefined behavior.
+//!
+//! Where Rust's choice of niche is not guaranteed, this crate relies on it
+//! being the one current compilers use: the pointer of `Vec`, `String` and
+//! `VecDeque`, and for structs and tuples the niche with most invalid values,
+//! first in memory if there's a tie (see [`Niche::largest`]). If a compiler or
+//! standard library makes a different choice, `Option`s of the affected types
+//! fail to compile.
+//! The `option_layouts` test pins these assumptions for every type this crate
+//! implements `NICHE` for.
+//!
+//! [`Validate`]: crate::Validate
+//! [`Validate::NICHE`]: crate::Validate::NICHE
+//! [`Layout`]: crate::Layout
+//! [`Layout::NICHE`]: crate::Layout::NICHE
+
+use std::{
+	mem::{self, ManuallyDrop, MaybeUninit},
+	ptr,
+};
+
+/// Location of a type's niche, and how many invalid values it has available.
+///
+/// `size` must be the exact size of the field containing the niche (e.g. 4 for
+/// a `char`, or size of the tag of an enum), and at most 16.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Niche {
+	/// Offset of niche in bytes, from start of the type
+	pub offset: usize,
+	/// Size of niche in bytes
+	pub size: usize,
+	/// Number of bit patterns of the niche's bytes which are invalid
+	pub available: u128,
+}
+
+impl Niche {
+	/// Create [`Niche`].
+	pub const fn new(offset: usize, size: usize, available: u128) -> Self {
+		Self {
+			offset,
+			size,
+			available,
+		}
+	}
+
+	/// Get niche of a pointer, which is invalid only when null.
+	pub const fn ptr(offset: usize) -> Option<Self> {
+		Some(Self::new(offset, mem::size_of::<usize>(), 1))
+	}
+
+	/// Get niche of a tag (or whole fieldless enum) of `size` bytes, which can
+	/// have only the values in `values`.
+	///
+	/// Rust treats values between lowest and highest as valid, so the niche is
+	/// the values outside that range.
+	pub const fn tag(size: usize, values: &[i128]) -> Option<Self> {
+		if size == 0 || values.is_empty() {
+			return None;
+		}
+
+		let (mut min, mut max) = (values[0], values[0]);
+		let mut index = 1;
+		while index < values.len() {
+			if values[index] < min {
+				min = values[index];
+			}
+			if values[index] > max {
+				max = values[index];
+			}
+			index += 1;
+		}
+
+		let max_value = if size >= 16 {
+			u128::MAX
+		} else {
+			(1 << (size * 8)) - 1
+		};
+		match max_value - max.abs_diff(min) {
+			0 => None,
+			available => Some(Self::new(0, size, available)),
+		}
+	}
+
+	/// Get niche of a field at `offset` in a containing type.
+	pub const fn field(niche: Option<Self>, offset: usize) -> Option<Self> {
+		match niche {
+			Some(niche) => {
+				Some(Self::new(
+					niche.offset + offset,
+					niche.size,
+					niche.available,
+				))
+			}
+			None => None,
+		}
+	}
+
+	/// Get largest of niches of a type's fields.
+	///
+	/// Rust chooses the niche which has the most available values. If more than
+	/// one have the same number, it chooses the first in memory. This is not
+	/// guaranteed, but if it changes, `Option`s of types using it fail to
+	/// compile. See [module docs](self).
+	pub const fn largest(niches: &[Option<Self>]) -> Option<Self> {
+		let mut largest: Option<Self> = None;
+		let mut index = 0;
+		while index < niches.len() {
+			if let Some(niche) = niches[index] {
+				largest = match largest {
+					Some(current)
+						if current.available > niche.available
+							|| (current.available == niche.available && current.offset < niche.offset) =>
+					{
+						Some(current)
+					}
+					_ => Some(niche),
+				};
+			}
+			index += 1;
+		}
+		largest
+	}
+}
+
+/// Get offset of a field from pointers to the containing value and the field,
+/// in const context.
+///
+/// Used by `Validate` and `Layout` derive macros to calculate `NICHE`, with a
+/// pointer obtained from a [`Probe`].
+///
+/// # Safety
+///
+/// `field_ptr` must be a pointer to a field within value at `ptr`.
+#[doc(hidden)]
+pub const unsafe fn field_offset<T, F>(ptr: *const T, field_ptr: *const F) -> usize {
+	(field_ptr as *const u8).offset_from(ptr as *const u8) as usize
+}
+
+/// Wrapper for getting a pointer to a value in const context.
+///
+/// Const evaluation does not allow taking a reference or pointer to a value
+/// which may contain interior mutability, but does allow a pointer to `start`,
+/// which is at same address as `value`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct Probe<T> {
+	pub start: [u8; 0],
+	pub value: T,
+}
+
+impl<T> Probe<T> {
+	#[doc(hidden)]
+	pub const fn new(value: T) -> Self {
+		Self { start: [], value }
+	}
+}
+
+/// Layout of `Option<T>`.
+#[derive(Clone, Copy)]
+pub(crate) struct OptionRepr {
+	/// Offset of `T` within `Option<T>`
+	pub payload_offset: usize,
+	/// Offset of bytes which encode whether option is `Some` or `None`
+	pub tag_offset: usize,
+	/// Number of bytes which encode whether option is `Some` or `None`
+	pub tag_size: usize,
+	/// Value of tag bytes for `None`, as read by [`read_bytes`]
+	pub none: u128,
+	/// Value of tag bytes for `Some`, if `Option<T>` is not niche-encoded.
+	/// If it is niche-encoded, any value other than `none` is `Some`.
+	pub some: Option<u128>,
+	/// Niche of `Option<T>` itself
+	pub niche: Option<Niche>,
+}
+
+impl OptionRepr {
+	/// Determine layout of `Option<T>`, where `T`'s niche is `niche`.
+	///
+	/// Panics if `niche` is not consistent with layout of `Option<T>`.
+	/// See [module docs](self) for the checks.
+	///
+	/// # Safety
+	///
+	/// Must only be called in const context (i.e. to initialize a `const`).
+	/// Reads bytes of `Option`s which may be uninitialized. In const context,
+	/// that's a compile-time error, but at runtime it'd be undefined behavior.
+	pub const unsafe fn new<T>(niche: Option<Niche>) -> Self {
+		let none = Probe::new(ManuallyDrop::new(None::<T>));
+		let none_ptr = ptr::addr_of!(none.start) as *const u8;
+
+		if mem::size_of::<Option<T>>() == mem::size_of::<T>() {
+			// `None` is represented by a niche in `T`
+			let Some(niche) = niche else {
+				panic!("Location of niche unknown. Type must specify `NICHE`.");
+			};
+			assert!(niche.size <= 16 && niche.offset + niche.size <= mem::size_of::<T>());
+			assert!(
+				(niche.available > 1) == (mem::size_of::<Option<Option<T>>>() == mem::size_of::<T>()),
+				"`NICHE` of type does not match its layout"
+			);
+
+			let option_niche = if niche.available > 1 {
+				Some(Niche::new(niche.offset, niche.size, niche.available - 1))
+			} else {
+				None
+			};
+			return Self {
+				payload_offset: 0,
+				tag_offset: niche.offset,
+				tag_size: niche.size,
+				none: read_bytes(none_ptr.add(niche.offset), niche.size),
+				some: None,
+				niche: option_niche,
+			};
+		}
+
+		assert!(niche.is_none(), "`NICHE` of type does not match its layout");
+
+		// Tag is before the `T`. Rust makes tag as large as alignment of `T` if it
+		// can, so it's that size unless alignment is larger than any integer.
+		// If it's smaller, then bytes after the tag are padding, and reading them
+		// is a compile-time error.
+		let payload_offset = mem::size_of::<Option<T>>() - mem::size_of::<T>();
+		let tag_size = if payload_offset <= 16 {
+			payload_offset
+		} else {
+			1
+		};
+		let none = read_bytes(none_ptr, tag_size);
+
+		// `Option<MaybeUninit<T>>` has same layout as `Option<T>` where `T` has no
+		// niche, and can be created without a `T`
+		assert!(mem::size_of::<Option<MaybeUninit<T>>>() == mem::size_of::<Option<T>>());
+		let some_none = Probe::new(ManuallyDrop::new(None::<MaybeUninit<T>>));
+		assert!(read_bytes(ptr::addr_of!(some_none.start) as *const u8, tag_size) == none);
+		let some = Probe::new(ManuallyDrop::new(Some(MaybeUninit::<T>::uninit())));
+		let some = read_bytes(ptr::addr_of!(some.start) as *const u8, tag_size);
+		assert!(some != none);
+
+		let max_value = if tag_size >= 16 {
+			u128::MAX
+		} else {
+			(1 << (tag_size * 8)) - 1
+		};
+		Self {
+			payload_offset,
+			tag_offset: 0,
+			tag_size,
+			none,
+			some: Some(some),
+			niche: Some(Niche::new(0, tag_size, max_value - 1)),
+		}
+	}
+
+	/// Get bytes of tag for `None`, with their offsets.
+	pub fn none_bytes(&self) -> Vec<(usize, u8)> {
+		(0..self.tag_size)
+			.map(|index| (self.tag_offset + index, (self.none >> (index * 8)) as u8))
+			.collect()
+	}
+}
+
+/// Read `len` bytes from `ptr` into a `u128`. First byte is lowest 8 bits.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads of `len` bytes, and `len` must be 16 or less.
+#[inline]
+pub(crate) const unsafe fn read_bytes(ptr: *const u8, len: usize) -> u128 {
+	let mut value = 0;
+	let mut index = 0;
+	while index < len {
+		value |= (*ptr.add(index) as u128) << (index * 8);
+		index += 1;
+	}
+	value
+}
@@ -0,0 +1,166 @@
+use std::{
+	ops::{Deref, DerefMut},
+	sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use crate::Serializer;
+
+/// Pool of reusable [`Serializer`]s.
+///
+/// For serializing many values, each into its own output, without allocating
+/// new memory for each one.
+///
+/// [`get`] takes an idle serializer from the pool (or creates a new one if pool
+/// is empty). When the [`PooledSerializer`] it returns is dropped, the
+/// serializer is [`reset`] and returned to the pool, retaining its allocated
+/// memory for next use.
+///
+/// Pool can be shared between threads, if serializer is `Send`.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::{AlignedVec, RandomAccessStorage},
+///     util::aligned_max_capacity,
+///     CompleteSerializer, Serialize, Serializer, SerializerPool,
+/// };
+///
+/// #[derive(Serialize)]
+/// struct Message {
+///     id: u32,
+///     body: String,
+/// }
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Ser = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+///
+/// // Pool of 4 serializers, each with 1 KiB capacity pre-allocated
+/// let pool = SerializerPool::with_size(4, || Ser::with_capacity(1024));
+///
+/// for id in 0..100 {
+///     let input = Message { id, body: format!("Message {id}") };
+///     let mut ser = pool.get();
+///     let pos = ser.serialize_in_place(&input);
+///     let output: &Message = unsafe { ser.storage().read(pos) };
+///     assert_eq!(output.id, id);
+///     assert_eq!(output.body, input.body);
+///     // Serializer is returned to pool when `ser` is dropped
+/// }
+/// assert_eq!(pool.idle(), 4);
+/// ```
+///
+/// [`get`]: SerializerPool::get
+/// [`reset`]: Serializer::reset
+pub struct SerializerPool<S: Serializer, F: Fn() -> S = fn() -> S> {
+	serializers: Mutex<Vec<S>>,
+	create: F,
+}
+
+impl<S: Serializer, F: Fn() -> S> SerializerPool<S, F> {
+	/// Create new empty [`SerializerPool`].
+	///
+	/// `create` is called to create a new serializer whenever one is requested
+	/// when the pool is empty.
+	#[inline]
+	pub fn new(create: F) -> Self {
+		Self {
+			serializers: Mutex::new(Vec::new()),
+			create,
+		}
+	}
+
+	/// Create new [`SerializerPool`], pre-warmed with `size` serializers.
+	///
+	/// `create` is called to create each of them, and to create further
+	/// serializers whenever one is requested when the pool is empty.
+	pub fn with_size(size: usize, create: F) -> Self {
+		let serializers = (0..size).map(|_| create()).collect();
+		Self {
+			serializers: Mutex::new(serializers),
+			create,
+		}
+	}
+
+	/// Get a serializer from the pool.
+	///
+	/// If pool has no idle serializers, a new one is created.
+	///
+	/// Serializer is returned to the pool when the returned [`PooledSerializer`]
+	/// is dropped.
+	pub fn get(&self) -> PooledSerializer<'_, S, F> {
+		let serializer = self.lock().pop().unwrap_or_else(|| (self.create)());
+		PooledSerializer {
+			serializer: Some(serializer),
+			pool: self,
+		}
+	}
+
+	/// Get number of idle serializers in the pool.
+	#[inline]
+	pub fn idle(&self) -> usize {
+		self.lock().len()
+	}
+
+	/// Lock pool's store of serializers.
+	///
+	/// A panic in another thread cannot leave the `Vec` in an invalid state, so
+	/// lock poisoning is ignored.
+	#[inline]
+	fn lock(&self) -> MutexGuard<'_, Vec<S>> {
+		self
+			.serializers
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+/// [`Serializer`] borrowed from a [`SerializerPool`].
+///
+/// Dereferences to the serializer. When dropped, serializer is [`reset`] and
+/// returned to the pool.
+///
+/// [`reset`]: Serializer::reset
+pub struct PooledSerializer<'p, S: Serializer, F: Fn() -> S = fn() -> S> {
+	// Only `None` after `detach` or once dropped
+	serializer: Option<S>,
+	pool: &'p SerializerPool<S, F>,
+}
+
+impl<'p, S: Serializer, F: Fn() -> S> PooledSerializer<'p, S, F> {
+	/// Remove serializer from the pool permanently, and return it.
+	///
+	/// e.g. to call [`finalize`](Serializer::finalize) on it, which consumes
+	/// the serializer.
+	#[inline]
+	pub fn detach(mut self) -> S {
+		self.serializer.take().unwrap()
+	}
+}
+
+impl<'p, S: Serializer, F: Fn() -> S> Deref for PooledSerializer<'p, S, F> {
+	type Target = S;
+
+	#[inline]
+	fn deref(&self) -> &S {
+		self.serializer.as_ref().unwrap()
+	}
+}
+
+impl<'p, S: Serializer, F: Fn() -> S> DerefMut for PooledSerializer<'p, S, F> {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut S {
+		self.serializer.as_mut().unwrap()
+	}
+}
+
+impl<'p, S: Serializer, F: Fn() -> S> Drop for PooledSerializer<'p, S, F> {
+	fn drop(&mut self) {
+		if let Some(mut serializer) = self.serializer.take() {
+			// Reset before returning to pool, so stale state from this use
+			// (e.g. recorded pointers) can never leak into next use
+			serializer.reset();
+			self.pool.lock().push(serializer);
+		}
+	}
+}
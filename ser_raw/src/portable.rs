@@ -0,0 +1,179 @@
+//! Portable output format.
+//!
+//! Other serializers copy Rust's native memory layouts verbatim, so their
+//! output can only be read on a system with same endianness and pointer width
+//! as the one which produced it. Portable serializers (e.g.
+//! [`PortableSerializer`]) instead write values in a fixed format, which is
+//! the same on all systems.
+//!
+//! # Format
+//!
+//! Every type has a fixed-size representation, [`Serialize::PORTABLE_SIZE`]
+//! bytes long. There is no alignment or padding within values.
+//!
+//! * Integers, floats, and `NonZero*` integers are little-endian.
+//! * `usize` and `isize` are written as 64-bit integers.
+//! * `bool` is 1 byte, `0` or `1`.
+//! * `char` is a little-endian `u32`.
+//! * `()`, `PhantomData` and `RangeFull` are 0 bytes.
+//! * `cmp::Ordering` is 1 byte, `-1`, `0` or `1`.
+//! * `Duration` is seconds as a `u64`, followed by nanoseconds as a `u32`.
+//! * Atomics are their current value.
+//! * `Cell`, `RefCell`, `Mutex`, `RwLock`, `ManuallyDrop`, `Wrapping` and
+//!   `Reverse` are the value they contain.
+//! * `Range` and `RangeInclusive` are start followed by end. `RangeFrom`,
+//!   `RangeTo` and `RangeToInclusive` are their single bound.
+//! * Structs, tuples and arrays are their fields / elements in order.
+//! * `Option<T>` is a 1-byte tag (`0` for `None`, `1` for `Some`), followed by
+//!   `T`. For `None`, the bytes for `T` are zero.
+//! * Enums are a 4-byte little-endian tag containing the index of the variant
+//!   (in order of definition, starting at 0, ignoring any explicit
+//!   discriminants), followed by the variant's fields. Enum's size is
+//!   [`TAG_SIZE`] plus size of its largest variant. Unused bytes are zero.
+//!
+//! Data behind pointers is written elsewhere in output, and pointers are
+//! replaced by offsets (position of the data relative to start of output).
+//! Offsets and lengths are little-endian integers, either 4 or 8 bytes,
+//! depending on serializer's [`PORTABLE_OFFSET_SIZE`].
+//!
+//! * `Box<T>` is offset of the `T`.
+//! * `Vec<T>` is offset of its first element, followed by number of elements.
+//! * `String` is offset of its content (UTF-8), followed by length in bytes.
+//! * `Box<[T]>`, `Box<str>` and `Box<Path>` are same as `Vec<T>` and `String`.
+//! * `Cow<[T]>` and `Cow<str>` are same as `Vec<T>` and `String`, whether
+//!   borrowed or owned.
+//! * `VecDeque<T>` is same as `Vec<T>`, with front element first.
+//! * `HashSet<T>` and `BTreeSet<T>` are same as `Vec<T>`, with values sorted.
+//! * `HashMap<K, V>` and `BTreeMap<K, V>` are same as `Vec<(K, V)>`, with
+//!   entries sorted by key. Each entry is key followed by value.
+//!
+//! Empty `Vec`s, `String`s, boxed slices and collections have offset 0. Values
+//! are written in depth-first order, same as [`PureCopySerializer`], but as
+//! they are reached via offsets, they can be read in any order.
+//!
+//! All bytes in output which aren't part of a value (e.g. padding inserted by
+//! [`Storage`] when `VALUE_ALIGNMENT` is more than 1) are zero, so serializing
+//! the same value always produces identical output.
+//!
+//! # Implementing for your own types
+//!
+//! Derive macro for [`Serialize`] implements portable serialization for
+//! structs and enums. For manual implementations, implement
+//! [`PORTABLE_SIZE`](Serialize::PORTABLE_SIZE) and
+//! [`serialize_portable`](Serialize::serialize_portable). Functions in this
+//! module help with this.
+//!
+//! ```
+//! use ser_raw::{portable, Serialize, Serializer};
+//!
+//! // Serialized as a `u16` offset (little-endian), followed by a 1-byte flag
+//! struct Foo {
+//!     offset: u16,
+//!     flag: bool,
+//! }
+//!
+//! impl<S: Serializer> Serialize<S> for Foo {
+//!     const PORTABLE_SIZE: usize = 3;
+//!
+//!     fn serialize_data(&self, _serializer: &mut S) {}
+//!
+//!     fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+//!         serializer.write_portable(pos, &self.offset.to_le_bytes());
+//!         serializer.write_portable(pos + 2, &[self.flag as u8]);
+//!     }
+//! }
+//! ```
+//!
+//! [`PortableSerializer`]: crate::PortableSerializer
+//! [`PureCopySerializer`]: crate::PureCopySerializer
+//! [`PORTABLE_OFFSET_SIZE`]: Serializer::PORTABLE_OFFSET_SIZE
+//! [`Storage`]: crate::storage::Storage
+
+use crate::{Serialize, Serializer};
+
+/// Size of enum tags in bytes.
+pub const TAG_SIZE: usize = 4;
+
+/// Zeroes for filling reserved space.
+const ZEROS: [u8; 256] = [0; 256];
+
+/// Reserve `size` bytes in output, and fill with zeros.
+///
+/// Any padding which storage inserts before or after is also zeroed.
+///
+/// Returns position of reserved space.
+pub fn reserve<S: Serializer>(serializer: &mut S, size: usize) -> usize {
+	let start = serializer.pos();
+	let pos = serializer.push_empty_slice::<u8>(size);
+	write_zeros(serializer, start, serializer.pos() - start);
+	pos
+}
+
+/// Write `len` zero bytes at `pos`.
+fn write_zeros<S: Serializer>(serializer: &mut S, mut pos: usize, mut len: usize) {
+	while len > 0 {
+		let chunk_len = len.min(ZEROS.len());
+		serializer.write_portable(pos, &ZEROS[..chunk_len]);
+		pos += chunk_len;
+		len -= chunk_len;
+	}
+}
+
+/// Push a slice of values to output in portable format.
+///
+/// Reserves space for all values, and then calls
+/// [`serialize_portable`](Serialize::serialize_portable) for each in turn.
+///
+/// Returns position of first value in output.
+pub fn push_slice<T: Serialize<S>, S: Serializer>(serializer: &mut S, slice: &[T]) -> usize {
+	let pos = reserve(serializer, T::PORTABLE_SIZE * slice.len());
+	for (index, value) in slice.iter().enumerate() {
+		value.serialize_portable(serializer, pos + index * T::PORTABLE_SIZE);
+	}
+	pos
+}
+
+/// Push raw bytes to output.
+///
+/// Returns position of bytes in output.
+pub fn push_bytes<S: Serializer>(serializer: &mut S, bytes: &[u8]) -> usize {
+	let pos = reserve(serializer, bytes.len());
+	serializer.write_portable(pos, bytes);
+	pos
+}
+
+/// Write an offset or length at `pos`, as a little-endian integer of
+/// [`PORTABLE_OFFSET_SIZE`](Serializer::PORTABLE_OFFSET_SIZE) bytes.
+///
+/// # Panics
+///
+/// Panics if `value` does not fit in `PORTABLE_OFFSET_SIZE` bytes.
+pub fn write_offset<S: Serializer>(serializer: &mut S, pos: usize, value: usize) {
+	if S::PORTABLE_OFFSET_SIZE == 4 {
+		let value = u32::try_from(value).expect("Offset or length exceeds u32::MAX");
+		serializer.write_portable(pos, &value.to_le_bytes());
+	} else {
+		serializer.write_portable(pos, &(value as u64).to_le_bytes());
+	}
+}
+
+/// Write enum tag for variant with index `index` at `pos`.
+#[inline]
+pub fn write_tag<S: Serializer>(serializer: &mut S, pos: usize, index: u32) {
+	serializer.write_portable(pos, &index.to_le_bytes());
+}
+
+/// Get largest of `sizes`, or 0 if `sizes` is empty.
+///
+/// Used for calculating size of enums.
+pub const fn max_size(sizes: &[usize]) -> usize {
+	let mut max = 0;
+	let mut index = 0;
+	while index < sizes.len() {
+		if sizes[index] > max {
+			max = sizes[index];
+		}
+		index += 1;
+	}
+	max
+}
@@ -1,6 +1,8 @@
 //! Types and traits used for tracking position in output, and locations of
 //! pointers.
 
+use std::collections::HashMap;
+
 /// Mapping from input address (i.e. memory address of value being serialized)
 /// to output position (i.e. position of that value's representation in
 /// serializer's output).
@@ -157,6 +159,78 @@ impl Ptrs {
 			past: Vec::new(),
 		}
 	}
+
+	/// Get positions of all pointers, from all groups.
+	pub fn ptr_positions(&self) -> Vec<usize> {
+		let mut ptr_positions = Vec::with_capacity(
+			self
+				.past
+				.iter()
+				.map(|ptr_group| ptr_group.ptr_positions.len())
+				.sum::<usize>()
+				+ self.current.ptr_positions.len(),
+		);
+		for ptr_group in &self.past {
+			ptr_positions.extend_from_slice(&ptr_group.ptr_positions);
+		}
+		ptr_positions.extend_from_slice(&self.current.ptr_positions);
+		ptr_positions
+	}
+
+	/// Clear all recorded pointers, retaining allocated capacity of current
+	/// group.
+	#[inline]
+	pub fn clear(&mut self) {
+		self.current.clear();
+		self.past.clear();
+	}
+
+	/// Get a checkpoint recording number of pointers recorded so far, which can
+	/// be rolled back to with [`rollback`](Ptrs::rollback).
+	#[inline]
+	pub fn checkpoint(&self) -> PtrsCheckpoint {
+		PtrsCheckpoint {
+			past_len: self.past.len(),
+			current_len: self.current.ptr_positions.len(),
+		}
+	}
+
+	/// Discard all pointers recorded after `checkpoint` was taken.
+	///
+	/// If storage moved after the checkpoint, the group which was current at
+	/// that time becomes current again. Its pointers were written at the old
+	/// storage address, so a new group is started when next pointer is
+	/// recorded.
+	///
+	/// # Panics
+	///
+	/// Panics if `checkpoint` is after pointers currently recorded.
+	pub fn rollback(&mut self, checkpoint: PtrsCheckpoint) {
+		if self.past.len() > checkpoint.past_len {
+			self.past.truncate(checkpoint.past_len + 1);
+			self.current = self.past.pop().unwrap();
+		}
+		assert!(
+			self.past.len() == checkpoint.past_len
+				&& self.current.ptr_positions.len() >= checkpoint.current_len,
+			"Cannot roll back pointers to a later checkpoint"
+		);
+		self.current.ptr_positions.truncate(checkpoint.current_len);
+	}
+}
+
+/// Number of pointers recorded in a [`Ptrs`] at a point in time.
+/// See [`Ptrs::checkpoint`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PtrsCheckpoint {
+	past_len: usize,
+	current_len: usize,
+}
+
+impl Default for Ptrs {
+	fn default() -> Ptrs {
+		Ptrs::new()
+	}
 }
 
 /// A group of pointers which were written to storage when the memory address of
@@ -207,12 +281,26 @@ impl PtrGroup {
 		self.storage_addr = storage_addr;
 	}
 
+	/// Get positions of pointers in this [`PtrGroup`].
+	#[inline]
+	pub fn ptr_positions(&self) -> &[usize] {
+		&self.ptr_positions
+	}
+
 	/// Push a pointer position to this [`PtrGroup`].
 	#[inline]
 	pub fn push_pos(&mut self, pos: usize) {
 		self.ptr_positions.push(pos);
 	}
 
+	/// Remove all pointer positions from this [`PtrGroup`] and reset it to a
+	/// dummy, retaining allocated capacity.
+	#[inline]
+	pub fn clear(&mut self) {
+		self.storage_addr = 0;
+		self.ptr_positions.clear();
+	}
+
 	/// Correct pointers in storage.
 	///
 	/// # Safety
@@ -236,3 +324,194 @@ impl PtrGroup {
 		}
 	}
 }
+
+/// Kind of reference to a shared allocation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RefKind {
+	/// Strong reference e.g. `Rc<T>`, `Arc<T>`.
+	Strong,
+	/// Weak reference e.g. `rc::Weak<T>`, `sync::Weak<T>`.
+	Weak,
+}
+
+/// A record of shared allocations (e.g. the allocations which `Rc`s and `Arc`s
+/// point to) which have been serialized.
+///
+/// Maps memory address of each allocation in input to its position in output,
+/// so each allocation is only written to output once, and all references to it
+/// can point to that single copy.
+///
+/// Addresses are only remembered while serializing a single top-level value
+/// (see [`start_value`](SharedAllocs::start_value)). Between values, an
+/// allocation may be dropped and its address reused for another.
+///
+/// Also counts strong and weak references to each allocation which have been
+/// serialized.
+#[derive(Default)]
+pub struct SharedAllocs {
+	/// All allocations serialized, in order they were written to output
+	allocs: Vec<SharedAlloc>,
+	/// Maps memory address of allocations serialized as part of current
+	/// top-level value to their index in `allocs`
+	indexes: HashMap<usize, usize>,
+	/// References added to allocations already serialized, since first
+	/// checkpoint was taken, so they can be removed again on rollback.
+	/// Allocations are identified by index in `allocs`.
+	/// `None` if no checkpoint has been taken.
+	added_refs: Option<Vec<(usize, RefKind)>>,
+}
+
+/// A shared allocation which has been serialized. See [`SharedAllocs`].
+#[derive(Copy, Clone, Debug)]
+pub struct SharedAlloc {
+	/// Position of allocation in output
+	pub pos: usize,
+	/// Number of strong references to allocation serialized
+	pub strong: usize,
+	/// Number of weak references to allocation serialized
+	pub weak: usize,
+}
+
+impl SharedAllocs {
+	/// Create new empty [`SharedAllocs`].
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			allocs: Vec::new(),
+			indexes: HashMap::new(),
+			added_refs: None,
+		}
+	}
+
+	/// Record a reference to allocation at memory address `addr`.
+	///
+	/// If allocation has already been serialized, returns its position in
+	/// output. Otherwise returns `None`, and caller should serialize the
+	/// allocation, and then call [`insert`](SharedAllocs::insert).
+	#[inline]
+	pub fn add_ref(&mut self, addr: usize, kind: RefKind) -> Option<usize> {
+		let index = *self.indexes.get(&addr)?;
+		let alloc = &mut self.allocs[index];
+		match kind {
+			RefKind::Strong => alloc.strong += 1,
+			RefKind::Weak => alloc.weak += 1,
+		}
+		if let Some(added_refs) = &mut self.added_refs {
+			added_refs.push((index, kind));
+		}
+		Some(alloc.pos)
+	}
+
+	/// Record that allocation at memory address `addr` has been written to
+	/// output at position `pos`, with a reference to it of kind `kind`.
+	///
+	/// `pos` must be after position of all allocations recorded previously.
+	#[inline]
+	pub fn insert(&mut self, addr: usize, pos: usize, kind: RefKind) {
+		debug_assert!(self.allocs.last().map_or(true, |last| last.pos < pos));
+		let (strong, weak) = match kind {
+			RefKind::Strong => (1, 0),
+			RefKind::Weak => (0, 1),
+		};
+		self.indexes.insert(addr, self.allocs.len());
+		self.allocs.push(SharedAlloc { pos, strong, weak });
+	}
+
+	/// Forget memory addresses of allocations serialized so far, so references
+	/// encountered from now on are not deduplicated against them.
+	///
+	/// Called at start of serializing each top-level value. Input is borrowed
+	/// for duration of serializing a value, so allocations it references cannot
+	/// be dropped during that time. But an allocation serialized as part of an
+	/// earlier value may since have been dropped, and a new allocation created at
+	/// the same address.
+	///
+	/// Allocations are still included in [`iter`](SharedAllocs::iter).
+	#[inline]
+	pub fn start_value(&mut self) {
+		self.indexes.clear();
+	}
+
+	/// Get iterator over all allocations which have been serialized.
+	#[inline]
+	pub fn iter(&self) -> impl Iterator<Item = &SharedAlloc> {
+		self.allocs.iter()
+	}
+
+	/// Get number of allocations which have been serialized.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.allocs.len()
+	}
+
+	/// Returns `true` if no allocations have been serialized.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.allocs.is_empty()
+	}
+
+	/// Clear record of allocations, retaining allocated capacity.
+	#[inline]
+	pub fn clear(&mut self) {
+		self.allocs.clear();
+		self.indexes.clear();
+		self.added_refs = None;
+	}
+
+	/// Get a checkpoint which can be rolled back to with
+	/// [`rollback`](SharedAllocs::rollback).
+	///
+	/// After first checkpoint is taken, references added to allocations are
+	/// recorded, so they can be removed on rollback, until record is
+	/// [`clear`](SharedAllocs::clear)ed.
+	#[inline]
+	pub fn checkpoint(&mut self) -> usize {
+		self.added_refs.get_or_insert_with(Vec::new).len()
+	}
+
+	/// Remove allocations written to output at or after position `pos`, and
+	/// references added after `checkpoint` was taken.
+	///
+	/// `pos` must be position in output at time checkpoint was taken.
+	pub fn rollback(&mut self, checkpoint: usize, pos: usize) {
+		if let Some(added_refs) = &mut self.added_refs {
+			if added_refs.len() > checkpoint {
+				for (index, kind) in added_refs.drain(checkpoint..) {
+					let alloc = &mut self.allocs[index];
+					match kind {
+						RefKind::Strong => alloc.strong -= 1,
+						RefKind::Weak => alloc.weak -= 1,
+					}
+				}
+			}
+		}
+
+		// Allocations are in order of position
+		let len = self.allocs.partition_point(|alloc| alloc.pos < pos);
+		self.allocs.truncate(len);
+		self.indexes.retain(|_, index| *index < len);
+	}
+}
+
+/// Checkpoint in serialization, which a [`Serializer`] can be rolled back to
+/// with [`rollback`](crate::Serializer::rollback).
+///
+/// Created by [`Serializer::checkpoint`].
+///
+/// [`Serializer`]: crate::Serializer
+/// [`Serializer::checkpoint`]: crate::Serializer::checkpoint
+#[derive(Copy, Clone, Debug)]
+pub struct Checkpoint {
+	pub(crate) pos: usize,
+	pub(crate) pos_mapping: PosMapping,
+	pub(crate) ptrs: Option<PtrsCheckpoint>,
+	pub(crate) shared_allocs: Option<usize>,
+}
+
+impl Checkpoint {
+	/// Get position in output at time checkpoint was taken.
+	#[inline]
+	pub fn pos(&self) -> usize {
+		self.pos
+	}
+}
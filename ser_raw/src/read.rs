@@ -0,0 +1,971 @@
+//! Lazy readers for output of [`PtrOffsetSerializer`].
+//!
+//! [`PtrOffsetSerializer`] replaces pointers in output with the position of the
+//! value they point to, relative to start of output. The types in this module
+//! follow those offsets on demand, so individual parts of the output can be
+//! read without deserializing the whole tree.
+//!
+//! Start with an [`OffsetRef`] for the root value, then navigate from there:
+//!
+//! * [`OffsetRef::field`] to get a reference to a field of a struct.
+//! * [`OffsetRef::variant`] to get a reference to a field of an enum variant.
+//! * [`as_option`] for an `Option<T>`.
+//! * [`as_box`] for a `Box<T>` (returns an [`OffsetBox`]).
+//! * [`as_vec`] for a `Vec<T>` (returns an [`OffsetVec`]).
+//! * [`as_string`] for a `String` (returns an [`OffsetStr`]).
+//! * [`as_slice`] for a `Box<[T]>` (returns an [`OffsetVec`]).
+//! * [`as_str`] for a `Box<str>` (returns an [`OffsetStr`]).
+//! * [`as_rc`] / [`as_arc`] for an `Rc<T>` / `Arc<T>`, and `as_weak` for a
+//!   `Weak<T>`.
+//! * [`as_vec_deque`] for a `VecDeque<T>` (returns an [`OffsetVec`]).
+//! * `as_map` for a `HashMap<K, V>` or `BTreeMap<K, V>` (returns an
+//!   [`OffsetMap`]).
+//! * `as_set` for a `HashSet<T>` or `BTreeSet<T>` (returns an [`OffsetVec`] of
+//!   the set's values, sorted).
+//! * [`OffsetRef::get`] to get a `&T` for a value which contains no pointers
+//!   (e.g. a `u32`).
+//!
+//! # Example
+//!
+//! ```
+//! use std::ptr;
+//!
+//! use ser_raw::{
+//!     read::OffsetRef,
+//!     storage::AlignedVec,
+//!     util::aligned_max_capacity,
+//!     PtrOffsetSerializer, Serialize, Serializer,
+//! };
+//!
+//! #[derive(Serialize)]
+//! struct Foo {
+//!     name: String,
+//!     nums: Vec<Box<u32>>,
+//! }
+//!
+//! let foo = Foo {
+//!     name: "Bob".to_string(),
+//!     nums: vec![Box::new(1), Box::new(2)],
+//! };
+//!
+//! const MAX_CAPACITY: usize = aligned_max_capacity(16);
+//! type Ser = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+//! let (pos, storage) = Ser::new().serialize(&foo);
+//!
+//! let foo_ref = unsafe { OffsetRef::<Foo>::new(&storage, pos) };
+//! let name = unsafe { foo_ref.field(|foo| ptr::addr_of!((*foo).name)) }.as_string();
+//! assert_eq!(name.as_str(), "Bob");
+//!
+//! let nums = unsafe { foo_ref.field(|foo| ptr::addr_of!((*foo).nums)) }.as_vec();
+//! let second = nums.get(1).unwrap().as_box().get();
+//! assert_eq!(unsafe { *second.get() }, 2);
+//! ```
+//!
+//! # 32-bit offsets
+//!
+//! Output of [`PtrOffset32Serializer`] contains `u32` offsets. Read it with
+//! `OffsetRef<T, u32>` e.g. `OffsetRef::<Foo, u32>::new(&storage, pos)`.
+//! Readers for `u32` offsets are not compatible with output containing `usize`
+//! offsets, and vice versa.
+//!
+//! [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+//! [`PtrOffset32Serializer`]: crate::PtrOffset32Serializer
+//! [`as_option`]: OffsetRef::as_option
+//! [`as_box`]: OffsetRef::as_box
+//! [`as_vec`]: OffsetRef::as_vec
+//! [`as_string`]: OffsetRef::as_string
+//! [`as_slice`]: OffsetRef::as_slice
+//! [`as_str`]: OffsetRef::as_str
+//! [`as_rc`]: OffsetRef::as_rc
+//! [`as_arc`]: OffsetRef::as_arc
+//! [`as_vec_deque`]: OffsetRef::as_vec_deque
+
+use std::{
+	cmp::Ordering,
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+	fmt,
+	marker::PhantomData,
+	mem,
+	ops::Deref,
+	ptr::{self, NonNull},
+	rc::{self, Rc},
+	slice, str,
+	sync::{self, Arc},
+};
+
+pub use crate::serialize_impls::collections::MapEntry;
+use crate::{
+	niche::{read_bytes, OptionRepr},
+	serialize_impls::{
+		collections::VecDequeOffsets,
+		ptrs::{
+			VecOffsets, BOXED_SLICE_LEN_OFFSET, BOXED_SLICE_PTR_OFFSET, OFFSETS_STRING, STRING_PTR_OFFSET,
+		},
+		shared::{RcBox, DANGLING_WEAK_ADDR},
+	},
+	storage::ContiguousStorage,
+	util::is_aligned_to,
+	Validate,
+};
+
+/// Integer type which offsets are stored as in output.
+///
+/// Implemented for `usize` (output of [`PtrOffsetSerializer`]) and `u32`
+/// (output of [`PtrOffset32Serializer`]).
+///
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+/// [`PtrOffset32Serializer`]: crate::PtrOffset32Serializer
+pub trait Offset: Copy {
+	/// Read offset stored at start of a pointer.
+	///
+	/// # Safety
+	///
+	/// `ptr` must be valid for reads of a `usize`, and aligned for `usize`.
+	unsafe fn read_at(ptr: *const u8) -> usize;
+}
+
+impl Offset for usize {
+	#[inline]
+	unsafe fn read_at(ptr: *const u8) -> usize {
+		ptr.cast::<usize>().read()
+	}
+}
+
+impl Offset for u32 {
+	#[inline]
+	unsafe fn read_at(ptr: *const u8) -> usize {
+		ptr.cast::<u32>().read() as usize
+	}
+}
+
+/// Reference to a value of type `T` in output of [`PtrOffsetSerializer`].
+///
+/// Pointers within the value have been replaced by offsets, so it's not safe to
+/// get a `&T` for any value which contains pointers (e.g. `Box`, `Vec`,
+/// `String`). Use the methods of [`OffsetRef`] to follow those pointers
+/// instead.
+///
+/// `O` is the type offsets are stored as. `usize` for [`PtrOffsetSerializer`],
+/// `u32` for [`PtrOffset32Serializer`].
+///
+/// See [module docs](self) for an example.
+///
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+/// [`PtrOffset32Serializer`]: crate::PtrOffset32Serializer
+pub struct OffsetRef<'a, T, O = usize> {
+	buf: &'a [u8],
+	ptr: NonNull<T>,
+	offset_type: PhantomData<O>,
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, T, O> {
+	/// Create [`OffsetRef`] for value at position `pos` in `storage`.
+	///
+	/// # Safety
+	///
+	/// `storage` must contain output of [`PtrOffsetSerializer`], and a `T` must
+	/// have been serialized at position `pos` (e.g. `pos` is the position
+	/// returned by [`Serializer::serialize`]).
+	///
+	/// # Panics
+	///
+	/// Panics if `pos` is out of bounds or not correctly aligned for `T`.
+	///
+	/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+	/// [`Serializer::serialize`]: crate::Serializer::serialize
+	#[inline]
+	pub unsafe fn new<S: ContiguousStorage>(storage: &'a S, pos: usize) -> Self {
+		Self::from_bytes(storage.as_slice(), pos)
+	}
+
+	/// Create [`OffsetRef`] for value at position `pos` in `buf`.
+	///
+	/// # Safety
+	///
+	/// `buf` must contain output of [`PtrOffsetSerializer`], and a `T` must
+	/// have been serialized at position `pos`.
+	///
+	/// # Panics
+	///
+	/// Panics if `pos` is out of bounds or not correctly aligned for `T`.
+	///
+	/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+	#[inline]
+	pub unsafe fn from_bytes(buf: &'a [u8], pos: usize) -> Self {
+		Self {
+			buf,
+			ptr: ptr_at(buf, pos, 1),
+			offset_type: PhantomData,
+		}
+	}
+
+	/// Get position of this value in output.
+	#[inline]
+	pub fn pos(&self) -> usize {
+		self.ptr.as_ptr() as usize - self.buf.as_ptr() as usize
+	}
+
+	/// Get a raw pointer to this value.
+	#[inline]
+	pub fn as_ptr(&self) -> *const T {
+		self.ptr.as_ptr()
+	}
+
+	/// Get a reference to the value.
+	///
+	/// # Safety
+	///
+	/// Any pointers within the value contain offsets, not valid pointers.
+	/// Caller must not dereference them. This is only really safe to use for
+	/// values which contain no pointers (e.g. primitives).
+	#[inline]
+	pub unsafe fn get(&self) -> &'a T {
+		&*self.ptr.as_ptr()
+	}
+
+	/// Get [`OffsetRef`] for a field of this value.
+	///
+	/// `project` receives a pointer to this value, and must return a pointer to
+	/// a field within it e.g. `|foo| ptr::addr_of!((*foo).bar)`.
+	///
+	/// # Safety
+	///
+	/// `project` must not create a reference to the value or any part of it
+	/// which contains pointers (e.g. `&(*foo).boxed`), or dereference any
+	/// pointers within it (e.g. `ptr::addr_of!(*(*foo).boxed)`). They contain
+	/// offsets, not valid pointers, so that would be undefined behavior.
+	/// Use [`ptr::addr_of!`] to project to the field.
+	///
+	/// # Panics
+	///
+	/// Panics if pointer returned by `project` is not within this value.
+	#[inline]
+	pub unsafe fn field<U, P>(&self, project: P) -> OffsetRef<'a, U, O>
+	where P: FnOnce(*const T) -> *const U {
+		let field = project(self.as_ptr());
+		self.child(field)
+	}
+
+	/// Get [`OffsetRef`] for a field of an enum variant.
+	///
+	/// `project` receives a pointer to this value, and should return a pointer
+	/// to a field within it, or `None` if the enum is a different variant.
+	///
+	/// `project` can read the enum's discriminant by matching on the value
+	/// with a pattern which binds no fields e.g. `matches!(*foo, Foo::Bar(..))`.
+	/// That reads only the discriminant, without creating a reference.
+	///
+	/// Rust does not define where a variant's fields are for enums without a
+	/// `#[repr]` attribute, and they can't be located without creating a
+	/// reference. Enums with a primitive representation (`#[repr(u8)]` etc) or
+	/// `#[repr(C, u8)]` etc have a defined layout, so `project` can get a
+	/// pointer to the field by casting to a `#[repr(C)]` struct with the same
+	/// layout as the variant e.g.:
+	///
+	/// ```ignore
+	/// // Layout of `Foo::Bar` variant of a `#[repr(u8)]` enum
+	/// #[repr(C)]
+	/// struct FooBar {
+	///     tag: u8,
+	///     bar: Box<u32>,
+	/// }
+	///
+	/// |foo| matches!(*foo, Foo::Bar(..)).then(|| ptr::addr_of!((*foo.cast::<FooBar>()).bar))
+	/// ```
+	///
+	/// # Safety
+	///
+	/// Same requirements as [`field`](OffsetRef::field). Pattern `project` uses
+	/// to read the discriminant must not bind any fields (e.g. `Foo::Bar(bar)`
+	/// or `Foo::Bar(ref bar)`), as that creates a reference to the field.
+	///
+	/// # Panics
+	///
+	/// Panics if pointer returned by `project` is not within this value.
+	#[inline]
+	pub unsafe fn variant<U, P>(&self, project: P) -> Option<OffsetRef<'a, U, O>>
+	where P: FnOnce(*const T) -> Option<*const U> {
+		project(self.as_ptr()).map(|field| self.child(field))
+	}
+
+	/// Get [`OffsetRef`] for a field within this value.
+	#[inline]
+	fn child<U>(&self, field: *const U) -> OffsetRef<'a, U, O> {
+		let start = self.ptr.as_ptr() as usize;
+		let field_start = field as usize;
+		assert!(
+			field_start >= start && field_start + mem::size_of::<U>() <= start + mem::size_of::<T>(),
+			"Field is not within value"
+		);
+		OffsetRef {
+			buf: self.buf,
+			// Field is within this value, so pointer is not null
+			ptr: unsafe { NonNull::new_unchecked(field as *mut U) },
+			offset_type: PhantomData,
+		}
+	}
+
+	/// Read offset stored in a pointer field at `offset` bytes from start of this
+	/// value.
+	#[inline]
+	fn read_offset(&self, offset: usize) -> usize {
+		debug_assert!(offset + mem::size_of::<usize>() <= mem::size_of::<T>());
+		unsafe { O::read_at((self.ptr.as_ptr() as *const u8).add(offset)) }
+	}
+
+	/// Get [`OffsetRef`] for value in shared allocation which this value
+	/// (`Rc<U>`, `Arc<U>` or `Weak<U>`) points to.
+	///
+	/// Returns `None` if pointer is a dangling `Weak`, or allocation's value was
+	/// dropped before serialization.
+	#[inline]
+	fn read_shared<U>(&self) -> Option<OffsetRef<'a, U, O>> {
+		// Dangling `Weak`s are not overwritten
+		if self.read_usize(0) == DANGLING_WEAK_ADDR {
+			return None;
+		}
+
+		let alloc = ptr_at::<RcBox<U>>(self.buf, self.read_offset(0), 1).as_ptr();
+		if unsafe { (*alloc).strong } == 0 {
+			return None;
+		}
+		let value = unsafe { ptr::addr_of_mut!((*alloc).value) };
+		Some(OffsetRef {
+			buf: self.buf,
+			ptr: unsafe { NonNull::new_unchecked(value) },
+			offset_type: PhantomData,
+		})
+	}
+
+	/// Read `usize` field (e.g. length of a `Vec`) at `offset` bytes from start
+	/// of this value.
+	#[inline]
+	fn read_usize(&self, offset: usize) -> usize {
+		debug_assert!(offset + mem::size_of::<usize>() <= mem::size_of::<T>());
+		unsafe {
+			(self.ptr.as_ptr() as *const u8)
+				.add(offset)
+				.cast::<usize>()
+				.read()
+		}
+	}
+
+	/// Get [`OffsetVec`] for entries of a map or set.
+	///
+	/// First field of map or set is a pointer to a `Box<[U]>` containing its
+	/// entries.
+	#[inline]
+	fn read_entries<U>(&self) -> OffsetVec<'a, U, O> {
+		let header = OffsetRef::<Box<[U]>, O> {
+			buf: self.buf,
+			ptr: ptr_at(self.buf, self.read_offset(0), 1),
+			offset_type: PhantomData,
+		};
+		header.as_slice()
+	}
+}
+
+impl<'a, T: Validate, O: Offset> OffsetRef<'a, Option<T>, O> {
+	/// Get [`OffsetRef`] for contents of an `Option`, or `None` if it's `None`.
+	///
+	/// Layout of `Option<T>` is determined from `T`'s niche. See [`niche`]
+	/// module.
+	///
+	/// # Panics
+	///
+	/// Panics if `Option` is neither `None` nor `Some`.
+	///
+	/// [`niche`]: crate::niche
+	#[inline]
+	pub fn as_option(&self) -> Option<OffsetRef<'a, T, O>> {
+		// Read tag bytes from pointer. Pointer may contain offsets, so creating a
+		// `&Option<T>` to match on would be undefined behavior.
+		// If `None` is a null pointer niche, an offset cannot be mistaken for it, as
+		// `PtrOffset` serializers never write an offset of 0.
+		let repr = OptionReprOf::<T>::REPR;
+		let ptr = self.as_ptr() as *const u8;
+		let tag = unsafe { read_bytes(ptr.add(repr.tag_offset), repr.tag_size) };
+		if tag == repr.none {
+			return None;
+		}
+		if let Some(some) = repr.some {
+			assert!(tag == some, "Invalid `Option` discriminant");
+		}
+
+		let value = unsafe { ptr.add(repr.payload_offset) } as *mut T;
+		Some(OffsetRef {
+			buf: self.buf,
+			ptr: unsafe { NonNull::new_unchecked(value) },
+			offset_type: PhantomData,
+		})
+	}
+}
+
+struct OptionReprOf<T> {
+	_marker: PhantomData<T>,
+}
+
+impl<T: Validate> OptionReprOf<T> {
+	// Safe because evaluated in const context
+	const REPR: OptionRepr = unsafe { OptionRepr::new::<T>(T::NICHE) };
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, Box<T>, O> {
+	/// Get [`OffsetBox`] for a `Box<T>`.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned for `T`.
+	#[inline]
+	pub fn as_box(&self) -> OffsetBox<'a, T, O> {
+		let ptr = if mem::size_of::<T>() == 0 {
+			// ZSTs are not written to output, so pointer was not overwritten
+			NonNull::dangling()
+		} else {
+			ptr_at(self.buf, self.read_offset(0), 1)
+		};
+		OffsetBox {
+			value: OffsetRef {
+				buf: self.buf,
+				ptr,
+				offset_type: PhantomData,
+			},
+		}
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, Vec<T>, O> {
+	/// Get [`OffsetVec`] for a `Vec<T>`.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned for `T`.
+	#[inline]
+	pub fn as_vec(&self) -> OffsetVec<'a, T, O> {
+		let len = self.read_usize(VecOffsets::<T>::OFFSETS_VEC.len());
+		let ptr = if len == 0 || mem::size_of::<T>() == 0 {
+			// Nothing written to output, so pointer was not overwritten
+			NonNull::dangling()
+		} else {
+			let pos = self.read_offset(VecOffsets::<T>::PTR_OFFSET);
+			ptr_at(self.buf, pos, len)
+		};
+		OffsetVec {
+			buf: self.buf,
+			ptr,
+			len,
+			offset_type: PhantomData,
+		}
+	}
+}
+
+impl<'a, O: Offset> OffsetRef<'a, String, O> {
+	/// Get [`OffsetStr`] for a `String`.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds, or string is not valid UTF-8.
+	#[inline]
+	pub fn as_string(&self) -> OffsetStr<'a> {
+		let len = self.read_usize(OFFSETS_STRING.len());
+		let bytes: &'a [u8] = if len == 0 {
+			&[]
+		} else {
+			let pos = self.read_offset(STRING_PTR_OFFSET);
+			let ptr = ptr_at::<u8>(self.buf, pos, len);
+			unsafe { slice::from_raw_parts(ptr.as_ptr(), len) }
+		};
+		OffsetStr {
+			str: str::from_utf8(bytes).expect("String is not valid UTF-8"),
+		}
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, Box<[T]>, O> {
+	/// Get [`OffsetVec`] for a `Box<[T]>`.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned for `T`.
+	#[inline]
+	pub fn as_slice(&self) -> OffsetVec<'a, T, O> {
+		let len = self.read_usize(BOXED_SLICE_LEN_OFFSET);
+		let ptr = if len == 0 || mem::size_of::<T>() == 0 {
+			// Nothing written to output, so pointer was not overwritten
+			NonNull::dangling()
+		} else {
+			let pos = self.read_offset(BOXED_SLICE_PTR_OFFSET);
+			ptr_at(self.buf, pos, len)
+		};
+		OffsetVec {
+			buf: self.buf,
+			ptr,
+			len,
+			offset_type: PhantomData,
+		}
+	}
+}
+
+impl<'a, O: Offset> OffsetRef<'a, Box<str>, O> {
+	/// Get [`OffsetStr`] for a `Box<str>`.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds, or string is not valid UTF-8.
+	#[inline]
+	pub fn as_str(&self) -> OffsetStr<'a> {
+		let len = self.read_usize(BOXED_SLICE_LEN_OFFSET);
+		let bytes: &'a [u8] = if len == 0 {
+			&[]
+		} else {
+			let pos = self.read_offset(BOXED_SLICE_PTR_OFFSET);
+			let ptr = ptr_at::<u8>(self.buf, pos, len);
+			unsafe { slice::from_raw_parts(ptr.as_ptr(), len) }
+		};
+		OffsetStr {
+			str: str::from_utf8(bytes).expect("String is not valid UTF-8"),
+		}
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, Rc<T>, O> {
+	/// Get [`OffsetRef`] for value an `Rc<T>` points to.
+	///
+	/// All `Rc`s which pointed to the same value in input point to the same value
+	/// in output.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_rc(&self) -> OffsetRef<'a, T, O> {
+		self.read_shared().expect("`Rc` points to a dropped value")
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, rc::Weak<T>, O> {
+	/// Get [`OffsetRef`] for value an `rc::Weak<T>` points to, or `None` if
+	/// `Weak` is dangling, or value had been dropped before serialization.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_weak(&self) -> Option<OffsetRef<'a, T, O>> {
+		self.read_shared()
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, Arc<T>, O> {
+	/// Get [`OffsetRef`] for value an `Arc<T>` points to.
+	///
+	/// All `Arc`s which pointed to the same value in input point to the same
+	/// value in output.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_arc(&self) -> OffsetRef<'a, T, O> {
+		self.read_shared().expect("`Arc` points to a dropped value")
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, sync::Weak<T>, O> {
+	/// Get [`OffsetRef`] for value a `sync::Weak<T>` points to, or `None` if
+	/// `Weak` is dangling, or value had been dropped before serialization.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_weak(&self) -> Option<OffsetRef<'a, T, O>> {
+		self.read_shared()
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, VecDeque<T>, O> {
+	/// Get [`OffsetVec`] for a `VecDeque<T>`, with front element first.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned for `T`.
+	#[inline]
+	pub fn as_vec_deque(&self) -> OffsetVec<'a, T, O> {
+		let len = self.read_usize(VecDequeOffsets::<T>::get().len);
+		let ptr = if len == 0 || mem::size_of::<T>() == 0 {
+			// Nothing written to output, so pointer was not overwritten
+			NonNull::dangling()
+		} else {
+			let pos = self.read_offset(VecDequeOffsets::<T>::PTR_OFFSET);
+			ptr_at(self.buf, pos, len)
+		};
+		OffsetVec {
+			buf: self.buf,
+			ptr,
+			len,
+			offset_type: PhantomData,
+		}
+	}
+}
+
+impl<'a, K, V, H, O: Offset> OffsetRef<'a, HashMap<K, V, H>, O> {
+	/// Get [`OffsetMap`] for a `HashMap<K, V>`.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_map(&self) -> OffsetMap<'a, K, V, O> {
+		OffsetMap {
+			entries: self.read_entries(),
+		}
+	}
+}
+
+impl<'a, K, V, O: Offset> OffsetRef<'a, BTreeMap<K, V>, O> {
+	/// Get [`OffsetMap`] for a `BTreeMap<K, V>`.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_map(&self) -> OffsetMap<'a, K, V, O> {
+		OffsetMap {
+			entries: self.read_entries(),
+		}
+	}
+}
+
+impl<'a, T, H, O: Offset> OffsetRef<'a, HashSet<T, H>, O> {
+	/// Get [`OffsetVec`] for values of a `HashSet<T>`, sorted.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_set(&self) -> OffsetVec<'a, T, O> {
+		self.read_entries()
+	}
+}
+
+impl<'a, T, O: Offset> OffsetRef<'a, BTreeSet<T>, O> {
+	/// Get [`OffsetVec`] for values of a `BTreeSet<T>`, sorted.
+	///
+	/// # Panics
+	///
+	/// Panics if offset is out of bounds or not correctly aligned.
+	#[inline]
+	pub fn as_set(&self) -> OffsetVec<'a, T, O> {
+		self.read_entries()
+	}
+}
+
+impl<'a, T, O> Clone for OffsetRef<'a, T, O> {
+	#[inline]
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<'a, T, O> Copy for OffsetRef<'a, T, O> {}
+
+// `OffsetRef` is equivalent to a `&T`
+unsafe impl<'a, T: Sync, O> Send for OffsetRef<'a, T, O> {}
+unsafe impl<'a, T: Sync, O> Sync for OffsetRef<'a, T, O> {}
+
+impl<'a, T, O: Offset> fmt::Debug for OffsetRef<'a, T, O> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("OffsetRef")
+			.field("pos", &self.pos())
+			.finish()
+	}
+}
+
+/// View of a `Box<T>` in output of [`PtrOffsetSerializer`].
+///
+/// Created by [`OffsetRef::as_box`].
+///
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+#[derive(Clone, Copy, Debug)]
+pub struct OffsetBox<'a, T, O: Offset = usize> {
+	value: OffsetRef<'a, T, O>,
+}
+
+impl<'a, T, O: Offset> OffsetBox<'a, T, O> {
+	/// Get [`OffsetRef`] for the boxed value.
+	#[inline]
+	pub fn get(&self) -> OffsetRef<'a, T, O> {
+		self.value
+	}
+}
+
+/// View of a `Vec<T>` in output of [`PtrOffsetSerializer`].
+///
+/// Created by [`OffsetRef::as_vec`].
+///
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+pub struct OffsetVec<'a, T, O = usize> {
+	buf: &'a [u8],
+	ptr: NonNull<T>,
+	len: usize,
+	offset_type: PhantomData<O>,
+}
+
+impl<'a, T, O: Offset> OffsetVec<'a, T, O> {
+	/// Get number of elements in the `Vec`.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Returns `true` if the `Vec` contains no elements.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Get [`OffsetRef`] for element at `index`, or `None` if out of bounds.
+	#[inline]
+	pub fn get(&self, index: usize) -> Option<OffsetRef<'a, T, O>> {
+		if index < self.len {
+			Some(self.get_unchecked(index))
+		} else {
+			None
+		}
+	}
+
+	/// Get iterator over [`OffsetRef`]s for the `Vec`'s elements.
+	#[inline]
+	pub fn iter(&self) -> impl ExactSizeIterator<Item = OffsetRef<'a, T, O>> + '_ {
+		(0..self.len).map(|index| self.get_unchecked(index))
+	}
+
+	/// Binary search a sorted `Vec` with a comparator function.
+	///
+	/// Same as [`slice::binary_search_by`], except comparator receives an
+	/// [`OffsetRef`] for each element.
+	#[inline]
+	pub fn binary_search_by<F>(&self, mut f: F) -> Result<usize, usize>
+	where F: FnMut(OffsetRef<'a, T, O>) -> Ordering {
+		let (mut low, mut high) = (0, self.len);
+		while low < high {
+			let mid = low + (high - low) / 2;
+			match f(self.get_unchecked(mid)) {
+				Ordering::Less => low = mid + 1,
+				Ordering::Greater => high = mid,
+				Ordering::Equal => return Ok(mid),
+			}
+		}
+		Err(low)
+	}
+
+	/// Get contents of the `Vec` as a slice.
+	///
+	/// # Safety
+	///
+	/// Any pointers within the elements contain offsets, not valid pointers.
+	/// Caller must not dereference them. This is only really safe to use for
+	/// values which contain no pointers (e.g. `Vec<u32>`).
+	#[inline]
+	pub unsafe fn as_slice(&self) -> &'a [T] {
+		slice::from_raw_parts(self.ptr.as_ptr(), self.len)
+	}
+
+	#[inline]
+	fn get_unchecked(&self, index: usize) -> OffsetRef<'a, T, O> {
+		debug_assert!(index < self.len);
+		OffsetRef {
+			buf: self.buf,
+			ptr: unsafe { NonNull::new_unchecked(self.ptr.as_ptr().add(index)) },
+			offset_type: PhantomData,
+		}
+	}
+}
+
+impl<'a, T, O> Clone for OffsetVec<'a, T, O> {
+	#[inline]
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<'a, T, O> Copy for OffsetVec<'a, T, O> {}
+
+// `OffsetVec` is equivalent to a `&[T]`
+unsafe impl<'a, T: Sync, O> Send for OffsetVec<'a, T, O> {}
+unsafe impl<'a, T: Sync, O> Sync for OffsetVec<'a, T, O> {}
+
+impl<'a, T, O> fmt::Debug for OffsetVec<'a, T, O> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("OffsetVec").field("len", &self.len).finish()
+	}
+}
+
+/// View of a `HashMap<K, V>` or `BTreeMap<K, V>` in output of
+/// [`PtrOffsetSerializer`].
+///
+/// Maps are serialized as a slice of [`MapEntry`]s, sorted by key, so keys can
+/// be looked up with a binary search.
+///
+/// Created by `OffsetRef::as_map`.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use ser_raw::{
+///     read::OffsetRef,
+///     storage::AlignedVec,
+///     util::aligned_max_capacity,
+///     PtrOffsetSerializer, Serializer,
+/// };
+///
+/// let mut map = HashMap::new();
+/// map.insert("one".to_string(), 1u32);
+/// map.insert("two".to_string(), 2u32);
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Ser = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+/// let (pos, storage) = Ser::new().serialize(&map);
+///
+/// let map = unsafe { OffsetRef::<HashMap<String, u32>>::new(&storage, pos) }.as_map();
+/// assert_eq!(map.len(), 2);
+/// let value = map.get_by(|key| key.as_string().as_str().cmp("two")).unwrap();
+/// assert_eq!(unsafe { *value.get() }, 2);
+/// assert!(map.get_by(|key| key.as_string().as_str().cmp("three")).is_none());
+/// ```
+///
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+pub struct OffsetMap<'a, K, V, O = usize> {
+	entries: OffsetVec<'a, MapEntry<K, V>, O>,
+}
+
+impl<'a, K, V, O: Offset> OffsetMap<'a, K, V, O> {
+	/// Get number of entries in the map.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Returns `true` if the map contains no entries.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Get [`OffsetVec`] for the map's entries, sorted by key.
+	#[inline]
+	pub fn entries(&self) -> OffsetVec<'a, MapEntry<K, V>, O> {
+		self.entries
+	}
+
+	/// Get iterator over [`OffsetRef`]s for the map's keys and values, sorted by
+	/// key.
+	#[inline]
+	pub fn iter(
+		&self,
+	) -> impl ExactSizeIterator<Item = (OffsetRef<'a, K, O>, OffsetRef<'a, V, O>)> + '_ {
+		self.entries.iter().map(entry_parts)
+	}
+
+	/// Look up a value by key.
+	///
+	/// `compare` receives an [`OffsetRef`] for a key, and must return how that
+	/// key compares to the key being looked up, consistent with the key type's
+	/// `Ord` implementation (see [`slice::binary_search_by`]).
+	///
+	/// Returns [`OffsetRef`] for the value, or `None` if key is not found.
+	#[inline]
+	pub fn get_by<F>(&self, mut compare: F) -> Option<OffsetRef<'a, V, O>>
+	where F: FnMut(OffsetRef<'a, K, O>) -> Ordering {
+		let index = self
+			.entries
+			.binary_search_by(|entry| compare(entry_parts(entry).0))
+			.ok()?;
+		Some(entry_parts(self.entries.get_unchecked(index)).1)
+	}
+}
+
+impl<'a, K, V, O> Clone for OffsetMap<'a, K, V, O> {
+	#[inline]
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<'a, K, V, O> Copy for OffsetMap<'a, K, V, O> {}
+
+impl<'a, K, V, O> fmt::Debug for OffsetMap<'a, K, V, O> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("OffsetMap")
+			.field("len", &self.entries.len)
+			.finish()
+	}
+}
+
+/// Split [`OffsetRef`] for a [`MapEntry`] into [`OffsetRef`]s for its key and
+/// value.
+#[inline]
+fn entry_parts<K, V, O: Offset>(
+	entry: OffsetRef<'_, MapEntry<K, V>, O>,
+) -> (OffsetRef<'_, K, O>, OffsetRef<'_, V, O>) {
+	// Projecting to fields does not dereference any pointers
+	unsafe {
+		(
+			entry.field(|entry| ptr::addr_of!((*entry).key)),
+			entry.field(|entry| ptr::addr_of!((*entry).value)),
+		)
+	}
+}
+
+/// View of a `String` in output of [`PtrOffsetSerializer`].
+///
+/// Created by [`OffsetRef::as_string`]. Dereferences to `str`.
+///
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OffsetStr<'a> {
+	str: &'a str,
+}
+
+impl<'a> OffsetStr<'a> {
+	/// Get string as a `&str`.
+	#[inline]
+	pub fn as_str(&self) -> &'a str {
+		self.str
+	}
+}
+
+impl<'a> Deref for OffsetStr<'a> {
+	type Target = str;
+
+	#[inline]
+	fn deref(&self) -> &str {
+		self.str
+	}
+}
+
+/// Get pointer to a slice of `len` x `T` at position `pos` in `buf`.
+///
+/// # Panics
+///
+/// Panics if slice would be out of bounds of `buf`, or `pos` is not correctly
+/// aligned for `T`.
+#[inline]
+fn ptr_at<T>(buf: &[u8], pos: usize, len: usize) -> NonNull<T> {
+	let size = mem::size_of::<T>()
+		.checked_mul(len)
+		.expect("Offset out of bounds");
+	assert!(
+		pos <= buf.len() && size <= buf.len() - pos,
+		"Offset out of bounds"
+	);
+	let ptr = unsafe { buf.as_ptr().add(pos) };
+	assert!(
+		is_aligned_to(ptr as usize, mem::align_of::<T>()),
+		"Offset is not correctly aligned"
+	);
+	unsafe { NonNull::new_unchecked(ptr as *mut T) }
+}
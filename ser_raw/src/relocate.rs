@@ -0,0 +1,300 @@
+//! Relocation of [`CompleteSerializer`] output.
+//!
+//! [`CompleteSerializer`] writes pointers into its output which point to
+//! memory addresses within the output buffer. So by default the output is only
+//! valid at the memory address it was at when serialization completed.
+//!
+//! If serializer also records where those pointers are (a "relocation table"),
+//! the output can be moved (e.g. copied to another buffer, written to disk and
+//! read back, or sent to another process) and the pointers fixed afterwards to
+//! point to the buffer's new location, with [`rebase`] or
+//! [`Relocations::rebase`].
+//!
+//! Relocation table can be obtained either:
+//!
+//! * As a separate [`Relocations`] object, with
+//!   [`CompleteSerializer::serialize_with_relocations`].
+//! * Appended to end of the output, with
+//!   [`CompleteSerializer::serialize_with_relocation_table`].
+//!
+//! Rebasing to `new_base = 0` turns all pointers into positions relative to
+//! start of the buffer, so output doesn't contain any memory addresses. This
+//! can be useful for e.g. ensuring output written to disk is deterministic.
+//!
+//! # Example
+//!
+//! ```
+//! use ser_raw::{
+//!     relocate::rebase,
+//!     storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+//!     util::aligned_max_capacity,
+//!     CompleteSerializer, Serialize, Serializer,
+//! };
+//!
+//! #[derive(Serialize)]
+//! struct Foo {
+//!     names: Vec<String>,
+//! }
+//!
+//! let foo = Foo {
+//!     names: vec!["Alfred".to_string(), "Gertrude".to_string()],
+//! };
+//!
+//! const MAX_CAPACITY: usize = aligned_max_capacity(16);
+//! type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+//! let ser = CompleteSerializer::<16, 16, 8, MAX_CAPACITY, Store>::new();
+//! let (pos, storage) = ser.serialize_with_relocation_table(&foo);
+//!
+//! // Copy output to a new buffer
+//! let mut copy = Store::with_capacity(storage.pos());
+//! copy.push_bytes(storage.as_slice());
+//! drop(storage);
+//!
+//! // Fix pointers to point into the new buffer
+//! let new_base = copy.as_ptr() as usize;
+//! rebase(copy.as_mut_slice(), new_base).unwrap();
+//!
+//! // This is safe because serialization and deserialization are performed
+//! // on same system with same binary, and pointers have been rebased
+//! let foo_out: &Foo = unsafe { copy.read(pos) };
+//! assert_eq!(foo_out.names, foo.names);
+//! ```
+//!
+//! [`CompleteSerializer`]: crate::CompleteSerializer
+//! [`CompleteSerializer::serialize_with_relocations`]: crate::CompleteSerializer::serialize_with_relocations
+//! [`CompleteSerializer::serialize_with_relocation_table`]: crate::CompleteSerializer::serialize_with_relocation_table
+
+use std::{error::Error, fmt, mem, ptr};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+/// Size of trailer at end of a relocation table appended to output.
+/// Trailer comprises 3 x `usize`: base address, length of data, and number of
+/// pointers.
+pub(crate) const TRAILER_SIZE: usize = PTR_SIZE * 3;
+
+/// Relocation table for [`CompleteSerializer`] output.
+///
+/// Records positions of all pointers in the output, and the memory address
+/// which they currently point relative to.
+///
+/// [`CompleteSerializer`]: crate::CompleteSerializer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocations {
+	/// Address which pointers are currently relative to
+	base_addr: usize,
+	/// Positions of pointers in output (relative to start of output)
+	ptr_positions: Vec<usize>,
+}
+
+impl Relocations {
+	/// Create new [`Relocations`].
+	///
+	/// `base_addr` is the address which pointers in the output are currently
+	/// relative to (usually the memory address of the output buffer).
+	/// `ptr_positions` are the positions of those pointers in the output.
+	#[inline]
+	pub fn new(base_addr: usize, ptr_positions: Vec<usize>) -> Self {
+		Self {
+			base_addr,
+			ptr_positions,
+		}
+	}
+
+	/// Get address which pointers in output are currently relative to.
+	#[inline]
+	pub fn base_addr(&self) -> usize {
+		self.base_addr
+	}
+
+	/// Get positions of pointers in output.
+	#[inline]
+	pub fn ptr_positions(&self) -> &[usize] {
+		&self.ptr_positions
+	}
+
+	/// Adjust all pointers in `bytes` so they're relative to `new_base`, instead
+	/// of the current base address.
+	///
+	/// Usually `new_base` will be the memory address of `bytes`, so that the
+	/// pointers point into `bytes`.
+	///
+	/// If any pointer position is out of bounds of `bytes`, returns an error and
+	/// `bytes` is not altered.
+	pub fn rebase(&mut self, bytes: &mut [u8], new_base: usize) -> Result<(), RelocateError> {
+		rebase_ptrs(bytes, &self.ptr_positions, self.base_addr, new_base)?;
+		self.base_addr = new_base;
+		Ok(())
+	}
+}
+
+/// Adjust all pointers in `bytes` so they're relative to `new_base`, using the
+/// relocation table appended to end of `bytes` by
+/// [`CompleteSerializer::serialize_with_relocation_table`].
+///
+/// Usually `new_base` will be the memory address of `bytes`, so that the
+/// pointers point into `bytes`.
+///
+/// Relocation table is updated with the new base address, so `bytes` can be
+/// rebased again later. Returns length of the data preceding the relocation
+/// table.
+///
+/// If the relocation table is malformed, returns an error and `bytes` is not
+/// altered.
+///
+/// See [module docs](self) for an example.
+///
+/// [`CompleteSerializer::serialize_with_relocation_table`]: crate::CompleteSerializer::serialize_with_relocation_table
+pub fn rebase(bytes: &mut [u8], new_base: usize) -> Result<usize, RelocateError> {
+	let (relocations, data_len) = read_table(bytes)?;
+
+	rebase_ptrs(
+		&mut bytes[..data_len],
+		&relocations.ptr_positions,
+		relocations.base_addr,
+		new_base,
+	)?;
+
+	// Record new base address in trailer.
+	// Safe because `read_table` checked trailer is within bounds.
+	let trailer_pos = bytes.len() - TRAILER_SIZE;
+	unsafe { write_usize(bytes, trailer_pos, new_base) };
+
+	Ok(data_len)
+}
+
+/// Read relocation table appended to end of `bytes` by
+/// [`CompleteSerializer::serialize_with_relocation_table`].
+///
+/// Returns [`Relocations`] and length of the data preceding the relocation
+/// table.
+///
+/// [`CompleteSerializer::serialize_with_relocation_table`]: crate::CompleteSerializer::serialize_with_relocation_table
+pub fn read_table(bytes: &[u8]) -> Result<(Relocations, usize), RelocateError> {
+	if bytes.len() < TRAILER_SIZE {
+		return Err(RelocateError::InvalidTable);
+	}
+	let trailer_pos = bytes.len() - TRAILER_SIZE;
+	let (base_addr, data_len, ptr_count) = unsafe {
+		(
+			read_usize(bytes, trailer_pos),
+			read_usize(bytes, trailer_pos + PTR_SIZE),
+			read_usize(bytes, trailer_pos + PTR_SIZE * 2),
+		)
+	};
+
+	// Table follows data (and possibly padding), and precedes trailer
+	let table_size = ptr_count
+		.checked_mul(PTR_SIZE)
+		.ok_or(RelocateError::InvalidTable)?;
+	if table_size > trailer_pos || data_len > trailer_pos - table_size {
+		return Err(RelocateError::InvalidTable);
+	}
+	let table_pos = trailer_pos - table_size;
+
+	let ptr_positions = (0..ptr_count)
+		.map(|index| unsafe { read_usize(bytes, table_pos + index * PTR_SIZE) })
+		.collect();
+
+	Ok((Relocations::new(base_addr, ptr_positions), data_len))
+}
+
+/// Get relocation table to append to output of length `data_len`.
+///
+/// Base address is left as 0, to be written once it's known.
+///
+/// Storage pads after each push to keep position aligned to `value_alignment`.
+/// So table is padded at the start to make its size a multiple of
+/// `value_alignment`, which ensures trailer is at the very end of the output.
+pub(crate) fn get_table(
+	ptr_positions: &[usize],
+	data_len: usize,
+	value_alignment: usize,
+) -> Vec<usize> {
+	let len = ptr_positions.len() + TRAILER_SIZE / PTR_SIZE;
+	let align_len = (value_alignment / PTR_SIZE).max(1);
+	let padding_len = (align_len - len % align_len) % align_len;
+
+	let mut table = Vec::with_capacity(padding_len + len);
+	table.resize(padding_len, 0);
+	table.extend_from_slice(ptr_positions);
+	table.push(0);
+	table.push(data_len);
+	table.push(ptr_positions.len());
+	table
+}
+
+/// Shift pointers at `ptr_positions` in `bytes` from `old_base` to `new_base`.
+///
+/// Bounds of all positions are checked before any pointers are altered.
+fn rebase_ptrs(
+	bytes: &mut [u8],
+	ptr_positions: &[usize],
+	old_base: usize,
+	new_base: usize,
+) -> Result<(), RelocateError> {
+	if let Some(&pos) = ptr_positions
+		.iter()
+		.find(|&&pos| pos > bytes.len() || bytes.len() - pos < PTR_SIZE)
+	{
+		return Err(RelocateError::PtrOutOfBounds { pos });
+	}
+
+	// Same logic as `PtrGroup::correct_ptrs`.
+	// Using `wrapping_*` for correct maths whether new base is less than or
+	// greater than old base.
+	let shift_by = new_base.wrapping_sub(old_base);
+	if shift_by != 0 {
+		for &pos in ptr_positions {
+			// Safe because bounds checked above
+			unsafe {
+				let ptr = read_usize(bytes, pos);
+				write_usize(bytes, pos, ptr.wrapping_add(shift_by));
+			}
+		}
+	}
+
+	Ok(())
+}
+
+/// Read a `usize` from `bytes` at `pos`. `bytes` does not need to be aligned.
+///
+/// # Safety
+///
+/// `pos + PTR_SIZE` must be within bounds of `bytes`.
+#[inline]
+unsafe fn read_usize(bytes: &[u8], pos: usize) -> usize {
+	debug_assert!(pos + PTR_SIZE <= bytes.len());
+	ptr::read_unaligned(bytes.as_ptr().add(pos) as *const usize)
+}
+
+/// Write a `usize` to `bytes` at `pos`. `bytes` does not need to be aligned.
+///
+/// # Safety
+///
+/// `pos + PTR_SIZE` must be within bounds of `bytes`.
+#[inline]
+unsafe fn write_usize(bytes: &mut [u8], pos: usize, value: usize) {
+	debug_assert!(pos + PTR_SIZE <= bytes.len());
+	ptr::write_unaligned(bytes.as_mut_ptr().add(pos) as *mut usize, value);
+}
+
+/// Error returned when relocation fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RelocateError {
+	/// Relocation table appended to output is malformed
+	InvalidTable,
+	/// Pointer position is outside the buffer
+	PtrOutOfBounds { pos: usize },
+}
+
+impl fmt::Display for RelocateError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::InvalidTable => write!(f, "Relocation table is invalid"),
+			Self::PtrOutOfBounds { pos } => write!(f, "Pointer position {pos} is out of bounds"),
+		}
+	}
+}
+
+impl Error for RelocateError {}
@@ -1,4 +1,4 @@
-use crate::Serializer;
+use crate::{fingerprint, header::SerializerKind, Serializer};
 
 /// Trait for types which can be serialized.
 ///
@@ -11,8 +11,60 @@ use crate::Serializer;
 ///
 /// #[derive(Serialize)]
 /// struct Foo {
-/// 	smalls: Vec<u8>,
-/// 	bigs: Vec<u32>,
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
+/// }
+/// ```
+///
+/// ## Skipping fields
+///
+/// Fields which must not be followed during serialization (caches, handles
+/// etc) can be skipped with `#[ser_skip]`, `#[ser_default]` or
+/// `#[ser_replace = "expr"]`. Data owned by a skipped field is not serialized,
+/// and skipped fields are not included in portable output.
+///
+/// * `#[ser_skip]` leaves the field in output as a bitwise copy of the input,
+///   and the derived [`Deserialize`] keeps that copy. Field's type must be
+///   `Copy`, so it owns no data outside the value e.g. a file descriptor.
+/// * `#[ser_default]` replaces the field with `Default::default()`.
+/// * `#[ser_replace = "expr"]` replaces the field with value of `expr`.
+///
+/// Replacement values are written to output with [`overwrite`], so output of
+/// [`CompleteSerializer`] and [`PtrOffsetSerializer`] contains the replacement
+/// in place of the original field, and the derived [`Deserialize`] sets the
+/// field to the replacement. [`PureCopySerializer`] cannot write at arbitrary
+/// positions in output, so its output contains the original field's bytes
+/// (including any pointers), which are only replaced by the derived
+/// [`Deserialize`]. Replacement values should not own any data outside the
+/// value themselves e.g. `None` or `Vec::new()`. The replacement is not dropped
+/// after it's written to output. If it does own data, that data is leaked, and
+/// output points to it, outside of output.
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use ser_raw::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     name: String,
+///     #[ser_skip]
+///     fd: i32,
+///     #[ser_default]
+///     cache: Vec<String>,
+///     #[ser_replace = "None"]
+///     handle: Option<Arc<Mutex<String>>>,
+/// }
+/// ```
+///
+/// `#[ser_skip]` on a field which owns data is an error:
+///
+/// ```compile_fail
+/// use ser_raw::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     #[ser_skip]
+///     names: Vec<String>,
 /// }
 /// ```
 ///
@@ -37,18 +89,18 @@ use crate::Serializer;
 ///
 /// ```
 /// struct Foo {
-/// 	// Contained within the type itself.
-/// 	// No need for `serialize_data()` to do anything for these fields.
-/// 	num: u32,
-/// 	yes_or_no: bool,
-/// 	ip: [u8; 4],
-/// 	opt: Option<i8>,
-///
-/// 	// Contain pointers to data outside of this type's memory allocation.
-/// 	// `serialize_data()` needs to handle serializing the external data for these fields.
-/// 	name: String,
-/// 	nums: Vec<u64>,
-/// 	parent: Option<Box<Foo>>,
+///     // Contained within the type itself.
+///     // No need for `serialize_data()` to do anything for these fields.
+///     num: u32,
+///     yes_or_no: bool,
+///     ip: [u8; 4],
+///     opt: Option<i8>,
+///
+///     // Contain pointers to data outside of this type's memory allocation.
+///     // `serialize_data()` needs to handle serializing the external data for these fields.
+///     name: String,
+///     nums: Vec<u64>,
+///     parent: Option<Box<Foo>>,
 /// }
 /// ```
 ///
@@ -75,17 +127,17 @@ use crate::Serializer;
 /// use ser_raw::{Serialize, Serializer};
 ///
 /// struct Foo {
-/// 	smalls: Vec<u8>,
-/// 	bigs: Vec<u32>,
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
 /// }
 ///
 /// impl<S> Serialize<S> for Foo
 /// where S: Serializer
 /// {
-/// 	fn serialize_data(&self, serializer: &mut S) {
-/// 		self.smalls.serialize_data(serializer);
-/// 		self.bigs.serialize_data(serializer);
-/// 	}
+///     fn serialize_data(&self, serializer: &mut S) {
+///         self.smalls.serialize_data(serializer);
+///         self.bigs.serialize_data(serializer);
+///     }
 /// }
 /// ```
 ///
@@ -94,11 +146,54 @@ use crate::Serializer;
 /// [`Serialize` implementation for `Box` and `Vec`]:
 /// https://docs.rs/ser_raw/latest/src/ser_raw/serialize_impls/ptrs.rs.html
 /// [`PureCopySerializer`]: crate::PureCopySerializer
-pub trait Serialize<Ser: Serializer> {
+/// [`overwrite`]: Serializer::overwrite
+/// [`CompleteSerializer`]: crate::CompleteSerializer
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+/// [`Deserialize`]: crate::Deserialize
+pub trait Serialize<Ser: Serializer>: Sized {
+	/// Fingerprint of this type's memory layout.
+	///
+	/// Recorded in output by [`serialize_with_header`], to detect if output is
+	/// read using a different type definition.
+	///
+	/// Derive macro computes a fingerprint from the type's definition.
+	/// Default is computed from only the type's size and alignment.
+	/// See [`fingerprint`](crate::fingerprint) module for more details.
+	///
+	/// [`serialize_with_header`]: Serializer::serialize_with_header
+	const FINGERPRINT: u64 = fingerprint::of_layout::<Self>();
+
+	/// Size of this type's representation in output of a portable serializer
+	/// (e.g. [`PortableSerializer`]).
+	///
+	/// See [`portable`](crate::portable) module for details of the format.
+	///
+	/// Default causes a compile-time error if used with a portable serializer,
+	/// so types which don't implement [`serialize_portable`] can still be
+	/// serialized with other serializers. Derive macro implements this.
+	///
+	/// [`PortableSerializer`]: crate::PortableSerializer
+	/// [`serialize_portable`]: Serialize::serialize_portable
+	const PORTABLE_SIZE: usize = unsupported_portable_size::<Ser>();
+
 	/// Serialize data owned by this value, outside value's own memory allocation.
 	///
 	/// See [`Serialize`] trait for more details.
-	fn serialize_data(&self, serializer: &mut Ser) -> ();
+	fn serialize_data(&self, serializer: &mut Ser);
+
+	/// Write this value's portable representation at position `pos` in output,
+	/// and push any data it owns.
+	///
+	/// `PORTABLE_SIZE` bytes at `pos` have already been reserved, and are
+	/// zeroed. Only called by portable serializers.
+	///
+	/// See [`portable`](crate::portable) module for details of the format.
+	/// Default is unimplemented. Derive macro implements this.
+	#[allow(unused_variables)]
+	fn serialize_portable(&self, serializer: &mut Ser, pos: usize) {
+		let _ = Self::PORTABLE_SIZE;
+		unimplemented!("Type does not support portable serialization");
+	}
 }
 
 /// Trait for implementing an equivalent of [`Serialize`] on foreign types for
@@ -116,27 +211,71 @@ pub trait Serialize<Ser: Serializer> {
 /// // Our own type which contains the foreign type
 /// #[derive(Serialize)]
 /// struct Foo {
-/// 	#[ser_with(BigUintProxy)]
-/// 	big: BigUint,
+///     #[ser_with(BigUintProxy)]
+///     big: BigUint,
 /// }
 ///
 /// struct BigUintProxy;
 /// impl<S> SerializeWith<BigUint, S> for BigUintProxy
 /// where S: Serializer
 /// {
-/// 	fn serialize_data_with(biguint: &BigUint, serializer: &mut S) {
-/// 		let bytes = biguint.to_bytes_le();
-/// 		// Actually next line isn't quite right.
-/// 		// We need to offset `Addr` to the position of pointer within `BigUint`.
-/// 		let ptr_addr = S::Addr::from_ref(biguint);
-/// 		serializer.push(&bytes.len(), ptr_addr);
-/// 		serializer.push_raw_bytes(bytes.as_slice());
-/// 	}
+///     fn serialize_data_with(biguint: &BigUint, serializer: &mut S) {
+///         let bytes = biguint.to_bytes_le();
+///         // Actually next line isn't quite right.
+///         // We need to offset `Addr` to the position of pointer within `BigUint`.
+///         let ptr_addr = S::Addr::from_ref(biguint);
+///         serializer.push(&bytes.len(), ptr_addr);
+///         serializer.push_raw_bytes(bytes.as_slice());
+///     }
+/// }
+/// ```
+///
+/// `#[ser_with]` can also be used on fields of enum variants. It can only be
+/// used on fields - using it on a type or an enum variant is an error:
+///
+/// ```compile_fail
+/// # use ser_raw::{Serialize, Serializer, SerializeWith};
+/// # struct Foreign;
+/// # struct ForeignProxy;
+/// # impl<S: Serializer> SerializeWith<Foreign, S> for ForeignProxy {
+/// #     fn serialize_data_with(_foreign: &Foreign, _serializer: &mut S) {}
+/// # }
+/// #[derive(Serialize)]
+/// enum Foo {
+///     #[ser_with(ForeignProxy)]
+///     Bar(Foreign),
 /// }
 /// ```
 pub trait SerializeWith<T, Ser: Serializer> {
 	/// Serialize data owned by this value, outside value's own memory allocation.
 	///
 	/// See [`SerializeWith`] trait for more details.
-	fn serialize_data_with(value: &T, serializer: &mut Ser) -> ();
+	fn serialize_data_with(value: &T, serializer: &mut Ser);
+
+	/// Size of value's representation in output of a portable serializer.
+	///
+	/// See [`Serialize::PORTABLE_SIZE`].
+	const PORTABLE_SIZE: usize = unsupported_portable_size::<Ser>();
+
+	/// Write value's portable representation at position `pos` in output, and
+	/// push any data it owns.
+	///
+	/// See [`Serialize::serialize_portable`].
+	#[allow(unused_variables)]
+	fn serialize_portable_with(value: &T, serializer: &mut Ser, pos: usize) {
+		let _ = Self::PORTABLE_SIZE;
+		unimplemented!("Type does not support portable serialization");
+	}
+}
+
+/// Default for `PORTABLE_SIZE`, for types which don't support portable
+/// serialization.
+///
+/// Produces a compile-time error if evaluated for a portable serializer.
+/// Depends on `Ser`, so is not evaluated until serializer type is known.
+const fn unsupported_portable_size<Ser: Serializer>() -> usize {
+	match Ser::KIND {
+		SerializerKind::Portable => panic!("Type does not support portable serialization"),
+		_ => 0,
+	}
 }
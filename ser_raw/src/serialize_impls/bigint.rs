@@ -1,12 +1,9 @@
-use std::{
-	marker::PhantomData,
-	mem::{self, MaybeUninit},
-};
+use std::mem::{self, MaybeUninit};
 
 use num_bigint::{BigInt, BigUint, Sign};
 
-use super::ptrs::VecOffsets;
-use crate::{Serialize, Serializer};
+use super::ptrs::{SameSizeAndAlignment, VecOffsets};
+use crate::{fingerprint, portable, Serialize, Serializer};
 
 const PTR_SIZE: usize = mem::size_of::<usize>();
 
@@ -17,17 +14,37 @@ const PTR_SIZE: usize = mem::size_of::<usize>();
 impl<S> Serialize<S> for BigUint
 where S: Serializer
 {
+	const FINGERPRINT: u64 = fingerprint::of_named::<BigUint>("BigUint");
+
+	// Portable representation is same as a `Vec<u8>` containing the number's
+	// bytes in little-endian order (offset + length), as digits are `usize`s,
+	// so differ between systems. Zero has no bytes.
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
 	// Inline because cast produces no machine instructions,
 	// so this is exactly equivalent to `Vec<usize>::serialize_data`
 	#[inline]
 	fn serialize_data(&self, serializer: &mut S) {
 		// Compile-time check `BigUint` and `Vec<usize>` have same size and alignment
+		#[allow(clippy::let_unit_value)]
 		let _ = SameSizeAndAlignment::<BigUint, Vec<usize>>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
 
 		let ptr = self as *const BigUint as *const Vec<usize>;
 		let vec: &Vec<usize> = unsafe { &*ptr.cast() };
 		vec.serialize_data(serializer);
 	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let bytes = self.to_bytes_le();
+		// `to_bytes_le()` returns `[0]` for zero
+		if bytes == [0] {
+			return;
+		}
+
+		let target_pos = portable::push_bytes(serializer, &bytes);
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, bytes.len());
+	}
 }
 
 // `BigInt` is defined as `BigInt { sign: Sign, data: BigUint }`.
@@ -37,12 +54,19 @@ where S: Serializer
 impl<S> Serialize<S> for BigInt
 where S: Serializer
 {
+	const FINGERPRINT: u64 = fingerprint::of_named::<BigInt>("BigInt");
+
+	// Portable representation is sign as an `i8` (-1, 0 or 1), followed by
+	// magnitude as a `BigUint`
+	const PORTABLE_SIZE: usize = 1 + <BigUint as Serialize<S>>::PORTABLE_SIZE;
+
 	// Inline because `data_offset` should always be 0 (see below)
 	// and cast produces no machine instructions, so this should be exactly
 	// equivalent to `BigUint::serialize_data`
 	#[inline]
 	fn serialize_data(&self, serializer: &mut S) {
 		// Compile-time check `BigInt` and `(u8, BigUint)` have same size and alignment
+		#[allow(clippy::let_unit_value)]
 		let _ = SameSizeAndAlignment::<BigInt, (u8, BigUint)>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
 
 		let data_offset = bigint_data_offset();
@@ -50,6 +74,16 @@ where S: Serializer
 		let biguint: &BigUint = unsafe { &*ptr.add(data_offset).cast() };
 		biguint.serialize_data(serializer);
 	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let sign: i8 = match self.sign() {
+			Sign::Minus => -1,
+			Sign::NoSign => 0,
+			Sign::Plus => 1,
+		};
+		serializer.write_portable(pos, &sign.to_le_bytes());
+		self.magnitude().serialize_portable(serializer, pos + 1);
+	}
 }
 
 /// `BigInt` is defined as `BigInt { sign: Sign, data: BigUint }`.
@@ -63,7 +97,7 @@ where S: Serializer
 /// integer. See ASM output:
 /// https://play.rust-lang.org/?version=stable&mode=release&edition=2021&gist=16964e78dfb89715902c42d75d05a40f
 #[inline]
-fn bigint_data_offset() -> usize {
+pub(crate) fn bigint_data_offset() -> usize {
 	// Create positive and negative `BigInt`s.
 	// Need to use this hack of creating a `BigUint` with len 1, as
 	// `BigInt::from_biguint` calls `BigUint::is_zero()` (which calls
@@ -111,16 +145,3 @@ fn create_bigint_bytes(sign: Sign) -> [MaybeUninit<u8>; PTR_SIZE * 4] {
 	// Transmute to array of `MaybeUninit<u8>`s. It's now safe to drop.
 	unsafe { mem::transmute(bigint) }
 }
-
-/// Type for static assertion that 2 types have same size and alignment
-struct SameSizeAndAlignment<T1, T2> {
-	_marker1: PhantomData<T1>,
-	_marker2: PhantomData<T2>,
-}
-
-impl<T1, T2> SameSizeAndAlignment<T1, T2> {
-	const ASSERT_SAME_SIZE_AND_ALIGNMENT: () = {
-		assert!(mem::size_of::<T1>() == mem::size_of::<T2>());
-		assert!(mem::align_of::<T1>() == mem::align_of::<T2>());
-	};
-}
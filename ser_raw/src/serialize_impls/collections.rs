@@ -0,0 +1,497 @@
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+	marker::PhantomData,
+	mem::{self, ManuallyDrop},
+	ops::Deref,
+	ptr, slice,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::ptrs::BOXED_SLICE_PTR_OFFSET;
+use crate::{fingerprint, header::SerializerKind, portable, pos::Addr, Serialize, Serializer};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+/// Entry of a map (`HashMap` or `BTreeMap`) in output.
+///
+/// Maps are serialized as a sorted slice of `MapEntry`s. `#[repr(C)]`, so
+/// `key` is always at start, followed by `value`.
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MapEntry<K, V> {
+	/// Key
+	pub key: K,
+	/// Value
+	pub value: V,
+}
+
+impl<K, V, S> Serialize<S> for MapEntry<K, V>
+where
+	S: Serializer,
+	K: Serialize<S>,
+	V: Serialize<S>,
+{
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::hash_u64(
+			fingerprint::of_named::<MapEntry<K, V>>("MapEntry"),
+			K::FINGERPRINT,
+		),
+		V::FINGERPRINT,
+	);
+
+	const PORTABLE_SIZE: usize = K::PORTABLE_SIZE + V::PORTABLE_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		self.key.serialize_data(serializer);
+		self.value.serialize_data(serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		self.key.serialize_portable(serializer, pos);
+		self
+			.value
+			.serialize_portable(serializer, pos + K::PORTABLE_SIZE);
+	}
+}
+
+/// `HashMap` is serialized as a pointer to a `Box<[MapEntry<K, V>]>`, with
+/// entries sorted by key.
+impl<K, V, H, S> Serialize<S> for HashMap<K, V, H>
+where
+	S: Serializer,
+	K: Serialize<S> + Ord,
+	V: Serialize<S>,
+{
+	// Only layouts of `K` and `V` included, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::hash_u64(
+			fingerprint::of_named::<HashMap<K, V, H>>("HashMap"),
+			fingerprint::of_layout::<K>(),
+		),
+		fingerprint::of_layout::<V>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		let entries = map_entries(self.iter(), true);
+		serialize_entries(self, &entries, serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let entries = map_entries(self.iter(), true);
+		serialize_entries_portable(&entries, serializer, pos);
+	}
+}
+
+/// `BTreeMap` is serialized as a pointer to a `Box<[MapEntry<K, V>]>`, with
+/// entries sorted by key.
+impl<K, V, S> Serialize<S> for BTreeMap<K, V>
+where
+	S: Serializer,
+	K: Serialize<S> + Ord,
+	V: Serialize<S>,
+{
+	// Only layouts of `K` and `V` included, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::hash_u64(
+			fingerprint::of_named::<BTreeMap<K, V>>("BTreeMap"),
+			fingerprint::of_layout::<K>(),
+		),
+		fingerprint::of_layout::<V>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		let entries = map_entries(self.iter(), false);
+		serialize_entries(self, &entries, serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let entries = map_entries(self.iter(), false);
+		serialize_entries_portable(&entries, serializer, pos);
+	}
+}
+
+/// `HashSet` is serialized as a pointer to a `Box<[T]>`, with values sorted.
+impl<T, H, S> Serialize<S> for HashSet<T, H>
+where
+	S: Serializer,
+	T: Serialize<S> + Ord,
+{
+	// Only layout of `T` included, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<HashSet<T, H>>("HashSet"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		let entries = set_entries(self.iter(), true);
+		serialize_entries(self, &entries, serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let entries = set_entries(self.iter(), true);
+		serialize_entries_portable(&entries, serializer, pos);
+	}
+}
+
+/// `BTreeSet` is serialized as a pointer to a `Box<[T]>`, with values sorted.
+impl<T, S> Serialize<S> for BTreeSet<T>
+where
+	S: Serializer,
+	T: Serialize<S> + Ord,
+{
+	// Only layout of `T` included, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<BTreeSet<T>>("BTreeSet"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		let entries = set_entries(self.iter(), false);
+		serialize_entries(self, &entries, serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let entries = set_entries(self.iter(), false);
+		serialize_entries_portable(&entries, serializer, pos);
+	}
+}
+
+/// `VecDeque`'s contents are written contiguously, starting with front element.
+/// `head` is overwritten with 0 and `capacity` with `len`, so output is a valid
+/// `VecDeque` for [`CompleteSerializer`].
+///
+/// [`CompleteSerializer`]: crate::CompleteSerializer
+impl<T, S> Serialize<S> for VecDeque<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<VecDeque<T>>("VecDeque"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// No need to do anything if deque contains ZSTs
+		if mem::size_of::<T>() == 0 {
+			return;
+		}
+
+		// Overwrite `head = 0` and `capacity = len`.
+		// If deque is empty, also `ptr = <dangling>`, as nothing is written.
+		serializer.overwrite_with(|serializer| {
+			let offsets = VecDequeOffsets::<T>::get();
+			unsafe {
+				serializer.overwrite(S::Addr::from_ref_offset(self, offsets.head), &0usize);
+				serializer.overwrite(
+					S::Addr::from_ref_offset(self, offsets.capacity),
+					&self.len(),
+				);
+				if self.is_empty() {
+					serializer.overwrite(
+						S::Addr::from_ref_offset(self, VecDequeOffsets::<T>::PTR_OFFSET),
+						&mem::align_of::<T>(),
+					);
+				}
+			}
+		});
+
+		// No need to write contents if deque is empty
+		if self.is_empty() {
+			return;
+		}
+
+		// Write deque's contents.
+		// If ring buffer wraps around, contents need to be copied into a contiguous
+		// buffer first, so they can be written as one slice.
+		let ptr_addr = S::Addr::from_ref_offset(self, VecDequeOffsets::<T>::PTR_OFFSET);
+		let (front, back) = self.as_slices();
+		let copies;
+		let values: &[T] = if back.is_empty() {
+			front
+		} else {
+			copies = BitwiseCopies::new(self.iter());
+			&copies
+		};
+		serializer.push_and_process_slice(values, ptr_addr, |serializer| {
+			// Serialize deque's contents
+			for value in values {
+				value.serialize_data(serializer);
+			}
+		});
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty deque is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::reserve(serializer, T::PORTABLE_SIZE * self.len());
+		for (index, value) in self.iter().enumerate() {
+			value.serialize_portable(serializer, target_pos + index * T::PORTABLE_SIZE);
+		}
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
+
+/// Get bitwise copies of a map's entries, sorted by key if `sort` is `true`.
+fn map_entries<'a, K, V, I>(iter: I, sort: bool) -> BitwiseCopies<MapEntry<K, V>>
+where
+	K: Ord + 'a,
+	V: 'a,
+	I: Iterator<Item = (&'a K, &'a V)>,
+{
+	let mut entries = iter.collect::<Vec<_>>();
+	if sort {
+		entries.sort_unstable_by(|(key1, _), (key2, _)| key1.cmp(key2));
+	}
+	let copies = entries.into_iter().map(|(key, value)| {
+		ManuallyDrop::new(MapEntry {
+			key: unsafe { ptr::read(key) },
+			value: unsafe { ptr::read(value) },
+		})
+	});
+	BitwiseCopies {
+		copies: copies.collect(),
+	}
+}
+
+/// Get bitwise copies of a set's values, sorted if `sort` is `true`.
+fn set_entries<'a, T, I>(iter: I, sort: bool) -> BitwiseCopies<T>
+where
+	T: Ord + 'a,
+	I: Iterator<Item = &'a T>,
+{
+	if sort {
+		let mut values = iter.collect::<Vec<_>>();
+		values.sort_unstable();
+		BitwiseCopies::new(values.into_iter())
+	} else {
+		BitwiseCopies::new(iter)
+	}
+}
+
+/// Serialize a map or set's entries.
+///
+/// Entries are written as a `Box<[T]>` (the "header"), followed by the
+/// entries themselves. First pointer-sized field of the map or set in output
+/// is a pointer to the header, and the rest of its fields are zeroed (not
+/// for [`PureCopySerializer`], which makes no corrections).
+///
+/// The map or set in output is not a valid value of its type, so using this
+/// with a `Complete` serializer (e.g. [`CompleteSerializer`]) is a
+/// compile-time error.
+///
+/// [`PureCopySerializer`]: crate::PureCopySerializer
+/// [`CompleteSerializer`]: crate::CompleteSerializer
+fn serialize_entries<C, T, S>(collection: &C, entries: &[T], serializer: &mut S)
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Static assertions (evaluated at compile time)
+	#[allow(clippy::let_unit_value)]
+	let _ = NotComplete::<S>::ASSERT_NOT_COMPLETE;
+	#[allow(clippy::let_unit_value)]
+	let _ = MinSize::<C, PTR_SIZE>::ASSERT_MIN_SIZE;
+
+	// Zero all fields apart from pointer to header
+	serializer.overwrite_with(|serializer| {
+		let mut offset = PTR_SIZE;
+		while offset + PTR_SIZE <= mem::size_of::<C>() {
+			unsafe { serializer.overwrite(S::Addr::from_ref_offset(collection, offset), &0usize) };
+			offset += PTR_SIZE;
+		}
+	});
+
+	// Write header, and then entries
+	let header: *const [T] = entries;
+	serializer.push_and_process(&header, S::Addr::from_ref(collection), |serializer| {
+		// No need to write entries if they're ZSTs, or there are none
+		if mem::size_of::<T>() == 0 || entries.is_empty() {
+			return;
+		}
+
+		let ptr_addr = S::Addr::from_ref_offset(&header, BOXED_SLICE_PTR_OFFSET);
+		serializer.push_and_process_slice(entries, ptr_addr, |serializer| {
+			for entry in entries {
+				entry.serialize_data(serializer);
+			}
+		});
+	});
+}
+
+/// Serialize a map or set's entries in portable format.
+/// Same format as `Vec<T>`.
+fn serialize_entries_portable<T, S>(entries: &[T], serializer: &mut S, pos: usize)
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Empty map or set is offset 0, length 0. Space is already zeroed.
+	if entries.is_empty() {
+		return;
+	}
+
+	let target_pos = portable::push_slice(serializer, entries);
+	portable::write_offset(serializer, pos, target_pos);
+	portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, entries.len());
+}
+
+/// Bitwise copies of values, in a contiguous buffer.
+///
+/// Copies are never dropped, so values they own are not freed twice.
+/// Dereferences to `&[T]`.
+struct BitwiseCopies<T> {
+	copies: Vec<ManuallyDrop<T>>,
+}
+
+impl<T> BitwiseCopies<T> {
+	fn new<'a, I: Iterator<Item = &'a T>>(iter: I) -> Self
+	where T: 'a {
+		let copies = iter.map(|value| ManuallyDrop::new(unsafe { ptr::read(value) }));
+		Self {
+			copies: copies.collect(),
+		}
+	}
+}
+
+impl<T> Deref for BitwiseCopies<T> {
+	type Target = [T];
+
+	#[inline]
+	fn deref(&self) -> &[T] {
+		// `ManuallyDrop<T>` is `#[repr(transparent)]`, so has same layout as `T`
+		unsafe { slice::from_raw_parts(self.copies.as_ptr() as *const T, self.copies.len()) }
+	}
+}
+
+/// Type for static assertion that serializer is not a `Complete` serializer.
+struct NotComplete<S> {
+	_marker: PhantomData<S>,
+}
+
+impl<S: Serializer> NotComplete<S> {
+	const ASSERT_NOT_COMPLETE: () = assert!(
+		!matches!(S::KIND, SerializerKind::Complete),
+		"Maps and sets cannot be serialized with a Complete serializer"
+	);
+}
+
+/// Type for static assertion of minimum size of type.
+struct MinSize<T, const SIZE: usize> {
+	_marker: PhantomData<T>,
+}
+
+impl<T, const SIZE: usize> MinSize<T, SIZE> {
+	const ASSERT_MIN_SIZE: () = assert!(mem::size_of::<T>() >= SIZE);
+}
+
+/// Offsets of fields in `VecDeque<T>`.
+///
+/// Offset of `ptr` field is calculated at compile time. `VecDeque` has no
+/// `const` constructor which sets the other fields to distinguishable values,
+/// so their offsets are determined at runtime by inspecting a `VecDeque` with
+/// known values. This is only done once, and result cached.
+pub(crate) struct VecDequeOffsets<T> {
+	pub head: usize,
+	pub len: usize,
+	pub capacity: usize,
+	_marker: PhantomData<T>,
+}
+
+impl<T> VecDequeOffsets<T> {
+	const PTR_INDEX: usize = {
+		// Empty deque does not allocate
+		let deque = VecDeque::<T>::new();
+		// Will fail to compile if `VecDeque<T>` is not implemented as 4 x `usize`
+		let words: [usize; 4] = unsafe { mem::transmute(deque) };
+		let dangle = mem::align_of::<T>();
+		let mut index = 0;
+		let mut ptr_index = usize::MAX;
+		while index < 4 {
+			if words[index] == dangle {
+				assert!(ptr_index == usize::MAX);
+				ptr_index = index;
+			} else {
+				assert!(words[index] == 0);
+			}
+			index += 1;
+		}
+		assert!(
+			ptr_index != usize::MAX,
+			"Could not determine offset of VecDeque's ptr field"
+		);
+		ptr_index
+	};
+
+	pub(crate) const PTR_OFFSET: usize = Self::PTR_INDEX * PTR_SIZE;
+
+	/// Get offsets of `head`, `len` and `capacity` fields.
+	pub(crate) fn get() -> Self {
+		static INDEXES: AtomicUsize = AtomicUsize::new(0);
+
+		let mut indexes = INDEXES.load(Ordering::Relaxed);
+		if indexes == 0 {
+			indexes = probe_vec_deque_indexes();
+			INDEXES.store(indexes, Ordering::Relaxed);
+		}
+
+		// Layout of `VecDeque` does not depend on `T`, as all its fields are
+		// pointer-sized. Check anyway.
+		assert_eq!(
+			indexes & 3,
+			Self::PTR_INDEX,
+			"Could not determine layout of VecDeque"
+		);
+		Self {
+			head: ((indexes >> 2) & 3) * PTR_SIZE,
+			len: ((indexes >> 4) & 3) * PTR_SIZE,
+			capacity: ((indexes >> 6) & 3) * PTR_SIZE,
+			_marker: PhantomData,
+		}
+	}
+}
+
+/// Determine indexes of `VecDeque`'s fields.
+///
+/// Returns indexes of `ptr`, `head`, `len` and `capacity` packed into 2 bits
+/// each. Bit 8 is always set, so result is never 0.
+#[cold]
+fn probe_vec_deque_indexes() -> usize {
+	// `head = 1`, `len = 2`, `capacity >= 4`
+	let mut deque = VecDeque::<u8>::with_capacity(4);
+	deque.extend([0, 0, 0]);
+	deque.pop_front();
+	assert!(deque.as_slices().1.is_empty());
+
+	let words: [usize; 4] = unsafe { mem::transmute_copy(&deque) };
+	let ptr = deque.as_slices().0.as_ptr() as usize - 1;
+	let index_of = |value: usize| {
+		let mut indexes = (0..4).filter(|&index| words[index] == value);
+		match (indexes.next(), indexes.next()) {
+			(Some(index), None) => index,
+			_ => panic!("Could not determine layout of VecDeque"),
+		}
+	};
+
+	index_of(ptr) | index_of(1) << 2 | index_of(2) << 4 | index_of(deque.capacity()) << 6 | 1 << 8
+}
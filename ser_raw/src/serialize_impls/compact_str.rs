@@ -0,0 +1,105 @@
+use std::mem;
+
+use compact_str::CompactString;
+
+use super::ptrs::SizeCheck;
+use crate::{fingerprint, portable, pos::Addr, Serialize, Serializer};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+// `CompactString` is 3 x `usize`. Last byte is the discriminant.
+//
+// If last byte is `HEAP_MASK`, string is on heap, and `CompactString` is
+// `#[repr(C)] HeapBuffer { ptr: NonNull<u8>, len: usize, cap: Capacity }`.
+// `Capacity` is the capacity's bytes in little-endian order, with last byte
+// replaced by `HEAP_MASK`.
+//
+// Otherwise string is inline. Last byte is length + `LENGTH_MASK`, or, if
+// string is `MAX_SIZE` bytes, its last byte. A UTF-8 string's last byte is
+// always less than `LENGTH_MASK`.
+//
+// This does rely on knowledge of `compact_str`'s internal implementation.
+// Dependency is on version 0.7.x only, as layout changes in 0.8.
+
+/// Size of `CompactString`, and maximum length of inline string
+pub(crate) const MAX_SIZE: usize = PTR_SIZE * 3;
+/// Value of last byte of a `CompactString` which is on heap
+pub(crate) const HEAP_MASK: u8 = 0b11111110;
+/// Offset added to length of an inline string in last byte
+pub(crate) const LENGTH_MASK: u8 = 0b11000000;
+/// Offset of heap pointer
+pub(crate) const PTR_OFFSET: usize = 0;
+/// Offset of heap length
+pub(crate) const LEN_OFFSET: usize = PTR_SIZE;
+/// Offset of heap capacity
+pub(crate) const CAP_OFFSET: usize = PTR_SIZE * 2;
+/// Maximum capacity which can be stored in `Capacity`.
+/// On 32-bit systems, larger capacities are stored on heap.
+pub(crate) const MAX_CAPACITY: usize = (1 << ((PTR_SIZE - 1) * 8)) - 2;
+
+impl<S> Serialize<S> for CompactString
+where S: Serializer
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<CompactString>("CompactString");
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Sanity check that `CompactString` is 3 x `usize` (evaluated at compile time)
+		#[allow(clippy::let_unit_value)]
+		let _ = SizeCheck::<CompactString, MAX_SIZE>::ASSERT_SIZE_IS;
+
+		// Inline strings are entirely within the `CompactString`
+		if !self.is_heap_allocated() {
+			return;
+		}
+
+		// No need to write contents if string is empty.
+		// Replace with an empty inline string, as pointer is not valid in output.
+		let len = self.len();
+		if len == 0 {
+			serializer.overwrite_with(|serializer| unsafe {
+				serializer.overwrite(S::Addr::from_ref(self), &CompactString::new_inline(""));
+			});
+			return;
+		}
+
+		// Overwrite `capacity = len`, if it's not already.
+		// Strings longer than `MAX_CAPACITY` would need capacity stored on heap,
+		// before the string, which isn't supported.
+		serializer.overwrite_with(|serializer| {
+			assert!(
+				len <= MAX_CAPACITY,
+				"Cannot serialize `CompactString` longer than {MAX_CAPACITY} bytes"
+			);
+			if self.capacity() != len {
+				let cap_addr = S::Addr::from_ref_offset(self, CAP_OFFSET);
+				unsafe { serializer.overwrite(cap_addr, &heap_capacity(len)) };
+			}
+		});
+
+		// Write string's content
+		let ptr_addr = S::Addr::from_ref_offset(self, PTR_OFFSET);
+		serializer.push_slice(self.as_bytes(), ptr_addr);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty string is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_bytes(serializer, self.as_bytes());
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
+
+/// Get bytes of capacity of a heap `CompactString`.
+#[inline]
+fn heap_capacity(capacity: usize) -> [u8; PTR_SIZE] {
+	let mut bytes = capacity.to_le_bytes();
+	bytes[PTR_SIZE - 1] = HEAP_MASK;
+	bytes
+}
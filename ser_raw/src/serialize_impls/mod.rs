@@ -1,7 +1,25 @@
+pub(crate) mod collections;
 mod multiples;
 mod other;
 mod primitives;
-mod ptrs;
+pub(crate) mod ptrs;
+pub(crate) mod shared;
+mod wrappers;
 
 #[cfg(feature = "num_bigint")]
-mod bigint;
+pub(crate) mod bigint;
+
+#[cfg(feature = "smallvec")]
+pub(crate) mod smallvec;
+
+#[cfg(feature = "thin_vec")]
+pub(crate) mod thin_vec;
+
+#[cfg(feature = "compact_str")]
+pub(crate) mod compact_str;
+
+#[cfg(feature = "smol_str")]
+pub(crate) mod smol_str;
+
+#[cfg(feature = "string_cache")]
+pub(crate) mod string_cache;
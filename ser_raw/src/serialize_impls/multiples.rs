@@ -1,13 +1,26 @@
-use crate::{Serialize, Serializer};
+use crate::{fingerprint, Serialize, Serializer};
 
 impl<T, S, const N: usize> Serialize<S> for [T; N]
 where
 	S: Serializer,
 	T: Serialize<S>,
 {
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::hash_usize(fingerprint::of_named::<[T; N]>("Array"), N),
+		T::FINGERPRINT,
+	);
+
+	const PORTABLE_SIZE: usize = T::PORTABLE_SIZE * N;
+
 	fn serialize_data(&self, serializer: &mut S) {
-		for i in 0..N {
-			self[i].serialize_data(serializer);
+		for value in self {
+			value.serialize_data(serializer);
+		}
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		for (index, value) in self.iter().enumerate() {
+			value.serialize_portable(serializer, pos + index * T::PORTABLE_SIZE);
 		}
 	}
 }
@@ -19,11 +32,29 @@ macro_rules! impl_tuple {
 		where Ser: Serializer,
 			$($t: Serialize<Ser>,)+
 		{
+			const FINGERPRINT: u64 = {
+				let hash = fingerprint::of_named::<($($t,)+)>("Tuple");
+				$(
+					let hash = fingerprint::hash_u64(hash, $t::FINGERPRINT);
+				)+
+				hash
+			};
+
+			const PORTABLE_SIZE: usize = 0 $(+ $t::PORTABLE_SIZE)+;
+
 			fn serialize_data(&self, serializer: &mut Ser) {
 				$(
 					self.$idx.serialize_data(serializer);
 				)+
 			}
+
+			#[allow(unused_assignments)]
+			fn serialize_portable(&self, serializer: &mut Ser, mut pos: usize) {
+				$(
+					self.$idx.serialize_portable(serializer, pos);
+					pos += $t::PORTABLE_SIZE;
+				)+
+			}
 		}
 	};
 }
@@ -1,13 +1,27 @@
-use crate::{Serialize, Serializer};
+use crate::{fingerprint, Serialize, Serializer};
 
 impl<T, S> Serialize<S> for Option<T>
 where
 	S: Serializer,
 	T: Serialize<S>,
 {
+	const FINGERPRINT: u64 =
+		fingerprint::hash_u64(fingerprint::of_named::<Option<T>>("Option"), T::FINGERPRINT);
+
+	// 1 byte tag + value
+	const PORTABLE_SIZE: usize = 1 + T::PORTABLE_SIZE;
+
 	fn serialize_data(&self, serializer: &mut S) {
 		if let Some(value) = self {
 			value.serialize_data(serializer);
 		}
 	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// `None` is all zeros, and space is already zeroed
+		if let Some(value) = self {
+			serializer.write_portable(pos, &[1]);
+			value.serialize_portable(serializer, pos + 1);
+		}
+	}
 }
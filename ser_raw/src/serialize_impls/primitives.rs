@@ -1,48 +1,126 @@
-use std::num;
+use std::{
+	cmp, mem, num,
+	sync::atomic::{self, Ordering},
+	time::Duration,
+};
 
-use crate::{Serialize, Serializer};
+use crate::{fingerprint, Serialize, Serializer};
 
 macro_rules! impl_primitive {
+	// Portable representation is same as native representation, but little-endian
 	($ty:ty) => {
+		impl_primitive!($ty, mem::size_of::<$ty>(), |value: &$ty| {
+			value.to_le_bytes()
+		});
+	};
+
+	// `to_bytes` converts value to its portable representation, of `size` bytes
+	($ty:ty, $size:expr, $to_bytes:expr) => {
 		impl<S: Serializer> Serialize<S> for $ty {
+			const FINGERPRINT: u64 = fingerprint::of_named::<$ty>(stringify!($ty));
+
+			const PORTABLE_SIZE: usize = $size;
+
 			#[inline(always)]
 			fn serialize_data(&self, _serializer: &mut S) {}
+
+			#[inline]
+			fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+				#[allow(clippy::redundant_closure_call)]
+				let bytes: [u8; $size] = ($to_bytes)(self);
+				serializer.write_portable(pos, &bytes);
+			}
 		}
 	};
 }
 
+macro_rules! impl_atomic {
+	($ty:ty) => {
+		impl_primitive!($ty, mem::size_of::<$ty>(), |value: &$ty| {
+			value.load(Ordering::Relaxed).to_le_bytes()
+		});
+	};
+}
+
+macro_rules! impl_non_zero {
+	($ty:ty) => {
+		impl_primitive!($ty, mem::size_of::<$ty>(), |value: &$ty| {
+			value.get().to_le_bytes()
+		});
+	};
+}
+
 impl_primitive!(u8);
 impl_primitive!(u16);
 impl_primitive!(u32);
 impl_primitive!(u64);
 impl_primitive!(u128);
-impl_primitive!(usize);
+// `usize` and `isize` are 64 bit in portable output, regardless of system's
+// pointer width
+impl_primitive!(usize, 8, |value: &usize| (*value as u64).to_le_bytes());
 
 impl_primitive!(i8);
 impl_primitive!(i16);
 impl_primitive!(i32);
 impl_primitive!(i64);
 impl_primitive!(i128);
-impl_primitive!(isize);
-
-impl_primitive!(num::NonZeroU8);
-impl_primitive!(num::NonZeroU16);
-impl_primitive!(num::NonZeroU32);
-impl_primitive!(num::NonZeroU64);
-impl_primitive!(num::NonZeroU128);
-impl_primitive!(num::NonZeroUsize);
-
-impl_primitive!(num::NonZeroI8);
-impl_primitive!(num::NonZeroI16);
-impl_primitive!(num::NonZeroI32);
-impl_primitive!(num::NonZeroI64);
-impl_primitive!(num::NonZeroI128);
-impl_primitive!(num::NonZeroIsize);
+impl_primitive!(isize, 8, |value: &isize| (*value as i64).to_le_bytes());
+
+impl_non_zero!(num::NonZeroU8);
+impl_non_zero!(num::NonZeroU16);
+impl_non_zero!(num::NonZeroU32);
+impl_non_zero!(num::NonZeroU64);
+impl_non_zero!(num::NonZeroU128);
+impl_primitive!(num::NonZeroUsize, 8, |value: &num::NonZeroUsize| {
+	(value.get() as u64).to_le_bytes()
+});
+
+impl_non_zero!(num::NonZeroI8);
+impl_non_zero!(num::NonZeroI16);
+impl_non_zero!(num::NonZeroI32);
+impl_non_zero!(num::NonZeroI64);
+impl_non_zero!(num::NonZeroI128);
+impl_primitive!(num::NonZeroIsize, 8, |value: &num::NonZeroIsize| {
+	(value.get() as i64).to_le_bytes()
+});
 
 impl_primitive!(f32);
 impl_primitive!(f64);
 
-impl_primitive!(bool);
-impl_primitive!(char);
+impl_primitive!(bool, 1, |value: &bool| [*value as u8]);
+impl_primitive!(char, 4, |value: &char| (*value as u32).to_le_bytes());
+
+impl_primitive!((), 0, |_: &()| []);
+
+impl_primitive!(cmp::Ordering, 1, |value: &cmp::Ordering| {
+	[*value as i8 as u8]
+});
+
+// Seconds as `u64`, followed by nanoseconds as `u32`
+impl_primitive!(Duration, 12, |value: &Duration| {
+	let mut bytes = [0; 12];
+	bytes[..8].copy_from_slice(&value.as_secs().to_le_bytes());
+	bytes[8..].copy_from_slice(&value.subsec_nanos().to_le_bytes());
+	bytes
+});
+
+// Atomics' portable representation is their current value
+impl_atomic!(atomic::AtomicU8);
+impl_atomic!(atomic::AtomicU16);
+impl_atomic!(atomic::AtomicU32);
+impl_atomic!(atomic::AtomicU64);
+impl_primitive!(atomic::AtomicUsize, 8, |value: &atomic::AtomicUsize| {
+	(value.load(Ordering::Relaxed) as u64).to_le_bytes()
+});
+
+impl_atomic!(atomic::AtomicI8);
+impl_atomic!(atomic::AtomicI16);
+impl_atomic!(atomic::AtomicI32);
+impl_atomic!(atomic::AtomicI64);
+impl_primitive!(atomic::AtomicIsize, 8, |value: &atomic::AtomicIsize| {
+	(value.load(Ordering::Relaxed) as i64).to_le_bytes()
+});
 
-impl_primitive!(());
+impl_primitive!(atomic::AtomicBool, 1, |value: &atomic::AtomicBool| {
+	[value.load(Ordering::Relaxed) as u8]
+});
@@ -1,6 +1,8 @@
-use std::{marker::PhantomData, mem};
+use std::{borrow::Cow, marker::PhantomData, mem, ptr, slice};
+#[cfg(unix)]
+use std::{os::unix::ffi::OsStrExt, path::Path};
 
-use crate::{pos::Addr, Serialize, Serializer};
+use crate::{fingerprint, portable, pos::Addr, Serialize, Serializer};
 
 const PTR_SIZE: usize = mem::size_of::<usize>();
 
@@ -9,9 +11,19 @@ where
 	S: Serializer,
 	T: Serialize<S> + Sized,
 {
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<Box<T>>("Box"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE;
+
 	fn serialize_data(&self, serializer: &mut S) {
 		// Sanity check that `Box<T>` is just a pointer (evaluated at compile time).
 		// Unsized types are not supported.
+		#[allow(clippy::let_unit_value)]
 		let _ = SizeCheck::<Box<T>, PTR_SIZE>::ASSERT_SIZE_IS;
 
 		// No need to do anything if box contains ZST
@@ -27,6 +39,11 @@ where
 			(**self).serialize_data(serializer);
 		});
 	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let target_pos = portable::push_slice(serializer, slice::from_ref(&**self));
+		portable::write_offset(serializer, pos, target_pos);
+	}
 }
 
 impl<T, S> Serialize<S> for Vec<T>
@@ -34,6 +51,15 @@ where
 	S: Serializer,
 	T: Serialize<S>,
 {
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<Vec<T>>("Vec"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
 	fn serialize_data(&self, serializer: &mut S) {
 		// No need to do anything if vec contains ZSTs
 		// TODO: Should we call `serialize_data()` in case user defines some behavior?
@@ -42,7 +68,7 @@ where
 		}
 
 		// No need to write contents if vec is empty
-		if self.len() == 0 {
+		if self.is_empty() {
 			// Overwrite `capacity = 0` and `ptr = <dangling>` if it's not already
 			serializer.overwrite_with(|serializer| {
 				if self.capacity() != 0 {
@@ -71,14 +97,30 @@ where
 			}
 		});
 	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty vec is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_slice(serializer, self.as_slice());
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
 }
 
 impl<S> Serialize<S> for String
 where S: Serializer
 {
+	const FINGERPRINT: u64 = fingerprint::of_named::<String>("String");
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
 	fn serialize_data(&self, serializer: &mut S) {
 		// No need to write contents if string is empty
-		if self.len() == 0 {
+		if self.is_empty() {
 			// Overwrite `capacity = 0` and `ptr = <dangling>` if it's not already
 			serializer.overwrite_with(|serializer| {
 				if self.capacity() != 0 {
@@ -102,15 +144,252 @@ where S: Serializer
 		let ptr_addr = S::Addr::from_ref_offset(self, STRING_PTR_OFFSET);
 		serializer.push_slice(self.as_bytes(), ptr_addr);
 	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty string is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_bytes(serializer, self.as_bytes());
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
+
+impl<T, S> Serialize<S> for Box<[T]>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<Box<[T]>>("Box<[T]>"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Sanity check that `Box<[T]>` is a pointer + length (evaluated at compile
+		// time)
+		#[allow(clippy::let_unit_value)]
+		let _ = SizeCheck::<Box<[T]>, { PTR_SIZE * 2 }>::ASSERT_SIZE_IS;
+
+		// No need to do anything if slice contains ZSTs, or is empty.
+		// Pointer is dangling, which is valid for an empty slice.
+		if mem::size_of::<T>() == 0 || self.is_empty() {
+			return;
+		}
+
+		// Write slice's contents
+		let ptr_addr = S::Addr::from_ref_offset(self, BOXED_SLICE_PTR_OFFSET);
+		serializer.push_and_process_slice(self, ptr_addr, |serializer| {
+			// Serialize slice's contents
+			for value in self.iter() {
+				value.serialize_data(serializer);
+			}
+		});
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty slice is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_slice(serializer, self);
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
+
+impl<S> Serialize<S> for Box<str>
+where S: Serializer
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<Box<str>>("Box<str>");
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		serialize_boxed_bytes(self, self.as_bytes(), serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		serialize_boxed_bytes_portable(self.as_bytes(), serializer, pos);
+	}
+}
+
+/// `Box<Path>` is serialized as the bytes of the path.
+/// Only supported on Unix, where paths are arbitrary bytes.
+#[cfg(unix)]
+impl<S> Serialize<S> for Box<Path>
+where S: Serializer
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<Box<Path>>("Box<Path>");
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		serialize_boxed_bytes(self, self.as_os_str().as_bytes(), serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		serialize_boxed_bytes_portable(self.as_os_str().as_bytes(), serializer, pos);
+	}
+}
+
+/// `Cow::Owned` is serialized same as `String`. `Cow::Borrowed` is serialized
+/// same as `Box<str>`, so output contains a `&str` pointing to the copied
+/// string.
+impl<'a, S> Serialize<S> for Cow<'a, str>
+where S: Serializer
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<Cow<str>>("Cow<str>");
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		match self {
+			Cow::Borrowed(s) => serialize_borrowed_slice(s, s.as_bytes(), serializer),
+			Cow::Owned(s) => s.serialize_data(serializer),
+		}
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		serialize_boxed_bytes_portable(self.as_bytes(), serializer, pos);
+	}
+}
+
+/// `Cow::Owned` is serialized same as `Vec<T>`. `Cow::Borrowed` is serialized
+/// same as `Box<[T]>`, so output contains a `&[T]` pointing to the copied
+/// slice.
+impl<'a, T, S> Serialize<S> for Cow<'a, [T]>
+where
+	S: Serializer,
+	T: Serialize<S> + Clone,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<Cow<[T]>>("Cow<[T]>"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		match self {
+			Cow::Borrowed(slice) => serialize_borrowed_slice(slice, slice, serializer),
+			Cow::Owned(vec) => vec.serialize_data(serializer),
+		}
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty slice is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_slice(serializer, self);
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
+
+/// Serialize a borrowed slice held in a `Cow` (`&str` or `&[T]`).
+///
+/// `slice_ref` is the reference within the `Cow`, `slice` is its content.
+#[inline]
+fn serialize_borrowed_slice<R, T, S>(slice_ref: &R, slice: &[T], serializer: &mut S)
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Sanity check that reference is a pointer + length (evaluated at compile
+	// time)
+	#[allow(clippy::let_unit_value)]
+	let _ = SizeCheck::<R, { PTR_SIZE * 2 }>::ASSERT_SIZE_IS;
+
+	let ptr_addr = S::Addr::from_ref_offset(slice_ref, BOXED_SLICE_PTR_OFFSET);
+
+	// Nothing to write if slice is empty or contains ZSTs. Unlike a `Box`, a
+	// borrowed empty slice may point to memory outside of output, so replace
+	// pointer with a dangling one.
+	if mem::size_of::<T>() == 0 || slice.is_empty() {
+		serializer.overwrite_with(|serializer| unsafe {
+			serializer.overwrite(ptr_addr, &mem::align_of::<T>());
+		});
+		return;
+	}
+
+	// Write slice's contents
+	serializer.push_and_process_slice(slice, ptr_addr, |serializer| {
+		// Serialize slice's contents
+		for value in slice {
+			value.serialize_data(serializer);
+		}
+	});
+}
+
+/// Serialize a boxed unsized type whose content is a slice of bytes
+/// (`Box<str>` or `Box<Path>`).
+///
+/// `boxed` is the box, `bytes` is its content.
+#[inline]
+fn serialize_boxed_bytes<B, S: Serializer>(boxed: &B, bytes: &[u8], serializer: &mut S) {
+	// Sanity check that box is a pointer + length (evaluated at compile time)
+	#[allow(clippy::let_unit_value)]
+	let _ = SizeCheck::<B, { PTR_SIZE * 2 }>::ASSERT_SIZE_IS;
+
+	// No need to write contents if empty. Pointer is dangling, which is valid.
+	if bytes.is_empty() {
+		return;
+	}
+
+	// Write content
+	let ptr_addr = S::Addr::from_ref_offset(boxed, BOXED_SLICE_PTR_OFFSET);
+	serializer.push_slice(bytes, ptr_addr);
+}
+
+/// Serialize content of a `Box<str>` or `Box<Path>` in portable format.
+/// Same format as `String`.
+#[inline]
+fn serialize_boxed_bytes_portable<S: Serializer>(bytes: &[u8], serializer: &mut S, pos: usize) {
+	// Empty is offset 0, length 0. Space is already zeroed.
+	if bytes.is_empty() {
+		return;
+	}
+
+	let target_pos = portable::push_bytes(serializer, bytes);
+	portable::write_offset(serializer, pos, target_pos);
+	portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, bytes.len());
 }
 
 /// Type for static assertion of size of type.
-struct SizeCheck<T, const SIZE: usize> {
+pub(crate) struct SizeCheck<T, const SIZE: usize> {
 	_marker: PhantomData<T>,
 }
 
 impl<T, const SIZE: usize> SizeCheck<T, SIZE> {
-	const ASSERT_SIZE_IS: () = assert!(mem::size_of::<T>() == SIZE);
+	pub(crate) const ASSERT_SIZE_IS: () = assert!(mem::size_of::<T>() == SIZE);
+}
+
+/// Type for static assertion that 2 types have same size and alignment
+pub(crate) struct SameSizeAndAlignment<T1, T2> {
+	_marker1: PhantomData<T1>,
+	_marker2: PhantomData<T2>,
+}
+
+impl<T1, T2> SameSizeAndAlignment<T1, T2> {
+	pub(crate) const ASSERT_SAME_SIZE_AND_ALIGNMENT: () = {
+		assert!(mem::size_of::<T1>() == mem::size_of::<T2>());
+		assert!(mem::align_of::<T1>() == mem::align_of::<T2>());
+	};
 }
 
 /// Type for calculating offset of fields in `Vec<T>` at compile time.
@@ -192,9 +471,9 @@ const STRING_PTR_INDEX: usize = {
 		panic!("Could not determine offset of String's ptr field");
 	}
 };
-const STRING_PTR_OFFSET: usize = STRING_PTR_INDEX * PTR_SIZE;
+pub(crate) const STRING_PTR_OFFSET: usize = STRING_PTR_INDEX * PTR_SIZE;
 
-const OFFSETS_STRING: mem::ManuallyDrop<String> = {
+pub(crate) const OFFSETS_STRING: mem::ManuallyDrop<String> = {
 	let dangle = 1;
 	let bytes = match STRING_PTR_INDEX {
 		0 => [dangle, PTR_SIZE, PTR_SIZE * 2],
@@ -205,6 +484,28 @@ const OFFSETS_STRING: mem::ManuallyDrop<String> = {
 	unsafe { mem::transmute(bytes) }
 };
 
+// Constants for offset of fields in fat pointers for boxed slices
+// (`Box<[T]>`, `Box<str>`, `Box<Path>`), calculated at compile time.
+// All have same layout as `*const [u8]`.
+//
+// * Offset of pointer: `BOXED_SLICE_PTR_OFFSET`
+// * Offset of length: `BOXED_SLICE_LEN_OFFSET`
+const BOXED_SLICE_PTR_INDEX: usize = {
+	// Pointer with address 2 and length 1
+	let ptr: *const [u8] = ptr::slice_from_raw_parts(2 as *const u8, 1);
+	// Will fail to compile if fat pointer is not 2 x `usize`
+	let words: [usize; 2] = unsafe { mem::transmute(ptr) };
+	if words[0] == 2 && words[1] == 1 {
+		0
+	} else if words[0] == 1 && words[1] == 2 {
+		1
+	} else {
+		panic!("Could not determine offset of boxed slice's ptr field");
+	}
+};
+pub(crate) const BOXED_SLICE_PTR_OFFSET: usize = BOXED_SLICE_PTR_INDEX * PTR_SIZE;
+pub(crate) const BOXED_SLICE_LEN_OFFSET: usize = (1 - BOXED_SLICE_PTR_INDEX) * PTR_SIZE;
+
 /// Overwrite `capacity` and `ptr` for empty `Vec<T>`.
 ///
 /// Will write both in a single write if the two fields are next to each other,
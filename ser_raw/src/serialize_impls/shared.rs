@@ -0,0 +1,232 @@
+use std::{
+	mem::{self, MaybeUninit},
+	ptr,
+	rc::{self, Rc},
+	sync::{self, Arc},
+};
+
+use super::ptrs::SizeCheck;
+use crate::{
+	fingerprint, portable,
+	pos::{Addr, RefKind},
+	serializer::expect_shared_allocs,
+	Serialize, Serializer,
+};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+/// Layout of the allocation which an `Rc<T>` or `Arc<T>` points to
+/// (`RcBox<T>` / `ArcInner<T>`). Both have this same layout.
+///
+/// Reference counts are `Cell<usize>` / `AtomicUsize`, which have same layout
+/// as `usize`.
+#[repr(C)]
+pub(crate) struct RcBox<T> {
+	pub strong: usize,
+	pub weak: usize,
+	pub value: T,
+}
+
+/// Address which a dangling `Weak` (created with `Weak::new()`) points to.
+pub(crate) const DANGLING_WEAK_ADDR: usize = usize::MAX;
+
+/// Get pointer to allocation which a `Rc<T>`, `Arc<T>` or `Weak<T>` points to.
+///
+/// All these types are a single pointer to the allocation.
+#[inline]
+pub(crate) fn alloc_ptr<P, T>(ptr: &P) -> *const RcBox<T> {
+	// Sanity check that pointer type is just a pointer (evaluated at compile time)
+	#[allow(clippy::let_unit_value)]
+	let _ = SizeCheck::<P, PTR_SIZE>::ASSERT_SIZE_IS;
+
+	unsafe { *(ptr as *const P as *const *const RcBox<T>) }
+}
+
+/// Serialize allocation which `ptr` points to, unless already serialized.
+///
+/// `ptr` is an `Rc<T>`, `Arc<T>` or `Weak<T>`.
+/// `is_alive` is `false` if allocation's value has been dropped.
+/// Such an allocation is still written to output, but only its reference
+/// counts. Its value is written as zeros.
+#[inline]
+fn serialize_shared<P, T, S>(ptr: &P, kind: RefKind, is_alive: bool, serializer: &mut S)
+where
+	T: Serialize<S>,
+	S: Serializer,
+{
+	let alloc = alloc_ptr::<P, T>(ptr);
+	debug_assert!(alloc as usize != DANGLING_WEAK_ADDR);
+
+	let ptr_addr = S::Addr::from_ref(ptr);
+	if is_alive {
+		// Safe because caller holds a strong reference, so value is not dropped
+		let alloc_ref = unsafe { &*alloc };
+		serializer.push_and_process_shared(alloc as usize, alloc_ref, ptr_addr, kind, |serializer| {
+			alloc_ref.value.serialize_data(serializer)
+		});
+	} else {
+		// Value has been dropped, so don't create a reference to it.
+		// Allocation itself still exists, as `ptr` is a `Weak`, so can read the
+		// reference counts.
+		let dead = unsafe {
+			RcBox {
+				strong: ptr::addr_of!((*alloc).strong).read(),
+				weak: ptr::addr_of!((*alloc).weak).read(),
+				value: MaybeUninit::<T>::zeroed(),
+			}
+		};
+		serializer.push_and_process_shared(alloc as usize, &dead, ptr_addr, kind, |_| {});
+	}
+}
+
+/// Serialize allocation in portable format, unless already serialized,
+/// and write offset to it at `pos`.
+///
+/// Allocation's reference counts are not written, only its value.
+///
+/// If `value` is `None` (dangling `Weak`, or allocation's value has been
+/// dropped), offset is written as all 1s.
+#[inline]
+fn serialize_shared_portable<T, S>(
+	addr: usize,
+	value: Option<&T>,
+	kind: RefKind,
+	serializer: &mut S,
+	pos: usize,
+) where
+	T: Serialize<S>,
+	S: Serializer,
+{
+	let value = match value {
+		Some(value) => value,
+		None => {
+			serializer.write_portable(pos, &[0xff; 8][..S::PORTABLE_OFFSET_SIZE]);
+			return;
+		}
+	};
+
+	let target_pos = match expect_shared_allocs(serializer).add_ref(addr, kind) {
+		Some(target_pos) => target_pos,
+		None => {
+			// Record position before serializing value, so any pointers back to it
+			// encountered while serializing it are deduplicated
+			let target_pos = portable::reserve(serializer, T::PORTABLE_SIZE);
+			expect_shared_allocs(serializer).insert(addr, target_pos, kind);
+			value.serialize_portable(serializer, target_pos);
+			target_pos
+		}
+	};
+	portable::write_offset(serializer, pos, target_pos);
+}
+
+impl<T, S> Serialize<S> for Rc<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<Rc<T>>("Rc"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		serialize_shared::<_, T, S>(self, RefKind::Strong, true, serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let addr = alloc_ptr::<_, T>(self) as usize;
+		serialize_shared_portable(addr, Some(&**self), RefKind::Strong, serializer, pos);
+	}
+}
+
+impl<T, S> Serialize<S> for rc::Weak<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<rc::Weak<T>>("rc::Weak"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Dangling `Weak`s don't point to an allocation. Nothing to do.
+		if alloc_ptr::<_, T>(self) as usize == DANGLING_WEAK_ADDR {
+			return;
+		}
+
+		// Hold a strong reference while serializing, so value is not dropped
+		let strong = self.upgrade();
+		serialize_shared::<_, T, S>(self, RefKind::Weak, strong.is_some(), serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let addr = alloc_ptr::<_, T>(self) as usize;
+		let strong = self.upgrade();
+		serialize_shared_portable(addr, strong.as_deref(), RefKind::Weak, serializer, pos);
+	}
+}
+
+impl<T, S> Serialize<S> for Arc<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<Arc<T>>("Arc"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		serialize_shared::<_, T, S>(self, RefKind::Strong, true, serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let addr = alloc_ptr::<_, T>(self) as usize;
+		serialize_shared_portable(addr, Some(&**self), RefKind::Strong, serializer, pos);
+	}
+}
+
+impl<T, S> Serialize<S> for sync::Weak<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<sync::Weak<T>>("sync::Weak"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Dangling `Weak`s don't point to an allocation. Nothing to do.
+		if alloc_ptr::<_, T>(self) as usize == DANGLING_WEAK_ADDR {
+			return;
+		}
+
+		// Hold a strong reference while serializing, so value is not dropped
+		let strong = self.upgrade();
+		serialize_shared::<_, T, S>(self, RefKind::Weak, strong.is_some(), serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let addr = alloc_ptr::<_, T>(self) as usize;
+		let strong = self.upgrade();
+		serialize_shared_portable(addr, strong.as_deref(), RefKind::Weak, serializer, pos);
+	}
+}
@@ -0,0 +1,161 @@
+use std::mem::{self, ManuallyDrop};
+
+use smallvec::{Array, SmallVec};
+
+use crate::{fingerprint, portable, pos::Addr, Serialize, Serializer};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+// `SmallVec<A>` is defined as `SmallVec { capacity: usize, data:
+// SmallVecData<A> }`. `smallvec`'s `union` feature (which `smallvec` feature of
+// this crate enables) makes `SmallVecData` a union of the inline array and a
+// heap `(ptr, len)`, with no tag. If `capacity` is no larger than inline
+// capacity, contents are inline, and `capacity` is their length. Otherwise
+// contents are on heap.
+//
+// Inline contents are within the `SmallVec` itself, so only need their own
+// data serialized. Heap contents are serialized same as a `Vec<T>`'s.
+impl<A, S> Serialize<S> for SmallVec<A>
+where
+	A: Array,
+	A::Item: Serialize<S>,
+	S: Serializer,
+{
+	// Only layout of `A::Item` included, not its fingerprint, to support recursive
+	// types. Inline capacity is included in layout of `SmallVec<A>`.
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<SmallVec<A>>("SmallVec"),
+		fingerprint::of_layout::<A::Item>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// No need to do anything if `SmallVec` contains ZSTs. They are always inline.
+		if mem::size_of::<A::Item>() == 0 {
+			return;
+		}
+
+		if !self.spilled() {
+			for value in self.iter() {
+				value.serialize_data(serializer);
+			}
+			return;
+		}
+
+		let offsets = SmallVecOffsets::of::<A>();
+
+		// No need to write contents if vec is empty.
+		// Replace with an empty inline `SmallVec`, as pointer may be invalid in output.
+		if self.is_empty() {
+			serializer.overwrite_with(|serializer| unsafe {
+				serializer.overwrite(S::Addr::from_ref(self), &SmallVec::<A>::new());
+			});
+			return;
+		}
+
+		// Overwrite `capacity = len`, if it's not already.
+		// Capacity must remain above inline capacity, or `SmallVec` would read its
+		// contents as inline.
+		serializer.overwrite_with(|serializer| {
+			let capacity = self.len().max(A::size() + 1);
+			if self.capacity() != capacity {
+				let cap_addr = S::Addr::from_ref_offset(self, offsets.capacity);
+				unsafe { serializer.overwrite(cap_addr, &capacity) };
+			}
+		});
+
+		// Write heap contents
+		let ptr_addr = S::Addr::from_ref_offset(self, offsets.ptr);
+		serializer.push_and_process_slice(self.as_slice(), ptr_addr, |serializer| {
+			for value in self.iter() {
+				value.serialize_data(serializer);
+			}
+		});
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty vec is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_slice(serializer, self.as_slice());
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
+
+/// Offsets of fields in `SmallVec<A>`.
+///
+/// Rust may reorder `SmallVec`'s fields, and the heap `(ptr, len)` tuple, so we
+/// deduce them from a `SmallVec` with known contents, similar to
+/// `bigint_data_offset`. Everything here can be statically evaluated.
+pub(crate) struct SmallVecOffsets {
+	/// Offset of `capacity` field
+	pub capacity: usize,
+	/// Offset of inline contents
+	pub inline: usize,
+	/// Offset of heap pointer
+	pub ptr: usize,
+	/// Offset of heap length
+	pub len: usize,
+}
+
+impl SmallVecOffsets {
+	#[inline]
+	pub(crate) fn of<A: Array>() -> Self {
+		// Inline contents are at start of `data` union.
+		// Empty `SmallVec` does not allocate.
+		let empty = SmallVec::<A>::new();
+		let inline = empty.as_ptr() as usize - &empty as *const SmallVec<A> as usize;
+
+		// `SmallVec` has 2 fields, `capacity` and `data`. Whichever is not first
+		// is after the other. `data` has same alignment as `SmallVec`, and its size
+		// is a multiple of its alignment, so if `data` is first, `capacity` follows
+		// it directly.
+		let capacity = if inline == 0 {
+			mem::size_of::<SmallVec<A>>() - mem::align_of::<SmallVec<A>>()
+		} else {
+			0
+		};
+
+		// `SmallVec`s of ZSTs are never on heap
+		if mem::size_of::<A::Item>() == 0 {
+			return Self {
+				capacity,
+				inline,
+				ptr: inline,
+				len: inline + PTR_SIZE,
+			};
+		}
+
+		// Create a `SmallVec` with heap pointer, length and capacity all different.
+		// This `SmallVec` must NOT be dropped or accessed, as its pointer is
+		// dangling, and its length and capacity are invalid. We only read its bytes.
+		const LEN: usize = usize::MAX - 1;
+		const CAPACITY: usize = usize::MAX;
+		let dangle = mem::align_of::<A::Item>();
+		let heap = ManuallyDrop::new(unsafe {
+			SmallVec::<A>::from_raw_parts(dangle as *mut A::Item, LEN, CAPACITY)
+		});
+		let heap_ptr = &*heap as *const SmallVec<A> as *const u8;
+		let read = |offset: usize| unsafe { *(heap_ptr.add(offset) as *const usize) };
+
+		assert!(read(capacity) == CAPACITY);
+		let (ptr, len) = if read(inline) == dangle {
+			(inline, inline + PTR_SIZE)
+		} else {
+			(inline + PTR_SIZE, inline)
+		};
+		assert!(read(ptr) == dangle && read(len) == LEN);
+
+		Self {
+			capacity,
+			inline,
+			ptr,
+			len,
+		}
+	}
+}
@@ -0,0 +1,125 @@
+use std::mem;
+
+use smol_str::SmolStr;
+
+use super::{
+	ptrs::{BOXED_SLICE_LEN_OFFSET, BOXED_SLICE_PTR_OFFSET},
+	shared::RcBox,
+};
+use crate::{fingerprint, portable, pos::Addr, Serialize, Serializer};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+// `SmolStr` is defined as:
+//
+// ```
+// enum Repr {
+//     Heap(Arc<str>),
+//     Inline { len: InlineSize, buf: [u8; INLINE_CAP] },
+//     Substring { newlines: usize, spaces: usize },
+// }
+// ```
+//
+// `InlineSize` is a fieldless enum with values 0 to `INLINE_CAP`. Rust uses
+// values after that as the tag for the other variants, numbered by variant
+// index: `Heap` is `INLINE_CAP + 1`, `Substring` is `INLINE_CAP + 3`.
+// `Substring` is a slice of a static string of newlines followed by spaces.
+//
+// Only `Heap` contains a pointer. `Inline` and `Substring` need no further
+// serialization.
+//
+// This does rely on knowledge of `smol_str`'s internal implementation.
+// Dependency is on version 0.1.x only, as layout changes in 0.2.
+
+/// Size of `SmolStr`
+pub(crate) const SIZE: usize = 24;
+/// Maximum length of an inline string
+pub(crate) const INLINE_CAP: usize = SIZE - 1;
+/// Maximum number of newlines in a `Substring`
+pub(crate) const N_NEWLINES: usize = 32;
+/// Maximum number of spaces in a `Substring`
+pub(crate) const N_SPACES: usize = 128;
+/// Tag of `Heap` variant
+pub(crate) const HEAP_TAG: u8 = INLINE_CAP as u8 + 1;
+/// Tag of `Substring` variant
+pub(crate) const SUBSTRING_TAG: u8 = INLINE_CAP as u8 + 3;
+
+/// Offset of tag, which is `len` field of `Inline` variant.
+/// Either first or last byte.
+pub(crate) const TAG_OFFSET: usize = {
+	// Will fail to compile if `SmolStr` is not `SIZE` bytes
+	let empty: [u8; SIZE] = unsafe { mem::transmute(SmolStr::new_inline("")) };
+	let full: [u8; SIZE] = unsafe { mem::transmute(SmolStr::new_inline("aaaaaaaaaaaaaaaaaaaaaaa")) };
+	if empty[0] == 0 && full[0] == INLINE_CAP as u8 {
+		0
+	} else if empty[SIZE - 1] == 0 && full[SIZE - 1] == INLINE_CAP as u8 {
+		SIZE - 1
+	} else {
+		panic!("Could not determine offset of SmolStr's tag");
+	}
+};
+
+/// Offset of `buf` field of `Inline` variant
+pub(crate) const INLINE_OFFSET: usize = if TAG_OFFSET == 0 { 1 } else { 0 };
+
+/// Offset of `Heap` and `Substring` variants' fields.
+/// Both are 2 x `usize`, and can only be placed where they don't overlap tag.
+pub(crate) const FIELDS_OFFSET: usize = if TAG_OFFSET == 0 { PTR_SIZE } else { 0 };
+
+/// Offset of pointer of `Heap` variant's `Arc<str>`
+pub(crate) const HEAP_PTR_OFFSET: usize = FIELDS_OFFSET + BOXED_SLICE_PTR_OFFSET;
+/// Offset of length of `Heap` variant's `Arc<str>`
+pub(crate) const HEAP_LEN_OFFSET: usize = FIELDS_OFFSET + BOXED_SLICE_LEN_OFFSET;
+
+/// Heap strings are written to output as a new allocation - reference counts,
+/// followed by the string - and `Arc`'s pointer points to it. Clones of a
+/// `SmolStr` which share the allocation each get their own copy.
+impl<S> Serialize<S> for SmolStr
+where S: Serializer
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<SmolStr>("SmolStr");
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		if !self.is_heap_allocated() {
+			return;
+		}
+
+		let alloc_addr =
+			unsafe { *((self as *const SmolStr as *const u8).add(HEAP_PTR_OFFSET) as *const usize) };
+		assert!(
+			alloc_addr + mem::size_of::<RcBox<[u8; 0]>>() == self.as_str().as_ptr() as usize,
+			"`SmolStr` has unexpected layout"
+		);
+
+		let ptr_addr = S::Addr::from_ref_offset(self, HEAP_PTR_OFFSET);
+		let rc_box = RcBox {
+			strong: 1,
+			weak: 1,
+			value: [0u8; 0],
+		};
+		let pos = serializer.push(&rc_box, ptr_addr);
+		let str_pos = serializer.push_raw_bytes(self.as_bytes());
+
+		// Storage pads values to its value alignment. If that's larger than
+		// reference counts, string is not directly after them.
+		assert!(
+			str_pos == pos + mem::size_of::<RcBox<[u8; 0]>>(),
+			"Cannot serialize `SmolStr` with a storage with value alignment greater than {}",
+			mem::size_of::<RcBox<[u8; 0]>>()
+		);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty string is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_bytes(serializer, self.as_bytes());
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
@@ -0,0 +1,169 @@
+use std::{
+	mem::{self, MaybeUninit},
+	ptr,
+};
+
+use string_cache::{Atom, StaticAtomSet};
+
+use super::ptrs::{
+	SameSizeAndAlignment, SizeCheck, BOXED_SLICE_LEN_OFFSET, BOXED_SLICE_PTR_OFFSET,
+};
+use crate::{fingerprint, portable, pos::Addr, Serialize, Serializer};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+// `Atom` is a `NonZeroU64`. Low 2 bits are the tag:
+//
+// * `DYNAMIC_TAG`: Pointer to an `Entry` in global interner.
+// * `INLINE_TAG`: String of up to 7 bytes stored in upper 7 bytes. Length is in
+//   upper nybble of lowest byte.
+// * `STATIC_TAG`: Index into `Static`'s atom set in upper 32 bits.
+//
+// Only dynamic atoms contain a pointer. Interner is not part of output, so a
+// new `Entry` is written to output, followed by the string, and atom points to
+// it.
+//
+// This does rely on knowledge of `string_cache`'s internal implementation.
+// Dependency is on version 0.8.x only.
+
+/// Tag of an atom which points to an `Entry`
+pub(crate) const DYNAMIC_TAG: u64 = 0b00;
+/// Tag of an atom with its string stored inline
+pub(crate) const INLINE_TAG: u64 = 0b01;
+/// Tag of an atom which is an index into static atom set
+pub(crate) const STATIC_TAG: u64 = 0b10;
+/// Mask for tag bits
+pub(crate) const TAG_MASK: u64 = 0b11;
+/// Maximum length of an inline string
+pub(crate) const MAX_INLINE_LEN: usize = 7;
+/// Shift of length of an inline string
+pub(crate) const INLINE_LEN_SHIFT: u64 = 4;
+/// Shift of index of a static atom
+pub(crate) const STATIC_SHIFT: u64 = 32;
+
+/// Same fields as `string_cache`'s private `Entry` type, so has the same
+/// layout. Only used to get offsets of fields.
+///
+/// `Entry`'s `ref_count` is an `AtomicIsize`, which has same in-memory
+/// representation as `isize`. Using `isize` allows offsets to be calculated in
+/// const context.
+#[allow(dead_code)]
+struct EntryLayout {
+	string: Box<str>,
+	hash: u32,
+	ref_count: isize,
+	next_in_bucket: Option<Box<EntryLayout>>,
+}
+
+/// Get offset of a field of `EntryLayout`. Evaluated at compile time.
+macro_rules! entry_offset {
+	($field:ident) => {{
+		let entry = MaybeUninit::<EntryLayout>::uninit();
+		let base = entry.as_ptr();
+		unsafe { (ptr::addr_of!((*base).$field) as *const u8).offset_from(base as *const u8) as usize }
+	}};
+}
+
+/// Size of an `Entry`, in words
+pub(crate) const ENTRY_WORDS: usize = mem::size_of::<EntryLayout>() / PTR_SIZE;
+/// Offset of pointer of `Entry`'s `string` field
+pub(crate) const ENTRY_STR_PTR_OFFSET: usize = entry_offset!(string) + BOXED_SLICE_PTR_OFFSET;
+/// Offset of length of `Entry`'s `string` field
+pub(crate) const ENTRY_STR_LEN_OFFSET: usize = entry_offset!(string) + BOXED_SLICE_LEN_OFFSET;
+/// Offset of `Entry`'s `hash` field
+pub(crate) const ENTRY_HASH_OFFSET: usize = entry_offset!(hash);
+/// Offset of `Entry`'s `ref_count` field
+pub(crate) const ENTRY_REF_COUNT_OFFSET: usize = entry_offset!(ref_count);
+
+/// An `Entry`, as written to output.
+///
+/// Reference count is 1, and `next_in_bucket` is `None`. An `Entry` in output
+/// is not in the interner, so is not in a bucket.
+#[repr(C)]
+pub(crate) struct EntryBytes {
+	pub words: [usize; ENTRY_WORDS],
+}
+
+impl EntryBytes {
+	#[inline]
+	fn new(str_ptr: usize, len: usize, hash: u32) -> Self {
+		// Sanity check that `Entry` is a whole number of words, and aligned to a word
+		// (evaluated at compile time)
+		#[allow(clippy::let_unit_value)]
+		let _ = SameSizeAndAlignment::<EntryLayout, EntryBytes>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
+
+		let mut entry = Self {
+			words: [0; ENTRY_WORDS],
+		};
+		let base = entry.words.as_mut_ptr() as *mut u8;
+		unsafe {
+			ptr::write_unaligned(base.add(ENTRY_STR_PTR_OFFSET) as *mut usize, str_ptr);
+			ptr::write_unaligned(base.add(ENTRY_STR_LEN_OFFSET) as *mut usize, len);
+			ptr::write_unaligned(base.add(ENTRY_HASH_OFFSET) as *mut u32, hash);
+			ptr::write_unaligned(base.add(ENTRY_REF_COUNT_OFFSET) as *mut isize, 1);
+		}
+		entry
+	}
+}
+
+/// Dynamic atoms are written to output as a new `Entry`, followed by the
+/// string, and atom points to the `Entry`.
+///
+/// With `CompleteSerializer`, dynamic atoms in output are not interned, so
+/// compare unequal (`==`) to atoms for the same string outside output.
+/// Comparing their strings works as usual. `PureCopyDeserializer` re-interns
+/// them.
+impl<Static, S> Serialize<S> for Atom<Static>
+where
+	Static: StaticAtomSet,
+	S: Serializer,
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<Atom<Static>>("Atom");
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Sanity check that `Atom` is a `u64` (evaluated at compile time)
+		#[allow(clippy::let_unit_value)]
+		let _ = SizeCheck::<Atom<Static>, 8>::ASSERT_SIZE_IS;
+
+		let data = self.unsafe_data();
+		if data & TAG_MASK != DYNAMIC_TAG {
+			return;
+		}
+
+		// Check `Entry` has expected layout. `string` and `hash` are immutable,
+		// so reading them doesn't race with other threads.
+		let string: &str = self;
+		let entry = data as usize as *const u8;
+		unsafe {
+			assert!(
+				*(entry.add(ENTRY_STR_PTR_OFFSET) as *const usize) == string.as_ptr() as usize
+					&& *(entry.add(ENTRY_STR_LEN_OFFSET) as *const usize) == string.len()
+					&& *(entry.add(ENTRY_HASH_OFFSET) as *const u32) == self.get_hash(),
+				"`Atom` has unexpected layout"
+			);
+		}
+
+		// Write a new `Entry`, followed by string. `EntryBytes` is a local, but
+		// address of its `string` field maps to its position in output.
+		let entry = EntryBytes::new(string.as_ptr() as usize, string.len(), self.get_hash());
+		serializer.push_and_process(&entry, S::Addr::from_ref(self), |serializer| {
+			let str_ptr_addr = S::Addr::from_ref_offset(&entry, ENTRY_STR_PTR_OFFSET);
+			serializer.push_slice(string.as_bytes(), str_ptr_addr);
+		});
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty string is offset 0, length 0. Space is already zeroed.
+		let string: &str = self;
+		if string.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_bytes(serializer, string.as_bytes());
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, string.len());
+	}
+}
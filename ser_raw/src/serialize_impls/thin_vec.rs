@@ -0,0 +1,122 @@
+use std::mem;
+
+use thin_vec::ThinVec;
+
+use super::ptrs::SizeCheck;
+use crate::{fingerprint, portable, pos::Addr, Serialize, Serializer};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+/// Header at start of a `ThinVec<T>`'s allocation, followed by its contents.
+///
+/// `thin-vec` defines its header as `Header { len: usize, cap: usize }`, with
+/// contents following it at an offset which is aligned for both. This type
+/// includes that padding, so contents follow directly after it.
+///
+/// `thin-vec`'s `gecko-ffi` feature changes the header to 2 x `u32`. That's
+/// not supported. `ThinVec::serialize_data` panics if it's enabled.
+#[repr(C)]
+pub(crate) struct ThinVecHeader<T> {
+	pub len: usize,
+	pub cap: usize,
+	_contents: [T; 0],
+}
+
+impl<T> ThinVecHeader<T> {
+	#[inline]
+	fn new(len: usize) -> Self {
+		Self {
+			len,
+			cap: len,
+			_contents: [],
+		}
+	}
+}
+
+/// Get pointer to header of a `ThinVec<T>`.
+///
+/// `ThinVec<T>` is a single pointer to its header.
+#[inline]
+pub(crate) fn header_ptr<T>(vec: &ThinVec<T>) -> *const ThinVecHeader<T> {
+	// Sanity check that `ThinVec<T>` is just a pointer (evaluated at compile time)
+	#[allow(clippy::let_unit_value)]
+	let _ = SizeCheck::<ThinVec<T>, PTR_SIZE>::ASSERT_SIZE_IS;
+
+	unsafe { *(vec as *const ThinVec<T> as *const *const ThinVecHeader<T>) }
+}
+
+// Header and contents are written to output together, and pointer points to
+// the header.
+//
+// An empty `ThinVec` may point to a static header shared by all empty
+// `ThinVec`s, which is outside output, so a new header is written for it.
+impl<T, S> Serialize<S> for ThinVec<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	// Only layout of `T` included, not its fingerprint, to support recursive types
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<ThinVec<T>>("ThinVec"),
+		fingerprint::of_layout::<T>(),
+	);
+
+	// Offset + length
+	const PORTABLE_SIZE: usize = S::PORTABLE_OFFSET_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		let ptr_addr = S::Addr::from_ref(self);
+
+		if self.is_empty() {
+			serializer.push(&ThinVecHeader::<T>::new(0), ptr_addr);
+			return;
+		}
+
+		let header_ptr = header_ptr(self);
+		assert!(
+			self.as_ptr() as usize - header_ptr as usize == mem::size_of::<ThinVecHeader<T>>(),
+			"`ThinVec` header has unexpected layout. `thin-vec`'s `gecko-ffi` feature is not supported."
+		);
+
+		// Write header from the allocation itself, so addresses of contents map to
+		// their positions in output
+		let header = unsafe { &*header_ptr };
+		let mut contents_pos = 0;
+		let header_pos = serializer.push_and_process(header, ptr_addr, |serializer| {
+			// Overwrite `cap = len`, if it's not already
+			serializer.overwrite_with(|serializer| {
+				if header.cap != header.len {
+					unsafe { serializer.overwrite(S::Addr::from_ref(&header.cap), &header.len) };
+				}
+			});
+
+			contents_pos = serializer.push_raw_slice(self.as_slice());
+
+			// No need to serialize contents' data if they're ZSTs
+			if mem::size_of::<T>() > 0 {
+				for value in self.iter() {
+					value.serialize_data(serializer);
+				}
+			}
+		});
+
+		// Storage pads values to its value alignment. If that's larger than header,
+		// contents are not directly after it.
+		assert!(
+			mem::size_of::<T>() == 0 || contents_pos == header_pos + mem::size_of::<ThinVecHeader<T>>(),
+			"Cannot serialize `ThinVec` with a storage with value alignment greater than {}",
+			mem::size_of::<ThinVecHeader<T>>()
+		);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		// Empty vec is offset 0, length 0. Space is already zeroed.
+		if self.is_empty() {
+			return;
+		}
+
+		let target_pos = portable::push_slice(serializer, self.as_slice());
+		portable::write_offset(serializer, pos, target_pos);
+		portable::write_offset(serializer, pos + S::PORTABLE_OFFSET_SIZE, self.len());
+	}
+}
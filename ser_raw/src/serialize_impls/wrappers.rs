@@ -0,0 +1,317 @@
+use std::{
+	cell::{Cell, RefCell},
+	cmp::Reverse,
+	marker::PhantomData,
+	mem::{ManuallyDrop, MaybeUninit},
+	num::Wrapping,
+	ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
+	ptr,
+	sync::{LockResult, Mutex, PoisonError, RwLock, TryLockError, TryLockResult},
+};
+
+use super::ptrs::SameSizeAndAlignment;
+use crate::{
+	fingerprint, header::SerializerKind, pos::Addr, Serialize, SerializeError, Serializer,
+};
+
+impl<T, S> Serialize<S> for PhantomData<T>
+where
+	S: Serializer,
+	T: ?Sized,
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<PhantomData<T>>("PhantomData");
+
+	const PORTABLE_SIZE: usize = 0;
+
+	#[inline(always)]
+	fn serialize_data(&self, _serializer: &mut S) {}
+
+	#[inline(always)]
+	fn serialize_portable(&self, _serializer: &mut S, _pos: usize) {}
+}
+
+impl<S> Serialize<S> for RangeFull
+where S: Serializer
+{
+	const FINGERPRINT: u64 = fingerprint::of_named::<RangeFull>("RangeFull");
+
+	const PORTABLE_SIZE: usize = 0;
+
+	#[inline(always)]
+	fn serialize_data(&self, _serializer: &mut S) {}
+
+	#[inline(always)]
+	fn serialize_portable(&self, _serializer: &mut S, _pos: usize) {}
+}
+
+/// Implement `Serialize` for a wrapper type which contains a single value.
+/// `$get` is an expression getting a reference to the wrapped value from
+/// `$value`.
+/// Portable representation is same as the wrapped value's.
+macro_rules! impl_wrapper {
+	($ty:ident, |$value:ident| $get:expr) => {
+		impl<T, S> Serialize<S> for $ty<T>
+		where
+			S: Serializer,
+			T: Serialize<S>,
+		{
+			const FINGERPRINT: u64 = fingerprint::hash_u64(
+				fingerprint::of_named::<$ty<T>>(stringify!($ty)),
+				T::FINGERPRINT,
+			);
+
+			const PORTABLE_SIZE: usize = T::PORTABLE_SIZE;
+
+			#[inline]
+			fn serialize_data(&self, serializer: &mut S) {
+				let $value = self;
+				let value: &T = $get;
+				value.serialize_data(serializer);
+			}
+
+			#[inline]
+			fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+				let $value = self;
+				let value: &T = $get;
+				value.serialize_portable(serializer, pos);
+			}
+		}
+	};
+}
+
+impl_wrapper!(ManuallyDrop, |value| &**value);
+impl_wrapper!(Wrapping, |value| &value.0);
+impl_wrapper!(Reverse, |value| &value.0);
+// `Cell` is not `Sync`, and serialization never mutates values, so contents
+// of the `Cell` cannot change while the reference is held
+impl_wrapper!(Cell, |value| unsafe { &*value.as_ptr() });
+
+impl<T, S> Serialize<S> for RefCell<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<RefCell<T>>("RefCell"),
+		T::FINGERPRINT,
+	);
+
+	const PORTABLE_SIZE: usize = T::PORTABLE_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Borrow flag is copied to output as is, so must not be borrowed already
+		if self.try_borrow_mut().is_err() {
+			SerializeError::Borrowed.fail();
+		}
+		let value = self.borrow();
+		value.serialize_data(serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let value = self
+			.try_borrow()
+			.unwrap_or_else(|_| SerializeError::Borrowed.fail());
+		value.serialize_portable(serializer, pos);
+	}
+}
+
+impl<T, S> Serialize<S> for Mutex<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	const FINGERPRINT: u64 =
+		fingerprint::hash_u64(fingerprint::of_named::<Mutex<T>>("Mutex"), T::FINGERPRINT);
+
+	const PORTABLE_SIZE: usize = T::PORTABLE_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Static assertion (evaluated at compile time)
+		#[allow(clippy::let_unit_value)]
+		let _ = NativeLocks::<S>::ASSERT_SUPPORTED;
+
+		// Must not be locked already. Lock and value were copied to output before
+		// lock was taken, so overwrite them with an unlocked copy, before applying
+		// any corrections to value.
+		let guard = lock_result(self.try_lock(), "Mutex");
+		overwrite_lock(self, &*guard, Mutex::new, Mutex::get_mut, serializer);
+		guard.serialize_data(serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let guard = lock_result(self.try_lock(), "Mutex");
+		guard.serialize_portable(serializer, pos);
+	}
+}
+
+impl<T, S> Serialize<S> for RwLock<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	const FINGERPRINT: u64 =
+		fingerprint::hash_u64(fingerprint::of_named::<RwLock<T>>("RwLock"), T::FINGERPRINT);
+
+	const PORTABLE_SIZE: usize = T::PORTABLE_SIZE;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		// Static assertion (evaluated at compile time)
+		#[allow(clippy::let_unit_value)]
+		let _ = NativeLocks::<S>::ASSERT_SUPPORTED;
+
+		// Must not be write-locked already. Lock and value were copied to output
+		// before lock was taken, so overwrite them with an unlocked copy, before
+		// applying any corrections to value.
+		let guard = lock_result(self.try_read(), "RwLock");
+		overwrite_lock(self, &*guard, RwLock::new, RwLock::get_mut, serializer);
+		guard.serialize_data(serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		let guard = lock_result(self.try_read(), "RwLock");
+		guard.serialize_portable(serializer, pos);
+	}
+}
+
+/// Get guard from result of trying to acquire a lock.
+///
+/// Poisoning is ignored. If lock is already locked, fails with
+/// [`SerializeError::Locked`], rather than blocking.
+#[inline]
+fn lock_result<G>(result: TryLockResult<G>, lock: &'static str) -> G {
+	match result {
+		Ok(guard) => guard,
+		Err(TryLockError::Poisoned(err)) => err.into_inner(),
+		Err(TryLockError::WouldBlock) => SerializeError::Locked { lock }.fail(),
+	}
+}
+
+/// Overwrite copy of a `Mutex` or `RwLock` in output with a new unlocked lock,
+/// containing a copy of its value.
+///
+/// `value` is the lock's value, borrowed through a guard, so cannot be mutated
+/// by other threads while it's copied. `new` creates a lock, and `get_mut` gets
+/// a lock's value.
+#[inline]
+fn overwrite_lock<L, C, T, S>(
+	lock: &L,
+	value: &T,
+	new: fn(MaybeUninit<T>) -> C,
+	get_mut: fn(&mut C) -> LockResult<&mut MaybeUninit<T>>,
+	serializer: &mut S,
+) where
+	S: Serializer,
+{
+	// `L` is `Mutex<T>` / `RwLock<T>`, and `C` is same with `MaybeUninit<T>`.
+	// Check they have same layout (evaluated at compile time), and value is at
+	// same offset in both.
+	#[allow(clippy::let_unit_value)]
+	let _ = SameSizeAndAlignment::<L, C>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
+
+	// Copy value bitwise. `MaybeUninit` ensures it's not dropped.
+	let mut copy = new(MaybeUninit::uninit());
+	let copy_value = get_mut(&mut copy)
+		.unwrap_or_else(PoisonError::into_inner)
+		.as_mut_ptr();
+	unsafe { ptr::copy_nonoverlapping(value, copy_value, 1) };
+
+	let offset = value as *const T as usize - lock as *const L as usize;
+	let copy_offset = copy_value as usize - &copy as *const C as usize;
+	assert!(offset == copy_offset, "Lock has unexpected layout");
+
+	// `lock` is within a value already serialized
+	unsafe { serializer.overwrite(S::Addr::from_ref(lock), &copy) };
+}
+
+/// Type for static assertion that `Mutex` and `RwLock` can be serialized with
+/// serializer `S`.
+///
+/// Native serializers copy locks, and overwrite the copy with an unlocked lock
+/// once the lock has been acquired. So they must be able to overwrite values
+/// in output, which pure copy serializers cannot.
+///
+/// On platforms where `std` implements locks with futexes, lock state is a few
+/// integers, and a new unlocked lock is written to output. On other platforms,
+/// locks contain a pointer to a lazily-allocated OS lock, which cannot be
+/// copied, so only portable serializers can serialize them.
+struct NativeLocks<S> {
+	_marker: PhantomData<S>,
+}
+
+impl<S: Serializer> NativeLocks<S> {
+	const ASSERT_SUPPORTED: () = {
+		assert!(
+			!matches!(S::KIND, SerializerKind::PureCopy),
+			"`Mutex` and `RwLock` cannot be serialized with a pure copy serializer"
+		);
+		assert!(
+			COPYABLE_LOCKS || matches!(S::KIND, SerializerKind::Portable),
+			"`Mutex` and `RwLock` can only be serialized with a portable serializer on this platform"
+		);
+	};
+}
+
+/// Whether `std`'s locks on this platform are futexes, which can be copied
+const COPYABLE_LOCKS: bool = cfg!(any(
+	target_os = "linux",
+	target_os = "android",
+	target_os = "freebsd",
+	target_os = "openbsd",
+	target_os = "dragonfly",
+	target_os = "fuchsia",
+));
+
+impl<T, S> Serialize<S> for Range<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	const FINGERPRINT: u64 =
+		fingerprint::hash_u64(fingerprint::of_named::<Range<T>>("Range"), T::FINGERPRINT);
+
+	// Start + end
+	const PORTABLE_SIZE: usize = T::PORTABLE_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		self.start.serialize_data(serializer);
+		self.end.serialize_data(serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		self.start.serialize_portable(serializer, pos);
+		self
+			.end
+			.serialize_portable(serializer, pos + T::PORTABLE_SIZE);
+	}
+}
+
+impl<T, S> Serialize<S> for RangeInclusive<T>
+where
+	S: Serializer,
+	T: Serialize<S>,
+{
+	const FINGERPRINT: u64 = fingerprint::hash_u64(
+		fingerprint::of_named::<RangeInclusive<T>>("RangeInclusive"),
+		T::FINGERPRINT,
+	);
+
+	// Start + end. Whether range has been exhausted by iterating it is not
+	// included in portable output.
+	const PORTABLE_SIZE: usize = T::PORTABLE_SIZE * 2;
+
+	fn serialize_data(&self, serializer: &mut S) {
+		self.start().serialize_data(serializer);
+		self.end().serialize_data(serializer);
+	}
+
+	fn serialize_portable(&self, serializer: &mut S, pos: usize) {
+		self.start().serialize_portable(serializer, pos);
+		self
+			.end()
+			.serialize_portable(serializer, pos + T::PORTABLE_SIZE);
+	}
+}
+
+impl_wrapper!(RangeFrom, |value| &value.start);
+impl_wrapper!(RangeTo, |value| &value.end);
+impl_wrapper!(RangeToInclusive, |value| &value.end);
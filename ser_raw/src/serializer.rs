@@ -1,17 +1,26 @@
-use std::{borrow::BorrowMut, slice};
+use std::{borrow::BorrowMut, mem, slice};
 
-use crate::{pos::Addr, storage::Storage, Serialize};
+use crate::{
+	fallible::{self, SerializeError},
+	header::{Header, SerializerKind},
+	pos::{Addr, Checkpoint, PosMapping, RefKind, SharedAllocs},
+	storage::{RandomAccessStorage, Storage},
+	Serialize,
+};
 
 /// Serializers implement this trait.
 ///
 /// # With derive macro
 ///
-///	The simplest way to build a custom [`Serializer`] is with the derive macro.
+/// The simplest way to build a custom [`Serializer`] is with the derive macro.
 ///
 /// * `#[derive(Serializer)]`
 /// * `#[ser_type(...)]` with the type of serializer you're building.
 /// * Add required fields and tag them e.g. `#[ser_storage]` (see examples
 ///   below).
+/// * Optionally add a [`SharedAllocs`] field tagged `#[ser_shared]`, to support
+///   serializing shared pointers (`Rc`, `Arc`). All serializer types support
+///   this.
 ///
 /// ## Pure copy serializer
 ///
@@ -19,9 +28,9 @@ use crate::{pos::Addr, storage::Storage, Serialize};
 ///
 /// ```
 /// use ser_raw::{
-/// 	storage::{AlignedVec, Storage},
-/// 	util::aligned_max_capacity,
-/// 	Serializer,
+///     storage::{AlignedVec, Storage},
+///     util::aligned_max_capacity,
+///     Serializer,
 /// };
 ///
 /// const MAX_CAPACITY: usize = aligned_max_capacity(16);
@@ -30,16 +39,16 @@ use crate::{pos::Addr, storage::Storage, Serialize};
 /// #[derive(Serializer)]
 /// #[ser_type(pure_copy)]
 /// struct MySer {
-/// 	#[ser_storage(Store)]
-/// 	storage: Store,
+///     #[ser_storage(Store)]
+///     storage: Store,
 /// }
 ///
 /// impl MySer {
-/// 	pub fn new() -> MySer {
-/// 		MySer {
-/// 			storage: Store::new(),
-/// 		}
-/// 	}
+///     pub fn new() -> MySer {
+///         MySer {
+///             storage: Store::new(),
+///         }
+///     }
 /// }
 /// ```
 ///
@@ -49,10 +58,10 @@ use crate::{pos::Addr, storage::Storage, Serialize};
 ///
 /// ```
 /// use ser_raw::{
-/// 	pos::PosMapping,
-/// 	storage::{AlignedVec, Storage},
-/// 	util::aligned_max_capacity,
-/// 	Serializer,
+///     pos::PosMapping,
+///     storage::{AlignedVec, Storage},
+///     util::aligned_max_capacity,
+///     Serializer,
 /// };
 ///
 /// const MAX_CAPACITY: usize = aligned_max_capacity(16);
@@ -61,19 +70,19 @@ use crate::{pos::Addr, storage::Storage, Serialize};
 /// #[derive(Serializer)]
 /// #[ser_type(ptr_offset)]
 /// struct MySer {
-/// 	#[ser_storage(Store)]
-/// 	storage: Store,
-/// 	#[ser_pos_mapping]
-/// 	pos_mapping: PosMapping,
+///     #[ser_storage(Store)]
+///     storage: Store,
+///     #[ser_pos_mapping]
+///     pos_mapping: PosMapping,
 /// }
 ///
 /// impl MySer {
-/// 	pub fn new() -> MySer {
-/// 		MySer {
-/// 			storage: Store::new(),
-/// 			pos_mapping: PosMapping::dummy(),
-/// 		}
-/// 	}
+///     pub fn new() -> MySer {
+///         MySer {
+///             storage: Store::new(),
+///             pos_mapping: PosMapping::dummy(),
+///         }
+///     }
 /// }
 /// ```
 ///
@@ -83,10 +92,10 @@ use crate::{pos::Addr, storage::Storage, Serialize};
 ///
 /// ```
 /// use ser_raw::{
-/// 	pos::{PosMapping, Ptrs},
-/// 	storage::{AlignedVec, Storage},
-/// 	util::aligned_max_capacity,
-/// 	Serializer,
+///     pos::{PosMapping, Ptrs},
+///     storage::{AlignedVec, Storage},
+///     util::aligned_max_capacity,
+///     Serializer,
 /// };
 ///
 /// const MAX_CAPACITY: usize = aligned_max_capacity(16);
@@ -95,22 +104,58 @@ use crate::{pos::Addr, storage::Storage, Serialize};
 /// #[derive(Serializer)]
 /// #[ser_type(complete)]
 /// struct MySer {
-/// 	#[ser_storage(Store)]
-/// 	storage: Store,
-/// 	#[ser_pos_mapping]
-/// 	pos_mapping: PosMapping,
-/// 	#[ser_ptrs]
-/// 	ptrs: Ptrs,
+///     #[ser_storage(Store)]
+///     storage: Store,
+///     #[ser_pos_mapping]
+///     pos_mapping: PosMapping,
+///     #[ser_ptrs]
+///     ptrs: Ptrs,
 /// }
 ///
 /// impl MySer {
-/// 	pub fn new() -> MySer {
-/// 		MySer {
-/// 			storage: Store::new(),
-/// 			pos_mapping: PosMapping::dummy(),
-/// 			ptrs: Ptrs::new(),
-/// 		}
-/// 	}
+///     pub fn new() -> MySer {
+///         MySer {
+///             storage: Store::new(),
+///             pos_mapping: PosMapping::dummy(),
+///             ptrs: Ptrs::new(),
+///         }
+///     }
+/// }
+/// ```
+///
+/// If storage is a [`FixedStorage`] (e.g. [`FixedVec`]), which never moves,
+/// `#[ser_ptrs]` field can be omitted. Pointers are then not recorded, which
+/// makes serialization faster, but relocations are not available.
+///
+/// ## Portable serializer
+///
+/// [`PortableSerializer`]-style. `#[ser_offset_size(...)]` sets size of offsets
+/// in output (4 or 8 bytes). Defaults to 8 if omitted.
+///
+/// ```
+/// use ser_raw::{
+///     storage::{AlignedVec, Storage},
+///     util::aligned_max_capacity,
+///     Serializer,
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Store = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+///
+/// #[derive(Serializer)]
+/// #[ser_type(portable)]
+/// #[ser_offset_size(4)]
+/// struct MySer {
+///     #[ser_storage(Store)]
+///     storage: Store,
+/// }
+///
+/// impl MySer {
+///     pub fn new() -> MySer {
+///         MySer {
+///             storage: Store::new(),
+///         }
+///     }
 /// }
 /// ```
 ///
@@ -142,39 +187,43 @@ use crate::{pos::Addr, storage::Storage, Serialize};
 ///
 /// ```
 /// use ser_raw::{
-/// 	pos::NoopAddr,
-/// 	storage::{AlignedVec, Storage},
-/// 	util::aligned_max_capacity,
-/// 	Serializer,
+///     pos::NoopAddr,
+///     storage::{AlignedVec, Storage},
+///     util::aligned_max_capacity,
+///     Serializer,
 /// };
 ///
 /// const MAX_CAPACITY: usize = aligned_max_capacity(16);
 /// type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
 ///
 /// struct MySerializer {
-/// 	storage: Store,
+///     storage: Store,
 /// }
 ///
 /// impl MySerializer {
-/// 	fn new() -> Self {
-/// 		Self { storage: Store::new() }
-/// 	}
+///     fn new() -> Self {
+///         Self { storage: Store::new() }
+///     }
 /// }
 ///
 /// impl Serializer for MySerializer {
-/// 	type Storage = Store;
-/// 	type BorrowedStorage = Store;
-/// 	type Addr = NoopAddr;
+///     type Storage = Store;
+///     type BorrowedStorage = Store;
+///     type Addr = NoopAddr;
 ///
-/// 	fn storage(&self) -> &Store { &self.storage }
-/// 	fn storage_mut(&mut self) -> &mut Store { &mut self.storage }
-/// 	fn into_storage(self) -> Store { self.storage }
+///     fn storage(&self) -> &Store { &self.storage }
+///     fn storage_mut(&mut self) -> &mut Store { &mut self.storage }
+///     fn into_storage(self) -> Store { self.storage }
 /// }
 /// ```
 ///
 /// [`PureCopySerializer`]: crate::PureCopySerializer
 /// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
 /// [`CompleteSerializer`]: crate::CompleteSerializer
+/// [`PortableSerializer`]: crate::PortableSerializer
+/// [`SharedAllocs`]: crate::pos::SharedAllocs
+/// [`FixedStorage`]: crate::storage::FixedStorage
+/// [`FixedVec`]: crate::storage::FixedVec
 pub trait Serializer: Sized {
 	/// [`Storage`] which backs this serializer.
 	type Storage: Storage;
@@ -186,6 +235,20 @@ pub trait Serializer: Sized {
 	/// [`Addr`] type this serializer uses.
 	type Addr: Addr;
 
+	/// Type of serializer. Recorded in [`Header`] by
+	/// [`serialize_with_header`](Serializer::serialize_with_header).
+	///
+	/// [`Header`]: crate::header::Header
+	const KIND: SerializerKind = SerializerKind::PureCopy;
+
+	/// Size in bytes of offsets and lengths in output of portable serializers
+	/// (e.g. [`PortableSerializer`]). Either 4 or 8.
+	///
+	/// Not used by other serializers.
+	///
+	/// [`PortableSerializer`]: crate::PortableSerializer
+	const PORTABLE_OFFSET_SIZE: usize = 8;
+
 	/// Serialize a value and all its dependencies.
 	///
 	/// This is the entry point for serializing, when serializing a single value.
@@ -197,15 +260,15 @@ pub trait Serializer: Sized {
 	///
 	/// ```
 	/// use ser_raw::{
-	/// 	storage::Storage,
-	/// 	util::aligned_max_capacity,
-	/// 	PureCopySerializer, Serialize, Serializer,
+	///     storage::Storage,
+	///     util::aligned_max_capacity,
+	///     PureCopySerializer, Serialize, Serializer,
 	/// };
 	///
 	/// #[derive(Serialize)]
 	/// struct Foo {
-	/// 	small: u8,
-	/// 	big: u32,
+	///     small: u8,
+	///     big: u32,
 	/// }
 	///
 	/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
@@ -220,6 +283,67 @@ pub trait Serializer: Sized {
 		(pos, storage)
 	}
 
+	/// Serialize a value and all its dependencies, with a [`Header`] at start of
+	/// output.
+	///
+	/// Header records the type of serializer, system architecture, storage
+	/// const parameters, position of the value, and fingerprint of the value's
+	/// type. Use [`header::check`] when reading output to check it matches.
+	///
+	/// Storage must be empty before calling this, so header is at position 0.
+	///
+	/// Consume serializer and return backing storage as `BorrowMut<Storage>`,
+	/// along with position of the serialized value in storage.
+	///
+	/// # Example
+	///
+	/// ```
+	/// use ser_raw::{
+	///     header::{self, HEADER_SIZE},
+	///     storage::{AlignedVec, ContiguousStorage, Storage},
+	///     util::aligned_max_capacity,
+	///     PureCopySerializer, Serialize, Serializer,
+	/// };
+	///
+	/// #[derive(Serialize)]
+	/// struct Foo {
+	///     small: u8,
+	///     big: u32,
+	/// }
+	///
+	/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+	/// type Ser = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec<16, 16, 8, MAX_CAPACITY>>;
+	/// let mut ser = Ser::new();
+	/// let (pos, storage) = ser.serialize_with_header(&Foo { small: 1, big: 2 });
+	/// assert_eq!(pos, HEADER_SIZE);
+	/// assert_eq!(storage.pos(), HEADER_SIZE + 8);
+	///
+	/// assert_eq!(header::check::<Foo, Ser>(storage.as_slice()), Ok(pos));
+	/// ```
+	///
+	/// [`Header`]: crate::header::Header
+	/// [`header::check`]: crate::header::check
+	fn serialize_with_header<T: Serialize<Self>>(
+		mut self,
+		value: &T,
+	) -> (usize, Self::BorrowedStorage)
+	where
+		Self::Storage: RandomAccessStorage,
+	{
+		// Leave space for header, and write it once position of value is known
+		let header_pos = self.push_empty::<Header>();
+		let pos = self.serialize_value(value);
+		let mut header = Header::new::<T, Self>(pos);
+		// Portable serializers' header is little-endian, like the rest of output
+		if Self::KIND == SerializerKind::Portable {
+			header = header.to_le();
+		}
+		unsafe { self.storage_mut().write(header_pos, &header) };
+
+		let storage = self.finalize();
+		(pos, storage)
+	}
+
 	/// Serialize a value and all its dependencies.
 	///
 	/// This is the entry point for serializing, when serializing multiple values
@@ -234,15 +358,15 @@ pub trait Serializer: Sized {
 	///
 	/// ```
 	/// use ser_raw::{
-	/// 	storage::Storage,
-	/// 	util::aligned_max_capacity,
-	/// 	PureCopySerializer, Serialize, Serializer,
+	///     storage::Storage,
+	///     util::aligned_max_capacity,
+	///     PureCopySerializer, Serialize, Serializer,
 	/// };
 	///
 	/// #[derive(Serialize)]
 	/// struct Foo {
-	/// 	small: u8,
-	/// 	big: u32,
+	///     small: u8,
+	///     big: u32,
 	/// }
 	///
 	/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
@@ -258,6 +382,8 @@ pub trait Serializer: Sized {
 	///
 	/// [`finalize`]: Serializer::finalize
 	fn serialize_value<T: Serialize<Self>>(&mut self, value: &T) -> usize {
+		start_value(self);
+
 		// Push value to storage
 		let pos = self.push_raw(value);
 
@@ -268,6 +394,124 @@ pub trait Serializer: Sized {
 		pos
 	}
 
+	/// Serialize a value and all its dependencies, returning an error if output
+	/// cannot be produced.
+	///
+	/// Same as [`serialize`](Serializer::serialize), except returns a
+	/// [`SerializeError`] instead of panicking if storage cannot grow to hold the
+	/// output, allocating memory fails, or a `RefCell`, `Mutex` or `RwLock` in
+	/// the value is borrowed / locked.
+	///
+	/// See [`fallible`](crate::fallible) module for details.
+	///
+	/// # Unwinding
+	///
+	/// Errors are signalled by unwinding, so this requires panics to unwind. If
+	/// built with `panic = "abort"`, failure aborts the process instead of
+	/// returning an error. See [`fallible`](crate::fallible).
+	///
+	/// # Example
+	///
+	/// ```
+	/// use ser_raw::{
+	///     storage::{AlignedVec, Storage},
+	///     SerializeError, PureCopySerializer, Serializer,
+	/// };
+	///
+	/// type Ser = PureCopySerializer<16, 16, 8, 1024, AlignedVec<16, 16, 8, 1024>>;
+	///
+	/// let (pos, storage) = Ser::new().try_serialize(&vec![0u8; 100]).unwrap();
+	/// assert_eq!(pos, 0);
+	/// assert_eq!(storage.pos(), 24 + 104);
+	///
+	/// let result = Ser::new().try_serialize(&vec![0u8; 2000]);
+	/// assert!(matches!(result, Err(SerializeError::CapacityExceeded { .. })));
+	/// ```
+	fn try_serialize<T: Serialize<Self>>(
+		self,
+		value: &T,
+	) -> Result<(usize, Self::BorrowedStorage), SerializeError> {
+		self.try_serialize_with_budget(value, usize::MAX)
+	}
+
+	/// Serialize a value and all its dependencies, returning an error if output
+	/// cannot be produced, or would exceed `budget` bytes.
+	///
+	/// Same as [`try_serialize`](Serializer::try_serialize), except also returns
+	/// [`SerializeError::BudgetExceeded`] if output would be larger than
+	/// `budget`. Storage will not grow larger than `budget` (rounded up to a
+	/// multiple of `MAX_VALUE_ALIGNMENT`), unless it already was.
+	///
+	/// # Unwinding
+	///
+	/// Errors are signalled by unwinding, so this requires panics to unwind. If
+	/// built with `panic = "abort"`, failure aborts the process instead of
+	/// returning an error. See [`fallible`](crate::fallible).
+	fn try_serialize_with_budget<T: Serialize<Self>>(
+		mut self,
+		value: &T,
+		budget: usize,
+	) -> Result<(usize, Self::BorrowedStorage), SerializeError> {
+		let pos = self.try_serialize_value_with_budget(value, budget)?;
+		let storage = self.finalize();
+		Ok((pos, storage))
+	}
+
+	/// Serialize a value and all its dependencies, returning an error if output
+	/// cannot be produced.
+	///
+	/// Same as [`serialize_value`](Serializer::serialize_value), except returns a
+	/// [`SerializeError`] instead of panicking if storage cannot grow to hold the
+	/// output, allocating memory fails, or a `RefCell`, `Mutex` or `RwLock` in
+	/// the value is borrowed / locked.
+	///
+	/// If an error is returned, output is incomplete, and serializer must be
+	/// [`reset`](Serializer::reset), or rolled back to a [`Checkpoint`] taken
+	/// before this call with [`rollback`](Serializer::rollback), before it's used
+	/// again.
+	///
+	/// See [`fallible`](crate::fallible) module for details.
+	///
+	/// # Unwinding
+	///
+	/// Errors are signalled by unwinding, so this requires panics to unwind. If
+	/// built with `panic = "abort"`, failure aborts the process instead of
+	/// returning an error. See [`fallible`](crate::fallible).
+	fn try_serialize_value<T: Serialize<Self>>(
+		&mut self,
+		value: &T,
+	) -> Result<usize, SerializeError> {
+		self.try_serialize_value_with_budget(value, usize::MAX)
+	}
+
+	/// Serialize a value and all its dependencies, returning an error if output
+	/// cannot be produced, or would exceed `budget` bytes.
+	///
+	/// Same as [`try_serialize_value`](Serializer::try_serialize_value), except
+	/// also returns [`SerializeError::BudgetExceeded`] if total size of output
+	/// (including any values serialized previously) would be larger than
+	/// `budget`.
+	///
+	/// # Unwinding
+	///
+	/// Errors are signalled by unwinding, so this requires panics to unwind. If
+	/// built with `panic = "abort"`, failure aborts the process instead of
+	/// returning an error. See [`fallible`](crate::fallible).
+	fn try_serialize_value_with_budget<T: Serialize<Self>>(
+		&mut self,
+		value: &T,
+		budget: usize,
+	) -> Result<usize, SerializeError> {
+		let pos = fallible::catch(budget, || self.serialize_value(value))?;
+
+		// Storage only checks budget when it grows, so check output is within budget,
+		// in case storage already had sufficient capacity
+		if self.pos() > budget {
+			return Err(SerializeError::BudgetExceeded { budget });
+		}
+		Ok(pos)
+	}
+
 	/// Push a value to output.
 	///
 	/// This is a value in a separate allocation, reached by a pointer
@@ -341,6 +585,45 @@ pub trait Serializer: Sized {
 		pos
 	}
 
+	/// Push a shared allocation to output and continue processing its contents.
+	///
+	/// This is an allocation which may be pointed to by multiple pointers
+	/// (e.g. `Rc<T>`). `addr` is address of the allocation, which identifies it.
+	/// `value` is what's written to output for it - usually the whole allocation
+	/// itself, including any reference counts. But it can differ from contents of
+	/// the allocation, e.g. if allocation's value has been dropped.
+	///
+	/// Each allocation is only added to output once per top-level value. If it
+	/// has already been added, it's not added again and `process()` is not
+	/// called. `kind` is the kind of pointer which points to the allocation.
+	///
+	/// Some Serializers may record/overwrite the pointer address.
+	///
+	/// Returns position of the allocation in storage.
+	///
+	/// # Panics
+	///
+	/// Panics if serializer does not record shared allocations
+	/// (see [`shared_allocs`](Serializer::shared_allocs)).
+	#[inline]
+	fn push_and_process_shared<T, P: FnOnce(&mut Self)>(
+		&mut self,
+		addr: usize,
+		value: &T,
+		#[allow(unused_variables)] ptr_addr: Self::Addr,
+		kind: RefKind,
+		process: P,
+	) -> usize {
+		if let Some(pos) = expect_shared_allocs(self).add_ref(addr, kind) {
+			return pos;
+		}
+
+		let pos = self.push_raw(value);
+		expect_shared_allocs(self).insert(addr, pos, kind);
+		process(self);
+		pos
+	}
+
 	/// Push a value to output.
 	///
 	/// Unlike [`push`](Serializer::push) and
@@ -391,15 +674,15 @@ pub trait Serializer: Sized {
 	/// impl<S> SerializeWith<MyString, S> for MyStringProxy
 	/// where S: Serializer
 	/// {
-	/// 	fn serialize_data_with(my_str: &MyString, serializer: &mut S) {
-	/// 		// Serializer may record pointer to this.
-	/// 		// Actually next line is not quite right -
-	/// 		// We need address of the pointer inside `String`, not `String` itself.
-	/// 		let ptr_addr = S::Addr::from_ref(&my_str.inner);
-	/// 		serializer.push(&my_str.inner.len(), ptr_addr);
-	/// 		// No need to record pointer to this, as it's deductible from pointer to `len`
-	/// 		serializer.push_raw_bytes(my_str.inner.as_bytes());
-	/// 	}
+	///     fn serialize_data_with(my_str: &MyString, serializer: &mut S) {
+	///         // Serializer may record pointer to this.
+	///         // Actually next line is not quite right -
+	///         // We need address of the pointer inside `String`, not `String` itself.
+	///         let ptr_addr = S::Addr::from_ref(&my_str.inner);
+	///         serializer.push(&my_str.inner.len(), ptr_addr);
+	///         // No need to record pointer to this, as it's deductible from pointer to `len`
+	///         serializer.push_raw_bytes(my_str.inner.as_bytes());
+	///     }
 	/// }
 	/// ```
 	#[inline]
@@ -478,13 +761,37 @@ pub trait Serializer: Sized {
 	/// method as:
 	/// ```ignore
 	/// fn overwrite_with<W: FnOnce(&mut Self)>(&mut self, write: W) {
-	/// 	write(self);
+	///     write(self);
 	/// }
 	/// ```
 	#[allow(unused_variables)]
 	#[inline(always)]
 	fn overwrite_with<W: FnOnce(&mut Self)>(&mut self, write: W) {}
 
+	/// Write bytes at a specific position in output.
+	///
+	/// Used by [`Serialize::serialize_portable`] implementations to write
+	/// values' portable representations. Portable serializers implement this.
+	/// Default implementation is a no-op, as other serializers never call
+	/// [`serialize_portable`](Serialize::serialize_portable).
+	///
+	/// # Panics
+	///
+	/// Portable serializers panic if `pos + bytes.len()` exceeds current
+	/// position in output.
+	#[allow(unused_variables)]
+	#[inline(always)]
+	fn write_portable(&mut self, pos: usize, bytes: &[u8]) {}
+
+	/// Get size of a `T` in this serializer's output.
+	///
+	/// This is `size_of::<T>()`, except for portable serializers, where it's
+	/// [`T::PORTABLE_SIZE`](Serialize::PORTABLE_SIZE).
+	#[inline(always)]
+	fn value_size<T: Serialize<Self>>() -> usize {
+		mem::size_of::<T>()
+	}
+
 	/// Finalize serialization, consume serializer, and return backing storage as
 	/// `BorrowMut<Storage>`.
 	#[inline]
@@ -492,6 +799,146 @@ pub trait Serializer: Sized {
 		self.into_storage()
 	}
 
+	/// Serialize a value and all its dependencies, without consuming serializer.
+	///
+	/// This is the entry point for serializing, when serializing many values one
+	/// after another, each into its own output, reusing the serializer's memory.
+	///
+	/// Serializer is [`reset`] first, so output contains only this value. Output
+	/// is then finalized in place, and can be read from [`storage`]. Serializer
+	/// can then be used again.
+	///
+	/// Returns position of value in output.
+	///
+	/// # Example
+	///
+	/// ```
+	/// use ser_raw::{
+	///     storage::{RandomAccessStorage, Storage},
+	///     util::aligned_max_capacity,
+	///     PureCopySerializer, Serialize, Serializer,
+	/// };
+	///
+	/// #[derive(Serialize)]
+	/// struct Foo {
+	///     small: u8,
+	///     big: u32,
+	/// }
+	///
+	/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+	/// let mut ser = PureCopySerializer::<16, 16, 8, MAX_CAPACITY, _>::new();
+	/// for i in 0..10 {
+	///     let pos = ser.serialize_in_place(&Foo { small: i, big: 2 });
+	///     assert_eq!(pos, 0);
+	///     assert_eq!(ser.pos(), 8);
+	///     let output: &Foo = unsafe { ser.storage().read(pos) };
+	///     assert_eq!(output.small, i);
+	/// }
+	/// ```
+	///
+	/// [`reset`]: Serializer::reset
+	/// [`storage`]: Serializer::storage
+	fn serialize_in_place<T: Serialize<Self>>(&mut self, value: &T) -> usize {
+		self.reset();
+		let pos = self.serialize_value(value);
+		self.finalize_in_place();
+		pos
+	}
+
+	/// Finalize serialization without consuming serializer.
+	///
+	/// After this, output can be read from [`storage`](Serializer::storage).
+	/// Serializer can then be [`reset`](Serializer::reset) and used again.
+	///
+	/// Default implementation is a no-op. Serializers which need to make final
+	/// changes to output at end of serialization (e.g. [`CompleteSerializer`])
+	/// override it.
+	///
+	/// [`CompleteSerializer`]: crate::CompleteSerializer
+	#[inline]
+	fn finalize_in_place(&mut self) {}
+
+	/// Reset serializer, ready to serialize again.
+	///
+	/// Clears output, and all state recorded during previous serializations
+	/// (record of shared allocations, position mapping, pointers), but retains
+	/// allocated memory, so it can be reused.
+	///
+	/// Default implementation clears storage and record of shared allocations.
+	/// Serializers which keep further state override it.
+	#[inline]
+	fn reset(&mut self) {
+		reset_storage_and_shared_allocs(self);
+	}
+
+	/// Get a [`Checkpoint`] recording current state of serializer, which it can
+	/// be rolled back to later with [`rollback`](Serializer::rollback).
+	///
+	/// Use to abandon serializing a value part-way through (e.g. if
+	/// [`try_serialize_value`](Serializer::try_serialize_value) fails), and
+	/// continue serializing further values.
+	///
+	/// Default implementation records position in storage and record of shared
+	/// allocations. Serializers which keep further state override it.
+	///
+	/// # Example
+	///
+	/// ```
+	/// use ser_raw::{
+	///     storage::{AlignedVec, Storage},
+	///     CompleteSerializer, Serializer,
+	/// };
+	///
+	/// type Ser = CompleteSerializer<16, 16, 8, 1024, AlignedVec<16, 16, 8, 1024>>;
+	/// let mut ser = Ser::new();
+	///
+	/// let mut positions = vec![];
+	/// for len in [10, 2000, 20] {
+	///     let checkpoint = ser.checkpoint();
+	///     match ser.try_serialize_value(&vec![0u8; len]) {
+	///         Ok(pos) => positions.push(pos),
+	///         // Value is too large. Skip it.
+	///         Err(_) => ser.rollback(checkpoint).unwrap(),
+	///     }
+	/// }
+	///
+	/// let storage = ser.finalize();
+	/// assert_eq!(positions, vec![0, 40]);
+	/// assert_eq!(storage.pos(), 40 + 24 + 24);
+	/// ```
+	#[inline]
+	fn checkpoint(&mut self) -> Checkpoint {
+		checkpoint_storage_and_shared_allocs(self)
+	}
+
+	/// Roll back serializer to a [`Checkpoint`] taken earlier with
+	/// [`checkpoint`](Serializer::checkpoint).
+	///
+	/// Discards output written after the checkpoint, and all state recorded
+	/// since (shared allocations, pointers), so serialization can continue as if
+	/// nothing had been serialized after the checkpoint was taken.
+	///
+	/// `checkpoint` must have been taken from this serializer, and serializer
+	/// must not have been [`reset`](Serializer::reset) or rolled back to an
+	/// earlier checkpoint since.
+	///
+	/// # Errors
+	///
+	/// Returns [`SerializeError::AlreadyWritten`] if output after the checkpoint
+	/// cannot be discarded because storage has already written it out (e.g. a
+	/// [`WriteStorage`] which has written it to its writer). Serializer is left
+	/// unchanged in that case.
+	///
+	/// # Panics
+	///
+	/// Panics if `checkpoint` is after current position.
+	///
+	/// [`WriteStorage`]: crate::storage::WriteStorage
+	#[inline]
+	fn rollback(&mut self, checkpoint: Checkpoint) -> Result<(), SerializeError> {
+		rollback_storage_and_shared_allocs(self, checkpoint)
+	}
+
 	/// Get current capacity of output.
 	#[inline]
 	fn capacity(&self) -> usize {
@@ -504,6 +951,17 @@ pub trait Serializer: Sized {
 		self.storage().pos()
 	}
 
+	/// Get mutable ref to record of shared allocations (e.g. `Rc`s) which have
+	/// been serialized, if this serializer keeps one.
+	///
+	/// Serializers which don't keep a record cannot serialize `Rc`s or `Arc`s.
+	/// Default implementation returns `None`. Derive macro implements this
+	/// method if serializer has a field tagged `#[ser_shared]`.
+	#[inline]
+	fn shared_allocs(&mut self) -> Option<&mut SharedAllocs> {
+		None
+	}
+
 	/// Get immutable ref to [`Storage`] backing this [`Serializer`].
 	fn storage(&self) -> &Self::Storage;
 
@@ -518,3 +976,70 @@ pub trait Serializer: Sized {
 	/// make final changes to the output at the end of serialization.
 	fn into_storage(self) -> Self::BorrowedStorage;
 }
+
+/// Get serializer's record of shared allocations.
+///
+/// # Panics
+///
+/// Panics if serializer does not record shared allocations.
+#[inline]
+pub(crate) fn expect_shared_allocs<S: Serializer>(serializer: &mut S) -> &mut SharedAllocs {
+	serializer
+		.shared_allocs()
+		.expect("Serializer does not support shared pointers. Add a `#[ser_shared]` field to it.")
+}
+
+/// Get checkpoint of serializer's storage position and record of shared
+/// allocations (if it keeps one).
+#[inline]
+pub(crate) fn checkpoint_storage_and_shared_allocs<S: Serializer>(
+	serializer: &mut S,
+) -> Checkpoint {
+	Checkpoint {
+		pos: serializer.pos(),
+		pos_mapping: PosMapping::dummy(),
+		ptrs: None,
+		shared_allocs: serializer
+			.shared_allocs()
+			.map(|shared_allocs| shared_allocs.checkpoint()),
+	}
+}
+
+/// Roll back serializer's storage and record of shared allocations (if it
+/// keeps one) to `checkpoint`.
+///
+/// Storage is truncated first, so nothing is changed if that fails.
+pub(crate) fn rollback_storage_and_shared_allocs<S: Serializer>(
+	serializer: &mut S,
+	checkpoint: Checkpoint,
+) -> Result<(), SerializeError> {
+	serializer.storage_mut().try_truncate(checkpoint.pos)?;
+	if let (Some(shared_allocs), Some(shared_checkpoint)) =
+		(serializer.shared_allocs(), checkpoint.shared_allocs)
+	{
+		shared_allocs.rollback(shared_checkpoint, checkpoint.pos);
+	}
+	Ok(())
+}
+
+/// Prepare to serialize a top-level value.
+///
+/// Forgets addresses of shared allocations already serialized (if serializer
+/// keeps a record of them), as they may have been dropped since, and their
+/// addresses reused.
+#[inline]
+pub(crate) fn start_value<S: Serializer>(serializer: &mut S) {
+	if let Some(shared_allocs) = serializer.shared_allocs() {
+		shared_allocs.start_value();
+	}
+}
+
+/// Clear serializer's storage and record of shared allocations (if it keeps
+/// one), retaining allocated memory.
+#[inline]
+pub(crate) fn reset_storage_and_shared_allocs<S: Serializer>(serializer: &mut S) {
+	serializer.storage_mut().clear();
+	if let Some(shared_allocs) = serializer.shared_allocs() {
+		shared_allocs.clear();
+	}
+}
@@ -1,10 +1,12 @@
 use std::mem;
 
 use crate::{
-	pos::{ActiveAddr, PtrGroup, Ptrs},
+	pos::{ActiveAddr, Checkpoint, PtrGroup, Ptrs},
+	relocate::{self, Relocations},
 	ser_traits::{PosTracking, Writable},
-	storage::{ContiguousStorage, RandomAccessStorage},
+	storage::{ContiguousStorage, RandomAccessStorage, Storage},
 	util::is_aligned_to,
+	SerializeError,
 };
 
 /// Trait for serializers that produce a buffer which is a complete valid
@@ -15,12 +17,25 @@ where
 	Self::Storage: ContiguousStorage + RandomAccessStorage,
 	Self::Addr: ActiveAddr,
 {
-	// Get reference to record of pointers written.
-	fn ptrs(&self) -> &Ptrs;
+	/// Get reference to record of pointers written.
+	///
+	/// Returns `None` if serializer does not record pointers, because its
+	/// storage is a [`FixedStorage`] which never moves.
+	///
+	/// [`FixedStorage`]: crate::storage::FixedStorage
+	fn ptrs(&self) -> Option<&Ptrs>;
 
-	// Get mutable reference to record of pointers written.
-	fn ptrs_mut(&mut self) -> &mut Ptrs;
+	/// Get mutable reference to record of pointers written.
+	///
+	/// Returns `None` if serializer does not record pointers.
+	fn ptrs_mut(&mut self) -> Option<&mut Ptrs>;
 
+	/// Overwrite a value in storage at position mapped from `addr`.
+	///
+	/// # Safety
+	///
+	/// `addr` must be the memory address of a value already serialized (its
+	/// original memory address, **not** the position in serializer's storage).
 	#[inline]
 	unsafe fn do_overwrite<T>(&mut self, addr: Self::Addr, value: &T) {
 		let pos = self.pos_mapping().pos_for_addr(addr);
@@ -53,8 +68,13 @@ where
 		self.storage_mut().write(ptr_pos, &target_addr);
 
 		// Record position of this pointer in storage so can be adjusted later if
-		// storage grows and so moves
-		let ptrs = self.ptrs_mut();
+		// storage grows and so moves.
+		// Skipped if storage never moves. `ptrs_mut()` always returns `None` in that
+		// case, so this is compiled out.
+		let ptrs = match self.ptrs_mut() {
+			Some(ptrs) => ptrs,
+			None => return,
+		};
 		if storage_addr != ptrs.current.addr() {
 			// Storage has moved. Create a new pointer group for new storage address.
 			new_ptr_group(ptrs, storage_addr);
@@ -81,27 +101,173 @@ where
 	/// After this, the serializer cannot be used any further, so this method
 	/// consumes it and returns the underlying `BorrowMut<Storage>`.
 	fn do_finalize(mut self) -> Self::BorrowedStorage {
+		self.do_finalize_in_place();
+		self.into_storage()
+	}
+
+	/// Finalize the serialized output in place, as [`do_finalize`], without
+	/// consuming the serializer.
+	///
+	/// Output is valid after this, and can be read from storage. Serializer can
+	/// be [`do_reset`] and used again.
+	///
+	/// [`do_finalize`]: Complete::do_finalize
+	/// [`do_reset`]: Complete::do_reset
+	fn do_finalize_in_place(&mut self) {
+		self.write_ref_counts();
+		self.correct_ptrs();
+	}
+
+	/// Reset serializer, ready to serialize again.
+	///
+	/// Clears storage, record of shared allocations, position mapping, and record
+	/// of pointers, retaining allocated memory.
+	///
+	/// Pointers recorded in a previous serialization must not be corrected again
+	/// once storage is reused for new output, so they're discarded here.
+	#[inline]
+	fn do_reset(&mut self) {
+		PosTracking::do_reset(self);
+		if let Some(ptrs) = self.ptrs_mut() {
+			ptrs.clear();
+		}
+	}
+
+	/// Get checkpoint of serializer's state.
+	///
+	/// Records position in storage, record of shared allocations, position
+	/// mapping, and number of pointers recorded.
+	#[inline]
+	fn do_checkpoint(&mut self) -> Checkpoint {
+		let mut checkpoint = PosTracking::do_checkpoint(self);
+		checkpoint.ptrs = self.ptrs().map(Ptrs::checkpoint);
+		checkpoint
+	}
+
+	/// Roll back serializer's state to `checkpoint`.
+	///
+	/// Pointers recorded after the checkpoint point into output which is
+	/// discarded, so must not be corrected later. They're discarded too.
+	#[inline]
+	fn do_rollback(&mut self, checkpoint: Checkpoint) -> Result<(), SerializeError> {
+		PosTracking::do_rollback(self, checkpoint)?;
+		if let (Some(ptrs), Some(ptrs_checkpoint)) = (self.ptrs_mut(), checkpoint.ptrs) {
+			ptrs.rollback(ptrs_checkpoint);
+		}
+		Ok(())
+	}
+
+	/// Finalize the serialized output, as [`do_finalize`], and also return a
+	/// relocation table recording positions of all pointers in output.
+	///
+	/// [`do_finalize`]: Complete::do_finalize
+	fn do_finalize_with_relocations(mut self) -> (Self::BorrowedStorage, Relocations) {
+		self.write_ref_counts();
+		let storage_addr = self.correct_ptrs();
+		let relocations = Relocations::new(storage_addr, self.ptr_positions());
+		(self.into_storage(), relocations)
+	}
+
+	/// Finalize the serialized output, as [`do_finalize`], and append a
+	/// relocation table recording positions of all pointers to end of output.
+	///
+	/// [`do_finalize`]: Complete::do_finalize
+	fn do_finalize_with_relocation_table(mut self) -> Self::BorrowedStorage {
+		self.write_ref_counts();
+
+		// Push table before correcting pointers, as pushing may cause storage to
+		// grow, and so move
+		let data_len = self.pos();
+		let table = relocate::get_table(
+			&self.ptr_positions(),
+			data_len,
+			Self::Storage::VALUE_ALIGNMENT,
+		);
+		self.storage_mut().push_slice(&table);
+
+		let storage_addr = self.correct_ptrs();
+
+		// Write storage address into trailer.
+		// `get_table` ensures trailer is at end of storage.
+		let base_addr_pos = self.pos() - relocate::TRAILER_SIZE;
+		unsafe { self.storage_mut().write(base_addr_pos, &storage_addr) };
+
+		self.into_storage()
+	}
+
+	/// Get positions of all pointers in output.
+	///
+	/// # Panics
+	///
+	/// Panics if serializer does not record pointers.
+	fn ptr_positions(&self) -> Vec<usize> {
+		self
+			.ptrs()
+			.expect("Relocations require serializer to record pointers with `#[ser_ptrs]`")
+			.ptr_positions()
+	}
+
+	/// Write reference counts of shared allocations (e.g. `Rc`s) in output.
+	///
+	/// Counts are set to number of references to each allocation in output,
+	/// rather than the counts in input, which may include references from
+	/// outside the serialized tree.
+	fn write_ref_counts(&mut self) {
+		// Take record of allocations temporarily, to allow writing to storage while
+		// iterating over it
+		let shared_allocs = match self.shared_allocs() {
+			Some(shared_allocs) => mem::take(shared_allocs),
+			None => return,
+		};
+
+		for alloc in shared_allocs.iter() {
+			// Strong references collectively hold 1 weak reference
+			let weak = alloc.weak + (alloc.strong > 0) as usize;
+			// Allocations are `RcBox` / `ArcInner`, which start with strong count,
+			// followed by weak count. Both are `usize`.
+			// `pos` is position of an allocation already written, so within bounds.
+			let storage = self.storage_mut();
+			unsafe {
+				storage.write(alloc.pos, &alloc.strong);
+				storage.write(alloc.pos + mem::size_of::<usize>(), &weak);
+			}
+		}
+
+		// Put record back, so serialization can continue, and its memory be reused
+		*self.shared_allocs().unwrap() = shared_allocs;
+	}
+
+	/// Update any pointers which have been made invalid because storage moved
+	/// since the pointers were written.
+	///
+	/// Pointer groups are updated with storage's current address, so pointers
+	/// are not corrected again if this is called a second time.
+	///
+	/// Returns current memory address of storage.
+	fn correct_ptrs(&mut self) -> usize {
 		let storage_ptr = self.storage_mut().as_mut_ptr();
 
-		let ptrs = self.ptrs_mut();
+		// If pointers aren't recorded, storage never moves, so no correction needed
+		let ptrs = match self.ptrs_mut() {
+			Some(ptrs) => ptrs,
+			None => return storage_ptr as usize,
+		};
 
 		// Safe if all pointers have been recorded accurately
 		unsafe {
 			if ptrs.current.addr() != storage_ptr as usize && !ptrs.current.is_empty() {
 				ptrs.current.correct_ptrs(storage_ptr);
+				ptrs.current.set_addr(storage_ptr as usize);
 			}
 
-			for ptr_group in &ptrs.past {
+			for ptr_group in &mut ptrs.past {
 				if ptr_group.addr() != storage_ptr as usize {
 					ptr_group.correct_ptrs(storage_ptr);
+					ptr_group.set_addr(storage_ptr as usize);
 				}
 			}
 		}
 
-		self.into_storage()
+		storage_ptr as usize
 	}
 }
-
-// TODO: If also provided a `Storage` with fixed capacity which can never move,
-// recording pointers for later correction could be skipped as they'll always be
-// accurate when they're written.
@@ -1,5 +1,7 @@
 mod complete;
 pub use complete::Complete;
+mod portable;
+pub use portable::Portable;
 mod pos_tracking;
 pub use pos_tracking::PosTracking;
 mod ptr_offset;
@@ -0,0 +1,55 @@
+use crate::{serializer::start_value, storage::RandomAccessStorage, Serialize, Serializer};
+
+/// Trait for serializers which write values in portable format, independent of
+/// system endianness and pointer width.
+///
+/// See [`portable`](crate::portable) module for details of the format.
+///
+/// Used by `PortableSerializer` serializer, provided by this crate.
+pub trait Portable: Serializer
+where Self::Storage: RandomAccessStorage
+{
+	/// Static assertion that `PORTABLE_OFFSET_SIZE` is valid.
+	const ASSERT_OFFSET_SIZE_VALID: () = assert!(
+		Self::PORTABLE_OFFSET_SIZE == 4 || Self::PORTABLE_OFFSET_SIZE == 8,
+		"PORTABLE_OFFSET_SIZE must be 4 or 8"
+	);
+
+	/// Serialize a value in portable format.
+	///
+	/// Reserves space for value's portable representation, and then calls
+	/// [`Serialize::serialize_portable`] to write it.
+	///
+	/// Returns position of value in output.
+	#[inline]
+	fn do_serialize_value<T: Serialize<Self>>(&mut self, value: &T) -> usize {
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_OFFSET_SIZE_VALID;
+
+		start_value(self);
+		let pos = crate::portable::reserve(self, T::PORTABLE_SIZE);
+		value.serialize_portable(self, pos);
+		pos
+	}
+
+	/// Write bytes at a specific position in output.
+	///
+	/// # Panics
+	///
+	/// Panics if `pos + bytes.len()` exceeds current position in output.
+	#[inline]
+	fn do_write_portable(&mut self, pos: usize, bytes: &[u8]) {
+		assert!(
+			pos <= self.pos() && bytes.len() <= self.pos() - pos,
+			"Write out of bounds"
+		);
+		// Bounds checked above. Alignment of `u8` is 1, so `pos` is always aligned.
+		unsafe { self.storage_mut().write_slice(pos, bytes) };
+	}
+
+	/// Get size of a `T` in output.
+	#[inline(always)]
+	fn do_value_size<T: Serialize<Self>>() -> usize {
+		T::PORTABLE_SIZE
+	}
+}
@@ -1,4 +1,12 @@
-use crate::{pos::PosMapping, storage::Storage, Serialize, Serializer};
+use crate::{
+	pos::{Checkpoint, PosMapping, RefKind},
+	serializer::{
+		checkpoint_storage_and_shared_allocs, expect_shared_allocs, reset_storage_and_shared_allocs,
+		rollback_storage_and_shared_allocs, start_value,
+	},
+	storage::Storage,
+	Serialize, SerializeError, Serializer,
+};
 
 /// Trait for serializers which track position in output.
 ///
@@ -12,7 +20,7 @@ pub trait PosTracking: Serializer {
 	fn pos_mapping(&self) -> &PosMapping;
 
 	/// Set current position mapping
-	fn set_pos_mapping(&mut self, pos_mapping: PosMapping) -> ();
+	fn set_pos_mapping(&mut self, pos_mapping: PosMapping);
 
 	/// Get position for a value
 	#[inline]
@@ -21,6 +29,8 @@ pub trait PosTracking: Serializer {
 	}
 
 	fn do_serialize_value<T: Serialize<Self>>(&mut self, value: &T) -> usize {
+		start_value(self);
+
 		// Push value to storage
 		let pos = self.push_raw(value);
 
@@ -34,6 +44,35 @@ pub trait PosTracking: Serializer {
 		pos
 	}
 
+	/// Reset serializer, ready to serialize again.
+	///
+	/// Clears storage, record of shared allocations, and position mapping,
+	/// retaining allocated memory.
+	#[inline]
+	fn do_reset(&mut self) {
+		reset_storage_and_shared_allocs(self);
+		self.set_pos_mapping(PosMapping::dummy());
+	}
+
+	/// Get checkpoint of serializer's state.
+	///
+	/// Records position in storage, record of shared allocations, and position
+	/// mapping.
+	#[inline]
+	fn do_checkpoint(&mut self) -> Checkpoint {
+		let mut checkpoint = checkpoint_storage_and_shared_allocs(self);
+		checkpoint.pos_mapping = *self.pos_mapping();
+		checkpoint
+	}
+
+	/// Roll back serializer's state to `checkpoint`.
+	#[inline]
+	fn do_rollback(&mut self, checkpoint: Checkpoint) -> Result<(), SerializeError> {
+		rollback_storage_and_shared_allocs(self, checkpoint)?;
+		self.set_pos_mapping(checkpoint.pos_mapping);
+		Ok(())
+	}
+
 	// Skip recording position when no further processing for a slice
 	#[inline]
 	fn do_push_slice<T>(&mut self, slice: &[T], _ptr_addr: Self::Addr) -> usize {
@@ -65,4 +104,36 @@ pub trait PosTracking: Serializer {
 		// Return position of value
 		pos
 	}
+
+	fn do_push_and_process_shared<T, P: FnOnce(&mut Self)>(
+		&mut self,
+		addr: usize,
+		value: &T,
+		_ptr_addr: Self::Addr,
+		kind: RefKind,
+		process: P,
+	) -> usize {
+		// If allocation has already been pushed, don't push it again
+		if let Some(pos) = expect_shared_allocs(self).add_ref(addr, kind) {
+			return pos;
+		}
+
+		// Get position mapping before processing this
+		let pos_mapping_before = *self.pos_mapping();
+
+		// Push value to storage, and record its position, before processing it,
+		// so any pointers back to it encountered while processing are deduplicated
+		let pos = self.push_raw(value);
+		expect_shared_allocs(self).insert(addr, pos, kind);
+
+		// Record position mapping for this value, and call `process` function
+		self.set_pos_mapping(PosMapping::new(addr, pos));
+		process(self);
+
+		// Reset position mapping back to as it was
+		self.set_pos_mapping(pos_mapping_before);
+
+		// Return position of value
+		pos
+	}
 }
@@ -1,14 +1,38 @@
 use std::mem;
 
-use crate::{ser_traits::PosTracking, storage::RandomAccessStorage, util::is_aligned_to};
+use crate::{
+	ser_traits::PosTracking,
+	storage::{RandomAccessStorage, Storage},
+	util::is_aligned_to,
+};
 
 /// Trait for serializers which overwrite pointers in output with position
 /// offsets relative to start of output.
 ///
-/// Used by `PtrOffsetSerializer` serializer, provided by this crate.
+/// Used by `PtrOffsetSerializer` and `PtrOffset32Serializer` serializers,
+/// provided by this crate.
 pub trait PtrOffset: PosTracking
 where Self::Storage: RandomAccessStorage
 {
+	/// Size of offsets in bytes.
+	///
+	/// Either size of a pointer (default), or 4. If 4, offsets are written as a
+	/// `u32` at start of the pointer, and any remaining bytes of the pointer are
+	/// zeroed. Storage's `MAX_CAPACITY` must not exceed `u32::MAX + 1`.
+	const OFFSET_SIZE: usize = mem::size_of::<usize>();
+
+	/// Static assertion that `OFFSET_SIZE` is valid.
+	const ASSERT_OFFSET_SIZE_VALID: () = {
+		assert!(
+			Self::OFFSET_SIZE == mem::size_of::<usize>() || Self::OFFSET_SIZE == 4,
+			"OFFSET_SIZE must be 4 or size of a pointer"
+		);
+		assert!(
+			Self::OFFSET_SIZE != 4 || Self::Storage::MAX_CAPACITY - 1 <= u32::MAX as usize,
+			"MAX_CAPACITY cannot exceed u32::MAX + 1 when OFFSET_SIZE is 4"
+		);
+	};
+
 	/// Overwrite pointer.
 	///
 	/// # Safety
@@ -17,13 +41,34 @@ where Self::Storage: RandomAccessStorage
 	/// * `target_pos` must be location of a valid value for the type being
 	///   pointed to.
 	/// * `ptr_pos` must be aligned for a pointer.
+	///
+	/// # Panics
+	///
+	/// Panics if `target_pos` is 0.
 	#[inline]
 	unsafe fn do_overwrite_ptr(&mut self, ptr_pos: usize, target_pos: usize) {
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_OFFSET_SIZE_VALID;
+
 		// Cannot fully check validity of `target_pos` because its type isn't known
 		debug_assert!(ptr_pos <= self.capacity() - mem::size_of::<usize>());
 		debug_assert!(is_aligned_to(ptr_pos, mem::align_of::<usize>()));
 		debug_assert!(target_pos <= self.capacity());
 
-		self.storage_mut().write(ptr_pos, &target_pos);
+		// Offset 0 would be written as a null pointer, and so read as `None` in an
+		// `Option<Box<T>>` etc. A pointer can only point to a value which is written
+		// after the pointer itself, so cannot occur in valid output.
+		assert!(target_pos != 0, "Cannot write pointer to start of output");
+
+		if Self::OFFSET_SIZE == 4 {
+			// Static assertion above ensures `target_pos` fits in a `u32`, as it's
+			// within bounds of output. Zero whole pointer first, so output does not
+			// contain remnants of the original pointer.
+			debug_assert!(target_pos <= u32::MAX as usize);
+			self.storage_mut().write(ptr_pos, &0usize);
+			self.storage_mut().write(ptr_pos, &(target_pos as u32));
+		} else {
+			self.storage_mut().write(ptr_pos, &target_pos);
+		}
 	}
 }
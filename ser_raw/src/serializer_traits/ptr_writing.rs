@@ -1,6 +1,7 @@
 use crate::{
-	pos::{ActiveAddr, PosMapping},
+	pos::{ActiveAddr, PosMapping, RefKind},
 	ser_traits::PosTracking,
+	serializer::expect_shared_allocs,
 	storage::Storage,
 };
 
@@ -15,16 +16,17 @@ where Self::Addr: ActiveAddr
 	///
 	/// # Safety
 	///
-	/// * `ptr_pos` must be less than or equal to
-	/// 	`capacity - mem::size_of::<usize>()`
-	/// 	(i.e. a position which is within the output)
-	/// * `target_pos` must be less than or equal to
-	/// 	`capacity - mem::size_of_val(value)`
-	/// 	where `value` is the value being pointed to.
+	/// `ptr_pos` must be less than or equal to
+	/// `capacity - mem::size_of::<usize>()`
+	/// (i.e. a position which is within the output).
+	///
+	/// `target_pos` must be less than or equal to
+	/// `capacity - mem::size_of_val(value)`
+	/// where `value` is the value being pointed to.
 	///
 	/// Some serializers may also impose requirements concerning alignment which
 	/// caller must satisfy.
-	unsafe fn overwrite_ptr(&mut self, ptr_pos: usize, target_pos: usize) -> ();
+	unsafe fn overwrite_ptr(&mut self, ptr_pos: usize, target_pos: usize);
 
 	// Skip recording position mapping here because no further processing of the
 	// slice, but still write pointer
@@ -68,4 +70,41 @@ where Self::Addr: ActiveAddr
 		// Return position of value in storage
 		pos
 	}
+
+	fn do_push_and_process_shared<T, P: FnOnce(&mut Self)>(
+		&mut self,
+		addr: usize,
+		value: &T,
+		ptr_addr: Self::Addr,
+		kind: RefKind,
+		process: P,
+	) -> usize {
+		// Get position mapping before this push
+		let pos_mapping_before = *self.pos_mapping();
+		let ptr_pos = pos_mapping_before.pos_for_addr(ptr_addr);
+
+		// If allocation has already been pushed, point pointer at existing copy
+		if let Some(pos) = expect_shared_allocs(self).add_ref(addr, kind) {
+			unsafe { self.overwrite_ptr(ptr_pos, pos) };
+			return pos;
+		}
+
+		// Push value to storage, and record its position, before processing it,
+		// so any pointers back to it encountered while processing are deduplicated
+		let pos = self.push_raw(value);
+		expect_shared_allocs(self).insert(addr, pos, kind);
+
+		// Overwrite pointer with position within output (relative to start of output)
+		unsafe { self.overwrite_ptr(ptr_pos, pos) };
+
+		// Record position mapping for this value, and call `process` function
+		self.set_pos_mapping(PosMapping::new(addr, pos));
+		process(self);
+
+		// Reset position mapping back to as it was before
+		self.set_pos_mapping(pos_mapping_before);
+
+		// Return position of value in storage
+		pos
+	}
 }
@@ -6,6 +6,12 @@ where
 	Self::Storage: RandomAccessStorage,
 	Self::Addr: ActiveAddr,
 {
+	/// Overwrite a value in storage at position mapped from `addr`.
+	///
+	/// # Safety
+	///
+	/// `addr` must be the memory address of a value already serialized (its
+	/// original memory address, **not** the position in serializer's storage).
 	#[inline]
 	unsafe fn do_overwrite<T>(&mut self, addr: Self::Addr, value: &T) {
 		let pos = self.pos_mapping().pos_for_addr(addr);
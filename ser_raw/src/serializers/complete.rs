@@ -1,9 +1,11 @@
 use std::borrow::BorrowMut;
 
 use crate::{
-	pos::{PosMapping, Ptrs},
+	pos::{PosMapping, Ptrs, SharedAllocs},
+	relocate::Relocations,
+	ser_traits::Complete,
 	storage::{AlignedVec, Storage},
-	Serializer,
+	Serialize, Serializer,
 };
 
 /// Serializer that produces a buffer which is a complete valid representation
@@ -36,14 +38,20 @@ use crate::{
 /// grow and reallocate to a different memory location. However, the pointers in
 /// the storage buffer will still point to the old memory locations, which are
 /// no longer valid. Accessing the deserialized value will then be UB.
+/// If output does need to be moved, use
+/// [`serialize_with_relocations`](CompleteSerializer::serialize_with_relocations)
+/// or
+/// [`serialize_with_relocation_table`](CompleteSerializer::serialize_with_relocation_table)
+/// and then fix the pointers after the move (see [`relocate`](crate::relocate)
+/// module).
 ///
 /// # Example
 ///
 /// ```
 /// use ser_raw::{
-/// 	CompleteSerializer, Serialize, Serializer,
-/// 	storage::RandomAccessStorage,
-/// 	util::aligned_max_capacity,
+///     CompleteSerializer, Serialize, Serializer,
+///     storage::RandomAccessStorage,
+///     util::aligned_max_capacity,
 /// };
 ///
 /// let boxed: Box<u8> = Box::new(123);
@@ -77,6 +85,8 @@ pub struct CompleteSerializer<
 	pos_mapping: PosMapping,
 	#[ser_ptrs]
 	ptrs: Ptrs,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
 }
 
 impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
@@ -86,15 +96,18 @@ impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
 	///
 	/// If you know, or can estimate, the amount of buffer space that's going to
 	/// be needed in advance, allocating upfront with [`with_capacity`] can
-	/// dramatically improve performance vs using `new`.
+	/// dramatically improve performance vs using `new`. [`serialized_size`]
+	/// calculates exact capacity required.
 	///
 	/// [`with_capacity`]: CompleteSerializer::with_capacity
+	/// [`serialized_size`]: crate::serialized_size
 	#[inline]
 	pub fn new() -> Self {
 		Self {
 			storage: AlignedVec::new(),
 			pos_mapping: PosMapping::dummy(),
 			ptrs: Ptrs::new(),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
 
@@ -113,10 +126,20 @@ impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
 			storage: AlignedVec::with_capacity(capacity),
 			pos_mapping: PosMapping::dummy(),
 			ptrs: Ptrs::new(),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
 }
 
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize> Default
+	for CompleteSerializer<SA, MVA, VA, MAX, AlignedVec<SA, MVA, VA, MAX>>
+{
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize, BorrowedStorage>
 	CompleteSerializer<SA, MVA, VA, MAX, BorrowedStorage>
 where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
@@ -140,6 +163,88 @@ where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
 			storage,
 			pos_mapping: PosMapping::dummy(),
 			ptrs: Ptrs::new(),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
+
+	/// Serialize a value and all its dependencies, and also return a relocation
+	/// table which can be used to fix the pointers in output if it's moved.
+	///
+	/// See [`relocate`](crate::relocate) module for more details.
+	///
+	/// # Example
+	///
+	/// ```
+	/// use ser_raw::{
+	///     storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	///     util::aligned_max_capacity,
+	///     CompleteSerializer,
+	/// };
+	///
+	/// let boxed: Box<u8> = Box::new(123);
+	///
+	/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+	/// type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+	/// let ser = CompleteSerializer::<16, 16, 8, MAX_CAPACITY, Store>::new();
+	/// let (pos, storage, mut relocations) = ser.serialize_with_relocations(&boxed);
+	///
+	/// // Copy output to a new buffer, and fix pointers
+	/// let mut copy = Store::with_capacity(storage.pos());
+	/// copy.push_bytes(storage.as_slice());
+	/// let new_base = copy.as_ptr() as usize;
+	/// relocations.rebase(copy.as_mut_slice(), new_base).unwrap();
+	///
+	/// let boxed_out: &Box<u8> = unsafe { copy.read(pos) };
+	/// assert_eq!(boxed_out, &boxed);
+	/// ```
+	pub fn serialize_with_relocations<T: Serialize<Self>>(
+		mut self,
+		value: &T,
+	) -> (usize, BorrowedStorage, Relocations) {
+		let pos = self.serialize_value(value);
+		let (storage, relocations) = self.finalize_with_relocations();
+		(pos, storage, relocations)
+	}
+
+	/// Finalize serialization, as [`finalize`], and also return a relocation
+	/// table which can be used to fix the pointers in output if it's moved.
+	///
+	/// See [`relocate`](crate::relocate) module for more details.
+	///
+	/// [`finalize`]: Serializer::finalize
+	#[inline]
+	pub fn finalize_with_relocations(self) -> (BorrowedStorage, Relocations) {
+		Complete::do_finalize_with_relocations(self)
+	}
+
+	/// Serialize a value and all its dependencies, and append a relocation table
+	/// to end of output, which can be used to fix the pointers in output if it's
+	/// moved.
+	///
+	/// Use [`relocate::rebase`] to fix the pointers.
+	/// See [`relocate`](crate::relocate) module for more details.
+	///
+	/// [`relocate::rebase`]: crate::relocate::rebase
+	pub fn serialize_with_relocation_table<T: Serialize<Self>>(
+		mut self,
+		value: &T,
+	) -> (usize, BorrowedStorage) {
+		let pos = self.serialize_value(value);
+		let storage = self.finalize_with_relocation_table();
+		(pos, storage)
+	}
+
+	/// Finalize serialization, as [`finalize`], and append a relocation table to
+	/// end of output, which can be used to fix the pointers in output if it's
+	/// moved.
+	///
+	/// Use [`relocate::rebase`] to fix the pointers.
+	/// See [`relocate`](crate::relocate) module for more details.
+	///
+	/// [`finalize`]: Serializer::finalize
+	/// [`relocate::rebase`]: crate::relocate::rebase
+	#[inline]
+	pub fn finalize_with_relocation_table(self) -> BorrowedStorage {
+		Complete::do_finalize_with_relocation_table(self)
+	}
 }
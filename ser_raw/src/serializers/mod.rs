@@ -2,5 +2,11 @@ mod pure_copy;
 pub use pure_copy::PureCopySerializer;
 mod ptr_offset;
 pub use ptr_offset::PtrOffsetSerializer;
+mod ptr_offset32;
+pub use ptr_offset32::PtrOffset32Serializer;
 mod complete;
 pub use complete::CompleteSerializer;
+mod portable;
+pub use portable::PortableSerializer;
+mod size_counting;
+pub use size_counting::{serialized_size, serialized_size_with_header, SizeCountingSerializer};
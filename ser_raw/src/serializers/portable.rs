@@ -0,0 +1,147 @@
+use std::borrow::BorrowMut;
+
+use crate::{
+	pos::SharedAllocs,
+	storage::{AlignedVec, Storage},
+	Serializer,
+};
+
+/// Serializer that produces output in a portable format, which is the same
+/// regardless of the endianness and pointer width of the system which
+/// produces it.
+///
+/// Primitives are written little-endian, pointers are replaced with offsets of
+/// 4 or 8 bytes (`OFFSET_SIZE` const parameter), and enums have a defined tag
+/// encoding. Values are not aligned. See [`portable`](crate::portable) module
+/// for details of the format.
+///
+/// Output can be produced on one system, and read on another with different
+/// architecture. Use `VALUE_ALIGNMENT` of 1 for most compact output.
+///
+/// `OFFSET_SIZE` of 4 produces smaller output, but serialization will panic if
+/// output exceeds `u32::MAX` bytes.
+///
+/// Types being serialized must implement
+/// [`serialize_portable`](crate::Serialize::serialize_portable) (derive macro
+/// implements it). Using this serializer with a type which doesn't produces a
+/// compile-time error.
+///
+/// See [`Storage`] for an explanation of the other const parameters.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::ContiguousStorage,
+///     util::aligned_max_capacity,
+///     PortableSerializer, Serialize, Serializer,
+/// };
+///
+/// let boxed: Box<u16> = Box::new(0x0102);
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// let mut ser = PortableSerializer::<16, 16, 1, MAX_CAPACITY, 4, _>::new();
+/// let (pos, storage) = ser.serialize(&boxed);
+/// assert_eq!(pos, 0);
+///
+/// // 4-byte offset of the `u16`, followed by the `u16`
+/// assert_eq!(storage.as_slice(), &[4, 0, 0, 0, 2, 1]);
+/// ```
+#[derive(Serializer)]
+#[ser_type(portable)]
+#[ser_offset_size(OFFSET_SIZE)]
+#[__local]
+pub struct PortableSerializer<
+	const STORAGE_ALIGNMENT: usize,
+	const MAX_VALUE_ALIGNMENT: usize,
+	const VALUE_ALIGNMENT: usize,
+	const MAX_CAPACITY: usize,
+	const OFFSET_SIZE: usize,
+	BorrowedStorage: BorrowMut<AlignedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>>,
+> {
+	#[ser_storage(AlignedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>)]
+	storage: BorrowedStorage,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize, const OS: usize>
+	PortableSerializer<SA, MVA, VA, MAX, OS, AlignedVec<SA, MVA, VA, MAX>>
+{
+	/// Create new [`PortableSerializer`] with no memory pre-allocated.
+	///
+	/// If you know, or can estimate, the amount of buffer space that's going to
+	/// be needed in advance, allocating upfront with [`with_capacity`] can
+	/// dramatically improve performance vs using `new`. [`serialized_size`]
+	/// calculates exact capacity required.
+	///
+	/// [`with_capacity`]: PortableSerializer::with_capacity
+	/// [`serialized_size`]: crate::serialized_size
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			storage: AlignedVec::new(),
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+
+	/// Create new [`PortableSerializer`] with buffer pre-allocated with
+	/// capacity of at least `capacity` bytes.
+	///
+	/// `capacity` will be rounded up to a multiple of `MAX_VALUE_ALIGNMENT`.
+	///
+	/// # Panics
+	///
+	/// Panics if `capacity` exceeds `MAX_CAPACITY`.
+	pub fn with_capacity(capacity: usize) -> Self {
+		// `AlignedVec::with_capacity()` ensures capacity is `< MAX_CAPACITY`
+		// and rounds up capacity to a multiple of `MAX_VALUE_ALIGNMENT`
+		Self {
+			storage: AlignedVec::with_capacity(capacity),
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+}
+
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize, const OS: usize> Default
+	for PortableSerializer<SA, MVA, VA, MAX, OS, AlignedVec<SA, MVA, VA, MAX>>
+{
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<
+		const SA: usize,
+		const MVA: usize,
+		const VA: usize,
+		const MAX: usize,
+		const OS: usize,
+		BorrowedStorage,
+	> PortableSerializer<SA, MVA, VA, MAX, OS, BorrowedStorage>
+where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
+{
+	/// Alignment of output buffer
+	pub const STORAGE_ALIGNMENT: usize = SA;
+
+	/// Maximum alignment of values being serialized
+	pub const MAX_VALUE_ALIGNMENT: usize = MVA;
+
+	/// Typical alignment of values being serialized
+	pub const VALUE_ALIGNMENT: usize = VA;
+
+	/// Maximum capacity of output buffer.
+	pub const MAX_CAPACITY: usize = MAX;
+
+	/// Size of offsets and lengths in output
+	pub const OFFSET_SIZE: usize = OS;
+
+	/// Create new [`PortableSerializer`] from an existing
+	/// `BorrowMut<AlignedVec>`.
+	pub fn from_storage(storage: BorrowedStorage) -> Self {
+		Self {
+			storage,
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+}
@@ -1,7 +1,7 @@
 use std::borrow::BorrowMut;
 
 use crate::{
-	pos::PosMapping,
+	pos::{PosMapping, SharedAllocs},
 	storage::{AlignedVec, Storage},
 	Serializer,
 };
@@ -20,9 +20,9 @@ use crate::{
 ///
 /// ```
 /// use ser_raw::{
-/// 	PtrOffsetSerializer, Serialize, Serializer,
-/// 	storage::RandomAccessStorage,
-/// 	util::aligned_max_capacity,
+///     PtrOffsetSerializer, Serialize, Serializer,
+///     storage::RandomAccessStorage,
+///     util::aligned_max_capacity,
 /// };
 ///
 /// let boxed: Box<u8> = Box::new(123);
@@ -51,6 +51,8 @@ pub struct PtrOffsetSerializer<
 	storage: BorrowedStorage,
 	#[ser_pos_mapping]
 	pos_mapping: PosMapping,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
 }
 
 impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
@@ -60,14 +62,17 @@ impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
 	///
 	/// If you know, or can estimate, the amount of buffer space that's going to
 	/// be needed in advance, allocating upfront with [`with_capacity`] can
-	/// dramatically improve performance vs using `new`.
+	/// dramatically improve performance vs using `new`. [`serialized_size`]
+	/// calculates exact capacity required.
 	///
 	/// [`with_capacity`]: PtrOffsetSerializer::with_capacity
+	/// [`serialized_size`]: crate::serialized_size
 	#[inline]
 	pub fn new() -> Self {
 		Self {
 			storage: AlignedVec::new(),
 			pos_mapping: PosMapping::dummy(),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
 
@@ -85,10 +90,20 @@ impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
 		Self {
 			storage: AlignedVec::with_capacity(capacity),
 			pos_mapping: PosMapping::dummy(),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
 }
 
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize> Default
+	for PtrOffsetSerializer<SA, MVA, VA, MAX, AlignedVec<SA, MVA, VA, MAX>>
+{
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize, BorrowedStorage>
 	PtrOffsetSerializer<SA, MVA, VA, MAX, BorrowedStorage>
 where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
@@ -111,6 +126,7 @@ where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
 		Self {
 			storage,
 			pos_mapping: PosMapping::dummy(),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
 }
@@ -0,0 +1,141 @@
+use std::borrow::BorrowMut;
+
+use crate::{
+	pos::{PosMapping, SharedAllocs},
+	storage::{AlignedVec, Storage},
+	Serializer,
+};
+
+/// Serializer that overwrites pointers in output with `u32` position offsets,
+/// relative to the start of the output buffer.
+///
+/// Same as [`PtrOffsetSerializer`], except offsets are written as a `u32` at
+/// the start of each pointer, and rest of the pointer is zeroed. Offsets can be
+/// read as a `u32` (e.g. with `DataView.getUint32` in JavaScript), instead of
+/// requiring a 64-bit read on 64-bit systems.
+///
+/// Read output with [`OffsetRef<T, u32>`](crate::read::OffsetRef).
+///
+/// `MAX_CAPACITY` cannot exceed `u32::MAX + 1`. Use
+/// [`aligned_max_u32_capacity`] to get the largest valid value. Using a larger
+/// value is a compile-time error.
+///
+/// See [`Storage`] for an explanation of the const parameters.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     PtrOffset32Serializer, Serialize, Serializer,
+///     storage::RandomAccessStorage,
+///     util::aligned_max_u32_capacity,
+/// };
+///
+/// let boxed: Box<u8> = Box::new(123);
+/// const MAX_CAPACITY: usize = aligned_max_u32_capacity(16);
+/// let mut ser = PtrOffset32Serializer::<16, 16, 8, MAX_CAPACITY, _>::new();
+/// let (pos, storage) = ser.serialize(&boxed);
+/// assert_eq!(pos, 0);
+///
+/// let offset: u32 = unsafe { *storage.read(pos) };
+/// let value: u8 = unsafe { *storage.read(pos + offset as usize) };
+/// assert_eq!(offset, std::mem::size_of::<usize>() as u32);
+/// assert_eq!(value, 123);
+/// ```
+///
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+/// [`aligned_max_u32_capacity`]: crate::util::aligned_max_u32_capacity
+#[derive(Serializer)]
+#[ser_type(ptr_offset)]
+#[ser_offset_size(4)]
+#[__local]
+pub struct PtrOffset32Serializer<
+	const STORAGE_ALIGNMENT: usize,
+	const MAX_VALUE_ALIGNMENT: usize,
+	const VALUE_ALIGNMENT: usize,
+	const MAX_CAPACITY: usize,
+	BorrowedStorage: BorrowMut<AlignedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>>,
+> {
+	#[ser_storage(AlignedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>)]
+	storage: BorrowedStorage,
+	#[ser_pos_mapping]
+	pos_mapping: PosMapping,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
+	PtrOffset32Serializer<SA, MVA, VA, MAX, AlignedVec<SA, MVA, VA, MAX>>
+{
+	/// Create new [`PtrOffset32Serializer`] with no memory pre-allocated.
+	///
+	/// If you know, or can estimate, the amount of buffer space that's going to
+	/// be needed in advance, allocating upfront with [`with_capacity`] can
+	/// dramatically improve performance vs using `new`. [`serialized_size`]
+	/// calculates exact capacity required.
+	///
+	/// [`with_capacity`]: PtrOffset32Serializer::with_capacity
+	/// [`serialized_size`]: crate::serialized_size
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			storage: AlignedVec::new(),
+			pos_mapping: PosMapping::dummy(),
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+
+	/// Create new [`PtrOffset32Serializer`] with buffer pre-allocated with
+	/// capacity of at least `capacity` bytes.
+	///
+	/// `capacity` will be rounded up to a multiple of `MAX_VALUE_ALIGNMENT`.
+	///
+	/// # Panics
+	///
+	/// Panics if `capacity` exceeds `MAX_CAPACITY`.
+	pub fn with_capacity(capacity: usize) -> Self {
+		// `AlignedVec::with_capacity()` ensures capacity is `< MAX_CAPACITY`
+		// and rounds up capacity to a multiple of `MAX_VALUE_ALIGNMENT`
+		Self {
+			storage: AlignedVec::with_capacity(capacity),
+			pos_mapping: PosMapping::dummy(),
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+}
+
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize> Default
+	for PtrOffset32Serializer<SA, MVA, VA, MAX, AlignedVec<SA, MVA, VA, MAX>>
+{
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize, BorrowedStorage>
+	PtrOffset32Serializer<SA, MVA, VA, MAX, BorrowedStorage>
+where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
+{
+	/// Alignment of output buffer
+	pub const STORAGE_ALIGNMENT: usize = SA;
+
+	/// Maximum alignment of values being serialized
+	pub const MAX_VALUE_ALIGNMENT: usize = MVA;
+
+	/// Typical alignment of values being serialized
+	pub const VALUE_ALIGNMENT: usize = VA;
+
+	/// Maximum capacity of output buffer.
+	pub const MAX_CAPACITY: usize = MAX;
+
+	/// Create new [`PtrOffset32Serializer`] from an existing
+	/// `BorrowMut<AlignedVec>`.
+	pub fn from_storage(storage: BorrowedStorage) -> Self {
+		Self {
+			storage,
+			pos_mapping: PosMapping::dummy(),
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+}
@@ -1,6 +1,7 @@
 use std::borrow::BorrowMut;
 
 use crate::{
+	pos::SharedAllocs,
 	storage::{AlignedVec, Storage},
 	Serializer,
 };
@@ -28,8 +29,8 @@ use crate::{
 ///
 /// ```
 /// use ser_raw::{
-/// 	util::aligned_max_capacity,
-/// 	PureCopySerializer, Serialize, Serializer,
+///     util::aligned_max_capacity,
+///     PureCopySerializer, Serialize, Serializer,
 /// };
 ///
 /// let boxed: Box<u8> = Box::new(123);
@@ -57,6 +58,8 @@ pub struct PureCopySerializer<
 > {
 	#[ser_storage(AlignedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>)]
 	storage: BorrowedStorage,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
 }
 
 impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
@@ -66,13 +69,16 @@ impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
 	///
 	/// If you know, or can estimate, the amount of buffer space that's going to
 	/// be needed in advance, allocating upfront with [`with_capacity`] can
-	/// dramatically improve performance vs using `new`.
+	/// dramatically improve performance vs using `new`. [`serialized_size`]
+	/// calculates exact capacity required.
 	///
 	/// [`with_capacity`]: PureCopySerializer::with_capacity
+	/// [`serialized_size`]: crate::serialized_size
 	#[inline]
 	pub fn new() -> Self {
 		Self {
 			storage: AlignedVec::new(),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
 
@@ -89,10 +95,20 @@ impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize>
 		// and rounds up capacity to a multiple of `MAX_VALUE_ALIGNMENT`
 		Self {
 			storage: AlignedVec::with_capacity(capacity),
+			shared_allocs: SharedAllocs::new(),
 		}
 	}
 }
 
+impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize> Default
+	for PureCopySerializer<SA, MVA, VA, MAX, AlignedVec<SA, MVA, VA, MAX>>
+{
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl<const SA: usize, const MVA: usize, const VA: usize, const MAX: usize, BorrowedStorage>
 	PureCopySerializer<SA, MVA, VA, MAX, BorrowedStorage>
 where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
@@ -112,6 +128,9 @@ where BorrowedStorage: BorrowMut<AlignedVec<SA, MVA, VA, MAX>>
 	/// Create new [`PureCopySerializer`] from an existing
 	/// `BorrowMut<AlignedVec>`.
 	pub fn from_storage(storage: BorrowedStorage) -> Self {
-		Self { storage }
+		Self {
+			storage,
+			shared_allocs: SharedAllocs::new(),
+		}
 	}
 }
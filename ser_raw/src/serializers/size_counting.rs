@@ -0,0 +1,192 @@
+use std::{marker::PhantomData, mem};
+
+use crate::{
+	header::{Header, SerializerKind},
+	portable,
+	pos::{NoopAddr, SharedAllocs},
+	serializer::start_value,
+	storage::{CountingStorage, Storage},
+	Serialize, Serializer,
+};
+
+/// Serializer which calculates size of output another serializer would
+/// produce, without producing it.
+///
+/// Performs a dry run of serialization with serializer `Ser`'s configuration,
+/// but only counts bytes instead of writing them. Storage is a
+/// [`CountingStorage`] with same const parameters as `Ser`'s storage, so
+/// alignment and padding are identical.
+///
+/// This is useful for pre-allocating the exact capacity required, with
+/// `with_capacity`, so serializer's storage never has to grow.
+///
+/// Size is exact for all serializers provided by this crate, and for custom
+/// serializers built with the derive macro, as long as their storage uses the
+/// default alignment logic of [`Storage`] trait. Size does not include a
+/// relocation table appended by
+/// [`CompleteSerializer::serialize_with_relocation_table`].
+///
+/// Usually it's easier to use [`serialized_size`] or
+/// [`serialized_size_with_header`].
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::{AlignedVec, Storage},
+///     util::aligned_max_capacity,
+///     CompleteSerializer, Serialize, Serializer, SizeCountingSerializer,
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Ser = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+///
+/// let input = vec![vec![1u8, 2, 3], vec![4, 5]];
+/// let (_, counted) = SizeCountingSerializer::<Ser>::new().serialize(&input);
+///
+/// let ser = Ser::with_capacity(counted.pos());
+/// let (_, storage) = ser.serialize(&input);
+/// assert_eq!(storage.pos(), counted.pos());
+/// ```
+///
+/// [`CompleteSerializer::serialize_with_relocation_table`]: crate::CompleteSerializer::serialize_with_relocation_table
+pub struct SizeCountingSerializer<Ser: Serializer> {
+	storage: CountingStorage<Ser::Storage>,
+	shared_allocs: SharedAllocs,
+	_marker: PhantomData<Ser>,
+}
+
+impl<Ser: Serializer> SizeCountingSerializer<Ser> {
+	/// Create new [`SizeCountingSerializer`].
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			storage: CountingStorage::new(),
+			shared_allocs: SharedAllocs::new(),
+			_marker: PhantomData,
+		}
+	}
+}
+
+impl<Ser: Serializer> Default for SizeCountingSerializer<Ser> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<Ser: Serializer> Serializer for SizeCountingSerializer<Ser> {
+	type Storage = CountingStorage<Ser::Storage>;
+	type BorrowedStorage = CountingStorage<Ser::Storage>;
+	type Addr = NoopAddr;
+
+	// Same kind as `Ser`, so types select same representation as they would with
+	// `Ser` (e.g. `PORTABLE_SIZE`)
+	const KIND: SerializerKind = Ser::KIND;
+
+	const PORTABLE_OFFSET_SIZE: usize = Ser::PORTABLE_OFFSET_SIZE;
+
+	fn serialize_value<T: Serialize<Self>>(&mut self, value: &T) -> usize {
+		start_value(self);
+		if Self::KIND == SerializerKind::Portable {
+			// Same as `Portable::do_serialize_value`. `write_portable` is a no-op.
+			let pos = portable::reserve(self, T::PORTABLE_SIZE);
+			value.serialize_portable(self, pos);
+			pos
+		} else {
+			let pos = self.push_raw(value);
+			value.serialize_data(self);
+			pos
+		}
+	}
+
+	#[inline(always)]
+	fn value_size<T: Serialize<Self>>() -> usize {
+		if Self::KIND == SerializerKind::Portable {
+			T::PORTABLE_SIZE
+		} else {
+			mem::size_of::<T>()
+		}
+	}
+
+	#[inline]
+	fn shared_allocs(&mut self) -> Option<&mut SharedAllocs> {
+		Some(&mut self.shared_allocs)
+	}
+
+	#[inline]
+	fn storage(&self) -> &CountingStorage<Ser::Storage> {
+		&self.storage
+	}
+
+	#[inline]
+	fn storage_mut(&mut self) -> &mut CountingStorage<Ser::Storage> {
+		&mut self.storage
+	}
+
+	#[inline]
+	fn into_storage(self) -> CountingStorage<Ser::Storage> {
+		self.storage
+	}
+}
+
+/// Calculate exact size in bytes of output serializer `Ser` would produce when
+/// serializing `value` with [`serialize`](Serializer::serialize).
+///
+/// Use to pre-allocate storage with exact capacity required.
+///
+/// See [`SizeCountingSerializer`] for details.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     serialized_size,
+///     storage::{AlignedVec, Storage},
+///     util::aligned_max_capacity,
+///     PtrOffsetSerializer, Serialize, Serializer,
+/// };
+///
+/// #[derive(Serialize)]
+/// struct Foo {
+///     name: String,
+///     nums: Vec<u32>,
+/// }
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Ser = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+///
+/// let input = Foo { name: "foo".to_string(), nums: vec![1, 2, 3] };
+/// let size = serialized_size::<Ser, _>(&input);
+/// assert_eq!(size, 48 + 8 + 16);
+///
+/// // Capacity is rounded up to a multiple of `MAX_VALUE_ALIGNMENT`
+/// let (_, storage) = Ser::with_capacity(size).serialize(&input);
+/// assert_eq!(storage.pos(), size);
+/// assert_eq!(storage.capacity(), 80);
+/// ```
+pub fn serialized_size<Ser, T>(value: &T) -> usize
+where
+	Ser: Serializer,
+	T: Serialize<SizeCountingSerializer<Ser>>,
+{
+	let mut ser = SizeCountingSerializer::<Ser>::new();
+	ser.serialize_value(value);
+	ser.pos()
+}
+
+/// Calculate exact size in bytes of output serializer `Ser` would produce when
+/// serializing `value` with
+/// [`serialize_with_header`](Serializer::serialize_with_header).
+///
+/// See [`serialized_size`] and [`SizeCountingSerializer`] for details.
+pub fn serialized_size_with_header<Ser, T>(value: &T) -> usize
+where
+	Ser: Serializer,
+	T: Serialize<SizeCountingSerializer<Ser>>,
+{
+	let mut ser = SizeCountingSerializer::<Ser>::new();
+	ser.push_empty::<Header>();
+	ser.serialize_value(value);
+	ser.pos()
+}
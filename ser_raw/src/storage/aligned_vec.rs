@@ -10,7 +10,10 @@ use std::{
 };
 
 use super::{ContiguousStorage, RandomAccessStorage, Storage};
-use crate::util::{align_up_to, aligned_max_capacity, is_aligned_to};
+use crate::{
+	fallible::{self, SerializeError},
+	util::{align_up_to, aligned_max_capacity, is_aligned_to},
+};
 
 const PTR_SIZE: usize = mem::size_of::<usize>();
 const DEFAULT_STORAGE_ALIGNMENT: usize = 16;
@@ -101,6 +104,7 @@ impl<
 	#[inline]
 	fn new() -> Self {
 		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
 		let _ = Self::ASSERT_ALIGNMENTS_VALID;
 
 		Self {
@@ -123,6 +127,7 @@ impl<
 	/// [`MAX_VALUE_ALIGNMENT`]: AlignedVec::MAX_VALUE_ALIGNMENT
 	unsafe fn with_capacity_unchecked(capacity: usize) -> Self {
 		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
 		let _ = Self::ASSERT_ALIGNMENTS_VALID;
 
 		debug_assert!(capacity > 0, "capacity cannot be 0");
@@ -133,7 +138,7 @@ impl<
 		debug_assert!(is_aligned_to(capacity, MAX_VALUE_ALIGNMENT));
 
 		Self {
-			ptr: Self::alloc(capacity),
+			ptr: Self::alloc(capacity).unwrap_or_else(|err| err.fail()),
 			capacity,
 			pos: 0,
 		}
@@ -225,7 +230,27 @@ impl<
 		// but avoids having to handle potential overflow here
 		let remaining = self.capacity.wrapping_sub(self.pos);
 		if additional > remaining {
-			self.grow_for_reserve(additional);
+			if let Err(err) = self.grow_for_reserve(additional) {
+				err.fail();
+			}
+		}
+	}
+
+	/// Reserve capacity for at least `additional` more bytes to be inserted into
+	/// the [`AlignedVec`], returning an error if it cannot.
+	///
+	/// Same as [`reserve`](AlignedVec::reserve), except returns an error if
+	/// [`MAX_CAPACITY`] would be exceeded, allocation fails, or byte budget
+	/// of fallible serialization in progress would be exceeded.
+	///
+	/// [`MAX_CAPACITY`]: AlignedVec::MAX_CAPACITY
+	#[inline]
+	fn try_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let remaining = self.capacity.wrapping_sub(self.pos);
+		if additional > remaining {
+			self.grow_for_reserve(additional)
+		} else {
+			Ok(())
 		}
 	}
 
@@ -245,7 +270,7 @@ impl<
 					self.dealloc();
 					NonNull::dangling()
 				} else {
-					self.realloc(new_cap)
+					self.realloc(new_cap).unwrap_or_else(|err| err.fail())
 				}
 			};
 			self.capacity = new_cap;
@@ -267,82 +292,90 @@ impl<
 	/// This keeps the path for common case where capacity is already sufficient
 	/// as fast as possible, and makes `reserve` more likely to be inlined.
 	/// This is the same trick that Rust's `Vec::reserve` uses.
+	///
+	/// Returns an error if `MAX_CAPACITY` or byte budget would be exceeded,
+	/// or allocation fails.
 	#[cold]
-	fn grow_for_reserve(&mut self, additional: usize) {
+	fn grow_for_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
 		debug_assert!(additional > 0);
 
 		// Where `reserve` was called by `push` / `push_slice`, we could actually avoid
 		// the checked add. A valid value / slice cannot be larger than `isize::MAX`,
 		// and ditto `capacity`, so this can't overflow.
 		// TODO: Maybe create a specialized version of this function for that usage?
-		let mut new_cap = self
+		let required = self
 			.pos
 			.checked_add(additional)
-			.expect("Cannot grow AlignedVec further");
+			.ok_or_else(fallible::capacity_exceeded::<Self>)?;
 
-		new_cap = if new_cap > MAX_CAPACITY.next_power_of_two() / 2 {
+		let mut new_cap = if required > MAX_CAPACITY.next_power_of_two() / 2 {
 			// Rounding up to next power of 2 would result in more than `MAX_CAPACITY`,
 			// so cap at max instead.
-			assert!(new_cap <= MAX_CAPACITY, "Cannot grow AlignedVec further");
+			if required > MAX_CAPACITY {
+				return Err(fallible::capacity_exceeded::<Self>());
+			}
 			MAX_CAPACITY
 		} else {
 			// Cannot overflow due to check above
-			new_cap.next_power_of_two()
+			required.next_power_of_two()
 		};
+		fallible::check_budget(required)?;
+
+		// Ensuring at least `MAX_VALUE_ALIGNMENT` here makes sure capacity will always
+		// remain a multiple of `MAX_VALUE_ALIGNMENT` hereafter, as growth after this
+		// will be in powers of 2. `shrink_to_fit` also enforces this invariant.
+		new_cap = cmp::max(new_cap, MAX_VALUE_ALIGNMENT);
+		// Don't allocate more than byte budget allows
+		new_cap = fallible::limit_to_budget(new_cap, MAX_VALUE_ALIGNMENT);
 
 		// Above calculation ensures `alloc` / `realloc`'s requirements are met
 		self.ptr = unsafe {
 			if self.capacity == 0 {
-				// Ensuring at least `MAX_VALUE_ALIGNMENT` here makes sure capacity will always
-				// remain a multiple of `MAX_VALUE_ALIGNMENT` hereafter, as growth after this
-				// will be in powers of 2. `shrink_to_fit` also enforces this invariant.
-				new_cap = cmp::max(new_cap, MAX_VALUE_ALIGNMENT);
-				Self::alloc(new_cap)
+				Self::alloc(new_cap)?
 			} else {
-				self.realloc(new_cap)
+				self.realloc(new_cap)?
 			}
 		};
 		self.capacity = new_cap;
+		Ok(())
 	}
 
 	/// Allocate backing memory.
 	///
+	/// Returns an error if allocation fails.
+	///
 	/// # Safety
 	///
 	/// * `capacity` must not be 0.
 	/// * `capacity` must not exceed `isize::MAX + 1 - STORAGE_ALIGNMENT`.
-	unsafe fn alloc(capacity: usize) -> NonNull<u8> {
+	unsafe fn alloc(capacity: usize) -> Result<NonNull<u8>, SerializeError> {
 		debug_assert!(capacity > 0);
 		debug_assert!(capacity <= aligned_max_capacity(STORAGE_ALIGNMENT));
 
 		let layout = Layout::from_size_align_unchecked(capacity, STORAGE_ALIGNMENT);
 		let ptr = alloc::alloc(layout);
-		if ptr.is_null() {
-			alloc::handle_alloc_error(layout);
-		}
-		NonNull::new_unchecked(ptr)
+		NonNull::new(ptr).ok_or(SerializeError::AllocFailed { layout })
 	}
 
 	/// Reallocate backing memory.
 	///
+	/// Returns an error if allocation fails. Existing memory remains allocated
+	/// in that case.
+	///
 	/// # Safety
 	///
 	/// * `self.capacity` must not be 0 (i.e. already has memory allocated).
 	/// * `new_cap` must not be 0.
 	/// * `new_cap` must not exceed `isize::MAX + 1 - STORAGE_ALIGNMENT`.
-	unsafe fn realloc(&mut self, new_cap: usize) -> NonNull<u8> {
+	unsafe fn realloc(&mut self, new_cap: usize) -> Result<NonNull<u8>, SerializeError> {
 		debug_assert!(self.capacity > 0);
 		debug_assert!(new_cap > 0);
 		debug_assert!(new_cap <= aligned_max_capacity(STORAGE_ALIGNMENT));
 
 		let new_ptr = alloc::realloc(self.ptr.as_ptr(), self.layout(), new_cap);
-		if new_ptr.is_null() {
-			alloc::handle_alloc_error(Layout::from_size_align_unchecked(
-				new_cap,
-				STORAGE_ALIGNMENT,
-			));
-		}
-		NonNull::new_unchecked(new_ptr)
+		NonNull::new(new_ptr).ok_or(SerializeError::AllocFailed {
+			layout: Layout::from_size_align_unchecked(new_cap, STORAGE_ALIGNMENT),
+		})
 	}
 
 	/// Deallocate backing memory.
@@ -375,10 +408,11 @@ impl<
 	///
 	/// # Safety
 	///
-	/// * Storage `capacity` must be greater or equal to
-	/// 	`pos + std::mem::size_of::<T>() * slice.len()`.
-	/// 	i.e. write is within storage's allocation.
-	/// * `pos` must be aligned for `T`.
+	/// Storage `capacity` must be greater or equal to
+	/// `pos + std::mem::size_of::<T>() * slice.len()`.
+	/// i.e. write is within storage's allocation.
+	///
+	/// `pos` must be aligned for `T`.
 	#[inline]
 	unsafe fn write_slice<T>(&mut self, pos: usize, slice: &[T]) {
 		debug_assert!(pos <= self.capacity);
@@ -0,0 +1,600 @@
+use std::{
+	alloc::Layout,
+	cmp, mem,
+	ptr::{self, NonNull},
+	slice,
+};
+
+use super::{ContiguousStorage, RandomAccessStorage, Storage};
+use crate::{
+	fallible::{self, SerializeError},
+	util::{align_up_to, aligned_max_capacity, is_aligned_to},
+};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+const DEFAULT_STORAGE_ALIGNMENT: usize = 16;
+const DEFAULT_VALUE_ALIGNMENT: usize = PTR_SIZE;
+const DEFAULT_MAX_CAPACITY: usize = aligned_max_capacity(DEFAULT_STORAGE_ALIGNMENT);
+
+/// Trait for arena allocators which [`ArenaStorage`] can allocate from.
+///
+/// Implement this trait for your own arena (e.g. a bump allocator) to have
+/// serializer output allocated from it.
+///
+/// Memory allocated from an arena is never freed individually. It's owned by
+/// the arena, and is freed when the arena is dropped or reset.
+///
+/// # Safety
+///
+/// Memory returned by [`alloc`] and [`grow`] must:
+///
+/// * be valid for reads and writes of the requested size.
+/// * be aligned to the requested alignment.
+/// * not overlap any other allocation.
+/// * remain valid for as long as the arena is borrowed immutably (i.e. the
+///   arena can only free memory when it's dropped, or via a method which takes
+///   `&mut self`).
+///
+/// [`alloc`]: Arena::alloc
+/// [`grow`]: Arena::grow
+pub unsafe trait Arena {
+	/// Allocate memory with size and alignment of `layout`.
+	///
+	/// `layout` will never have size 0.
+	///
+	/// If allocation fails, should panic or call
+	/// [`handle_alloc_error`](std::alloc::handle_alloc_error).
+	fn alloc(&self, layout: Layout) -> NonNull<u8>;
+
+	/// Grow an allocation to `new_size` bytes.
+	///
+	/// Returns pointer to the new allocation, which contains the contents of the
+	/// old allocation. After this call, old allocation will not be used again by
+	/// the caller.
+	///
+	/// Default implementation allocates a new region with [`alloc`] and copies
+	/// contents of the old allocation into it. Arenas which can extend their most
+	/// recent allocation in place should override this.
+	///
+	/// # Safety
+	///
+	/// * `ptr` must have been returned by [`alloc`] or [`grow`] on this arena.
+	/// * `old_layout` must be the layout which `ptr` was allocated with.
+	/// * `new_size` must be greater than `old_layout.size()`.
+	/// * `new_size`, rounded up to `old_layout.align()`, must not exceed
+	///   `isize::MAX`.
+	///
+	/// [`alloc`]: Arena::alloc
+	/// [`grow`]: Arena::grow
+	unsafe fn grow(&self, ptr: NonNull<u8>, old_layout: Layout, new_size: usize) -> NonNull<u8> {
+		debug_assert!(new_size > old_layout.size());
+
+		let new_layout = Layout::from_size_align_unchecked(new_size, old_layout.align());
+		let new_ptr = self.alloc(new_layout);
+		ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), old_layout.size());
+		new_ptr
+	}
+}
+
+/// Aligned contiguous memory buffer allocated from an [`Arena`], which can
+/// grow.
+///
+/// Buffer is allocated from the arena supplied by caller. Output of a
+/// serialization is always contiguous. If buffer runs out of capacity, a larger
+/// buffer is requested from the arena with [`Arena::grow`], which may extend
+/// the buffer in place, or relocate it. Memory is never freed by
+/// [`ArenaStorage`] - it's owned by the arena.
+///
+/// Growth follows the same pattern as [`AlignedVec`], and buffer is always
+/// aligned to `STORAGE_ALIGNMENT`, with capacity a multiple of
+/// `MAX_VALUE_ALIGNMENT`.
+///
+/// [`into_slice`] returns output as a slice which lives as long as the arena.
+///
+/// Supports random access reads and writes via [`RandomAccessStorage`] trait.
+///
+/// See [`Storage`] trait for details of the const parameters.
+///
+/// # Example
+///
+/// ```
+/// use std::{
+///     alloc::{self, Layout},
+///     cell::RefCell,
+///     ptr::NonNull,
+/// };
+/// use ser_raw::{
+///     pos::{PosMapping, Ptrs},
+///     storage::{Arena, ArenaStorage, RandomAccessStorage},
+///     util::aligned_max_capacity,
+///     Serialize, Serializer,
+/// };
+///
+/// // Very simple arena, which frees all its allocations when it's dropped
+/// #[derive(Default)]
+/// struct MyArena {
+///     allocations: RefCell<Vec<(NonNull<u8>, Layout)>>,
+/// }
+///
+/// unsafe impl Arena for MyArena {
+///     fn alloc(&self, layout: Layout) -> NonNull<u8> {
+///         let ptr = NonNull::new(unsafe { alloc::alloc(layout) })
+///             .unwrap_or_else(|| alloc::handle_alloc_error(layout));
+///         self.allocations.borrow_mut().push((ptr, layout));
+///         ptr
+///     }
+/// }
+///
+/// impl Drop for MyArena {
+///     fn drop(&mut self) {
+///         for &(ptr, layout) in self.allocations.borrow().iter() {
+///             unsafe { alloc::dealloc(ptr.as_ptr(), layout) };
+///         }
+///     }
+/// }
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Store<'a> = ArenaStorage<'a, MyArena, 16, 16, 8, MAX_CAPACITY>;
+///
+/// #[derive(Serializer)]
+/// #[ser_type(complete)]
+/// struct ArenaSerializer<'a> {
+///     #[ser_storage(Store<'a>)]
+///     storage: Store<'a>,
+///     #[ser_pos_mapping]
+///     pos_mapping: PosMapping,
+///     #[ser_ptrs]
+///     ptrs: Ptrs,
+/// }
+///
+/// let arena = MyArena::default();
+/// let ser = ArenaSerializer {
+///     storage: Store::new_in(&arena),
+///     pos_mapping: PosMapping::dummy(),
+///     ptrs: Ptrs::new(),
+/// };
+/// let input = vec![vec![1u8, 2, 3], vec![4, 5]];
+/// let (pos, storage) = ser.serialize(&input);
+/// let output: &Vec<Vec<u8>> = unsafe { storage.read(pos) };
+/// assert_eq!(output, &input);
+/// ```
+///
+/// [`AlignedVec`]: super::AlignedVec
+/// [`into_slice`]: ArenaStorage::into_slice
+pub struct ArenaStorage<
+	'a,
+	A: Arena,
+	const STORAGE_ALIGNMENT: usize = DEFAULT_STORAGE_ALIGNMENT,
+	const MAX_VALUE_ALIGNMENT: usize = STORAGE_ALIGNMENT,
+	const VALUE_ALIGNMENT: usize = DEFAULT_VALUE_ALIGNMENT,
+	const MAX_CAPACITY: usize = DEFAULT_MAX_CAPACITY,
+> {
+	ptr: NonNull<u8>,
+	capacity: usize,
+	pos: usize,
+	arena: Option<&'a A>,
+}
+
+impl<
+		'a,
+		A: Arena,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> ArenaStorage<'a, A, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Create new [`ArenaStorage`] allocating from `arena`, with no
+	/// pre-allocated capacity.
+	#[inline]
+	pub fn new_in(arena: &'a A) -> Self {
+		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_ALIGNMENTS_VALID;
+
+		Self {
+			ptr: NonNull::dangling(),
+			capacity: 0,
+			pos: 0,
+			arena: Some(arena),
+		}
+	}
+
+	/// Create new [`ArenaStorage`] allocating from `arena`, with pre-allocated
+	/// capacity.
+	///
+	/// Capacity will be rounded up to a multiple of `MAX_VALUE_ALIGNMENT`.
+	///
+	/// # Panics
+	///
+	/// Panics if `capacity` exceeds `MAX_CAPACITY`.
+	pub fn with_capacity_in(capacity: usize, arena: &'a A) -> Self {
+		let mut storage = Self::new_in(arena);
+		if capacity > 0 {
+			assert!(
+				capacity <= MAX_CAPACITY,
+				"capacity cannot exceed MAX_CAPACITY"
+			);
+			let capacity = align_up_to(capacity, MAX_VALUE_ALIGNMENT);
+			storage.ptr = arena.alloc(Self::layout_for(capacity));
+			storage.capacity = capacity;
+		}
+		storage
+	}
+
+	/// Consume storage and return its contents as a slice, which lives as long
+	/// as the arena.
+	#[inline]
+	pub fn into_slice(self) -> &'a mut [u8] {
+		// Memory is owned by arena, not the storage, and remains valid for `'a`
+		unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.pos) }
+	}
+
+	/// Extend capacity after `reserve` has found it's necessary.
+	///
+	/// Same growth strategy as `AlignedVec`.
+	///
+	/// Returns an error if `MAX_CAPACITY` or byte budget would be exceeded, or
+	/// storage has no arena.
+	#[cold]
+	fn grow_for_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		debug_assert!(additional > 0);
+
+		// Storage without an arena cannot grow beyond 0 capacity
+		let arena = self.arena.ok_or_else(fallible::capacity_exceeded::<Self>)?;
+
+		let required = self
+			.pos
+			.checked_add(additional)
+			.ok_or_else(fallible::capacity_exceeded::<Self>)?;
+
+		let mut new_cap = if required > MAX_CAPACITY.next_power_of_two() / 2 {
+			// Rounding up to next power of 2 would result in more than `MAX_CAPACITY`,
+			// so cap at max instead.
+			if required > MAX_CAPACITY {
+				return Err(fallible::capacity_exceeded::<Self>());
+			}
+			MAX_CAPACITY
+		} else {
+			// Cannot overflow due to check above
+			required.next_power_of_two()
+		};
+		fallible::check_budget(required)?;
+
+		// Ensuring at least `MAX_VALUE_ALIGNMENT` here makes sure capacity will always
+		// remain a multiple of `MAX_VALUE_ALIGNMENT` hereafter, as growth after this
+		// will be in powers of 2
+		new_cap = cmp::max(new_cap, MAX_VALUE_ALIGNMENT);
+		// Don't allocate more than byte budget allows
+		new_cap = fallible::limit_to_budget(new_cap, MAX_VALUE_ALIGNMENT);
+
+		self.ptr = if self.capacity == 0 {
+			arena.alloc(Self::layout_for(new_cap))
+		} else {
+			// `ptr` was allocated from this arena with current layout.
+			// `new_cap > capacity` and `new_cap <= MAX_CAPACITY`.
+			unsafe { arena.grow(self.ptr, Self::layout_for(self.capacity), new_cap) }
+		};
+		self.capacity = new_cap;
+		Ok(())
+	}
+
+	/// Get memory layout for a buffer of `capacity` bytes.
+	#[inline]
+	fn layout_for(capacity: usize) -> Layout {
+		// `capacity` cannot exceed `MAX_CAPACITY`, which cannot exceed
+		// `isize::MAX + 1 - STORAGE_ALIGNMENT`
+		unsafe { Layout::from_size_align_unchecked(capacity, STORAGE_ALIGNMENT) }
+	}
+}
+
+impl<
+		'a,
+		A: Arena,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Storage
+	for ArenaStorage<'a, A, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Alignment of storage's memory buffer.
+	///
+	/// See [`Storage`] trait for explanation.
+	const STORAGE_ALIGNMENT: usize = STORAGE_ALIGNMENT;
+
+	/// Maximum alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_VALUE_ALIGNMENT: usize = MAX_VALUE_ALIGNMENT;
+
+	/// Typical alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const VALUE_ALIGNMENT: usize = VALUE_ALIGNMENT;
+
+	/// Maximum capacity of storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_CAPACITY: usize = MAX_CAPACITY;
+
+	/// Create new [`ArenaStorage`] with no arena.
+	///
+	/// Storage without an arena has capacity 0 and cannot grow, so nothing can
+	/// be pushed to it. Reserving capacity fails, as if `MAX_CAPACITY` was
+	/// exceeded ([`try_reserve`] returns
+	/// [`SerializeError::CapacityExceeded`]). Use [`new_in`] instead.
+	///
+	/// [`try_reserve`]: ArenaStorage::try_reserve
+	/// [`new_in`]: ArenaStorage::new_in
+	#[inline]
+	fn new() -> Self {
+		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_ALIGNMENTS_VALID;
+
+		Self {
+			ptr: NonNull::dangling(),
+			capacity: 0,
+			pos: 0,
+			arena: None,
+		}
+	}
+
+	/// Always fails with [`SerializeError::CapacityExceeded`], as
+	/// [`ArenaStorage`] cannot allocate without an arena. Use
+	/// [`with_capacity_in`] instead.
+	///
+	/// # Safety
+	///
+	/// No requirements, as this method never returns.
+	///
+	/// [`with_capacity_in`]: ArenaStorage::with_capacity_in
+	unsafe fn with_capacity_unchecked(_capacity: usize) -> Self {
+		fallible::capacity_exceeded::<Self>().fail();
+	}
+
+	/// Returns current capacity of storage in bytes.
+	#[inline]
+	fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Returns current position in storage.
+	#[inline]
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	/// Set current position in storage.
+	///
+	/// # Safety
+	///
+	/// * `new_pos` must be less than or equal to [`capacity()`].
+	/// * `new_pos` must be a multiple of [`VALUE_ALIGNMENT`].
+	///
+	/// [`capacity()`]: ArenaStorage::capacity
+	/// [`VALUE_ALIGNMENT`]: ArenaStorage::VALUE_ALIGNMENT
+	#[inline]
+	unsafe fn set_pos(&mut self, new_pos: usize) {
+		debug_assert!(new_pos <= self.capacity);
+		debug_assert!(is_aligned_to(new_pos, VALUE_ALIGNMENT));
+
+		self.pos = new_pos;
+	}
+
+	/// Push a slice of values `&T` to storage, without alignment checks and
+	/// without reserving capacity for it.
+	///
+	/// # Safety
+	///
+	/// Caller must ensure [`ArenaStorage`] has sufficient capacity.
+	///
+	/// `size` must be total size in bytes of `&[T]`.
+	/// i.e. `size = mem::size_of::<T>() * slice.len()`.
+	///
+	/// Caller must uphold alignment invariants. See
+	/// [`Storage::push_slice_unchecked`].
+	#[inline]
+	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize) {
+		debug_assert!(self.capacity - self.pos >= size);
+		debug_assert_eq!(size, mem::size_of::<T>() * slice.len());
+		debug_assert!(is_aligned_to(self.pos, mem::align_of::<T>()));
+
+		// Do nothing if ZST. This function will be compiled down to a no-op for ZSTs.
+		if mem::size_of::<T>() == 0 {
+			return;
+		}
+
+		self.write_slice(self.pos, slice);
+		self.pos += size;
+	}
+
+	/// Reserve capacity for at least `additional` more bytes to be inserted into
+	/// the [`ArenaStorage`], growing the buffer if required.
+	///
+	/// Growth of capacity occurs in powers of 2 up to [`MAX_CAPACITY`], and is
+	/// always at minimum [`MAX_VALUE_ALIGNMENT`].
+	///
+	/// # Panics
+	///
+	/// Panics if this reservation would cause [`ArenaStorage`] to exceed
+	/// [`MAX_CAPACITY`], or if storage has to grow and has no arena.
+	/// Use [`try_reserve`](ArenaStorage::try_reserve) to get an error instead.
+	///
+	/// [`MAX_CAPACITY`]: ArenaStorage::MAX_CAPACITY
+	/// [`MAX_VALUE_ALIGNMENT`]: ArenaStorage::MAX_VALUE_ALIGNMENT
+	#[inline]
+	fn reserve(&mut self, additional: usize) {
+		// Cannot wrap because capacity always exceeds pos,
+		// but avoids having to handle potential overflow here
+		let remaining = self.capacity.wrapping_sub(self.pos);
+		if additional > remaining {
+			if let Err(err) = self.grow_for_reserve(additional) {
+				err.fail();
+			}
+		}
+	}
+
+	/// Reserve capacity for at least `additional` more bytes to be inserted into
+	/// the [`ArenaStorage`], returning an error if it cannot.
+	///
+	/// Same as [`reserve`](ArenaStorage::reserve), except returns an error if
+	/// [`MAX_CAPACITY`] or byte budget of fallible serialization in progress
+	/// would be exceeded, or storage has to grow and has no arena. Failure to
+	/// allocate from the arena is handled by the [`Arena`].
+	///
+	/// [`MAX_CAPACITY`]: ArenaStorage::MAX_CAPACITY
+	#[inline]
+	fn try_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let remaining = self.capacity.wrapping_sub(self.pos);
+		if additional > remaining {
+			self.grow_for_reserve(additional)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Does nothing. Memory allocated from an [`Arena`] cannot be freed
+	/// individually.
+	#[inline]
+	fn shrink_to_fit(&mut self) {}
+}
+
+impl<
+		'a,
+		A: Arena,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> RandomAccessStorage
+	for ArenaStorage<'a, A, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Write a slice of values at a specific position in storage's buffer.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::write_slice`].
+	#[inline]
+	unsafe fn write_slice<T>(&mut self, pos: usize, slice: &[T]) {
+		debug_assert!(pos <= self.capacity);
+		debug_assert!(self.capacity - pos >= mem::size_of::<T>() * slice.len());
+		debug_assert!(is_aligned_to(pos, mem::align_of::<T>()));
+
+		// Do nothing if ZST. This function will be compiled down to a no-op for ZSTs.
+		if mem::size_of::<T>() == 0 {
+			return;
+		}
+
+		let src = slice.as_ptr();
+		let dst = self.ptr.as_ptr().add(pos) as *mut T;
+		ptr::copy_nonoverlapping(src, dst, slice.len());
+	}
+
+	/// Get immutable reference for a value at a specific position in storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::read`].
+	#[inline]
+	unsafe fn read<T>(&self, pos: usize) -> &T {
+		debug_assert!(pos + mem::size_of::<T>() <= self.pos);
+		debug_assert!(is_aligned_to(pos, mem::align_of::<T>()));
+
+		let ptr = self.ptr.as_ptr().add(pos) as *const T;
+		&*ptr
+	}
+
+	/// Get mutable reference for a value at a specific position in storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::read_mut`].
+	#[inline]
+	unsafe fn read_mut<T>(&mut self, pos: usize) -> &mut T {
+		debug_assert!(pos + mem::size_of::<T>() <= self.pos);
+		debug_assert!(is_aligned_to(pos, mem::align_of::<T>()));
+
+		let ptr = self.ptr.as_ptr().add(pos) as *mut T;
+		&mut *ptr
+	}
+
+	/// Returns a raw pointer to a position in the storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::ptr`].
+	#[inline]
+	unsafe fn ptr(&self, pos: usize) -> *const u8 {
+		debug_assert!(self.capacity > 0);
+		debug_assert!(pos <= self.capacity);
+
+		self.ptr.as_ptr().add(pos)
+	}
+
+	/// Returns an unsafe mutable pointer a position in the storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::mut_ptr`].
+	#[inline]
+	unsafe fn mut_ptr(&mut self, pos: usize) -> *mut u8 {
+		debug_assert!(self.capacity > 0);
+		debug_assert!(pos <= self.capacity);
+
+		self.ptr.as_ptr().add(pos)
+	}
+}
+
+impl<
+		'a,
+		A: Arena,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> ContiguousStorage
+	for ArenaStorage<'a, A, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Returns a raw pointer to the start of the storage's buffer, or a dangling
+	/// raw pointer valid for zero sized reads if the storage has no capacity.
+	#[inline]
+	fn as_ptr(&self) -> *const u8 {
+		self.ptr.as_ptr()
+	}
+
+	/// Returns an unsafe mutable pointer to the start of the storage's buffer, or
+	/// a dangling raw pointer valid for zero sized reads if the storage has no
+	/// capacity.
+	#[inline]
+	fn as_mut_ptr(&mut self) -> *mut u8 {
+		self.ptr.as_ptr()
+	}
+}
+
+// Safe to be `Send` and `Sync` if arena is `Sync` (so `&A` is `Send`), because
+// pointer is not aliased and does not use interior mutability.
+unsafe impl<
+		'a,
+		A: Arena + Sync,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Send
+	for ArenaStorage<'a, A, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+}
+
+unsafe impl<
+		'a,
+		A: Arena + Sync,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Sync
+	for ArenaStorage<'a, A, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+}
@@ -0,0 +1,213 @@
+use std::{marker::PhantomData, mem};
+
+use super::Storage;
+use crate::{
+	fallible::{self, SerializeError},
+	util::{align_up_to, is_aligned_to},
+};
+
+/// [`Storage`] which does not store anything, and only counts how many bytes
+/// are pushed to it.
+///
+/// Uses same const parameters as the [`Storage`] `S`, and same alignment
+/// rules as all [`Storage`]s, so [`pos()`] after pushing values is exactly
+/// the number of bytes `S` would contain after pushing the same values.
+///
+/// Used by [`SizeCountingSerializer`] to calculate size of output without
+/// producing it.
+///
+/// [`capacity()`] is the minimum capacity storage `S` would need to hold what
+/// has been pushed, rounded up to a multiple of `MAX_VALUE_ALIGNMENT`.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::{AlignedVec, CountingStorage, Storage},
+///     util::aligned_max_capacity,
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+///
+/// let mut storage = CountingStorage::<Store>::new();
+/// storage.push(&1u8);
+/// storage.push(&2u32);
+/// assert_eq!(storage.pos(), 16);
+///
+/// let mut aligned_vec = Store::new();
+/// aligned_vec.push(&1u8);
+/// aligned_vec.push(&2u32);
+/// assert_eq!(aligned_vec.pos(), 16);
+/// ```
+///
+/// [`pos()`]: CountingStorage::pos
+/// [`capacity()`]: CountingStorage::capacity
+/// [`SizeCountingSerializer`]: crate::SizeCountingSerializer
+pub struct CountingStorage<S: Storage> {
+	pos: usize,
+	capacity: usize,
+	_marker: PhantomData<S>,
+}
+
+impl<S: Storage> CountingStorage<S> {
+	/// Extend capacity after `reserve` has found it's necessary.
+	///
+	/// Returns an error if `MAX_CAPACITY` or byte budget would be exceeded.
+	#[cold]
+	fn grow_for_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let new_cap = self
+			.pos
+			.checked_add(additional)
+			.filter(|&new_cap| new_cap <= S::MAX_CAPACITY)
+			.ok_or_else(fallible::capacity_exceeded::<Self>)?;
+		fallible::check_budget(new_cap)?;
+		// Cannot overflow, as `MAX_CAPACITY` is a multiple of `MAX_VALUE_ALIGNMENT`
+		self.capacity = align_up_to(new_cap, S::MAX_VALUE_ALIGNMENT);
+		Ok(())
+	}
+}
+
+impl<S: Storage> Storage for CountingStorage<S> {
+	/// Alignment of storage's memory buffer.
+	///
+	/// Same as `S`'s. See [`Storage`] trait for explanation.
+	const STORAGE_ALIGNMENT: usize = S::STORAGE_ALIGNMENT;
+
+	/// Maximum alignment of values being added to storage.
+	///
+	/// Same as `S`'s. See [`Storage`] trait for explanation.
+	const MAX_VALUE_ALIGNMENT: usize = S::MAX_VALUE_ALIGNMENT;
+
+	/// Typical alignment of values being added to storage.
+	///
+	/// Same as `S`'s. See [`Storage`] trait for explanation.
+	const VALUE_ALIGNMENT: usize = S::VALUE_ALIGNMENT;
+
+	/// Maximum capacity of storage.
+	///
+	/// Same as `S`'s. See [`Storage`] trait for explanation.
+	const MAX_CAPACITY: usize = S::MAX_CAPACITY;
+
+	/// Create new [`CountingStorage`].
+	#[inline]
+	fn new() -> Self {
+		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_ALIGNMENTS_VALID;
+
+		Self {
+			pos: 0,
+			capacity: 0,
+			_marker: PhantomData,
+		}
+	}
+
+	/// Create new [`CountingStorage`] with initial capacity.
+	///
+	/// Nothing is allocated, so this only sets starting value of
+	/// [`capacity()`](CountingStorage::capacity).
+	///
+	/// # Safety
+	///
+	/// * `capacity` must not be 0.
+	/// * `capacity` must be less than or equal to `MAX_CAPACITY`.
+	/// * `capacity` must be a multiple of `MAX_VALUE_ALIGNMENT`.
+	#[inline]
+	unsafe fn with_capacity_unchecked(capacity: usize) -> Self {
+		let mut storage = Self::new();
+		storage.capacity = capacity;
+		storage
+	}
+
+	/// Returns current capacity of storage in bytes.
+	#[inline]
+	fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Returns current position in storage.
+	#[inline]
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	/// Set current position in storage.
+	///
+	/// # Safety
+	///
+	/// * `new_pos` must be less than or equal to [`capacity()`].
+	/// * `new_pos` must be a multiple of `VALUE_ALIGNMENT`.
+	///
+	/// [`capacity()`]: CountingStorage::capacity
+	#[inline]
+	unsafe fn set_pos(&mut self, new_pos: usize) {
+		debug_assert!(new_pos <= self.capacity);
+		debug_assert!(is_aligned_to(new_pos, S::VALUE_ALIGNMENT));
+
+		self.pos = new_pos;
+	}
+
+	/// Count a slice of values `&T` pushed to storage. Contents of the slice are
+	/// ignored.
+	///
+	/// # Safety
+	///
+	/// Caller must ensure [`CountingStorage`] has sufficient capacity.
+	///
+	/// `size` must be total size in bytes of `&[T]`.
+	/// i.e. `size = mem::size_of::<T>() * slice.len()`.
+	///
+	/// Caller must uphold alignment invariants. See
+	/// [`Storage::push_slice_unchecked`].
+	#[inline]
+	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize) {
+		debug_assert!(self.capacity - self.pos >= size);
+		debug_assert_eq!(size, mem::size_of::<T>() * slice.len());
+		debug_assert!(is_aligned_to(self.pos, mem::align_of::<T>()));
+
+		self.pos += size;
+	}
+
+	/// Reserve capacity for at least `additional` more bytes.
+	///
+	/// Capacity is only increased as far as required, rounded up to a multiple of
+	/// `MAX_VALUE_ALIGNMENT`.
+	///
+	/// # Panics
+	///
+	/// Panics if this reservation would cause [`CountingStorage`] to exceed
+	/// `MAX_CAPACITY`.
+	#[inline]
+	fn reserve(&mut self, additional: usize) {
+		// Cannot wrap because capacity always exceeds pos,
+		// but avoids having to handle potential overflow here
+		let remaining = self.capacity.wrapping_sub(self.pos);
+		if additional > remaining {
+			if let Err(err) = self.grow_for_reserve(additional) {
+				err.fail();
+			}
+		}
+	}
+
+	/// Reserve capacity for at least `additional` more bytes, returning an error
+	/// if this reservation would cause [`CountingStorage`] to exceed
+	/// `MAX_CAPACITY`, or byte budget of fallible serialization in progress.
+	#[inline]
+	fn try_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let remaining = self.capacity.wrapping_sub(self.pos);
+		if additional > remaining {
+			self.grow_for_reserve(additional)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Shrink capacity to current position, rounded up to a multiple of
+	/// `MAX_VALUE_ALIGNMENT`.
+	#[inline]
+	fn shrink_to_fit(&mut self) {
+		// Cannot overflow, as `pos <= capacity <= MAX_CAPACITY`
+		self.capacity = align_up_to(self.pos, S::MAX_VALUE_ALIGNMENT);
+	}
+}
@@ -0,0 +1,290 @@
+use std::mem;
+
+use super::{AlignedVec, ContiguousStorage, FixedStorage, RandomAccessStorage, Storage};
+use crate::{
+	fallible::{self, SerializeError},
+	util::aligned_max_capacity,
+};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+const DEFAULT_STORAGE_ALIGNMENT: usize = 16;
+const DEFAULT_VALUE_ALIGNMENT: usize = PTR_SIZE;
+const DEFAULT_MAX_CAPACITY: usize = aligned_max_capacity(DEFAULT_STORAGE_ALIGNMENT);
+
+/// Aligned contiguous memory buffer with fixed capacity.
+///
+/// Memory is allocated upfront by [`with_capacity`], and is never reallocated,
+/// so buffer never moves. Pushing more than capacity allows panics, rather
+/// than growing the buffer.
+///
+/// Implements [`FixedStorage`], so a Complete serializer using [`FixedVec`]
+/// does not need to record pointers for correction, and can omit its
+/// `#[ser_ptrs]` field. Pointers written to output are always accurate when
+/// they're written. Such a serializer's storage must not be replaced during a
+/// serialization - see [`FixedStorage`].
+///
+/// Otherwise same as [`AlignedVec`]. See [`Storage`] trait for details of the
+/// const parameters.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     pos::PosMapping,
+///     storage::{FixedVec, RandomAccessStorage, Storage},
+///     util::aligned_max_capacity,
+///     Serializer,
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Store = FixedVec<16, 16, 8, MAX_CAPACITY>;
+///
+/// // No `#[ser_ptrs]` field required
+/// #[derive(Serializer)]
+/// #[ser_type(complete)]
+/// struct FixedCompleteSerializer {
+///     #[ser_storage(Store)]
+///     storage: Store,
+///     #[ser_pos_mapping]
+///     pos_mapping: PosMapping,
+/// }
+///
+/// let ser = FixedCompleteSerializer {
+///     storage: Store::with_capacity(1024),
+///     pos_mapping: PosMapping::dummy(),
+/// };
+/// let input = vec![Box::new(1u8), Box::new(2u8)];
+/// let (pos, storage) = ser.serialize(&input);
+/// let output: &Vec<Box<u8>> = unsafe { storage.read(pos) };
+/// assert_eq!(output, &input);
+/// ```
+///
+/// [`with_capacity`]: Storage::with_capacity
+pub struct FixedVec<
+	const STORAGE_ALIGNMENT: usize = DEFAULT_STORAGE_ALIGNMENT,
+	const MAX_VALUE_ALIGNMENT: usize = STORAGE_ALIGNMENT,
+	const VALUE_ALIGNMENT: usize = DEFAULT_VALUE_ALIGNMENT,
+	const MAX_CAPACITY: usize = DEFAULT_MAX_CAPACITY,
+> {
+	inner: AlignedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>,
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Storage for FixedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Alignment of storage's memory buffer.
+	///
+	/// See [`Storage`] trait for explanation.
+	const STORAGE_ALIGNMENT: usize = STORAGE_ALIGNMENT;
+
+	/// Maximum alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_VALUE_ALIGNMENT: usize = MAX_VALUE_ALIGNMENT;
+
+	/// Typical alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const VALUE_ALIGNMENT: usize = VALUE_ALIGNMENT;
+
+	/// Maximum capacity of storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_CAPACITY: usize = MAX_CAPACITY;
+
+	/// Create new [`FixedVec`] with no capacity.
+	///
+	/// Nothing can be pushed to it. Use [`with_capacity`] instead.
+	///
+	/// [`with_capacity`]: Storage::with_capacity
+	#[inline]
+	fn new() -> Self {
+		Self {
+			inner: AlignedVec::new(),
+		}
+	}
+
+	/// Create new [`FixedVec`] with fixed capacity, without safety checks.
+	///
+	/// # Safety
+	///
+	/// * `capacity` must not be 0.
+	/// * `capacity` must be less than or equal to [`MAX_CAPACITY`].
+	/// * `capacity` must be a multiple of [`MAX_VALUE_ALIGNMENT`].
+	///
+	/// [`MAX_CAPACITY`]: FixedVec::MAX_CAPACITY
+	/// [`MAX_VALUE_ALIGNMENT`]: FixedVec::MAX_VALUE_ALIGNMENT
+	#[inline]
+	unsafe fn with_capacity_unchecked(capacity: usize) -> Self {
+		Self {
+			inner: AlignedVec::with_capacity_unchecked(capacity),
+		}
+	}
+
+	/// Returns capacity of storage in bytes.
+	#[inline]
+	fn capacity(&self) -> usize {
+		self.inner.capacity()
+	}
+
+	/// Returns current position in storage.
+	#[inline]
+	fn pos(&self) -> usize {
+		self.inner.pos()
+	}
+
+	/// Set current position in storage.
+	///
+	/// # Safety
+	///
+	/// * `new_pos` must be less than or equal to [`capacity()`].
+	/// * `new_pos` must be a multiple of [`VALUE_ALIGNMENT`].
+	///
+	/// [`capacity()`]: FixedVec::capacity
+	/// [`VALUE_ALIGNMENT`]: FixedVec::VALUE_ALIGNMENT
+	#[inline]
+	unsafe fn set_pos(&mut self, new_pos: usize) {
+		self.inner.set_pos(new_pos);
+	}
+
+	/// Push a slice of values `&T` to storage, without alignment checks and
+	/// without reserving capacity for it.
+	///
+	/// # Safety
+	///
+	/// See [`Storage::push_slice_unchecked`].
+	#[inline]
+	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize) {
+		self.inner.push_slice_unchecked(slice, size);
+	}
+
+	/// Check there's capacity for `additional` more bytes to be inserted into
+	/// the [`FixedVec`].
+	///
+	/// # Panics
+	///
+	/// Panics if there is insufficient capacity. [`FixedVec`] never grows.
+	#[inline]
+	fn reserve(&mut self, additional: usize) {
+		// Cannot wrap because capacity always exceeds pos
+		let remaining = self.capacity().wrapping_sub(self.pos());
+		if additional > remaining {
+			fallible::capacity_exceeded::<Self>().fail();
+		}
+	}
+
+	/// Check there's capacity for `additional` more bytes to be inserted into
+	/// the [`FixedVec`], returning an error if there is not.
+	#[inline]
+	fn try_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let remaining = self.capacity().wrapping_sub(self.pos());
+		if additional > remaining {
+			Err(fallible::capacity_exceeded::<Self>())
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Does nothing. [`FixedVec`] cannot be shrunk, as that could move its
+	/// buffer.
+	#[inline]
+	fn shrink_to_fit(&mut self) {}
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> RandomAccessStorage
+	for FixedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Write a slice of values at a specific position in storage's buffer.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::write_slice`].
+	#[inline]
+	unsafe fn write_slice<T>(&mut self, pos: usize, slice: &[T]) {
+		self.inner.write_slice(pos, slice);
+	}
+
+	/// Get immutable reference for a value at a specific position in storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::read`].
+	#[inline]
+	unsafe fn read<T>(&self, pos: usize) -> &T {
+		self.inner.read(pos)
+	}
+
+	/// Get mutable reference for a value at a specific position in storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::read_mut`].
+	#[inline]
+	unsafe fn read_mut<T>(&mut self, pos: usize) -> &mut T {
+		self.inner.read_mut(pos)
+	}
+
+	/// Returns a raw pointer to a position in the storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::ptr`].
+	#[inline]
+	unsafe fn ptr(&self, pos: usize) -> *const u8 {
+		self.inner.ptr(pos)
+	}
+
+	/// Returns an unsafe mutable pointer a position in the storage.
+	///
+	/// # Safety
+	///
+	/// See [`RandomAccessStorage::mut_ptr`].
+	#[inline]
+	unsafe fn mut_ptr(&mut self, pos: usize) -> *mut u8 {
+		self.inner.mut_ptr(pos)
+	}
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> ContiguousStorage
+	for FixedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Returns a raw pointer to the start of the storage's buffer, or a dangling
+	/// raw pointer valid for zero sized reads if the storage has no capacity.
+	#[inline]
+	fn as_ptr(&self) -> *const u8 {
+		self.inner.as_ptr()
+	}
+
+	/// Returns an unsafe mutable pointer to the start of the storage's buffer, or
+	/// a dangling raw pointer valid for zero sized reads if the storage has no
+	/// capacity.
+	#[inline]
+	fn as_mut_ptr(&mut self) -> *mut u8 {
+		self.inner.as_mut_ptr()
+	}
+}
+
+// Buffer is allocated on creation, and `reserve` and `shrink_to_fit` never
+// reallocate it
+unsafe impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> FixedStorage for FixedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+}
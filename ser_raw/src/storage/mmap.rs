@@ -0,0 +1,1028 @@
+use std::{
+	alloc::Layout,
+	cmp,
+	fs::{File, OpenOptions},
+	io, mem,
+	os::unix::io::{AsRawFd, FromRawFd, RawFd},
+	path::Path,
+	process,
+	ptr::{self, NonNull},
+	slice,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::{ContiguousStorage, RandomAccessStorage, Storage};
+use crate::{
+	fallible::{self, SerializeError},
+	header::{self, HeaderError},
+	util::{align_up_to, aligned_max_capacity, is_aligned_to},
+	Serialize, Serializer,
+};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+const DEFAULT_STORAGE_ALIGNMENT: usize = 16;
+const DEFAULT_VALUE_ALIGNMENT: usize = PTR_SIZE;
+const DEFAULT_MAX_CAPACITY: usize = aligned_max_capacity(DEFAULT_STORAGE_ALIGNMENT);
+
+/// Minimum page size on all supported platforms.
+/// Memory maps are always aligned to page size, so `STORAGE_ALIGNMENT` cannot
+/// exceed this.
+const MIN_PAGE_SIZE: usize = 4096;
+
+/// Contiguous storage backed by a memory-mapped file, which can grow.
+///
+/// Output is written directly into the file, so no copy is required to write
+/// output to disk once serialization is complete. The file grows as required
+/// when capacity is reserved, in the same way as [`AlignedVec`].
+///
+/// While serializing, the file's length is the storage's capacity. When the
+/// storage is finished with [`into_file`] or dropped, the file is truncated to
+/// [`pos()`], so it contains only the output.
+///
+/// A file written by [`MmapStorage`] can be reopened with [`open`], which maps
+/// it into memory without copying it.
+///
+/// File is mapped with `MAP_SHARED`, so any change made to it by another
+/// process (or by other code in this process, via another handle) is visible in
+/// the storage's memory. Constructors which take a path or a file are therefore
+/// `unsafe`. Caller must ensure nothing else modifies or truncates the file
+/// while the storage exists.
+///
+/// Supports random access reads and writes via [`RandomAccessStorage`] trait.
+///
+/// See [`Storage`] trait for details of the const parameters.
+/// `STORAGE_ALIGNMENT` cannot exceed 4096.
+///
+/// Only available on Unix, with `mmap` feature enabled.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::{ContiguousStorage, MmapStorage, Storage},
+///     util::aligned_max_capacity,
+///     Serialize, Serializer,
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Store = MmapStorage<16, 16, 8, MAX_CAPACITY>;
+///
+/// #[derive(Serializer)]
+/// #[ser_type(pure_copy)]
+/// struct MmapSerializer {
+///     #[ser_storage(Store)]
+///     storage: Store,
+/// }
+///
+/// # let dir = std::env::temp_dir().join("ser_raw_mmap_doctest");
+/// # std::fs::create_dir_all(&dir).unwrap();
+/// # let path = dir.join("output.bin");
+/// // Nothing else modifies the file
+/// let storage = unsafe { Store::create(&path) }.unwrap();
+/// let ser = MmapSerializer { storage };
+/// let (_, storage) = ser.serialize(&vec![1u32, 2, 3]);
+/// let len = storage.pos();
+/// storage.into_file().unwrap();
+///
+/// // Reopen file without copying it
+/// let storage = unsafe { Store::open(&path) }.unwrap();
+/// assert_eq!(storage.pos(), len);
+/// # drop(storage);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+///
+/// [`AlignedVec`]: super::AlignedVec
+/// [`into_file`]: MmapStorage::into_file
+/// [`open`]: MmapStorage::open
+/// [`pos()`]: Storage::pos
+pub struct MmapStorage<
+	const STORAGE_ALIGNMENT: usize = DEFAULT_STORAGE_ALIGNMENT,
+	const MAX_VALUE_ALIGNMENT: usize = STORAGE_ALIGNMENT,
+	const VALUE_ALIGNMENT: usize = DEFAULT_VALUE_ALIGNMENT,
+	const MAX_CAPACITY: usize = DEFAULT_MAX_CAPACITY,
+> {
+	ptr: NonNull<u8>,
+	capacity: usize,
+	pos: usize,
+	file: File,
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Assertion that memory maps' alignment satisfies `STORAGE_ALIGNMENT`.
+	const ASSERT_STORAGE_ALIGNMENT_VALID: () = assert!(
+		STORAGE_ALIGNMENT <= MIN_PAGE_SIZE,
+		"MmapStorage's STORAGE_ALIGNMENT cannot exceed 4096"
+	);
+
+	/// Create new [`MmapStorage`] writing to file at `path`.
+	///
+	/// File is created if it doesn't exist, and truncated if it does.
+	///
+	/// # Safety
+	///
+	/// File must not be modified or truncated by anything else while the storage
+	/// exists. See [`MmapStorage`].
+	pub unsafe fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		let file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.open(path)?;
+		Self::from_file(file)
+	}
+
+	/// Create new [`MmapStorage`] writing to file at `path`, with capacity
+	/// pre-allocated.
+	///
+	/// File is created if it doesn't exist, and truncated if it does.
+	///
+	/// Capacity will be rounded up to a multiple of `MAX_VALUE_ALIGNMENT`.
+	///
+	/// # Panics
+	///
+	/// Panics if `capacity` exceeds `MAX_CAPACITY`.
+	///
+	/// # Safety
+	///
+	/// File must not be modified or truncated by anything else while the storage
+	/// exists. See [`MmapStorage`].
+	pub unsafe fn create_with_capacity<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+		let mut storage = Self::create(path)?;
+		storage.try_reserve_capacity(capacity)?;
+		Ok(storage)
+	}
+
+	/// Create new [`MmapStorage`] writing to an existing [`File`].
+	///
+	/// File must be opened for reading and writing. Any existing contents of the
+	/// file will be overwritten.
+	///
+	/// # Safety
+	///
+	/// File must not be modified or truncated by anything else (including via
+	/// other handles to the same file) while the storage exists.
+	/// See [`MmapStorage`].
+	pub unsafe fn from_file(file: File) -> io::Result<Self> {
+		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_ALIGNMENTS_VALID;
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_STORAGE_ALIGNMENT_VALID;
+
+		file.set_len(0)?;
+		Ok(Self {
+			ptr: NonNull::dangling(),
+			capacity: 0,
+			pos: 0,
+			file,
+		})
+	}
+
+	/// Create new [`MmapStorage`] backed by an anonymous shared memory segment.
+	///
+	/// Segment's file descriptor is available from [`file()`] or [`as_raw_fd()`],
+	/// and can be passed to another process, which can map it with
+	/// [`MmapReader`] to read the output without copying it.
+	///
+	/// On Linux and Android, segment is created with `memfd_create`. On other
+	/// platforms, it's a temporary file which is deleted immediately.
+	///
+	/// File descriptor is close-on-exec. To pass it to a child process, either
+	/// clear that flag before spawning the child, or send it over a Unix socket.
+	///
+	/// # Safety
+	///
+	/// Segment must not be modified or truncated by anything else (e.g. a process
+	/// its file descriptor is passed to) while the storage exists.
+	/// See [`MmapStorage`].
+	///
+	/// [`file()`]: MmapStorage::file
+	/// [`as_raw_fd()`]: AsRawFd::as_raw_fd
+	pub unsafe fn shared_memory() -> io::Result<Self> {
+		Self::from_file(shared_memory_file()?)
+	}
+
+	/// Open existing file at `path` previously written by [`MmapStorage`], and
+	/// map its contents into memory, without copying it.
+	///
+	/// [`pos()`](Storage::pos) is set to the end of the file, so further values
+	/// can be pushed to storage after existing contents.
+	///
+	/// File must be writable, as its length may be extended to a
+	/// multiple of `MAX_VALUE_ALIGNMENT`. It's truncated to its original
+	/// length again when storage is dropped.
+	///
+	/// Returns an error if file's length is not a multiple of `VALUE_ALIGNMENT`,
+	/// or exceeds `MAX_CAPACITY`.
+	///
+	/// # Safety
+	///
+	/// File must not be modified or truncated by anything else while the storage
+	/// exists. See [`MmapStorage`].
+	pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_ALIGNMENTS_VALID;
+		#[allow(clippy::let_unit_value)]
+		let _ = Self::ASSERT_STORAGE_ALIGNMENT_VALID;
+
+		let file = OpenOptions::new().read(true).write(true).open(path)?;
+		let len = file.metadata()?.len();
+		let len = match usize::try_from(len) {
+			Ok(len) if len <= MAX_CAPACITY => len,
+			_ => return Err(invalid_data("File exceeds MAX_CAPACITY")),
+		};
+		if !is_aligned_to(len, VALUE_ALIGNMENT) {
+			return Err(invalid_data(
+				"File length is not a multiple of VALUE_ALIGNMENT",
+			));
+		}
+
+		// `pos` is set before mapping, so file is restored to its original length
+		// if mapping fails and storage is dropped
+		let mut storage = Self {
+			ptr: NonNull::dangling(),
+			capacity: 0,
+			pos: len,
+			file,
+		};
+		storage.try_reserve_capacity(len)?;
+		Ok(storage)
+	}
+
+	/// Get reference to the underlying [`File`].
+	#[inline]
+	pub fn file(&self) -> &File {
+		&self.file
+	}
+
+	/// Flush contents of storage to disk.
+	pub fn flush(&self) -> io::Result<()> {
+		if self.capacity > 0 {
+			let res = unsafe {
+				libc::msync(
+					self.ptr.as_ptr() as *mut libc::c_void,
+					self.capacity,
+					libc::MS_SYNC,
+				)
+			};
+			if res != 0 {
+				return Err(io::Error::last_os_error());
+			}
+		}
+		Ok(())
+	}
+
+	/// Consume storage, truncating file to length of storage's contents,
+	/// and return the underlying [`File`].
+	pub fn into_file(self) -> io::Result<File> {
+		let mut storage = mem::ManuallyDrop::new(self);
+		let res = storage.unmap_and_truncate();
+		// `storage` is not dropped, so move `file` out of it, and drop nothing else.
+		// No other fields own anything.
+		let file = unsafe { ptr::read(&storage.file) };
+		res.map(|_| file)
+	}
+
+	/// Grow capacity to at least `capacity` bytes, returning an error if
+	/// growing the file or mapping it fails.
+	///
+	/// # Panics
+	///
+	/// Panics if `capacity` exceeds `MAX_CAPACITY`.
+	fn try_reserve_capacity(&mut self, capacity: usize) -> io::Result<()> {
+		assert!(
+			capacity <= MAX_CAPACITY,
+			"capacity cannot exceed MAX_CAPACITY"
+		);
+		let capacity = align_up_to(capacity, MAX_VALUE_ALIGNMENT);
+		if capacity > self.capacity {
+			unsafe { self.remap(capacity) }?;
+		}
+		Ok(())
+	}
+
+	/// Extend capacity after `reserve` has found it's necessary.
+	///
+	/// Panics if growing the file fails, unless fallible serialization is in
+	/// progress.
+	#[cold]
+	fn grow_for_reserve(&mut self, additional: usize) {
+		let new_cap = self
+			.capacity_for_reserve(additional)
+			.unwrap_or_else(|err| err.fail());
+
+		if let Err(err) = unsafe { self.grow_to(new_cap) } {
+			if fallible::in_progress() {
+				Self::alloc_failed(new_cap).fail();
+			}
+			panic!("Cannot grow MmapStorage: {err}");
+		}
+	}
+
+	/// Extend capacity after `try_reserve` has found it's necessary.
+	///
+	/// Returns an error if `MAX_CAPACITY` or byte budget would be exceeded,
+	/// or growing the file or mapping it fails.
+	#[cold]
+	fn try_grow_for_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let new_cap = self.capacity_for_reserve(additional)?;
+		unsafe { self.grow_to(new_cap) }.map_err(|_| Self::alloc_failed(new_cap))
+	}
+
+	/// Calculate capacity to grow to, to fit `additional` more bytes.
+	///
+	/// Growth policy is same as [`AlignedVec`](super::AlignedVec), except
+	/// minimum capacity is 4 KiB.
+	///
+	/// Returns an error if `MAX_CAPACITY` or byte budget would be exceeded.
+	fn capacity_for_reserve(&self, additional: usize) -> Result<usize, SerializeError> {
+		debug_assert!(additional > 0);
+
+		let required = self
+			.pos
+			.checked_add(additional)
+			.ok_or_else(fallible::capacity_exceeded::<Self>)?;
+
+		let new_cap = if required > MAX_CAPACITY.next_power_of_two() / 2 {
+			// Rounding up to next power of 2 would result in more than `MAX_CAPACITY`,
+			// so cap at max instead.
+			if required > MAX_CAPACITY {
+				return Err(fallible::capacity_exceeded::<Self>());
+			}
+			MAX_CAPACITY
+		} else {
+			// Cannot overflow due to check above.
+			// `MIN_PAGE_SIZE` is a multiple of `MAX_VALUE_ALIGNMENT`, as
+			// `MAX_VALUE_ALIGNMENT <= STORAGE_ALIGNMENT <= MIN_PAGE_SIZE`.
+			cmp::min(
+				cmp::max(required.next_power_of_two(), MIN_PAGE_SIZE),
+				MAX_CAPACITY,
+			)
+		};
+		fallible::check_budget(required)?;
+
+		// Don't grow file more than byte budget allows
+		Ok(fallible::limit_to_budget(new_cap, MAX_VALUE_ALIGNMENT))
+	}
+
+	/// Grow file to `new_cap` and map it into memory.
+	///
+	/// If this fails, previous mapping is restored, so storage remains usable.
+	///
+	/// # Panics
+	///
+	/// Panics if restoring previous mapping fails.
+	///
+	/// # Safety
+	///
+	/// Same as [`remap`](MmapStorage::remap).
+	unsafe fn grow_to(&mut self, new_cap: usize) -> io::Result<()> {
+		let old_cap = self.capacity;
+		if let Err(err) = self.remap(new_cap) {
+			if let Err(restore_err) = self.remap(old_cap) {
+				panic!("Cannot grow MmapStorage: {err}, and cannot restore it: {restore_err}");
+			}
+			return Err(err);
+		}
+		Ok(())
+	}
+
+	/// Create [`SerializeError::AllocFailed`] error for growing to `new_cap`.
+	fn alloc_failed(new_cap: usize) -> SerializeError {
+		// `new_cap` cannot exceed `MAX_CAPACITY`, which cannot exceed
+		// `isize::MAX + 1 - STORAGE_ALIGNMENT`
+		let layout = unsafe { Layout::from_size_align_unchecked(new_cap, STORAGE_ALIGNMENT) };
+		SerializeError::AllocFailed { layout }
+	}
+
+	/// Resize file to `new_cap` and map it into memory.
+	/// Any existing map is unmapped first.
+	///
+	/// # Safety
+	///
+	/// * `new_cap` must be greater than or equal to `self.pos`.
+	/// * `new_cap` must not exceed `MAX_CAPACITY`.
+	/// * `new_cap` must be a multiple of `MAX_VALUE_ALIGNMENT`.
+	unsafe fn remap(&mut self, new_cap: usize) -> io::Result<()> {
+		debug_assert!(new_cap >= self.pos);
+		debug_assert!(new_cap <= MAX_CAPACITY);
+		debug_assert!(is_aligned_to(new_cap, MAX_VALUE_ALIGNMENT));
+
+		self.unmap();
+		self.file.set_len(new_cap as u64)?;
+		if new_cap > 0 {
+			self.ptr = map(&self.file, new_cap)?;
+			self.capacity = new_cap;
+		}
+		Ok(())
+	}
+
+	/// Unmap memory, if mapped.
+	fn unmap(&mut self) {
+		if self.capacity > 0 {
+			// `munmap` can only fail if arguments are invalid, which they're not
+			unsafe { libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.capacity) };
+			self.ptr = NonNull::dangling();
+			self.capacity = 0;
+		}
+	}
+
+	/// Unmap memory and truncate file to length of contents.
+	fn unmap_and_truncate(&mut self) -> io::Result<()> {
+		self.unmap();
+		self.file.set_len(self.pos as u64)
+	}
+}
+
+/// Map `len` bytes of `file` into memory, shared and writable.
+///
+/// # Safety
+///
+/// `len` must not be 0, and file must be at least `len` bytes long.
+unsafe fn map(file: &File, len: usize) -> io::Result<NonNull<u8>> {
+	let ptr = libc::mmap(
+		ptr::null_mut(),
+		len,
+		libc::PROT_READ | libc::PROT_WRITE,
+		libc::MAP_SHARED,
+		file.as_raw_fd(),
+		0,
+	);
+	if ptr == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(NonNull::new_unchecked(ptr as *mut u8))
+}
+
+/// Create an anonymous shared memory file.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn shared_memory_file() -> io::Result<File> {
+	let fd = unsafe { libc::memfd_create(b"ser_raw\0".as_ptr().cast(), libc::MFD_CLOEXEC) };
+	if fd == -1 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// Create an anonymous shared memory file.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn shared_memory_file() -> io::Result<File> {
+	temp_file()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Create a temporary file, which is deleted immediately, so it's removed
+/// from disk when it's closed.
+fn temp_file() -> io::Result<File> {
+	static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+	let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+	let path = std::env::temp_dir().join(format!("ser_raw_mmap_{}_{id}", process::id()));
+	let file = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.create_new(true)
+		.open(&path)?;
+	std::fs::remove_file(&path)?;
+	Ok(file)
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Storage for MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Alignment of storage's memory buffer.
+	///
+	/// See [`Storage`] trait for explanation.
+	const STORAGE_ALIGNMENT: usize = STORAGE_ALIGNMENT;
+
+	/// Maximum alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_VALUE_ALIGNMENT: usize = MAX_VALUE_ALIGNMENT;
+
+	/// Typical alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const VALUE_ALIGNMENT: usize = VALUE_ALIGNMENT;
+
+	/// Maximum capacity of storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_CAPACITY: usize = MAX_CAPACITY;
+
+	/// Create new [`MmapStorage`] backed by a temporary file, with no
+	/// pre-allocated capacity.
+	///
+	/// Temporary file is deleted when storage is dropped. Use
+	/// [`create`](MmapStorage::create) to write to a file which persists.
+	///
+	/// # Panics
+	///
+	/// Panics if temporary file cannot be created.
+	fn new() -> Self {
+		let file = temp_file().expect("Cannot create temporary file for MmapStorage");
+		// Safe because temporary file is deleted immediately, so nothing else can
+		// open it
+		unsafe { Self::from_file(file) }.expect("Cannot create MmapStorage")
+	}
+
+	/// Create new [`MmapStorage`] backed by a temporary file, with
+	/// pre-allocated capacity, without safety checks.
+	///
+	/// # Safety
+	///
+	/// * `capacity` must not be 0.
+	/// * `capacity` must be less than or equal to [`MAX_CAPACITY`].
+	/// * `capacity` must be a multiple of [`MAX_VALUE_ALIGNMENT`].
+	///
+	/// # Panics
+	///
+	/// Panics if temporary file cannot be created or mapped.
+	///
+	/// [`MAX_CAPACITY`]: MmapStorage::MAX_CAPACITY
+	/// [`MAX_VALUE_ALIGNMENT`]: MmapStorage::MAX_VALUE_ALIGNMENT
+	unsafe fn with_capacity_unchecked(capacity: usize) -> Self {
+		debug_assert!(capacity > 0, "capacity cannot be 0");
+		debug_assert!(
+			capacity <= MAX_CAPACITY,
+			"capacity cannot exceed MAX_CAPACITY"
+		);
+		debug_assert!(is_aligned_to(capacity, MAX_VALUE_ALIGNMENT));
+
+		let mut storage = Self::new();
+		if let Err(err) = storage.remap(capacity) {
+			panic!("Cannot create MmapStorage: {err}");
+		}
+		storage
+	}
+
+	/// Returns current capacity of storage in bytes.
+	#[inline]
+	fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Returns current position in storage.
+	#[inline]
+	fn pos(&self) -> usize {
+		self.pos
+	}
+
+	/// Set current position in storage.
+	///
+	/// # Safety
+	///
+	/// * `new_pos` must be less than or equal to [`capacity()`].
+	/// * `new_pos` must be a multiple of [`VALUE_ALIGNMENT`].
+	///
+	/// [`capacity()`]: MmapStorage::capacity
+	/// [`VALUE_ALIGNMENT`]: MmapStorage::VALUE_ALIGNMENT
+	#[inline]
+	unsafe fn set_pos(&mut self, new_pos: usize) {
+		debug_assert!(new_pos <= self.capacity);
+		debug_assert!(is_aligned_to(new_pos, VALUE_ALIGNMENT));
+
+		self.pos = new_pos;
+	}
+
+	/// Push a slice of values `&T` to storage, without alignment checks and
+	/// without reserving capacity for it.
+	///
+	/// # Safety
+	///
+	/// Caller must ensure [`MmapStorage`] has sufficient capacity.
+	///
+	/// `size` must be total size in bytes of `&[T]`.
+	/// i.e. `size = mem::size_of::<T>() * slice.len()`.
+	///
+	/// Caller must uphold alignment invariants. See
+	/// [`Storage::push_slice_unchecked`].
+	#[inline]
+	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize) {
+		debug_assert!(self.capacity - self.pos >= size);
+		debug_assert_eq!(size, mem::size_of::<T>() * slice.len());
+		debug_assert!(is_aligned_to(self.pos, mem::align_of::<T>()));
+
+		// Do nothing if ZST. This function will be compiled down to a no-op for ZSTs.
+		if mem::size_of::<T>() == 0 {
+			return;
+		}
+
+		self.write_slice(self.pos, slice);
+		self.pos += size;
+	}
+
+	/// Reserve capacity for at least `additional` more bytes to be inserted into
+	/// the [`MmapStorage`], growing the file if required.
+	///
+	/// # Panics
+	///
+	/// Panics if this reservation would cause [`MmapStorage`] to exceed
+	/// [`MAX_CAPACITY`], or if growing the file fails.
+	///
+	/// [`MAX_CAPACITY`]: MmapStorage::MAX_CAPACITY
+	#[inline]
+	fn reserve(&mut self, additional: usize) {
+		// Cannot wrap because capacity always exceeds pos,
+		// but avoids having to handle potential overflow here
+		let remaining = self.capacity.wrapping_sub(self.pos);
+		if additional > remaining {
+			self.grow_for_reserve(additional);
+		}
+	}
+
+	/// Reserve capacity for at least `additional` more bytes to be inserted into
+	/// the [`MmapStorage`], returning an error if it cannot.
+	///
+	/// Same as [`reserve`](MmapStorage::reserve), except returns an error if
+	/// [`MAX_CAPACITY`] or byte budget of fallible serialization in progress
+	/// would be exceeded, or growing the file fails.
+	///
+	/// [`MAX_CAPACITY`]: MmapStorage::MAX_CAPACITY
+	#[inline]
+	fn try_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let remaining = self.capacity.wrapping_sub(self.pos);
+		if additional > remaining {
+			self.try_grow_for_reserve(additional)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Shrink the capacity of the storage, and the file, as much as possible.
+	///
+	/// `capacity` will be be a multiple of [`MAX_VALUE_ALIGNMENT`].
+	///
+	/// # Panics
+	///
+	/// Panics if resizing the file fails.
+	///
+	/// [`MAX_VALUE_ALIGNMENT`]: MmapStorage::MAX_VALUE_ALIGNMENT
+	fn shrink_to_fit(&mut self) {
+		let new_cap = align_up_to(self.pos, MAX_VALUE_ALIGNMENT);
+		if new_cap != self.capacity {
+			if let Err(err) = unsafe { self.remap(new_cap) } {
+				panic!("Cannot shrink MmapStorage: {err}");
+			}
+		}
+	}
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> RandomAccessStorage
+	for MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Write a slice of values at a specific position in storage's buffer.
+	///
+	/// # Safety
+	///
+	/// Storage `capacity` must be greater or equal to
+	/// `pos + std::mem::size_of::<T>() * slice.len()`.
+	/// i.e. write is within storage's allocation.
+	///
+	/// `pos` must be aligned for `T`.
+	#[inline]
+	unsafe fn write_slice<T>(&mut self, pos: usize, slice: &[T]) {
+		debug_assert!(pos <= self.capacity);
+		debug_assert!(self.capacity - pos >= mem::size_of::<T>() * slice.len());
+		debug_assert!(is_aligned_to(pos, mem::align_of::<T>()));
+
+		// Do nothing if ZST. This function will be compiled down to a no-op for ZSTs.
+		if mem::size_of::<T>() == 0 {
+			return;
+		}
+
+		let src = slice.as_ptr();
+		let dst = self.ptr.as_ptr().add(pos) as *mut T;
+		ptr::copy_nonoverlapping(src, dst, slice.len());
+	}
+
+	/// Get immutable reference for a value at a specific position in storage.
+	///
+	/// # Safety
+	///
+	/// * A `T` must be present at this position in the storage.
+	/// * `pos` must be correctly aligned for `T`.
+	unsafe fn read<T>(&self, pos: usize) -> &T {
+		debug_assert!(pos + mem::size_of::<T>() <= self.pos);
+		debug_assert!(is_aligned_to(pos, mem::align_of::<T>()));
+
+		let ptr = self.ptr.as_ptr().add(pos) as *const T;
+		&*ptr.cast()
+	}
+
+	/// Get mutable reference for a value at a specific position in storage.
+	///
+	/// # Safety
+	///
+	/// * A `T` must be present at this position in the storage.
+	/// * `pos` must be correctly aligned for `T`.
+	unsafe fn read_mut<T>(&mut self, pos: usize) -> &mut T {
+		debug_assert!(pos + mem::size_of::<T>() <= self.pos);
+		debug_assert!(is_aligned_to(pos, mem::align_of::<T>()));
+
+		let ptr = self.ptr.as_ptr().add(pos) as *mut T;
+		&mut *ptr.cast()
+	}
+
+	/// Returns a raw pointer to a position in the storage.
+	///
+	/// Growing the storage may cause the file to be remapped to a different
+	/// address, which would make any pointers to it invalid.
+	///
+	/// # Safety
+	///
+	/// * Storage must have capacity (i.e. initialized with [`with_capacity`], or
+	///   have had some values pushed to it).
+	/// * `pos` must be a valid position within the storage's allocation.
+	///
+	/// [`with_capacity`]: MmapStorage::with_capacity
+	#[inline]
+	unsafe fn ptr(&self, pos: usize) -> *const u8 {
+		debug_assert!(self.capacity > 0);
+		debug_assert!(pos <= self.capacity);
+
+		self.ptr.as_ptr().add(pos)
+	}
+
+	/// Returns an unsafe mutable pointer a position in the storage.
+	///
+	/// Growing the storage may cause the file to be remapped to a different
+	/// address, which would make any pointers to it invalid.
+	///
+	/// # Safety
+	///
+	/// * Storage must have capacity (i.e. initialized with [`with_capacity`], or
+	///   have had some values pushed to it).
+	/// * `pos` must be a valid position within the storage's allocation.
+	///
+	/// [`with_capacity`]: MmapStorage::with_capacity
+	#[inline]
+	unsafe fn mut_ptr(&mut self, pos: usize) -> *mut u8 {
+		debug_assert!(self.capacity > 0);
+		debug_assert!(pos <= self.capacity);
+
+		self.ptr.as_ptr().add(pos)
+	}
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> ContiguousStorage
+	for MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Returns a raw pointer to the start of the storage's buffer, or a dangling
+	/// raw pointer valid for zero sized reads if the storage has no capacity.
+	///
+	/// Growing the storage may cause the file to be remapped to a different
+	/// address, which would make any pointers to it invalid.
+	#[inline]
+	fn as_ptr(&self) -> *const u8 {
+		self.ptr.as_ptr()
+	}
+
+	/// Returns an unsafe mutable pointer to the start of the storage's buffer, or
+	/// a dangling raw pointer valid for zero sized reads if the storage has no
+	/// capacity.
+	///
+	/// Growing the storage may cause the file to be remapped to a different
+	/// address, which would make any pointers to it invalid.
+	#[inline]
+	fn as_mut_ptr(&mut self) -> *mut u8 {
+		self.ptr.as_ptr()
+	}
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> AsRawFd for MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	#[inline]
+	fn as_raw_fd(&self) -> RawFd {
+		self.file.as_raw_fd()
+	}
+}
+
+impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Drop for MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	fn drop(&mut self) {
+		// Errors cannot be reported from `drop`. Use `into_file` to handle them.
+		let _ = self.unmap_and_truncate();
+	}
+}
+
+// Safe to be `Send` and `Sync` because mapped memory is not aliased and does
+// not use interior mutability.
+unsafe impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Send for MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+}
+
+unsafe impl<
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Sync for MmapStorage<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+}
+
+/// Read-only memory map of output written by [`MmapStorage`].
+///
+/// Maps the whole of a file or shared memory segment into memory, without
+/// copying it. Typically used in another process to read output of a
+/// serializer which used [`MmapStorage::shared_memory`].
+///
+/// If output was serialized with [`serialize_with_header`], [`root_pos`]
+/// checks the header, and returns position of root value.
+///
+/// Output of [`CompleteSerializer`] contains pointers to the memory address
+/// it was written at, so it cannot be read from a different mapping. Use
+/// [`PtrOffsetSerializer`]-style output (read with [`OffsetRef`]) or
+/// [`PortableSerializer`]-style output instead.
+///
+/// Only available on Unix, with `mmap` feature enabled.
+///
+/// # Example
+///
+/// ```
+/// use std::os::unix::io::AsRawFd;
+/// use ser_raw::{
+///     pos::PosMapping,
+///     read::OffsetRef,
+///     storage::{MmapReader, MmapStorage, Storage},
+///     util::aligned_max_capacity,
+///     Serialize, Serializer,
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Store = MmapStorage<16, 16, 8, MAX_CAPACITY>;
+///
+/// #[derive(Serializer)]
+/// #[ser_type(ptr_offset)]
+/// struct ShmSerializer {
+///     #[ser_storage(Store)]
+///     storage: Store,
+///     #[ser_pos_mapping]
+///     pos_mapping: PosMapping,
+/// }
+///
+/// // Writer
+/// let ser = ShmSerializer {
+///     storage: unsafe { Store::shared_memory() }.unwrap(),
+///     pos_mapping: PosMapping::dummy(),
+/// };
+/// let input = vec![1u32, 2, 3];
+/// let (_, storage) = ser.serialize_with_header(&input);
+/// let file = storage.into_file().unwrap();
+///
+/// // Reader (usually in another process, given `file.as_raw_fd()`).
+/// // Writer has finished, so nothing modifies the segment.
+/// let reader = unsafe { MmapReader::new(&file) }.unwrap();
+/// let pos = reader.root_pos::<Vec<u32>, ShmSerializer>().unwrap();
+/// let output = unsafe { OffsetRef::<Vec<u32>>::from_bytes(reader.as_slice(), pos) };
+/// assert_eq!(unsafe { output.as_vec().as_slice() }, &[1, 2, 3]);
+/// ```
+///
+/// [`serialize_with_header`]: Serializer::serialize_with_header
+/// [`root_pos`]: MmapReader::root_pos
+/// [`CompleteSerializer`]: crate::CompleteSerializer
+/// [`PtrOffsetSerializer`]: crate::PtrOffsetSerializer
+/// [`PortableSerializer`]: crate::PortableSerializer
+/// [`OffsetRef`]: crate::read::OffsetRef
+pub struct MmapReader {
+	ptr: NonNull<u8>,
+	len: usize,
+}
+
+impl MmapReader {
+	/// Map contents of `file` into memory, read-only.
+	///
+	/// # Safety
+	///
+	/// File is mapped with `MAP_SHARED`, and its contents are handed out as a
+	/// `&[u8]` by [`as_slice`](MmapReader::as_slice). So file must not be
+	/// modified or truncated, by this or any other process, while the
+	/// [`MmapReader`] exists.
+	pub unsafe fn new(file: &File) -> io::Result<Self> {
+		let len = usize::try_from(file.metadata()?.len())
+			.map_err(|_| invalid_data("File is too large to map"))?;
+		if len == 0 {
+			// `mmap` cannot map 0 bytes. Use a dangling pointer, aligned as a mapping
+			// would be.
+			return Ok(Self {
+				ptr: NonNull::new(MIN_PAGE_SIZE as *mut u8).unwrap(),
+				len,
+			});
+		}
+
+		let ptr = libc::mmap(
+			ptr::null_mut(),
+			len,
+			libc::PROT_READ,
+			libc::MAP_SHARED,
+			file.as_raw_fd(),
+			0,
+		);
+		if ptr == libc::MAP_FAILED {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(Self {
+			ptr: NonNull::new_unchecked(ptr as *mut u8),
+			len,
+		})
+	}
+
+	/// Map contents of file or shared memory segment with file descriptor `fd`
+	/// into memory, read-only.
+	///
+	/// `fd` is closed once it's mapped. Mapping remains valid.
+	///
+	/// # Safety
+	///
+	/// `fd` must be an open file descriptor, which is not used elsewhere.
+	/// Same requirements as [`new`](MmapReader::new) concerning modifying the
+	/// file.
+	pub unsafe fn from_raw_fd(fd: RawFd) -> io::Result<Self> {
+		let file = File::from_raw_fd(fd);
+		Self::new(&file)
+	}
+
+	/// Returns length of mapped contents in bytes.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Returns `true` if mapped contents is empty.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Returns slice containing mapped contents.
+	///
+	/// Slice is aligned to at least 4096.
+	#[inline]
+	pub fn as_slice(&self) -> &[u8] {
+		unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+	}
+
+	/// Check [`Header`](header::Header) at start of contents matches serializing
+	/// a value of type `T` with serializer `S`, and return position of root
+	/// value.
+	///
+	/// Contents must have been serialized with
+	/// [`serialize_with_header`](Serializer::serialize_with_header).
+	#[inline]
+	pub fn root_pos<T: Serialize<S>, S: Serializer>(&self) -> Result<usize, HeaderError> {
+		header::check::<T, S>(self.as_slice())
+	}
+}
+
+impl Drop for MmapReader {
+	fn drop(&mut self) {
+		if self.len > 0 {
+			unsafe { libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len) };
+		}
+	}
+}
+
+// Safe to be `Send` and `Sync` because mapped memory is read-only
+unsafe impl Send for MmapReader {}
+
+unsafe impl Sync for MmapReader {}
@@ -2,16 +2,34 @@
 
 use std::{marker::PhantomData, mem, slice};
 
-use crate::util::{align_up_to, aligned_max_capacity};
+use crate::{
+	fallible::{self, SerializeError},
+	util::{align_up_to, aligned_max_capacity, is_aligned_to},
+};
 
 mod aligned_vec;
 pub use aligned_vec::AlignedVec;
+mod arena;
+pub use arena::{Arena, ArenaStorage};
+mod counting;
+pub use counting::CountingStorage;
+mod fixed_vec;
+pub use fixed_vec::FixedVec;
+#[cfg(all(unix, feature = "mmap"))]
+mod mmap;
+#[cfg(all(unix, feature = "mmap"))]
+pub use mmap::{MmapReader, MmapStorage};
+mod write_storage;
+pub use write_storage::WriteStorage;
 
 /// Trait for storage used by [`Serializer`]s which ensures values added to
 /// storage maintain correct alignment in memory for their types.
 ///
-/// [`AlignedVec`] implements this trait. You could also build your own
-/// implementation of [`Storage`] with different properties.
+/// [`AlignedVec`], [`FixedVec`], [`ArenaStorage`] and [`WriteStorage`]
+/// implement this trait, as does `MmapStorage` (Unix only, with `mmap` feature
+/// enabled). [`CountingStorage`] implements it too, but only counts bytes
+/// pushed to it. You could also build your own implementation of [`Storage`]
+/// with different properties.
 ///
 /// # Const parameters
 ///
@@ -195,6 +213,7 @@ pub trait Storage: Sized {
 	/// Panics if `capacity` exceeds [`MAX_CAPACITY`](Storage::MAX_CAPACITY).
 	fn with_capacity(capacity: usize) -> Self {
 		// Ensure (at compile time) that const params are valid
+		#[allow(clippy::let_unit_value)]
 		let _ = Self::ASSERT_ALIGNMENTS_VALID;
 
 		if capacity == 0 {
@@ -214,6 +233,25 @@ pub trait Storage: Sized {
 		unsafe { Self::with_capacity_unchecked(capacity) }
 	}
 
+	/// Create new [`Storage`] with pre-allocated capacity, returning an error if
+	/// `capacity` exceeds [`MAX_CAPACITY`](Storage::MAX_CAPACITY), or allocating
+	/// memory fails.
+	///
+	/// Capacity will be rounded up to a multiple of
+	/// [`MAX_VALUE_ALIGNMENT`](Storage::MAX_VALUE_ALIGNMENT).
+	///
+	/// # Unwinding
+	///
+	/// Allocation failure is signalled by unwinding, so this requires panics to
+	/// unwind. If built with `panic = "abort"`, allocation failure aborts the
+	/// process. See [`fallible`](crate::fallible).
+	fn try_with_capacity(capacity: usize) -> Result<Self, SerializeError> {
+		if capacity > Self::MAX_CAPACITY {
+			return Err(fallible::capacity_exceeded::<Self>());
+		}
+		fallible::catch(usize::MAX, || Self::with_capacity(capacity))
+	}
+
 	/// Create new `Storage` instance with pre-allocated capacity,
 	/// without safety checks.
 	///
@@ -242,7 +280,7 @@ pub trait Storage: Sized {
 	/// [`Storage`] implementations can impose further constraints.
 	///
 	/// [`VALUE_ALIGNMENT`]: Storage::VALUE_ALIGNMENT
-	unsafe fn set_pos(&mut self, new_pos: usize) -> ();
+	unsafe fn set_pos(&mut self, new_pos: usize);
 
 	/// Push a value of type `T` to storage.
 	#[inline]
@@ -324,7 +362,7 @@ pub trait Storage: Sized {
 	///
 	/// [`pos()`]: Storage::pos
 	/// [`VALUE_ALIGNMENT`]: Storage::VALUE_ALIGNMENT
-	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize) -> ();
+	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize);
 
 	/// Advance buffer position to leave space to write a `T` at current position
 	/// later.
@@ -356,9 +394,15 @@ pub trait Storage: Sized {
 		let size = mem::size_of::<T>() * len;
 		self.reserve(size);
 		let pos = self.pos();
-		unsafe { self.set_pos(self.pos() + size) };
 
-		self.align_after::<T>();
+		// Align position, ready for next push.
+		// Must be done before `set_pos()`, as it requires an aligned position.
+		let mut new_pos = pos + size;
+		if mem::size_of::<T>() % Self::VALUE_ALIGNMENT > 0 {
+			new_pos = align_up_to(new_pos, Self::VALUE_ALIGNMENT);
+		}
+		unsafe { self.set_pos(new_pos) };
+
 		pos
 	}
 
@@ -369,7 +413,35 @@ pub trait Storage: Sized {
 	///
 	/// Panics if this reservation would cause the [`Storage`] to exceed
 	/// [`MAX_CAPACITY`](Storage::MAX_CAPACITY).
-	fn reserve(&mut self, additional: usize) -> ();
+	fn reserve(&mut self, additional: usize);
+
+	/// Reserve space in storage for `additional` bytes, growing capacity if
+	/// required, returning an error if it cannot.
+	///
+	/// Returns an error instead of panicking if this reservation would cause the
+	/// [`Storage`] to exceed [`MAX_CAPACITY`](Storage::MAX_CAPACITY), or if
+	/// allocating memory fails. Also returns an error if it would exceed byte
+	/// budget of fallible serialization in progress (see [`fallible`]).
+	///
+	/// Default implementation checks `MAX_CAPACITY` and byte budget, and then
+	/// calls [`reserve`](Storage::reserve), so does not panic. But it cannot
+	/// report allocation failure, which aborts as it does for `Vec`. [`Storage`]
+	/// implementations should override it.
+	///
+	/// [`fallible`]: crate::fallible
+	#[inline]
+	fn try_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let required = self
+			.pos()
+			.checked_add(additional)
+			.filter(|&required| required <= Self::MAX_CAPACITY)
+			.ok_or_else(fallible::capacity_exceeded::<Self>)?;
+		if required > self.capacity() {
+			fallible::check_budget(required)?;
+		}
+		self.reserve(additional);
+		Ok(())
+	}
 
 	/// Align position in storage to alignment of `T`.
 	///
@@ -379,6 +451,7 @@ pub trait Storage: Sized {
 	fn align_for<T>(&mut self) {
 		// Ensure (at compile time) that `T`'s alignment does not exceed
 		// `MAX_VALUE_ALIGNMENT`
+		#[allow(clippy::let_unit_value)]
 		let _ = AlignmentCheck::<T, Self>::ASSERT_ALIGNMENT_DOES_NOT_EXCEED;
 
 		// Align position in output buffer to alignment of `T`.
@@ -462,8 +535,47 @@ pub trait Storage: Sized {
 		unsafe { self.set_pos(0) };
 	}
 
+	/// Discard contents of storage after position `new_pos`.
+	///
+	/// Does not reduce the storage's capacity, just sets
+	/// [`pos()`](Storage::pos) back to `new_pos`.
+	///
+	/// # Panics
+	///
+	/// Panics if `new_pos` is after current [`pos()`](Storage::pos), or is not a
+	/// multiple of [`VALUE_ALIGNMENT`](Storage::VALUE_ALIGNMENT).
+	#[inline]
+	fn truncate(&mut self, new_pos: usize) {
+		assert!(
+			new_pos <= self.pos() && is_aligned_to(new_pos, Self::VALUE_ALIGNMENT),
+			"Cannot truncate storage to {new_pos}"
+		);
+		// `pos() <= capacity()`, so `new_pos` is too
+		unsafe { self.set_pos(new_pos) };
+	}
+
+	/// Discard contents of storage after position `new_pos`, returning an error
+	/// if storage cannot be truncated to `new_pos`.
+	///
+	/// Returns [`SerializeError::AlreadyWritten`] if output after `new_pos` has
+	/// already been written out and cannot be recalled (e.g. by a
+	/// [`WriteStorage`]). Storage is left unchanged in that case.
+	///
+	/// Default implementation calls [`truncate`](Storage::truncate). [`Storage`]
+	/// implementations which cannot always be truncated should override it.
+	///
+	/// # Panics
+	///
+	/// Panics if `new_pos` is after current [`pos()`](Storage::pos), or is not a
+	/// multiple of [`VALUE_ALIGNMENT`](Storage::VALUE_ALIGNMENT).
+	#[inline]
+	fn try_truncate(&mut self, new_pos: usize) -> Result<(), SerializeError> {
+		self.truncate(new_pos);
+		Ok(())
+	}
+
 	/// Shrink the capacity of the storage as much as possible.
-	fn shrink_to_fit(&mut self) -> ();
+	fn shrink_to_fit(&mut self);
 }
 
 /// Trait for [`Storage`] which supports random access read and writes.
@@ -497,7 +609,7 @@ pub trait RandomAccessStorage: Storage {
 	/// `pos` must be correctly aligned for `T`.
 	///
 	/// [`capacity()`]: Storage::capacity
-	unsafe fn write_slice<T>(&mut self, pos: usize, slice: &[T]) -> ();
+	unsafe fn write_slice<T>(&mut self, pos: usize, slice: &[T]);
 
 	/// Get immutable reference for a value at a specific position in storage.
 	///
@@ -568,8 +680,76 @@ pub trait ContiguousStorage: Storage {
 	/// may cause its buffer to be reallocated, which would also make any pointers
 	/// to it invalid.
 	fn as_mut_ptr(&mut self) -> *mut u8;
+
+	/// Returns a slice containing the storage's contents (i.e. bytes from start
+	/// of the storage's buffer up to [`pos()`]).
+	///
+	/// [`pos()`]: Storage::pos
+	#[inline]
+	fn as_slice(&self) -> &[u8] {
+		unsafe { slice::from_raw_parts(self.as_ptr(), self.pos()) }
+	}
+
+	/// Returns a mutable slice containing the storage's contents (i.e. bytes from
+	/// start of the storage's buffer up to [`pos()`]).
+	///
+	/// [`pos()`]: Storage::pos
+	#[inline]
+	fn as_mut_slice(&mut self) -> &mut [u8] {
+		let pos = self.pos();
+		unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), pos) }
+	}
 }
 
+/// Marker trait for [`Storage`] whose buffer never moves.
+///
+/// Complete serializers write pointers which point to their targets' current
+/// memory addresses in storage. If storage grows and its buffer moves, those
+/// pointers need to be corrected, so Complete serializers ordinarily record
+/// all pointers in [`Ptrs`]. For a [`FixedStorage`], this is unnecessary, so
+/// a Complete serializer using it can omit its `#[ser_ptrs]` field, and skip
+/// recording pointers entirely.
+///
+/// [`FixedVec`] implements this trait.
+///
+/// Pointers written to output point into the storage's buffer, so a serializer
+/// without `#[ser_ptrs]` must not have its storage replaced (e.g. by assigning
+/// to [`storage_mut`]) during a serialization, from the first value being
+/// serialized until output is finalized. Pointers already written would point
+/// into the old buffer. Replacing storage after [`reset`], before the next
+/// serialization begins, is fine.
+///
+/// Omitting `#[ser_ptrs]` with a [`Storage`] which may move is a compile-time
+/// error:
+///
+/// ```compile_fail
+/// use ser_raw::{
+///     pos::PosMapping,
+///     storage::AlignedVec,
+///     Serializer,
+/// };
+///
+/// #[derive(Serializer)]
+/// #[ser_type(complete)]
+/// struct MySer {
+///     #[ser_storage(AlignedVec)]
+///     storage: AlignedVec,
+///     #[ser_pos_mapping]
+///     pos_mapping: PosMapping,
+/// }
+/// ```
+///
+/// # Safety
+///
+/// Once storage has capacity, the memory address of its buffer must never
+/// change. [`reserve`](Storage::reserve) must panic rather than reallocating,
+/// and [`shrink_to_fit`](Storage::shrink_to_fit) must not move the buffer.
+///
+/// [`Ptrs`]: crate::pos::Ptrs
+/// [`storage_mut`]: crate::Serializer::storage_mut
+/// [`reset`]: crate::Serializer::reset
+pub unsafe trait FixedStorage: ContiguousStorage {}
+
 /// Type for static assertion that types being serialized do not have a higher
 /// alignment requirement than the alignment of the output buffer
 pub(crate) struct AlignmentCheck<T, S: Storage> {
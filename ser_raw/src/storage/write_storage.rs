@@ -0,0 +1,620 @@
+use std::{
+	io::{self, Write},
+	mem, ptr, slice,
+};
+
+use super::{AlignedVec, ContiguousStorage, Storage};
+use crate::{
+	fallible::{self, SerializeError},
+	util::{align_up_to, aligned_max_capacity, is_aligned_to},
+};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+const DEFAULT_STORAGE_ALIGNMENT: usize = 16;
+const DEFAULT_VALUE_ALIGNMENT: usize = PTR_SIZE;
+const DEFAULT_MAX_CAPACITY: usize = aligned_max_capacity(DEFAULT_STORAGE_ALIGNMENT);
+
+/// Default size of buffer chunks for [`WriteStorage`].
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Storage which streams output to an [`io::Write`] (file, pipe, socket etc).
+///
+/// Output is buffered in memory in a chunk of fixed size. When the chunk is
+/// full, its contents are written to the writer, and the buffer is reused.
+/// Slices too large to fit in the buffer are written directly to the writer.
+/// So output can be larger than available memory, and a consumer can read
+/// output while it's still being produced.
+///
+/// Alignment is maintained relative to start of the stream, so output is
+/// byte-for-byte identical to output of same serializer using [`AlignedVec`].
+/// Padding bytes are zeroed.
+///
+/// Output cannot be modified once it's been pushed, so [`WriteStorage`] does
+/// not implement [`RandomAccessStorage`]. It can only be used with pure copy
+/// serializers.
+///
+/// Errors from the writer can't be reported while serializing. Once an error
+/// occurs, further output is discarded, and the error is returned by
+/// [`flush`] or [`finish`]. [`finish`] must be called to write remaining
+/// output and get the writer back. If [`WriteStorage`] is dropped without
+/// calling [`finish`], it attempts to write remaining output, but ignores any
+/// errors.
+///
+/// See [`Storage`] trait for details of the const parameters.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::{Storage, WriteStorage},
+///     util::aligned_max_capacity,
+///     Deserializer, PureCopyDeserializer, Serialize, Serializer,
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// type Store = WriteStorage<Vec<u8>, 16, 16, 8, MAX_CAPACITY>;
+///
+/// #[derive(Serializer)]
+/// #[ser_type(pure_copy)]
+/// struct StreamSerializer {
+///     #[ser_storage(Store)]
+///     storage: Store,
+/// }
+///
+/// // `Vec<u8>` could be replaced by any `io::Write` e.g. a `File` or `TcpStream`
+/// let storage = Store::with_chunk_size(Vec::new(), 1024);
+/// let ser = StreamSerializer { storage };
+/// let input: Vec<u32> = (0..1000).collect();
+/// let (_, storage) = ser.serialize(&input);
+/// let output = storage.finish().unwrap();
+///
+/// let de = unsafe {
+///     PureCopyDeserializer::<16, 16, 8, MAX_CAPACITY>::new(output.as_slice())
+/// };
+/// let output: Vec<u32> = de.deserialize();
+/// assert_eq!(output, input);
+/// ```
+///
+/// [`RandomAccessStorage`]: super::RandomAccessStorage
+/// [`flush`]: WriteStorage::flush
+/// [`finish`]: WriteStorage::finish
+pub struct WriteStorage<
+	W: Write,
+	const STORAGE_ALIGNMENT: usize = DEFAULT_STORAGE_ALIGNMENT,
+	const MAX_VALUE_ALIGNMENT: usize = STORAGE_ALIGNMENT,
+	const VALUE_ALIGNMENT: usize = DEFAULT_VALUE_ALIGNMENT,
+	const MAX_CAPACITY: usize = DEFAULT_MAX_CAPACITY,
+> {
+	buf: AlignedVec<STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>,
+	writer: Option<W>,
+	/// Number of bytes which have been removed from buffer and written to writer
+	/// (or discarded after an error). Always a multiple of `MAX_VALUE_ALIGNMENT`
+	/// until storage is finished.
+	flushed: usize,
+	error: Option<io::Error>,
+}
+
+impl<
+		W: Write,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> WriteStorage<W, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Create new [`WriteStorage`] writing to `writer`, with default chunk size
+	/// (64 KiB).
+	pub fn from_writer(writer: W) -> Self {
+		let chunk_size = align_up_to(DEFAULT_CHUNK_SIZE, MAX_VALUE_ALIGNMENT);
+		Self::with_chunk_size(writer, chunk_size.min(MAX_CAPACITY))
+	}
+
+	/// Create new [`WriteStorage`] writing to `writer`, with chunk size
+	/// `chunk_size` bytes.
+	///
+	/// Chunk size will be rounded up to a multiple of `MAX_VALUE_ALIGNMENT`.
+	///
+	/// # Panics
+	///
+	/// Panics if `chunk_size` exceeds `MAX_CAPACITY`.
+	pub fn with_chunk_size(writer: W, chunk_size: usize) -> Self {
+		Self {
+			buf: AlignedVec::with_capacity(chunk_size),
+			writer: Some(writer),
+			flushed: 0,
+			error: None,
+		}
+	}
+
+	/// Get reference to the underlying writer.
+	///
+	/// # Panics
+	///
+	/// Panics if storage has no writer (was created with [`Storage::new`]).
+	#[inline]
+	pub fn get_ref(&self) -> &W {
+		match &self.writer {
+			Some(writer) => writer,
+			None => no_writer(),
+		}
+	}
+
+	/// Write all complete chunks of output to the writer, and flush the writer.
+	///
+	/// Up to `MAX_VALUE_ALIGNMENT - 1` bytes of output may remain buffered, to
+	/// maintain alignment. Use [`finish`](WriteStorage::finish) to write all
+	/// output.
+	///
+	/// Returns an error if writing to the writer has failed, now or previously.
+	///
+	/// # Panics
+	///
+	/// Panics if storage has no writer (was created with [`Storage::new`]).
+	pub fn flush(&mut self) -> io::Result<()> {
+		self.write_buffer(false);
+		self.flush_writer();
+		match &self.error {
+			// `io::Error` is not `Clone`. Original error is retained for `finish`.
+			Some(err) => Err(io::Error::new(err.kind(), err.to_string())),
+			None => Ok(()),
+		}
+	}
+
+	/// Consume storage, writing all remaining output to the writer and flushing
+	/// it, and return the writer.
+	///
+	/// Returns an error if writing to the writer has failed, now or previously.
+	///
+	/// # Panics
+	///
+	/// Panics if storage has no writer (was created with [`Storage::new`]).
+	pub fn finish(mut self) -> io::Result<W> {
+		self.write_buffer(true);
+		self.flush_writer();
+		match self.error.take() {
+			Some(err) => Err(err),
+			// `write_buffer` would have panicked if there was no writer
+			None => Ok(self.writer.take().unwrap()),
+		}
+	}
+
+	/// Write contents of buffer to writer.
+	///
+	/// If `all` is `false`, only write a multiple of `MAX_VALUE_ALIGNMENT` bytes,
+	/// and move remainder to start of the buffer. This ensures `flushed` remains
+	/// a multiple of `MAX_VALUE_ALIGNMENT`, so positions in the buffer have the
+	/// same alignment as positions in the stream.
+	fn write_buffer(&mut self, all: bool) {
+		let pos = self.buf.pos();
+		let len = if all {
+			pos
+		} else {
+			pos & !(MAX_VALUE_ALIGNMENT - 1)
+		};
+		if len == 0 {
+			return;
+		}
+
+		let writer = match &mut self.writer {
+			Some(writer) => writer,
+			None => no_writer(),
+		};
+		write_to(writer, &mut self.error, &self.buf.as_slice()[..len]);
+
+		// Move remainder to start of buffer.
+		// `remainder` is less than `MAX_VALUE_ALIGNMENT` so is a valid position.
+		let remainder = pos - len;
+		unsafe {
+			if remainder > 0 {
+				let ptr = self.buf.as_mut_ptr();
+				ptr::copy(ptr.add(len), ptr, remainder);
+			}
+			self.buf.set_pos(remainder);
+		}
+		self.flushed += len;
+	}
+
+	/// Flush writer, unless an error has already occurred.
+	fn flush_writer(&mut self) {
+		if self.error.is_none() {
+			if let Some(writer) = &mut self.writer {
+				if let Err(err) = writer.flush() {
+					self.error = Some(err);
+				}
+			}
+		}
+	}
+
+	/// Push bytes which don't fit in remaining capacity of buffer, after
+	/// `push_slice` has found it's necessary.
+	///
+	/// Writes contents of buffer to writer. If `bytes` still doesn't fit in
+	/// buffer, writes it directly to the writer too, up to a multiple of
+	/// `MAX_VALUE_ALIGNMENT`, and pushes remainder to the buffer.
+	///
+	/// Returns position of `bytes` in the stream.
+	///
+	/// Caller must call `align_after` afterwards.
+	#[cold]
+	fn push_bytes_slow(&mut self, bytes: &[u8]) -> usize {
+		let size = bytes.len();
+		if let Err(err) = self.check_len(size) {
+			err.fail();
+		}
+
+		self.write_buffer(false);
+		let buf_pos = self.buf.pos();
+		let pos = self.flushed + buf_pos;
+		if size < MAX_VALUE_ALIGNMENT || size <= self.buf.capacity() - buf_pos {
+			self.buf.reserve(size);
+			// `reserve` ensures sufficient capacity. `u8` has no alignment requirement.
+			unsafe { self.buf.push_slice_unchecked(bytes, size) };
+			return pos;
+		}
+
+		// Write remainder in buffer, and then `bytes`, directly to writer.
+		// `direct_end > pos` because `size >= MAX_VALUE_ALIGNMENT`.
+		let direct_end = (pos + size) & !(MAX_VALUE_ALIGNMENT - 1);
+		let direct_len = direct_end - pos;
+		let writer = match &mut self.writer {
+			Some(writer) => writer,
+			None => no_writer(),
+		};
+		write_to(writer, &mut self.error, self.buf.as_slice());
+		write_to(writer, &mut self.error, &bytes[..direct_len]);
+		self.flushed = direct_end;
+
+		// Push rest of `bytes` to buffer. `direct_end` is a multiple of
+		// `MAX_VALUE_ALIGNMENT` so 0 is a valid position.
+		let rest = &bytes[direct_len..];
+		unsafe { self.buf.set_pos(0) };
+		self.buf.reserve(rest.len());
+		unsafe { self.buf.push_slice_unchecked(rest, rest.len()) };
+
+		pos
+	}
+
+	/// Write buffer to writer to make space for `additional` bytes, after
+	/// `reserve` has found it's necessary. Grow buffer if it's still too small.
+	///
+	/// Separate function marked `#[cold]` to keep `reserve` small and inlinable.
+	#[cold]
+	fn write_for_reserve(&mut self, additional: usize) {
+		if let Err(err) = self.check_len(additional) {
+			err.fail();
+		}
+		self.write_buffer(false);
+		self.buf.reserve(additional);
+	}
+
+	/// Same as `write_for_reserve`, but returns an error instead of panicking.
+	#[cold]
+	fn try_write_for_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		self.check_len(additional)?;
+		self.write_buffer(false);
+		self.buf.try_reserve(additional)
+	}
+
+	/// Check total length of output would not exceed `MAX_CAPACITY` or byte
+	/// budget of fallible serialization in progress, after `additional` more
+	/// bytes are added.
+	fn check_len(&self, additional: usize) -> Result<(), SerializeError> {
+		// `pos() <= MAX_CAPACITY`, so cannot wrap
+		if additional > MAX_CAPACITY - self.pos() {
+			return Err(fallible::capacity_exceeded::<Self>());
+		}
+		fallible::check_budget(self.pos() + additional)
+	}
+}
+
+/// Write `bytes` to `writer`, unless an error has already occurred.
+/// Record error if write fails.
+#[inline]
+fn write_to<W: Write>(writer: &mut W, error: &mut Option<io::Error>, bytes: &[u8]) {
+	if error.is_none() {
+		if let Err(err) = writer.write_all(bytes) {
+			*error = Some(err);
+		}
+	}
+}
+
+#[cold]
+fn no_writer() -> ! {
+	panic!("WriteStorage has no writer");
+}
+
+impl<
+		W: Write,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Storage
+	for WriteStorage<W, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	/// Alignment of storage's memory buffer.
+	///
+	/// See [`Storage`] trait for explanation.
+	const STORAGE_ALIGNMENT: usize = STORAGE_ALIGNMENT;
+
+	/// Maximum alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_VALUE_ALIGNMENT: usize = MAX_VALUE_ALIGNMENT;
+
+	/// Typical alignment of values being added to storage.
+	///
+	/// See [`Storage`] trait for explanation.
+	const VALUE_ALIGNMENT: usize = VALUE_ALIGNMENT;
+
+	/// Maximum capacity of storage.
+	///
+	/// For [`WriteStorage`] this is the maximum total length of output.
+	///
+	/// See [`Storage`] trait for explanation.
+	const MAX_CAPACITY: usize = MAX_CAPACITY;
+
+	/// Create new [`WriteStorage`] with no writer.
+	///
+	/// Output cannot be written anywhere, so this panics once its buffer is
+	/// full. Use [`from_writer`] or [`with_chunk_size`] instead.
+	///
+	/// [`from_writer`]: WriteStorage::from_writer
+	/// [`with_chunk_size`]: WriteStorage::with_chunk_size
+	#[inline]
+	fn new() -> Self {
+		Self {
+			buf: AlignedVec::new(),
+			writer: None,
+			flushed: 0,
+			error: None,
+		}
+	}
+
+	/// Create new [`WriteStorage`] with no writer, and a buffer of `capacity`
+	/// bytes, without safety checks.
+	///
+	/// Output cannot be written anywhere, so this panics once its buffer is
+	/// full. Use [`from_writer`] or [`with_chunk_size`] instead.
+	///
+	/// # Safety
+	///
+	/// * `capacity` must not be 0.
+	/// * `capacity` must be less than or equal to [`MAX_CAPACITY`].
+	/// * `capacity` must be a multiple of [`MAX_VALUE_ALIGNMENT`].
+	///
+	/// [`from_writer`]: WriteStorage::from_writer
+	/// [`with_chunk_size`]: WriteStorage::with_chunk_size
+	/// [`MAX_CAPACITY`]: WriteStorage::MAX_CAPACITY
+	/// [`MAX_VALUE_ALIGNMENT`]: WriteStorage::MAX_VALUE_ALIGNMENT
+	#[inline]
+	unsafe fn with_capacity_unchecked(capacity: usize) -> Self {
+		Self {
+			buf: AlignedVec::with_capacity_unchecked(capacity),
+			writer: None,
+			flushed: 0,
+			error: None,
+		}
+	}
+
+	/// Returns current capacity of storage in bytes.
+	///
+	/// This is total length of output which has been written to the writer, plus
+	/// capacity of the buffer.
+	#[inline]
+	fn capacity(&self) -> usize {
+		self.flushed + self.buf.capacity()
+	}
+
+	/// Returns current position in storage, relative to start of the stream.
+	#[inline]
+	fn pos(&self) -> usize {
+		self.flushed + self.buf.pos()
+	}
+
+	/// Set current position in storage.
+	///
+	/// If position is advanced, bytes skipped over are zeroed.
+	///
+	/// # Safety
+	///
+	/// * `new_pos` must be less than or equal to [`capacity()`].
+	/// * `new_pos` must be a multiple of [`VALUE_ALIGNMENT`].
+	/// * `new_pos` must not be before start of the buffer i.e. output which has
+	///   already been written to the writer cannot be rewound.
+	///
+	/// [`capacity()`]: WriteStorage::capacity
+	/// [`VALUE_ALIGNMENT`]: WriteStorage::VALUE_ALIGNMENT
+	#[inline]
+	unsafe fn set_pos(&mut self, new_pos: usize) {
+		debug_assert!(new_pos >= self.flushed);
+		debug_assert!(is_aligned_to(new_pos, VALUE_ALIGNMENT));
+
+		let new_buf_pos = new_pos - self.flushed;
+		let buf_pos = self.buf.pos();
+		if new_buf_pos > buf_pos {
+			ptr::write_bytes(self.buf.as_mut_ptr().add(buf_pos), 0, new_buf_pos - buf_pos);
+		}
+		self.buf.set_pos(new_buf_pos);
+	}
+
+	/// Push a slice of values `&T` to storage, without alignment checks and
+	/// without reserving capacity for it.
+	///
+	/// # Safety
+	///
+	/// Caller must ensure [`WriteStorage`] has sufficient capacity.
+	///
+	/// `size` must be total size in bytes of `&[T]`.
+	/// i.e. `size = mem::size_of::<T>() * slice.len()`.
+	///
+	/// Caller must uphold alignment invariants. See
+	/// [`Storage::push_slice_unchecked`].
+	#[inline]
+	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize) {
+		// `flushed` is a multiple of `MAX_VALUE_ALIGNMENT`, so position in buffer
+		// has same alignment as position in stream
+		self.buf.push_slice_unchecked(slice, size);
+	}
+
+	/// Push a slice of values `&T` to storage.
+	///
+	/// Returns position of the slice in the stream.
+	/// If `T` is a zero-size type, returns 0.
+	///
+	/// If slice does not fit in buffer, buffer's contents are written to the
+	/// writer. If it still does not fit, slice is written directly to the
+	/// writer.
+	///
+	/// # Panics
+	///
+	/// Panics if total length of output would exceed [`MAX_CAPACITY`], or if
+	/// storage has no writer.
+	///
+	/// [`MAX_CAPACITY`]: WriteStorage::MAX_CAPACITY
+	#[inline]
+	fn push_slice<T>(&mut self, slice: &[T]) -> usize {
+		// Do nothing if ZST. This function will be compiled down to a no-op for ZSTs.
+		if mem::size_of::<T>() == 0 {
+			return 0;
+		}
+
+		// Align storage for `T`
+		self.align_for::<T>();
+
+		let size = mem::size_of::<T>() * slice.len();
+		// Cannot wrap because capacity always exceeds pos
+		let remaining = self.buf.capacity().wrapping_sub(self.buf.pos());
+		let pos = if size <= remaining {
+			let pos = self.pos();
+			// Capacity checked above. `align_for::<T>()` ensures position is aligned.
+			unsafe { self.buf.push_slice_unchecked(slice, size) };
+			pos
+		} else {
+			let bytes = unsafe { slice::from_raw_parts(slice.as_ptr().cast::<u8>(), size) };
+			self.push_bytes_slow(bytes)
+		};
+
+		// Align position, ready for next push
+		self.align_after::<T>();
+
+		pos
+	}
+
+	/// Reserve capacity for at least `additional` more bytes to be inserted into
+	/// the [`WriteStorage`].
+	///
+	/// If buffer has insufficient capacity, its contents are written to the
+	/// writer. If it's still too small, the buffer grows.
+	///
+	/// # Panics
+	///
+	/// Panics if total length of output would exceed [`MAX_CAPACITY`], or if
+	/// storage has no writer.
+	///
+	/// [`MAX_CAPACITY`]: WriteStorage::MAX_CAPACITY
+	#[inline]
+	fn reserve(&mut self, additional: usize) {
+		// Cannot wrap because capacity always exceeds pos,
+		// but avoids having to handle potential overflow here
+		let remaining = self.buf.capacity().wrapping_sub(self.buf.pos());
+		if additional > remaining {
+			self.write_for_reserve(additional);
+		}
+	}
+
+	/// Reserve capacity for at least `additional` more bytes to be inserted into
+	/// the [`WriteStorage`], returning an error if total length of output would
+	/// exceed [`MAX_CAPACITY`] or byte budget of fallible serialization in
+	/// progress, or growing buffer fails.
+	///
+	/// # Panics
+	///
+	/// Panics if storage has no writer.
+	///
+	/// [`MAX_CAPACITY`]: WriteStorage::MAX_CAPACITY
+	#[inline]
+	fn try_reserve(&mut self, additional: usize) -> Result<(), SerializeError> {
+		let remaining = self.buf.capacity().wrapping_sub(self.buf.pos());
+		if additional > remaining {
+			self.try_write_for_reserve(additional)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Discard output which has not yet been written to the writer.
+	///
+	/// # Panics
+	///
+	/// Panics if any output has already been written to the writer, as it cannot
+	/// be recalled.
+	#[inline]
+	fn clear(&mut self) {
+		assert!(
+			self.flushed == 0,
+			"Cannot clear WriteStorage after output has been written"
+		);
+		self.buf.clear();
+	}
+
+	/// Discard output after position `new_pos`.
+	///
+	/// # Panics
+	///
+	/// Panics if `new_pos` is after current position, is not a multiple of
+	/// `VALUE_ALIGNMENT`, or is before end of output which has already been
+	/// written to the writer, as that cannot be recalled.
+	#[inline]
+	fn truncate(&mut self, new_pos: usize) {
+		assert!(
+			new_pos >= self.flushed,
+			"Cannot truncate WriteStorage before output which has been written"
+		);
+		assert!(
+			new_pos <= self.pos() && is_aligned_to(new_pos, VALUE_ALIGNMENT),
+			"Cannot truncate storage to {new_pos}"
+		);
+		// `new_pos` is within buffer, so this satisfies `set_pos`'s requirements
+		unsafe { self.set_pos(new_pos) };
+	}
+
+	/// Discard output after position `new_pos`, returning an error if output
+	/// after `new_pos` has already been written to the writer.
+	///
+	/// # Panics
+	///
+	/// Panics if `new_pos` is after current position, or is not a multiple of
+	/// `VALUE_ALIGNMENT`.
+	#[inline]
+	fn try_truncate(&mut self, new_pos: usize) -> Result<(), SerializeError> {
+		if new_pos < self.flushed {
+			return Err(SerializeError::AlreadyWritten);
+		}
+		self.truncate(new_pos);
+		Ok(())
+	}
+
+	/// Shrink the capacity of the buffer as much as possible.
+	#[inline]
+	fn shrink_to_fit(&mut self) {
+		self.buf.shrink_to_fit();
+	}
+}
+
+impl<
+		W: Write,
+		const STORAGE_ALIGNMENT: usize,
+		const MAX_VALUE_ALIGNMENT: usize,
+		const VALUE_ALIGNMENT: usize,
+		const MAX_CAPACITY: usize,
+	> Drop for WriteStorage<W, STORAGE_ALIGNMENT, MAX_VALUE_ALIGNMENT, VALUE_ALIGNMENT, MAX_CAPACITY>
+{
+	fn drop(&mut self) {
+		// Errors cannot be reported from `drop`. Use `finish` to handle them.
+		if self.writer.is_some() {
+			self.write_buffer(true);
+			self.flush_writer();
+		}
+	}
+}
@@ -0,0 +1,298 @@
+use std::{error::Error, fmt, mem};
+
+use crate::niche::Niche;
+
+/// Validate output of [`CompleteSerializer`], and get a reference to the
+/// value at `pos`.
+///
+/// Checks that the value at `pos`, and all data it owns, is a valid `T`.
+/// In particular, that all pointers point to data within `bytes`, correctly
+/// aligned, and that every value they point to is valid too.
+///
+/// [`CompleteSerializer`] writes pointers which point into its output buffer,
+/// so `bytes` must be that buffer, at the same memory address it was at when
+/// the value was serialized. If `bytes` has been copied elsewhere, all pointers
+/// will be found to be out of bounds, and validation will fail.
+///
+/// # Example
+///
+/// ```
+/// use ser_raw::{
+///     storage::{AlignedVec, ContiguousStorage},
+///     util::aligned_max_capacity,
+///     validate, CompleteSerializer, Serialize, Serializer, Validate,
+/// };
+///
+/// #[derive(Serialize, Validate, Debug, PartialEq)]
+/// struct Foo {
+///     boxed: Box<u8>,
+///     names: Vec<String>,
+/// }
+///
+/// let foo = Foo {
+///     boxed: Box::new(123),
+///     names: vec!["Alfred".to_string(), "Gertrude".to_string()],
+/// };
+///
+/// const MAX_CAPACITY: usize = aligned_max_capacity(16);
+/// let ser = CompleteSerializer::<16, 16, 8, MAX_CAPACITY, AlignedVec<16, 16, 8, MAX_CAPACITY>>::new();
+/// let (pos, storage) = ser.serialize(&foo);
+///
+/// let output: &Foo = validate(storage.as_slice(), pos).unwrap();
+/// assert_eq!(output, &foo);
+/// ```
+///
+/// [`CompleteSerializer`]: crate::CompleteSerializer
+pub fn validate<T: Validate>(bytes: &[u8], pos: usize) -> Result<&T, ValidateError> {
+	let mut validator = Validator::new(bytes);
+	let ptr = bytes.as_ptr().wrapping_add(pos) as *const T;
+	unsafe {
+		validator.claim(ptr)?;
+		T::validate(ptr, &mut validator)?;
+		Ok(&*ptr)
+	}
+}
+
+/// Trait for types which can be validated.
+///
+/// Usually implemented with the derive macro.
+///
+/// # With derive macro
+///
+/// ```
+/// use ser_raw::{Serialize, Validate};
+///
+/// #[derive(Serialize, Validate)]
+/// struct Foo {
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
+/// }
+/// ```
+///
+/// Enums with fields must have an explicit discriminant type e.g.
+/// `#[repr(u8)]` or `#[repr(C, u8)]`, as otherwise Rust gives no guarantees
+/// about where the discriminant is located.
+///
+/// # Manual implementation
+///
+/// [`validate`](Validate::validate) receives a pointer to a value which may not
+/// be valid, and must check that it is. It can rely on the pointer being within
+/// the buffer, and correctly aligned.
+///
+/// Any pointers the value contains must be checked with [`Validator::claim`] or
+/// [`Validator::claim_slice`] before they are dereferenced. The value they
+/// point to must then be validated too. This must happen in the same order as
+/// [`serialize_data`] pushes the values the pointers point to.
+///
+/// [`NICHE`](Validate::NICHE) is required. It states where the type's niche
+/// (bit patterns which are not valid) is, or `None` if it has no niche.
+/// It's used to determine layout of `Option`s of the type, and is checked at
+/// compile time when an `Option` of the type is validated. See
+/// [`niche`](crate::niche) module.
+///
+/// # Example
+///
+/// ```
+/// use std::ptr;
+///
+/// use ser_raw::{niche::Niche, Validate, ValidateError, Validator};
+///
+/// #[repr(C)]
+/// struct Foo {
+///     smalls: Vec<u8>,
+///     bigs: Vec<u32>,
+/// }
+///
+/// unsafe impl Validate for Foo {
+///     // Both fields have a niche of same size. `smalls` is first, so its niche
+///     // is used.
+///     const NICHE: Option<Niche> = <Vec<u8> as Validate>::NICHE;
+///
+///     unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+///         Validate::validate(ptr::addr_of!((*ptr).smalls), validator)?;
+///         Validate::validate(ptr::addr_of!((*ptr).bigs), validator)
+///     }
+/// }
+/// ```
+///
+/// # Safety
+///
+/// [`validate`](Validate::validate) must only return `Ok` if the value is a
+/// valid instance of the type, and all the data it owns is valid too.
+///
+/// [`NICHE`](Validate::NICHE) must be the exact location and size of the
+/// niche Rust uses for the type, or `None`. If a type which has a niche states
+/// `None`, `Option`s of the type fail to compile.
+///
+/// [`serialize_data`]: crate::Serialize::serialize_data
+pub unsafe trait Validate {
+	/// Location of type's niche, used to validate `Option`s of the type.
+	/// `None` if type has no niche.
+	const NICHE: Option<Niche>;
+
+	/// Validate value at `ptr`.
+	///
+	/// # Safety
+	///
+	/// `ptr` must be correctly aligned for `Self`, and valid for reads of
+	/// `size_of::<Self>()` bytes.
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError>;
+}
+
+/// Trait for implementing an equivalent of [`Validate`] on foreign types for
+/// which it's not possible to implement [`Validate`] directly due to orphan
+/// rules. Counterpart to [`SerializeWith`].
+///
+/// The same type used with `#[ser_with]` should implement both
+/// [`SerializeWith`] and [`ValidateWith`].
+///
+/// # Safety
+///
+/// Same requirements as [`Validate`].
+///
+/// [`SerializeWith`]: crate::SerializeWith
+pub unsafe trait ValidateWith<T> {
+	/// Location of niche of type `T`. See [`Validate::NICHE`].
+	const NICHE: Option<Niche>;
+
+	/// Validate value at `ptr`.
+	///
+	/// # Safety
+	///
+	/// Same requirements as [`Validate::validate`].
+	unsafe fn validate_with(ptr: *const T, validator: &mut Validator) -> Result<(), ValidateError>;
+}
+
+/// Validator for output of [`CompleteSerializer`].
+///
+/// Tracks bounds of the buffer being validated, and the end of data which has
+/// been validated so far.
+///
+/// Serializers write values in order, and never write a value which is pointed
+/// to before the pointer to it. So each pointer must point to data *after* all
+/// data which has been validated already. Enforcing this ensures no 2 values
+/// overlap, and prevents cycles.
+///
+/// [`CompleteSerializer`]: crate::CompleteSerializer
+pub struct Validator {
+	start: usize,
+	end: usize,
+	next: usize,
+}
+
+impl Validator {
+	/// Create new [`Validator`] for buffer `bytes`.
+	#[inline]
+	fn new(bytes: &[u8]) -> Self {
+		let start = bytes.as_ptr() as usize;
+		Self {
+			start,
+			end: start + bytes.len(),
+			next: start,
+		}
+	}
+
+	/// Get position in buffer of an address.
+	#[inline]
+	pub fn pos(&self, addr: usize) -> usize {
+		addr.wrapping_sub(self.start)
+	}
+
+	/// Check a pointer to a `T`, and claim the memory it points to.
+	///
+	/// See [`claim_slice`](Validator::claim_slice).
+	#[inline]
+	pub fn claim<T>(&mut self, ptr: *const T) -> Result<(), ValidateError> {
+		self.claim_slice(ptr, 1)
+	}
+
+	/// Check a pointer to a slice of `len` x `T`, and claim the memory it points
+	/// to.
+	///
+	/// Pointer must be non-null and correctly aligned. If slice has non-zero
+	/// size, it must be entirely within the buffer, and not before the end of
+	/// any data claimed previously.
+	///
+	/// Once this method returns `Ok`, it's valid to read `len` x `T` from `ptr`
+	/// (but those values need to be validated before they can be used).
+	pub fn claim_slice<T>(&mut self, ptr: *const T, len: usize) -> Result<(), ValidateError> {
+		let addr = ptr as usize;
+		if addr == 0 {
+			return Err(ValidateError::NullPointer);
+		}
+		if addr % mem::align_of::<T>() != 0 {
+			return Err(ValidateError::Misaligned { addr });
+		}
+
+		// Pointers to ZSTs and empty slices can be dangling
+		if mem::size_of::<T>() == 0 || len == 0 {
+			return Ok(());
+		}
+
+		let size = mem::size_of::<T>()
+			.checked_mul(len)
+			.ok_or(ValidateError::OutOfBounds { addr })?;
+		if addr < self.start || addr > self.end || size > self.end - addr {
+			return Err(ValidateError::OutOfBounds { addr });
+		}
+		if addr < self.next {
+			return Err(ValidateError::Overlapping {
+				pos: self.pos(addr),
+			});
+		}
+
+		self.next = addr + size;
+		Ok(())
+	}
+}
+
+/// Error returned by [`validate`] when input is not valid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidateError {
+	/// Pointer is null
+	NullPointer,
+	/// Pointer is not correctly aligned for its type
+	Misaligned { addr: usize },
+	/// Pointer points outside the buffer
+	OutOfBounds { addr: usize },
+	/// Pointer points to data before end of data already validated
+	Overlapping { pos: usize },
+	/// `Vec` or `String` has length greater than its capacity
+	LenExceedsCapacity { len: usize, capacity: usize },
+	/// `Vec` or `String` has capacity greater than `isize::MAX`
+	CapacityOverflow { capacity: usize },
+	/// `String` is not valid UTF-8
+	InvalidUtf8 { pos: usize },
+	/// `bool` which is not 0 or 1
+	InvalidBool { value: u8 },
+	/// `char` which is not a valid unicode scalar value
+	InvalidChar { value: u32 },
+	/// `NonZero*` which is zero
+	ZeroNonZero,
+	/// Enum discriminant which does not match any variant
+	InvalidDiscriminant,
+}
+
+impl fmt::Display for ValidateError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NullPointer => write!(f, "Pointer is null"),
+			Self::Misaligned { addr } => write!(f, "Pointer {addr:#x} is not correctly aligned"),
+			Self::OutOfBounds { addr } => write!(f, "Pointer {addr:#x} is out of bounds"),
+			Self::Overlapping { pos } => {
+				write!(f, "Pointer to position {pos} overlaps previous data")
+			}
+			Self::LenExceedsCapacity { len, capacity } => {
+				write!(f, "Length {len} exceeds capacity {capacity}")
+			}
+			Self::CapacityOverflow { capacity } => write!(f, "Capacity {capacity} is too large"),
+			Self::InvalidUtf8 { pos } => write!(f, "Invalid UTF-8 in string at position {pos}"),
+			Self::InvalidBool { value } => write!(f, "Invalid bool {value}"),
+			Self::InvalidChar { value } => write!(f, "Invalid char {value:#x}"),
+			Self::ZeroNonZero => write!(f, "Non-zero number is zero"),
+			Self::InvalidDiscriminant => write!(f, "Invalid enum discriminant"),
+		}
+	}
+}
+
+impl Error for ValidateError {}
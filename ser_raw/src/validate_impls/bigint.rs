@@ -0,0 +1,64 @@
+use std::mem;
+
+use num_bigint::{BigInt, BigUint, Sign};
+
+use crate::{
+	niche::Niche,
+	serialize_impls::{bigint::bigint_data_offset, ptrs::SameSizeAndAlignment},
+	Validate, ValidateError, Validator,
+};
+
+// `BigUint` is just a wrapper around a `Vec<usize>`.
+// See `Serialize` implementation.
+unsafe impl Validate for BigUint {
+	const NICHE: Option<Niche> = <Vec<usize> as Validate>::NICHE;
+
+	#[inline]
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		#[allow(clippy::let_unit_value)]
+		let _ = SameSizeAndAlignment::<BigUint, Vec<usize>>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
+
+		Vec::<usize>::validate(ptr as *const Vec<usize>, validator)
+	}
+}
+
+// `BigInt` is defined as `BigInt { sign: Sign, data: BigUint }`.
+// `data` is either at start or end, and `sign` is at the other end.
+// See `Serialize` implementation.
+unsafe impl Validate for BigInt {
+	// `sign`'s niche (253 invalid values) is larger than `data`'s.
+	// `bigint_data_offset` can't be evaluated at compile time, so assume `sign`
+	// is last, as it is in practice. If it isn't, `Option<BigInt>` will fail to
+	// compile, rather than being validated incorrectly.
+	const NICHE: Option<Niche> = Niche::field(
+		Niche::tag(
+			1,
+			&[
+				Sign::Minus as i128,
+				Sign::NoSign as i128,
+				Sign::Plus as i128,
+			],
+		),
+		mem::size_of::<BigUint>(),
+	);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		#[allow(clippy::let_unit_value)]
+		let _ = SameSizeAndAlignment::<BigInt, (u8, BigUint)>::ASSERT_SAME_SIZE_AND_ALIGNMENT;
+
+		let ptr = ptr as *const u8;
+		let data_offset = bigint_data_offset();
+		let sign_offset = if data_offset == 0 {
+			mem::size_of::<BigUint>()
+		} else {
+			0
+		};
+
+		let sign = *ptr.add(sign_offset);
+		if sign != Sign::Minus as u8 && sign != Sign::NoSign as u8 && sign != Sign::Plus as u8 {
+			return Err(ValidateError::InvalidDiscriminant);
+		}
+
+		BigUint::validate(ptr.add(data_offset) as *const BigUint, validator)
+	}
+}
@@ -0,0 +1,42 @@
+use std::{collections::VecDeque, mem};
+
+use super::ptrs::check_len_and_capacity;
+use crate::{
+	niche::Niche, serialize_impls::collections::VecDequeOffsets, Validate, ValidateError, Validator,
+};
+
+/// Serializers write `VecDeque`s contiguously, with `head` of 0, so only
+/// contiguous `VecDeque`s (`head + len <= capacity`) are accepted.
+unsafe impl<T> Validate for VecDeque<T>
+where T: Validate
+{
+	const NICHE: Option<Niche> = Niche::ptr(VecDequeOffsets::<T>::PTR_OFFSET);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let ptr = ptr as *const u8;
+		let offsets = VecDequeOffsets::<T>::get();
+		let data = *(ptr.add(VecDequeOffsets::<T>::PTR_OFFSET) as *const *const T);
+		let len = *(ptr.add(offsets.len) as *const usize);
+
+		// Deques of ZSTs have no contents in output, and capacity is irrelevant.
+		// All ZSTs of same type are identical, so only need to validate one.
+		if mem::size_of::<T>() == 0 {
+			validator.claim_slice(data, len)?;
+			if len > 0 {
+				T::validate(data, validator)?;
+			}
+			return Ok(());
+		}
+
+		let head = *(ptr.add(offsets.head) as *const usize);
+		let capacity = *(ptr.add(offsets.capacity) as *const usize);
+		check_len_and_capacity(head.saturating_add(len), capacity)?;
+
+		let data = data.wrapping_add(head);
+		validator.claim_slice(data, len)?;
+		for index in 0..len {
+			T::validate(data.add(index), validator)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,59 @@
+use std::{slice, str};
+
+use compact_str::CompactString;
+
+use super::ptrs::check_len_and_capacity;
+use crate::{
+	niche::Niche,
+	serialize_impls::compact_str::{
+		CAP_OFFSET, HEAP_MASK, LENGTH_MASK, LEN_OFFSET, MAX_CAPACITY, MAX_SIZE, PTR_OFFSET,
+	},
+	Validate, ValidateError, Validator,
+};
+
+const PTR_SIZE: usize = std::mem::size_of::<usize>();
+
+// Last byte is discriminant. See `Serialize` implementation.
+unsafe impl Validate for CompactString {
+	// Last byte can be any value except 255
+	const NICHE: Option<Niche> = Niche::field(Niche::tag(1, &[0, HEAP_MASK as i128]), MAX_SIZE - 1);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let ptr = ptr as *const u8;
+		let last_byte = *ptr.add(MAX_SIZE - 1);
+
+		let (data, len) = if last_byte == HEAP_MASK {
+			let data = *(ptr.add(PTR_OFFSET) as *const *const u8);
+			let len = *(ptr.add(LEN_OFFSET) as *const usize);
+
+			// Capacity is bytes other than last
+			let mut capacity_bytes = *(ptr.add(CAP_OFFSET) as *const [u8; PTR_SIZE]);
+			capacity_bytes[PTR_SIZE - 1] = 0;
+			let capacity = usize::from_le_bytes(capacity_bytes);
+			// Larger values indicate capacity is stored on heap, which isn't supported
+			if capacity > MAX_CAPACITY {
+				return Err(ValidateError::CapacityOverflow { capacity });
+			}
+			check_len_and_capacity(len, capacity)?;
+
+			validator.claim_slice(data, len)?;
+			(data, len)
+		} else {
+			// Inline string is within the `CompactString`, which is already claimed.
+			// Length is calculated same way as `compact_str` does. If last byte is
+			// not a valid length, string is `MAX_SIZE` bytes, and UTF-8 check
+			// rejects it if its last byte is invalid.
+			let len = (last_byte.wrapping_sub(LENGTH_MASK) as usize).min(MAX_SIZE);
+			(ptr, len)
+		};
+
+		match str::from_utf8(slice::from_raw_parts(data, len)) {
+			Ok(_) => Ok(()),
+			Err(_) => {
+				Err(ValidateError::InvalidUtf8 {
+					pos: validator.pos(data as usize),
+				})
+			}
+		}
+	}
+}
@@ -0,0 +1,23 @@
+mod collections;
+mod multiples;
+mod other;
+mod primitives;
+pub(crate) mod ptrs;
+
+#[cfg(feature = "num_bigint")]
+mod bigint;
+
+#[cfg(feature = "smallvec")]
+mod smallvec;
+
+#[cfg(feature = "thin_vec")]
+mod thin_vec;
+
+#[cfg(feature = "compact_str")]
+mod compact_str;
+
+#[cfg(feature = "smol_str")]
+mod smol_str;
+
+#[cfg(feature = "string_cache")]
+mod string_cache;
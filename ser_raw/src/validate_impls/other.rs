@@ -0,0 +1,40 @@
+use std::marker::PhantomData;
+
+use crate::{
+	niche::{read_bytes, Niche, OptionRepr},
+	Validate, ValidateError, Validator,
+};
+
+// Rust gives no guarantees about layout of `Option<T>`, so it's determined at
+// compile time, from `T`'s niche. See `niche` module.
+unsafe impl<T> Validate for Option<T>
+where T: Validate
+{
+	const NICHE: Option<Niche> = OptionReprOf::<T>::REPR.niche;
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let repr = OptionReprOf::<T>::REPR;
+		let ptr = ptr as *const u8;
+		let tag = read_bytes(ptr.add(repr.tag_offset), repr.tag_size);
+		if tag == repr.none {
+			return Ok(());
+		}
+
+		match repr.some {
+			// Niche-encoded. Not `None`, so must be `Some`, and `T` occupies the whole
+			// of `Option<T>`. `T::validate` checks niche is a valid value for `T`.
+			None => T::validate(ptr as *const T, validator),
+			Some(some) if tag == some => T::validate(ptr.add(repr.payload_offset) as *const T, validator),
+			Some(_) => Err(ValidateError::InvalidDiscriminant),
+		}
+	}
+}
+
+struct OptionReprOf<T> {
+	_marker: PhantomData<T>,
+}
+
+impl<T: Validate> OptionReprOf<T> {
+	// Safe because evaluated in const context
+	const REPR: OptionRepr = unsafe { OptionRepr::new::<T>(T::NICHE) };
+}
@@ -0,0 +1,100 @@
+use std::{mem, num};
+
+use crate::{niche::Niche, Validate, ValidateError, Validator};
+
+// Types for which every bit pattern is valid
+macro_rules! impl_primitive {
+	($ty:ty) => {
+		unsafe impl Validate for $ty {
+			const NICHE: Option<Niche> = None;
+
+			#[inline(always)]
+			unsafe fn validate(
+				_ptr: *const Self,
+				_validator: &mut Validator,
+			) -> Result<(), ValidateError> {
+				Ok(())
+			}
+		}
+	};
+}
+
+impl_primitive!(u8);
+impl_primitive!(u16);
+impl_primitive!(u32);
+impl_primitive!(u64);
+impl_primitive!(u128);
+impl_primitive!(usize);
+
+impl_primitive!(i8);
+impl_primitive!(i16);
+impl_primitive!(i32);
+impl_primitive!(i64);
+impl_primitive!(i128);
+impl_primitive!(isize);
+
+impl_primitive!(f32);
+impl_primitive!(f64);
+
+impl_primitive!(());
+
+macro_rules! impl_non_zero {
+	($ty:ty, $int:ty) => {
+		unsafe impl Validate for $ty {
+			// Only 0 is invalid
+			const NICHE: Option<Niche> = Some(Niche::new(0, mem::size_of::<$int>(), 1));
+
+			#[inline]
+			unsafe fn validate(
+				ptr: *const Self,
+				_validator: &mut Validator,
+			) -> Result<(), ValidateError> {
+				match *(ptr as *const $int) {
+					0 => Err(ValidateError::ZeroNonZero),
+					_ => Ok(()),
+				}
+			}
+		}
+	};
+}
+
+impl_non_zero!(num::NonZeroU8, u8);
+impl_non_zero!(num::NonZeroU16, u16);
+impl_non_zero!(num::NonZeroU32, u32);
+impl_non_zero!(num::NonZeroU64, u64);
+impl_non_zero!(num::NonZeroU128, u128);
+impl_non_zero!(num::NonZeroUsize, usize);
+
+impl_non_zero!(num::NonZeroI8, i8);
+impl_non_zero!(num::NonZeroI16, i16);
+impl_non_zero!(num::NonZeroI32, i32);
+impl_non_zero!(num::NonZeroI64, i64);
+impl_non_zero!(num::NonZeroI128, i128);
+impl_non_zero!(num::NonZeroIsize, isize);
+
+unsafe impl Validate for bool {
+	// Only 0 and 1 are valid
+	const NICHE: Option<Niche> = Niche::tag(1, &[0, 1]);
+
+	#[inline]
+	unsafe fn validate(ptr: *const Self, _validator: &mut Validator) -> Result<(), ValidateError> {
+		match *(ptr as *const u8) {
+			0 | 1 => Ok(()),
+			value => Err(ValidateError::InvalidBool { value }),
+		}
+	}
+}
+
+unsafe impl Validate for char {
+	// Rust treats `0..=char::MAX` as valid (including surrogates)
+	const NICHE: Option<Niche> = Niche::tag(4, &[0, char::MAX as i128]);
+
+	#[inline]
+	unsafe fn validate(ptr: *const Self, _validator: &mut Validator) -> Result<(), ValidateError> {
+		let value = *(ptr as *const u32);
+		match char::from_u32(value) {
+			Some(_) => Ok(()),
+			None => Err(ValidateError::InvalidChar { value }),
+		}
+	}
+}
@@ -0,0 +1,151 @@
+#[cfg(unix)]
+use std::path::Path;
+use std::{mem, slice, str};
+
+use crate::{
+	niche::Niche,
+	serialize_impls::ptrs::{
+		VecOffsets, BOXED_SLICE_LEN_OFFSET, BOXED_SLICE_PTR_OFFSET, OFFSETS_STRING, STRING_PTR_OFFSET,
+	},
+	Validate, ValidateError, Validator,
+};
+
+unsafe impl<T> Validate for Box<T>
+where T: Validate
+{
+	const NICHE: Option<Niche> = Niche::ptr(0);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let data = *(ptr as *const *const T);
+		validator.claim(data)?;
+		T::validate(data, validator)
+	}
+}
+
+unsafe impl<T> Validate for Vec<T>
+where T: Validate
+{
+	const NICHE: Option<Niche> = Niche::ptr(VecOffsets::<T>::PTR_OFFSET);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let ptr = ptr as *const u8;
+		let data = *(ptr.add(VecOffsets::<T>::PTR_OFFSET) as *const *const T);
+		let len = *(ptr.add(VecOffsets::<T>::OFFSETS_VEC.len()) as *const usize);
+
+		// Vecs of ZSTs have no contents in output, and capacity is irrelevant.
+		// All ZSTs of same type are identical, so only need to validate one.
+		if mem::size_of::<T>() == 0 {
+			validator.claim_slice(data, len)?;
+			if len > 0 {
+				T::validate(data, validator)?;
+			}
+			return Ok(());
+		}
+
+		let capacity = *(ptr.add(VecOffsets::<T>::OFFSETS_VEC.capacity()) as *const usize);
+		check_len_and_capacity(len, capacity)?;
+
+		validator.claim_slice(data, len)?;
+		for index in 0..len {
+			T::validate(data.add(index), validator)?;
+		}
+		Ok(())
+	}
+}
+
+unsafe impl Validate for String {
+	const NICHE: Option<Niche> = Niche::ptr(STRING_PTR_OFFSET);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let ptr = ptr as *const u8;
+		let data = *(ptr.add(STRING_PTR_OFFSET) as *const *const u8);
+		let len = *(ptr.add(OFFSETS_STRING.len()) as *const usize);
+		let capacity = *(ptr.add(OFFSETS_STRING.capacity()) as *const usize);
+		check_len_and_capacity(len, capacity)?;
+
+		validator.claim_slice(data, len)?;
+		match str::from_utf8(slice::from_raw_parts(data, len)) {
+			Ok(_) => Ok(()),
+			Err(_) => {
+				Err(ValidateError::InvalidUtf8 {
+					pos: validator.pos(data as usize),
+				})
+			}
+		}
+	}
+}
+
+unsafe impl<T> Validate for Box<[T]>
+where T: Validate
+{
+	const NICHE: Option<Niche> = Niche::ptr(BOXED_SLICE_PTR_OFFSET);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let (data, len) = boxed_slice_parts::<Self, T>(ptr);
+
+		validator.claim_slice(data, len)?;
+		// All ZSTs of same type are identical, so only need to validate one
+		let validate_len = if mem::size_of::<T>() == 0 {
+			len.min(1)
+		} else {
+			len
+		};
+		for index in 0..validate_len {
+			T::validate(data.add(index), validator)?;
+		}
+		Ok(())
+	}
+}
+
+unsafe impl Validate for Box<str> {
+	const NICHE: Option<Niche> = Niche::ptr(BOXED_SLICE_PTR_OFFSET);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let (data, len) = boxed_slice_parts::<Self, u8>(ptr);
+
+		validator.claim_slice(data, len)?;
+		match str::from_utf8(slice::from_raw_parts(data, len)) {
+			Ok(_) => Ok(()),
+			Err(_) => {
+				Err(ValidateError::InvalidUtf8 {
+					pos: validator.pos(data as usize),
+				})
+			}
+		}
+	}
+}
+
+/// On Unix, paths can contain any bytes, so only bounds are checked.
+#[cfg(unix)]
+unsafe impl Validate for Box<Path> {
+	const NICHE: Option<Niche> = Niche::ptr(BOXED_SLICE_PTR_OFFSET);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let (data, len) = boxed_slice_parts::<Self, u8>(ptr);
+		validator.claim_slice(data, len)
+	}
+}
+
+/// Get pointer and length from fat pointer of a boxed slice (`Box<[T]>`,
+/// `Box<str>` or `Box<Path>`) at `ptr`.
+#[inline]
+unsafe fn boxed_slice_parts<B, T>(ptr: *const B) -> (*const T, usize) {
+	let ptr = ptr as *const u8;
+	let data = *(ptr.add(BOXED_SLICE_PTR_OFFSET) as *const *const T);
+	let len = *(ptr.add(BOXED_SLICE_LEN_OFFSET) as *const usize);
+	(data, len)
+}
+
+/// Check `len` and `capacity` of a `Vec`, `String` or `VecDeque` are valid.
+/// Capacity can never exceed `isize::MAX` (Rust may use values above that as a
+/// niche e.g. to represent `None` in `Option<Vec<T>>`).
+#[inline]
+pub(crate) fn check_len_and_capacity(len: usize, capacity: usize) -> Result<(), ValidateError> {
+	if capacity > isize::MAX as usize {
+		Err(ValidateError::CapacityOverflow { capacity })
+	} else if len > capacity {
+		Err(ValidateError::LenExceedsCapacity { len, capacity })
+	} else {
+		Ok(())
+	}
+}
@@ -0,0 +1,49 @@
+use std::mem;
+
+use smallvec::{Array, SmallVec};
+
+use super::ptrs::check_len_and_capacity;
+use crate::{
+	niche::Niche, serialize_impls::smallvec::SmallVecOffsets, Validate, ValidateError, Validator,
+};
+
+// If `capacity` is no larger than inline capacity, contents are inline, and
+// `capacity` is their length. Otherwise contents are on heap.
+// See `Serialize` implementation.
+unsafe impl<A> Validate for SmallVec<A>
+where
+	A: Array,
+	A::Item: Validate,
+{
+	// `capacity` can be any value, and `data` is a union with no tag
+	const NICHE: Option<Niche> = None;
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let offsets = SmallVecOffsets::of::<A>();
+		let ptr = ptr as *const u8;
+		let capacity = *(ptr.add(offsets.capacity) as *const usize);
+
+		// `SmallVec`s of ZSTs are always inline
+		let (data, len) = if mem::size_of::<A::Item>() == 0 || capacity <= A::size() {
+			// Inline contents are within the `SmallVec`, which is already claimed
+			(ptr.add(offsets.inline) as *const A::Item, capacity)
+		} else {
+			let data = *(ptr.add(offsets.ptr) as *const *const A::Item);
+			let len = *(ptr.add(offsets.len) as *const usize);
+			check_len_and_capacity(len, capacity)?;
+			validator.claim_slice(data, len)?;
+			(data, len)
+		};
+
+		// All ZSTs of same type are identical, so only need to validate one
+		let validate_len = if mem::size_of::<A::Item>() == 0 {
+			len.min(1)
+		} else {
+			len
+		};
+		for index in 0..validate_len {
+			A::Item::validate(data.add(index), validator)?;
+		}
+		Ok(())
+	}
+}
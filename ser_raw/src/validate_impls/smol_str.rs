@@ -0,0 +1,60 @@
+use std::{mem, slice, str};
+
+use smol_str::SmolStr;
+
+use crate::{
+	niche::Niche,
+	serialize_impls::{
+		shared::RcBox,
+		smol_str::{
+			FIELDS_OFFSET, HEAP_LEN_OFFSET, HEAP_PTR_OFFSET, HEAP_TAG, INLINE_CAP, INLINE_OFFSET,
+			N_NEWLINES, N_SPACES, SUBSTRING_TAG, TAG_OFFSET,
+		},
+	},
+	Validate, ValidateError, Validator,
+};
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+// Tag is `len` field of `Inline` variant, or tag of another variant.
+// See `Serialize` implementation.
+unsafe impl Validate for SmolStr {
+	const NICHE: Option<Niche> = Niche::field(Niche::tag(1, &[0, SUBSTRING_TAG as i128]), TAG_OFFSET);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let ptr = ptr as *const u8;
+		let tag = *ptr.add(TAG_OFFSET);
+
+		let (data, len) = if tag as usize <= INLINE_CAP {
+			// Inline string is within the `SmolStr`, which is already claimed
+			(ptr.add(INLINE_OFFSET), tag as usize)
+		} else if tag == HEAP_TAG {
+			let rc_box = *(ptr.add(HEAP_PTR_OFFSET) as *const *const RcBox<[u8; 0]>);
+			let len = *(ptr.add(HEAP_LEN_OFFSET) as *const usize);
+			validator.claim(rc_box)?;
+			let data = (rc_box as *const u8).add(mem::size_of::<RcBox<[u8; 0]>>());
+			validator.claim_slice(data, len)?;
+			(data, len)
+		} else if tag == SUBSTRING_TAG {
+			// Slice of a static string, so only need to check it's in bounds
+			let newlines = *(ptr.add(FIELDS_OFFSET) as *const usize);
+			let spaces = *(ptr.add(FIELDS_OFFSET + PTR_SIZE) as *const usize);
+			return if newlines <= N_NEWLINES && spaces <= N_SPACES {
+				Ok(())
+			} else {
+				Err(ValidateError::InvalidDiscriminant)
+			};
+		} else {
+			return Err(ValidateError::InvalidDiscriminant);
+		};
+
+		match str::from_utf8(slice::from_raw_parts(data, len)) {
+			Ok(_) => Ok(()),
+			Err(_) => {
+				Err(ValidateError::InvalidUtf8 {
+					pos: validator.pos(data as usize),
+				})
+			}
+		}
+	}
+}
@@ -0,0 +1,67 @@
+use std::{slice, str};
+
+use string_cache::{Atom, StaticAtomSet};
+
+use crate::{
+	niche::Niche,
+	serialize_impls::string_cache::{
+		EntryBytes, DYNAMIC_TAG, ENTRY_STR_LEN_OFFSET, ENTRY_STR_PTR_OFFSET, INLINE_LEN_SHIFT,
+		INLINE_TAG, MAX_INLINE_LEN, STATIC_SHIFT, STATIC_TAG, TAG_MASK,
+	},
+	Validate, ValidateError, Validator,
+};
+
+// Low 2 bits are tag. See `Serialize` implementation.
+unsafe impl<Static> Validate for Atom<Static>
+where Static: StaticAtomSet
+{
+	// `Atom` is a `NonZeroU64`. Only 0 is invalid.
+	const NICHE: Option<Niche> = Some(Niche::new(0, 8, 1));
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let data = *(ptr as *const u64);
+		let (str_ptr, len) = match data & TAG_MASK {
+			DYNAMIC_TAG => {
+				let entry = data as usize as *const EntryBytes;
+				validator.claim(entry)?;
+				let entry = entry as *const u8;
+				let str_ptr = *(entry.add(ENTRY_STR_PTR_OFFSET) as *const *const u8);
+				let len = *(entry.add(ENTRY_STR_LEN_OFFSET) as *const usize);
+				validator.claim_slice(str_ptr, len)?;
+				(str_ptr, len)
+			}
+			INLINE_TAG => {
+				// String is within the `Atom`, which is already claimed.
+				// String is in all bytes except the lowest.
+				let len = ((data & 0xf0) >> INLINE_LEN_SHIFT) as usize;
+				if len > MAX_INLINE_LEN {
+					return Err(ValidateError::InvalidDiscriminant);
+				}
+				let str_ptr = if cfg!(target_endian = "little") {
+					(ptr as *const u8).add(1)
+				} else {
+					ptr as *const u8
+				};
+				(str_ptr, len)
+			}
+			STATIC_TAG => {
+				let index = (data >> STATIC_SHIFT) as usize;
+				return if index < Static::get().atoms.len() {
+					Ok(())
+				} else {
+					Err(ValidateError::InvalidDiscriminant)
+				};
+			}
+			_ => return Err(ValidateError::InvalidDiscriminant),
+		};
+
+		match str::from_utf8(slice::from_raw_parts(str_ptr, len)) {
+			Ok(_) => Ok(()),
+			Err(_) => {
+				Err(ValidateError::InvalidUtf8 {
+					pos: validator.pos(str_ptr as usize),
+				})
+			}
+		}
+	}
+}
@@ -0,0 +1,36 @@
+use std::mem;
+
+use thin_vec::ThinVec;
+
+use super::ptrs::check_len_and_capacity;
+use crate::{
+	niche::Niche, serialize_impls::thin_vec::ThinVecHeader, Validate, ValidateError, Validator,
+};
+
+// `ThinVec<T>` is a pointer to a header, followed by contents.
+// See `Serialize` implementation.
+unsafe impl<T> Validate for ThinVec<T>
+where T: Validate
+{
+	const NICHE: Option<Niche> = Niche::ptr(0);
+
+	unsafe fn validate(ptr: *const Self, validator: &mut Validator) -> Result<(), ValidateError> {
+		let header = *(ptr as *const *const ThinVecHeader<T>);
+		validator.claim(header)?;
+		let len = (*header).len;
+		check_len_and_capacity(len, (*header).cap)?;
+
+		let data = header.add(1) as *const T;
+		validator.claim_slice(data, len)?;
+		// All ZSTs of same type are identical, so only need to validate one
+		let validate_len = if mem::size_of::<T>() == 0 {
+			len.min(1)
+		} else {
+			len
+		};
+		for index in 0..validate_len {
+			T::validate(data.add(index), validator)?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,301 @@
+use std::{
+	alloc::{self, Layout},
+	cell::{Cell, RefCell},
+	ptr::NonNull,
+};
+
+use ser_raw::{
+	pos::{PosMapping, Ptrs, SharedAllocs},
+	storage::{AlignedVec, Arena, ArenaStorage, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, PureCopySerializer, Serialize, SerializeError, Serializer,
+	Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store<'a> = ArenaStorage<'a, BumpArena, 16, 16, 8, MAX_CAPACITY>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+
+/// Bump allocator which allocates in chunks.
+/// Most recent allocation can be grown in place if there's room in the chunk.
+struct BumpArena {
+	chunk_size: usize,
+	chunks: RefCell<Vec<(NonNull<u8>, Layout)>>,
+	/// Start of free space in current chunk
+	next: Cell<usize>,
+	/// End of current chunk
+	end: Cell<usize>,
+	/// Start of most recent allocation
+	last: Cell<usize>,
+	grown_in_place: Cell<usize>,
+}
+
+impl BumpArena {
+	fn new(chunk_size: usize) -> Self {
+		Self {
+			chunk_size,
+			chunks: RefCell::new(Vec::new()),
+			next: Cell::new(0),
+			end: Cell::new(0),
+			last: Cell::new(0),
+			grown_in_place: Cell::new(0),
+		}
+	}
+
+	fn num_chunks(&self) -> usize {
+		self.chunks.borrow().len()
+	}
+}
+
+unsafe impl Arena for BumpArena {
+	fn alloc(&self, layout: Layout) -> NonNull<u8> {
+		let mut start = (self.next.get() + layout.align() - 1) & !(layout.align() - 1);
+		if self.end.get() == 0 || start + layout.size() > self.end.get() {
+			// Start a new chunk
+			let size = self.chunk_size.max(layout.size());
+			let chunk_layout = Layout::from_size_align(size, 64).unwrap();
+			let ptr = unsafe { alloc::alloc(chunk_layout) };
+			let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(chunk_layout));
+			self.chunks.borrow_mut().push((ptr, chunk_layout));
+			start = ptr.as_ptr() as usize;
+			self.end.set(start + size);
+		}
+		self.next.set(start + layout.size());
+		self.last.set(start);
+		NonNull::new(start as *mut u8).unwrap()
+	}
+
+	unsafe fn grow(&self, ptr: NonNull<u8>, old_layout: Layout, new_size: usize) -> NonNull<u8> {
+		let start = ptr.as_ptr() as usize;
+		if start == self.last.get() && start + new_size <= self.end.get() {
+			self.next.set(start + new_size);
+			self.grown_in_place.set(self.grown_in_place.get() + 1);
+			return ptr;
+		}
+
+		let new_ptr = self.alloc(Layout::from_size_align_unchecked(
+			new_size,
+			old_layout.align(),
+		));
+		std::ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), old_layout.size());
+		new_ptr
+	}
+}
+
+impl Drop for BumpArena {
+	fn drop(&mut self) {
+		for &(ptr, layout) in self.chunks.borrow().iter() {
+			unsafe { alloc::dealloc(ptr.as_ptr(), layout) };
+		}
+	}
+}
+
+/// Arena which only implements `alloc`, so uses default `grow`
+#[derive(Default)]
+struct SimpleArena {
+	allocations: RefCell<Vec<(NonNull<u8>, Layout)>>,
+}
+
+unsafe impl Arena for SimpleArena {
+	fn alloc(&self, layout: Layout) -> NonNull<u8> {
+		let ptr = NonNull::new(unsafe { alloc::alloc(layout) })
+			.unwrap_or_else(|| alloc::handle_alloc_error(layout));
+		self.allocations.borrow_mut().push((ptr, layout));
+		ptr
+	}
+}
+
+impl Drop for SimpleArena {
+	fn drop(&mut self) {
+		for &(ptr, layout) in self.allocations.borrow().iter() {
+			unsafe { alloc::dealloc(ptr.as_ptr(), layout) };
+		}
+	}
+}
+
+#[derive(Serializer)]
+#[ser_type(pure_copy)]
+struct ArenaPureCopySer<'a> {
+	#[ser_storage(Store<'a>)]
+	storage: Store<'a>,
+}
+
+#[derive(Serializer)]
+#[ser_type(complete)]
+struct ArenaCompleteSer<'a, A: Arena> {
+	#[ser_storage(ArenaStorage<'a, A, 16, 16, 8, MAX_CAPACITY>)]
+	storage: ArenaStorage<'a, A, 16, 16, 8, MAX_CAPACITY>,
+	#[ser_pos_mapping]
+	pos_mapping: PosMapping,
+	#[ser_ptrs]
+	ptrs: Ptrs,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+impl<'a, A: Arena> ArenaCompleteSer<'a, A> {
+	fn new_in(arena: &'a A) -> Self {
+		Self {
+			storage: ArenaStorage::new_in(arena),
+			pos_mapping: PosMapping::dummy(),
+			ptrs: Ptrs::new(),
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+}
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+struct Module {
+	name: String,
+	items: Vec<Item>,
+}
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+#[repr(u8)]
+enum Item {
+	Const(u64),
+	Func { name: String, body: Vec<Item> },
+}
+
+fn create_module(size: usize) -> Module {
+	Module {
+		name: "module".to_string(),
+		items: (0..size)
+			.map(|i| {
+				if i % 2 == 0 {
+					Item::Const(i as u64)
+				} else {
+					Item::Func {
+						name: format!("func_{i}"),
+						body: (0..(i % 5) as u64).map(Item::Const).collect(),
+					}
+				}
+			})
+			.collect(),
+	}
+}
+
+#[test]
+fn pure_copy_output_matches_aligned_vec() {
+	let input = create_module(100);
+	let (_, expected) = PureCopySer::new().serialize(&input);
+
+	let arena = BumpArena::new(1024);
+	let ser = ArenaPureCopySer {
+		storage: Store::new_in(&arena),
+	};
+	let (_, storage) = ser.serialize(&input);
+	assert_eq!(storage.pos(), expected.pos());
+	assert_eq!(storage.as_slice(), expected.as_slice());
+}
+
+#[test]
+fn complete_grows_in_place() {
+	let input = create_module(100);
+	let (_, expected) = CompleteSer::new().serialize(&input);
+
+	// Chunk is large enough for whole output, so buffer is always grown in place
+	let arena = BumpArena::new(1024 * 1024);
+	let (pos, storage) = ArenaCompleteSer::new_in(&arena).serialize(&input);
+	assert_eq!(storage.pos(), expected.pos());
+	assert_eq!(arena.num_chunks(), 1);
+	assert!(arena.grown_in_place.get() > 0);
+
+	let output: &Module = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+	let output: &Module = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn complete_relocates_on_overflow() {
+	let input = create_module(100);
+
+	// Small chunks, so buffer has to be relocated into a new chunk as it grows
+	let arena = BumpArena::new(256);
+	let (pos, storage) = ArenaCompleteSer::new_in(&arena).serialize(&input);
+	assert!(arena.num_chunks() > 1);
+
+	let output: &Module = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+	let output: &Module = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn default_grow() {
+	let input = create_module(50);
+	let arena = SimpleArena::default();
+	let (pos, storage) = ArenaCompleteSer::new_in(&arena).serialize(&input);
+	assert!(arena.allocations.borrow().len() > 1);
+
+	let output: &Module = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn output_outlives_storage() {
+	let arena = BumpArena::new(4096);
+
+	let inputs = [create_module(10), create_module(20)];
+	let outputs = inputs
+		.iter()
+		.map(|input| {
+			let (pos, storage) = ArenaCompleteSer::new_in(&arena).serialize(input);
+			(pos, storage.into_slice())
+		})
+		.collect::<Vec<_>>();
+
+	for ((pos, bytes), input) in outputs.into_iter().zip(inputs.iter()) {
+		assert_eq!(bytes.as_ptr() as usize % 16, 0);
+		let output: &Module = validate(bytes, pos).unwrap();
+		assert_eq!(output, input);
+	}
+}
+
+#[test]
+fn alignment_and_capacity() {
+	let arena = BumpArena::new(4096);
+	// Misalign arena's next allocation
+	arena.alloc(Layout::from_size_align(3, 1).unwrap());
+
+	let mut storage = Store::with_capacity_in(20, &arena);
+	assert_eq!(storage.capacity(), 32);
+	assert_eq!(storage.as_ptr() as usize % 16, 0);
+
+	storage.push_slice(&[1u64; 10]);
+	assert_eq!(storage.pos(), 80);
+	assert_eq!(storage.capacity(), 128);
+	assert_eq!(storage.as_ptr() as usize % 16, 0);
+
+	// `shrink_to_fit` is a no-op
+	storage.shrink_to_fit();
+	assert_eq!(storage.capacity(), 128);
+}
+
+#[test]
+fn no_arena_returns_error() {
+	let err = SerializeError::CapacityExceeded {
+		storage: "ArenaStorage",
+	};
+
+	let mut storage = Store::new();
+	assert_eq!(storage.capacity(), 0);
+	assert_eq!(storage.try_reserve(0), Ok(()));
+	assert_eq!(storage.try_reserve(1), Err(err));
+	assert_eq!(Store::try_with_capacity(0).unwrap().capacity(), 0);
+	assert_eq!(Store::try_with_capacity(16).err(), Some(err));
+
+	let ser = ArenaPureCopySer {
+		storage: Store::new(),
+	};
+	assert_eq!(ser.try_serialize(&create_module(10)).err(), Some(err));
+}
+
+#[test]
+#[should_panic(expected = "Cannot grow ArenaStorage further: ArenaStorage capacity exceeded")]
+fn no_arena_panics() {
+	let mut storage = Store::new();
+	storage.push(&1u64);
+}
@@ -0,0 +1,279 @@
+use std::{ptr, rc::Rc};
+
+use ser_raw::{
+	read::OffsetRef,
+	ser_traits::Complete,
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage, WriteStorage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, PortableSerializer, PtrOffsetSerializer, PureCopySerializer,
+	Serialize, SerializeError, Serializer, Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 8, PortableStore>;
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+struct Doc {
+	title: String,
+	paras: Vec<String>,
+}
+
+fn create_doc(num_paras: usize) -> Doc {
+	Doc {
+		title: format!("doc_{num_paras}"),
+		paras: (0..num_paras).map(|i| format!("paragraph {i}")).collect(),
+	}
+}
+
+/// Serialize `keep[0]`, then `discard`, roll back, then `keep[1]`.
+/// Returns positions of kept values.
+fn serialize_with_rollback<Ser: Serializer>(
+	ser: &mut Ser,
+	keep: [&Doc; 2],
+	discard: &Doc,
+) -> [usize; 2]
+where
+	Doc: Serialize<Ser>,
+{
+	let pos1 = ser.serialize_value(keep[0]);
+	let checkpoint = ser.checkpoint();
+	assert_eq!(checkpoint.pos(), ser.pos());
+	ser.serialize_value(discard);
+	ser.rollback(checkpoint).unwrap();
+	assert_eq!(ser.pos(), checkpoint.pos());
+	let pos2 = ser.serialize_value(keep[1]);
+	[pos1, pos2]
+}
+
+#[test]
+fn complete_rollback() {
+	let first = create_doc(5);
+	let second = create_doc(10);
+	// Storage grows and moves while serializing this, so pointer groups are
+	// created which must be discarded
+	let discard = create_doc(1000);
+
+	let mut ser = CompleteSer::new();
+	let [pos1, pos2] = serialize_with_rollback(&mut ser, [&first, &second], &discard);
+
+	let mut expected = CompleteSer::new();
+	assert_eq!(expected.serialize_value(&first), pos1);
+	assert_eq!(expected.serialize_value(&second), pos2);
+	assert_eq!(ser.ptr_positions(), expected.ptr_positions());
+	assert_eq!(ser.pos(), expected.pos());
+
+	let storage = ser.finalize();
+	let output: &Doc = validate(storage.as_slice(), pos1).unwrap();
+	assert_eq!(output, &first);
+	let output: &Doc = validate(storage.as_slice(), pos2).unwrap();
+	assert_eq!(output, &second);
+}
+
+#[test]
+fn complete_rollback_after_finalize_in_place() {
+	let first = create_doc(5);
+	let second = create_doc(3);
+
+	let mut ser = CompleteSer::new();
+	let pos1 = ser.serialize_value(&first);
+	ser.finalize_in_place();
+	let checkpoint = ser.checkpoint();
+	ser.serialize_value(&create_doc(2000));
+	ser.rollback(checkpoint).unwrap();
+	let pos2 = ser.serialize_value(&second);
+
+	let storage = ser.finalize();
+	let output: &Doc = validate(storage.as_slice(), pos1).unwrap();
+	assert_eq!(output, &first);
+	let output: &Doc = validate(storage.as_slice(), pos2).unwrap();
+	assert_eq!(output, &second);
+}
+
+#[test]
+fn ptr_offset_rollback() {
+	let first = create_doc(5);
+	let second = create_doc(10);
+
+	let mut ser = PtrOffsetSer::new();
+	let positions = serialize_with_rollback(&mut ser, [&first, &second], &create_doc(1000));
+
+	let mut expected = PtrOffsetSer::new();
+	assert_eq!(expected.serialize_value(&first), positions[0]);
+	assert_eq!(expected.serialize_value(&second), positions[1]);
+	assert_eq!(ser.pos(), expected.pos());
+
+	let storage = ser.finalize();
+	for (input, pos) in [&first, &second].into_iter().zip(positions) {
+		let output = unsafe { OffsetRef::<Doc>::new(&storage, pos) };
+		let title = unsafe { output.field(|doc| ptr::addr_of!((*doc).title)) }.as_string();
+		assert_eq!(title.as_str(), input.title);
+		let paras = unsafe { output.field(|doc| ptr::addr_of!((*doc).paras)) }.as_vec();
+		assert_eq!(paras.len(), input.paras.len());
+		for (para, input_para) in paras.iter().zip(&input.paras) {
+			assert_eq!(para.as_string().as_str(), input_para);
+		}
+	}
+}
+
+#[test]
+fn pure_copy_and_portable_rollback() {
+	let first = create_doc(5);
+	let second = create_doc(10);
+	let discard = create_doc(1000);
+
+	// Output is identical to serializing only kept values (except padding bytes)
+	let mut ser = PureCopySer::new();
+	let positions = serialize_with_rollback(&mut ser, [&first, &second], &discard);
+	let mut expected = PureCopySer::new();
+	assert_eq!(expected.serialize_value(&first), positions[0]);
+	assert_eq!(expected.serialize_value(&second), positions[1]);
+	assert_eq!(ser.pos(), expected.pos());
+
+	let mut ser = PortableSer::new();
+	let positions = serialize_with_rollback(&mut ser, [&first, &second], &discard);
+	let mut expected = PortableSer::new();
+	assert_eq!(expected.serialize_value(&first), positions[0]);
+	assert_eq!(expected.serialize_value(&second), positions[1]);
+	assert_eq!(ser.pos(), expected.pos());
+}
+
+#[test]
+fn rollback_after_failed_serialization() {
+	let first = create_doc(5);
+	let second = create_doc(10);
+
+	let mut ser = CompleteSer::new();
+	let pos1 = ser.serialize_value(&first);
+	let checkpoint = ser.checkpoint();
+	assert_eq!(
+		ser.try_serialize_value_with_budget(&create_doc(1000), 2048),
+		Err(SerializeError::BudgetExceeded { budget: 2048 })
+	);
+	ser.rollback(checkpoint).unwrap();
+	let pos2 = ser.try_serialize_value(&second).unwrap();
+
+	let mut expected = CompleteSer::new();
+	expected.serialize_value(&first);
+	expected.serialize_value(&second);
+	assert_eq!(ser.ptr_positions(), expected.ptr_positions());
+
+	let storage = ser.finalize();
+	let output: &Doc = validate(storage.as_slice(), pos1).unwrap();
+	assert_eq!(output, &first);
+	let output: &Doc = validate(storage.as_slice(), pos2).unwrap();
+	assert_eq!(output, &second);
+}
+
+#[test]
+fn nested_checkpoints() {
+	let docs = [create_doc(1), create_doc(2), create_doc(3), create_doc(4)];
+
+	let mut ser = CompleteSer::new();
+	let pos0 = ser.serialize_value(&docs[0]);
+	let outer = ser.checkpoint();
+	ser.serialize_value(&docs[1]);
+	let inner = ser.checkpoint();
+	ser.serialize_value(&docs[2]);
+	ser.rollback(inner).unwrap();
+	ser.serialize_value(&docs[3]);
+	ser.rollback(outer).unwrap();
+	let pos1 = ser.serialize_value(&docs[1]);
+
+	let mut expected = CompleteSer::new();
+	expected.serialize_value(&docs[0]);
+	expected.serialize_value(&docs[1]);
+	assert_eq!(ser.ptr_positions(), expected.ptr_positions());
+
+	let storage = ser.finalize();
+	let output: &Doc = validate(storage.as_slice(), pos0).unwrap();
+	assert_eq!(output, &docs[0]);
+	let output: &Doc = validate(storage.as_slice(), pos1).unwrap();
+	assert_eq!(output, &docs[1]);
+}
+
+#[derive(Serialize)]
+struct Node {
+	id: u32,
+	shared: Rc<u64>,
+}
+
+#[test]
+fn rollback_shared_allocs() {
+	let before = Rc::new(1u64);
+	let after = Rc::new(2u64);
+
+	let mut ser = CompleteSer::new();
+	let pos1 = ser.serialize_value(&Node {
+		id: 1,
+		shared: before.clone(),
+	});
+	let checkpoint = ser.checkpoint();
+
+	// Adds references to allocation serialized before checkpoint, and writes
+	// another allocation after it
+	ser.serialize_value(&vec![
+		Node {
+			id: 2,
+			shared: before,
+		},
+		Node {
+			id: 3,
+			shared: after.clone(),
+		},
+	]);
+	ser.rollback(checkpoint).unwrap();
+	assert_eq!(ser.shared_allocs().unwrap().len(), 1);
+
+	// Allocation written after checkpoint must be written again, not pointed to
+	// in discarded output
+	let pos2 = ser.serialize_value(&Node {
+		id: 4,
+		shared: after,
+	});
+	assert_eq!(ser.shared_allocs().unwrap().len(), 2);
+
+	let storage = ser.finalize();
+	let node1: &Node = unsafe { storage.read(pos1) };
+	let node2: &Node = unsafe { storage.read(pos2) };
+	assert_eq!(*node1.shared, 1);
+	assert_eq!(*node2.shared, 2);
+	assert!(!ptr::eq(&*node1.shared, &*node2.shared));
+	// Ref counts only include references in output which was kept
+	assert_eq!(Rc::strong_count(&node1.shared), 1);
+	assert_eq!(Rc::strong_count(&node2.shared), 1);
+}
+
+#[test]
+fn storage_truncate() {
+	let mut storage = Store::new();
+	storage.push(&1u64);
+	storage.push(&2u64);
+	storage.truncate(8);
+	assert_eq!(storage.pos(), 8);
+	storage.push(&3u64);
+	assert_eq!(unsafe { *storage.read::<u64>(8) }, 3);
+}
+
+#[test]
+#[should_panic(expected = "Cannot truncate storage to 16")]
+fn storage_truncate_after_pos_panics() {
+	let mut storage = Store::new();
+	storage.push(&1u64);
+	storage.truncate(16);
+}
+
+#[test]
+#[should_panic(expected = "Cannot truncate WriteStorage before output which has been written")]
+fn write_storage_truncate_flushed_output_panics() {
+	let mut storage =
+		WriteStorage::<Vec<u8>, 16, 16, 8, MAX_CAPACITY>::with_chunk_size(Vec::new(), 64);
+	storage.push(&[0u64; 8]);
+	let pos = storage.pos();
+	storage.push(&[0u64; 8]);
+	storage.truncate(pos - 8);
+}
@@ -0,0 +1,261 @@
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+	ptr,
+};
+
+use ser_raw::{
+	read::OffsetRef,
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::{aligned_max_capacity, aligned_max_u32_capacity},
+	validate, CompleteSerializer, Deserialize, Deserializer, PortableSerializer,
+	PtrOffset32Serializer, PtrOffsetSerializer, PureCopyDeserializer, PureCopySerializer, Serialize,
+	Serializer, Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+const MAX_U32_CAPACITY: usize = aligned_max_u32_capacity(16);
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PtrOffset32Store = AlignedVec<16, 16, 8, MAX_U32_CAPACITY>;
+type PtrOffset32Ser = PtrOffset32Serializer<16, 16, 8, MAX_U32_CAPACITY, PtrOffset32Store>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 4, PortableStore>;
+type De<'a> = PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Collections {
+	hash_map: HashMap<String, Vec<u32>>,
+	btree_map: BTreeMap<u16, String>,
+	hash_set: HashSet<String>,
+	btree_set: BTreeSet<i64>,
+	deque: VecDeque<String>,
+	empty_map: HashMap<u8, u8>,
+	empty_set: BTreeSet<Box<u32>>,
+	empty_deque: VecDeque<u64>,
+}
+
+fn create_collections() -> Collections {
+	let hash_map = (0..20)
+		.map(|index| (format!("key {index}"), (0..index).collect()))
+		.collect();
+	let btree_map = [(3, "three"), (1, "one"), (2, "two")]
+		.into_iter()
+		.map(|(key, value)| (key, value.to_string()))
+		.collect();
+	let hash_set = ["cherry", "apple", "banana"]
+		.into_iter()
+		.map(str::to_string)
+		.collect();
+	let btree_set = [5, -1, 100, 0].into_iter().collect();
+
+	Collections {
+		hash_map,
+		btree_map,
+		hash_set,
+		btree_set,
+		deque: wrapped_deque(),
+		empty_map: HashMap::with_capacity(10),
+		empty_set: BTreeSet::new(),
+		empty_deque: VecDeque::with_capacity(10),
+	}
+}
+
+/// Create a `VecDeque` where ring buffer wraps around.
+/// Contains "0" to "5" (front to back).
+fn wrapped_deque() -> VecDeque<String> {
+	let mut deque = VecDeque::with_capacity(8);
+	for index in 2..8 {
+		deque.push_back(index.to_string());
+	}
+	for _ in 2..8 {
+		deque.pop_front();
+	}
+	for index in 0..6 {
+		deque.push_back(index.to_string());
+	}
+	assert!(!deque.as_slices().1.is_empty());
+	deque
+}
+
+#[test]
+fn pure_copy_round_trip() {
+	let input = create_collections();
+	let (pos, storage) = PureCopySer::new().serialize(&input);
+	assert_eq!(pos, 0);
+
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output: Collections = de.deserialize_value();
+	assert_eq!(de.pos(), storage.pos());
+	assert_eq!(output, input);
+}
+
+#[test]
+fn ptr_offset_maps() {
+	let input = create_collections();
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	let output = unsafe { OffsetRef::<Collections>::new(&storage, pos) };
+
+	// Entries are sorted by key
+	let map = unsafe { output.field(|value| ptr::addr_of!((*value).hash_map)) }.as_map();
+	assert_eq!(map.len(), 20);
+	let keys = map
+		.iter()
+		.map(|(key, _)| key.as_string().to_string())
+		.collect::<Vec<_>>();
+	let mut expected_keys = input.hash_map.keys().cloned().collect::<Vec<_>>();
+	expected_keys.sort();
+	assert_eq!(keys, expected_keys);
+
+	for (key, value) in &input.hash_map {
+		let output_value = map
+			.get_by(|output_key| output_key.as_string().as_str().cmp(key))
+			.unwrap()
+			.as_vec();
+		assert_eq!(unsafe { output_value.as_slice() }, value.as_slice());
+	}
+	assert!(map
+		.get_by(|key| key.as_string().as_str().cmp("key 100"))
+		.is_none());
+
+	let map = unsafe { output.field(|value| ptr::addr_of!((*value).btree_map)) }.as_map();
+	let entries = map
+		.iter()
+		.map(|(key, value)| (unsafe { *key.get() }, value.as_string().to_string()))
+		.collect::<Vec<_>>();
+	assert_eq!(
+		entries,
+		vec![
+			(1, "one".to_string()),
+			(2, "two".to_string()),
+			(3, "three".to_string())
+		]
+	);
+	let value = map.get_by(|key| unsafe { key.get() }.cmp(&2)).unwrap();
+	assert_eq!(value.as_string().as_str(), "two");
+	assert!(map.get_by(|key| unsafe { key.get() }.cmp(&4)).is_none());
+
+	let map = unsafe { output.field(|value| ptr::addr_of!((*value).empty_map)) }.as_map();
+	assert!(map.is_empty());
+	assert!(map.get_by(|key| unsafe { key.get() }.cmp(&0)).is_none());
+}
+
+#[test]
+fn ptr_offset_sets() {
+	let input = create_collections();
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	let output = unsafe { OffsetRef::<Collections>::new(&storage, pos) };
+
+	let set = unsafe { output.field(|value| ptr::addr_of!((*value).hash_set)) }.as_set();
+	let values = set
+		.iter()
+		.map(|value| value.as_string().to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(values, vec!["apple", "banana", "cherry"]);
+	assert_eq!(
+		set.binary_search_by(|value| value.as_string().as_str().cmp("banana")),
+		Ok(1)
+	);
+	assert_eq!(
+		set.binary_search_by(|value| value.as_string().as_str().cmp("blueberry")),
+		Err(2)
+	);
+
+	let set = unsafe { output.field(|value| ptr::addr_of!((*value).btree_set)) }.as_set();
+	assert_eq!(unsafe { set.as_slice() }, &[-1, 0, 5, 100]);
+
+	let set = unsafe { output.field(|value| ptr::addr_of!((*value).empty_set)) }.as_set();
+	assert!(set.is_empty());
+}
+
+#[test]
+fn ptr_offset_deques() {
+	let input = create_collections();
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	let output = unsafe { OffsetRef::<Collections>::new(&storage, pos) };
+
+	let deque = unsafe { output.field(|value| ptr::addr_of!((*value).deque)) }.as_vec_deque();
+	let values = deque
+		.iter()
+		.map(|value| value.as_string().to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(values, vec!["0", "1", "2", "3", "4", "5"]);
+
+	let deque = unsafe { output.field(|value| ptr::addr_of!((*value).empty_deque)) }.as_vec_deque();
+	assert!(deque.is_empty());
+}
+
+#[test]
+fn ptr_offset32_maps() {
+	let input = create_collections();
+	let (pos, storage) = PtrOffset32Ser::new().serialize(&input);
+	let output = unsafe { OffsetRef::<Collections, u32>::new(&storage, pos) };
+
+	let map = unsafe { output.field(|value| ptr::addr_of!((*value).btree_map)) }.as_map();
+	let value = map.get_by(|key| unsafe { key.get() }.cmp(&3)).unwrap();
+	assert_eq!(value.as_string().as_str(), "three");
+
+	let deque = unsafe { output.field(|value| ptr::addr_of!((*value).deque)) }.as_vec_deque();
+	assert_eq!(deque.get(5).unwrap().as_string().as_str(), "5");
+}
+
+#[test]
+fn complete_deques() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	struct Deques {
+		wrapped: VecDeque<String>,
+		contiguous: VecDeque<Box<u16>>,
+		empty: VecDeque<u32>,
+		zsts: VecDeque<()>,
+	}
+
+	let mut contiguous = VecDeque::with_capacity(20);
+	contiguous.extend([Box::new(1), Box::new(2), Box::new(3)]);
+	contiguous.pop_front();
+	let mut zsts = VecDeque::new();
+	zsts.extend([(), (), ()]);
+	zsts.pop_front();
+	let input = Deques {
+		wrapped: wrapped_deque(),
+		contiguous,
+		empty: VecDeque::with_capacity(10),
+		zsts,
+	};
+
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+	let output: &Deques = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+	assert_eq!(output.wrapped.capacity(), 6);
+	assert!(output.wrapped.as_slices().1.is_empty());
+	assert_eq!(output.contiguous.capacity(), 2);
+	assert_eq!(output.empty.capacity(), 0);
+	assert_eq!(output.zsts.len(), 2);
+
+	let output: &Deques = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn portable_bytes() {
+	let mut map = HashMap::new();
+	map.insert(2u8, 0x0102u16);
+	map.insert(1u8, 0x0304u16);
+	let mut deque = VecDeque::with_capacity(4);
+	deque.extend([9u8, 9, 9, 1]);
+	deque.drain(..3);
+	deque.extend([2, 3]);
+	let input = (map, deque, BTreeSet::<u8>::new());
+
+	let (pos, storage) = PortableSer::new().serialize(&input);
+	assert_eq!(pos, 0);
+	assert_eq!(
+		storage.as_slice(),
+		[
+			24, 0, 0, 0, 2, 0, 0, 0, // Map
+			30, 0, 0, 0, 3, 0, 0, 0, // Deque
+			0, 0, 0, 0, 0, 0, 0, 0, // Empty set
+			1, 4, 3, 2, 2, 1, // Map entries
+			1, 2, 3, // Deque contents
+		]
+	);
+}
@@ -4,9 +4,9 @@ use std::{mem, ops};
 
 use rand::Rng;
 use rand_pcg::Lcg64Xsh32;
-use ser_raw::Serialize;
+use ser_raw::{Deserialize, Serialize, Validate};
 
-#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Validate, Clone, Copy, Debug, PartialEq)]
 pub enum GameType {
 	Survival,
 	Creative,
@@ -26,7 +26,7 @@ impl Generate for GameType {
 	}
 }
 
-#[derive(Serialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Validate, Clone, Debug, PartialEq)]
 pub struct Item {
 	pub count: i8,
 	pub slot: u8,
@@ -53,7 +53,7 @@ impl Generate for Item {
 	}
 }
 
-#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Validate, Clone, Copy, Debug, PartialEq)]
 pub struct Abilities {
 	pub walk_speed: f32,
 	pub fly_speed: f32,
@@ -78,7 +78,7 @@ impl Generate for Abilities {
 	}
 }
 
-#[derive(Serialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Validate, Clone, Debug, PartialEq)]
 pub struct Entity {
 	pub id: String,
 	pub pos: (f64, f64, f64),
@@ -129,7 +129,7 @@ impl Generate for Entity {
 	}
 }
 
-#[derive(Serialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Validate, Clone, Debug, PartialEq)]
 pub struct RecipeBook {
 	pub recipes: Vec<String>,
 	pub to_be_displayed: Vec<String>,
@@ -178,7 +178,7 @@ impl Generate for RecipeBook {
 	}
 }
 
-#[derive(Serialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Validate, Clone, Debug, PartialEq)]
 pub struct Player {
 	pub game_type: GameType,
 	pub previous_game_type: GameType,
@@ -249,7 +249,7 @@ impl Generate for Player {
 	}
 }
 
-#[derive(Serialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Validate, Clone, Debug, PartialEq)]
 pub struct Players {
 	pub players: Vec<Player>,
 }
@@ -315,6 +315,7 @@ macro_rules! impl_array {
 			fn generate<R: Rng>(rng: &mut R) -> Self {
 				let mut result = mem::MaybeUninit::<Self>::uninit();
 				let result_ptr = result.as_mut_ptr().cast::<T>();
+				#[allow(clippy::reversed_empty_ranges)]
 				for i in 0..$len {
 					unsafe {
 						result_ptr.add(i).write(T::generate(rng));
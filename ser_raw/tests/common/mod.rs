@@ -24,18 +24,59 @@ pub enum Test {
 	StringsWithZeroLenExcessCapacity2,
 	StringsWithExcessCapacity,
 	StringsWithExcessCapacity2,
+	BoxedSlices,
 	Options,
+	Wrappers,
+	Ranges,
+	// Not used by tests invoked with `no_locks`
+	#[allow(dead_code)]
+	Locks,
+	Atomics,
+	Cows,
 	BigUint,
 	BigInt,
 	StructureWhereStorageGrowsAfterLastPointerWritten,
 	MinecraftData,
 }
 
+/// Define tests which call `$test_serialize` with a value to serialize.
+///
+/// Pure copy serializers cannot serialize `Mutex` or `RwLock`, so their tests
+/// are invoked with `no_locks`.
 macro_rules! tests {
 	($test_serialize:ident) => {
+		tests!($test_serialize, no_locks);
+
+		#[test]
+		fn locks() {
+			use std::sync::{Mutex, RwLock};
+
+			#[derive(Serialize, Deserialize, Debug)]
+			struct Foo {
+				mutex: Mutex<Vec<u16>>,
+				rw_lock: RwLock<String>,
+			}
+
+			// Locks don't implement `PartialEq`
+			impl PartialEq for Foo {
+				fn eq(&self, other: &Self) -> bool {
+					*self.mutex.lock().unwrap() == *other.mutex.lock().unwrap()
+						&& *self.rw_lock.read().unwrap() == *other.rw_lock.read().unwrap()
+				}
+			}
+
+			let input = Foo {
+				mutex: Mutex::new(vec![0x0102, 0x0304]),
+				rw_lock: RwLock::new("abc".to_string()),
+			};
+			$test_serialize(&input, Test::Locks, 0);
+		}
+	};
+
+	($test_serialize:ident, no_locks) => {
 		#[test]
 		fn primitives() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Foo {
 				u8: u8,
 				u16: u16,
@@ -80,7 +121,7 @@ macro_rules! tests {
 		fn non_zero_numbers() {
 			use std::num;
 
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			#[allow(non_snake_case)]
 			struct Foo {
 				NonZeroU8: num::NonZeroU8,
@@ -116,7 +157,7 @@ macro_rules! tests {
 
 		#[test]
 		fn arrays() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Foo {
 				empty: [u8; 0],
 				single: [u8; 1],
@@ -135,7 +176,7 @@ macro_rules! tests {
 
 		#[test]
 		fn arrays_of_boxes() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Bar {
 				empty: [Box<u8>; 0],
 				single: [Box<u8>; 1],
@@ -158,7 +199,7 @@ macro_rules! tests {
 
 		#[test]
 		fn tuples() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Foo {
 				tup: (u8, u16, u32),
 				tup_of_boxes: (Box<u8>, Box<u16>, Box<u32>),
@@ -173,7 +214,7 @@ macro_rules! tests {
 
 		#[test]
 		fn enum_fieldless() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			enum Foo {
 				One,
 				Two,
@@ -187,19 +228,19 @@ macro_rules! tests {
 
 		#[test]
 		fn enum_with_fields() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			enum Foo {
 				Bar(Bar),
 				Qux(Qux),
 			}
 
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Bar {
 				small: u8,
 				big: u32,
 			}
 
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			enum Qux {
 				Small(i8),
 				Big(i16),
@@ -219,7 +260,7 @@ macro_rules! tests {
 
 		#[test]
 		fn boxed_primitives() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Foo {
 				u8: Box<u8>,
 				u16: Box<u16>,
@@ -262,13 +303,13 @@ macro_rules! tests {
 
 		#[test]
 		fn boxed_structs() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Foo {
 				bar: Box<Bar>,
 				bar2: Box<Bar>,
 			}
 
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Bar {
 				small: u8,
 				big: Box<u32>,
@@ -289,7 +330,7 @@ macro_rules! tests {
 
 		#[test]
 		fn vec_of_primitives() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Foo {
 				small: Vec<u8>,
 				middle: Vec<u16>,
@@ -426,9 +467,32 @@ macro_rules! tests {
 			$test_serialize(&input, Test::StringsWithExcessCapacity2, 0);
 		}
 
+		#[test]
+		fn boxed_slices() {
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
+			struct Foo {
+				nums: Box<[u16]>,
+				empty: Box<[u16]>,
+				boxes: Box<[Box<u32>]>,
+				str: Box<str>,
+				empty_str: Box<str>,
+				strs: Box<[Box<str>]>,
+			}
+
+			let input = Foo {
+				nums: vec![0x0102, 0x0304, 0x0506].into_boxed_slice(),
+				empty: Box::new([]),
+				boxes: vec![Box::new(0x0708090a), Box::new(0x0b0c0d0e)].into_boxed_slice(),
+				str: "abc".into(),
+				empty_str: "".into(),
+				strs: vec!["d".into(), "ef".into()].into_boxed_slice(),
+			};
+			$test_serialize(&input, Test::BoxedSlices, 0);
+		}
+
 		#[test]
 		fn options() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Foo {
 				bar: Option<Bar>,
 				boxed: Option<Box<Bar>>,
@@ -436,7 +500,7 @@ macro_rules! tests {
 				str: Option<String>,
 			}
 
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			struct Bar {
 				small: u8,
 				big: u32,
@@ -515,6 +579,130 @@ macro_rules! tests {
 			);
 		}
 
+		#[test]
+		fn wrappers() {
+			use std::{
+				cell::{Cell, RefCell},
+				cmp::{Ordering, Reverse},
+				marker::PhantomData,
+				mem::ManuallyDrop,
+				num::Wrapping,
+				time::Duration,
+			};
+
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
+			struct Foo {
+				phantom: PhantomData<String>,
+				cell: Cell<u16>,
+				ref_cell: RefCell<Vec<String>>,
+				manually_drop: ManuallyDrop<Box<u32>>,
+				wrapping: Wrapping<u8>,
+				reverse: Reverse<String>,
+				duration: Duration,
+				ordering: Ordering,
+			}
+
+			let input = Foo {
+				phantom: PhantomData,
+				cell: Cell::new(0x0102),
+				ref_cell: RefCell::new(vec!["abc".to_string(), "de".to_string()]),
+				manually_drop: ManuallyDrop::new(Box::new(0x03040506)),
+				wrapping: Wrapping(0x07),
+				reverse: Reverse("fghi".to_string()),
+				duration: Duration::new(0x08090a0b, 0x0c0d0e0f),
+				ordering: Ordering::Less,
+			};
+			$test_serialize(&input, Test::Wrappers, 0);
+		}
+
+		#[test]
+		fn ranges() {
+			use std::ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive};
+
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
+			struct Foo {
+				range: Range<u32>,
+				inclusive: RangeInclusive<Box<u16>>,
+				exhausted: RangeInclusive<u8>,
+				from: RangeFrom<u64>,
+				to: RangeTo<String>,
+				to_inclusive: RangeToInclusive<i8>,
+				full: RangeFull,
+			}
+
+			let mut exhausted = 0x05..=0x05;
+			exhausted.next();
+			let input = Foo {
+				range: 0x01..0x02,
+				inclusive: Box::new(0x03)..=Box::new(0x04),
+				exhausted,
+				from: 0x06..,
+				to: ..("abc".to_string()),
+				to_inclusive: ..=-0x07,
+				full: ..,
+			};
+			$test_serialize(&input, Test::Ranges, 0);
+		}
+
+		#[test]
+		fn atomics() {
+			use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicUsize, Ordering};
+
+			#[derive(Serialize, Deserialize, Debug)]
+			struct Foo {
+				bool: AtomicBool,
+				u32: AtomicU32,
+				i64: AtomicI64,
+				usize: AtomicUsize,
+			}
+
+			// Atomics don't implement `PartialEq`
+			impl PartialEq for Foo {
+				fn eq(&self, other: &Self) -> bool {
+					self.bool.load(Ordering::Relaxed) == other.bool.load(Ordering::Relaxed)
+						&& self.u32.load(Ordering::Relaxed) == other.u32.load(Ordering::Relaxed)
+						&& self.i64.load(Ordering::Relaxed) == other.i64.load(Ordering::Relaxed)
+						&& self.usize.load(Ordering::Relaxed) == other.usize.load(Ordering::Relaxed)
+				}
+			}
+
+			let input = Foo {
+				bool: AtomicBool::new(true),
+				u32: AtomicU32::new(0x05060708),
+				i64: AtomicI64::new(-0x090a0b0c0d0e0f10),
+				usize: AtomicUsize::new(usize::MAX),
+			};
+			$test_serialize(&input, Test::Atomics, 0);
+		}
+
+		#[test]
+		fn cows() {
+			use std::borrow::Cow;
+
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
+			struct Foo {
+				borrowed_str: Cow<'static, str>,
+				owned_str: Cow<'static, str>,
+				empty_str: Cow<'static, str>,
+				borrowed_slice: Cow<'static, [u32]>,
+				owned_slice: Cow<'static, [u32]>,
+				empty_slice: Cow<'static, [u32]>,
+				strings: Cow<'static, [String]>,
+			}
+
+			let strings = vec!["efg".to_string(), "h".to_string()];
+			let input = Foo {
+				borrowed_str: Cow::Borrowed("abc"),
+				owned_str: Cow::Owned("d".to_string()),
+				empty_str: Cow::Borrowed(""),
+				borrowed_slice: Cow::Borrowed(&[0x01020304, 0x05060708]),
+				owned_slice: Cow::Owned(vec![0x090a0b0c]),
+				empty_slice: Cow::Borrowed(&[]),
+				strings: Cow::Borrowed(Box::leak(strings.into_boxed_slice())),
+			};
+			$test_serialize(&input, Test::Cows, 0);
+		}
+
 		#[cfg(feature = "num_bigint")]
 		#[test]
 		fn biguints() {
@@ -574,7 +762,7 @@ macro_rules! tests {
 
 		#[test]
 		fn structure_where_storage_grows_after_last_pointer_written() {
-			#[derive(Serialize, Debug, PartialEq)]
+			#[derive(Serialize, Deserialize, Debug, PartialEq)]
 			#[repr(C)]
 			struct Foo {
 				boxed: Box<u32>,
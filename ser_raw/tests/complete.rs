@@ -3,9 +3,9 @@ use std::{fmt::Debug, mem};
 mod common;
 use common::{generate_minecraft_data, tests, Test};
 use ser_raw::{
-	storage::{AlignedVec, RandomAccessStorage, Storage},
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
 	util::aligned_max_capacity,
-	CompleteSerializer, Serialize, Serializer,
+	CompleteSerializer, Deserialize, Serialize, Serializer,
 };
 
 // NB: Cannot easily test for error if try to serialize a type with alignment
@@ -186,3 +186,95 @@ fn strings_with_excess_capacity_represented_correctly() {
 	assert_eq!(output.len(), 3);
 	assert_eq!(output.capacity(), 3);
 }
+
+#[cfg(unix)]
+#[test]
+fn boxed_paths() {
+	use std::path::Path;
+
+	let input: Vec<Box<Path>> = vec![
+		Path::new("/usr/local/bin").into(),
+		Path::new("").into(),
+		Path::new("relative/file.txt").into(),
+	];
+	let (pos, storage) = serialize(&input);
+
+	let output: &Vec<Box<Path>> = deserialize(&storage, pos);
+	assert_eq!(&input, output);
+}
+
+#[test]
+fn borrowed_cows_point_into_output() {
+	use std::borrow::Cow;
+
+	let input: (Cow<str>, Cow<[u16]>) = (Cow::Borrowed("abc"), Cow::Borrowed(&[1, 2]));
+	let (pos, storage) = serialize(&input);
+
+	let output: &(Cow<str>, Cow<[u16]>) = deserialize(&storage, pos);
+	assert_eq!(&input, output);
+	let range = storage.as_ptr() as usize..storage.as_ptr() as usize + storage.pos();
+	assert!(range.contains(&(output.0.as_ptr() as usize)));
+	assert!(range.contains(&(output.1.as_ptr() as usize)));
+}
+
+#[test]
+fn locks_and_cells_are_unlocked_in_output() {
+	use std::{cell::RefCell, sync::Mutex};
+
+	let input = (RefCell::new(vec![1u8]), Mutex::new("abc".to_string()));
+	let (pos, storage) = serialize(&input);
+
+	let output: &(RefCell<Vec<u8>>, Mutex<String>) = deserialize(&storage, pos);
+	assert_eq!(*output.0.try_borrow_mut().unwrap(), vec![1]);
+	assert_eq!(*output.1.try_lock().unwrap(), "abc");
+}
+
+#[test]
+fn lock_contents_copied_while_locked() {
+	use std::{cell::Cell, ptr, sync::Mutex};
+
+	/// Type which modifies contents of a `Mutex` when serialized, as another
+	/// thread could after `Mutex` is copied to output, but before it's locked
+	struct Modifier(Cell<*const Mutex<Vec<u16>>>);
+
+	impl<S: Serializer> Serialize<S> for Modifier {
+		fn serialize_data(&self, _serializer: &mut S) {
+			let mutex = unsafe { &*self.0.get() };
+			mutex
+				.lock()
+				.unwrap()
+				.extend_from_slice(&[3, 4, 5, 6, 7, 8, 9]);
+		}
+	}
+
+	let input = (Modifier(Cell::new(ptr::null())), Mutex::new(vec![1u16, 2]));
+	input.0 .0.set(&input.1);
+	let (pos, storage) = serialize(&input);
+
+	let output: &(Modifier, Mutex<Vec<u16>>) = deserialize(&storage, pos);
+	let vec = output.1.try_lock().unwrap();
+	assert_eq!(*vec, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+	assert_eq!(vec.capacity(), 9);
+	let range = storage.as_ptr() as usize..storage.as_ptr() as usize + storage.pos();
+	assert!(range.contains(&(vec.as_ptr() as usize)));
+}
+
+#[test]
+#[should_panic(expected = "Cannot serialize a `RefCell` which is borrowed")]
+fn borrowed_ref_cell_panics() {
+	use std::cell::RefCell;
+
+	let input = RefCell::new(Box::new(1u8));
+	let _borrow = input.borrow();
+	serialize(&input);
+}
+
+#[test]
+#[should_panic(expected = "Cannot serialize a `Mutex` which is locked")]
+fn locked_mutex_panics() {
+	use std::sync::Mutex;
+
+	let input = Mutex::new(Box::new(1u8));
+	let _guard = input.lock().unwrap();
+	serialize(&input);
+}
@@ -0,0 +1,481 @@
+use std::{
+	alloc::Layout,
+	cell::{Cell, RefCell},
+	fmt::Debug,
+	panic,
+	sync::{Mutex, RwLock},
+};
+
+mod common;
+use common::{generate_minecraft_data, tests, Test};
+use ser_raw::{
+	fallible,
+	storage::{
+		AlignedVec, ContiguousStorage, CountingStorage, FixedVec, RandomAccessStorage, Storage,
+		WriteStorage,
+	},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, Deserialize, PortableSerializer, PtrOffsetSerializer,
+	PureCopySerializer, Serialize, SerializeError, Serializer, Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 8, PortableStore>;
+
+const SMALL_CAPACITY: usize = 256;
+type SmallStore = AlignedVec<16, 16, 8, SMALL_CAPACITY>;
+type SmallPureCopySer = PureCopySerializer<16, 16, 8, SMALL_CAPACITY, SmallStore>;
+type SmallPtrOffsetSer = PtrOffsetSerializer<16, 16, 8, SMALL_CAPACITY, SmallStore>;
+type SmallCompleteSer = CompleteSerializer<16, 16, 8, SMALL_CAPACITY, SmallStore>;
+type SmallPortableSer =
+	PortableSerializer<16, 16, 1, SMALL_CAPACITY, 8, AlignedVec<16, 16, 1, SMALL_CAPACITY>>;
+
+/// Check fallible serialization produces same output as infallible
+fn test_serialize<T>(input: &T, _test: Test, _test_num: usize)
+where T: Serialize<PureCopySer>
+		+ Serialize<PtrOffsetSer>
+		+ Serialize<CompleteSer>
+		+ Serialize<PortableSer>
+		+ Debug
+		+ PartialEq {
+	fn check<Ser: Serializer<Storage = Store, BorrowedStorage = Store>, T: Serialize<Ser>>(
+		input: &T,
+		create: fn() -> Ser,
+	) {
+		let (pos, storage) = create().serialize(input);
+		let (try_pos, try_storage) = create().try_serialize(input).unwrap();
+		assert_eq!(try_pos, pos);
+		assert_eq!(try_storage.pos(), storage.pos());
+
+		// Budget which output fits exactly within succeeds
+		let (_, storage) = create()
+			.try_serialize_with_budget(input, storage.pos())
+			.unwrap();
+		assert_eq!(storage.pos(), try_storage.pos());
+	}
+
+	check(input, PureCopySer::new);
+	check(input, PtrOffsetSer::new);
+	check(input, CompleteSer::new);
+
+	let (pos, storage) = PortableSer::new().serialize(input);
+	let (try_pos, try_storage) = PortableSer::new().try_serialize(input).unwrap();
+	assert_eq!(try_pos, pos);
+	assert_eq!(try_storage.as_slice(), storage.as_slice());
+}
+
+tests!(test_serialize, no_locks);
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+struct Doc {
+	title: String,
+	paras: Vec<String>,
+}
+
+fn create_doc(num_paras: usize) -> Doc {
+	Doc {
+		title: "title".to_string(),
+		paras: (0..num_paras).map(|i| format!("paragraph {i}")).collect(),
+	}
+}
+
+#[test]
+fn capacity_exceeded() {
+	let small = create_doc(2);
+	let big = create_doc(100);
+	let err = SerializeError::CapacityExceeded {
+		storage: "AlignedVec",
+	};
+
+	assert!(SmallPureCopySer::new().try_serialize(&small).is_ok());
+	assert_eq!(SmallPureCopySer::new().try_serialize(&big).err(), Some(err));
+	assert!(SmallPtrOffsetSer::new().try_serialize(&small).is_ok());
+	assert_eq!(
+		SmallPtrOffsetSer::new().try_serialize(&big).err(),
+		Some(err)
+	);
+	assert!(SmallCompleteSer::new().try_serialize(&small).is_ok());
+	assert_eq!(SmallCompleteSer::new().try_serialize(&big).err(), Some(err));
+	assert!(SmallPortableSer::new().try_serialize(&small).is_ok());
+	assert_eq!(SmallPortableSer::new().try_serialize(&big).err(), Some(err));
+}
+
+#[test]
+fn budget_exceeded() {
+	let input = generate_minecraft_data();
+	let (_, storage) = CompleteSer::new().serialize(&input);
+	let size = storage.pos();
+
+	for budget in [0, 100, size / 2, size - 1] {
+		let mut ser = CompleteSer::new();
+		assert_eq!(
+			ser.try_serialize_value_with_budget(&input, budget),
+			Err(SerializeError::BudgetExceeded { budget })
+		);
+		// Storage does not grow beyond budget
+		assert!(ser.capacity() <= (budget + 15) & !15);
+	}
+
+	// Budget is checked even if storage already has capacity
+	let result = CompleteSer::with_capacity(size).try_serialize_with_budget(&input, size - 1);
+	assert_eq!(
+		result.err(),
+		Some(SerializeError::BudgetExceeded { budget: size - 1 })
+	);
+
+	let (_, storage) = CompleteSer::new()
+		.try_serialize_with_budget(&input, size)
+		.unwrap();
+	assert_eq!(storage.pos(), size);
+}
+
+#[test]
+fn budget_includes_previous_values() {
+	let doc = create_doc(3);
+	let (_, storage) = PureCopySer::new().serialize(&doc);
+	let size = storage.pos();
+
+	let mut ser = PureCopySer::new();
+	assert!(ser.try_serialize_value_with_budget(&doc, size * 2).is_ok());
+	assert!(ser.try_serialize_value_with_budget(&doc, size * 2).is_ok());
+	assert_eq!(
+		ser.try_serialize_value_with_budget(&doc, size * 2),
+		Err(SerializeError::BudgetExceeded { budget: size * 2 })
+	);
+}
+
+#[test]
+fn reuse_after_error() {
+	let mut ser = CompleteSer::new();
+	assert!(ser
+		.try_serialize_value_with_budget(&create_doc(1000), 1024)
+		.is_err());
+
+	ser.reset();
+	let input = create_doc(10);
+	let pos = ser.try_serialize_value(&input).unwrap();
+	let storage = ser.finalize();
+	let output: &Doc = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn fixed_storage_capacity_exceeded() {
+	type FixedStore = FixedVec<16, 16, 8, MAX_CAPACITY>;
+
+	#[derive(Serializer)]
+	#[ser_type(pure_copy)]
+	struct FixedSer {
+		#[ser_storage(FixedStore)]
+		storage: FixedStore,
+	}
+
+	let ser = FixedSer {
+		storage: FixedStore::with_capacity(64),
+	};
+	assert_eq!(
+		ser.try_serialize(&create_doc(10)).err(),
+		Some(SerializeError::CapacityExceeded {
+			storage: "FixedVec"
+		})
+	);
+}
+
+#[test]
+fn write_storage_budget_exceeded() {
+	type WriteStore = WriteStorage<Vec<u8>, 16, 16, 8, MAX_CAPACITY>;
+
+	#[derive(Serializer)]
+	#[ser_type(pure_copy)]
+	struct StreamSer {
+		#[ser_storage(WriteStore)]
+		storage: WriteStore,
+	}
+
+	// Budget applies to total output, not just what's buffered
+	let ser = StreamSer {
+		storage: WriteStore::with_chunk_size(Vec::new(), 64),
+	};
+	assert_eq!(
+		ser.try_serialize_with_budget(&create_doc(100), 512).err(),
+		Some(SerializeError::BudgetExceeded { budget: 512 })
+	);
+}
+
+#[test]
+fn try_reserve() {
+	let mut storage = SmallStore::new();
+	storage.push(&1u64);
+	assert_eq!(storage.try_reserve(64), Ok(()));
+	assert!(storage.capacity() >= 72);
+	assert_eq!(
+		storage.try_reserve(SMALL_CAPACITY),
+		Err(SerializeError::CapacityExceeded {
+			storage: "AlignedVec"
+		})
+	);
+	assert_eq!(
+		storage.try_reserve(usize::MAX),
+		Err(SerializeError::CapacityExceeded {
+			storage: "AlignedVec"
+		})
+	);
+	// Storage is unchanged
+	assert_eq!(storage.pos(), 8);
+	assert_eq!(unsafe { *storage.as_slice().as_ptr().cast::<u64>() }, 1);
+
+	let mut storage = FixedVec::<16, 16, 8, MAX_CAPACITY>::with_capacity(32);
+	assert_eq!(storage.try_reserve(32), Ok(()));
+	assert_eq!(
+		storage.try_reserve(33),
+		Err(SerializeError::CapacityExceeded {
+			storage: "FixedVec"
+		})
+	);
+
+	let mut storage = CountingStorage::<SmallStore>::new();
+	assert_eq!(storage.try_reserve(SMALL_CAPACITY), Ok(()));
+	assert_eq!(
+		storage.try_reserve(SMALL_CAPACITY + 1),
+		Err(SerializeError::CapacityExceeded {
+			storage: "CountingStorage"
+		})
+	);
+}
+
+/// Storage which uses default implementation of `try_reserve`
+struct DefaultTryReserveStore(SmallStore);
+
+impl Storage for DefaultTryReserveStore {
+	const STORAGE_ALIGNMENT: usize = SmallStore::STORAGE_ALIGNMENT;
+	const MAX_VALUE_ALIGNMENT: usize = SmallStore::MAX_VALUE_ALIGNMENT;
+	const VALUE_ALIGNMENT: usize = SmallStore::VALUE_ALIGNMENT;
+	const MAX_CAPACITY: usize = SmallStore::MAX_CAPACITY;
+
+	fn new() -> Self {
+		Self(SmallStore::new())
+	}
+
+	unsafe fn with_capacity_unchecked(capacity: usize) -> Self {
+		Self(SmallStore::with_capacity_unchecked(capacity))
+	}
+
+	fn capacity(&self) -> usize {
+		self.0.capacity()
+	}
+
+	fn pos(&self) -> usize {
+		self.0.pos()
+	}
+
+	unsafe fn set_pos(&mut self, new_pos: usize) {
+		self.0.set_pos(new_pos);
+	}
+
+	unsafe fn push_slice_unchecked<T>(&mut self, slice: &[T], size: usize) {
+		self.0.push_slice_unchecked(slice, size);
+	}
+
+	fn reserve(&mut self, additional: usize) {
+		self.0.reserve(additional);
+	}
+
+	fn shrink_to_fit(&mut self) {
+		self.0.shrink_to_fit();
+	}
+}
+
+#[test]
+fn default_try_reserve() {
+	let mut storage = DefaultTryReserveStore::new();
+	storage.push(&1u64);
+	assert_eq!(storage.try_reserve(64), Ok(()));
+	assert!(storage.capacity() >= 72);
+
+	// Returns errors, rather than calling `reserve`, which would panic
+	let err = Err(SerializeError::CapacityExceeded {
+		storage: "DefaultTryReserveStore",
+	});
+	assert_eq!(storage.try_reserve(SMALL_CAPACITY), err);
+	assert_eq!(storage.try_reserve(usize::MAX), err);
+	assert_eq!(storage.pos(), 8);
+
+	// Checks budget of fallible serialization in progress
+	#[derive(Serializer)]
+	#[ser_type(pure_copy)]
+	struct DefaultTryReserveSer {
+		#[ser_storage(DefaultTryReserveStore)]
+		storage: DefaultTryReserveStore,
+	}
+
+	/// Type which reserves space for its contents with `try_reserve`
+	struct Reserves(usize);
+
+	impl<S: Serializer> Serialize<S> for Reserves {
+		fn serialize_data(&self, serializer: &mut S) {
+			if let Err(err) = serializer.storage_mut().try_reserve(self.0) {
+				err.fail();
+			}
+		}
+	}
+
+	let ser = DefaultTryReserveSer {
+		storage: DefaultTryReserveStore::new(),
+	};
+	assert_eq!(
+		ser.try_serialize_with_budget(&Reserves(100), 64).err(),
+		Some(SerializeError::BudgetExceeded { budget: 64 })
+	);
+}
+
+#[test]
+fn alloc_failed() {
+	// Larger than address space, so allocation cannot succeed
+	let size = 1 << 60;
+	let layout = Layout::from_size_align(size, 16).unwrap();
+
+	assert_eq!(
+		Store::try_with_capacity(size).err(),
+		Some(SerializeError::AllocFailed { layout })
+	);
+
+	let mut storage = Store::new();
+	storage.push(&1u64);
+	assert_eq!(
+		storage.try_reserve(size - 8),
+		Err(SerializeError::AllocFailed { layout })
+	);
+	assert_eq!(storage.pos(), 8);
+
+	assert_eq!(
+		SmallStore::try_with_capacity(SMALL_CAPACITY + 1).err(),
+		Some(SerializeError::CapacityExceeded {
+			storage: "AlignedVec"
+		})
+	);
+	assert_eq!(SmallStore::try_with_capacity(100).unwrap().capacity(), 112);
+}
+
+#[test]
+fn no_budget_outside_fallible_serialization() {
+	assert_eq!(fallible::budget(), usize::MAX);
+	assert_eq!(fallible::check_budget(usize::MAX), Ok(()));
+
+	let _ = PureCopySer::new().try_serialize_with_budget(&create_doc(10), 16);
+	assert_eq!(fallible::budget(), usize::MAX);
+}
+
+#[test]
+#[should_panic(expected = "Cannot grow AlignedVec further: AlignedVec capacity exceeded")]
+fn infallible_serialization_still_panics() {
+	// Fallible serialization does not leave thread in fallible mode
+	let _ = SmallPureCopySer::new().try_serialize(&create_doc(100));
+	SmallPureCopySer::new().serialize(&create_doc(100));
+}
+
+#[test]
+fn borrowed_and_locked_wrappers_return_error() {
+	fn check<Ser: Serializer, T: Serialize<Ser>>(
+		input: &T,
+		create: fn() -> Ser,
+		err: SerializeError,
+	) {
+		assert_eq!(create().try_serialize(input).err(), Some(err));
+	}
+
+	let locked = SerializeError::Locked { lock: "Mutex" };
+	let mutex = Mutex::new(1u32);
+	let guard = mutex.lock().unwrap();
+	check(&mutex, PtrOffsetSer::new, locked);
+	check(&mutex, CompleteSer::new, locked);
+	check(&mutex, PortableSer::new, locked);
+	drop(guard);
+
+	// Read lock does not prevent serialization. Output is written unlocked.
+	let locked = SerializeError::Locked { lock: "RwLock" };
+	let rw_lock = RwLock::new(1u32);
+	let guard = rw_lock.read().unwrap();
+	assert!(PtrOffsetSer::new().try_serialize(&rw_lock).is_ok());
+	let (pos, storage) = CompleteSer::new().try_serialize(&rw_lock).unwrap();
+	let output: &RwLock<u32> = unsafe { storage.read(pos) };
+	assert_eq!(*output.try_write().unwrap(), 1);
+	assert!(PortableSer::new().try_serialize(&rw_lock).is_ok());
+	drop(guard);
+	let guard = rw_lock.write().unwrap();
+	check(&rw_lock, PtrOffsetSer::new, locked);
+	check(&rw_lock, CompleteSer::new, locked);
+	check(&rw_lock, PortableSer::new, locked);
+	drop(guard);
+
+	// Shared borrow prevents native serialization, as borrow flag is copied to
+	// output
+	let borrowed = SerializeError::Borrowed;
+	let cell = RefCell::new(1u32);
+	let borrow = cell.borrow();
+	check(&cell, PureCopySer::new, borrowed);
+	check(&cell, PtrOffsetSer::new, borrowed);
+	check(&cell, CompleteSer::new, borrowed);
+	assert!(PortableSer::new().try_serialize(&cell).is_ok());
+	drop(borrow);
+	let borrow = cell.borrow_mut();
+	check(&cell, PortableSer::new, borrowed);
+	drop(borrow);
+
+	assert!(CompleteSer::new().try_serialize(&cell).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "Cannot serialize a `RefCell` which is borrowed")]
+fn infallible_serialization_of_borrowed_ref_cell_panics() {
+	let cell = RefCell::new(1u32);
+	let _borrow = cell.borrow_mut();
+	PortableSer::new().serialize(&cell);
+}
+
+#[test]
+fn errors_do_not_invoke_panic_hook() {
+	thread_local! {
+		static PANICS: Cell<usize> = Cell::new(0);
+	}
+
+	// Hook is process-wide, so count only panics on this thread
+	let hook = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		PANICS.with(|panics| panics.set(panics.get() + 1));
+		hook(info);
+	}));
+
+	let cell = RefCell::new(1u32);
+	let _borrow = cell.borrow_mut();
+	let mut ser = SmallCompleteSer::new();
+	assert!(ser.try_serialize_value(&create_doc(100)).is_err());
+	assert!(ser
+		.try_serialize_value_with_budget(&create_doc(1), 8)
+		.is_err());
+	assert!(SmallPureCopySer::new().try_serialize(&cell).is_err());
+	assert!(SmallPureCopySer::new()
+		.try_serialize_with_budget(&create_doc(1), 8)
+		.is_err());
+	assert!(Store::try_with_capacity(1 << 60).is_err());
+	assert_eq!(PANICS.with(Cell::get), 0);
+}
+
+/// Type which panics when serialized
+struct Panics;
+
+impl<S: Serializer> Serialize<S> for Panics {
+	fn serialize_data(&self, _serializer: &mut S) {
+		panic!("Oops");
+	}
+}
+
+#[test]
+#[should_panic(expected = "Oops")]
+fn other_panics_propagate() {
+	let _ = CompleteSer::new().try_serialize(&Panics);
+}
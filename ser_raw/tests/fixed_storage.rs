@@ -0,0 +1,183 @@
+use std::rc::Rc;
+
+use ser_raw::{
+	pos::{PosMapping, Ptrs, SharedAllocs},
+	ser_traits::Complete,
+	storage::{AlignedVec, ContiguousStorage, FixedVec, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, Serialize, Serializer, SerializerPool, Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store = FixedVec<16, 16, 8, MAX_CAPACITY>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+
+/// Complete serializer which doesn't record pointers
+#[derive(Serializer)]
+#[ser_type(complete)]
+struct FixedCompleteSer {
+	#[ser_storage(Store)]
+	storage: Store,
+	#[ser_pos_mapping]
+	pos_mapping: PosMapping,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+impl FixedCompleteSer {
+	fn with_capacity(capacity: usize) -> Self {
+		Self {
+			storage: Store::with_capacity(capacity),
+			pos_mapping: PosMapping::dummy(),
+			shared_allocs: SharedAllocs::new(),
+		}
+	}
+}
+
+/// Complete serializer with fixed storage which does record pointers
+#[derive(Serializer)]
+#[ser_type(complete)]
+struct FixedCompleteSerWithPtrs {
+	#[ser_storage(Store)]
+	storage: Store,
+	#[ser_pos_mapping]
+	pos_mapping: PosMapping,
+	#[ser_ptrs]
+	ptrs: Ptrs,
+}
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+struct Tree {
+	name: String,
+	children: Vec<Tree>,
+}
+
+fn create_tree(depth: u32) -> Tree {
+	Tree {
+		name: format!("depth {depth}"),
+		children: if depth == 0 {
+			vec![]
+		} else {
+			(0..3).map(|_| create_tree(depth - 1)).collect()
+		},
+	}
+}
+
+#[test]
+fn complete_without_ptrs() {
+	let input = create_tree(4);
+	let (pos, storage) = FixedCompleteSer::with_capacity(64 * 1024).serialize(&input);
+	assert_eq!(storage.capacity(), 64 * 1024);
+
+	let output: &Tree = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+
+	let output: &Tree = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+
+	// Output is same size as with `CompleteSerializer`
+	let (_, expected) = CompleteSer::new().serialize(&input);
+	assert_eq!(storage.pos(), expected.pos());
+}
+
+#[test]
+fn shared_allocs_without_ptrs() {
+	let shared = Rc::new("shared".to_string());
+	let input = vec![shared.clone(), shared];
+	let (pos, storage) = FixedCompleteSer::with_capacity(1024).serialize(&input);
+
+	let output: &Vec<Rc<String>> = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+	assert!(Rc::ptr_eq(&output[0], &output[1]));
+	assert_eq!(Rc::strong_count(&output[0]), 2);
+}
+
+#[test]
+fn storage_recreated_after_reset_without_ptrs() {
+	let mut ser = FixedCompleteSer::with_capacity(64 * 1024);
+	let first = create_tree(2);
+	let pos = ser.serialize_in_place(&first);
+	let output: &Tree = unsafe { ser.storage().read(pos) };
+	assert_eq!(output, &first);
+
+	// Replace storage between serializations
+	ser.reset();
+	*ser.storage_mut() = Store::with_capacity(64 * 1024);
+	let second = create_tree(3);
+	let pos = ser.serialize_in_place(&second);
+	let output: &Tree = unsafe { ser.storage().read(pos) };
+	assert_eq!(output, &second);
+	let output: &Tree = validate(ser.storage().as_slice(), pos).unwrap();
+	assert_eq!(output, &second);
+
+	// Pointers in output point into new storage
+	let range = ser.storage().as_ptr() as usize..ser.storage().as_ptr() as usize + ser.pos();
+	assert!(range.contains(&(output.name.as_ptr() as usize)));
+	assert!(range.contains(&(output.children.as_ptr() as usize)));
+}
+
+#[test]
+fn pool_without_ptrs() {
+	let pool = SerializerPool::new(|| FixedCompleteSer::with_capacity(64 * 1024));
+
+	for depth in 0..4 {
+		// Hold 2 serializers at once, so pool creates 2, each with its own storage
+		let inputs = (create_tree(depth), create_tree(3 - depth));
+		let mut ser1 = pool.get();
+		let mut ser2 = pool.get();
+		let pos1 = ser1.serialize_in_place(&inputs.0);
+		let pos2 = ser2.serialize_in_place(&inputs.1);
+
+		let output: &Tree = validate(ser1.storage().as_slice(), pos1).unwrap();
+		assert_eq!(output, &inputs.0);
+		let output: &Tree = validate(ser2.storage().as_slice(), pos2).unwrap();
+		assert_eq!(output, &inputs.1);
+	}
+	assert_eq!(pool.idle(), 2);
+}
+
+#[test]
+fn relocations_with_ptrs() {
+	let input = create_tree(1);
+	let mut ser = FixedCompleteSerWithPtrs {
+		storage: Store::with_capacity(1024),
+		pos_mapping: PosMapping::dummy(),
+		ptrs: Ptrs::new(),
+	};
+	let pos = ser.serialize_value(&input);
+	let (storage, relocations) = ser.do_finalize_with_relocations();
+	// Root's `name` and `children`, and children's `name`s.
+	// Children's empty `children` don't point into output.
+	assert_eq!(relocations.ptr_positions().len(), 1 + 1 + 3);
+
+	let output: &Tree = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+}
+
+#[test]
+#[should_panic(expected = "Relocations require serializer to record pointers with `#[ser_ptrs]`")]
+fn relocations_without_ptrs_panics() {
+	let mut ser = FixedCompleteSer::with_capacity(1024);
+	ser.serialize_value(&create_tree(1));
+	ser.do_finalize_with_relocations();
+}
+
+#[test]
+#[should_panic(expected = "FixedVec capacity exceeded")]
+fn exceeding_capacity_panics() {
+	FixedCompleteSer::with_capacity(64).serialize(&create_tree(2));
+}
+
+#[test]
+fn storage_never_moves() {
+	let mut storage = Store::with_capacity(100);
+	let ptr = storage.as_ptr();
+	assert_eq!(storage.capacity(), 112);
+
+	storage.push_slice(&[1u8; 100]);
+	storage.shrink_to_fit();
+	assert_eq!(storage.capacity(), 112);
+	assert_eq!(storage.as_ptr(), ptr);
+	storage.clear();
+	assert_eq!(storage.as_ptr(), ptr);
+}
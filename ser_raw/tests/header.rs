@@ -0,0 +1,223 @@
+use std::{fmt::Debug, mem};
+
+mod common;
+use common::{generate_minecraft_data, tests, Test};
+use ser_raw::{
+	header::{self, Header, HeaderError, SerializerKind, HEADER_SIZE, MAGIC},
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage},
+	util::aligned_max_capacity,
+	CompleteSerializer, Deserialize, Deserializer, PtrOffsetSerializer, PureCopyDeserializer,
+	PureCopySerializer, Serialize, Serializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PureCopySer8 =
+	PureCopySerializer<16, 16, 4, MAX_CAPACITY, AlignedVec<16, 16, 4, MAX_CAPACITY>>;
+
+fn test_serialize<T>(input: &T, _test: Test, _test_num: usize)
+where T: Serialize<PureCopySer>
+		+ Serialize<PtrOffsetSer>
+		+ Serialize<CompleteSer>
+		+ for<'a> Deserialize<PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>>
+		+ Debug
+		+ PartialEq {
+	// Pure copy
+	let (pos, storage) = PureCopySer::new().serialize_with_header(input);
+	assert_eq!(header::check::<T, PureCopySer>(storage.as_slice()), Ok(pos));
+	let de =
+		unsafe { PureCopyDeserializer::<16, 16, 8, MAX_CAPACITY>::new_at(storage.as_slice(), pos) };
+	let output: T = de.deserialize();
+	assert_eq!(&output, input);
+
+	// Pointer offset
+	let (pos, storage) = PtrOffsetSer::new().serialize_with_header(input);
+	assert_eq!(
+		header::check::<T, PtrOffsetSer>(storage.as_slice()),
+		Ok(pos)
+	);
+
+	// Complete
+	let (pos, storage) = CompleteSer::new().serialize_with_header(input);
+	assert_eq!(header::check::<T, CompleteSer>(storage.as_slice()), Ok(pos));
+	let output: &T = unsafe { storage.read(pos) };
+	assert_eq!(output, input);
+}
+
+tests!(test_serialize, no_locks);
+
+#[test]
+fn header_contents() {
+	let (pos, storage) = CompleteSer::new().serialize_with_header(&Box::new(123u32));
+	assert_eq!(HEADER_SIZE, 64);
+	assert_eq!(pos, HEADER_SIZE);
+
+	let header = Header::read(storage.as_slice()).unwrap();
+	assert_eq!(header.magic, MAGIC);
+	assert_eq!(header.format_version, header::FORMAT_VERSION);
+	assert_eq!(header.serializer_kind, SerializerKind::Complete as u8);
+	assert_eq!(header.ptr_width as usize, mem::size_of::<usize>());
+	assert_eq!(header.storage_alignment, 16);
+	assert_eq!(header.max_value_alignment, 16);
+	assert_eq!(header.value_alignment, 8);
+	assert_eq!(header.max_capacity, MAX_CAPACITY as u64);
+	assert_eq!(header.root_pos, pos as u64);
+	assert_eq!(
+		header.fingerprint,
+		<Box<u32> as Serialize<CompleteSer>>::FINGERPRINT
+	);
+
+	let (_, storage) = PureCopySer::new().serialize_with_header(&1u8);
+	let header = Header::read(storage.as_slice()).unwrap();
+	assert_eq!(header.serializer_kind, SerializerKind::PureCopy as u8);
+
+	let (_, storage) = PtrOffsetSer::new().serialize_with_header(&1u8);
+	let header = Header::read(storage.as_slice()).unwrap();
+	assert_eq!(header.serializer_kind, SerializerKind::PtrOffset as u8);
+}
+
+#[test]
+fn mismatches() {
+	#[derive(Serialize)]
+	struct Foo {
+		num: u32,
+		name: String,
+	}
+
+	#[derive(Serialize)]
+	struct Bar {
+		num: u32,
+		name: String,
+	}
+
+	#[derive(Serialize)]
+	struct Foo2 {
+		num: u32,
+		label: String,
+	}
+
+	let input = Foo {
+		num: 1,
+		name: "abc".to_string(),
+	};
+	let (_, storage) = CompleteSer::new().serialize_with_header(&input);
+	let bytes = storage.as_slice();
+
+	// Different type
+	assert!(matches!(
+		header::check::<Bar, CompleteSer>(bytes),
+		Err(HeaderError::WrongFingerprint { .. })
+	));
+	assert!(matches!(
+		header::check::<Foo2, CompleteSer>(bytes),
+		Err(HeaderError::WrongFingerprint { .. })
+	));
+
+	// Different serializer
+	assert_eq!(
+		header::check::<Foo, PtrOffsetSer>(bytes),
+		Err(HeaderError::WrongSerializer)
+	);
+
+	// Different storage params
+	let (_, storage) = PureCopySer::new().serialize_with_header(&input);
+	assert_eq!(
+		header::check::<Foo, PureCopySer8>(storage.as_slice()),
+		Err(HeaderError::WrongStorageParams)
+	);
+}
+
+#[test]
+fn invalid_headers() {
+	let (_, storage) = CompleteSer::new().serialize_with_header(&1u64);
+	let bytes = storage.as_slice();
+
+	assert_eq!(
+		header::check::<u64, CompleteSer>(&bytes[..HEADER_SIZE - 1]),
+		Err(HeaderError::TooShort)
+	);
+
+	let mut corrupted = bytes.to_vec();
+	corrupted[0] = b'X';
+	assert_eq!(
+		header::check::<u64, CompleteSer>(&corrupted),
+		Err(HeaderError::InvalidMagic)
+	);
+
+	// Endianness
+	let mut corrupted = bytes.to_vec();
+	corrupted[14] ^= 1;
+	assert_eq!(
+		header::check::<u64, CompleteSer>(&corrupted),
+		Err(HeaderError::WrongArchitecture)
+	);
+
+	// Pointer width
+	let mut corrupted = bytes.to_vec();
+	corrupted[13] = 2;
+	assert_eq!(
+		header::check::<u64, CompleteSer>(&corrupted),
+		Err(HeaderError::WrongArchitecture)
+	);
+
+	// Version
+	let mut corrupted = bytes.to_vec();
+	corrupted[8..12].copy_from_slice(&2u32.to_ne_bytes());
+	assert_eq!(
+		header::check::<u64, CompleteSer>(&corrupted),
+		Err(HeaderError::UnsupportedVersion { version: 2 })
+	);
+
+	// Root value out of bounds
+	assert_eq!(
+		header::check::<u64, CompleteSer>(&bytes[..bytes.len() - 1]),
+		Err(HeaderError::RootPosOutOfBounds)
+	);
+}
+
+#[test]
+fn fingerprints() {
+	fn fingerprint<T: Serialize<CompleteSer>>() -> u64 {
+		T::FINGERPRINT
+	}
+
+	// Primitives of same size differ
+	assert_ne!(fingerprint::<u32>(), fingerprint::<i32>());
+	assert_ne!(fingerprint::<u32>(), fingerprint::<f32>());
+
+	// Containers depend on their contents
+	assert_ne!(fingerprint::<Vec<u8>>(), fingerprint::<Vec<u16>>());
+	assert_ne!(fingerprint::<Option<u8>>(), fingerprint::<Option<i8>>());
+	assert_ne!(fingerprint::<[u8; 2]>(), fingerprint::<[u8; 3]>());
+	assert_ne!(fingerprint::<(u8, u16)>(), fingerprint::<(u16, u8)>());
+
+	// Fingerprint is deterministic
+	assert_eq!(fingerprint::<Vec<String>>(), fingerprint::<Vec<String>>());
+
+	// Recursive types
+	#[derive(Serialize)]
+	struct Node {
+		children: Vec<Node>,
+		next: Option<Box<Node>>,
+	}
+	assert_ne!(fingerprint::<Node>(), 0);
+
+	// Enums
+	#[derive(Serialize)]
+	#[allow(dead_code)]
+	enum Enum1 {
+		A(u8),
+		B { x: u16 },
+	}
+
+	#[derive(Serialize)]
+	#[allow(dead_code)]
+	enum Enum2 {
+		A(u8),
+		B { y: u16 },
+	}
+	assert_ne!(fingerprint::<Enum1>(), fingerprint::<Enum2>());
+}
@@ -0,0 +1,705 @@
+use std::{fmt::Debug, mem, num::NonZeroU16};
+
+use num_bigint::BigInt;
+use ser_raw::{
+	header::Endianness,
+	layout::{discriminant_from_bytes, Field, JsonError, Schema, TypeKind, Variant},
+	niche::Niche,
+	storage::{AlignedVec, ContiguousStorage},
+	util::aligned_max_capacity,
+	Layout, LayoutWith, PtrOffsetSerializer, Serialize, SerializeWith, Serializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+const PTR_SIZE: usize = mem::size_of::<usize>();
+type Ser = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+
+/// Decode output of `PtrOffsetSerializer` using only the schema, and format
+/// it in same form as `Debug` does. This is what external tooling would do.
+fn decode(schema: &Schema, type_name: &str, bytes: &[u8], pos: usize) -> String {
+	let type_def = schema.get(type_name).unwrap();
+	let read_usize =
+		|pos: usize| usize::from_ne_bytes(bytes[pos..pos + PTR_SIZE].try_into().unwrap());
+
+	match &type_def.kind {
+		TypeKind::Primitive => decode_primitive(type_name, &bytes[pos..pos + type_def.size]),
+		TypeKind::Struct { fields } => {
+			let name = if type_name.starts_with('(') {
+				""
+			} else {
+				type_name
+			};
+			let out = decode_fields(schema, name, fields, bytes, pos);
+			if type_name.starts_with('(') && fields.len() == 1 {
+				out.replace(')', ",)")
+			} else {
+				out
+			}
+		}
+		TypeKind::Enum { tag_size, variants } => {
+			let tag = discriminant_from_bytes(&bytes[pos..pos + tag_size]);
+			let variant = variants
+				.iter()
+				.find(|variant| variant.discriminant == tag)
+				.unwrap();
+			decode_fields(schema, &variant.name, &variant.fields, bytes, pos)
+		}
+		TypeKind::Box { inner } => decode(schema, inner, bytes, read_usize(pos)),
+		TypeKind::Vec {
+			inner,
+			ptr_offset,
+			len_offset,
+			..
+		}
+		| TypeKind::BoxedSlice {
+			inner,
+			ptr_offset,
+			len_offset,
+		} => {
+			let len = read_usize(pos + len_offset);
+			let ptr = read_usize(pos + ptr_offset);
+			let size = schema.get(inner).unwrap().size;
+			let values = (0..len)
+				.map(|index| decode(schema, inner, bytes, ptr + index * size))
+				.collect::<Vec<_>>();
+			format!("[{}]", values.join(", "))
+		}
+		TypeKind::String {
+			ptr_offset,
+			len_offset,
+			..
+		}
+		| TypeKind::BoxedStr {
+			ptr_offset,
+			len_offset,
+		} => {
+			let len = read_usize(pos + len_offset);
+			let ptr = read_usize(pos + ptr_offset);
+			let str = std::str::from_utf8(&bytes[ptr..ptr + len]).unwrap();
+			format!("{str:?}")
+		}
+		TypeKind::Option {
+			inner,
+			payload_offset,
+			none,
+		} => {
+			if none
+				.iter()
+				.all(|&(offset, byte)| bytes[pos + offset] == byte)
+			{
+				"None".to_string()
+			} else {
+				format!(
+					"Some({})",
+					decode(schema, inner, bytes, pos + payload_offset)
+				)
+			}
+		}
+		TypeKind::Array { inner, len } => {
+			let size = schema.get(inner).unwrap().size;
+			let values = (0..*len)
+				.map(|index| decode(schema, inner, bytes, pos + index * size))
+				.collect::<Vec<_>>();
+			format!("[{}]", values.join(", "))
+		}
+	}
+}
+
+fn decode_fields(
+	schema: &Schema,
+	name: &str,
+	fields: &[Field],
+	bytes: &[u8],
+	pos: usize,
+) -> String {
+	if fields.is_empty() {
+		return name.to_string();
+	}
+	let values = fields
+		.iter()
+		.map(|field| decode(schema, &field.type_name, bytes, pos + field.offset));
+	if fields[0].name == "0" {
+		format!("{}({})", name, values.collect::<Vec<_>>().join(", "))
+	} else {
+		let values = fields
+			.iter()
+			.zip(values)
+			.map(|(field, value)| format!("{}: {}", field.name, value))
+			.collect::<Vec<_>>();
+		format!("{} {{ {} }}", name, values.join(", "))
+	}
+}
+
+fn decode_primitive(type_name: &str, bytes: &[u8]) -> String {
+	macro_rules! decode {
+		($($name:literal => $ty:ty),* $(,)?) => {
+			match type_name {
+				$($name => format!("{:?}", <$ty>::from_ne_bytes(bytes.try_into().unwrap())),)*
+				"bool" => format!("{:?}", bytes[0] != 0),
+				"char" => {
+					let value = u32::from_ne_bytes(bytes.try_into().unwrap());
+					format!("{:?}", char::from_u32(value).unwrap())
+				}
+				"()" => "()".to_string(),
+				_ => panic!("Unknown primitive {type_name}"),
+			}
+		};
+	}
+	decode!(
+		"u8" => u8, "u16" => u16, "u32" => u32, "u64" => u64, "u128" => u128, "usize" => usize,
+		"i8" => i8, "i16" => i16, "i32" => i32, "i64" => i64, "i128" => i128, "isize" => isize,
+		"f32" => f32, "f64" => f64, "NonZeroU16" => u16,
+	)
+}
+
+fn test_decode<T>(input: &T)
+where T: Serialize<Ser> + Layout + Debug {
+	let schema = Schema::new::<T>();
+	let (pos, storage) = Ser::new().serialize(input);
+	let output = decode(&schema, schema.root(), storage.as_slice(), pos);
+	assert_eq!(output, format!("{input:?}"));
+}
+
+#[derive(Serialize, Layout, Debug)]
+struct Foo {
+	num: u32,
+	small: u8,
+	big: i128,
+	float: f64,
+	non_zero: NonZeroU16,
+	flag: bool,
+	chars: [char; 3],
+	unit: (),
+	tuple: (u16, bool),
+	single: (u8,),
+	boxed: Box<u64>,
+	vec: Vec<u16>,
+	empty_vec: Vec<u32>,
+	string: String,
+	strings: Vec<String>,
+	nested: Vec<Vec<Bar>>,
+	none: Option<u32>,
+	some: Option<u32>,
+	niche_none: Option<Box<u8>>,
+	niche_some: Option<Box<u8>>,
+	bool_none: Option<bool>,
+	vec_none: Option<Vec<u8>>,
+	vec_some: Option<Vec<u8>>,
+	fieldless: Fieldless,
+	fieldless2: Fieldless,
+	enum_int: EnumInt,
+	enum_int2: EnumInt,
+	enum_int3: EnumInt,
+	enum_c: EnumC,
+	enum_c2: EnumC,
+	tuple_struct: TupleStruct,
+	unit_struct: UnitStruct,
+}
+
+#[derive(Serialize, Layout, Debug)]
+struct Bar {
+	x: u8,
+	y: Box<Bar2>,
+}
+
+#[derive(Serialize, Layout, Debug)]
+struct Bar2(u32, String);
+
+#[derive(Serialize, Layout, Debug)]
+#[allow(dead_code)]
+enum Fieldless {
+	A,
+	B = 10,
+	C,
+}
+
+#[derive(Serialize, Layout, Debug)]
+#[repr(u8)]
+#[allow(dead_code)]
+enum EnumInt {
+	Unit = 3,
+	Tuple(u32, Box<u16>),
+	Named { x: u16, y: String },
+}
+
+#[derive(Serialize, Layout, Debug)]
+#[repr(C, i16)]
+#[allow(dead_code)]
+enum EnumC {
+	Unit = -2,
+	Tuple(u64),
+	Named { x: u8, y: Vec<u8> },
+}
+
+#[derive(Serialize, Layout, Debug)]
+struct TupleStruct(u8, u64);
+
+#[derive(Serialize, Layout, Debug)]
+struct UnitStruct;
+
+#[test]
+fn decode_with_schema() {
+	test_decode(&Foo {
+		num: 123,
+		small: 45,
+		big: -1_000_000_000_000_000_000_000,
+		float: 1.5,
+		non_zero: NonZeroU16::new(77).unwrap(),
+		flag: true,
+		chars: ['a', 'ß', '😀'],
+		unit: (),
+		tuple: (999, false),
+		single: (6,),
+		boxed: Box::new(u64::MAX),
+		vec: vec![1, 2, 3],
+		empty_vec: vec![],
+		string: "hello".to_string(),
+		strings: vec!["a".to_string(), "\"quoted\"".to_string(), String::new()],
+		nested: vec![
+			vec![
+				Bar {
+					x: 1,
+					y: Box::new(Bar2(11, "eleven".to_string())),
+				},
+				Bar {
+					x: 2,
+					y: Box::new(Bar2(22, "twenty two".to_string())),
+				},
+			],
+			vec![],
+			vec![Bar {
+				x: 3,
+				y: Box::new(Bar2(33, "thirty three".to_string())),
+			}],
+		],
+		none: None,
+		some: Some(456),
+		niche_none: None,
+		niche_some: Some(Box::new(7)),
+		bool_none: None,
+		vec_none: None,
+		vec_some: Some(vec![8, 9]),
+		fieldless: Fieldless::B,
+		fieldless2: Fieldless::C,
+		enum_int: EnumInt::Unit,
+		enum_int2: EnumInt::Tuple(10, Box::new(20)),
+		enum_int3: EnumInt::Named {
+			x: 30,
+			y: "forty".to_string(),
+		},
+		enum_c: EnumC::Unit,
+		enum_c2: EnumC::Named {
+			x: 50,
+			y: vec![60, 70],
+		},
+		tuple_struct: TupleStruct(1, 2),
+		unit_struct: UnitStruct,
+	});
+
+	test_decode(&EnumC::Tuple(u64::MAX - 1));
+	test_decode(&vec![Some(Box::new(Bar2(1, "x".to_string()))), None]);
+	test_decode(&Box::new(Fieldless::A));
+	test_decode::<Box<[Box<str>]>>(&vec!["abc".into(), "".into(), "d".into()].into_boxed_slice());
+	test_decode::<Box<[u16]>>(&vec![].into_boxed_slice());
+}
+
+#[test]
+fn struct_layout() {
+	#[derive(Layout)]
+	#[repr(C)]
+	#[allow(dead_code)]
+	struct Foo {
+		small: u8,
+		big: u32,
+		boxed: Box<u16>,
+		vec: Vec<String>,
+	}
+
+	let schema = Schema::new::<Foo>();
+	assert_eq!(schema.root(), "Foo");
+
+	let foo_def = schema.get("Foo").unwrap();
+	assert_eq!(foo_def.size, 16 + PTR_SIZE * 3);
+	assert_eq!(foo_def.align, 8);
+	assert_eq!(
+		foo_def.kind,
+		TypeKind::Struct {
+			fields: vec![
+				Field::new("small", 0, "u8".to_string()),
+				Field::new("big", 4, "u32".to_string()),
+				Field::new("boxed", 8, "Box<u16>".to_string()),
+				Field::new("vec", 16, "Vec<String>".to_string()),
+			]
+		}
+	);
+
+	// All contained types are in schema, once each, root type first
+	let names = schema
+		.types()
+		.iter()
+		.map(|type_def| type_def.name.as_str())
+		.collect::<Vec<_>>();
+	assert_eq!(
+		names,
+		[
+			"Foo",
+			"u8",
+			"u32",
+			"Box<u16>",
+			"u16",
+			"Vec<String>",
+			"String"
+		]
+	);
+
+	assert!(matches!(
+		schema.get("Box<u16>").unwrap().kind,
+		TypeKind::Box { ref inner } if inner == "u16"
+	));
+	assert!(matches!(
+		schema.get("Vec<String>").unwrap().kind,
+		TypeKind::Vec { ref inner, .. } if inner == "String"
+	));
+	assert!(matches!(
+		schema.get("String").unwrap().kind,
+		TypeKind::String { .. }
+	));
+}
+
+#[test]
+fn enum_layout() {
+	#[derive(Layout)]
+	#[repr(u16)]
+	#[allow(dead_code)]
+	enum Foo {
+		A = 5,
+		B(u8),
+		C { x: u32 },
+	}
+
+	let schema = Schema::new::<Foo>();
+	assert_eq!(
+		schema.get("Foo").unwrap().kind,
+		TypeKind::Enum {
+			tag_size: 2,
+			variants: vec![
+				Variant::new("A", 5, vec![]),
+				Variant::new("B", 6, vec![Field::new("0", 2, "u8".to_string())]),
+				Variant::new("C", 7, vec![Field::new("x", 4, "u32".to_string())]),
+			]
+		}
+	);
+
+	// Negative discriminants are stored as unsigned
+	#[derive(Layout)]
+	#[repr(i8)]
+	#[allow(dead_code)]
+	enum Bar {
+		A = -1,
+		B,
+	}
+
+	let schema = Schema::new::<Bar>();
+	assert_eq!(
+		schema.get("Bar").unwrap().kind,
+		TypeKind::Enum {
+			tag_size: 1,
+			variants: vec![Variant::new("A", 255, vec![]), Variant::new("B", 0, vec![])]
+		}
+	);
+}
+
+/// Get `payload_offset` and bytes of `None` of `Option<T>` from schema.
+fn option_layout<T: Layout>() -> (usize, Vec<(usize, u8)>) {
+	let schema = Schema::new::<Option<T>>();
+	match &schema.get(schema.root()).unwrap().kind {
+		TypeKind::Option {
+			payload_offset,
+			none,
+			..
+		} => (*payload_offset, none.clone()),
+		_ => panic!("Not an option"),
+	}
+}
+
+/// Get offset of first pointer-sized word in `value` which equals `word`.
+fn word_offset<T>(value: &T, word: usize) -> usize {
+	let words = unsafe {
+		std::slice::from_raw_parts(
+			value as *const T as *const usize,
+			mem::size_of::<T>() / PTR_SIZE,
+		)
+	};
+	words.iter().position(|&w| w == word).unwrap() * PTR_SIZE
+}
+
+fn bytes_at(offset: usize, bytes: &[u8]) -> Vec<(usize, u8)> {
+	bytes
+		.iter()
+		.enumerate()
+		.map(|(index, &byte)| (offset + index, byte))
+		.collect()
+}
+
+// Pins which niche Rust uses for `None` of every type `NICHE` is implemented
+// for. If this fails, the compiler or standard library has changed its choice,
+// and `NICHE` of the type needs updating.
+#[test]
+fn option_layouts() {
+	#[derive(Layout)]
+	#[repr(C)]
+	#[allow(dead_code)]
+	struct TwoBoxes(Box<u8>, Box<u8>);
+
+	#[derive(Layout)]
+	#[repr(C)]
+	#[allow(dead_code)]
+	struct BoxAndBool(Box<u8>, bool);
+
+	let null = [0; PTR_SIZE];
+	// Empty `Vec`, `String` and boxed slice have a dangling pointer, which for
+	// `u8` is 1. Their other fields are 0.
+	let vec_ptr_offset = word_offset(&Vec::<u8>::new(), 1);
+	let string_ptr_offset = word_offset(&String::new(), 1);
+	let slice_ptr_offset = word_offset(&Vec::<u8>::new().into_boxed_slice(), 1);
+
+	// Tag before value
+	assert_eq!(option_layout::<u8>(), (1, bytes_at(0, &[0])));
+	assert_eq!(option_layout::<u32>(), (4, bytes_at(0, &[0; 4])));
+	assert_eq!(
+		option_layout::<Option<Box<u8>>>(),
+		(PTR_SIZE, bytes_at(0, &null))
+	);
+	// Niche is value after largest valid value
+	assert_eq!(option_layout::<bool>(), (0, bytes_at(0, &[2])));
+	assert_eq!(option_layout::<Option<bool>>(), (0, bytes_at(0, &[3])));
+	assert_eq!(
+		option_layout::<char>(),
+		(0, bytes_at(0, &0x110000u32.to_ne_bytes()))
+	);
+	assert_eq!(option_layout::<Fieldless>(), (0, bytes_at(0, &[12])));
+	assert_eq!(option_layout::<[bool; 2]>(), (0, bytes_at(0, &[2])));
+	// Niche is a null pointer
+	assert_eq!(option_layout::<NonZeroU16>(), (0, bytes_at(0, &[0; 2])));
+	assert_eq!(option_layout::<Box<u8>>(), (0, bytes_at(0, &null)));
+	assert_eq!(
+		option_layout::<Vec<u8>>(),
+		(0, bytes_at(vec_ptr_offset, &null))
+	);
+	assert_eq!(
+		option_layout::<String>(),
+		(0, bytes_at(string_ptr_offset, &null))
+	);
+	assert_eq!(
+		option_layout::<Box<[u8]>>(),
+		(0, bytes_at(slice_ptr_offset, &null))
+	);
+	assert_eq!(
+		option_layout::<Box<str>>(),
+		(0, bytes_at(slice_ptr_offset, &null))
+	);
+	// Niche with most invalid values is used, first in memory if a tie
+	assert_eq!(option_layout::<TwoBoxes>(), (0, bytes_at(0, &null)));
+	assert_eq!(option_layout::<BoxAndBool>(), (0, bytes_at(PTR_SIZE, &[2])));
+	// `Sign` is after the `Vec` of `BigUint`
+	assert_eq!(option_layout::<BigInt>(), (0, bytes_at(PTR_SIZE * 3, &[3])));
+}
+
+#[test]
+fn recursive_types() {
+	#[derive(Layout)]
+	#[allow(dead_code)]
+	struct Node {
+		children: Vec<Node>,
+		next: Option<Box<Node>>,
+	}
+
+	let schema = Schema::new::<Node>();
+	let names = schema
+		.types()
+		.iter()
+		.map(|type_def| type_def.name.as_str())
+		.collect::<Vec<_>>();
+	assert_eq!(
+		names,
+		["Node", "Vec<Node>", "Option<Box<Node>>", "Box<Node>"]
+	);
+}
+
+#[test]
+fn generics() {
+	#[derive(Layout)]
+	#[allow(dead_code)]
+	struct Foo<T: Layout, const N: usize> {
+		inner: [T; N],
+	}
+
+	let schema = Schema::new::<Foo<u8, 3>>();
+	assert_eq!(schema.root(), "Foo<u8, 3>");
+	assert!(matches!(
+		schema.get("[u8; 3]").unwrap().kind,
+		TypeKind::Array { ref inner, len: 3 } if inner == "u8"
+	));
+}
+
+#[test]
+fn ser_with() {
+	struct Foreign(u16);
+
+	struct ForeignProxy;
+
+	impl<S: Serializer> SerializeWith<Foreign, S> for ForeignProxy {
+		fn serialize_data_with(_foreign: &Foreign, _serializer: &mut S) {}
+	}
+
+	impl LayoutWith<Foreign> for ForeignProxy {
+		const NICHE: Option<Niche> = None;
+
+		fn type_name_with() -> String {
+			"Foreign".to_string()
+		}
+
+		fn type_kind_with(schema: &mut Schema) -> TypeKind {
+			TypeKind::Struct {
+				fields: vec![Field::new("0", 0, schema.add::<u16>())],
+			}
+		}
+	}
+
+	#[derive(Serialize, Layout)]
+	#[allow(dead_code)]
+	struct Foo {
+		#[ser_with(ForeignProxy)]
+		foreign: Foreign,
+	}
+
+	let schema = Schema::new::<Foo>();
+	let foreign = schema.get("Foreign").unwrap();
+	assert_eq!(foreign.size, 2);
+	assert_eq!(
+		foreign.kind,
+		TypeKind::Struct {
+			fields: vec![Field::new("0", 0, "u16".to_string())]
+		}
+	);
+}
+
+#[test]
+fn bigints() {
+	let schema = Schema::new::<BigInt>();
+	let TypeKind::Struct { fields } = &schema.get("BigInt").unwrap().kind else {
+		panic!("Expected struct");
+	};
+	assert_eq!(fields.len(), 2);
+	assert!(matches!(
+		schema.get("BigUint").unwrap().kind,
+		TypeKind::Vec { ref inner, .. } if inner == "usize"
+	));
+	assert!(matches!(
+		schema.get("Sign").unwrap().kind,
+		TypeKind::Enum { tag_size: 1, ref variants } if variants.len() == 3
+	));
+}
+
+#[test]
+fn json() {
+	#[derive(Layout)]
+	#[repr(C)]
+	#[allow(dead_code)]
+	struct Foo {
+		num: u16,
+		opt: Option<u8>,
+		chars: [char; 2],
+	}
+
+	let json = Schema::new::<Foo>().to_json();
+	let endianness = if cfg!(target_endian = "little") {
+		"little"
+	} else {
+		"big"
+	};
+	let expected = format!(
+		concat!(
+			r#"{{"root":"Foo","ptr_size":{},"endianness":"{}","types":["#,
+			r#"{{"name":"Foo","size":12,"align":4,"kind":"struct","fields":["#,
+			r#"{{"name":"num","offset":0,"type":"u16"}},"#,
+			r#"{{"name":"opt","offset":2,"type":"Option<u8>"}},"#,
+			r#"{{"name":"chars","offset":4,"type":"[char; 2]"}}"#,
+			r#"]}},"#,
+			r#"{{"name":"u16","size":2,"align":2,"kind":"primitive"}},"#,
+			r#"{{"name":"Option<u8>","size":2,"align":1,"kind":"option","inner":"u8","payload_offset":1,"none":[[0,0]]}},"#,
+			r#"{{"name":"u8","size":1,"align":1,"kind":"primitive"}},"#,
+			r#"{{"name":"[char; 2]","size":8,"align":4,"kind":"array","inner":"char","len":2}},"#,
+			r#"{{"name":"char","size":4,"align":4,"kind":"primitive"}}"#,
+			r#"]}}"#,
+		),
+		PTR_SIZE, endianness
+	);
+	assert_eq!(json, expected);
+}
+
+#[test]
+fn json_round_trip() {
+	let schema = Schema::new::<Foo>();
+	let parsed = Schema::from_json(&schema.to_json()).unwrap();
+	assert_eq!(parsed, schema);
+	assert_eq!(parsed.ptr_size(), PTR_SIZE);
+	assert_eq!(parsed.endianness(), Endianness::NATIVE);
+
+	// Whitespace and escapes
+	let json = r#" {
+		"root": "A\"ß😀",
+		"ptr_size": 4,
+		"endianness": "big",
+		"types": [
+			{ "name": "A\"ß😀", "size": 0, "align": 1, "kind": "struct", "fields": [] }
+		]
+	} "#;
+	let schema = Schema::from_json(json).unwrap();
+	assert_eq!(schema.root(), "A\"ß😀");
+	assert_eq!(schema.ptr_size(), 4);
+	assert_eq!(schema.endianness(), Endianness::Big);
+	assert_eq!(Schema::from_json(&schema.to_json()).unwrap(), schema);
+}
+
+#[test]
+fn json_errors() {
+	assert_eq!(
+		Schema::from_json(r#"{"root": }"#),
+		Err(JsonError::Syntax { pos: 9 })
+	);
+	assert_eq!(
+		Schema::from_json(r#"{"root": "A"} x"#),
+		Err(JsonError::Syntax { pos: 14 })
+	);
+
+	let invalid = |json: &str| {
+		matches!(
+			Schema::from_json(json),
+			Err(JsonError::InvalidSchema { .. })
+		)
+	};
+	assert!(invalid(r#"[]"#));
+	assert!(invalid(
+		r#"{"root":"A","ptr_size":8,"endianness":"little"}"#
+	));
+	assert!(invalid(
+		r#"{"root":"A","ptr_size":3,"endianness":"little","types":[]}"#
+	));
+	// Undefined types
+	assert!(invalid(
+		r#"{"root":"A","ptr_size":8,"endianness":"little","types":[]}"#
+	));
+	assert!(invalid(
+		r#"{"root":"A","ptr_size":8,"endianness":"little","types":[
+			{"name":"A","size":8,"align":8,"kind":"box","inner":"B"}
+		]}"#
+	));
+	// Duplicate types
+	assert!(invalid(
+		r#"{"root":"A","ptr_size":8,"endianness":"little","types":[
+			{"name":"A","size":1,"align":1,"kind":"primitive"},
+			{"name":"A","size":1,"align":1,"kind":"primitive"}
+		]}"#
+	));
+}
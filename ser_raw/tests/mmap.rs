@@ -0,0 +1,299 @@
+#![cfg(unix)]
+
+use std::{
+	env, fs,
+	io::ErrorKind,
+	os::unix::{io::AsRawFd, process::CommandExt},
+	path::PathBuf,
+	process::Command,
+	ptr,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ser_raw::{
+	pos::{PosMapping, Ptrs, SharedAllocs},
+	read::OffsetRef,
+	storage::{AlignedVec, ContiguousStorage, MmapReader, MmapStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	validate, PureCopySerializer, Serialize, Serializer, Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store = MmapStorage<16, 16, 8, MAX_CAPACITY>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+
+#[derive(Serializer)]
+#[ser_type(pure_copy)]
+struct MmapPureCopySer {
+	#[ser_storage(Store)]
+	storage: Store,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+#[derive(Serializer)]
+#[ser_type(ptr_offset)]
+struct MmapPtrOffsetSer {
+	#[ser_storage(Store)]
+	storage: Store,
+	#[ser_pos_mapping]
+	pos_mapping: PosMapping,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+#[derive(Serializer)]
+#[ser_type(complete)]
+struct MmapCompleteSer {
+	#[ser_storage(Store)]
+	storage: Store,
+	#[ser_pos_mapping]
+	pos_mapping: PosMapping,
+	#[ser_ptrs]
+	ptrs: Ptrs,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+struct Ast {
+	name: String,
+	nodes: Vec<Node>,
+}
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+struct Node {
+	id: u64,
+	children: Vec<u32>,
+}
+
+/// Create an `Ast` large enough that storage has to grow several times
+fn create_ast() -> Ast {
+	Ast {
+		name: "big".to_string(),
+		nodes: (0..2000)
+			.map(|id| {
+				Node {
+					id,
+					children: (0..(id as u32 % 20)).collect(),
+				}
+			})
+			.collect(),
+	}
+}
+
+/// Get path for a temporary file, which is deleted when guard is dropped
+fn temp_path() -> TempPath {
+	static COUNTER: AtomicUsize = AtomicUsize::new(0);
+	let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+	let path =
+		std::env::temp_dir().join(format!("ser_raw_mmap_test_{}_{id}.bin", std::process::id()));
+	TempPath(path)
+}
+
+struct TempPath(PathBuf);
+
+impl Drop for TempPath {
+	fn drop(&mut self) {
+		let _ = fs::remove_file(&self.0);
+	}
+}
+
+#[test]
+fn output_matches_aligned_vec() {
+	let input = create_ast();
+	let (_, expected) = PureCopySer::new().serialize(&input);
+
+	let path = temp_path();
+	let ser = MmapPureCopySer {
+		storage: unsafe { Store::create(&path.0) }.unwrap(),
+		shared_allocs: SharedAllocs::new(),
+	};
+	let (pos, storage) = ser.serialize(&input);
+	assert_eq!(pos, 0);
+	assert_eq!(storage.as_slice(), expected.as_slice());
+	assert!(storage.capacity() >= storage.pos());
+	assert_eq!(
+		storage.file().metadata().unwrap().len(),
+		storage.capacity() as u64
+	);
+
+	// File is truncated to length of output
+	let file = storage.into_file().unwrap();
+	assert_eq!(file.metadata().unwrap().len(), expected.pos() as u64);
+	drop(file);
+	assert_eq!(fs::read(&path.0).unwrap(), expected.as_slice());
+}
+
+#[test]
+fn reopen_ptr_offset_output() {
+	let input = create_ast();
+
+	let path = temp_path();
+	let ser = MmapPtrOffsetSer {
+		storage: unsafe { Store::create_with_capacity(&path.0, 64) }.unwrap(),
+		pos_mapping: PosMapping::dummy(),
+		shared_allocs: SharedAllocs::new(),
+	};
+	let (pos, storage) = ser.serialize(&input);
+	let len = storage.pos();
+	storage.flush().unwrap();
+	// Dropping storage also truncates file
+	drop(storage);
+	assert_eq!(fs::metadata(&path.0).unwrap().len(), len as u64);
+
+	let storage = unsafe { Store::open(&path.0) }.unwrap();
+	assert_eq!(storage.pos(), len);
+
+	let output = unsafe { OffsetRef::<Ast>::new(&storage, pos) };
+	let name = unsafe { output.field(|ast| ptr::addr_of!((*ast).name)) }.as_string();
+	assert_eq!(name.as_str(), "big");
+	let nodes = unsafe { output.field(|ast| ptr::addr_of!((*ast).nodes)) }.as_vec();
+	assert_eq!(nodes.len(), 2000);
+	let node = nodes.get(1999).unwrap();
+	assert_eq!(
+		unsafe { *node.field(|node| ptr::addr_of!((*node).id)).get() },
+		1999
+	);
+	let children = unsafe { node.field(|node| ptr::addr_of!((*node).children)) }.as_vec();
+	assert_eq!(
+		unsafe { children.as_slice() },
+		(0..19).collect::<Vec<u32>>().as_slice()
+	);
+
+	drop(storage);
+	assert_eq!(fs::metadata(&path.0).unwrap().len(), len as u64);
+}
+
+#[test]
+fn complete_with_temp_file() {
+	let input = create_ast();
+
+	let ser = MmapCompleteSer {
+		storage: Store::new(),
+		pos_mapping: PosMapping::dummy(),
+		ptrs: Ptrs::new(),
+		shared_allocs: SharedAllocs::new(),
+	};
+	let (pos, storage) = ser.serialize(&input);
+
+	let output: &Ast = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+
+	let output: &Ast = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn grow_and_shrink() {
+	let mut storage = Store::with_capacity(100);
+	assert_eq!(storage.capacity(), 112);
+
+	storage.push_slice(&[1u64; 1000]);
+	assert_eq!(storage.pos(), 8000);
+	assert_eq!(storage.capacity(), 8192);
+
+	storage.shrink_to_fit();
+	assert_eq!(storage.capacity(), 8000);
+	assert_eq!(storage.file().metadata().unwrap().len(), 8000);
+	assert!(storage
+		.as_slice()
+		.chunks(8)
+		.all(|chunk| chunk == 1u64.to_ne_bytes()));
+
+	storage.clear();
+	storage.shrink_to_fit();
+	assert_eq!(storage.capacity(), 0);
+}
+
+#[test]
+fn open_rejects_unaligned_file() {
+	let path = temp_path();
+	fs::write(&path.0, [0u8; 7]).unwrap();
+	let err = unsafe { Store::open(&path.0) }.err().unwrap();
+	assert_eq!(err.kind(), ErrorKind::InvalidData);
+	// File is not altered
+	assert_eq!(fs::read(&path.0).unwrap(), [0u8; 7]);
+}
+
+/// Env var used to pass shared memory file descriptor to child process
+const SHM_FD_VAR: &str = "SER_RAW_TEST_SHM_FD";
+
+#[test]
+fn shared_memory_handoff_to_child_process() {
+	let input = create_ast();
+	let ser = MmapPtrOffsetSer {
+		storage: unsafe { Store::shared_memory() }.unwrap(),
+		pos_mapping: PosMapping::dummy(),
+		shared_allocs: SharedAllocs::new(),
+	};
+	let (_, storage) = ser.serialize_with_header(&input);
+	let file = storage.into_file().unwrap();
+	let fd = file.as_raw_fd();
+
+	// Run `shared_memory_child` test in a child process, passing it the
+	// shared memory file descriptor
+	let mut command = Command::new(env::current_exe().unwrap());
+	command
+		.args(["--exact", "shared_memory_child", "--nocapture"])
+		.env(SHM_FD_VAR, fd.to_string());
+	unsafe {
+		command.pre_exec(move || {
+			// Allow child to inherit file descriptor
+			if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+				return Err(std::io::Error::last_os_error());
+			}
+			Ok(())
+		});
+	}
+	let output = command.output().unwrap();
+	assert!(
+		output.status.success(),
+		"Child process failed: {}",
+		String::from_utf8_lossy(&output.stdout)
+	);
+	assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+}
+
+/// Reader side of `shared_memory_handoff_to_child_process`.
+/// Does nothing unless run as child process by that test.
+#[test]
+fn shared_memory_child() {
+	let fd = match env::var(SHM_FD_VAR) {
+		Ok(fd) => fd.parse().unwrap(),
+		Err(_) => return,
+	};
+
+	let reader = unsafe { MmapReader::from_raw_fd(fd) }.unwrap();
+	let pos = reader.root_pos::<Ast, MmapPtrOffsetSer>().unwrap();
+	assert!(reader.root_pos::<Node, MmapPtrOffsetSer>().is_err());
+
+	let output = unsafe { OffsetRef::<Ast>::from_bytes(reader.as_slice(), pos) };
+	let name = unsafe { output.field(|ast| ptr::addr_of!((*ast).name)) }.as_string();
+	assert_eq!(name.as_str(), "big");
+	let nodes = unsafe { output.field(|ast| ptr::addr_of!((*ast).nodes)) }.as_vec();
+	assert_eq!(nodes.len(), 2000);
+	let node = nodes.get(1234).unwrap();
+	assert_eq!(
+		unsafe { *node.field(|node| ptr::addr_of!((*node).id)).get() },
+		1234
+	);
+	let children = unsafe { node.field(|node| ptr::addr_of!((*node).children)) }.as_vec();
+	assert_eq!(
+		unsafe { children.as_slice() },
+		(0..14).collect::<Vec<u32>>().as_slice()
+	);
+}
+
+#[test]
+fn reader_of_empty_file() {
+	let path = temp_path();
+	let file = unsafe { Store::create(path.0.as_path()) }
+		.unwrap()
+		.into_file()
+		.unwrap();
+	let reader = unsafe { MmapReader::new(&file) }.unwrap();
+	assert!(reader.is_empty());
+	assert_eq!(reader.as_slice(), &[] as &[u8]);
+	assert!(reader.root_pos::<Ast, MmapPtrOffsetSer>().is_err());
+}
@@ -0,0 +1,376 @@
+use std::fmt::Debug;
+
+mod common;
+use common::{generate_minecraft_data, tests, Test};
+use ser_raw::{
+	header::{self, Header, HeaderError, SerializerKind, FORMAT_VERSION, HEADER_SIZE},
+	storage::{AlignedVec, ContiguousStorage, Storage},
+	util::aligned_max_capacity,
+	Deserialize, PortableSerializer, Serialize, Serializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type Ser = PortableSerializer<16, 16, 1, MAX_CAPACITY, 8, Store>;
+type Ser32 = PortableSerializer<16, 16, 1, MAX_CAPACITY, 4, Store>;
+
+fn serialize<T: Serialize<Ser>>(value: &T) -> Vec<u8> {
+	let (pos, storage) = Ser::new().serialize(value);
+	assert_eq!(pos, 0);
+	storage.as_slice().to_vec()
+}
+
+fn serialize32<T: Serialize<Ser32>>(value: &T) -> Vec<u8> {
+	let (pos, storage) = Ser32::new().serialize(value);
+	assert_eq!(pos, 0);
+	storage.as_slice().to_vec()
+}
+
+fn test_serialize<T>(input: &T, test: Test, test_num: usize)
+where T: Serialize<Ser> + Serialize<Ser32> + Debug + PartialEq {
+	let output = serialize(input);
+
+	// Output is not decoded here, just its length.
+	// Contents of output are tested in tests below.
+	let expected_size = match test {
+		Test::Primitives => 95,
+		Test::NonZeroNumbers => 78,
+		Test::Arrays => 17,
+		Test::ArraysOfBoxes => 65,
+		Test::Tuples => 38,
+		Test::EnumFieldless => 4,
+		Test::EnumWithFields => 10,
+		Test::BoxedPrimitives => 223,
+		Test::BoxedStructs => 42,
+		Test::VecOfPrimitives => [48, 55, 73][test_num],
+		Test::VecOfVecs => 144,
+		Test::VecsWithZeroLenZeroCapacity => 16,
+		Test::VecsWithZeroLenExcessCapacity => 16,
+		Test::VecsWithZeroLenExcessCapacity2 => 16,
+		Test::VecsWithExcessCapacity => 17,
+		Test::VecsWithExcessCapacity2 => 28,
+		Test::Strings => [19, 17, 24, 30][test_num],
+		Test::StringsWithZeroLenZeroCapacity => 16,
+		Test::StringsWithZeroLenExcessCapacity => 16,
+		Test::StringsWithZeroLenExcessCapacity2 => 16,
+		Test::StringsWithExcessCapacity => 17,
+		Test::StringsWithExcessCapacity2 => 19,
+		Test::BoxedSlices => 164,
+		Test::Options => [49, 49, 69, 62][test_num],
+		Test::Wrappers => 101,
+		Test::Ranges => 58,
+		Test::Locks => 39,
+		Test::Atomics => 21,
+		Test::Cows => 164,
+		Test::BigUint => 112,
+		Test::BigInt => 215,
+		Test::StructureWhereStorageGrowsAfterLastPointerWritten => 104,
+		Test::MinecraftData => 925890,
+	};
+	assert_eq!(output.len(), expected_size);
+
+	// Output is deterministic
+	assert_eq!(serialize(input), output);
+
+	// 4-byte offsets
+	let output32 = serialize32(input);
+	assert!(output32.len() <= output.len());
+}
+
+tests!(test_serialize);
+
+/// Reader for portable output.
+struct Reader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+	offset_size: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn new(bytes: &'a [u8], offset_size: usize) -> Self {
+		Self {
+			bytes,
+			pos: 0,
+			offset_size,
+		}
+	}
+
+	fn at(&self, pos: usize) -> Self {
+		Self::new(self.bytes, self.offset_size).seek(pos)
+	}
+
+	fn seek(mut self, pos: usize) -> Self {
+		self.pos = pos;
+		self
+	}
+
+	fn take<const N: usize>(&mut self) -> [u8; N] {
+		let bytes = self.bytes[self.pos..self.pos + N].try_into().unwrap();
+		self.pos += N;
+		bytes
+	}
+
+	fn u8(&mut self) -> u8 {
+		u8::from_le_bytes(self.take())
+	}
+
+	fn u16(&mut self) -> u16 {
+		u16::from_le_bytes(self.take())
+	}
+
+	fn u32(&mut self) -> u32 {
+		u32::from_le_bytes(self.take())
+	}
+
+	fn u64(&mut self) -> u64 {
+		u64::from_le_bytes(self.take())
+	}
+
+	fn offset(&mut self) -> usize {
+		if self.offset_size == 4 {
+			self.u32() as usize
+		} else {
+			self.u64() as usize
+		}
+	}
+
+	fn string(&mut self) -> String {
+		let (pos, len) = (self.offset(), self.offset());
+		String::from_utf8(self.bytes[pos..pos + len].to_vec()).unwrap()
+	}
+}
+
+#[test]
+fn primitives_bytes() {
+	#[derive(Serialize)]
+	struct Foo {
+		u8: u8,
+		u16: u16,
+		u128: u128,
+		usize: usize,
+		isize: isize,
+		non_zero: std::num::NonZeroU32,
+		f32: f32,
+		f64: f64,
+		bool: bool,
+		char: char,
+		unit: (),
+	}
+	assert_eq!(<Foo as Serialize<Ser>>::PORTABLE_SIZE, 56);
+
+	let output = serialize(&Foo {
+		u8: 0x01,
+		u16: 0x0203,
+		u128: 0x0405060708090a0b0c0d0e0f10111213,
+		usize: 0x1415,
+		isize: -2,
+		non_zero: std::num::NonZeroU32::new(0x16171819).unwrap(),
+		f32: 1.5,
+		f64: -2.25,
+		bool: true,
+		char: '🦀',
+		unit: (),
+	});
+
+	let mut expected = vec![0x01];
+	expected.extend(0x0203u16.to_le_bytes());
+	expected.extend(0x0405060708090a0b0c0d0e0f10111213u128.to_le_bytes());
+	expected.extend(0x1415u64.to_le_bytes());
+	expected.extend((-2i64).to_le_bytes());
+	expected.extend(0x16171819u32.to_le_bytes());
+	expected.extend(1.5f32.to_le_bytes());
+	expected.extend((-2.25f64).to_le_bytes());
+	expected.push(1);
+	expected.extend(('🦀' as u32).to_le_bytes());
+	assert_eq!(output, expected);
+}
+
+#[test]
+fn enums() {
+	#[derive(Serialize)]
+	#[repr(u8)]
+	enum Foo {
+		A = 10,
+		B(u8, u16),
+		C { x: u32 },
+	}
+	assert_eq!(<Foo as Serialize<Ser>>::PORTABLE_SIZE, 8);
+
+	// Tag is index of variant, not discriminant. Unused bytes are zero.
+	assert_eq!(serialize(&Foo::A), [0, 0, 0, 0, 0, 0, 0, 0]);
+	assert_eq!(serialize(&Foo::B(1, 0x0203)), [1, 0, 0, 0, 1, 3, 2, 0]);
+	assert_eq!(
+		serialize(&Foo::C { x: 0x04050607 }),
+		[2, 0, 0, 0, 7, 6, 5, 4]
+	);
+
+	#[derive(Serialize)]
+	enum Empty {}
+	assert_eq!(<Empty as Serialize<Ser>>::PORTABLE_SIZE, 4);
+}
+
+#[test]
+fn options_bytes() {
+	let output = serialize(&(None::<u16>, Some(0x0102u16), Some(None::<u8>)));
+	assert_eq!(output, [0, 0, 0, 1, 2, 1, 1, 0, 0]);
+}
+
+#[test]
+fn pointers() {
+	#[derive(Serialize)]
+	struct Foo {
+		boxed: Box<u16>,
+		vec: Vec<Bar>,
+		empty: Vec<u8>,
+		str: String,
+	}
+
+	#[derive(Serialize)]
+	struct Bar {
+		name: String,
+		num: u8,
+	}
+
+	let input = Foo {
+		boxed: Box::new(0x0102),
+		vec: vec![
+			Bar {
+				name: "abc".to_string(),
+				num: 3,
+			},
+			Bar {
+				name: "de".to_string(),
+				num: 4,
+			},
+		],
+		empty: vec![],
+		str: "hello".to_string(),
+	};
+
+	for (output, offset_size) in [(serialize(&input), 8), (serialize32(&input), 4)] {
+		let mut root = Reader::new(&output, offset_size);
+
+		let boxed_pos = root.offset();
+		assert_eq!(root.at(boxed_pos).u16(), 0x0102);
+
+		let (vec_pos, vec_len) = (root.offset(), root.offset());
+		assert_eq!(vec_len, 2);
+		let mut items = root.at(vec_pos);
+		assert_eq!(items.string(), "abc");
+		assert_eq!(items.u8(), 3);
+		assert_eq!(items.string(), "de");
+		assert_eq!(items.u8(), 4);
+
+		assert_eq!((root.offset(), root.offset()), (0, 0));
+		assert_eq!(root.string(), "hello");
+		assert_eq!(root.pos, offset_size * 7);
+	}
+
+	// Pointees are written in depth-first order
+	let output = serialize32(&input);
+	assert_eq!(&output[28..30], &[2, 1]);
+	assert_eq!(
+		&output[30..48],
+		&[48, 0, 0, 0, 3, 0, 0, 0, 3, 51, 0, 0, 0, 2, 0, 0, 0, 4]
+	);
+	assert_eq!(&output[48..53], b"abcde");
+	assert_eq!(&output[53..], b"hello");
+}
+
+#[test]
+fn padding_is_zeroed() {
+	// Storage with `VALUE_ALIGNMENT` of 8 inserts padding after each value
+	#[derive(Serializer)]
+	#[ser_type(portable)]
+	#[ser_offset_size(4)]
+	struct MySer {
+		#[ser_storage(AlignedVec<16, 16, 8, MAX_CAPACITY>)]
+		storage: AlignedVec<16, 16, 8, MAX_CAPACITY>,
+	}
+
+	let input = (Box::new(1u8), "abc".to_string(), Box::new(2u8));
+	let output1 = {
+		let (_, storage) = MySer {
+			storage: AlignedVec::new(),
+		}
+		.serialize(&input);
+		storage.as_slice().to_vec()
+	};
+
+	// Dirty some memory, to make it likely uninitialized memory is not zero
+	drop(vec![0xffu8; 4096]);
+
+	let (_, storage) = MySer {
+		storage: AlignedVec::new(),
+	}
+	.serialize(&input);
+	let output2 = storage.as_slice();
+
+	assert_eq!(output1, output2);
+	assert_eq!(
+		output1,
+		[
+			16, 0, 0, 0, 24, 0, 0, 0, 3, 0, 0, 0, 32, 0, 0, 0, // Root value
+			1, 0, 0, 0, 0, 0, 0, 0, // Box 1
+			97, 98, 99, 0, 0, 0, 0, 0, // String
+			2, 0, 0, 0, 0, 0, 0, 0, // Box 2
+		]
+	);
+}
+
+#[test]
+fn header() {
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct Foo {
+		names: Vec<String>,
+	}
+
+	let input = Foo {
+		names: vec!["Alfred".to_string(), "Gertrude".to_string()],
+	};
+	let (pos, storage) = Ser32::new().serialize_with_header(&input);
+	let output = storage.as_slice();
+	assert_eq!(pos, HEADER_SIZE);
+	assert_eq!(header::check::<Foo, Ser32>(output), Ok(pos));
+
+	// Header is little-endian
+	assert_eq!(&output[8..12], &FORMAT_VERSION.to_le_bytes());
+	assert_eq!(&output[48..56], &(pos as u64).to_le_bytes());
+
+	let header = Header::read(output).unwrap();
+	assert_eq!(header.serializer_kind, SerializerKind::Portable as u8);
+	assert_eq!(header.ptr_width, 4);
+	assert_eq!(header.endianness, 0);
+	assert_eq!(header.root_pos, pos as u64);
+
+	// Offsets are relative to start of output, including header
+	let mut root = Reader::new(output, 4).seek(pos);
+	let (vec_pos, vec_len) = (root.offset(), root.offset());
+	assert_eq!(vec_len, 2);
+	let mut names = root.at(vec_pos);
+	assert_eq!(names.string(), "Alfred");
+	assert_eq!(names.string(), "Gertrude");
+
+	// Different offset size
+	assert_eq!(
+		header::check::<Foo, Ser>(output),
+		Err(HeaderError::WrongSerializer)
+	);
+
+	// Root value out of bounds
+	assert_eq!(
+		header::check::<Foo, Ser32>(&output[..pos + 7]),
+		Err(HeaderError::RootPosOutOfBounds)
+	);
+	assert_eq!(header::check::<Foo, Ser32>(&output[..pos + 8]), Ok(pos));
+}
+
+#[test]
+fn storage_pos() {
+	let mut ser = Ser::new();
+	let pos1 = ser.serialize_value(&(1u8, 2u32));
+	let pos2 = ser.serialize_value(&"ab".to_string());
+	let storage = ser.finalize();
+	assert_eq!((pos1, pos2), (0, 5));
+	assert_eq!(storage.pos(), 23);
+}
@@ -1,11 +1,13 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, ptr};
 
 mod common;
 use common::{generate_minecraft_data, tests, Test};
 use ser_raw::{
-	storage::{AlignedVec, Storage},
+	read::OffsetRef,
+	ser_traits::PtrWriting,
+	storage::{AlignedVec, RandomAccessStorage, Storage},
 	util::aligned_max_capacity,
-	PtrOffsetSerializer, Serialize, Serializer,
+	Deserialize, PtrOffsetSerializer, Serialize, Serializer, Validate,
 };
 
 // NB: Cannot easily test for error if try to serialize a type with alignment
@@ -24,8 +26,8 @@ fn test_serialize<T>(input: &T, test: Test, test_num: usize)
 where T: Serialize<Ser> + Debug + PartialEq {
 	let (pos, storage) = serialize(input);
 
-	// No deserializer, so can't test output. Just testing length of output for now.
-	// TODO: Test for correct relative pointers.
+	// Output is not type-checked here, just its length.
+	// Contents of output are tested with `OffsetRef` in tests below.
 	let expected_size = match test {
 		Test::Primitives => 96,
 		Test::NonZeroNumbers => 80,
@@ -49,7 +51,13 @@ where T: Serialize<Ser> + Debug + PartialEq {
 		Test::StringsWithZeroLenExcessCapacity2 => 24,
 		Test::StringsWithExcessCapacity => 32,
 		Test::StringsWithExcessCapacity2 => 32,
+		Test::BoxedSlices => 192,
 		Test::Options => [72, 72, 104, 96][test_num],
+		Test::Wrappers => 168,
+		Test::Ranges => 96,
+		Test::Locks => 88,
+		Test::Atomics => 24,
+		Test::Cows => 320,
 		Test::BigUint => 152,
 		Test::BigInt => 336,
 		Test::StructureWhereStorageGrowsAfterLastPointerWritten => 136,
@@ -61,3 +69,289 @@ where T: Serialize<Ser> + Debug + PartialEq {
 }
 
 tests!(test_serialize);
+
+/// Get `OffsetRef` for a value of same type as `_value`.
+/// Allows getting an `OffsetRef` for types which can't be named.
+unsafe fn offset_ref_for<'a, T>(
+	_value: &T,
+	storage: &'a AlignedVec,
+	pos: usize,
+) -> OffsetRef<'a, T> {
+	OffsetRef::new(storage, pos)
+}
+
+#[test]
+fn read_boxes() {
+	let input = Box::new(Box::new(123u32));
+	let (pos, storage) = serialize(&input);
+
+	let boxed = unsafe { OffsetRef::<Box<Box<u32>>>::new(&storage, pos) };
+	let inner = boxed.as_box().get().as_box().get();
+	assert_eq!(unsafe { *inner.get() }, 123);
+
+	// Zero-sized types are not written to output
+	let input = Box::new(());
+	let (pos, storage) = serialize(&input);
+	let boxed = unsafe { OffsetRef::<Box<()>>::new(&storage, pos) };
+	assert_eq!(unsafe { boxed.as_box().get().get() }, &());
+}
+
+#[test]
+fn read_vecs() {
+	let input: Vec<Vec<u16>> = vec![vec![1, 2, 3], vec![], Vec::with_capacity(10), vec![4]];
+	let (pos, storage) = serialize(&input);
+
+	let outer = unsafe { OffsetRef::<Vec<Vec<u16>>>::new(&storage, pos) }.as_vec();
+	assert_eq!(outer.len(), 4);
+	let inners = outer
+		.iter()
+		.map(|inner| unsafe { inner.as_vec().as_slice() }.to_vec())
+		.collect::<Vec<_>>();
+	assert_eq!(inners, input);
+	assert!(outer.get(4).is_none());
+}
+
+#[test]
+fn read_strings() {
+	let input = vec![
+		"".to_string(),
+		"abc".to_string(),
+		String::with_capacity(8),
+		"\u{1F600} long string which does not fit in a single alignment block".to_string(),
+	];
+	let (pos, storage) = serialize(&input);
+
+	let strs = unsafe { OffsetRef::<Vec<String>>::new(&storage, pos) }.as_vec();
+	let output = strs
+		.iter()
+		.map(|s| s.as_string().to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(output, input);
+}
+
+#[test]
+fn read_boxed_slices() {
+	let input: Box<[Box<[u16]>]> = vec![
+		vec![1, 2, 3].into_boxed_slice(),
+		Box::new([]),
+		vec![4].into_boxed_slice(),
+	]
+	.into_boxed_slice();
+	let (pos, storage) = serialize(&input);
+
+	let outer = unsafe { OffsetRef::<Box<[Box<[u16]>]>>::new(&storage, pos) }.as_slice();
+	assert_eq!(outer.len(), 3);
+	let inners = outer
+		.iter()
+		.map(|inner| unsafe { inner.as_slice().as_slice() }.into())
+		.collect::<Vec<Box<[u16]>>>();
+	assert_eq!(inners, input.into_vec());
+
+	let input: Box<[Box<str>]> = vec!["".into(), "abc".into()].into_boxed_slice();
+	let (pos, storage) = serialize(&input);
+
+	let strs = unsafe { OffsetRef::<Box<[Box<str>]>>::new(&storage, pos) }.as_slice();
+	let output = strs
+		.iter()
+		.map(|s| s.as_str().to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(output, vec!["", "abc"]);
+}
+
+#[test]
+fn read_options() {
+	let input = vec![None, Some(Box::new(1u8)), None, Some(Box::new(2u8))];
+	let (pos, storage) = serialize(&input);
+
+	let options = unsafe { OffsetRef::<Vec<Option<Box<u8>>>>::new(&storage, pos) }.as_vec();
+	let output = options
+		.iter()
+		.map(|option| {
+			option
+				.as_option()
+				.map(|boxed| unsafe { *boxed.as_box().get().get() })
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(output, vec![None, Some(1), None, Some(2)]);
+}
+
+#[test]
+fn read_options_with_tags_and_niches() {
+	#[derive(Serialize, Validate)]
+	struct Foo {
+		num: Option<u32>,
+		vec: Option<Vec<u16>>,
+		string: Option<String>,
+	}
+
+	let input = vec![
+		Foo {
+			num: Some(1),
+			vec: Some(vec![]),
+			string: None,
+		},
+		Foo {
+			num: None,
+			vec: Some(vec![2, 3]),
+			string: Some("abc".to_string()),
+		},
+		Foo {
+			num: Some(0),
+			vec: None,
+			string: Some(String::new()),
+		},
+	];
+	let (pos, storage) = serialize(&input);
+
+	let values = unsafe { OffsetRef::<Vec<Foo>>::new(&storage, pos) }.as_vec();
+	let output = values
+		.iter()
+		.map(|value| {
+			unsafe {
+				(
+					value
+						.field(|value| ptr::addr_of!((*value).num))
+						.as_option()
+						.map(|num| *num.get()),
+					value
+						.field(|value| ptr::addr_of!((*value).vec))
+						.as_option()
+						.map(|vec| vec.as_vec().as_slice().to_vec()),
+					value
+						.field(|value| ptr::addr_of!((*value).string))
+						.as_option()
+						.map(|string| string.as_string().to_string()),
+				)
+			}
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(
+		output,
+		vec![
+			(Some(1), Some(vec![]), None),
+			(None, Some(vec![2, 3]), Some("abc".to_string())),
+			(Some(0), None, Some(String::new())),
+		]
+	);
+}
+
+#[test]
+#[should_panic(expected = "Cannot write pointer to start of output")]
+fn pointer_to_start_of_output_panics() {
+	// Offset 0 would be indistinguishable from a null pointer
+	let mut ser = Ser::new();
+	ser.serialize_value(&Box::new(1u64));
+	unsafe { ser.overwrite_ptr(0, 0) };
+}
+
+#[test]
+fn read_structs_and_enums() {
+	#[derive(Serialize)]
+	struct Foo {
+		id: u32,
+		name: String,
+		bars: Vec<Bar>,
+	}
+
+	#[derive(Serialize)]
+	#[repr(u8)]
+	enum Bar {
+		Empty,
+		Num(Box<u64>),
+		Named { name: String },
+	}
+
+	// Layouts of `Bar`'s variants with fields
+	#[repr(C)]
+	struct BarNum {
+		tag: u8,
+		num: Box<u64>,
+	}
+
+	#[repr(C)]
+	struct BarNamed {
+		tag: u8,
+		name: String,
+	}
+
+	let input = Foo {
+		id: 42,
+		name: "foo".to_string(),
+		bars: vec![
+			Bar::Num(Box::new(1)),
+			Bar::Empty,
+			Bar::Named {
+				name: "bar".to_string(),
+			},
+		],
+	};
+	let (pos, storage) = serialize(&input);
+
+	let output = unsafe { OffsetRef::<Foo>::new(&storage, pos) };
+	assert_eq!(
+		unsafe { *output.field(|value| ptr::addr_of!((*value).id)).get() },
+		42
+	);
+	assert_eq!(
+		unsafe { output.field(|value| ptr::addr_of!((*value).name)) }
+			.as_string()
+			.as_str(),
+		"foo"
+	);
+
+	let bars = unsafe { output.field(|value| ptr::addr_of!((*value).bars)) }.as_vec();
+	assert_eq!(bars.len(), 3);
+
+	fn num(bar: OffsetRef<Bar>) -> Option<OffsetRef<Box<u64>>> {
+		unsafe {
+			bar.variant(|bar| {
+				matches!(*bar, Bar::Num(..)).then(|| ptr::addr_of!((*bar.cast::<BarNum>()).num))
+			})
+		}
+	}
+	fn name(bar: OffsetRef<Bar>) -> Option<OffsetRef<String>> {
+		unsafe {
+			bar.variant(|bar| {
+				matches!(*bar, Bar::Named { .. }).then(|| ptr::addr_of!((*bar.cast::<BarNamed>()).name))
+			})
+		}
+	}
+
+	let bar = bars.get(0).unwrap();
+	assert_eq!(unsafe { *num(bar).unwrap().as_box().get().get() }, 1);
+	assert!(name(bar).is_none());
+
+	let bar = bars.get(1).unwrap();
+	assert!(num(bar).is_none());
+	assert!(name(bar).is_none());
+
+	let bar = bars.get(2).unwrap();
+	assert!(num(bar).is_none());
+	assert_eq!(name(bar).unwrap().as_string().as_str(), "bar");
+}
+
+#[test]
+fn read_minecraft_data() {
+	let input = generate_minecraft_data();
+	let (pos, storage) = serialize(&input);
+
+	let output = unsafe { offset_ref_for(&input, &storage, pos) };
+	assert_eq!(output.pos(), 0);
+	let players = unsafe { output.field(|data| ptr::addr_of!((*data).players)) }.as_vec();
+	assert_eq!(players.len(), input.players.len());
+	for (player, input_player) in players.iter().zip(&input.players) {
+		let game_type = unsafe { player.field(|player| ptr::addr_of!((*player).game_type)) };
+		assert_eq!(unsafe { game_type.get() }, &input_player.game_type);
+	}
+}
+
+#[test]
+#[should_panic(expected = "Offset out of bounds")]
+fn read_out_of_bounds_offset_panics() {
+	let input = Box::new(1u64);
+	let (pos, mut storage) = serialize(&input);
+	unsafe { storage.write(pos, &1024usize) };
+
+	let boxed = unsafe { OffsetRef::<Box<u64>>::new(&storage, pos) };
+	boxed.as_box();
+}
@@ -0,0 +1,171 @@
+use std::{fmt::Debug, mem, ptr};
+
+mod common;
+use common::{generate_minecraft_data, tests, Test};
+use ser_raw::{
+	read::OffsetRef,
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_u32_capacity,
+	Deserialize, PtrOffset32Serializer, PtrOffsetSerializer, Serialize, Serializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_u32_capacity(16);
+type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+type Ser = PtrOffset32Serializer<16, 16, 8, MAX_CAPACITY, Store>;
+type UsizeSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+
+fn serialize<T: Serialize<Ser>>(value: &T) -> (usize, Store) {
+	let ser = Ser::new();
+	ser.serialize(value)
+}
+
+fn test_serialize<T>(input: &T, _test: Test, _test_num: usize)
+where T: Serialize<Ser> + Serialize<UsizeSer> + Debug + PartialEq {
+	let (pos, storage) = serialize(input);
+	let (usize_pos, usize_storage) = UsizeSer::new().serialize(input);
+
+	// Layout of output is same as `PtrOffsetSerializer`'s, only offsets differ.
+	// Contents of output are tested with `OffsetRef` in tests below.
+	assert_eq!(pos, usize_pos);
+	assert_eq!(storage.pos(), usize_storage.pos());
+}
+
+tests!(test_serialize);
+
+/// Get `OffsetRef` for a value of same type as `_value`.
+/// Allows getting an `OffsetRef` for types which can't be named.
+unsafe fn offset_ref_for<'a, T>(
+	_value: &T,
+	storage: &'a Store,
+	pos: usize,
+) -> OffsetRef<'a, T, u32> {
+	OffsetRef::new(storage, pos)
+}
+
+#[test]
+fn offsets_are_u32() {
+	let input = (Box::new(0x0102u16), vec![1u8, 2, 3], "abc".to_string());
+	let (pos, storage) = serialize(&input);
+
+	let ptr_size = mem::size_of::<usize>();
+	let bytes = storage.as_slice();
+	let offset_at = |ptr_pos: usize| {
+		// Rest of pointer is zeroed
+		assert!(bytes[ptr_pos + 4..ptr_pos + ptr_size]
+			.iter()
+			.all(|&byte| byte == 0));
+		let offset: u32 = unsafe { *storage.read(ptr_pos) };
+		offset as usize
+	};
+
+	let output = unsafe { offset_ref_for(&input, &storage, pos) };
+	let boxed = unsafe { output.field(|value| ptr::addr_of!((*value).0)) };
+	let offset = offset_at(boxed.pos());
+	assert_eq!(unsafe { *storage.read::<u16>(offset) }, 0x0102);
+	assert_eq!(unsafe { *boxed.as_box().get().get() }, 0x0102);
+
+	let vec = unsafe { output.field(|value| ptr::addr_of!((*value).1)) }.as_vec();
+	assert_eq!(unsafe { vec.as_slice() }, &[1, 2, 3]);
+
+	let str = unsafe { output.field(|value| ptr::addr_of!((*value).2)) }.as_string();
+	assert_eq!(str.as_str(), "abc");
+}
+
+#[test]
+fn read_options() {
+	let input = vec![Some(Box::new(1u8)), None, Some(Box::new(2u8))];
+	let (pos, storage) = serialize(&input);
+
+	let options = unsafe { OffsetRef::<Vec<Option<Box<u8>>>, u32>::new(&storage, pos) }.as_vec();
+	let output = options
+		.iter()
+		.map(|option| {
+			option
+				.as_option()
+				.map(|boxed| unsafe { *boxed.as_box().get().get() })
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(output, vec![Some(1), None, Some(2)]);
+}
+
+#[test]
+fn read_structs_and_enums() {
+	#[derive(Serialize)]
+	struct Foo {
+		id: u32,
+		names: Vec<String>,
+		bars: Vec<Bar>,
+	}
+
+	#[derive(Serialize)]
+	#[repr(u8)]
+	enum Bar {
+		Empty,
+		Num(Box<u64>),
+	}
+
+	// Layout of `Bar::Num` variant
+	#[repr(C)]
+	struct BarNum {
+		tag: u8,
+		num: Box<u64>,
+	}
+
+	let input = Foo {
+		id: 42,
+		names: vec!["abc".to_string(), "".to_string(), "def".to_string()],
+		bars: vec![Bar::Num(Box::new(1)), Bar::Empty],
+	};
+	let (pos, storage) = serialize(&input);
+
+	let output = unsafe { OffsetRef::<Foo, u32>::new(&storage, pos) };
+	assert_eq!(
+		unsafe { *output.field(|value| ptr::addr_of!((*value).id)).get() },
+		42
+	);
+
+	let names = unsafe { output.field(|value| ptr::addr_of!((*value).names)) }.as_vec();
+	let names = names
+		.iter()
+		.map(|name| name.as_string().to_string())
+		.collect::<Vec<_>>();
+	assert_eq!(names, input.names);
+
+	let bars = unsafe { output.field(|value| ptr::addr_of!((*value).bars)) }.as_vec();
+	assert_eq!(bars.len(), 2);
+	fn num(bar: OffsetRef<Bar, u32>) -> Option<OffsetRef<Box<u64>, u32>> {
+		unsafe {
+			bar.variant(|bar| {
+				matches!(*bar, Bar::Num(..)).then(|| ptr::addr_of!((*bar.cast::<BarNum>()).num))
+			})
+		}
+	}
+	let first = num(bars.get(0).unwrap()).unwrap();
+	assert_eq!(unsafe { *first.as_box().get().get() }, 1);
+	assert!(num(bars.get(1).unwrap()).is_none());
+}
+
+#[test]
+fn read_minecraft_data() {
+	let input = generate_minecraft_data();
+	let (pos, storage) = serialize(&input);
+
+	let output = unsafe { offset_ref_for(&input, &storage, pos) };
+	let players = unsafe { output.field(|data| ptr::addr_of!((*data).players)) }.as_vec();
+	assert_eq!(players.len(), input.players.len());
+	for (player, input_player) in players.iter().zip(&input.players) {
+		let game_type = unsafe { player.field(|player| ptr::addr_of!((*player).game_type)) };
+		assert_eq!(unsafe { game_type.get() }, &input_player.game_type);
+	}
+}
+
+#[test]
+#[should_panic(expected = "Offset out of bounds")]
+fn read_out_of_bounds_offset_panics() {
+	let input = Box::new(1u64);
+	let (pos, mut storage) = serialize(&input);
+	unsafe { storage.write(pos, &1024u32) };
+
+	let boxed = unsafe { OffsetRef::<Box<u64>, u32>::new(&storage, pos) };
+	boxed.as_box();
+}
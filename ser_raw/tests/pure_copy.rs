@@ -3,9 +3,9 @@ use std::fmt::Debug;
 mod common;
 use common::{generate_minecraft_data, tests, Test};
 use ser_raw::{
-	storage::{AlignedVec, Storage},
+	storage::{AlignedVec, ContiguousStorage, Storage},
 	util::aligned_max_capacity,
-	PureCopySerializer, Serialize, Serializer,
+	Deserialize, Deserializer, PureCopyDeserializer, PureCopySerializer, Serialize, Serializer,
 };
 
 // NB: Cannot easily test for error if try to serialize a type with alignment
@@ -14,6 +14,7 @@ use ser_raw::{
 
 const MAX_CAPACITY: usize = aligned_max_capacity(16);
 type Ser = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type De<'a> = PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>;
 
 fn serialize<T: Serialize<Ser>>(value: &T) -> (usize, AlignedVec) {
 	let ser = Ser::new();
@@ -21,10 +22,10 @@ fn serialize<T: Serialize<Ser>>(value: &T) -> (usize, AlignedVec) {
 }
 
 fn test_serialize<T>(input: &T, test: Test, test_num: usize)
-where T: Serialize<Ser> + Debug + PartialEq {
+where T: Serialize<Ser> + for<'a> Deserialize<De<'a>> + Debug + PartialEq {
 	let (pos, storage) = serialize(input);
 
-	// No deserializer, so can't test output. Just testing length of output for now.
+	// Test length of output
 	let expected_size = match test {
 		Test::Primitives => 96,
 		Test::NonZeroNumbers => 80,
@@ -48,7 +49,13 @@ where T: Serialize<Ser> + Debug + PartialEq {
 		Test::StringsWithZeroLenExcessCapacity2 => 24,
 		Test::StringsWithExcessCapacity => 32,
 		Test::StringsWithExcessCapacity2 => 32,
+		Test::BoxedSlices => 192,
 		Test::Options => [72, 72, 104, 96][test_num],
+		Test::Wrappers => 168,
+		Test::Ranges => 96,
+		Test::Locks => unreachable!(),
+		Test::Atomics => 24,
+		Test::Cows => 320,
 		Test::BigUint => 152,
 		Test::BigInt => 336,
 		Test::StructureWhereStorageGrowsAfterLastPointerWritten => 136,
@@ -57,6 +64,31 @@ where T: Serialize<Ser> + Debug + PartialEq {
 
 	assert_eq!(pos, 0);
 	assert_eq!(storage.pos(), expected_size);
+
+	// Test deserializing output recreates input
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output: T = de.deserialize_value();
+	assert_eq!(&output, input);
+	assert_eq!(de.pos(), expected_size);
 }
 
-tests!(test_serialize);
+tests!(test_serialize, no_locks);
+
+#[cfg(unix)]
+#[test]
+fn boxed_paths() {
+	use std::path::Path;
+
+	let input: Vec<Box<Path>> = vec![
+		Path::new("/usr/local/bin").into(),
+		Path::new("").into(),
+		Path::new("relative/file.txt").into(),
+	];
+	let (pos, storage) = serialize(&input);
+	assert_eq!(pos, 0);
+
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output: Vec<Box<Path>> = de.deserialize_value();
+	assert_eq!(output, input);
+	assert_eq!(de.pos(), storage.pos());
+}
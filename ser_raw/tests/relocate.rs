@@ -0,0 +1,155 @@
+use std::{fmt::Debug, mem};
+
+mod common;
+use common::{generate_minecraft_data, tests, Test};
+use ser_raw::{
+	relocate::{self, rebase, RelocateError, Relocations},
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	CompleteSerializer, Deserialize, Serialize, Serializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+const PTR_SIZE: usize = mem::size_of::<usize>();
+type Ser = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+
+/// Copy storage to a new buffer
+fn copy(storage: &AlignedVec) -> AlignedVec {
+	let mut copy = AlignedVec::with_capacity(storage.pos());
+	copy.push_bytes(storage.as_slice());
+	assert_ne!(copy.as_ptr(), storage.as_ptr());
+	copy
+}
+
+fn test_serialize<T>(input: &T, _test: Test, _test_num: usize)
+where T: Serialize<Ser> + Debug + PartialEq {
+	test_relocations(input);
+	test_relocation_table(input);
+}
+
+fn test_relocations<T>(input: &T)
+where T: Serialize<Ser> + Debug + PartialEq {
+	let (pos, storage, mut relocations) = Ser::new().serialize_with_relocations(input);
+	assert_eq!(relocations.base_addr(), storage.as_ptr() as usize);
+
+	let mut copied = copy(&storage);
+	drop(storage);
+
+	// Rebase to 0, and then to actual address of copy
+	relocations.rebase(copied.as_mut_slice(), 0).unwrap();
+	assert_eq!(relocations.base_addr(), 0);
+	let new_base = copied.as_ptr() as usize;
+	relocations.rebase(copied.as_mut_slice(), new_base).unwrap();
+	assert_eq!(relocations.base_addr(), new_base);
+
+	let output: &T = unsafe { copied.read(pos) };
+	assert_eq!(input, output);
+}
+
+fn test_relocation_table<T>(input: &T)
+where T: Serialize<Ser> + Debug + PartialEq {
+	let (pos, storage) = Ser::new().serialize_with_relocation_table(input);
+	let (_, expected_data_len) = Ser::new().serialize(input);
+
+	let (relocations, data_len) = relocate::read_table(storage.as_slice()).unwrap();
+	assert_eq!(relocations.base_addr(), storage.as_ptr() as usize);
+	assert_eq!(data_len, expected_data_len.pos());
+
+	let mut copied = copy(&storage);
+	drop(storage);
+
+	let new_base = copied.as_ptr() as usize;
+	let data_len = rebase(copied.as_mut_slice(), new_base).unwrap();
+	assert_eq!(data_len, expected_data_len.pos());
+
+	// Table records new base address
+	let (relocations, _) = relocate::read_table(copied.as_slice()).unwrap();
+	assert_eq!(relocations.base_addr(), new_base);
+
+	let output: &T = unsafe { copied.read(pos) };
+	assert_eq!(input, output);
+}
+
+tests!(test_serialize);
+
+#[test]
+fn relocations_record_all_ptrs() {
+	#[derive(Serialize)]
+	#[allow(clippy::vec_box)]
+	struct Foo {
+		boxed: Box<u32>,
+		empty_vec: Vec<u32>,
+		vec: Vec<Box<u8>>,
+		string: String,
+	}
+
+	let input = Foo {
+		boxed: Box::new(1),
+		empty_vec: vec![],
+		vec: vec![Box::new(2), Box::new(3)],
+		string: "abc".to_string(),
+	};
+
+	let (_, storage, relocations) = Ser::new().serialize_with_relocations(&input);
+	let base_addr = storage.as_ptr() as usize;
+
+	// `boxed`, `vec`, 2 x boxes in `vec`, and `string`.
+	// Empty `Vec` has a dangling pointer, which is not relocated.
+	let ptr_positions = relocations.ptr_positions();
+	assert_eq!(ptr_positions.len(), 5);
+
+	// All pointers point within storage
+	for &pos in ptr_positions {
+		let ptr = unsafe { *storage.read::<usize>(pos) };
+		assert!(ptr >= base_addr && ptr < base_addr + storage.pos());
+	}
+}
+
+#[test]
+fn rebase_out_of_bounds() {
+	let input = vec![Box::new(1u32)];
+	let (_, storage, mut relocations) = Ser::new().serialize_with_relocations(&input);
+	let mut copied = copy(&storage);
+	let bytes_before = copied.as_slice().to_vec();
+
+	let len = PTR_SIZE * 3;
+	let result = relocations.rebase(&mut copied.as_mut_slice()[..len], 0);
+	assert!(matches!(result, Err(RelocateError::PtrOutOfBounds { .. })));
+
+	// Buffer and relocations are unchanged
+	assert_eq!(copied.as_slice(), bytes_before.as_slice());
+	assert_eq!(relocations.base_addr(), storage.as_ptr() as usize);
+
+	let mut relocations = Relocations::new(0, vec![len - 1]);
+	let result = relocations.rebase(&mut copied.as_mut_slice()[..len], 0);
+	assert_eq!(result, Err(RelocateError::PtrOutOfBounds { pos: len - 1 }));
+}
+
+#[test]
+fn rebase_invalid_table() {
+	// Too short for trailer
+	let mut bytes = vec![0u8; PTR_SIZE * 2];
+	assert_eq!(rebase(&mut bytes, 0), Err(RelocateError::InvalidTable));
+
+	// Pointer count too large
+	let (_, storage) = Ser::new().serialize_with_relocation_table(&Box::new(1u8));
+	let mut bytes = storage.as_slice().to_vec();
+	let len = bytes.len();
+	bytes[len - PTR_SIZE..].copy_from_slice(&usize::MAX.to_ne_bytes());
+	assert_eq!(rebase(&mut bytes, 0), Err(RelocateError::InvalidTable));
+
+	// Data length too large
+	let mut bytes = storage.as_slice().to_vec();
+	bytes[len - PTR_SIZE * 2..len - PTR_SIZE].copy_from_slice(&len.to_ne_bytes());
+	assert_eq!(rebase(&mut bytes, 0), Err(RelocateError::InvalidTable));
+
+	// Pointer position outside data
+	let mut bytes = storage.as_slice().to_vec();
+	bytes[len - PTR_SIZE * 4..len - PTR_SIZE * 3].copy_from_slice(&(len - PTR_SIZE).to_ne_bytes());
+	let bytes_before = bytes.clone();
+	assert!(matches!(
+		rebase(&mut bytes, 0),
+		Err(RelocateError::PtrOutOfBounds { .. })
+	));
+	assert_eq!(bytes, bytes_before);
+}
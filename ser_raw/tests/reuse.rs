@@ -0,0 +1,232 @@
+use std::{ptr, rc::Rc, thread};
+
+use ser_raw::{
+	read::OffsetRef,
+	ser_traits::Complete,
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, PtrOffsetSerializer, PureCopySerializer, Serialize, Serializer,
+	SerializerPool, Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+struct Module {
+	name: String,
+	items: Vec<Item>,
+}
+
+#[derive(Serialize, Validate, Debug, PartialEq)]
+#[repr(u8)]
+enum Item {
+	Const(u64),
+	Func { name: String, body: Vec<Item> },
+}
+
+fn create_module(size: usize) -> Module {
+	Module {
+		name: format!("module_{size}"),
+		items: (0..size)
+			.map(|i| {
+				if i % 2 == 0 {
+					Item::Const(i as u64)
+				} else {
+					Item::Func {
+						name: format!("func_{i}"),
+						body: (0..(i % 5) as u64).map(Item::Const).collect(),
+					}
+				}
+			})
+			.collect(),
+	}
+}
+
+/// Get positions of pointers a fresh `CompleteSerializer` records for `input`
+fn fresh_ptr_positions(input: &Module) -> Vec<usize> {
+	let mut ser = CompleteSer::new();
+	ser.serialize_value(input);
+	ser.ptr_positions()
+}
+
+#[test]
+fn complete_reset_discards_stale_ptrs() {
+	let mut ser = CompleteSer::new();
+
+	// Storage grows and moves several times during first serialization,
+	// creating multiple pointer groups
+	let big = create_module(1000);
+	let pos = ser.serialize_in_place(&big);
+	assert!(!ser.ptrs().unwrap().past.is_empty());
+	let output: &Module = validate(ser.storage().as_slice(), pos).unwrap();
+	assert_eq!(output, &big);
+
+	let capacity = ser.capacity();
+	let storage_ptr = ser.storage().as_ptr();
+
+	// Second serialization reuses memory. If pointer groups from first
+	// serialization were not discarded, correcting them would corrupt output.
+	for size in [10, 1, 100, 0] {
+		let small = create_module(size);
+		let pos = ser.serialize_in_place(&small);
+		assert_eq!(pos, 0);
+		assert_eq!(ser.capacity(), capacity);
+		assert_eq!(ser.storage().as_ptr(), storage_ptr);
+		assert!(ser.ptrs().unwrap().past.is_empty());
+		assert_eq!(ser.ptr_positions(), fresh_ptr_positions(&small));
+
+		let output: &Module = validate(ser.storage().as_slice(), pos).unwrap();
+		assert_eq!(output, &small);
+	}
+}
+
+#[test]
+fn complete_reset_after_abandoned_serialization() {
+	let mut ser = CompleteSer::new();
+
+	// Serialize without finalizing, then reset
+	ser.serialize_value(&create_module(500));
+	ser.reset();
+	assert_eq!(ser.pos(), 0);
+	assert!(ser.ptr_positions().is_empty());
+
+	let input = create_module(20);
+	let pos = ser.serialize_in_place(&input);
+	assert_eq!(ser.ptr_positions(), fresh_ptr_positions(&input));
+	let output: &Module = validate(ser.storage().as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn complete_finalize_in_place_twice() {
+	let mut ser = CompleteSer::new();
+	let first = create_module(200);
+	let pos1 = ser.serialize_value(&first);
+	ser.finalize_in_place();
+	ser.finalize_in_place();
+	let output: &Module = unsafe { ser.storage().read(pos1) };
+	assert_eq!(output, &first);
+
+	// Serialization can continue after finalizing in place.
+	// Storage grows, so pointers in first value need correcting again.
+	let second = create_module(2000);
+	let pos2 = ser.serialize_value(&second);
+	let storage = ser.finalize();
+	let output: &Module = unsafe { storage.read(pos1) };
+	assert_eq!(output, &first);
+	let output: &Module = unsafe { storage.read(pos2) };
+	assert_eq!(output, &second);
+}
+
+#[test]
+fn reset_clears_shared_allocs() {
+	let shared = Rc::new(0x01020304u32);
+	let input = vec![shared.clone(), shared];
+
+	// If record of shared allocations was not cleared, 2nd serialization would
+	// point to allocation written in 1st, and not write it again
+	let mut ser = PureCopySer::new();
+	ser.serialize_in_place(&input);
+	let len = ser.pos();
+	ser.serialize_in_place(&input);
+	assert_eq!(ser.pos(), len);
+	assert!(ser.shared_allocs().unwrap().len() == 1);
+
+	let mut ser = CompleteSer::new();
+	for _ in 0..3 {
+		let pos = ser.serialize_in_place(&input);
+		let output: &Vec<Rc<u32>> = unsafe { ser.storage().read(pos) };
+		assert_eq!(*output[0], 0x01020304);
+		assert!(ptr::eq(&*output[0], &*output[1]));
+		// Ref count is number of references in output
+		assert_eq!(Rc::strong_count(&output[0]), 2);
+	}
+}
+
+#[test]
+fn ptr_offset_reuse() {
+	let mut ser = PtrOffsetSer::new();
+	ser.serialize_in_place(&create_module(1000));
+
+	for size in [0, 3, 50] {
+		let input = create_module(size);
+		let pos = ser.serialize_in_place(&input);
+		let (_, expected) = PtrOffsetSer::new().serialize(&input);
+		assert_eq!(ser.pos(), expected.pos());
+
+		let output = unsafe { OffsetRef::<Module>::new(ser.storage(), pos) };
+		let name = unsafe { output.field(|module| ptr::addr_of!((*module).name)) }.as_string();
+		assert_eq!(name.as_str(), input.name);
+		let items = unsafe { output.field(|module| ptr::addr_of!((*module).items)) }.as_vec();
+		assert_eq!(items.len(), size);
+	}
+}
+
+#[test]
+fn pool_reuses_serializers() {
+	let pool = SerializerPool::with_size(2, || CompleteSer::with_capacity(64));
+	assert_eq!(pool.idle(), 2);
+
+	let input = create_module(100);
+	let storage_ptr = {
+		let mut ser = pool.get();
+		assert_eq!(pool.idle(), 1);
+		let pos = ser.serialize_in_place(&input);
+		let output: &Module = validate(ser.storage().as_slice(), pos).unwrap();
+		assert_eq!(output, &input);
+		ser.storage().as_ptr()
+	};
+	assert_eq!(pool.idle(), 2);
+
+	// Same serializer is returned, reset, with memory retained
+	let mut ser = pool.get();
+	assert_eq!(ser.storage().as_ptr(), storage_ptr);
+	assert_eq!(ser.pos(), 0);
+	assert!(ser.ptr_positions().is_empty());
+	let small = create_module(3);
+	let pos = ser.serialize_in_place(&small);
+	let output: &Module = validate(ser.storage().as_slice(), pos).unwrap();
+	assert_eq!(output, &small);
+
+	// Pool creates a new serializer when empty
+	let ser2 = pool.get();
+	let ser3 = pool.get();
+	assert_eq!(pool.idle(), 0);
+	assert_eq!(ser3.capacity(), 64);
+	drop((ser, ser2, ser3));
+	assert_eq!(pool.idle(), 3);
+
+	// Detached serializer is not returned to pool
+	let mut ser = pool.get().detach();
+	assert_eq!(pool.idle(), 2);
+	let pos = ser.serialize_value(&small);
+	let storage = ser.finalize();
+	let output: &Module = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &small);
+	assert_eq!(pool.idle(), 2);
+}
+
+#[test]
+fn pool_shared_between_threads() {
+	let pool = SerializerPool::with_size(4, CompleteSer::new);
+
+	thread::scope(|scope| {
+		for thread_num in 0..4 {
+			let pool = &pool;
+			scope.spawn(move || {
+				for i in 0..50 {
+					let input = create_module((thread_num * 50 + i) % 120);
+					let mut ser = pool.get();
+					let pos = ser.serialize_in_place(&input);
+					let output: &Module = validate(ser.storage().as_slice(), pos).unwrap();
+					assert_eq!(output, &input);
+				}
+			});
+		}
+	});
+
+	assert!(pool.idle() >= 4);
+}
@@ -0,0 +1,147 @@
+use std::ptr;
+
+use ser_raw::{
+	niche::Niche,
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, Deserialize, DeserializeWith, Deserializer, PortableSerializer,
+	PureCopyDeserializer, PureCopySerializer, Serialize, SerializeWith, Serializer, Validate,
+	ValidateError, ValidateWith, Validator,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 8, PortableStore>;
+type De<'a> = PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>;
+
+/// Stands in for a type from another crate, which doesn't implement `Serialize`
+#[derive(Debug, PartialEq)]
+struct Name(String);
+
+struct NameProxy;
+
+impl<S: Serializer> SerializeWith<Name, S> for NameProxy {
+	const PORTABLE_SIZE: usize = <String as Serialize<S>>::PORTABLE_SIZE;
+
+	fn serialize_data_with(name: &Name, serializer: &mut S) {
+		name.0.serialize_data(serializer);
+	}
+
+	fn serialize_portable_with(name: &Name, serializer: &mut S, pos: usize) {
+		name.0.serialize_portable(serializer, pos);
+	}
+}
+
+impl<D: Deserializer> DeserializeWith<Name, D> for NameProxy {
+	unsafe fn deserialize_data_with(name: &mut Name, deserializer: &mut D) {
+		name.0.deserialize_data(deserializer);
+	}
+}
+
+unsafe impl ValidateWith<Name> for NameProxy {
+	const NICHE: Option<Niche> = <String as Validate>::NICHE;
+
+	unsafe fn validate_with(
+		ptr: *const Name,
+		validator: &mut Validator,
+	) -> Result<(), ValidateError> {
+		String::validate(ptr::addr_of!((*ptr).0), validator)
+	}
+}
+
+#[derive(Serialize, Deserialize, Validate, Debug, PartialEq)]
+#[repr(u8)]
+enum Token {
+	Empty,
+	Ident(#[ser_with(NameProxy)] Name, u32),
+	Keyword {
+		id: u16,
+		#[ser_with(NameProxy)]
+		name: Name,
+	},
+}
+
+#[derive(Serialize, Deserialize, Validate, Debug, PartialEq)]
+struct Tokens {
+	#[ser_with(NameProxy)]
+	file: Name,
+	tokens: Vec<Token>,
+}
+
+fn create_tokens() -> Tokens {
+	Tokens {
+		file: Name("main.rs".to_string()),
+		tokens: vec![
+			Token::Keyword {
+				id: 1,
+				name: Name("fn".to_string()),
+			},
+			Token::Ident(Name("main".to_string()), 2),
+			Token::Empty,
+			Token::Ident(Name("".to_string()), 3),
+		],
+	}
+}
+
+#[test]
+fn pure_copy_round_trip() {
+	let input = create_tokens();
+	let (pos, storage) = PureCopySer::new().serialize(&input);
+	assert_eq!(pos, 0);
+
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output: Tokens = de.deserialize_value();
+	assert_eq!(de.pos(), storage.pos());
+	assert_eq!(output, input);
+}
+
+#[test]
+fn complete_and_validate() {
+	let input = create_tokens();
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+
+	let output: &Tokens = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+
+	let output: &Tokens = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[test]
+fn portable_bytes() {
+	// Tag + largest variant (offset + length + `u32`)
+	assert_eq!(<Token as Serialize<PortableSer>>::PORTABLE_SIZE, 4 + 16 + 4);
+
+	let input = Token::Ident(Name("ab".to_string()), 0x01020304);
+	let (pos, storage) = PortableSer::new().serialize(&input);
+	assert_eq!(pos, 0);
+	assert_eq!(
+		storage.as_slice(),
+		[
+			1, 0, 0, 0, // Tag
+			24, 0, 0, 0, 0, 0, 0, 0, // Offset
+			2, 0, 0, 0, 0, 0, 0, 0, // Length
+			4, 3, 2, 1, // `u32`
+			b'a', b'b', // Name
+		]
+	);
+
+	let input = Token::Keyword {
+		id: 0x0506,
+		name: Name("c".to_string()),
+	};
+	let (_, storage) = PortableSer::new().serialize(&input);
+	assert_eq!(
+		storage.as_slice(),
+		[
+			2, 0, 0, 0, // Tag
+			6, 5, // `id`
+			24, 0, 0, 0, 0, 0, 0, 0, // Offset
+			1, 0, 0, 0, 0, 0, 0, 0, // Length
+			0, 0,    // Padding to size of largest variant
+			b'c', // Name
+		]
+	);
+}
@@ -0,0 +1,185 @@
+use std::{fmt::Debug, rc::Rc};
+
+mod common;
+use common::{generate_minecraft_data, tests, Test};
+use ser_raw::{
+	pos::{PosMapping, SharedAllocs},
+	serialized_size, serialized_size_with_header,
+	storage::{AlignedVec, CountingStorage, FixedVec, Storage},
+	util::{align_up_to, aligned_max_capacity, aligned_max_u32_capacity},
+	CompleteSerializer, Deserialize, PortableSerializer, PtrOffset32Serializer, PtrOffsetSerializer,
+	PureCopySerializer, Serialize, Serializer, SizeCountingSerializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+const MAX_U32_CAPACITY: usize = aligned_max_u32_capacity(16);
+type Store = AlignedVec<16, 16, 8, MAX_CAPACITY>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PureCopySer1 =
+	PureCopySerializer<16, 16, 1, MAX_CAPACITY, AlignedVec<16, 16, 1, MAX_CAPACITY>>;
+type PureCopySer16 =
+	PureCopySerializer<16, 16, 16, MAX_CAPACITY, AlignedVec<16, 16, 16, MAX_CAPACITY>>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PtrOffset32Ser =
+	PtrOffset32Serializer<16, 16, 8, MAX_U32_CAPACITY, AlignedVec<16, 16, 8, MAX_U32_CAPACITY>>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, Store>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 8, PortableStore>;
+type PortableSer32 = PortableSerializer<16, 16, 1, MAX_CAPACITY, 4, PortableStore>;
+
+/// Check `serialized_size` matches size of serializer's output, and that
+/// pre-allocating that capacity means storage never grows
+macro_rules! check_size {
+	($ser:ty, $input:expr) => {{
+		let size = serialized_size::<$ser, _>($input);
+		let (_, storage) = <$ser>::new().serialize($input);
+		assert_eq!(size, storage.pos());
+
+		let (_, storage) = <$ser>::with_capacity(size).serialize($input);
+		assert_eq!(storage.pos(), size);
+		assert_eq!(storage.capacity(), align_up_to(size, 16));
+
+		let size = serialized_size_with_header::<$ser, _>($input);
+		let (_, storage) = <$ser>::new().serialize_with_header($input);
+		assert_eq!(size, storage.pos());
+	}};
+}
+
+fn test_serialize<T>(input: &T, _test: Test, _test_num: usize)
+where T: Serialize<PureCopySer>
+		+ Serialize<PureCopySer1>
+		+ Serialize<PureCopySer16>
+		+ Serialize<PtrOffsetSer>
+		+ Serialize<PtrOffset32Ser>
+		+ Serialize<CompleteSer>
+		+ Serialize<PortableSer>
+		+ Serialize<PortableSer32>
+		+ Serialize<SizeCountingSerializer<PureCopySer>>
+		+ Serialize<SizeCountingSerializer<PureCopySer1>>
+		+ Serialize<SizeCountingSerializer<PureCopySer16>>
+		+ Serialize<SizeCountingSerializer<PtrOffsetSer>>
+		+ Serialize<SizeCountingSerializer<PtrOffset32Ser>>
+		+ Serialize<SizeCountingSerializer<CompleteSer>>
+		+ Serialize<SizeCountingSerializer<PortableSer>>
+		+ Serialize<SizeCountingSerializer<PortableSer32>>
+		+ Debug
+		+ PartialEq {
+	check_size!(PureCopySer, input);
+	check_size!(PureCopySer1, input);
+	check_size!(PureCopySer16, input);
+	check_size!(PtrOffsetSer, input);
+	check_size!(PtrOffset32Ser, input);
+	check_size!(CompleteSer, input);
+	check_size!(PortableSer, input);
+	check_size!(PortableSer32, input);
+}
+
+tests!(test_serialize, no_locks);
+
+#[derive(Serialize, Debug)]
+struct Node {
+	id: u8,
+	shared: Rc<Vec<u16>>,
+	children: Vec<Node>,
+}
+
+#[test]
+fn shared_allocs_counted_once() {
+	let shared = Rc::new(vec![1u16, 2, 3]);
+	let input = Node {
+		id: 0,
+		shared: shared.clone(),
+		children: (1..=3)
+			.map(|id| {
+				Node {
+					id,
+					shared: shared.clone(),
+					children: vec![],
+				}
+			})
+			.collect(),
+	};
+
+	check_size!(PureCopySer, &input);
+	check_size!(PtrOffsetSer, &input);
+	check_size!(CompleteSer, &input);
+	check_size!(PortableSer, &input);
+
+	// 4 x `Node` + 1 x `RcBox<Vec<u16>>` + 1 x `[u16; 3]` (padded to 8)
+	assert_eq!(serialized_size::<PureCopySer, _>(&input), 40 * 4 + 40 + 8);
+}
+
+type FixedStore = FixedVec<16, 16, 8, MAX_CAPACITY>;
+
+#[derive(Serializer)]
+#[ser_type(complete)]
+struct FixedCompleteSer {
+	#[ser_storage(FixedStore)]
+	storage: FixedStore,
+	#[ser_pos_mapping]
+	pos_mapping: PosMapping,
+	#[ser_shared]
+	shared_allocs: SharedAllocs,
+}
+
+#[test]
+fn exact_size_for_fixed_storage() {
+	let input = generate_minecraft_data();
+	let size = serialized_size::<FixedCompleteSer, _>(&input);
+
+	// Fixed storage with exactly the capacity required does not overflow
+	let ser = FixedCompleteSer {
+		storage: FixedStore::with_capacity(size),
+		pos_mapping: PosMapping::dummy(),
+		shared_allocs: SharedAllocs::new(),
+	};
+	let (_, storage) = ser.serialize(&input);
+	assert_eq!(storage.pos(), size);
+	assert_eq!(storage.capacity(), align_up_to(size, 16));
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[repr(align(16))]
+struct Wide(u8);
+
+#[test]
+fn counting_storage() {
+	let mut storage = CountingStorage::<Store>::new();
+	assert_eq!(storage.pos(), 0);
+	assert_eq!(storage.capacity(), 0);
+
+	// Same padding as `AlignedVec`
+	let mut aligned_vec = Store::new();
+	storage.push(&1u8);
+	aligned_vec.push(&1u8);
+	assert_eq!(storage.pos(), 8);
+	storage.push(&Wide(2));
+	aligned_vec.push(&Wide(2));
+	assert_eq!(storage.pos(), 32);
+	storage.push_slice(&[3u16; 3]);
+	aligned_vec.push_slice(&[3u16; 3]);
+	assert_eq!(storage.pos(), 40);
+	assert_eq!(storage.pos(), aligned_vec.pos());
+
+	// Capacity is only as large as required
+	assert_eq!(storage.capacity(), 48);
+	storage.shrink_to_fit();
+	assert_eq!(storage.capacity(), 48);
+
+	storage.clear();
+	assert_eq!(storage.pos(), 0);
+	storage.shrink_to_fit();
+	assert_eq!(storage.capacity(), 0);
+
+	let storage = CountingStorage::<Store>::with_capacity(20);
+	assert_eq!(storage.capacity(), 32);
+}
+
+#[test]
+#[should_panic(expected = "Cannot grow CountingStorage further")]
+fn counting_storage_exceeding_max_capacity_panics() {
+	type SmallStore = AlignedVec<16, 16, 8, 64>;
+	let mut storage = CountingStorage::<SmallStore>::new();
+	storage.push(&[0u64; 8]);
+	storage.push(&0u8);
+}
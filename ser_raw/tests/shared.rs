@@ -0,0 +1,419 @@
+use std::{
+	cell::RefCell,
+	mem, ptr,
+	rc::{self, Rc},
+	sync::{self, Arc},
+};
+
+use ser_raw::{
+	read::OffsetRef,
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	CompleteSerializer, Deserialize, Deserializer, PortableSerializer, PtrOffsetSerializer,
+	PureCopyDeserializer, PureCopySerializer, Serialize, Serializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+const PTR_SIZE: usize = mem::size_of::<usize>();
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 8, PortableStore>;
+type De<'a> = PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>;
+
+fn round_trip<T>(input: &T) -> T
+where T: Serialize<PureCopySer> + for<'a> Deserialize<De<'a>> {
+	let (pos, storage) = PureCopySer::new().serialize(input);
+	assert_eq!(pos, 0);
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output = de.deserialize_value();
+	assert_eq!(de.pos(), storage.pos());
+	output
+}
+
+#[derive(Serialize, Deserialize)]
+struct Node {
+	id: u32,
+	parent: rc::Weak<Node>,
+	children: Vec<Rc<Node>>,
+}
+
+/// Create tree of `Node`s, where each child has a `Weak` pointer to its parent.
+fn create_tree() -> Rc<Node> {
+	Rc::new_cyclic(|root| {
+		let children = (1..=3)
+			.map(|id| {
+				Rc::new(Node {
+					id,
+					parent: root.clone(),
+					children: vec![],
+				})
+			})
+			.collect();
+		Node {
+			id: 0,
+			parent: rc::Weak::new(),
+			children,
+		}
+	})
+}
+
+fn check_tree(root: &Node) {
+	assert_eq!(root.id, 0);
+	assert_eq!(root.parent.strong_count(), 0);
+	assert_eq!(root.children.len(), 3);
+	for (index, child) in root.children.iter().enumerate() {
+		assert_eq!(child.id as usize, index + 1);
+		assert!(ptr::eq(child.parent.as_ptr(), root));
+		assert!(child.children.is_empty());
+	}
+}
+
+#[test]
+fn shared_values_serialized_once() {
+	let shared = Rc::new(0x01020304u32);
+	let input = vec![shared.clone(), shared.clone(), shared];
+
+	// `Vec` + 3 pointers + 1 allocation (2 x `usize` counts + `u32` + padding)
+	let expected_size = 24 + PTR_SIZE * 3 + 24;
+	assert_eq!(PureCopySer::new().serialize(&input).1.pos(), expected_size);
+	assert_eq!(PtrOffsetSer::new().serialize(&input).1.pos(), expected_size);
+	assert_eq!(CompleteSer::new().serialize(&input).1.pos(), expected_size);
+
+	// `Vec` + 3 offsets + `u32`
+	assert_eq!(PortableSer::new().serialize(&input).1.pos(), 16 + 8 * 3 + 4);
+}
+
+#[test]
+fn pure_copy_round_trip() {
+	let rc = Rc::new("shared".to_string());
+	let arc = Arc::new(vec![1u16, 2, 3]);
+	let input = (
+		rc.clone(),
+		vec![rc.clone(), Rc::new("other".to_string()), rc],
+		arc.clone(),
+		Arc::downgrade(&arc),
+		arc,
+	);
+
+	let output = round_trip(&input);
+	assert_eq!(*output.0, "shared");
+	assert!(Rc::ptr_eq(&output.0, &output.1[0]));
+	assert!(Rc::ptr_eq(&output.0, &output.1[2]));
+	assert_eq!(*output.1[1], "other");
+	assert_eq!(Rc::strong_count(&output.0), 3);
+	assert_eq!(Rc::strong_count(&output.1[1]), 1);
+
+	assert_eq!(*output.2, vec![1, 2, 3]);
+	assert!(Arc::ptr_eq(&output.2, &output.4));
+	assert!(ptr::eq(output.3.as_ptr(), &*output.2));
+	assert_eq!(Arc::strong_count(&output.2), 2);
+	assert_eq!(Arc::weak_count(&output.2), 1);
+}
+
+#[test]
+fn pure_copy_round_trip_cycles() {
+	let input = create_tree();
+	let output = round_trip(&input);
+	check_tree(&output);
+	assert_eq!(Rc::strong_count(&output), 1);
+	assert_eq!(Rc::weak_count(&output), 3);
+}
+
+#[test]
+fn pure_copy_round_trip_strong_cycle() {
+	#[derive(Serialize, Deserialize)]
+	struct Link {
+		id: u32,
+		next: RefCell<Option<Rc<Link>>>,
+	}
+
+	// Leaked cycle: `first` -> `second` -> `first`
+	let first = Rc::new(Link {
+		id: 1,
+		next: RefCell::new(None),
+	});
+	let second = Rc::new(Link {
+		id: 2,
+		next: RefCell::new(Some(first.clone())),
+	});
+	*first.next.borrow_mut() = Some(second);
+
+	let output = round_trip(&first);
+	let second = output.next.borrow().clone().unwrap();
+	assert_eq!(output.id, 1);
+	assert_eq!(second.id, 2);
+	assert!(Rc::ptr_eq(second.next.borrow().as_ref().unwrap(), &output));
+	assert_eq!(Rc::strong_count(&output), 2);
+	assert_eq!(Rc::strong_count(&second), 2);
+
+	// Break cycles, so they're not leaked
+	first.next.borrow_mut().take();
+	output.next.borrow_mut().take();
+}
+
+#[test]
+fn pure_copy_round_trip_weak_only() {
+	// Value only reached via `Weak`s is dropped once deserialization is complete
+	let rc = Rc::new(123u64);
+	let input = vec![Rc::downgrade(&rc), Rc::downgrade(&rc)];
+
+	let output = round_trip(&input);
+	assert!(output[0].ptr_eq(&output[1]));
+	assert_eq!(output[0].strong_count(), 0);
+}
+
+#[test]
+fn dangling_and_dropped_weaks() {
+	let dropped = Rc::downgrade(&Rc::new(1u32));
+	let input = (rc::Weak::<u32>::new(), dropped, Rc::new(2u32));
+
+	let output = round_trip(&input);
+	assert!(output.0.upgrade().is_none());
+	assert!(output.1.upgrade().is_none());
+	assert_eq!(*output.2, 2);
+
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+	let output: &(rc::Weak<u32>, rc::Weak<u32>, Rc<u32>) = unsafe { storage.read(pos) };
+	assert_eq!(output.0.strong_count(), 0);
+	assert_eq!(output.1.strong_count(), 0);
+	assert_eq!(*output.2, 2);
+
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	let output = unsafe { OffsetRef::<(rc::Weak<u32>, rc::Weak<u32>, Rc<u32>)>::new(&storage, pos) };
+	assert!(unsafe { output.field(|value| ptr::addr_of!((*value).0)) }
+		.as_weak()
+		.is_none());
+	assert!(unsafe { output.field(|value| ptr::addr_of!((*value).1)) }
+		.as_weak()
+		.is_none());
+	let rc = unsafe { output.field(|value| ptr::addr_of!((*value).2)) }.as_rc();
+	assert_eq!(unsafe { *rc.get() }, 2);
+
+	// Offsets of dangling and dropped `Weak`s are all 1s
+	let (pos, storage) = PortableSer::new().serialize(&input);
+	let bytes = &storage.as_slice()[pos..];
+	assert_eq!(&bytes[..16], &[0xff; 16]);
+	assert_eq!(&bytes[16..24], &24u64.to_le_bytes());
+	assert_eq!(&bytes[24..28], &2u32.to_le_bytes());
+}
+
+#[test]
+fn dropped_weak_value_written_as_zeros() {
+	// `String`'s pointer is dangling once it's dropped, so must not be read
+	let dropped = Rc::downgrade(&Rc::new("dropped".to_string()));
+	let input = (dropped.clone(), dropped);
+
+	let (pos, storage) = PureCopySer::new().serialize(&input);
+	assert_eq!(pos, 0);
+	// 2 x `Weak`, then allocation, then nothing else
+	let bytes = storage.as_slice();
+	assert_eq!(
+		bytes.len(),
+		PTR_SIZE * 2 + mem::size_of::<[usize; 2]>() + mem::size_of::<String>()
+	);
+	let alloc = &bytes[PTR_SIZE * 2..];
+	// Strong count is 0, weak count is 2
+	assert_eq!(&alloc[..PTR_SIZE], &0usize.to_ne_bytes());
+	assert_eq!(&alloc[PTR_SIZE..PTR_SIZE * 2], &2usize.to_ne_bytes());
+	assert!(alloc[PTR_SIZE * 2..].iter().all(|&byte| byte == 0));
+
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	let output = unsafe { OffsetRef::<(rc::Weak<String>, rc::Weak<String>)>::new(&storage, pos) };
+	let offsets = [
+		unsafe { *storage.read::<usize>(output.field(|value| ptr::addr_of!((*value).0)).pos()) },
+		unsafe { *storage.read::<usize>(output.field(|value| ptr::addr_of!((*value).1)).pos()) },
+	];
+	assert_eq!(offsets, [PTR_SIZE * 2; 2]);
+	assert!(unsafe { output.field(|value| ptr::addr_of!((*value).0)) }
+		.as_weak()
+		.is_none());
+	assert!(storage.as_slice()[PTR_SIZE * 4..]
+		.iter()
+		.all(|&byte| byte == 0));
+
+	let output = round_trip(&input);
+	assert!(output.0.upgrade().is_none());
+	assert!(output.0.ptr_eq(&output.1));
+}
+
+#[test]
+fn ptr_offset_points_to_single_copy() {
+	let shared = Rc::new(0x01020304u32);
+	let input = vec![shared.clone(), Rc::new(5), shared];
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+
+	let output = unsafe { OffsetRef::<Vec<Rc<u32>>>::new(&storage, pos) }.as_vec();
+	let values = output
+		.iter()
+		.map(|rc| unsafe { *rc.as_rc().get() })
+		.collect::<Vec<_>>();
+	assert_eq!(values, vec![0x01020304, 5, 0x01020304]);
+
+	let offsets = output
+		.iter()
+		.map(|rc| unsafe { *storage.read::<usize>(rc.pos()) })
+		.collect::<Vec<_>>();
+	assert_eq!(offsets[0], offsets[2]);
+	assert_ne!(offsets[0], offsets[1]);
+
+	// Cycles are followed once
+	let input = create_tree();
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	let root = unsafe { OffsetRef::<Rc<Node>>::new(&storage, pos) }.as_rc();
+	let children = unsafe { root.field(|node| ptr::addr_of!((*node).children)) }.as_vec();
+	assert_eq!(children.len(), 3);
+	for child in children.iter() {
+		let child = child.as_rc();
+		let parent = unsafe { child.field(|node| ptr::addr_of!((*node).parent)) }
+			.as_weak()
+			.unwrap();
+		assert_eq!(parent.pos(), root.pos());
+	}
+}
+
+#[test]
+fn complete_ref_counts() {
+	let rc = Rc::new(7u32);
+	let arc = Arc::new("arc".to_string());
+	let input = (
+		rc.clone(),
+		vec![rc.clone(), rc.clone()],
+		Rc::downgrade(&rc),
+		arc.clone(),
+		Arc::downgrade(&arc),
+		Arc::downgrade(&arc),
+	);
+	// Extra references outside the serialized tree are not counted
+	let _extra = (rc, arc.clone(), Arc::downgrade(&arc));
+
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+	#[allow(clippy::type_complexity)]
+	let output: &(
+		Rc<u32>,
+		Vec<Rc<u32>>,
+		rc::Weak<u32>,
+		Arc<String>,
+		sync::Weak<String>,
+		sync::Weak<String>,
+	) = unsafe { storage.read(pos) };
+
+	assert_eq!(*output.0, 7);
+	assert!(Rc::ptr_eq(&output.0, &output.1[0]));
+	assert!(Rc::ptr_eq(&output.0, &output.1[1]));
+	assert!(ptr::eq(output.2.as_ptr(), &*output.0));
+	assert_eq!(Rc::strong_count(&output.0), 3);
+	assert_eq!(Rc::weak_count(&output.0), 1);
+
+	assert_eq!(*output.3, "arc");
+	assert!(ptr::eq(output.4.as_ptr(), &*output.3));
+	assert!(output.4.ptr_eq(&output.5));
+	assert_eq!(Arc::strong_count(&output.3), 1);
+	assert_eq!(Arc::weak_count(&output.3), 2);
+}
+
+#[test]
+fn complete_cycles() {
+	let input = create_tree();
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+	let output: &Rc<Node> = unsafe { storage.read(pos) };
+	check_tree(output);
+	assert_eq!(Rc::strong_count(output), 1);
+	assert_eq!(Rc::weak_count(output), 3);
+	for child in &output.children {
+		assert_eq!(Rc::strong_count(child), 1);
+	}
+}
+
+#[test]
+fn complete_storage_grows() {
+	let shared = (0..100)
+		.map(|index| Rc::new(format!("value {index}")))
+		.collect::<Vec<_>>();
+	let input = (0..1000)
+		.map(|index| shared[index % 100].clone())
+		.collect::<Vec<_>>();
+
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+	let output: &Vec<Rc<String>> = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+	for (index, rc) in output.iter().enumerate() {
+		assert!(Rc::ptr_eq(rc, &output[index % 100]));
+		assert_eq!(Rc::strong_count(rc), 10);
+	}
+}
+
+#[test]
+fn dropped_allocation_address_reused() {
+	// Allocation serialized in 1st value is dropped, and allocator is likely to
+	// reuse its address for 2nd. 2nd must not point to copy written for 1st.
+	let mut ser = CompleteSer::new();
+	let first = Rc::new(111u64);
+	let pos1 = ser.serialize_value(&first);
+	drop(first);
+	let second = Rc::new(222u64);
+	let pos2 = ser.serialize_value(&second);
+	assert_eq!(ser.shared_allocs().unwrap().len(), 2);
+
+	let storage = ser.finalize();
+	let output1: &Rc<u64> = unsafe { storage.read(pos1) };
+	let output2: &Rc<u64> = unsafe { storage.read(pos2) };
+	assert_eq!(**output1, 111);
+	assert_eq!(**output2, 222);
+	assert_eq!(Rc::strong_count(output1), 1);
+	assert_eq!(Rc::strong_count(output2), 1);
+
+	// Allocations shared between top-level values are written once for each
+	let shared = Rc::new(333u64);
+	let mut ser = CompleteSer::new();
+	let pos1 = ser.serialize_value(&shared);
+	let pos2 = ser.serialize_value(&vec![shared.clone(), shared]);
+	let storage = ser.finalize();
+	let output1: &Rc<u64> = unsafe { storage.read(pos1) };
+	let output2: &Vec<Rc<u64>> = unsafe { storage.read(pos2) };
+	assert!(!Rc::ptr_eq(output1, &output2[0]));
+	assert!(Rc::ptr_eq(&output2[0], &output2[1]));
+	assert_eq!(Rc::strong_count(output1), 1);
+	assert_eq!(Rc::strong_count(&output2[0]), 2);
+}
+
+#[test]
+fn portable_offsets() {
+	let shared = Rc::new(0x01020304u32);
+	let input = vec![shared.clone(), Rc::new(5), shared];
+	let (_, storage) = PortableSer::new().serialize(&input);
+	let bytes = storage.as_slice();
+
+	// `Vec` (offset + length), then 3 offsets, then the 2 values
+	assert_eq!(bytes.len(), 16 + 8 * 3 + 4 * 2);
+	let offsets = (0..3)
+		.map(|index| {
+			let pos = 16 + index * 8;
+			u64::from_le_bytes(bytes[pos..pos + 8].try_into().unwrap()) as usize
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(offsets, vec![40, 44, 40]);
+	assert_eq!(&bytes[40..44], &0x01020304u32.to_le_bytes());
+	assert_eq!(&bytes[44..48], &5u32.to_le_bytes());
+
+	// Cycles are followed once
+	let input = create_tree();
+	PortableSer::new().serialize(&input);
+}
+
+#[test]
+#[should_panic(expected = "Serializer does not support shared pointers")]
+fn serializer_without_shared_allocs_panics() {
+	#[derive(Serializer)]
+	#[ser_type(pure_copy)]
+	struct MySer {
+		#[ser_storage(AlignedVec)]
+		storage: AlignedVec,
+	}
+
+	let ser = MySer {
+		storage: AlignedVec::new(),
+	};
+	ser.serialize(&Rc::new(1u8));
+}
@@ -0,0 +1,186 @@
+use std::{
+	mem, slice,
+	sync::{Arc, Mutex},
+};
+
+use ser_raw::{
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, Deserialize, Deserializer, PortableSerializer, PtrOffsetSerializer,
+	PureCopyDeserializer, PureCopySerializer, Serialize, Serializer, Validate,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 8, PortableStore>;
+type De<'a> = PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Document {
+	name: String,
+	#[ser_skip]
+	fd: i32,
+	#[ser_default]
+	cache: Vec<String>,
+	#[ser_replace = "None"]
+	handle: Option<Arc<Mutex<String>>>,
+	parts: Vec<Part>,
+}
+
+#[derive(Serialize, Deserialize, Validate, Debug, PartialEq)]
+#[repr(u8)]
+enum Part {
+	Text(String, #[ser_default] Vec<u32>),
+	Link {
+		#[ser_skip]
+		id: u16,
+		#[ser_replace = "String::new()"]
+		target: String,
+	},
+}
+
+fn create_document() -> Document {
+	Document {
+		name: "doc".to_string(),
+		fd: 3,
+		cache: vec!["cached".to_string(); 3],
+		handle: Some(Arc::new(Mutex::new("handle".to_string()))),
+		parts: vec![
+			Part::Text("abc".to_string(), vec![1, 2, 3]),
+			Part::Link {
+				id: 7,
+				target: "https://example.com".to_string(),
+			},
+		],
+	}
+}
+
+/// `parts` after replaced fields have been replaced
+fn expected_parts() -> Vec<Part> {
+	vec![
+		Part::Text("abc".to_string(), vec![]),
+		Part::Link {
+			id: 7,
+			target: String::new(),
+		},
+	]
+}
+
+#[test]
+fn pure_copy_round_trip() {
+	let input = create_document();
+	let (pos, storage) = PureCopySer::new().serialize(&input);
+	assert_eq!(pos, 0);
+
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output: Document = de.deserialize_value();
+	assert_eq!(de.pos(), storage.pos());
+
+	assert_eq!(output.name, "doc");
+	assert_eq!(output.fd, 3);
+	assert!(output.cache.is_empty());
+	assert!(output.handle.is_none());
+	assert_eq!(output.parts, expected_parts());
+}
+
+#[test]
+fn complete_replaces_fields() {
+	let input = create_document();
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+
+	let output: &Document = unsafe { storage.read(pos) };
+	assert_eq!(output.name, "doc");
+	assert_eq!(output.fd, 3);
+	assert!(output.cache.is_empty());
+	assert!(output.handle.is_none());
+	assert_eq!(output.parts, expected_parts());
+}
+
+/// Get bytes of a value
+fn bytes_of<T>(value: &T) -> &[u8] {
+	unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
+
+#[test]
+fn ptr_offset_replaces_fields() {
+	let input = create_document();
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	let output = &storage.as_slice()[pos..pos + mem::size_of::<Document>()];
+
+	let field_bytes = |field: &[u8]| {
+		let offset = field.as_ptr() as usize - &input as *const Document as usize;
+		&output[offset..offset + field.len()]
+	};
+
+	// Replaced fields contain replacement values, not pointers from input
+	assert_eq!(
+		field_bytes(bytes_of(&input.handle)),
+		bytes_of(&None::<Arc<Mutex<String>>>)
+	);
+	assert_eq!(
+		field_bytes(bytes_of(&input.cache)),
+		bytes_of(&Vec::<String>::new())
+	);
+	// `#[ser_skip]` field is copied as is
+	assert_eq!(field_bytes(bytes_of(&input.fd)), bytes_of(&3i32));
+
+	// Pointer to `Arc`'s allocation does not appear anywhere in output
+	let arc_ptr = bytes_of(&input.handle).to_vec();
+	assert!(!storage
+		.as_slice()
+		.windows(arc_ptr.len())
+		.any(|bytes| bytes == arc_ptr));
+}
+
+#[test]
+fn complete_and_validate() {
+	let input = create_document().parts;
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+
+	let output: &Vec<Part> = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &expected_parts());
+}
+
+#[test]
+fn portable_excludes_skipped_fields() {
+	// Only `name` and `parts`
+	assert_eq!(<Document as Serialize<PortableSer>>::PORTABLE_SIZE, 16 + 16);
+	// Tag + largest variant (`String`)
+	assert_eq!(<Part as Serialize<PortableSer>>::PORTABLE_SIZE, 4 + 16);
+
+	let input = Part::Link {
+		id: 7,
+		target: "ab".to_string(),
+	};
+	let (pos, storage) = PortableSer::new().serialize(&input);
+	assert_eq!(pos, 0);
+	assert_eq!(
+		storage.as_slice(),
+		[
+			1, 0, 0, 0, // Tag
+			0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // No fields
+		]
+	);
+}
+
+#[test]
+fn owning_replacement_not_dropped() {
+	#[derive(Serialize)]
+	struct Foo {
+		#[ser_replace = "vec![1, 2, 3]"]
+		items: Vec<u32>,
+	}
+
+	let input = Foo { items: vec![] };
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+
+	// If replacement was dropped, these would likely reuse its memory
+	let others = (0..10).map(|_| vec![0u32; 3]).collect::<Vec<_>>();
+
+	let output: &Foo = unsafe { storage.read(pos) };
+	assert_eq!(output.items, [1, 2, 3]);
+	drop(others);
+}
@@ -0,0 +1,399 @@
+use std::mem;
+
+use compact_str::CompactString;
+use ser_raw::{
+	storage::{AlignedVec, ContiguousStorage, RandomAccessStorage, Storage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, Deserialize, Deserializer, PortableSerializer, PtrOffsetSerializer,
+	PureCopyDeserializer, PureCopySerializer, Serialize, Serializer, Validate, ValidateError,
+};
+use smallvec::{smallvec, SmallVec};
+use smol_str::SmolStr;
+use string_cache::DefaultAtom;
+use thin_vec::{thin_vec, ThinVec};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type CompleteSer = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PortableStore = AlignedVec<16, 16, 1, MAX_CAPACITY>;
+type PortableSer = PortableSerializer<16, 16, 1, MAX_CAPACITY, 4, PortableStore>;
+type De<'a> = PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>;
+
+const PTR_SIZE: usize = mem::size_of::<usize>();
+
+type Options = (
+	Option<SmallVec<[u8; 4]>>,
+	Option<ThinVec<u8>>,
+	Option<CompactString>,
+	Option<SmolStr>,
+);
+
+#[derive(Serialize, Deserialize, Validate, Debug, PartialEq)]
+struct SmallTypes {
+	inline_vec: SmallVec<[u32; 4]>,
+	spilled_vec: SmallVec<[String; 2]>,
+	spilled_empty_vec: SmallVec<[u16; 2]>,
+	excess_capacity_vec: SmallVec<[u8; 4]>,
+	zst_small_vec: SmallVec<[(); 2]>,
+	thin_vec: ThinVec<Box<u64>>,
+	empty_thin_vec: ThinVec<u32>,
+	empty_capacity_thin_vec: ThinVec<u32>,
+	excess_capacity_thin_vec: ThinVec<u16>,
+	zst_thin_vec: ThinVec<()>,
+	inline_compact: CompactString,
+	full_inline_compact: CompactString,
+	heap_compact: CompactString,
+	empty_heap_compact: CompactString,
+	inline_smol: SmolStr,
+	heap_smol: SmolStr,
+	shared_smol: SmolStr,
+	substring_smol: SmolStr,
+	options: Options,
+	none_options: Options,
+}
+
+fn create_small_types() -> SmallTypes {
+	let mut spilled_empty_vec = SmallVec::<[u16; 2]>::with_capacity(10);
+	assert!(spilled_empty_vec.spilled());
+	spilled_empty_vec.push(1);
+	spilled_empty_vec.clear();
+
+	let mut excess_capacity_vec = SmallVec::<[u8; 4]>::with_capacity(20);
+	excess_capacity_vec.extend([1, 2, 3, 4, 5, 6]);
+
+	let mut excess_capacity_thin_vec = ThinVec::with_capacity(20);
+	excess_capacity_thin_vec.extend([1, 2, 3]);
+
+	let mut empty_heap_compact = CompactString::with_capacity(100);
+	empty_heap_compact.push_str("");
+	assert!(empty_heap_compact.is_heap_allocated());
+
+	let heap_smol = SmolStr::new("a string which is too long to be inline");
+	assert!(heap_smol.is_heap_allocated());
+	let substring_smol = SmolStr::new(format!("\n{}", " ".repeat(30)));
+	assert!(!substring_smol.is_heap_allocated());
+
+	SmallTypes {
+		inline_vec: smallvec![1, 2, 3],
+		spilled_vec: (0..5).map(|index| index.to_string()).collect(),
+		spilled_empty_vec,
+		excess_capacity_vec,
+		zst_small_vec: smallvec![(), (), ()],
+		thin_vec: thin_vec![Box::new(1), Box::new(2), Box::new(3)],
+		empty_thin_vec: ThinVec::new(),
+		empty_capacity_thin_vec: ThinVec::with_capacity(10),
+		excess_capacity_thin_vec,
+		zst_thin_vec: thin_vec![(), ()],
+		inline_compact: CompactString::new("short"),
+		full_inline_compact: CompactString::new("abcdefghijklmnopqrstuvwx"),
+		heap_compact: CompactString::new("a string which is too long to be inline"),
+		empty_heap_compact,
+		inline_smol: SmolStr::new("short"),
+		shared_smol: heap_smol.clone(),
+		heap_smol,
+		substring_smol,
+		options: (
+			Some(smallvec![1, 2, 3, 4, 5]),
+			Some(thin_vec![1, 2]),
+			Some(CompactString::new("also too long to be stored inline")),
+			Some(SmolStr::new("also too long to be stored inline")),
+		),
+		none_options: (None, None, None, None),
+	}
+}
+
+#[test]
+fn pure_copy_round_trip() {
+	let input = create_small_types();
+	let (pos, storage) = PureCopySer::new().serialize(&input);
+	assert_eq!(pos, 0);
+
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output: SmallTypes = de.deserialize_value();
+	assert_eq!(de.pos(), storage.pos());
+	assert_eq!(output, input);
+	assert!(output.spilled_vec.spilled());
+	assert!(!output.inline_vec.spilled());
+	assert!(output.heap_compact.is_heap_allocated());
+	assert!(output.heap_smol.is_heap_allocated());
+}
+
+#[test]
+fn ptr_offset() {
+	#[derive(Serialize)]
+	#[repr(C)]
+	struct Heap {
+		small_vec: SmallVec<[u8; 2]>,
+		thin_vec: ThinVec<u8>,
+		compact: CompactString,
+		smol: SmolStr,
+	}
+
+	let mut small_vec = SmallVec::from_vec(Vec::with_capacity(10));
+	small_vec.extend([1, 2, 3]);
+	let string = "a string which is too long to be inline";
+	let input = Heap {
+		small_vec,
+		thin_vec: thin_vec![4, 5],
+		compact: CompactString::new(string),
+		smol: SmolStr::new(string),
+	};
+
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input);
+	assert_eq!(pos, 0);
+	let bytes = storage.as_slice();
+	let read_usize =
+		|pos: usize| usize::from_ne_bytes(bytes[pos..pos + PTR_SIZE].try_into().unwrap());
+
+	// Pointers are replaced with positions of their targets in output.
+	// `SmallVec`'s pointer is whichever field is not capacity or length.
+	let vec_pos = (0..3)
+		.map(|index| read_usize(index * PTR_SIZE))
+		.find(|&word| word != 10 && word != 3)
+		.unwrap();
+	assert_eq!(&bytes[vec_pos..vec_pos + 3], [1, 2, 3]);
+
+	// `ThinVec` points to header, followed by contents
+	let header_pos = read_usize(24);
+	assert_eq!(read_usize(header_pos), 2);
+	assert_eq!(
+		&bytes[header_pos + PTR_SIZE * 2..header_pos + PTR_SIZE * 2 + 2],
+		[4, 5]
+	);
+
+	let compact_pos = read_usize(32);
+	assert_eq!(
+		&bytes[compact_pos..compact_pos + string.len()],
+		string.as_bytes()
+	);
+
+	// `SmolStr` points to reference counts, followed by string.
+	// Pointer is whichever field is not length.
+	let rc_pos = (7..9)
+		.map(|index| read_usize(index * PTR_SIZE))
+		.find(|&word| word != string.len())
+		.unwrap();
+	assert_eq!((read_usize(rc_pos), read_usize(rc_pos + PTR_SIZE)), (1, 1));
+	let str_pos = rc_pos + PTR_SIZE * 2;
+	assert_eq!(&bytes[str_pos..str_pos + string.len()], string.as_bytes());
+}
+
+#[test]
+fn complete() {
+	let input = create_small_types();
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+	let output: &SmallTypes = unsafe { storage.read(pos) };
+	assert_eq!(output, &input);
+
+	assert!(!output.inline_vec.spilled());
+	assert!(output.spilled_vec.spilled());
+	assert_eq!(output.spilled_vec.capacity(), 5);
+	assert!(!output.spilled_empty_vec.spilled());
+	assert!(output.excess_capacity_vec.spilled());
+	assert_eq!(output.excess_capacity_vec.capacity(), 6);
+	assert_eq!(output.empty_capacity_thin_vec.capacity(), 0);
+	assert_eq!(output.excess_capacity_thin_vec.capacity(), 3);
+	assert_eq!(output.zst_thin_vec.len(), 2);
+	assert!(output.heap_compact.is_heap_allocated());
+	assert_eq!(output.heap_compact.capacity(), output.heap_compact.len());
+	assert!(!output.empty_heap_compact.is_heap_allocated());
+	assert!(output.heap_smol.is_heap_allocated());
+
+	// Pointers point into output
+	let range = storage.as_slice().as_ptr_range();
+	assert!(range.contains(&(output.spilled_vec.as_ptr() as *const u8)));
+	assert!(range.contains(&(output.thin_vec.as_ptr() as *const u8)));
+	assert!(range.contains(&(output.empty_thin_vec.as_ptr() as *const u8)));
+	assert!(range.contains(&output.heap_compact.as_ptr()));
+	assert!(range.contains(&output.heap_smol.as_ptr()));
+	assert!(range.contains(&output.shared_smol.as_ptr()));
+	assert_ne!(output.heap_smol.as_ptr(), output.shared_smol.as_ptr());
+
+	let output: &SmallTypes = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, &input);
+}
+
+#[derive(Serialize, Deserialize, Validate, Debug)]
+struct Atoms {
+	static_atom: DefaultAtom,
+	inline_atom: DefaultAtom,
+	dynamic_atom: DefaultAtom,
+	shared_atom: DefaultAtom,
+	option_atom: Option<DefaultAtom>,
+	none_atom: Option<DefaultAtom>,
+}
+
+impl Atoms {
+	fn strs(&self) -> [Option<&str>; 6] {
+		[
+			Some(&self.static_atom),
+			Some(&self.inline_atom),
+			Some(&self.dynamic_atom),
+			Some(&self.shared_atom),
+			self.option_atom.as_deref(),
+			self.none_atom.as_deref(),
+		]
+	}
+}
+
+fn create_atoms() -> Atoms {
+	let dynamic_atom = DefaultAtom::from("a string which is too long to be inline");
+	Atoms {
+		static_atom: DefaultAtom::from(""),
+		inline_atom: DefaultAtom::from("short"),
+		shared_atom: dynamic_atom.clone(),
+		dynamic_atom,
+		option_atom: Some(DefaultAtom::from("another string which is not inline")),
+		none_atom: None,
+	}
+}
+
+#[test]
+fn atoms() {
+	let input = create_atoms();
+	assert!(input.static_atom.is_static());
+	assert!(input.inline_atom.is_inline());
+	assert!(input.dynamic_atom.is_dynamic());
+
+	// Deserializing re-interns dynamic atoms, so they're equal to input
+	let (_, storage) = PureCopySer::new().serialize(&input);
+	let mut de = unsafe { De::new(storage.as_slice()) };
+	let output: Atoms = de.deserialize_value();
+	assert_eq!(de.pos(), storage.pos());
+	assert_eq!(output.strs(), input.strs());
+	assert_eq!(output.dynamic_atom, input.dynamic_atom);
+	assert_eq!(output.option_atom, input.option_atom);
+
+	// Dynamic atoms in `CompleteSerializer`'s output point to entries in output
+	let (pos, storage) = CompleteSer::new().serialize(&input);
+	let output: &Atoms = unsafe { storage.read(pos) };
+	assert_eq!(output.strs(), input.strs());
+	assert_eq!(output.static_atom, input.static_atom);
+	assert_eq!(output.inline_atom, input.inline_atom);
+	let range = storage.as_slice().as_ptr_range();
+	assert!(output.dynamic_atom.is_dynamic());
+	assert!(range.contains(&output.dynamic_atom.as_ptr()));
+	assert!(range.contains(&output.shared_atom.as_ptr()));
+	assert!(range.contains(&(output.dynamic_atom.unsafe_data() as usize as *const u8)));
+
+	// Cloning and dropping an atom in output doesn't affect interner
+	drop(output.dynamic_atom.clone());
+	assert_eq!(&*output.dynamic_atom, &*input.dynamic_atom);
+
+	let output: &Atoms = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output.strs(), input.strs());
+
+	// `PtrOffsetSerializer` replaces dynamic atom's pointer with position of entry
+	let (pos, storage) = PtrOffsetSer::new().serialize(&input.dynamic_atom);
+	assert_eq!(pos, 0);
+	let bytes = storage.as_slice();
+	let entry_pos = u64::from_ne_bytes(bytes[..8].try_into().unwrap()) as usize;
+	assert_eq!(entry_pos, 8);
+	let string = input.dynamic_atom.as_bytes();
+
+	// Entry is followed by string, and contains its position
+	let str_pos = bytes
+		.windows(string.len())
+		.position(|window| window == string)
+		.unwrap();
+	assert!((entry_pos..str_pos)
+		.step_by(8)
+		.any(|pos| u64::from_ne_bytes(bytes[pos..pos + 8].try_into().unwrap()) as usize == str_pos));
+
+	// Portable
+	let input = (DefaultAtom::from("ab"), DefaultAtom::from(""));
+	let (_, storage) = PortableSer::new().serialize(&input);
+	assert_eq!(
+		storage.as_slice(),
+		[
+			16, 0, 0, 0, 2, 0, 0, 0, // Inline atom
+			0, 0, 0, 0, 0, 0, 0, 0, // Empty atom
+			b'a', b'b', // Contents
+		]
+	);
+}
+
+fn validate_corrupted<T, F>(input: &T, corrupt: F) -> Result<(), ValidateError>
+where
+	T: Serialize<CompleteSer> + Validate,
+	F: FnOnce(&mut [u8]),
+{
+	let (pos, mut storage) = CompleteSer::new().serialize(input);
+	corrupt(&mut storage.as_mut_slice()[pos..]);
+	validate::<T>(storage.as_slice(), pos).map(|_| ())
+}
+
+#[test]
+fn invalid() {
+	// Determine which field of a spilled `SmallVec` is `len`
+	let vec = SmallVec::<[u8; 2]>::with_capacity(10);
+	let parts: [usize; 3] = unsafe { mem::transmute(vec) };
+	let len_index = parts.iter().position(|&part| part == 0).unwrap();
+	let _: SmallVec<[u8; 2]> = unsafe { mem::transmute(parts) };
+
+	// `SmallVec` with heap length greater than capacity
+	let result = validate_corrupted(&SmallVec::<[u8; 2]>::from_slice(&[1, 2, 3]), |bytes| {
+		bytes[len_index * PTR_SIZE] = 5;
+	});
+	assert_eq!(
+		result,
+		Err(ValidateError::LenExceedsCapacity {
+			len: 5,
+			capacity: 3
+		})
+	);
+
+	// Inline `CompactString` containing invalid UTF-8
+	let result = validate_corrupted(&CompactString::new("abc"), |bytes| bytes[1] = 0xff);
+	assert!(matches!(result, Err(ValidateError::InvalidUtf8 { .. })));
+
+	// Heap `CompactString` with capacity less than length
+	let string = CompactString::new("a string which is too long to be inline");
+	let result = validate_corrupted(&string, |bytes| bytes[PTR_SIZE * 2] = 1);
+	assert_eq!(
+		result,
+		Err(ValidateError::LenExceedsCapacity {
+			len: string.len(),
+			capacity: 1
+		})
+	);
+
+	// Inline `Atom` with invalid length
+	let result = validate_corrupted(&DefaultAtom::from("abc"), |bytes| {
+		bytes[0] = 0b1000_0001;
+	});
+	assert_eq!(result, Err(ValidateError::InvalidDiscriminant));
+
+	// `SmolStr` with invalid tag
+	let result = validate_corrupted(&SmolStr::new("abc"), |bytes| {
+		let tag_offset = if bytes[0] == 3 { 0 } else { 23 };
+		bytes[tag_offset] = 25;
+	});
+	assert_eq!(result, Err(ValidateError::InvalidDiscriminant));
+}
+
+#[test]
+fn portable_bytes() {
+	let input = (
+		SmallVec::<[u8; 2]>::from_slice(&[1, 2, 3]),
+		thin_vec![4u8, 5],
+		CompactString::new("ab"),
+		SmolStr::new(""),
+	);
+
+	let (pos, storage) = PortableSer::new().serialize(&input);
+	assert_eq!(pos, 0);
+	assert_eq!(
+		storage.as_slice(),
+		[
+			32, 0, 0, 0, 3, 0, 0, 0, // SmallVec
+			35, 0, 0, 0, 2, 0, 0, 0, // ThinVec
+			37, 0, 0, 0, 2, 0, 0, 0, // CompactString
+			0, 0, 0, 0, 0, 0, 0, 0, // Empty SmolStr
+			1, 2, 3, // SmallVec contents
+			4, 5, // ThinVec contents
+			b'a', b'b', // CompactString contents
+		]
+	);
+}
@@ -0,0 +1,625 @@
+use std::{collections::VecDeque, fmt::Debug, mem, num::NonZeroU32};
+
+#[allow(dead_code, unused_imports, unused_macros)]
+mod common;
+use common::generate_minecraft_data;
+use num_bigint::{BigInt, BigUint, Sign};
+use ser_raw::{
+	niche::Niche,
+	storage::{AlignedVec, ContiguousStorage, Storage},
+	util::aligned_max_capacity,
+	validate, CompleteSerializer, Serialize, Serializer, Validate, ValidateError,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+const PTR_SIZE: usize = mem::size_of::<usize>();
+type Ser = CompleteSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+
+fn serialize<T: Serialize<Ser>>(value: &T) -> (usize, AlignedVec) {
+	let ser = Ser::new();
+	ser.serialize(value)
+}
+
+fn test_valid<T>(input: &T)
+where T: Serialize<Ser> + Validate + Debug + PartialEq {
+	let (pos, storage) = serialize(input);
+	let output: &T = validate(storage.as_slice(), pos).unwrap();
+	assert_eq!(output, input);
+}
+
+/// Serialize value, alter output with `corrupt`, and validate.
+fn validate_corrupted<T, F>(input: &T, corrupt: F) -> Result<(), ValidateError>
+where
+	T: Serialize<Ser> + Validate,
+	F: FnOnce(&mut [u8]),
+{
+	let (pos, mut storage) = serialize(input);
+	corrupt(storage.as_mut_slice());
+	validate::<T>(storage.as_slice(), pos).map(|_| ())
+}
+
+fn write_usize(bytes: &mut [u8], pos: usize, value: usize) {
+	bytes[pos..pos + PTR_SIZE].copy_from_slice(&value.to_ne_bytes());
+}
+
+fn read_usize(bytes: &[u8], pos: usize) -> usize {
+	usize::from_ne_bytes(bytes[pos..pos + PTR_SIZE].try_into().unwrap())
+}
+
+#[test]
+fn primitives() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	struct Foo {
+		u8: u8,
+		u32: u32,
+		u128: u128,
+		i16: i16,
+		isize: isize,
+		f32: f32,
+		f64: f64,
+		bool: bool,
+		char: char,
+		non_zero: NonZeroU32,
+		unit: (),
+	}
+
+	test_valid(&Foo {
+		u8: 0x01,
+		u32: 0x02030405,
+		u128: 0x060708090a0b0c0d0e0f101112131415,
+		i16: -1,
+		isize: isize::MIN,
+		f32: f32::MAX,
+		f64: 1.5,
+		bool: true,
+		char: '🦀',
+		non_zero: NonZeroU32::new(123).unwrap(),
+		unit: (),
+	});
+}
+
+#[test]
+fn tuples_and_arrays() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	struct Foo((u8, bool, char), [u32; 3], [Box<u16>; 2], [(); 4]);
+
+	test_valid(&Foo(
+		(1, false, 'x'),
+		[2, 3, 4],
+		[Box::new(5), Box::new(6)],
+		[(); 4],
+	));
+}
+
+#[test]
+fn ptrs() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	#[allow(clippy::box_collection, clippy::redundant_allocation)]
+	struct Foo {
+		boxed: Box<u32>,
+		boxed_boxed: Box<Box<u8>>,
+		boxed_zst: Box<()>,
+		vec: Vec<u16>,
+		vec_of_vecs: Vec<Vec<u8>>,
+		vec_of_zsts: Vec<()>,
+		empty_vec: Vec<u64>,
+		string: String,
+		empty_string: String,
+		strings: Vec<String>,
+	}
+
+	test_valid(&Foo {
+		boxed: Box::new(1),
+		boxed_boxed: Box::new(Box::new(2)),
+		boxed_zst: Box::new(()),
+		vec: vec![3, 4, 5],
+		vec_of_vecs: vec![vec![6], vec![], vec![7, 8]],
+		vec_of_zsts: vec![(), ()],
+		empty_vec: Vec::with_capacity(10),
+		string: "abc".to_string(),
+		empty_string: String::with_capacity(10),
+		strings: vec!["Ruthven".to_string(), "Ambrose".to_string()],
+	});
+}
+
+#[test]
+fn boxed_slices() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	struct Foo {
+		slice: Box<[u16]>,
+		empty_slice: Box<[u64]>,
+		slice_of_zsts: Box<[()]>,
+		slices: Box<[Box<[u8]>]>,
+		str: Box<str>,
+		empty_str: Box<str>,
+	}
+
+	test_valid(&Foo {
+		slice: vec![1, 2, 3].into_boxed_slice(),
+		empty_slice: Box::new([]),
+		slice_of_zsts: vec![(), ()].into_boxed_slice(),
+		slices: vec![vec![4].into_boxed_slice(), Box::new([])].into_boxed_slice(),
+		str: "abc".into(),
+		empty_str: "".into(),
+	});
+}
+
+#[test]
+fn options() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	struct Foo {
+		u32: Option<u32>,
+		bool: Option<bool>,
+		char: Option<char>,
+		non_zero: Option<NonZeroU32>,
+		tuple: Option<(u8, bool)>,
+		nested: Option<Option<bool>>,
+		boxed: Option<Box<u64>>,
+		vec: Option<Vec<u8>>,
+		string: Option<String>,
+	}
+
+	test_valid(&Foo {
+		u32: Some(1),
+		bool: Some(false),
+		char: Some('a'),
+		non_zero: Some(NonZeroU32::new(2).unwrap()),
+		tuple: Some((3, true)),
+		nested: Some(Some(true)),
+		boxed: Some(Box::new(4)),
+		vec: Some(vec![5, 6]),
+		string: Some("xyz".to_string()),
+	});
+
+	test_valid(&Foo {
+		u32: None,
+		bool: None,
+		char: None,
+		non_zero: None,
+		tuple: None,
+		nested: Some(None),
+		boxed: None,
+		vec: None,
+		string: None,
+	});
+
+	test_valid(&Foo {
+		u32: Some(0),
+		bool: Some(true),
+		char: None,
+		non_zero: None,
+		tuple: Some((0, false)),
+		nested: None,
+		boxed: Some(Box::new(0)),
+		vec: Some(vec![]),
+		string: Some("".to_string()),
+	});
+}
+
+#[test]
+fn options_of_types_with_niches() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	struct Inner {
+		num: u32,
+		flag: bool,
+		ch: char,
+	}
+
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	enum Fieldless {
+		One,
+		Two,
+		Three,
+	}
+
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	#[repr(u8)]
+	enum WithFields {
+		Num(u32),
+		Flag(bool),
+		Empty,
+	}
+
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	#[repr(C, u16)]
+	enum WithFieldsC {
+		Num(u64),
+		Empty,
+	}
+
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	struct Foo {
+		inner: Option<Inner>,
+		fieldless: Option<Fieldless>,
+		with_fields: Option<WithFields>,
+		with_fields_c: Option<WithFieldsC>,
+		nested_char: Option<Option<char>>,
+		array: Option<[bool; 2]>,
+		slice: Option<Box<[u8]>>,
+		str: Option<Box<str>>,
+		deque: Option<VecDeque<u16>>,
+		bigint: Option<BigInt>,
+	}
+
+	// All are niche-encoded
+	assert_eq!(mem::size_of::<Option<Inner>>(), mem::size_of::<Inner>());
+	assert_eq!(mem::size_of::<Option<Fieldless>>(), 1);
+	assert_eq!(
+		mem::size_of::<Option<WithFields>>(),
+		mem::size_of::<WithFields>()
+	);
+	assert_eq!(
+		mem::size_of::<Option<WithFieldsC>>(),
+		mem::size_of::<WithFieldsC>()
+	);
+
+	test_valid(&Foo {
+		inner: Some(Inner {
+			num: 1,
+			flag: true,
+			ch: 'x',
+		}),
+		fieldless: Some(Fieldless::Three),
+		with_fields: Some(WithFields::Flag(false)),
+		with_fields_c: Some(WithFieldsC::Num(2)),
+		nested_char: Some(Some('y')),
+		array: Some([true, false]),
+		slice: Some(vec![3, 4].into_boxed_slice()),
+		str: Some("abc".into()),
+		deque: Some(VecDeque::from([5, 6])),
+		bigint: Some(BigInt::new(Sign::Minus, vec![7])),
+	});
+
+	test_valid(&Foo {
+		inner: None,
+		fieldless: None,
+		with_fields: None,
+		with_fields_c: None,
+		nested_char: Some(None),
+		array: None,
+		slice: None,
+		str: None,
+		deque: None,
+		bigint: None,
+	});
+
+	test_valid(&Foo {
+		inner: None,
+		fieldless: Some(Fieldless::One),
+		with_fields: Some(WithFields::Empty),
+		with_fields_c: Some(WithFieldsC::Empty),
+		nested_char: None,
+		array: Some([false, false]),
+		slice: Some(Box::new([])),
+		str: Some("".into()),
+		deque: Some(VecDeque::new()),
+		bigint: Some(BigInt::default()),
+	});
+
+	test_valid(&Some(WithFields::Num(8)));
+
+	// Invalid value in niche is not mistaken for `None`.
+	// `Option<Inner>` is niche-encoded, so `Inner` is at start of the `Option`.
+	let inner = Inner {
+		num: 1,
+		flag: true,
+		ch: 'x',
+	};
+	let flag_offset = &inner.flag as *const bool as usize - &inner as *const Inner as usize;
+	let result = validate_corrupted(&Some(inner), |bytes| bytes[flag_offset] = 2);
+	assert_eq!(result, Err(ValidateError::InvalidBool { value: 2 }));
+
+	let result = validate_corrupted(&Some(Fieldless::One), |bytes| bytes[0] = 4);
+	assert_eq!(result, Err(ValidateError::InvalidDiscriminant));
+}
+
+#[test]
+fn niches() {
+	#[derive(Validate)]
+	#[allow(dead_code)]
+	#[repr(C)]
+	struct Foo {
+		num: u32,
+		flag: bool,
+		ch: char,
+	}
+
+	#[derive(Validate)]
+	#[allow(dead_code)]
+	#[repr(i8)]
+	enum Signed {
+		Minus = -1,
+		Zero,
+		Plus,
+	}
+
+	assert_eq!(<u32 as Validate>::NICHE, None);
+	assert_eq!(<bool as Validate>::NICHE, Some(Niche::new(0, 1, 254)));
+	assert_eq!(
+		<char as Validate>::NICHE,
+		Some(Niche::new(0, 4, (1 << 32) - 0x110000))
+	);
+	assert_eq!(<NonZeroU32 as Validate>::NICHE, Some(Niche::new(0, 4, 1)));
+	assert_eq!(
+		<Box<u8> as Validate>::NICHE,
+		Some(Niche::new(0, PTR_SIZE, 1))
+	);
+	// Largest niche is `ch`
+	assert_eq!(
+		<Foo as Validate>::NICHE,
+		Some(Niche::new(8, 4, (1 << 32) - 0x110000))
+	);
+	assert_eq!(<Signed as Validate>::NICHE, Some(Niche::new(0, 1, 253)));
+	assert_eq!(
+		<Option<bool> as Validate>::NICHE,
+		Some(Niche::new(0, 1, 253))
+	);
+	// Tag of `Option<u32>` is 4 bytes, and can only be 0 or 1
+	assert_eq!(
+		<Option<u32> as Validate>::NICHE,
+		Some(Niche::new(0, 4, u32::MAX as u128 - 1))
+	);
+	assert_eq!(<[bool; 0] as Validate>::NICHE, None);
+	assert_eq!(<() as Validate>::NICHE, None);
+}
+
+#[test]
+fn enums() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	enum Fieldless {
+		One,
+		Two,
+		Three = 10,
+	}
+
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	#[repr(u8)]
+	enum WithFields {
+		Unit,
+		Tuple(u16, Box<u8>),
+		Named { string: String, bool: bool } = 5,
+		Last(Vec<Fieldless>),
+	}
+
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	#[repr(C, u16)]
+	enum WithFieldsC {
+		Unit = 100,
+		Small(u8),
+		Big { big: u64, vec: Vec<WithFields> },
+	}
+
+	for value in [Fieldless::One, Fieldless::Two, Fieldless::Three] {
+		test_valid(&value);
+	}
+
+	test_valid(&WithFields::Unit);
+	test_valid(&WithFields::Tuple(1, Box::new(2)));
+	test_valid(&WithFields::Named {
+		string: "abc".to_string(),
+		bool: true,
+	});
+	test_valid(&WithFields::Last(vec![Fieldless::Three, Fieldless::One]));
+
+	test_valid(&WithFieldsC::Unit);
+	test_valid(&WithFieldsC::Small(3));
+	test_valid(&WithFieldsC::Big {
+		big: u64::MAX,
+		vec: vec![WithFields::Unit, WithFields::Tuple(4, Box::new(5))],
+	});
+}
+
+#[test]
+fn generics() {
+	#[derive(Validate, Debug, PartialEq)]
+	struct Foo<T: Validate> {
+		inner: T,
+	}
+
+	#[derive(Validate, Debug, PartialEq)]
+	#[repr(u8)]
+	enum Bar<T: Validate> {
+		Yes(T),
+		No,
+	}
+
+	// No pointers, so can just copy values into storage
+	fn test_valid_raw<T: Validate + Debug + PartialEq>(input: &T) {
+		let mut storage = AlignedVec::<16, 16, 8, MAX_CAPACITY>::new();
+		storage.push(input);
+		let output: &T = validate(storage.as_slice(), 0).unwrap();
+		assert_eq!(output, input);
+	}
+
+	test_valid_raw(&Foo { inner: 123u32 });
+	test_valid_raw(&Foo { inner: (true, 'x') });
+	test_valid_raw(&Bar::Yes(456u64));
+	test_valid_raw(&Bar::<u8>::No);
+}
+
+#[test]
+fn bigints() {
+	test_valid(&[
+		BigUint::default(),
+		BigUint::new(vec![0x01020304]),
+		BigUint::new(vec![0x05060708, 0x05060708]),
+	]);
+
+	test_valid(&[
+		BigInt::default(),
+		BigInt::new(Sign::Plus, vec![0x01020304]),
+		BigInt::new(Sign::Minus, vec![0x05060708, 0x05060708]),
+	]);
+}
+
+#[test]
+fn minecraft_data() {
+	test_valid(&generate_minecraft_data());
+}
+
+#[test]
+fn invalid_bool() {
+	let result = validate_corrupted(&true, |bytes| bytes[0] = 2);
+	assert_eq!(result, Err(ValidateError::InvalidBool { value: 2 }));
+}
+
+#[test]
+fn invalid_char() {
+	let result = validate_corrupted(&'a', |bytes| {
+		bytes[0..4].copy_from_slice(&0xd800u32.to_ne_bytes())
+	});
+	assert_eq!(result, Err(ValidateError::InvalidChar { value: 0xd800 }));
+}
+
+#[test]
+fn invalid_non_zero() {
+	let result = validate_corrupted(&NonZeroU32::new(1).unwrap(), |bytes| {
+		bytes[0..4].copy_from_slice(&[0; 4])
+	});
+	assert_eq!(result, Err(ValidateError::ZeroNonZero));
+}
+
+#[test]
+fn invalid_discriminant() {
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	enum Fieldless {
+		One,
+		Two,
+	}
+
+	#[derive(Serialize, Validate, Debug, PartialEq)]
+	#[repr(u8)]
+	enum WithFields {
+		One(u8),
+		Two(u16),
+	}
+
+	test_valid(&Fieldless::One);
+	test_valid(&WithFields::One(1));
+
+	let result = validate_corrupted(&Fieldless::Two, |bytes| bytes[0] = 2);
+	assert_eq!(result, Err(ValidateError::InvalidDiscriminant));
+
+	let result = validate_corrupted(&WithFields::Two(1), |bytes| bytes[0] = 2);
+	assert_eq!(result, Err(ValidateError::InvalidDiscriminant));
+
+	// Niche in `bool`
+	let result = validate_corrupted(&Some(true), |bytes| bytes[0] = 3);
+	assert_eq!(result, Err(ValidateError::InvalidBool { value: 3 }));
+
+	let result = validate_corrupted(&Some(1u32), |bytes| {
+		bytes[0..4].copy_from_slice(&2u32.to_ne_bytes())
+	});
+	assert_eq!(result, Err(ValidateError::InvalidDiscriminant));
+}
+
+#[test]
+fn invalid_ptrs() {
+	// Null
+	let result = validate_corrupted(&Box::new(1u32), |bytes| write_usize(bytes, 0, 0));
+	assert_eq!(result, Err(ValidateError::NullPointer));
+
+	// Misaligned
+	let mut addr = 0;
+	let result = validate_corrupted(&Box::new(1u32), |bytes| {
+		addr = read_usize(bytes, 0) + 1;
+		write_usize(bytes, 0, addr);
+	});
+	assert_eq!(result, Err(ValidateError::Misaligned { addr }));
+
+	// Out of bounds
+	let mut addr = 0;
+	let result = validate_corrupted(&Box::new(1u32), |bytes| {
+		addr = read_usize(bytes, 0) + 1024;
+		write_usize(bytes, 0, addr);
+	});
+	assert_eq!(result, Err(ValidateError::OutOfBounds { addr }));
+
+	// Pointing to itself
+	let result = validate_corrupted(&Box::new(1usize), |bytes| {
+		let addr = bytes.as_ptr() as usize;
+		write_usize(bytes, 0, addr);
+	});
+	assert_eq!(result, Err(ValidateError::Overlapping { pos: 0 }));
+
+	// 2 boxes pointing to same value
+	let result = validate_corrupted(&(Box::new(1u64), Box::new(2u64)), |bytes| {
+		let addr = read_usize(bytes, 0);
+		write_usize(bytes, PTR_SIZE, addr);
+	});
+	assert_eq!(result, Err(ValidateError::Overlapping { pos: 16 }));
+}
+
+#[test]
+fn invalid_copied_buffer() {
+	// Pointers point into original buffer, so are out of bounds of the copy
+	let input = vec![1u32, 2, 3];
+	let (pos, storage) = serialize(&input);
+	let mut copy = AlignedVec::<16, 16, 8, MAX_CAPACITY>::new();
+	copy.push_slice(storage.as_slice());
+
+	let result = validate::<Vec<u32>>(copy.as_slice(), pos);
+	assert!(matches!(result, Err(ValidateError::OutOfBounds { .. })));
+}
+
+#[test]
+fn invalid_pos() {
+	let (_, storage) = serialize(&123u64);
+	let addr = storage.as_slice().as_ptr() as usize + 8;
+	let result = validate::<u64>(storage.as_slice(), 8);
+	assert_eq!(result, Err(ValidateError::OutOfBounds { addr }));
+}
+
+#[test]
+fn invalid_len() {
+	// Determine which field of `Vec` is `len` and which is `capacity`
+	let vec = Vec::<u8>::with_capacity(10);
+	let parts: [usize; 3] = unsafe { mem::transmute(vec) };
+	let cap_index = parts.iter().position(|&part| part == 10).unwrap();
+	let len_index = parts.iter().position(|&part| part == 0).unwrap();
+	let _: Vec<u8> = unsafe { mem::transmute(parts) };
+
+	let result = validate_corrupted(&vec![1u8, 2, 3], |bytes| {
+		write_usize(bytes, len_index * PTR_SIZE, 4);
+	});
+	assert_eq!(
+		result,
+		Err(ValidateError::LenExceedsCapacity {
+			len: 4,
+			capacity: 3
+		})
+	);
+
+	let result = validate_corrupted(&"abc".to_string(), |bytes| {
+		write_usize(bytes, cap_index * PTR_SIZE, 2);
+	});
+	assert_eq!(
+		result,
+		Err(ValidateError::LenExceedsCapacity {
+			len: 3,
+			capacity: 2
+		})
+	);
+
+	let capacity = isize::MAX as usize + 1;
+	let result = validate_corrupted(&vec![1u8, 2, 3], |bytes| {
+		write_usize(bytes, cap_index * PTR_SIZE, capacity);
+	});
+	assert_eq!(result, Err(ValidateError::CapacityOverflow { capacity }));
+}
+
+#[test]
+fn invalid_utf8() {
+	let result = validate_corrupted(&"abc".to_string(), |bytes| bytes[PTR_SIZE * 3 + 1] = 0xff);
+	assert_eq!(
+		result,
+		Err(ValidateError::InvalidUtf8 { pos: PTR_SIZE * 3 })
+	);
+
+	let input: Box<str> = "abc".into();
+	let result = validate_corrupted(&input, |bytes| bytes[PTR_SIZE * 2 + 1] = 0xff);
+	assert_eq!(
+		result,
+		Err(ValidateError::InvalidUtf8 { pos: PTR_SIZE * 2 })
+	);
+}
@@ -0,0 +1,271 @@
+use std::{
+	cell::RefCell,
+	fmt::Debug,
+	io::{self, ErrorKind, Write},
+	rc::Rc,
+};
+
+mod common;
+use common::{generate_minecraft_data, tests, Test};
+use ser_raw::{
+	storage::{AlignedVec, ContiguousStorage, Storage, WriteStorage},
+	util::aligned_max_capacity,
+	Deserialize, Deserializer, PureCopyDeserializer, PureCopySerializer, Serialize, SerializeError,
+	Serializer,
+};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type Store<W> = WriteStorage<W, 16, 16, 8, MAX_CAPACITY>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type De<'a> = PureCopyDeserializer<'a, 16, 16, 8, MAX_CAPACITY>;
+
+#[derive(Serializer)]
+#[ser_type(pure_copy)]
+struct StreamSer<W: Write> {
+	#[ser_storage(Store<W>)]
+	storage: Store<W>,
+}
+
+/// Serialize with a small chunk size, so output is written in many chunks
+fn serialize<T: Serialize<StreamSer<Vec<u8>>>>(value: &T) -> Vec<u8> {
+	let ser = StreamSer {
+		storage: Store::with_chunk_size(Vec::new(), 64),
+	};
+	let (pos, storage) = ser.serialize(value);
+	assert_eq!(pos, 0);
+	storage.finish().unwrap()
+}
+
+fn test_serialize<T>(input: &T, _test: Test, _test_num: usize)
+where T: Serialize<StreamSer<Vec<u8>>>
+		+ Serialize<PureCopySer>
+		+ for<'a> Deserialize<De<'a>>
+		+ Debug
+		+ PartialEq {
+	let output = serialize(input);
+
+	// Same length as output of `PureCopySerializer`
+	let (_, expected) = PureCopySer::new().serialize(input);
+	assert_eq!(output.len(), expected.pos());
+
+	// Test deserializing output recreates input
+	let mut de = unsafe { De::new(output.as_slice()) };
+	let value: T = de.deserialize_value();
+	assert_eq!(&value, input);
+	assert_eq!(de.pos(), output.len());
+}
+
+tests!(test_serialize, no_locks);
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Item {
+	tag: u8,
+	wide: Vec<Wide>,
+	name: String,
+	nums: Vec<u16>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+#[repr(align(16))]
+struct Wide(u32);
+
+fn create_items(count: usize) -> Vec<Item> {
+	(0..count)
+		.map(|i| {
+			Item {
+				tag: i as u8,
+				wide: (0..(i % 3)).map(|n| Wide(n as u32)).collect(),
+				name: "x".repeat(i % 13),
+				nums: (0..(i % 7) as u16).collect(),
+			}
+		})
+		.collect()
+}
+
+#[test]
+fn output_matches_aligned_vec() {
+	let input = create_items(200);
+
+	// Zero `AlignedVec`'s buffer so padding bytes are zero, as in `WriteStorage`
+	let mut storage = AlignedVec::with_capacity(64 * 1024);
+	storage.push_slice(&[0u8; 64 * 1024]);
+	storage.clear();
+	let (_, expected) = PureCopySer::from_storage(storage).serialize(&input);
+	assert_eq!(expected.capacity(), 64 * 1024);
+
+	for chunk_size in [0, 16, 24, 100, 4096, 1024 * 1024] {
+		let ser = StreamSer {
+			storage: Store::with_chunk_size(Vec::new(), chunk_size),
+		};
+		let (_, storage) = ser.serialize(&input);
+		assert_eq!(storage.pos(), expected.pos());
+		let output = storage.finish().unwrap();
+		assert_eq!(output.as_slice(), expected.as_slice());
+	}
+}
+
+/// Writer which records length of each write
+#[derive(Clone, Default)]
+struct RecordingWriter {
+	writes: Rc<RefCell<Vec<usize>>>,
+	flushes: Rc<RefCell<usize>>,
+}
+
+impl Write for RecordingWriter {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.writes.borrow_mut().push(buf.len());
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		*self.flushes.borrow_mut() += 1;
+		Ok(())
+	}
+}
+
+#[test]
+fn writes_while_serializing() {
+	let input = create_items(100);
+	let writer = RecordingWriter::default();
+	let mut ser = StreamSer {
+		storage: Store::with_chunk_size(writer.clone(), 256),
+	};
+	ser.serialize_value(&input);
+
+	// Output is written before serialization is finished, maintaining alignment.
+	// Top-level slice of `Item`s is larger than chunk, so is written directly.
+	let len = ser.storage().pos();
+	let written = writer.writes.borrow().iter().sum::<usize>();
+	assert!(writer.writes.borrow().len() > 10);
+	assert!(writer.writes.borrow().iter().any(|&len| len > 256));
+	assert_eq!(written % 16, 0);
+	assert!(len - written < 256);
+
+	ser.storage_mut().flush().unwrap();
+	assert_eq!(*writer.flushes.borrow(), 1);
+	let written = writer.writes.borrow().iter().sum::<usize>();
+	assert!(len - written < 16);
+
+	ser.into_storage().finish().unwrap();
+	assert_eq!(writer.writes.borrow().iter().sum::<usize>(), len);
+	assert_eq!(*writer.flushes.borrow(), 2);
+}
+
+#[test]
+fn value_larger_than_chunk() {
+	let input = vec![0xabu8; 1000];
+	let ser = StreamSer {
+		storage: Store::with_chunk_size(Vec::new(), 64),
+	};
+	let (_, storage) = ser.serialize(&input);
+	let output = storage.finish().unwrap();
+	assert_eq!(output.len(), 24 + 1000);
+	assert_eq!(&output[24..], input.as_slice());
+}
+
+#[test]
+fn drop_writes_remaining_output() {
+	let writer = RecordingWriter::default();
+	let ser = StreamSer {
+		storage: Store::from_writer(writer.clone()),
+	};
+	let (_, storage) = ser.serialize(&create_items(10));
+	let len = storage.pos();
+	assert!(writer.writes.borrow().is_empty());
+	drop(storage);
+	assert_eq!(writer.writes.borrow().iter().sum::<usize>(), len);
+}
+
+/// Writer which fails once more than `limit` bytes have been written
+struct FailingWriter {
+	written: usize,
+	limit: usize,
+}
+
+impl Write for FailingWriter {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		if self.written + buf.len() > self.limit {
+			return Err(io::Error::new(ErrorKind::BrokenPipe, "consumer gone"));
+		}
+		self.written += buf.len();
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+#[test]
+fn write_error_is_reported() {
+	let writer = FailingWriter {
+		written: 0,
+		limit: 1000,
+	};
+	let mut ser = StreamSer {
+		storage: Store::with_chunk_size(writer, 256),
+	};
+	// Serialization continues after error, but output is discarded
+	ser.serialize_value(&create_items(100));
+	let pos = ser.storage().pos();
+	assert!(pos > 1000);
+
+	let storage = ser.storage_mut();
+	assert_eq!(storage.flush().unwrap_err().kind(), ErrorKind::BrokenPipe);
+	assert!(storage.get_ref().written < 1000);
+	assert_eq!(storage.pos(), pos);
+
+	let err = ser.into_storage().finish().err().unwrap();
+	assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+}
+
+#[test]
+#[should_panic(expected = "Cannot clear WriteStorage after output has been written")]
+fn clear_after_write_panics() {
+	let mut storage = Store::with_chunk_size(Vec::new(), 16);
+	storage.push(&1u64);
+	storage.push(&[2u64; 2]);
+	storage.clear();
+}
+
+#[test]
+fn rollback_within_buffer() {
+	let mut ser = StreamSer {
+		storage: Store::with_chunk_size(Vec::new(), 256),
+	};
+	ser.serialize_value(&1u64);
+	let checkpoint = ser.checkpoint();
+	ser.serialize_value(&[2u64; 4]);
+	ser.rollback(checkpoint).unwrap();
+	assert_eq!(ser.storage().pos(), 8);
+	ser.serialize_value(&3u64);
+
+	let output = ser.into_storage().finish().unwrap();
+	assert_eq!(output.as_slice(), [1u64, 3].map(u64::to_ne_bytes).concat());
+}
+
+#[test]
+fn rollback_after_write_fails() {
+	let writer = RecordingWriter::default();
+	let mut ser = StreamSer {
+		storage: Store::with_chunk_size(writer.clone(), 64),
+	};
+	ser.serialize_value(&1u64);
+	let checkpoint = ser.checkpoint();
+	ser.serialize_value(&create_items(10));
+	assert!(!writer.writes.borrow().is_empty());
+
+	// Output after checkpoint has been written, so cannot be rolled back.
+	// Serializer is unchanged.
+	let pos = ser.storage().pos();
+	assert_eq!(
+		ser.rollback(checkpoint),
+		Err(SerializeError::AlreadyWritten)
+	);
+	assert_eq!(ser.storage().pos(), pos);
+
+	// Serialization can continue
+	ser.serialize_value(&2u64);
+	ser.into_storage().finish().unwrap();
+	assert_eq!(writer.writes.borrow().iter().sum::<usize>(), pos + 8);
+}
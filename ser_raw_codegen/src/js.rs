@@ -0,0 +1,358 @@
+use std::fmt::Write;
+
+use ser_raw::{
+	header::Endianness,
+	layout::{Field, TypeDef, TypeKind, Variant},
+};
+
+use crate::{is_tuple, property_key, string_literal, Primitive, Types};
+
+/// Generate JavaScript module.
+///
+/// Each type in schema gets a `decode_<ident>(pos)` function which decodes a
+/// value of that type at `pos`.
+///
+/// Output of `PtrOffsetSerializer` and `PureCopySerializer` differ only in how
+/// the location of data behind pointers is found. For `PtrOffsetSerializer`,
+/// pointers have been replaced with offsets of the data in the buffer.
+/// `PtrOffset32Serializer`'s output is the same, except offsets are `u32`s.
+/// For `PureCopySerializer`, data behind pointers is written in order, so is
+/// located by keeping a cursor and advancing it as each is read, following the
+/// same rules as `Storage::push_slice`. Depth-first order of object and array
+/// literals' evaluation matches the order serializer writes values in.
+pub(crate) fn generate(types: &Types) -> String {
+	let schema = types.schema;
+	let root = types.get(types.root());
+	let root_ident = &types.idents[types.root()];
+	let le = schema.endianness() == Endianness::Little;
+
+	let mut js = String::new();
+	writeln!(js, "// Generated by ser_raw_codegen. Do not edit.").unwrap();
+	writeln!(js, "// Root type: {}\n", root.name).unwrap();
+
+	js.push_str(PREAMBLE);
+
+	let read_usize = if schema.ptr_size() == 4 {
+		format!("view.getUint32(pos, {le})")
+	} else if le {
+		"view.getUint32(pos, true) + view.getUint32(pos + 4, true) * 4294967296".to_string()
+	} else {
+		"view.getUint32(pos + 4, false) + view.getUint32(pos, false) * 4294967296".to_string()
+	};
+
+	js.push_str(&function(
+		"export function deserializePtrOffset(buffer, pos = 0)",
+		&[
+			"init(buffer, false, 1, false);",
+			&format!("return decode_{root_ident}(pos);"),
+		],
+	));
+	js.push('\n');
+	js.push_str(&function(
+		"export function deserializePtrOffset32(buffer, pos = 0)",
+		&[
+			"init(buffer, false, 1, true);",
+			&format!("return decode_{root_ident}(pos);"),
+		],
+	));
+	js.push('\n');
+	js.push_str(&function(
+		"export function deserializePureCopy(buffer, pos = 0, valueAlignment = 8)",
+		&[
+			"init(buffer, true, valueAlignment, false);",
+			"cursor = pos;",
+			&format!(
+				"return decode_{root_ident}(take({}, {}, 1));",
+				root.size, root.align
+			),
+		],
+	));
+	js.push('\n');
+	js.push_str(&function(
+		"function readUsize(pos)",
+		&[&format!("return {read_usize};")],
+	));
+	js.push('\n');
+	js.push_str(&function(
+		"function readOffset(pos)",
+		&[&format!(
+			"return offset32 ? view.getUint32(pos, {le}) : readUsize(pos);"
+		)],
+	));
+
+	for (type_def, ident) in schema.types().iter().zip(&types.idents) {
+		let body = match &type_def.kind {
+			TypeKind::Primitive => vec![primitive_body(type_def, types, le)],
+			TypeKind::Struct { fields } => {
+				vec![format!("return {};", fields_expr(fields, types, true))]
+			}
+			TypeKind::Enum { tag_size, variants } => enum_body(type_def, *tag_size, variants, types, le),
+			TypeKind::Box { inner } => {
+				let inner_def = types.get(types.index_of(inner));
+				vec![format!(
+					"return decode_{}(pureCopy ? take({}, {}, 1) : readOffset(pos));",
+					types.ident(inner),
+					inner_def.size,
+					inner_def.align
+				)]
+			}
+			TypeKind::Vec {
+				inner,
+				ptr_offset,
+				len_offset,
+				..
+			}
+			| TypeKind::BoxedSlice {
+				inner,
+				ptr_offset,
+				len_offset,
+			} => {
+				let inner_def = types.get(types.index_of(inner));
+				let (size, align) = (inner_def.size, inner_def.align);
+				vec![
+					format!("const len = readUsize(pos + {len_offset});"),
+					"if (len === 0) return [];".to_string(),
+					format!(
+						"const ptr = pureCopy ? take({size}, {align}, len) : readOffset(pos + {ptr_offset});"
+					),
+					"const arr = new Array(len);".to_string(),
+					"for (let i = 0; i < len; i++) {".to_string(),
+					format!(
+						"\tarr[i] = decode_{}(ptr + i * {size});",
+						types.ident(inner)
+					),
+					"}".to_string(),
+					"return arr;".to_string(),
+				]
+			}
+			TypeKind::String {
+				ptr_offset,
+				len_offset,
+				..
+			}
+			| TypeKind::BoxedStr {
+				ptr_offset,
+				len_offset,
+			} => {
+				vec![
+					format!("const len = readUsize(pos + {len_offset});"),
+					"if (len === 0) return \"\";".to_string(),
+					format!("const ptr = pureCopy ? take(1, 1, len) : readOffset(pos + {ptr_offset});"),
+					"return textDecoder.decode(bytes.subarray(ptr, ptr + len));".to_string(),
+				]
+			}
+			TypeKind::Option {
+				inner,
+				payload_offset,
+				none,
+			} => {
+				let is_none = if none.is_empty() {
+					"true".to_string()
+				} else {
+					none
+						.iter()
+						.map(|(offset, byte)| format!("bytes[pos + {offset}] === {byte}"))
+						.collect::<Vec<_>>()
+						.join(" && ")
+				};
+				vec![
+					format!("if ({is_none}) return null;"),
+					format!(
+						"return decode_{}(pos + {payload_offset});",
+						types.ident(inner)
+					),
+				]
+			}
+			TypeKind::Array { inner, len } => {
+				let size = types.get(types.index_of(inner)).size;
+				vec![
+					format!("const arr = new Array({len});"),
+					format!("for (let i = 0; i < {len}; i++) {{"),
+					format!(
+						"\tarr[i] = decode_{}(pos + i * {size});",
+						types.ident(inner)
+					),
+					"}".to_string(),
+					"return arr;".to_string(),
+				]
+			}
+		};
+
+		writeln!(js, "\n// {}", type_def.name).unwrap();
+		js.push_str(&function(&format!("function decode_{ident}(pos)"), &body));
+	}
+
+	js
+}
+
+/// Get code for a function with signature `signature` and lines `body`.
+fn function<S: AsRef<str>>(signature: &str, body: &[S]) -> String {
+	let mut out = format!("{signature} {{\n");
+	for line in body {
+		writeln!(out, "\t{}", line.as_ref()).unwrap();
+	}
+	out.push_str("}\n");
+	out
+}
+
+/// Shared state and helpers. Same for all schemas.
+const PREAMBLE: &str = "\
+const textDecoder = new TextDecoder();
+
+let view, bytes, pureCopy, valueAlignment, cursor, offset32;
+
+function init(buffer, isPureCopy, alignment, isOffset32) {
+	if (ArrayBuffer.isView(buffer)) {
+		view = new DataView(buffer.buffer, buffer.byteOffset, buffer.byteLength);
+		bytes = new Uint8Array(buffer.buffer, buffer.byteOffset, buffer.byteLength);
+	} else {
+		view = new DataView(buffer);
+		bytes = new Uint8Array(buffer);
+	}
+	pureCopy = isPureCopy;
+	valueAlignment = alignment;
+	offset32 = isOffset32;
+}
+
+// Get position of next value(s) in `PureCopySerializer` output, and advance
+// cursor past them. Follows same rules as `Storage::push_slice`.
+function take(size, align, len) {
+	if (size === 0) return cursor;
+	if (align > valueAlignment) cursor = alignUp(cursor, align);
+	const pos = cursor;
+	cursor += size * len;
+	if (size % valueAlignment !== 0) cursor = alignUp(cursor, valueAlignment);
+	return pos;
+}
+
+function alignUp(pos, align) {
+	const rem = pos % align;
+	return rem === 0 ? pos : pos + align - rem;
+}
+
+";
+
+fn primitive_body(type_def: &TypeDef, types: &Types, le: bool) -> String {
+	let primitive = Primitive::from_name(&type_def.name, types.schema.ptr_size()).unwrap();
+	let expr = match primitive {
+		Primitive::U8 => "bytes[pos]".to_string(),
+		Primitive::I8 => "view.getInt8(pos)".to_string(),
+		Primitive::U16 => format!("view.getUint16(pos, {le})"),
+		Primitive::I16 => format!("view.getInt16(pos, {le})"),
+		Primitive::U32 => format!("view.getUint32(pos, {le})"),
+		Primitive::I32 => format!("view.getInt32(pos, {le})"),
+		Primitive::U64 => format!("view.getBigUint64(pos, {le})"),
+		Primitive::I64 => format!("view.getBigInt64(pos, {le})"),
+		Primitive::U128 | Primitive::I128 => {
+			let (low, high) = if le { (0, 8) } else { (8, 0) };
+			let value = format!(
+				"(view.getBigUint64(pos + {high}, {le}) << 64n) | view.getBigUint64(pos + {low}, {le})"
+			);
+			if matches!(primitive, Primitive::I128) {
+				format!("BigInt.asIntN(128, {value})")
+			} else {
+				value
+			}
+		}
+		Primitive::F32 => format!("view.getFloat32(pos, {le})"),
+		Primitive::F64 => format!("view.getFloat64(pos, {le})"),
+		Primitive::Bool => "bytes[pos] !== 0".to_string(),
+		Primitive::Char => format!("String.fromCodePoint(view.getUint32(pos, {le}))"),
+		Primitive::Unit => "null".to_string(),
+	};
+	format!("return {expr};")
+}
+
+/// Get expression for value of struct or enum variant's fields.
+/// Named fields produce an object, unnamed fields an array, and no fields
+/// `null`. If `multiline` is `true`, object properties are each on their own
+/// line.
+fn fields_expr(fields: &[Field], types: &Types, multiline: bool) -> String {
+	if fields.is_empty() {
+		return "null".to_string();
+	}
+
+	let values = fields.iter().map(|field| {
+		format!(
+			"decode_{}(pos + {})",
+			types.ident(&field.type_name),
+			field.offset
+		)
+	});
+	if is_tuple(fields) {
+		format!("[{}]", values.collect::<Vec<_>>().join(", "))
+	} else {
+		let props = fields
+			.iter()
+			.zip(values)
+			.map(|(field, value)| format!("{}: {}", property_key(&field.name), value))
+			.collect::<Vec<_>>();
+		if multiline {
+			format!("{{\n\t\t{},\n\t}}", props.join(",\n\t\t"))
+		} else {
+			format!("{{ {} }}", props.join(", "))
+		}
+	}
+}
+
+fn enum_body(
+	type_def: &TypeDef,
+	tag_size: usize,
+	variants: &[Variant],
+	types: &Types,
+	le: bool,
+) -> Vec<String> {
+	let is_fieldless = variants.iter().all(|variant| variant.fields.is_empty());
+	let variant_expr = |variant: &Variant| {
+		let name = string_literal(&variant.name);
+		if is_fieldless {
+			name
+		} else if variant.fields.is_empty() {
+			format!("{{ type: {name} }}")
+		} else if is_tuple(&variant.fields) && variant.fields.len() == 1 {
+			let field = &variant.fields[0];
+			format!(
+				"{{ type: {name}, value: decode_{}(pos + {}) }}",
+				types.ident(&field.type_name),
+				field.offset
+			)
+		} else {
+			format!(
+				"{{ type: {name}, value: {} }}",
+				fields_expr(&variant.fields, types, false)
+			)
+		}
+	};
+
+	let error = format!(
+		"throw new Error({});",
+		string_literal(&format!("Invalid discriminant for `{}`", type_def.name))
+	);
+
+	if tag_size == 0 {
+		return match variants.first() {
+			Some(variant) => vec![format!("return {};", variant_expr(variant))],
+			None => vec![error],
+		};
+	}
+
+	let tag = match tag_size {
+		1 => "bytes[pos]".to_string(),
+		2 => format!("view.getUint16(pos, {le})"),
+		4 => format!("view.getUint32(pos, {le})"),
+		_ => format!("view.getBigUint64(pos, {le})"),
+	};
+	let suffix = if tag_size == 8 { "n" } else { "" };
+
+	let mut body = vec![format!("switch ({tag}) {{")];
+	for variant in variants {
+		body.push(format!(
+			"\tcase {}{suffix}: return {};",
+			variant.discriminant,
+			variant_expr(variant)
+		));
+	}
+	body.push("}".to_string());
+	body.push(error);
+	body
+}
@@ -0,0 +1,302 @@
+//! # ser_raw_codegen
+//!
+//! Generate JavaScript decoders for output of [`ser_raw`]'s serializers.
+//!
+//! Takes a [`Schema`] describing the layout of a root type (see
+//! [`ser_raw::layout`]), and generates:
+//!
+//! * A JavaScript module which decodes output of [`PtrOffsetSerializer`],
+//!   [`PtrOffset32Serializer`] and [`PureCopySerializer`] from an `ArrayBuffer`
+//!   (or any `ArrayBufferView`, e.g. a Node.js `Buffer`).
+//! * TypeScript type definitions for that module.
+//!
+//! # Example
+//!
+//! ```
+//! use ser_raw::{layout::Schema, Layout, Serialize};
+//!
+//! #[derive(Serialize, Layout)]
+//! struct Foo {
+//!     small: u8,
+//!     names: Vec<String>,
+//! }
+//!
+//! let schema = Schema::new::<Foo>();
+//! let generated = ser_raw_codegen::generate(&schema).unwrap();
+//! assert!(generated.js.contains("export function deserializePtrOffset("));
+//! assert!(generated.dts.contains("export interface Foo {"));
+//! ```
+//!
+//! Schema can also be exported as JSON with [`Schema::to_json`], and decoders
+//! generated from the JSON file with the `ser_raw_codegen` binary:
+//!
+//! ```sh
+//! ser_raw_codegen schema.json out_dir --name decoder
+//! ```
+//!
+//! # Generated module
+//!
+//! The JavaScript module exports 3 functions:
+//!
+//! * `deserializePtrOffset(buffer, pos = 0)`: Decode value at `pos` in output
+//!   of [`PtrOffsetSerializer`].
+//! * `deserializePtrOffset32(buffer, pos = 0)`: Decode value at `pos` in output
+//!   of [`PtrOffset32Serializer`]. Offsets are read with `getUint32`.
+//! * `deserializePureCopy(buffer, pos = 0, valueAlignment = 8)`: Decode output
+//!   of [`PureCopySerializer`], starting at `pos`. `valueAlignment` must be the
+//!   serializer's `VALUE_ALIGNMENT` const parameter.
+//!
+//! Rust types are converted to JavaScript values as follows:
+//!
+//! * Integers up to 32 bits, `f32` and `f64`: `number`
+//! * 64-bit and 128-bit integers, and `usize` / `isize` on 64-bit systems:
+//!   `bigint`
+//! * `bool`: `boolean`
+//! * `char` and `String`: `string`
+//! * `()`, and structs with no fields: `null`
+//! * `Box<T>`: `T`
+//! * `Vec<T>` and `[T; N]`: Arrays
+//! * `Option<T>`: `T` or `null`
+//! * Structs: Objects
+//! * Tuples and tuple structs: Arrays
+//! * Fieldless enums: Name of variant as a string
+//! * Enums with fields: `{ type: "Variant", value: ... }`, where `value` is an
+//!   object for variants with named fields, value of the field for variants
+//!   with 1 unnamed field, or an array for variants with multiple unnamed
+//!   fields. `value` is omitted for variants with no fields.
+//!
+//! [`PtrOffsetSerializer`]: ser_raw::PtrOffsetSerializer
+//! [`PtrOffset32Serializer`]: ser_raw::PtrOffset32Serializer
+//! [`PureCopySerializer`]: ser_raw::PureCopySerializer
+
+#![allow(clippy::tabs_in_doc_comments)]
+
+use std::{collections::HashSet, error::Error, fmt};
+
+use ser_raw::layout::{Field, Schema, TypeDef, TypeKind};
+
+mod js;
+mod ts;
+
+/// Generated code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Generated {
+	/// JavaScript module (ES module syntax)
+	pub js: String,
+	/// TypeScript type definitions for JavaScript module
+	pub dts: String,
+}
+
+/// Generate JavaScript decoder and TypeScript type definitions for root type
+/// of `schema`.
+pub fn generate(schema: &Schema) -> Result<Generated, CodegenError> {
+	let types = Types::new(schema)?;
+	Ok(Generated {
+		js: js::generate(&types),
+		dts: ts::generate(&types),
+	})
+}
+
+/// Error returned by [`generate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodegenError {
+	/// Schema contains a primitive type which is not supported
+	UnknownPrimitive { name: String },
+	/// Schema contains an enum with a discriminant of unsupported size
+	UnsupportedTagSize { name: String, size: usize },
+}
+
+impl fmt::Display for CodegenError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::UnknownPrimitive { name } => write!(f, "Unknown primitive type `{name}`"),
+			Self::UnsupportedTagSize { name, size } => {
+				write!(
+					f,
+					"Enum `{name}` has discriminant of unsupported size {size}"
+				)
+			}
+		}
+	}
+}
+
+impl Error for CodegenError {}
+
+/// Schema's types, with identifiers to use for them in generated code.
+struct Types<'a> {
+	schema: &'a Schema,
+	idents: Vec<String>,
+}
+
+impl<'a> Types<'a> {
+	fn new(schema: &'a Schema) -> Result<Self, CodegenError> {
+		// Check all types are supported
+		for type_def in schema.types() {
+			match &type_def.kind {
+				TypeKind::Primitive => {
+					Primitive::from_name(&type_def.name, schema.ptr_size())?;
+				}
+				TypeKind::Enum { tag_size, .. } => {
+					if ![0, 1, 2, 4, 8].contains(tag_size) {
+						return Err(CodegenError::UnsupportedTagSize {
+							name: type_def.name.clone(),
+							size: *tag_size,
+						});
+					}
+				}
+				_ => {}
+			}
+		}
+
+		// Convert type names to identifiers.
+		// Add index as suffix if 2 types' names convert to same identifier.
+		let mut used = HashSet::new();
+		let idents = schema
+			.types()
+			.iter()
+			.enumerate()
+			.map(|(index, type_def)| {
+				let mut ident = to_ident(&type_def.name);
+				if !used.insert(ident.clone()) {
+					ident = format!("{ident}_{index}");
+					used.insert(ident.clone());
+				}
+				ident
+			})
+			.collect();
+
+		Ok(Self { schema, idents })
+	}
+
+	fn root(&self) -> usize {
+		self.index_of(self.schema.root())
+	}
+
+	fn get(&self, index: usize) -> &TypeDef {
+		&self.schema.types()[index]
+	}
+
+	fn index_of(&self, name: &str) -> usize {
+		self
+			.schema
+			.types()
+			.iter()
+			.position(|type_def| type_def.name == name)
+			.expect("Type missing from schema")
+	}
+
+	fn ident(&self, name: &str) -> &str {
+		&self.idents[self.index_of(name)]
+	}
+}
+
+/// Convert type name to an identifier e.g. `Vec<Option<u8>>` ->
+/// `Vec_Option_u8`, `(u8, [u16; 2])` -> `tuple_u8_array_u16_2`.
+fn to_ident(name: &str) -> String {
+	let name = name
+		.replace("()", "unit")
+		.replace('(', "tuple_")
+		.replace('[', "array_");
+	let mut ident = String::new();
+	for c in name.chars() {
+		if c.is_ascii_alphanumeric() {
+			ident.push(c);
+		} else if !ident.is_empty() && !ident.ends_with('_') {
+			ident.push('_');
+		}
+	}
+	while ident.ends_with('_') {
+		ident.pop();
+	}
+	if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+		ident.insert(0, '_');
+	}
+	ident
+}
+
+/// Get JavaScript string literal for `str`.
+fn string_literal(str: &str) -> String {
+	let mut out = String::from('"');
+	for c in str.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			c if (c as u32) < 0x20 || c == '\u{2028}' || c == '\u{2029}' => {
+				out.push_str(&format!("\\u{:04x}", c as u32))
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+	out
+}
+
+/// Get property key for an object literal or interface. Quoted unless it's a
+/// plain ASCII identifier.
+fn property_key(name: &str) -> String {
+	let is_ident = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+		&& name
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+	if is_ident {
+		name.to_string()
+	} else {
+		string_literal(name)
+	}
+}
+
+/// Get whether struct fields are unnamed (tuple or tuple struct).
+fn is_tuple(fields: &[Field]) -> bool {
+	fields.first().map_or(false, |field| field.name == "0")
+}
+
+/// Primitive types.
+#[derive(Clone, Copy)]
+enum Primitive {
+	U8,
+	I8,
+	U16,
+	I16,
+	U32,
+	I32,
+	U64,
+	I64,
+	U128,
+	I128,
+	F32,
+	F64,
+	Bool,
+	Char,
+	Unit,
+}
+
+impl Primitive {
+	fn from_name(name: &str, ptr_size: usize) -> Result<Self, CodegenError> {
+		let name = name
+			.strip_prefix("NonZero")
+			.map_or(name.to_string(), |name| name.to_ascii_lowercase());
+		let primitive = match name.as_str() {
+			"u8" => Self::U8,
+			"i8" => Self::I8,
+			"u16" => Self::U16,
+			"i16" => Self::I16,
+			"u32" => Self::U32,
+			"i32" => Self::I32,
+			"u64" => Self::U64,
+			"i64" => Self::I64,
+			"u128" => Self::U128,
+			"i128" => Self::I128,
+			"usize" if ptr_size == 4 => Self::U32,
+			"usize" => Self::U64,
+			"isize" if ptr_size == 4 => Self::I32,
+			"isize" => Self::I64,
+			"f32" => Self::F32,
+			"f64" => Self::F64,
+			"bool" => Self::Bool,
+			"char" => Self::Char,
+			"()" => Self::Unit,
+			_ => return Err(CodegenError::UnknownPrimitive { name }),
+		};
+		Ok(primitive)
+	}
+}
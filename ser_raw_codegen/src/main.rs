@@ -0,0 +1,52 @@
+//! Generate JavaScript decoder and TypeScript type definitions from a layout
+//! schema JSON file.
+//!
+//! Usage: `ser_raw_codegen <schema.json> <out_dir> [--name <name>]`
+//!
+//! Writes `<name>.js` and `<name>.d.ts` to `out_dir`. `name` defaults to
+//! `decoder`.
+
+use std::{env, fs, path::PathBuf, process};
+
+use ser_raw::layout::Schema;
+
+const USAGE: &str = "Usage: ser_raw_codegen <schema.json> <out_dir> [--name <name>]";
+
+fn main() {
+	if let Err(message) = run() {
+		eprintln!("{message}");
+		process::exit(1);
+	}
+}
+
+fn run() -> Result<(), String> {
+	let mut paths = vec![];
+	let mut name = "decoder".to_string();
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--name" => name = args.next().ok_or(USAGE)?,
+			"-h" | "--help" => {
+				println!("{USAGE}");
+				return Ok(());
+			}
+			_ => paths.push(PathBuf::from(arg)),
+		}
+	}
+	let [schema_path, out_dir]: [PathBuf; 2] = paths.try_into().map_err(|_| USAGE)?;
+
+	let json = fs::read_to_string(&schema_path)
+		.map_err(|err| format!("Failed to read {}: {err}", schema_path.display()))?;
+	let schema = Schema::from_json(&json)
+		.map_err(|err| format!("Invalid schema in {}: {err}", schema_path.display()))?;
+	let generated = ser_raw_codegen::generate(&schema).map_err(|err| err.to_string())?;
+
+	fs::create_dir_all(&out_dir)
+		.map_err(|err| format!("Failed to create {}: {err}", out_dir.display()))?;
+	for (ext, code) in [("js", &generated.js), ("d.ts", &generated.dts)] {
+		let path = out_dir.join(format!("{name}.{ext}"));
+		fs::write(&path, code).map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,186 @@
+use std::fmt::Write;
+
+use ser_raw::layout::{Field, TypeKind, Variant};
+
+use crate::{is_tuple, property_key, string_literal, Primitive, Types};
+
+/// Generate TypeScript type definitions for JavaScript module.
+///
+/// User-defined structs and enums get a named type declaration.
+/// All other types are expressed inline e.g. `Vec<Option<u8>>` is
+/// `(number | null)[]`.
+pub(crate) fn generate(types: &Types) -> String {
+	let schema = types.schema;
+
+	let mut dts = String::new();
+	writeln!(dts, "// Generated by ser_raw_codegen. Do not edit.").unwrap();
+	writeln!(dts, "// Root type: {}\n", schema.root()).unwrap();
+
+	let root_type = type_expr(schema.root(), types);
+	let buffer_param = "\tbuffer: ArrayBuffer | ArrayBufferView,";
+	writeln!(dts, "export declare function deserializePtrOffset(").unwrap();
+	writeln!(dts, "{buffer_param}\n\tpos?: number,\n): {root_type};\n").unwrap();
+	writeln!(dts, "export declare function deserializePtrOffset32(").unwrap();
+	writeln!(dts, "{buffer_param}\n\tpos?: number,\n): {root_type};\n").unwrap();
+	writeln!(dts, "export declare function deserializePureCopy(").unwrap();
+	writeln!(
+		dts,
+		"{buffer_param}\n\tpos?: number,\n\tvalueAlignment?: number,"
+	)
+	.unwrap();
+	writeln!(dts, "): {root_type};").unwrap();
+
+	for (type_def, ident) in schema.types().iter().zip(&types.idents) {
+		if !is_named(&type_def.name, &type_def.kind) {
+			continue;
+		}
+
+		dts.push('\n');
+		match &type_def.kind {
+			TypeKind::Struct { fields } if !fields.is_empty() && !is_tuple(fields) => {
+				writeln!(dts, "export interface {ident} {{").unwrap();
+				for field in fields {
+					writeln!(
+						dts,
+						"\t{}: {};",
+						property_key(&field.name),
+						type_expr(&field.type_name, types)
+					)
+					.unwrap();
+				}
+				dts.push_str("}\n");
+			}
+			TypeKind::Struct { fields } => {
+				writeln!(dts, "export type {ident} = {};", fields_expr(fields, types)).unwrap();
+			}
+			TypeKind::Enum { variants, .. } => {
+				// Unions of variants with fields start on a new line
+				let expr = enum_expr(variants, types);
+				let sep = if expr.starts_with('\n') { "" } else { " " };
+				writeln!(dts, "export type {ident} ={sep}{expr};").unwrap();
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	dts
+}
+
+/// Get whether type gets a named declaration.
+/// Only user-defined structs and enums do. Tuples are also structs, but their
+/// names start with `(`.
+fn is_named(name: &str, kind: &TypeKind) -> bool {
+	match kind {
+		TypeKind::Struct { .. } => !name.starts_with('('),
+		TypeKind::Enum { .. } => true,
+		_ => false,
+	}
+}
+
+/// Get TypeScript type expression for a type.
+fn type_expr(name: &str, types: &Types) -> String {
+	let index = types.index_of(name);
+	let type_def = types.get(index);
+	if is_named(name, &type_def.kind) {
+		return types.idents[index].clone();
+	}
+
+	match &type_def.kind {
+		TypeKind::Primitive => {
+			let primitive = Primitive::from_name(name, types.schema.ptr_size()).unwrap();
+			match primitive {
+				Primitive::U8
+				| Primitive::I8
+				| Primitive::U16
+				| Primitive::I16
+				| Primitive::U32
+				| Primitive::I32
+				| Primitive::F32
+				| Primitive::F64 => "number",
+				Primitive::U64 | Primitive::I64 | Primitive::U128 | Primitive::I128 => "bigint",
+				Primitive::Bool => "boolean",
+				Primitive::Char => "string",
+				Primitive::Unit => "null",
+			}
+			.to_string()
+		}
+		TypeKind::Struct { fields } => fields_expr(fields, types),
+		TypeKind::Box { inner } => type_expr(inner, types),
+		TypeKind::Vec { inner, .. }
+		| TypeKind::BoxedSlice { inner, .. }
+		| TypeKind::Array { inner, .. } => {
+			let inner = type_expr(inner, types);
+			if inner.contains(' ') {
+				format!("({inner})[]")
+			} else {
+				format!("{inner}[]")
+			}
+		}
+		TypeKind::String { .. } | TypeKind::BoxedStr { .. } => "string".to_string(),
+		TypeKind::Option { inner, .. } => format!("{} | null", type_expr(inner, types)),
+		TypeKind::Enum { .. } => unreachable!(),
+	}
+}
+
+/// Get TypeScript type expression for struct or enum variant's fields.
+/// Mirrors `js::fields_expr`.
+fn fields_expr(fields: &[Field], types: &Types) -> String {
+	if fields.is_empty() {
+		"null".to_string()
+	} else if is_tuple(fields) {
+		let values = fields
+			.iter()
+			.map(|field| type_expr(&field.type_name, types))
+			.collect::<Vec<_>>();
+		format!("[{}]", values.join(", "))
+	} else {
+		let props = fields
+			.iter()
+			.map(|field| {
+				format!(
+					"{}: {}",
+					property_key(&field.name),
+					type_expr(&field.type_name, types)
+				)
+			})
+			.collect::<Vec<_>>();
+		format!("{{ {} }}", props.join("; "))
+	}
+}
+
+fn enum_expr(variants: &[Variant], types: &Types) -> String {
+	if variants.is_empty() {
+		return "never".to_string();
+	}
+
+	let is_fieldless = variants.iter().all(|variant| variant.fields.is_empty());
+	if is_fieldless {
+		let names = variants
+			.iter()
+			.map(|variant| string_literal(&variant.name))
+			.collect::<Vec<_>>();
+		return names.join(" | ");
+	}
+
+	let variants = variants
+		.iter()
+		.map(|variant| {
+			let name = string_literal(&variant.name);
+			let fields = &variant.fields;
+			if fields.is_empty() {
+				format!("\n\t| {{ type: {name} }}")
+			} else if is_tuple(fields) && fields.len() == 1 {
+				format!(
+					"\n\t| {{ type: {name}; value: {} }}",
+					type_expr(&fields[0].type_name, types)
+				)
+			} else {
+				format!(
+					"\n\t| {{ type: {name}; value: {} }}",
+					fields_expr(fields, types)
+				)
+			}
+		})
+		.collect::<Vec<_>>();
+	variants.concat()
+}
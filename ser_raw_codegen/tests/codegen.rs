@@ -0,0 +1,383 @@
+// Fixtures are generated on a 64-bit little-endian system.
+// Set `UPDATE_FIXTURES=1` env var to regenerate them.
+#![cfg(all(target_pointer_width = "64", target_endian = "little"))]
+
+use std::{
+	env, fs,
+	num::NonZeroU32,
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+use ser_raw::{
+	layout::Schema,
+	storage::{AlignedVec, ContiguousStorage},
+	util::{aligned_max_capacity, aligned_max_u32_capacity},
+	Layout, PtrOffset32Serializer, PtrOffsetSerializer, PureCopySerializer, Serialize, Serializer,
+};
+use ser_raw_codegen::{generate, CodegenError};
+
+const MAX_CAPACITY: usize = aligned_max_capacity(16);
+type PtrOffsetSer = PtrOffsetSerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+type PureCopySer = PureCopySerializer<16, 16, 8, MAX_CAPACITY, AlignedVec>;
+const MAX_U32_CAPACITY: usize = aligned_max_u32_capacity(16);
+type PtrOffset32Ser =
+	PtrOffset32Serializer<16, 16, 8, MAX_U32_CAPACITY, AlignedVec<16, 16, 8, MAX_U32_CAPACITY>>;
+
+#[derive(Serialize, Layout)]
+#[allow(clippy::vec_box)]
+struct Root {
+	primitives: Primitives,
+	non_zero: NonZeroU32,
+	unit: (),
+	array: [u16; 3],
+	tuple: (u8, String, Box<u32>),
+	single: (bool,),
+	boxed: Box<Point>,
+	boxed_unit: Box<()>,
+	vec: Vec<u8>,
+	empty_vec: Vec<u64>,
+	units: Vec<()>,
+	nested: Vec<Vec<Point>>,
+	boxes: Vec<Box<String>>,
+	string: String,
+	empty_string: String,
+	unicode: String,
+	none: Option<u32>,
+	some: Option<u32>,
+	niche_none: Option<Box<u8>>,
+	niche_some: Option<Box<u8>>,
+	options: Vec<Option<String>>,
+	aligned: Aligned,
+	boxed_aligned: Box<Aligned>,
+	colors: [Color; 3],
+	wide: Wide,
+	shapes: Vec<Shape>,
+	tagged: Vec<Tagged>,
+	point3: Point3,
+	empty: Empty,
+	r#type: u8,
+}
+
+#[derive(Serialize, Layout)]
+struct Primitives {
+	u8: u8,
+	u16: u16,
+	u32: u32,
+	u64: u64,
+	u128: u128,
+	usize: usize,
+	i8: i8,
+	i16: i16,
+	i32: i32,
+	i64: i64,
+	i128: i128,
+	isize: isize,
+	f32: f32,
+	f64: f64,
+	bool: bool,
+	char: char,
+}
+
+#[derive(Serialize, Layout)]
+struct Point {
+	x: i32,
+	y: i32,
+}
+
+#[derive(Serialize, Layout)]
+struct Point3(i16, i16, i16);
+
+#[derive(Serialize, Layout)]
+struct Empty;
+
+#[derive(Serialize, Layout)]
+#[repr(align(16))]
+struct Aligned(u8);
+
+#[derive(Serialize, Layout)]
+#[allow(dead_code)]
+enum Color {
+	Red,
+	Green,
+	Blue = 10,
+}
+
+#[derive(Serialize, Layout)]
+#[repr(u64)]
+#[allow(dead_code)]
+enum Wide {
+	Small = 1,
+	Big = 0x1000_0000_0000,
+}
+
+#[derive(Serialize, Layout)]
+#[repr(u8)]
+enum Shape {
+	Empty,
+	Circle(f64),
+	Rect { width: u32, height: u32 },
+	Polygon(Vec<Point>, String),
+}
+
+#[derive(Serialize, Layout)]
+#[repr(C, u16)]
+enum Tagged {
+	A,
+	B(Box<u32>),
+	C { name: String },
+}
+
+fn input() -> Root {
+	Root {
+		primitives: Primitives {
+			u8: 0x01,
+			u16: 0x0203,
+			u32: 0x04050607,
+			u64: u64::MAX,
+			u128: u128::MAX - 1,
+			usize: 0x08090a0b0c0d0e0f,
+			i8: -1,
+			i16: -0x0203,
+			i32: -0x04050607,
+			i64: i64::MIN,
+			i128: i128::MIN + 1,
+			isize: -0x08090a0b0c0d0e0f,
+			f32: 1.5,
+			f64: -123.25,
+			bool: true,
+			char: '🦀',
+		},
+		non_zero: NonZeroU32::new(123).unwrap(),
+		unit: (),
+		array: [1, 2, 3],
+		tuple: (4, "five".to_string(), Box::new(6)),
+		single: (true,),
+		boxed: Box::new(Point { x: -7, y: 8 }),
+		boxed_unit: Box::new(()),
+		vec: vec![9, 10, 11],
+		empty_vec: vec![],
+		units: vec![(), ()],
+		nested: vec![
+			vec![Point { x: 1, y: 2 }],
+			vec![],
+			vec![Point { x: 3, y: 4 }, Point { x: 5, y: 6 }],
+		],
+		boxes: vec![
+			Box::new("boxed".to_string()),
+			Box::new("strings".to_string()),
+		],
+		string: "hello".to_string(),
+		empty_string: String::new(),
+		unicode: "“quoted” ünïcödé".to_string(),
+		none: None,
+		some: Some(12),
+		niche_none: None,
+		niche_some: Some(Box::new(13)),
+		options: vec![Some("a".to_string()), None, Some("bcdefghijk".to_string())],
+		aligned: Aligned(14),
+		boxed_aligned: Box::new(Aligned(15)),
+		colors: [Color::Blue, Color::Red, Color::Green],
+		wide: Wide::Big,
+		shapes: vec![
+			Shape::Empty,
+			Shape::Circle(16.5),
+			Shape::Rect {
+				width: 17,
+				height: 18,
+			},
+			Shape::Polygon(
+				vec![Point { x: 19, y: 20 }, Point { x: 21, y: 22 }],
+				"polygon".to_string(),
+			),
+		],
+		tagged: vec![
+			Tagged::C {
+				name: "tagged".to_string(),
+			},
+			Tagged::A,
+			Tagged::B(Box::new(23)),
+		],
+		point3: Point3(24, -25, 26),
+		empty: Empty,
+		r#type: 27,
+	}
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR"))
+		.join("tests/fixtures")
+		.join(name)
+}
+
+/// Compare `actual` to contents of fixture file, or update fixture if
+/// `UPDATE_FIXTURES` env var is set.
+fn check_fixture(name: &str, actual: &str) {
+	let path = fixture_path(name);
+	if env::var_os("UPDATE_FIXTURES").is_some() {
+		fs::write(&path, actual).unwrap();
+		return;
+	}
+	let expected = fs::read_to_string(&path).unwrap();
+	assert!(
+		actual == expected,
+		"Output does not match fixture `{name}`. Run with `UPDATE_FIXTURES=1` to update it."
+	);
+}
+
+/// Create empty temp dir for a test.
+fn temp_dir(name: &str) -> PathBuf {
+	let dir = env::temp_dir().join(format!("ser_raw_codegen_{}_{name}", std::process::id()));
+	if dir.exists() {
+		fs::remove_dir_all(&dir).unwrap();
+	}
+	fs::create_dir_all(&dir).unwrap();
+	dir
+}
+
+#[test]
+fn generated_code() {
+	let generated = generate(&Schema::new::<Root>()).unwrap();
+	check_fixture("decoder.js", &generated.js);
+	check_fixture("decoder.d.ts", &generated.dts);
+}
+
+#[test]
+fn decode_with_node() {
+	if Command::new("node").arg("--version").output().is_err() {
+		eprintln!("Node.js not found. Skipping test.");
+		return;
+	}
+
+	let dir = temp_dir("decode");
+	let generated = generate(&Schema::new::<Root>()).unwrap();
+	fs::write(dir.join("decoder.mjs"), generated.js).unwrap();
+
+	let input = input();
+	let (ptr_offset_pos, storage) = PtrOffsetSer::new().serialize(&input);
+	fs::write(dir.join("ptr_offset.bin"), storage.as_slice()).unwrap();
+	let (ptr_offset32_pos, storage) = PtrOffset32Ser::new().serialize(&input);
+	fs::write(dir.join("ptr_offset32.bin"), storage.as_slice()).unwrap();
+	let (_, storage) = PureCopySer::new().serialize(&input);
+	fs::write(dir.join("pure_copy.bin"), storage.as_slice()).unwrap();
+
+	let script = format!(
+		r#"
+import {{ readFileSync, writeFileSync }} from "fs";
+import {{ deserializePtrOffset, deserializePtrOffset32, deserializePureCopy }} from "./decoder.mjs";
+
+const stringify = value => JSON.stringify(
+	value,
+	(key, value) => typeof value === "bigint" ? `${{value}}n` : value,
+	"\t",
+) + "\n";
+
+const ptrOffset = readFileSync(new URL("./ptr_offset.bin", import.meta.url));
+const ptrOffset32 = readFileSync(new URL("./ptr_offset32.bin", import.meta.url));
+const pureCopy = readFileSync(new URL("./pure_copy.bin", import.meta.url));
+
+writeFileSync(
+	new URL("./ptr_offset.json", import.meta.url),
+	stringify(deserializePtrOffset(ptrOffset, {ptr_offset_pos})),
+);
+writeFileSync(
+	new URL("./ptr_offset32.json", import.meta.url),
+	stringify(deserializePtrOffset32(ptrOffset32, {ptr_offset32_pos})),
+);
+writeFileSync(
+	new URL("./pure_copy.json", import.meta.url),
+	stringify(deserializePureCopy(pureCopy.buffer.slice(pureCopy.byteOffset), 0, 8)),
+);
+"#
+	);
+	fs::write(dir.join("run.mjs"), script).unwrap();
+
+	let output = Command::new("node")
+		.arg(dir.join("run.mjs"))
+		.output()
+		.unwrap();
+	assert!(
+		output.status.success(),
+		"Node.js failed:\n{}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+
+	let ptr_offset = fs::read_to_string(dir.join("ptr_offset.json")).unwrap();
+	let ptr_offset32 = fs::read_to_string(dir.join("ptr_offset32.json")).unwrap();
+	let pure_copy = fs::read_to_string(dir.join("pure_copy.json")).unwrap();
+	check_fixture("decoded.json", &ptr_offset);
+	assert_eq!(ptr_offset32, ptr_offset);
+	assert_eq!(pure_copy, ptr_offset);
+
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli() {
+	let dir = temp_dir("cli");
+	let schema = Schema::new::<Root>();
+	let schema_path = dir.join("schema.json");
+	fs::write(&schema_path, schema.to_json()).unwrap();
+
+	let out_dir = dir.join("out");
+	let output = Command::new(env!("CARGO_BIN_EXE_ser_raw_codegen"))
+		.arg(&schema_path)
+		.arg(&out_dir)
+		.args(["--name", "root"])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let generated = generate(&schema).unwrap();
+	assert_eq!(
+		fs::read_to_string(out_dir.join("root.js")).unwrap(),
+		generated.js
+	);
+	assert_eq!(
+		fs::read_to_string(out_dir.join("root.d.ts")).unwrap(),
+		generated.dts
+	);
+
+	// Invalid schema
+	fs::write(&schema_path, "{}").unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_ser_raw_codegen"))
+		.arg(&schema_path)
+		.arg(&out_dir)
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	assert!(String::from_utf8_lossy(&output.stderr).starts_with("Invalid schema in "));
+
+	fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn errors() {
+	let json = r#"{"root":"Foo","ptr_size":8,"endianness":"little","types":[
+		{"name":"Foo","size":2,"align":2,"kind":"primitive"}
+	]}"#;
+	let schema = Schema::from_json(json).unwrap();
+	assert_eq!(
+		generate(&schema),
+		Err(CodegenError::UnknownPrimitive {
+			name: "Foo".to_string()
+		})
+	);
+
+	let json = r#"{"root":"Foo","ptr_size":8,"endianness":"little","types":[
+		{"name":"Foo","size":3,"align":1,"kind":"enum","tag_size":3,"variants":[]}
+	]}"#;
+	let schema = Schema::from_json(json).unwrap();
+	let err = generate(&schema).unwrap_err();
+	assert_eq!(
+		err,
+		CodegenError::UnsupportedTagSize {
+			name: "Foo".to_string(),
+			size: 3
+		}
+	);
+	assert_eq!(
+		err.to_string(),
+		"Enum `Foo` has discriminant of unsupported size 3"
+	);
+}
@@ -0,0 +1,96 @@
+// Generated by ser_raw_codegen. Do not edit.
+// Root type: Root
+
+export declare function deserializePtrOffset(
+	buffer: ArrayBuffer | ArrayBufferView,
+	pos?: number,
+): Root;
+
+export declare function deserializePtrOffset32(
+	buffer: ArrayBuffer | ArrayBufferView,
+	pos?: number,
+): Root;
+
+export declare function deserializePureCopy(
+	buffer: ArrayBuffer | ArrayBufferView,
+	pos?: number,
+	valueAlignment?: number,
+): Root;
+
+export interface Root {
+	primitives: Primitives;
+	non_zero: number;
+	unit: null;
+	array: number[];
+	tuple: [number, string, number];
+	single: [boolean];
+	boxed: Point;
+	boxed_unit: null;
+	vec: number[];
+	empty_vec: bigint[];
+	units: null[];
+	nested: Point[][];
+	boxes: string[];
+	string: string;
+	empty_string: string;
+	unicode: string;
+	none: number | null;
+	some: number | null;
+	niche_none: number | null;
+	niche_some: number | null;
+	options: (string | null)[];
+	aligned: Aligned;
+	boxed_aligned: Aligned;
+	colors: Color[];
+	wide: Wide;
+	shapes: Shape[];
+	tagged: Tagged[];
+	point3: Point3;
+	empty: Empty;
+	type: number;
+}
+
+export interface Primitives {
+	u8: number;
+	u16: number;
+	u32: number;
+	u64: bigint;
+	u128: bigint;
+	usize: bigint;
+	i8: number;
+	i16: number;
+	i32: number;
+	i64: bigint;
+	i128: bigint;
+	isize: bigint;
+	f32: number;
+	f64: number;
+	bool: boolean;
+	char: string;
+}
+
+export interface Point {
+	x: number;
+	y: number;
+}
+
+export type Aligned = [number];
+
+export type Color = "Red" | "Green" | "Blue";
+
+export type Wide = "Small" | "Big";
+
+export type Shape =
+	| { type: "Empty" }
+	| { type: "Circle"; value: number }
+	| { type: "Rect"; value: { width: number; height: number } }
+	| { type: "Polygon"; value: [Point[], string] };
+
+export type Tagged =
+	| { type: "A" }
+	| { type: "B"; value: number }
+	| { type: "C"; value: { name: string } };
+
+export type Point3 = [number, number, number];
+
+export type Empty = null;
@@ -0,0 +1,462 @@
+// Generated by ser_raw_codegen. Do not edit.
+// Root type: Root
+
+const textDecoder = new TextDecoder();
+
+let view, bytes, pureCopy, valueAlignment, cursor, offset32;
+
+function init(buffer, isPureCopy, alignment, isOffset32) {
+	if (ArrayBuffer.isView(buffer)) {
+		view = new DataView(buffer.buffer, buffer.byteOffset, buffer.byteLength);
+		bytes = new Uint8Array(buffer.buffer, buffer.byteOffset, buffer.byteLength);
+	} else {
+		view = new DataView(buffer);
+		bytes = new Uint8Array(buffer);
+	}
+	pureCopy = isPureCopy;
+	valueAlignment = alignment;
+	offset32 = isOffset32;
+}
+
+// Get position of next value(s) in `PureCopySerializer` output, and advance
+// cursor past them. Follows same rules as `Storage::push_slice`.
+function take(size, align, len) {
+	if (size === 0) return cursor;
+	if (align > valueAlignment) cursor = alignUp(cursor, align);
+	const pos = cursor;
+	cursor += size * len;
+	if (size % valueAlignment !== 0) cursor = alignUp(cursor, valueAlignment);
+	return pos;
+}
+
+function alignUp(pos, align) {
+	const rem = pos % align;
+	return rem === 0 ? pos : pos + align - rem;
+}
+
+export function deserializePtrOffset(buffer, pos = 0) {
+	init(buffer, false, 1, false);
+	return decode_Root(pos);
+}
+
+export function deserializePtrOffset32(buffer, pos = 0) {
+	init(buffer, false, 1, true);
+	return decode_Root(pos);
+}
+
+export function deserializePureCopy(buffer, pos = 0, valueAlignment = 8) {
+	init(buffer, true, valueAlignment, false);
+	cursor = pos;
+	return decode_Root(take(512, 16, 1));
+}
+
+function readUsize(pos) {
+	return view.getUint32(pos, true) + view.getUint32(pos + 4, true) * 4294967296;
+}
+
+function readOffset(pos) {
+	return offset32 ? view.getUint32(pos, true) : readUsize(pos);
+}
+
+// Root
+function decode_Root(pos) {
+	return {
+		primitives: decode_Primitives(pos + 0),
+		non_zero: decode_NonZeroU32(pos + 480),
+		unit: decode_unit(pos + 0),
+		array: decode_array_u16_3(pos + 484),
+		tuple: decode_tuple_u8_String_Box_u32(pos + 128),
+		single: decode_tuple_bool(pos + 500),
+		boxed: decode_Box_Point(pos + 168),
+		boxed_unit: decode_Box_unit(pos + 176),
+		vec: decode_Vec_u8(pos + 184),
+		empty_vec: decode_Vec_u64(pos + 208),
+		units: decode_Vec_unit(pos + 232),
+		nested: decode_Vec_Vec_Point(pos + 256),
+		boxes: decode_Vec_Box_String(pos + 280),
+		string: decode_String(pos + 304),
+		empty_string: decode_String(pos + 328),
+		unicode: decode_String(pos + 352),
+		none: decode_Option_u32(pos + 456),
+		some: decode_Option_u32(pos + 464),
+		niche_none: decode_Option_Box_u8(pos + 112),
+		niche_some: decode_Option_Box_u8(pos + 120),
+		options: decode_Vec_Option_String(pos + 376),
+		aligned: decode_Aligned(pos + 96),
+		boxed_aligned: decode_Box_Aligned(pos + 400),
+		colors: decode_array_Color_3(pos + 497),
+		wide: decode_Wide(pos + 472),
+		shapes: decode_Vec_Shape(pos + 408),
+		tagged: decode_Vec_Tagged(pos + 432),
+		point3: decode_Point3(pos + 490),
+		empty: decode_Empty(pos + 0),
+		type: decode_u8(pos + 496),
+	};
+}
+
+// Primitives
+function decode_Primitives(pos) {
+	return {
+		u8: decode_u8(pos + 92),
+		u16: decode_u16(pos + 88),
+		u32: decode_u32(pos + 72),
+		u64: decode_u64(pos + 32),
+		u128: decode_u128(pos + 0),
+		usize: decode_usize(pos + 40),
+		i8: decode_i8(pos + 93),
+		i16: decode_i16(pos + 90),
+		i32: decode_i32(pos + 76),
+		i64: decode_i64(pos + 48),
+		i128: decode_i128(pos + 16),
+		isize: decode_isize(pos + 56),
+		f32: decode_f32(pos + 80),
+		f64: decode_f64(pos + 64),
+		bool: decode_bool(pos + 94),
+		char: decode_char(pos + 84),
+	};
+}
+
+// u8
+function decode_u8(pos) {
+	return bytes[pos];
+}
+
+// u16
+function decode_u16(pos) {
+	return view.getUint16(pos, true);
+}
+
+// u32
+function decode_u32(pos) {
+	return view.getUint32(pos, true);
+}
+
+// u64
+function decode_u64(pos) {
+	return view.getBigUint64(pos, true);
+}
+
+// u128
+function decode_u128(pos) {
+	return (view.getBigUint64(pos + 8, true) << 64n) | view.getBigUint64(pos + 0, true);
+}
+
+// usize
+function decode_usize(pos) {
+	return view.getBigUint64(pos, true);
+}
+
+// i8
+function decode_i8(pos) {
+	return view.getInt8(pos);
+}
+
+// i16
+function decode_i16(pos) {
+	return view.getInt16(pos, true);
+}
+
+// i32
+function decode_i32(pos) {
+	return view.getInt32(pos, true);
+}
+
+// i64
+function decode_i64(pos) {
+	return view.getBigInt64(pos, true);
+}
+
+// i128
+function decode_i128(pos) {
+	return BigInt.asIntN(128, (view.getBigUint64(pos + 8, true) << 64n) | view.getBigUint64(pos + 0, true));
+}
+
+// isize
+function decode_isize(pos) {
+	return view.getBigInt64(pos, true);
+}
+
+// f32
+function decode_f32(pos) {
+	return view.getFloat32(pos, true);
+}
+
+// f64
+function decode_f64(pos) {
+	return view.getFloat64(pos, true);
+}
+
+// bool
+function decode_bool(pos) {
+	return bytes[pos] !== 0;
+}
+
+// char
+function decode_char(pos) {
+	return String.fromCodePoint(view.getUint32(pos, true));
+}
+
+// NonZeroU32
+function decode_NonZeroU32(pos) {
+	return view.getUint32(pos, true);
+}
+
+// ()
+function decode_unit(pos) {
+	return null;
+}
+
+// [u16; 3]
+function decode_array_u16_3(pos) {
+	const arr = new Array(3);
+	for (let i = 0; i < 3; i++) {
+		arr[i] = decode_u16(pos + i * 2);
+	}
+	return arr;
+}
+
+// (u8, String, Box<u32>)
+function decode_tuple_u8_String_Box_u32(pos) {
+	return [decode_u8(pos + 24), decode_String(pos + 0), decode_Box_u32(pos + 32)];
+}
+
+// String
+function decode_String(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return "";
+	const ptr = pureCopy ? take(1, 1, len) : readOffset(pos + 8);
+	return textDecoder.decode(bytes.subarray(ptr, ptr + len));
+}
+
+// Box<u32>
+function decode_Box_u32(pos) {
+	return decode_u32(pureCopy ? take(4, 4, 1) : readOffset(pos));
+}
+
+// (bool,)
+function decode_tuple_bool(pos) {
+	return [decode_bool(pos + 0)];
+}
+
+// Box<Point>
+function decode_Box_Point(pos) {
+	return decode_Point(pureCopy ? take(8, 4, 1) : readOffset(pos));
+}
+
+// Point
+function decode_Point(pos) {
+	return {
+		x: decode_i32(pos + 0),
+		y: decode_i32(pos + 4),
+	};
+}
+
+// Box<()>
+function decode_Box_unit(pos) {
+	return decode_unit(pureCopy ? take(0, 1, 1) : readOffset(pos));
+}
+
+// Vec<u8>
+function decode_Vec_u8(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(1, 1, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_u8(ptr + i * 1);
+	}
+	return arr;
+}
+
+// Vec<u64>
+function decode_Vec_u64(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(8, 8, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_u64(ptr + i * 8);
+	}
+	return arr;
+}
+
+// Vec<()>
+function decode_Vec_unit(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(0, 1, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_unit(ptr + i * 0);
+	}
+	return arr;
+}
+
+// Vec<Vec<Point>>
+function decode_Vec_Vec_Point(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(24, 8, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_Vec_Point(ptr + i * 24);
+	}
+	return arr;
+}
+
+// Vec<Point>
+function decode_Vec_Point(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(8, 4, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_Point(ptr + i * 8);
+	}
+	return arr;
+}
+
+// Vec<Box<String>>
+function decode_Vec_Box_String(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(8, 8, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_Box_String(ptr + i * 8);
+	}
+	return arr;
+}
+
+// Box<String>
+function decode_Box_String(pos) {
+	return decode_String(pureCopy ? take(24, 8, 1) : readOffset(pos));
+}
+
+// Option<u32>
+function decode_Option_u32(pos) {
+	if (bytes[pos + 0] === 0 && bytes[pos + 1] === 0 && bytes[pos + 2] === 0 && bytes[pos + 3] === 0) return null;
+	return decode_u32(pos + 4);
+}
+
+// Option<Box<u8>>
+function decode_Option_Box_u8(pos) {
+	if (bytes[pos + 0] === 0 && bytes[pos + 1] === 0 && bytes[pos + 2] === 0 && bytes[pos + 3] === 0 && bytes[pos + 4] === 0 && bytes[pos + 5] === 0 && bytes[pos + 6] === 0 && bytes[pos + 7] === 0) return null;
+	return decode_Box_u8(pos + 0);
+}
+
+// Box<u8>
+function decode_Box_u8(pos) {
+	return decode_u8(pureCopy ? take(1, 1, 1) : readOffset(pos));
+}
+
+// Vec<Option<String>>
+function decode_Vec_Option_String(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(24, 8, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_Option_String(ptr + i * 24);
+	}
+	return arr;
+}
+
+// Option<String>
+function decode_Option_String(pos) {
+	if (bytes[pos + 8] === 0 && bytes[pos + 9] === 0 && bytes[pos + 10] === 0 && bytes[pos + 11] === 0 && bytes[pos + 12] === 0 && bytes[pos + 13] === 0 && bytes[pos + 14] === 0 && bytes[pos + 15] === 0) return null;
+	return decode_String(pos + 0);
+}
+
+// Aligned
+function decode_Aligned(pos) {
+	return [decode_u8(pos + 0)];
+}
+
+// Box<Aligned>
+function decode_Box_Aligned(pos) {
+	return decode_Aligned(pureCopy ? take(16, 16, 1) : readOffset(pos));
+}
+
+// [Color; 3]
+function decode_array_Color_3(pos) {
+	const arr = new Array(3);
+	for (let i = 0; i < 3; i++) {
+		arr[i] = decode_Color(pos + i * 1);
+	}
+	return arr;
+}
+
+// Color
+function decode_Color(pos) {
+	switch (bytes[pos]) {
+		case 0: return "Red";
+		case 1: return "Green";
+		case 10: return "Blue";
+	}
+	throw new Error("Invalid discriminant for `Color`");
+}
+
+// Wide
+function decode_Wide(pos) {
+	switch (view.getBigUint64(pos, true)) {
+		case 1n: return "Small";
+		case 17592186044416n: return "Big";
+	}
+	throw new Error("Invalid discriminant for `Wide`");
+}
+
+// Vec<Shape>
+function decode_Vec_Shape(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(56, 8, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_Shape(ptr + i * 56);
+	}
+	return arr;
+}
+
+// Shape
+function decode_Shape(pos) {
+	switch (bytes[pos]) {
+		case 0: return { type: "Empty" };
+		case 1: return { type: "Circle", value: decode_f64(pos + 8) };
+		case 2: return { type: "Rect", value: { width: decode_u32(pos + 4), height: decode_u32(pos + 8) } };
+		case 3: return { type: "Polygon", value: [decode_Vec_Point(pos + 8), decode_String(pos + 32)] };
+	}
+	throw new Error("Invalid discriminant for `Shape`");
+}
+
+// Vec<Tagged>
+function decode_Vec_Tagged(pos) {
+	const len = readUsize(pos + 16);
+	if (len === 0) return [];
+	const ptr = pureCopy ? take(32, 8, len) : readOffset(pos + 8);
+	const arr = new Array(len);
+	for (let i = 0; i < len; i++) {
+		arr[i] = decode_Tagged(ptr + i * 32);
+	}
+	return arr;
+}
+
+// Tagged
+function decode_Tagged(pos) {
+	switch (view.getUint16(pos, true)) {
+		case 0: return { type: "A" };
+		case 1: return { type: "B", value: decode_Box_u32(pos + 8) };
+		case 2: return { type: "C", value: { name: decode_String(pos + 8) } };
+	}
+	throw new Error("Invalid discriminant for `Tagged`");
+}
+
+// Point3
+function decode_Point3(pos) {
+	return [decode_i16(pos + 0), decode_i16(pos + 2), decode_i16(pos + 4)];
+}
+
+// Empty
+function decode_Empty(pos) {
+	return null;
+}
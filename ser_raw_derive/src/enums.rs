@@ -1,16 +1,39 @@
 use proc_macro2::TokenStream;
-use quote::{quote, quote_spanned};
-use syn::{DataEnum, Fields, FieldsNamed, FieldsUnnamed, Generics, Ident};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{DataEnum, Field, Fields, FieldsNamed, FieldsUnnamed, Generics, Ident};
 
-// TODO: Handle `ser_with` attribute
+use crate::{
+	structs::{check_no_with, get_fingerprint_fields, get_portable_fields, get_skip, Skip},
+	DeriveTrait, FingerprintGroup, PortableField,
+};
 
-pub fn derive_enum(
+pub(crate) fn derive_enum(
 	data: DataEnum,
 	ident: Ident,
 	generics: Generics,
 	generics_for_impl: Generics,
+	derive_trait: DeriveTrait,
 ) -> TokenStream {
 	let num_variants = data.variants.len();
+	for variant in &data.variants {
+		check_no_with(&variant.attrs, "an enum variant");
+	}
+
+	let fingerprint = derive_trait.fingerprint_const(
+		&ident,
+		data
+			.variants
+			.iter()
+			.map(|variant| {
+				FingerprintGroup {
+					variant_name: Some(variant.ident.to_string()),
+					fields: get_fingerprint_fields(&variant.fields),
+				}
+			})
+			.collect(),
+	);
+
+	let portable = get_portable_items(&data, derive_trait);
 
 	let mut matches = data
 		.variants
@@ -18,13 +41,15 @@ pub fn derive_enum(
 		.filter_map(|variant| {
 			match variant.fields {
 				Fields::Unit => None,
-				Fields::Unnamed(fields) => get_match_for_unnamed_fields(variant.ident, fields),
-				Fields::Named(fields) => get_match_for_named_fields(variant.ident, fields),
+				Fields::Unnamed(fields) => {
+					get_match_for_unnamed_fields(variant.ident, fields, derive_trait)
+				}
+				Fields::Named(fields) => get_match_for_named_fields(variant.ident, fields, derive_trait),
 			}
 		})
 		.collect::<Vec<_>>();
 
-	let match_stmt = if matches.len() == 0 {
+	let match_stmt = if matches.is_empty() {
 		quote! {}
 	} else {
 		if matches.len() < num_variants {
@@ -40,61 +65,133 @@ pub fn derive_enum(
 		}
 	};
 
-	let (impl_generics, _, _) = generics_for_impl.split_for_impl();
-	let (_, type_generics, where_clause) = generics.split_for_impl();
+	derive_trait.impl_block(
+		&ident,
+		&generics,
+		&generics_for_impl,
+		quote! {
+			#fingerprint
+			#portable
+		},
+		match_stmt,
+	)
+}
 
-	quote! {
-		#[automatically_derived]
-		impl #impl_generics ::ser_raw::Serialize<__S> for #ident #type_generics #where_clause {
-			fn serialize_data(&self, serializer: &mut __S) {
-				#match_stmt
+/// Get `PORTABLE_SIZE` const and `serialize_portable` method.
+///
+/// Portable representation is variant's index as a 4-byte tag, followed by
+/// variant's fields. Size is size of tag + size of largest variant.
+fn get_portable_items(data: &DataEnum, derive_trait: DeriveTrait) -> TokenStream {
+	let mut sizes = vec![];
+	let matches = data
+		.variants
+		.iter()
+		.enumerate()
+		.map(|(index, variant)| {
+			// `Self::Foo { 0: val_0, 1: val_1, .. }` pattern works for all kinds of
+			// variant. Skipped fields are not bound.
+			let fields = get_portable_fields(&variant.fields, |member| {
+				let alias = format_ident!("val_{}", member.to_string());
+				quote! {#alias}
+			});
+			let members = variant
+				.fields
+				.iter()
+				.enumerate()
+				.filter(|(_, field)| get_skip(field).is_none())
+				.map(|(index, field)| {
+					match &field.ident {
+						Some(ident) => quote! {#ident},
+						None => {
+							let index = syn::Index::from(index);
+							quote! {#index}
+						}
+					}
+				});
+			let aliases = fields.iter().map(|field| &field.value);
+
+			sizes.push(PortableField::total_size(&fields));
+			let stmts = PortableField::stmts(&fields);
+			let ident = &variant.ident;
+			let index = index as u32;
+			quote! {
+				Self::#ident { #(#members: #aliases,)* .. } => {
+					::ser_raw::portable::write_tag(serializer, pos, #index);
+					let pos = pos + ::ser_raw::portable::TAG_SIZE;
+					#stmts
+				}
+			}
+		})
+		.collect::<Vec<_>>();
+
+	// Enum with no variants can't be instantiated
+	let body = if matches.is_empty() {
+		quote! { match *self {} }
+	} else {
+		quote! {
+			match self {
+				#(#matches)*
 			}
 		}
-	}
+	};
+
+	derive_trait.portable_items(
+		quote! { ::ser_raw::portable::TAG_SIZE + ::ser_raw::portable::max_size(&[#(#sizes),*]) },
+		body,
+	)
 }
 
-fn get_match_for_unnamed_fields(ident: Ident, fields: FieldsUnnamed) -> Option<TokenStream> {
+fn get_match_for_unnamed_fields(
+	ident: Ident,
+	fields: FieldsUnnamed,
+	derive_trait: DeriveTrait,
+) -> Option<TokenStream> {
 	let fields = fields.unnamed;
-	if fields.len() == 0 {
+	if fields.is_empty() {
 		return None;
 	}
 
 	let field_idents = (0..fields.len())
-		.into_iter()
 		.map(|index| Ident::new(&("val_".to_string() + &index.to_string()), ident.span()))
 		.collect::<Vec<_>>();
-	let stmts = get_field_stmts(&field_idents);
+	let stmts = get_field_stmts(&field_idents, fields.iter(), derive_trait);
+	let bindings = get_bindings(&field_idents, fields.iter());
 
 	Some(quote_spanned! {ident.span()=>
-		Self::#ident(#(#field_idents),*) => {
+		Self::#ident(#(#bindings),*) => {
 			#(#stmts)*
 		}
 	})
 }
 
-fn get_match_for_named_fields(ident: Ident, fields: FieldsNamed) -> Option<TokenStream> {
+fn get_match_for_named_fields(
+	ident: Ident,
+	fields: FieldsNamed,
+	derive_trait: DeriveTrait,
+) -> Option<TokenStream> {
 	let fields = fields.named;
-	if fields.len() == 0 {
+	if fields.is_empty() {
 		return None;
 	}
 
 	let field_idents = fields
-		.into_iter()
-		.map(|field| field.ident.unwrap())
+		.iter()
+		.map(|field| field.ident.clone().unwrap())
 		.collect::<Vec<_>>();
 
-	// Aliases are required in case of a field called `serializer`.
+	// Aliases are required in case of a field called `serializer` / `deserializer`.
 	// `Self::Foo {x: val_x} =>` instead of just `Self::Foo {x} =>`.
 	let field_aliases = field_idents
 		.iter()
 		.map(|ident| Ident::new(&("val_".to_string() + &ident.to_string()), ident.span()))
 		.collect::<Vec<_>>();
 
-	let var_mappings = std::iter::zip(&field_idents, &field_aliases)
-		.map(|(ident, alias)| quote! { #ident: #alias })
+	let bindings = get_bindings(&field_aliases, fields.iter());
+	let var_mappings = std::iter::zip(&field_idents, &bindings)
+		.map(|(ident, binding)| quote! { #ident: #binding })
 		.collect::<Vec<_>>();
 
-	let stmts = get_field_stmts(&field_aliases);
+	let stmts = get_field_stmts(&field_aliases, fields.iter(), derive_trait);
 
 	Some(quote_spanned! {ident.span()=>
 		Self::#ident{#(#var_mappings),*} => {
@@ -103,12 +200,26 @@ fn get_match_for_named_fields(ident: Ident, fields: FieldsNamed) -> Option<Token
 	})
 }
 
-fn get_field_stmts(idents: &Vec<Ident>) -> Vec<TokenStream> {
-	idents
-		.iter()
-		.map(|ident| {
-			quote! {
-				::ser_raw::Serialize::<__S>::serialize_data(#ident, serializer);
+/// Get statements for fields. `idents` are the names fields are bound to in
+/// the `match` arm.
+fn get_field_stmts<'a>(
+	idents: &[Ident],
+	fields: impl Iterator<Item = &'a Field>,
+	derive_trait: DeriveTrait,
+) -> Vec<TokenStream> {
+	std::iter::zip(idents, fields)
+		.map(|(ident, field)| derive_trait.field_stmt(quote! {#ident}, field))
+		.collect::<Vec<_>>()
+}
+
+/// Get patterns to bind fields to in the `match` arm.
+/// Fields with `#[ser_skip]` are not used, so are not bound.
+fn get_bindings<'a>(idents: &[Ident], fields: impl Iterator<Item = &'a Field>) -> Vec<TokenStream> {
+	std::iter::zip(idents, fields)
+		.map(|(ident, field)| {
+			match get_skip(field) {
+				Some(Skip::Skip) => quote! {_},
+				_ => quote! {#ident},
 			}
 		})
 		.collect::<Vec<_>>()
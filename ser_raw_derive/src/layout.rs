@@ -0,0 +1,286 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{
+	ext::IdentExt, spanned::Spanned, Attribute, Data, DataEnum, DeriveInput, Fields, GenericParam,
+	Generics, Ident, Index,
+};
+
+use crate::{
+	niche::{fieldless_enum_niche_const, struct_niche_const, tag_niche_const, NicheTrait},
+	repr::{get_discriminants, get_repr_for_enum_with_fields, payload_offset_stmt, VariantStruct},
+	structs::get_with,
+};
+
+/// Derive `Layout`.
+///
+/// Offsets of fields are calculated at runtime from a `MaybeUninit<Self>`,
+/// so they're correct whatever layout Rust chooses.
+///
+/// Enums with fields require `#[repr(Int)]` or `#[repr(C, Int)]`, which have
+/// a defined layout. Offsets of their fields are obtained in the same way as
+/// `Validate` derive does, by defining a `#[repr(C)]` struct with same layout
+/// as each variant.
+pub(crate) fn derive_layout(input: DeriveInput) -> TokenStream {
+	let ident = input.ident;
+	let generics = input.generics;
+
+	let (variant_structs, niche, kind) = match input.data {
+		Data::Struct(data) => {
+			(
+				vec![],
+				struct_niche_const(&data.fields, NicheTrait::Layout),
+				get_struct_kind(&data.fields),
+			)
+		}
+		Data::Enum(data) => get_enum_kind(&data, &ident, &generics, &input.attrs),
+		Data::Union(_) => todo!("Deriving `Layout` on Unions not supported"),
+	};
+	let type_name = get_type_name(&ident, &generics);
+
+	let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+	quote! {
+		const _: () = {
+			#(#variant_structs)*
+
+			#[automatically_derived]
+			impl #impl_generics ::ser_raw::Layout for #ident #type_generics #where_clause {
+				#niche
+
+				fn type_name() -> ::std::string::String {
+					#type_name
+				}
+
+				#[allow(unused_variables)]
+				fn type_kind(schema: &mut ::ser_raw::layout::Schema) -> ::ser_raw::layout::TypeKind {
+					#kind
+				}
+			}
+		};
+	}
+}
+
+/// Get expression for type's name.
+/// Includes names of type params and values of const params e.g. `Foo<u8, 4>`.
+fn get_type_name(ident: &Ident, generics: &Generics) -> TokenStream {
+	let name = ident.unraw().to_string();
+	let params = generics
+		.params
+		.iter()
+		.filter_map(|param| {
+			match param {
+				GenericParam::Type(param) => {
+					let param = &param.ident;
+					Some(quote! { <#param as ::ser_raw::Layout>::type_name() })
+				}
+				GenericParam::Const(param) => {
+					let param = &param.ident;
+					Some(quote! { ::std::string::ToString::to_string(&#param) })
+				}
+				GenericParam::Lifetime(_) => None,
+			}
+		})
+		.collect::<Vec<_>>();
+
+	if params.is_empty() {
+		quote! { ::std::string::String::from(#name) }
+	} else {
+		quote! {
+			::std::format!("{}<{}>", #name, [#(#params),*].join(", "))
+		}
+	}
+}
+
+fn get_struct_kind(fields: &Fields) -> TokenStream {
+	let fields = fields
+		.iter()
+		.enumerate()
+		.map(|(index, field)| {
+			let (name, member) = match &field.ident {
+				Some(ident) => (ident.unraw().to_string(), quote! { #ident }),
+				None => {
+					let index = Index::from(index);
+					(index.index.to_string(), quote! { #index })
+				}
+			};
+			let type_name = get_field_type_name(field);
+			quote_spanned! {field.span()=>
+				::ser_raw::layout::Field::new(
+					#name,
+					::ser_raw::layout::field_offset(ptr, unsafe { ::core::ptr::addr_of!((*ptr).#member) }),
+					#type_name,
+				)
+			}
+		})
+		.collect::<Vec<_>>();
+
+	quote! {
+		let value = ::core::mem::MaybeUninit::<Self>::uninit();
+		let ptr = value.as_ptr();
+		::ser_raw::layout::TypeKind::Struct {
+			fields: ::std::vec![#(#fields),*],
+		}
+	}
+}
+
+fn get_enum_kind(
+	data: &DataEnum,
+	ident: &Ident,
+	generics: &Generics,
+	attrs: &[Attribute],
+) -> (Vec<TokenStream>, TokenStream, TokenStream) {
+	let is_fieldless = data
+		.variants
+		.iter()
+		.all(|variant| variant.fields.is_empty());
+	if is_fieldless {
+		(
+			vec![],
+			fieldless_enum_niche_const(data),
+			get_fieldless_enum_kind(data),
+		)
+	} else {
+		get_enum_with_fields_kind(data, ident, generics, attrs)
+	}
+}
+
+/// Fieldless enums' discriminants are obtained from the bytes of each variant.
+/// The whole enum is the tag.
+fn get_fieldless_enum_kind(data: &DataEnum) -> TokenStream {
+	let variants = data
+		.variants
+		.iter()
+		.map(|variant| {
+			let variant_ident = &variant.ident;
+			let name = variant_ident.unraw().to_string();
+			let value = match &variant.fields {
+				Fields::Unit => quote! { Self::#variant_ident },
+				Fields::Unnamed(_) => quote! { Self::#variant_ident() },
+				Fields::Named(_) => quote! { Self::#variant_ident {} },
+			};
+			quote! {
+				{
+					let variant = ::core::mem::ManuallyDrop::new(#value);
+					let bytes = unsafe {
+						::core::slice::from_raw_parts(
+							&*variant as *const Self as *const u8, ::core::mem::size_of::<Self>()
+						)
+					};
+					::ser_raw::layout::Variant::new(
+						#name,
+						::ser_raw::layout::discriminant_from_bytes(bytes),
+						::std::vec![],
+					)
+				}
+			}
+		})
+		.collect::<Vec<_>>();
+
+	quote! {
+		::ser_raw::layout::TypeKind::Enum {
+			tag_size: ::core::mem::size_of::<Self>(),
+			variants: ::std::vec![#(#variants),*],
+		}
+	}
+}
+
+fn get_enum_with_fields_kind(
+	data: &DataEnum,
+	ident: &Ident,
+	generics: &Generics,
+	attrs: &[Attribute],
+) -> (Vec<TokenStream>, TokenStream, TokenStream) {
+	let (is_c, int) = get_repr_for_enum_with_fields(attrs, "Layout");
+	let (discriminants, discriminant_stmts) = get_discriminants(data, &int);
+	let (_, type_generics, _) = generics.split_for_impl();
+
+	let mut variant_structs = vec![];
+	let mut variants = vec![];
+	for (variant, discriminant) in data.variants.iter().zip(&discriminants) {
+		let name = variant.ident.unraw().to_string();
+		let discriminant = quote! {
+			::ser_raw::layout::discriminant_from_bytes(&#discriminant.to_ne_bytes())
+		};
+
+		if variant.fields.is_empty() {
+			variants.push(quote! {
+				::ser_raw::layout::Variant::new(#name, #discriminant, ::std::vec![])
+			});
+			continue;
+		}
+
+		let variant_struct = VariantStruct::new(ident, variant, generics, is_c, &int);
+		let struct_ident = &variant_struct.ident;
+		let base_offset = if is_c {
+			quote! { payload_offset }
+		} else {
+			quote! { 0 }
+		};
+
+		let fields = variant
+			.fields
+			.iter()
+			.zip(&variant_struct.field_names)
+			.enumerate()
+			.map(|(index, (field, member))| {
+				let name = match &field.ident {
+					Some(ident) => ident.unraw().to_string(),
+					None => index.to_string(),
+				};
+				let type_name = get_field_type_name(field);
+				quote_spanned! {field.span()=>
+					::ser_raw::layout::Field::new(
+						#name,
+						#base_offset + ::ser_raw::layout::field_offset(
+							ptr, unsafe { ::core::ptr::addr_of!((*ptr).#member) }
+						),
+						#type_name,
+					)
+				}
+			})
+			.collect::<Vec<_>>();
+
+		variants.push(quote! {
+			{
+				let value = ::core::mem::MaybeUninit::<#struct_ident #type_generics>::uninit();
+				let ptr = value.as_ptr();
+				::ser_raw::layout::Variant::new(#name, #discriminant, ::std::vec![#(#fields),*])
+			}
+		});
+
+		variant_structs.push(variant_struct);
+	}
+
+	let payload_offset = if is_c {
+		payload_offset_stmt(&variant_structs, &int, generics)
+	} else {
+		quote! {}
+	};
+
+	let niche = tag_niche_const(&int, &discriminants, &discriminant_stmts);
+
+	let kind = quote! {
+		#(#discriminant_stmts)*
+		#payload_offset
+		::ser_raw::layout::TypeKind::Enum {
+			tag_size: ::core::mem::size_of::<#int>(),
+			variants: ::std::vec![#(#variants),*],
+		}
+	};
+
+	let variant_struct_defs = variant_structs
+		.into_iter()
+		.map(|variant_struct| variant_struct.def)
+		.collect();
+	(variant_struct_defs, niche, kind)
+}
+
+/// Get expression which adds field's type to schema, and evaluates to its
+/// name. Fields with `#[ser_with]` use `LayoutWith` implementation.
+fn get_field_type_name(field: &syn::Field) -> TokenStream {
+	let ty = &field.ty;
+	match get_with(field) {
+		Some(with) => quote! { schema.add_with::<#with, #ty>() },
+		None => quote! { schema.add::<#ty>() },
+	}
+}
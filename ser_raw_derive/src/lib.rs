@@ -1,47 +1,460 @@
-use proc_macro2;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
 use syn::{
-	parse_macro_input, parse_quote, Attribute, Data, DeriveInput, GenericParam, Generics, TraitBound,
+	parse_macro_input, parse_quote, spanned::Spanned, Attribute, Data, DeriveInput, Field,
+	GenericParam, Generics, Ident, Path, TraitBound, Type,
 };
 
 mod structs;
-use structs::derive_struct;
+use structs::{check_no_with, derive_struct, get_skip, get_with, Skip};
 mod enums;
 use enums::derive_enum;
+mod validate;
+use validate::derive_validate_enum;
+mod layout;
+use layout::derive_layout;
+mod niche;
+use niche::{struct_niche_const, NicheTrait};
+mod repr;
 
 /// Derive macro for [`ser_raw::Serialize`]. See [`Serialize`] documentation
 /// for examples of usage.
 ///
 /// [`ser_raw::Serialize`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Serialize.html
 /// [`Serialize`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Serialize.html
-#[proc_macro_derive(Serialize, attributes(ser_with, ser_bound))]
+#[proc_macro_derive(
+	Serialize,
+	attributes(ser_with, ser_skip, ser_default, ser_replace, ser_bound)
+)]
 pub fn serialize(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 	let input = parse_macro_input!(input as DeriveInput);
 	serialize_impl(input).into()
 }
 
-fn serialize_impl(input: DeriveInput) -> proc_macro2::TokenStream {
+fn serialize_impl(input: DeriveInput) -> TokenStream {
+	derive_impl(input, DeriveTrait::Serialize)
+}
+
+/// Derive macro for [`ser_raw::Deserialize`]. See [`Deserialize`]
+/// documentation for examples of usage.
+///
+/// [`ser_raw::Deserialize`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Deserialize.html
+/// [`Deserialize`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Deserialize.html
+#[proc_macro_derive(Deserialize, attributes(ser_with, ser_skip, ser_default, ser_replace))]
+pub fn deserialize(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	deserialize_impl(input).into()
+}
+
+fn deserialize_impl(input: DeriveInput) -> TokenStream {
+	derive_impl(input, DeriveTrait::Deserialize)
+}
+
+/// Derive macro for [`ser_raw::Validate`]. See [`Validate`] documentation for
+/// examples of usage.
+///
+/// [`ser_raw::Validate`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Validate.html
+/// [`Validate`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Validate.html
+#[proc_macro_derive(Validate, attributes(ser_with, ser_skip, ser_default, ser_replace))]
+pub fn validate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	validate_impl(input).into()
+}
+
+fn validate_impl(input: DeriveInput) -> TokenStream {
+	derive_impl(input, DeriveTrait::Validate)
+}
+
+/// Derive macro for [`ser_raw::Layout`]. See [`Layout`] documentation for
+/// examples of usage.
+///
+/// [`ser_raw::Layout`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Layout.html
+/// [`Layout`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Layout.html
+#[proc_macro_derive(Layout, attributes(ser_with, ser_skip, ser_default, ser_replace))]
+pub fn layout(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	derive_layout(input).into()
+}
+
+fn derive_impl(input: DeriveInput, derive_trait: DeriveTrait) -> TokenStream {
+	check_no_with(&input.attrs, "a type");
+
 	let generics = input.generics;
-	let generics_for_impl = get_generics(input.attrs, &generics);
+	let generics_for_impl = get_generics(input.attrs.clone(), &generics, derive_trait);
 
 	match input.data {
-		Data::Struct(data) => derive_struct(data, input.ident, generics, generics_for_impl),
-		Data::Enum(data) => derive_enum(data, input.ident, generics, generics_for_impl),
-		Data::Union(_) => todo!("Deriving `Serialize` on Unions not supported"),
+		Data::Struct(data) => {
+			derive_struct(data, input.ident, generics, generics_for_impl, derive_trait)
+		}
+		Data::Enum(data) => {
+			match derive_trait {
+				DeriveTrait::Validate => derive_validate_enum(data, input.ident, generics, &input.attrs),
+				_ => derive_enum(data, input.ident, generics, generics_for_impl, derive_trait),
+			}
+		}
+		Data::Union(_) => todo!("Deriving `{}` on Unions not supported", derive_trait.name()),
 	}
 }
 
-/// Amend generics to add Serializer trait bound
-fn get_generics(attrs: Vec<Attribute>, generics: &Generics) -> Generics {
-	// Parse attributes for user-specified serializer bound `#[ser_bound]`
-	let ser_bound = get_ser_bound(attrs);
+/// Trait being derived.
+///
+/// `Serialize`, `Deserialize` and `Validate` impls for structs have the same
+/// shape - a method which visits every field in order. They differ only in the
+/// trait, the method, and the generic param for serializer / deserializer.
+#[derive(Clone, Copy)]
+enum DeriveTrait {
+	Serialize,
+	Deserialize,
+	Validate,
+}
+
+impl DeriveTrait {
+	/// Get name of trait
+	fn name(self) -> &'static str {
+		match self {
+			DeriveTrait::Serialize => "Serialize",
+			DeriveTrait::Deserialize => "Deserialize",
+			DeriveTrait::Validate => "Validate",
+		}
+	}
+
+	/// Wrap method body in `impl` block.
+	/// `items` are any other items to include in the `impl` (e.g. consts).
+	fn impl_block(
+		self,
+		ident: &Ident,
+		generics: &Generics,
+		generics_for_impl: &Generics,
+		items: TokenStream,
+		body: TokenStream,
+	) -> TokenStream {
+		let (impl_generics, _, _) = generics_for_impl.split_for_impl();
+		let (_, type_generics, where_clause) = generics.split_for_impl();
+
+		match self {
+			DeriveTrait::Serialize => {
+				quote! {
+					#[automatically_derived]
+					impl #impl_generics ::ser_raw::Serialize<__S> for #ident #type_generics #where_clause {
+						#items
+
+						fn serialize_data(&self, serializer: &mut __S) {
+							#body
+						}
+					}
+				}
+			}
+			DeriveTrait::Deserialize => {
+				quote! {
+					#[automatically_derived]
+					impl #impl_generics ::ser_raw::Deserialize<__D> for #ident #type_generics #where_clause {
+						#items
+
+						unsafe fn deserialize_data(&mut self, deserializer: &mut __D) {
+							#body
+						}
+					}
+				}
+			}
+			DeriveTrait::Validate => {
+				quote! {
+					#[automatically_derived]
+					unsafe impl #impl_generics ::ser_raw::Validate for #ident #type_generics #where_clause {
+						#items
+
+						#[allow(unused_variables)]
+						unsafe fn validate(
+							ptr: *const Self,
+							validator: &mut ::ser_raw::Validator
+						) -> ::core::result::Result<(), ::ser_raw::ValidateError> {
+							#body
+							::core::result::Result::Ok(())
+						}
+					}
+				}
+			}
+		}
+	}
+
+	/// Get `FINGERPRINT` const for `Serialize` impl.
+	/// Other traits don't have a fingerprint, so get nothing.
+	///
+	/// Fingerprint is computed from type's name, size and alignment, and the
+	/// names and fingerprints of its fields. `groups` contains the fields of a
+	/// struct (a single group with no name), or of each variant of an enum.
+	fn fingerprint_const(self, ident: &Ident, groups: Vec<FingerprintGroup>) -> TokenStream {
+		if !matches!(self, DeriveTrait::Serialize) {
+			return quote! {};
+		}
+
+		let stmts = groups.into_iter().map(|group| {
+			let variant_stmt = group.variant_name.map(|variant_name| {
+				quote! { let hash = ::ser_raw::fingerprint::hash_str(hash, #variant_name); }
+			});
+			let num_fields = group.fields.len();
+			let field_stmts = group.fields.into_iter().map(|(field_name, fingerprint)| {
+				quote! {
+					let hash = ::ser_raw::fingerprint::hash_str(hash, #field_name);
+					let hash = ::ser_raw::fingerprint::hash_u64(hash, #fingerprint);
+				}
+			});
+			quote! {
+				#variant_stmt
+				let hash = ::ser_raw::fingerprint::hash_usize(hash, #num_fields);
+				#(#field_stmts)*
+			}
+		});
+
+		let name = ident.to_string();
+		quote! {
+			const FINGERPRINT: u64 = {
+				let hash = ::ser_raw::fingerprint::of_named::<Self>(#name);
+				#(#stmts)*
+				hash
+			};
+		}
+	}
 
-	// Add bounds for serializer + storage.
-	// Add bound from `#[ser_bound(...)]` to Serializer if present.
-	let mut generic_param: GenericParam = parse_quote!(__S: ::ser_raw::Serializer);
-	if let Some(ser_bound) = ser_bound {
-		generic_param = parse_quote!(#generic_param + #ser_bound);
+	/// Get `NICHE` const for `Validate` impl of a struct.
+	/// Other traits don't have a niche, so get nothing.
+	fn niche_const(self, fields: &syn::Fields) -> TokenStream {
+		match self {
+			DeriveTrait::Validate => struct_niche_const(fields, NicheTrait::Validate),
+			_ => quote! {},
+		}
 	}
 
+	/// Get `PORTABLE_SIZE` const and `serialize_portable` method for `Serialize`
+	/// impl. Other traits don't support portable serialization, so get nothing.
+	///
+	/// `size` is expression for size of type's portable representation, and
+	/// `body` is body of `serialize_portable`, which writes value at `pos`.
+	fn portable_items(self, size: TokenStream, body: TokenStream) -> TokenStream {
+		if !matches!(self, DeriveTrait::Serialize) {
+			return quote! {};
+		}
+
+		quote! {
+			const PORTABLE_SIZE: usize = #size;
+
+			#[allow(unused_variables)]
+			fn serialize_portable(&self, serializer: &mut __S, pos: usize) {
+				#body
+			}
+		}
+	}
+
+	/// Get statement to serialize / deserialize / validate a field.
+	/// `value` is an expression for a reference (or pointer, for `Validate`) to
+	/// the field's value.
+	fn field_stmt(self, value: TokenStream, field: &Field) -> TokenStream {
+		let span = field.span();
+
+		// Skipped fields are still present in output, so `Validate` checks them as
+		// normal
+		if !matches!(self, DeriveTrait::Validate) {
+			if let Some(skip) = get_skip(field) {
+				return self.skipped_field_stmt(value, &field.ty, skip, span);
+			}
+		}
+
+		match (self, get_with(field)) {
+			(DeriveTrait::Serialize, Some(with)) => {
+				quote_spanned! {span=>
+					<#with as ::ser_raw::SerializeWith::<_, __S>>::serialize_data_with(#value, serializer);
+				}
+			}
+			(DeriveTrait::Serialize, None) => {
+				quote_spanned! {span=>
+					::ser_raw::Serialize::<__S>::serialize_data(#value, serializer);
+				}
+			}
+			(DeriveTrait::Deserialize, Some(with)) => {
+				quote_spanned! {span=>
+					<#with as ::ser_raw::DeserializeWith::<_, __D>>::deserialize_data_with(
+						#value, deserializer
+					);
+				}
+			}
+			(DeriveTrait::Deserialize, None) => {
+				quote_spanned! {span=>
+					::ser_raw::Deserialize::<__D>::deserialize_data(#value, deserializer);
+				}
+			}
+			(DeriveTrait::Validate, Some(with)) => {
+				quote_spanned! {span=>
+					<#with as ::ser_raw::ValidateWith::<_>>::validate_with(#value, validator)?;
+				}
+			}
+			(DeriveTrait::Validate, None) => {
+				quote_spanned! {span=>
+					::ser_raw::Validate::validate(#value, validator)?;
+				}
+			}
+		}
+	}
+
+	/// Get statement to serialize / deserialize a field with `#[ser_skip]`,
+	/// `#[ser_default]` or `#[ser_replace]` attribute.
+	///
+	/// Field's data is not serialized. For `#[ser_skip]`, field is left in
+	/// output as a bitwise copy, so its type must be `Copy`, which is checked at
+	/// compile time.
+	///
+	/// For `#[ser_default]` / `#[ser_replace]`, field is overwritten in output
+	/// with the replacement value (all serializers which can write at arbitrary
+	/// positions in output do this, not only those which apply corrections), and
+	/// deserialized field is the replacement value. Output contains a bitwise
+	/// copy of the replacement, so it is not dropped after it's written.
+	fn skipped_field_stmt(
+		self,
+		value: TokenStream,
+		ty: &Type,
+		skip: Skip,
+		span: Span,
+	) -> TokenStream {
+		let replacement = match skip {
+			Skip::Skip => {
+				return match self {
+					DeriveTrait::Serialize => {
+						quote_spanned! {span=>
+							{
+								fn assert_skipped_field_is_copy<T: ::core::marker::Copy>() {}
+								assert_skipped_field_is_copy::<#ty>();
+							}
+						}
+					}
+					_ => quote! {},
+				};
+			}
+			Skip::Replace(replacement) => replacement,
+		};
+
+		match self {
+			DeriveTrait::Serialize => {
+				quote_spanned! {span=>
+					let replacement: #ty = #replacement;
+					unsafe {
+						::ser_raw::Serializer::overwrite(
+							serializer,
+							<<__S as ::ser_raw::Serializer>::Addr as ::ser_raw::pos::Addr>::from_ref(#value),
+							&replacement,
+						);
+					}
+					::core::mem::forget(replacement);
+				}
+			}
+			DeriveTrait::Deserialize => {
+				quote_spanned! {span=>
+					let replacement: #ty = #replacement;
+					::core::ptr::write(#value, replacement);
+				}
+			}
+			DeriveTrait::Validate => unreachable!(),
+		}
+	}
+}
+
+/// Names and fingerprint expressions of a struct's fields, or an enum variant's
+/// fields.
+struct FingerprintGroup {
+	variant_name: Option<String>,
+	fields: Vec<(String, TokenStream)>,
+}
+
+impl FingerprintGroup {
+	/// Get fingerprint expression for a field.
+	/// Fields with `#[ser_with]` or which are skipped use the fingerprint of the
+	/// field type's layout.
+	fn field_fingerprint(field: &Field) -> TokenStream {
+		let ty = &field.ty;
+		if get_with(field).is_some() || get_skip(field).is_some() {
+			quote! { ::ser_raw::fingerprint::of_layout::<#ty>() }
+		} else {
+			quote! { <#ty as ::ser_raw::Serialize<__S>>::FINGERPRINT }
+		}
+	}
+}
+
+/// A field, for portable serialization.
+struct PortableField {
+	/// Expression for a reference to the field's value
+	value: TokenStream,
+	ty: Type,
+	with: Option<Path>,
+	span: Span,
+}
+
+impl PortableField {
+	/// Get expression for size of field's portable representation.
+	fn size(&self) -> TokenStream {
+		let ty = &self.ty;
+		match &self.with {
+			Some(with) => quote! { <#with as ::ser_raw::SerializeWith<#ty, __S>>::PORTABLE_SIZE },
+			None => quote! { <#ty as ::ser_raw::Serialize<__S>>::PORTABLE_SIZE },
+		}
+	}
+
+	/// Get expression for total size of `fields`' portable representations.
+	fn total_size(fields: &[PortableField]) -> TokenStream {
+		let sizes = fields.iter().map(PortableField::size);
+		quote! { 0 #(+ #sizes)* }
+	}
+
+	/// Get statements to write `fields` one after another, starting at `pos`.
+	fn stmts(fields: &[PortableField]) -> TokenStream {
+		let stmts = fields.iter().enumerate().map(|(index, field)| {
+			let (value, ty, span) = (&field.value, &field.ty, field.span);
+			let stmt = match &field.with {
+				Some(with) => {
+					quote_spanned! {span=>
+						<#with as ::ser_raw::SerializeWith<#ty, __S>>::serialize_portable_with(
+							#value, serializer, pos
+						);
+					}
+				}
+				None => {
+					quote_spanned! {span=>
+						::ser_raw::Serialize::<__S>::serialize_portable(#value, serializer, pos);
+					}
+				}
+			};
+
+			// Advance `pos` past field, unless it's the last
+			if index < fields.len() - 1 {
+				let size = field.size();
+				quote! {
+					#stmt
+					let pos = pos + #size;
+				}
+			} else {
+				stmt
+			}
+		});
+		quote! { #(#stmts)* }
+	}
+}
+
+/// Amend generics to add Serializer / Deserializer trait bound.
+/// `Validate` has no generic param, so generics are unchanged.
+fn get_generics(attrs: Vec<Attribute>, generics: &Generics, derive_trait: DeriveTrait) -> Generics {
+	let generic_param: GenericParam = match derive_trait {
+		DeriveTrait::Serialize => {
+			// Parse attributes for user-specified serializer bound `#[ser_bound]`
+			let ser_bound = get_ser_bound(attrs);
+
+			// Add bounds for serializer + storage.
+			// Add bound from `#[ser_bound(...)]` to Serializer if present.
+			let mut generic_param: GenericParam = parse_quote!(__S: ::ser_raw::Serializer);
+			if let Some(ser_bound) = ser_bound {
+				generic_param = parse_quote!(#generic_param + #ser_bound);
+			}
+			generic_param
+		}
+		DeriveTrait::Deserialize => parse_quote!(__D: ::ser_raw::Deserializer),
+		DeriveTrait::Validate => return generics.clone(),
+	};
+
 	let mut generics_for_impl = generics.clone();
 	generics_for_impl.params.push(generic_param);
 	generics_for_impl
@@ -0,0 +1,106 @@
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, DataEnum, Fields, Ident, Index};
+
+use crate::structs::get_with;
+
+/// Trait `NICHE` const is being derived for.
+///
+/// `NICHE` of a struct is calculated from `NICHE` of its fields' types, from
+/// the same trait.
+#[derive(Clone, Copy)]
+pub(crate) enum NicheTrait {
+	Validate,
+	Layout,
+}
+
+impl NicheTrait {
+	/// Get expression for `NICHE` of a field's type.
+	/// Fields with `#[ser_with]` use `ValidateWith` / `LayoutWith`
+	/// implementation.
+	fn field_niche(self, field: &syn::Field) -> TokenStream {
+		let ty = &field.ty;
+		match (self, get_with(field)) {
+			(NicheTrait::Validate, Some(with)) => {
+				quote! { <#with as ::ser_raw::ValidateWith<#ty>>::NICHE }
+			}
+			(NicheTrait::Validate, None) => quote! { <#ty as ::ser_raw::Validate>::NICHE },
+			(NicheTrait::Layout, Some(with)) => quote! { <#with as ::ser_raw::LayoutWith<#ty>>::NICHE },
+			(NicheTrait::Layout, None) => quote! { <#ty as ::ser_raw::Layout>::NICHE },
+		}
+	}
+}
+
+/// Get `NICHE` const for a struct.
+///
+/// Niche is the largest niche of its fields. Offsets of fields are calculated
+/// in const context, from a pointer obtained from a `Probe`.
+pub(crate) fn struct_niche_const(fields: &Fields, niche_trait: NicheTrait) -> TokenStream {
+	let niches = fields
+		.iter()
+		.enumerate()
+		.map(|(index, field)| {
+			let member = match &field.ident {
+				Some(ident) => quote! { #ident },
+				None => {
+					let index = Index::from(index);
+					quote! { #index }
+				}
+			};
+			let niche = niche_trait.field_niche(field);
+			quote_spanned! {field.span()=>
+				::ser_raw::niche::Niche::field(#niche, unsafe {
+					::ser_raw::niche::field_offset(ptr, ::core::ptr::addr_of!((*ptr).#member))
+				})
+			}
+		})
+		.collect::<Vec<_>>();
+
+	quote! {
+		const NICHE: ::core::option::Option<::ser_raw::niche::Niche> = {
+			let probe = ::ser_raw::niche::Probe::new(::core::mem::MaybeUninit::<Self>::uninit());
+			let ptr = ::core::ptr::addr_of!(probe.start) as *const Self;
+			::ser_raw::niche::Niche::largest(&[#(#niches),*])
+		};
+	}
+}
+
+/// Get `NICHE` const for a fieldless enum.
+/// Niche is the values of the whole enum which are not a variant's
+/// discriminant.
+pub(crate) fn fieldless_enum_niche_const(data: &DataEnum) -> TokenStream {
+	let discriminants = data.variants.iter().map(|variant| {
+		let variant_ident = &variant.ident;
+		match &variant.fields {
+			Fields::Unit => quote! { Self::#variant_ident as i128 },
+			Fields::Unnamed(_) => quote! { Self::#variant_ident() as i128 },
+			Fields::Named(_) => quote! { Self::#variant_ident {} as i128 },
+		}
+	});
+
+	quote! {
+		const NICHE: ::core::option::Option<::ser_raw::niche::Niche> =
+			::ser_raw::niche::Niche::tag(::core::mem::size_of::<Self>(), &[#(#discriminants),*]);
+	}
+}
+
+/// Get `NICHE` const for an enum with fields, with `#[repr(Int)]` or
+/// `#[repr(C, Int)]`. Niche is the values of the tag which are not a variant's
+/// discriminant.
+///
+/// `discriminants` are identifiers of vars defined by `discriminant_stmts`.
+pub(crate) fn tag_niche_const(
+	int: &Ident,
+	discriminants: &[Ident],
+	discriminant_stmts: &[TokenStream],
+) -> TokenStream {
+	quote! {
+		const NICHE: ::core::option::Option<::ser_raw::niche::Niche> = {
+			#(#discriminant_stmts)*
+			::ser_raw::niche::Niche::tag(
+				::core::mem::size_of::<#int>(),
+				&[#(#discriminants as i128),*],
+			)
+		};
+	}
+}
@@ -0,0 +1,162 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, DataEnum, Generics, Ident, Meta, NestedMeta, Type, Variant};
+
+const INT_TYPES: [&str; 12] = [
+	"u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// Parse `#[repr(...)]` attributes.
+/// Returns whether `C` repr is specified, and integer type if specified.
+pub(crate) fn get_repr(attrs: &[Attribute]) -> (bool, Option<Ident>) {
+	let mut is_c = false;
+	let mut int = None;
+	for attr in attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+		if let Ok(Meta::List(list)) = attr.parse_meta() {
+			for nested in list.nested {
+				if let NestedMeta::Meta(Meta::Path(path)) = nested {
+					if let Some(repr_ident) = path.get_ident() {
+						let repr = repr_ident.to_string();
+						if repr == "C" {
+							is_c = true;
+						} else if INT_TYPES.contains(&repr.as_str()) {
+							int = Some(repr_ident.clone());
+						}
+					}
+				}
+			}
+		}
+	}
+	(is_c, int)
+}
+
+/// Parse `#[repr(...)]` attributes of an enum with fields.
+/// Returns whether `C` repr is specified, and integer type.
+///
+/// Panics if no integer type is specified, as then the enum has no defined
+/// layout.
+pub(crate) fn get_repr_for_enum_with_fields(
+	attrs: &[Attribute],
+	trait_name: &str,
+) -> (bool, Ident) {
+	let (is_c, int) = get_repr(attrs);
+	let int = int.unwrap_or_else(|| {
+		panic!(
+			"`#[derive({trait_name})]` on an enum with fields requires an explicit discriminant type \
+			 e.g. `#[repr(u8)]` or `#[repr(C, u8)]`"
+		)
+	});
+	(is_c, int)
+}
+
+/// Get statements defining a `discriminant_<index>` var for each variant,
+/// of type `int`. Implicit discriminants are previous discriminant + 1.
+///
+/// Returns identifier of var for each variant, and the statements.
+pub(crate) fn get_discriminants(data: &DataEnum, int: &Ident) -> (Vec<Ident>, Vec<TokenStream>) {
+	let mut idents: Vec<Ident> = vec![];
+	let mut stmts = vec![];
+	for (index, variant) in data.variants.iter().enumerate() {
+		let discriminant = format_ident!("discriminant_{}", index);
+		let value = match (&variant.discriminant, idents.last()) {
+			(Some((_, expr)), _) => quote! { #expr },
+			(None, Some(prev)) => quote! { #prev + 1 },
+			(None, None) => quote! { 0 },
+		};
+		stmts.push(quote! { let #discriminant: #int = #value; });
+		idents.push(discriminant);
+	}
+	(idents, stmts)
+}
+
+/// `#[repr(C)]` struct with same layout as an enum variant with fields.
+///
+/// `#[repr(Int)]`: Struct has the tag as first field.
+/// `#[repr(C, Int)]`: Struct does not include the tag. It's located after the
+/// tag, at offset given by [`payload_offset_stmt`].
+///
+/// See RFC 2195.
+pub(crate) struct VariantStruct {
+	/// Identifier of struct
+	pub ident: Ident,
+	/// Definition of struct
+	pub def: TokenStream,
+	/// Names of struct's fields for the variant's fields.
+	/// Unnamed fields are named `_0`, `_1` etc.
+	pub field_names: Vec<Ident>,
+}
+
+impl VariantStruct {
+	pub(crate) fn new(
+		enum_ident: &Ident,
+		variant: &Variant,
+		generics: &Generics,
+		is_c: bool,
+		int: &Ident,
+	) -> Self {
+		let (_, type_generics, where_clause) = generics.split_for_impl();
+		let params = &generics.params;
+
+		let field_names = variant
+			.fields
+			.iter()
+			.enumerate()
+			.map(|(field_index, field)| {
+				match &field.ident {
+					Some(field_ident) => field_ident.clone(),
+					None => format_ident!("_{}", field_index),
+				}
+			})
+			.collect::<Vec<_>>();
+		let field_types = variant
+			.fields
+			.iter()
+			.map(|field| &field.ty)
+			.collect::<Vec<&Type>>();
+		let tag_field = if is_c {
+			quote! {}
+		} else {
+			quote! { __tag: #int, }
+		};
+
+		let ident = format_ident!("__{}{}", enum_ident, variant.ident);
+		let def = quote! {
+			#[repr(C)]
+			#[allow(dead_code, non_camel_case_types)]
+			struct #ident<#params> #where_clause {
+				#tag_field
+				#(#field_names: #field_types,)*
+				__marker: ::core::marker::PhantomData<fn() -> #enum_ident #type_generics>,
+			}
+		};
+
+		Self {
+			ident,
+			def,
+			field_names,
+		}
+	}
+}
+
+/// Get statement defining `payload_offset` var for a `#[repr(C, Int)]` enum.
+/// Payload is a union of the variant structs, after the tag.
+pub(crate) fn payload_offset_stmt(
+	variant_structs: &[VariantStruct],
+	int: &Ident,
+	generics: &Generics,
+) -> TokenStream {
+	let (_, type_generics, _) = generics.split_for_impl();
+	let struct_idents = variant_structs
+		.iter()
+		.map(|variant_struct| &variant_struct.ident);
+	quote! {
+		let mut payload_align = 1;
+		#(
+			let align = ::core::mem::align_of::<#struct_idents #type_generics>();
+			if align > payload_align {
+				payload_align = align;
+			}
+		)*
+		let payload_offset = (::core::mem::size_of::<#int>() + payload_align - 1) / payload_align * payload_align;
+	}
+}
@@ -1,78 +1,137 @@
 use proc_macro2::TokenStream;
-use quote::{quote, quote_spanned};
+use quote::quote;
 use syn::{
-	spanned::Spanned, DataStruct, Field, Fields, FieldsNamed, FieldsUnnamed, Generics, Ident, Index,
-	Meta, MetaList, NestedMeta, Path,
+	spanned::Spanned, Attribute, DataStruct, Field, Fields, FieldsNamed, FieldsUnnamed, Generics,
+	Ident, Index, Lit, Meta, MetaList, MetaNameValue, NestedMeta, Path,
 };
 
-pub fn derive_struct(
+use crate::{DeriveTrait, FingerprintGroup, PortableField};
+
+pub(crate) fn derive_struct(
 	data: DataStruct,
 	ident: Ident,
 	generics: Generics,
 	generics_for_impl: Generics,
+	derive_trait: DeriveTrait,
 ) -> TokenStream {
+	let fingerprint = derive_trait.fingerprint_const(
+		&ident,
+		vec![FingerprintGroup {
+			variant_name: None,
+			fields: get_fingerprint_fields(&data.fields),
+		}],
+	);
+
+	let niche = derive_trait.niche_const(&data.fields);
+
+	let portable_fields = get_portable_fields(&data.fields, |member| quote! { &self.#member });
+	let portable = derive_trait.portable_items(
+		PortableField::total_size(&portable_fields),
+		PortableField::stmts(&portable_fields),
+	);
+
 	let field_stmts: Vec<TokenStream> = match data.fields {
-		Fields::Named(fields) => get_named_field_stmts(fields),
-		Fields::Unnamed(fields) => get_unnamed_field_stmts(fields),
+		Fields::Named(fields) => get_named_field_stmts(fields, derive_trait),
+		Fields::Unnamed(fields) => get_unnamed_field_stmts(fields, derive_trait),
 		Fields::Unit => vec![],
 	};
 
-	let (impl_generics, _, _) = generics_for_impl.split_for_impl();
-	let (_, type_generics, where_clause) = generics.split_for_impl();
-
-	quote! {
-		#[automatically_derived]
-		impl #impl_generics ::ser_raw::Serialize<__S> for #ident #type_generics #where_clause {
-			fn serialize_data(&self, serializer: &mut __S) {
-				#(#field_stmts)*
-			}
-		}
-	}
+	derive_trait.impl_block(
+		&ident,
+		&generics,
+		&generics_for_impl,
+		quote! {
+			#fingerprint
+			#niche
+			#portable
+		},
+		quote! { #(#field_stmts)* },
+	)
 }
 
-fn get_named_field_stmts(fields: FieldsNamed) -> Vec<TokenStream> {
+fn get_named_field_stmts(fields: FieldsNamed, derive_trait: DeriveTrait) -> Vec<TokenStream> {
 	fields
 		.named
 		.iter()
 		.map(|field| {
 			let field_name = field.ident.as_ref().expect("Missing field name");
-			get_field_stmt(quote! {#field_name}, field)
+			get_field_stmt(quote! {#field_name}, field, derive_trait)
 		})
 		.collect()
 }
 
-fn get_unnamed_field_stmts(fields: FieldsUnnamed) -> Vec<TokenStream> {
+fn get_unnamed_field_stmts(fields: FieldsUnnamed, derive_trait: DeriveTrait) -> Vec<TokenStream> {
 	fields
 		.unnamed
 		.iter()
 		.enumerate()
 		.map(|(index, field)| {
 			let index = Index::from(index);
-			get_field_stmt(quote! {#index}, field)
+			get_field_stmt(quote! {#index}, field, derive_trait)
 		})
 		.collect()
 }
 
-fn get_field_stmt(field_name: TokenStream, field: &Field) -> TokenStream {
-	match get_with(field) {
-		Some(with) => {
-			quote_spanned! {field.span()=>
-				<#with as ::ser_raw::SerializeWith::<_, __S>>::serialize_data_with(
-					&self.#field_name, serializer
-				);
-			}
-		}
-		None => {
-			quote_spanned! {field.span()=>
-				::ser_raw::Serialize::<__S>::serialize_data(
-					&self.#field_name, serializer
-				);
+fn get_field_stmt(
+	field_name: TokenStream,
+	field: &Field,
+	derive_trait: DeriveTrait,
+) -> TokenStream {
+	let value = match derive_trait {
+		DeriveTrait::Serialize => quote! { &self.#field_name },
+		DeriveTrait::Deserialize => quote! { &mut self.#field_name },
+		DeriveTrait::Validate => quote! { ::core::ptr::addr_of!((*ptr).#field_name) },
+	};
+	derive_trait.field_stmt(value, field)
+}
+
+/// Get fields for portable serialization.
+/// `value` gets expression for reference to field's value from field's name
+/// or index.
+///
+/// Skipped fields are not included in portable output.
+pub(crate) fn get_portable_fields(
+	fields: &Fields,
+	value: impl Fn(TokenStream) -> TokenStream,
+) -> Vec<PortableField> {
+	fields
+		.iter()
+		.enumerate()
+		.filter(|(_, field)| get_skip(field).is_none())
+		.map(|(index, field)| {
+			let member = match &field.ident {
+				Some(ident) => quote! {#ident},
+				None => {
+					let index = Index::from(index);
+					quote! {#index}
+				}
+			};
+			PortableField {
+				value: value(member),
+				ty: field.ty.clone(),
+				with: get_with(field),
+				span: field.span(),
 			}
-		}
-	}
+		})
+		.collect()
 }
 
-fn get_with(field: &Field) -> Option<Path> {
+/// Get names and fingerprint expressions for fields.
+pub(crate) fn get_fingerprint_fields(fields: &Fields) -> Vec<(String, TokenStream)> {
+	fields
+		.iter()
+		.enumerate()
+		.map(|(index, field)| {
+			let name = match &field.ident {
+				Some(ident) => ident.to_string(),
+				None => index.to_string(),
+			};
+			(name, FingerprintGroup::field_fingerprint(field))
+		})
+		.collect()
+}
+
+pub(crate) fn get_with(field: &Field) -> Option<Path> {
 	let attrs = field
 		.attrs
 		.iter()
@@ -81,7 +140,7 @@ fn get_with(field: &Field) -> Option<Path> {
 		.filter(|attr| attr.path().is_ident("ser_with"))
 		.collect::<Vec<_>>();
 
-	if attrs.len() == 0 {
+	if attrs.is_empty() {
 		return None;
 	}
 
@@ -89,11 +148,11 @@ fn get_with(field: &Field) -> Option<Path> {
 		panic!("Cannot have more than 1 `#[ser_with]` attribute on a field");
 	}
 
-	let attr = attrs.into_iter().nth(0).unwrap();
+	let attr = attrs.into_iter().next().unwrap();
 	if let Meta::List(MetaList { nested, .. }) = attr {
 		let parts: Vec<NestedMeta> = nested.into_iter().collect();
 		if parts.len() == 1 {
-			let first = parts.into_iter().nth(0).unwrap();
+			let first = parts.into_iter().next().unwrap();
 			if let NestedMeta::Meta(Meta::Path(with)) = first {
 				return Some(with);
 			}
@@ -101,3 +160,77 @@ fn get_with(field: &Field) -> Option<Path> {
 	}
 	panic!("`#[ser_with]` needs a path e.g. `#[ser_with(ForeignTypeProxy)]`");
 }
+
+/// How a field is skipped, specified by `#[ser_skip]`, `#[ser_default]` or
+/// `#[ser_replace = "expr"]` attribute.
+pub(crate) enum Skip {
+	/// Field's data is not serialized. Field is left in output as it is.
+	Skip,
+	/// Field's data is not serialized, and field is replaced with value of the
+	/// expression.
+	Replace(TokenStream),
+}
+
+pub(crate) fn get_skip(field: &Field) -> Option<Skip> {
+	let attrs = field
+		.attrs
+		.iter()
+		.filter(|attr| {
+			attr.path.is_ident("ser_skip")
+				|| attr.path.is_ident("ser_default")
+				|| attr.path.is_ident("ser_replace")
+		})
+		.collect::<Vec<_>>();
+
+	if attrs.is_empty() {
+		return None;
+	}
+
+	if attrs.len() != 1 {
+		panic!(
+			"Cannot have more than 1 `#[ser_skip]`, `#[ser_default]` or `#[ser_replace]` attribute on a \
+			 field"
+		);
+	}
+
+	if get_with(field).is_some() {
+		panic!(
+			"Cannot use `#[ser_with]` on a field with `#[ser_skip]`, `#[ser_default]` or \
+			 `#[ser_replace]`"
+		);
+	}
+
+	let attr = attrs[0];
+	let meta = attr.parse_meta();
+	if attr.path.is_ident("ser_replace") {
+		if let Ok(Meta::NameValue(MetaNameValue {
+			lit: Lit::Str(expr),
+			..
+		})) = meta
+		{
+			if let Ok(expr) = expr.value().parse::<TokenStream>() {
+				return Some(Skip::Replace(quote! { (#expr) }));
+			}
+		}
+		panic!("`#[ser_replace]` needs an expression e.g. `#[ser_replace = \"None\"]`");
+	}
+
+	if !matches!(meta, Ok(Meta::Path(_))) {
+		panic!("`#[ser_skip]` and `#[ser_default]` attributes take no arguments");
+	}
+	if attr.path.is_ident("ser_default") {
+		Some(Skip::Replace(
+			quote! { ::core::default::Default::default() },
+		))
+	} else {
+		Some(Skip::Skip)
+	}
+}
+
+/// Panic if `#[ser_with]` attribute is present somewhere other than on a field.
+/// `position` describes where the attributes are from e.g. "an enum variant".
+pub(crate) fn check_no_with(attrs: &[Attribute], position: &str) {
+	if attrs.iter().any(|attr| attr.path.is_ident("ser_with")) {
+		panic!("`#[ser_with]` can only be used on fields, not on {position}");
+	}
+}
@@ -0,0 +1,172 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, DataEnum, Fields, Generics, Ident};
+
+use crate::{
+	niche::{fieldless_enum_niche_const, tag_niche_const},
+	repr::{get_discriminants, get_repr_for_enum_with_fields, payload_offset_stmt, VariantStruct},
+	structs::check_no_with,
+	DeriveTrait,
+};
+
+/// Derive `Validate` for an enum.
+///
+/// Fieldless enums are validated by comparing their bytes against each variant.
+///
+/// Enums with fields require `#[repr(Int)]` or `#[repr(C, Int)]`, which have
+/// a defined layout (see RFC 2195). For each variant with fields, we define a
+/// `#[repr(C)]` struct with the same layout as the variant, and validate the
+/// fields of that struct.
+pub(crate) fn derive_validate_enum(
+	data: DataEnum,
+	ident: Ident,
+	generics: Generics,
+	attrs: &[Attribute],
+) -> TokenStream {
+	for variant in &data.variants {
+		check_no_with(&variant.attrs, "an enum variant");
+	}
+
+	let is_fieldless = data
+		.variants
+		.iter()
+		.all(|variant| variant.fields.is_empty());
+	let (variant_structs, niche, body) = if is_fieldless {
+		(
+			vec![],
+			fieldless_enum_niche_const(&data),
+			get_fieldless_body(&data),
+		)
+	} else {
+		get_body_with_fields(&data, &ident, &generics, attrs)
+	};
+
+	let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+	quote! {
+		const _: () = {
+			#(#variant_structs)*
+
+			#[automatically_derived]
+			unsafe impl #impl_generics ::ser_raw::Validate for #ident #type_generics #where_clause {
+				#niche
+
+				#[allow(unused_variables)]
+				unsafe fn validate(
+					ptr: *const Self,
+					validator: &mut ::ser_raw::Validator
+				) -> ::core::result::Result<(), ::ser_raw::ValidateError> {
+					#body
+				}
+			}
+		};
+	}
+}
+
+fn get_fieldless_body(data: &DataEnum) -> TokenStream {
+	let checks = data
+		.variants
+		.iter()
+		.map(|variant| {
+			let variant_ident = &variant.ident;
+			let value = match &variant.fields {
+				Fields::Unit => quote! { Self::#variant_ident },
+				Fields::Unnamed(_) => quote! { Self::#variant_ident() },
+				Fields::Named(_) => quote! { Self::#variant_ident {} },
+			};
+			quote! {
+				let variant = ::core::mem::ManuallyDrop::new(#value);
+				let variant_bytes = ::core::slice::from_raw_parts(
+					&*variant as *const Self as *const u8, ::core::mem::size_of::<Self>()
+				);
+				if bytes == variant_bytes {
+					return ::core::result::Result::Ok(());
+				}
+			}
+		})
+		.collect::<Vec<_>>();
+
+	quote! {
+		let bytes = ::core::slice::from_raw_parts(ptr as *const u8, ::core::mem::size_of::<Self>());
+		#(#checks)*
+		::core::result::Result::Err(::ser_raw::ValidateError::InvalidDiscriminant)
+	}
+}
+
+fn get_body_with_fields(
+	data: &DataEnum,
+	ident: &Ident,
+	generics: &Generics,
+	attrs: &[Attribute],
+) -> (Vec<TokenStream>, TokenStream, TokenStream) {
+	let (is_c, int) = get_repr_for_enum_with_fields(attrs, "Validate");
+	let (discriminants, discriminant_stmts) = get_discriminants(data, &int);
+	let (_, type_generics, _) = generics.split_for_impl();
+
+	let mut variant_structs = vec![];
+	let mut checks = vec![];
+	for (variant, discriminant) in data.variants.iter().zip(&discriminants) {
+		if variant.fields.is_empty() {
+			checks.push(quote! {
+				if tag == #discriminant {
+					return ::core::result::Result::Ok(());
+				}
+			});
+			continue;
+		}
+
+		let variant_struct = VariantStruct::new(ident, variant, generics, is_c, &int);
+		let struct_ident = &variant_struct.ident;
+		let field_stmts =
+			variant
+				.fields
+				.iter()
+				.zip(&variant_struct.field_names)
+				.map(|(field, field_name)| {
+					DeriveTrait::Validate.field_stmt(
+						quote! { ::core::ptr::addr_of!((*variant_ptr).#field_name) },
+						field,
+					)
+				});
+
+		let variant_ptr = if is_c {
+			quote! {
+				(ptr as *const u8).add(payload_offset) as *const #struct_ident #type_generics
+			}
+		} else {
+			quote! { ptr as *const #struct_ident #type_generics }
+		};
+
+		checks.push(quote! {
+			if tag == #discriminant {
+				let variant_ptr = #variant_ptr;
+				#(#field_stmts)*
+				return ::core::result::Result::Ok(());
+			}
+		});
+
+		variant_structs.push(variant_struct);
+	}
+
+	let payload_offset = if is_c {
+		payload_offset_stmt(&variant_structs, &int, generics)
+	} else {
+		quote! {}
+	};
+
+	let niche = tag_niche_const(&int, &discriminants, &discriminant_stmts);
+
+	let body = quote! {
+		let tag = *(ptr as *const #int);
+		#(#discriminant_stmts)*
+		#payload_offset
+		#(#checks)*
+		::core::result::Result::Err(::ser_raw::ValidateError::InvalidDiscriminant)
+	};
+
+	let variant_struct_defs = variant_structs
+		.into_iter()
+		.map(|variant_struct| variant_struct.def)
+		.collect();
+	(variant_struct_defs, niche, body)
+}
@@ -1,8 +1,8 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{
-	Attribute, Data, DataStruct, DeriveInput, Field, Fields, FieldsNamed, Ident, Meta, MetaList,
-	NestedMeta, Type,
+	Attribute, Data, DataStruct, DeriveInput, Expr, Field, Fields, FieldsNamed, Ident, Meta,
+	MetaList, NestedMeta, Type,
 };
 
 pub enum SerializerType {
@@ -10,6 +10,7 @@ pub enum SerializerType {
 	PosTracking,
 	PtrOffset,
 	Complete,
+	Portable,
 }
 
 /// Get type of serializer to be implemented from `#[ser_type]` attribute
@@ -19,25 +20,18 @@ pub fn get_ser_type(input: &DeriveInput) -> SerializerType {
 		.iter()
 		.filter(|attr| attr.path.is_ident("ser_type"))
 		.collect::<Vec<_>>();
-	if attrs.len() == 0 {
+	if attrs.is_empty() {
 		panic!("`#[ser_type]` attribute is required");
 	} else if attrs.len() > 1 {
 		panic!("Found more than 1 `#[ser_type]` attribute");
 	}
 
 	let ser_type = match attrs[0].parse_meta() {
-		Ok(meta) => {
-			match meta {
-				Meta::List(MetaList { nested, .. }) => {
-					match nested.len() {
-						1 => {
-							match &nested[0] {
-								NestedMeta::Meta(Meta::Path(path)) => {
-									path.get_ident().map(|ident| ident.to_string())
-								}
-								_ => None,
-							}
-						}
+		Ok(Meta::List(MetaList { nested, .. })) => {
+			match nested.len() {
+				1 => {
+					match &nested[0] {
+						NestedMeta::Meta(Meta::Path(path)) => path.get_ident().map(|ident| ident.to_string()),
 						_ => None,
 					}
 				}
@@ -53,10 +47,11 @@ pub fn get_ser_type(input: &DeriveInput) -> SerializerType {
 		"pos_tracking" => SerializerType::PosTracking,
 		"ptr_offset" => SerializerType::PtrOffset,
 		"complete" => SerializerType::Complete,
+		"portable" => SerializerType::Portable,
 		_ => {
 			panic!(
 				"Unrecognised `#[ser_type]` type. Valid options are 'pure_copy', 'pos_tracking', \
-				 'ptr_offset', 'complete'"
+				 'ptr_offset', 'complete', 'portable'"
 			);
 		}
 	}
@@ -68,12 +63,7 @@ pub fn get_ser_type(input: &DeriveInput) -> SerializerType {
 /// When macros are used within `ser_raw`'s own codebase, the structs are tagged
 /// `#[__local]`, and then a `crate` namespace is used instead.
 pub fn get_namespace(input: &DeriveInput) -> TokenStream {
-	let is_local = input
-		.attrs
-		.iter()
-		.filter(|attr| attr.path.is_ident("__local"))
-		.next()
-		.is_some();
+	let is_local = input.attrs.iter().any(|attr| attr.path.is_ident("__local"));
 	match is_local {
 		true => quote! {crate},
 		false => quote! {::ser_raw},
@@ -99,36 +89,58 @@ pub fn get_fields(input: &DeriveInput) -> Vec<Field> {
 /// Get struct's field tagged with `#[<tag>]`.
 /// Panics if no tag found, or more than one tag found.
 /// Returns the field name, field type, and attribute.
-pub fn get_tagged_field(fields: &Vec<Field>, tag: &str) -> (Ident, Type, Attribute) {
+pub fn get_tagged_field(fields: &[Field], tag: &str) -> (Ident, Type, Attribute) {
+	get_optional_tagged_field(fields, tag)
+		.unwrap_or_else(|| panic!("One of struct's fields must have a `#[{tag}]` attribute"))
+}
+
+/// Get struct's field tagged with `#[<tag>]`, if there is one.
+/// Panics if more than one tag found.
+/// Returns the field name, field type, and attribute.
+pub fn get_optional_tagged_field(fields: &[Field], tag: &str) -> Option<(Ident, Type, Attribute)> {
 	let filtered_fields = fields
-		.into_iter()
+		.iter()
 		.filter_map(|field| {
 			let attrs = field
 				.attrs
 				.iter()
 				.filter(|attr| attr.path.is_ident(tag))
 				.collect::<Vec<_>>();
-			if attrs.len() == 0 {
+			if attrs.is_empty() {
 				None
 			} else if attrs.len() > 1 {
-				panic!("Only 1 `#[{}]` attribute can be used on a field", tag);
+				panic!("Only 1 `#[{tag}]` attribute can be used on a field");
 			} else {
-				Some((field, attrs.into_iter().nth(0).unwrap()))
+				Some((field, attrs.into_iter().next().unwrap()))
 			}
 		})
 		.collect::<Vec<_>>();
 
-	if filtered_fields.len() == 0 {
-		panic!("One of struct's fields must have a `#[{}]` attribute", tag);
-	} else if filtered_fields.len() > 1 {
-		panic!(
-			"Only one of struct's fields can have a `#[{}]` attribute",
-			tag
-		);
+	if filtered_fields.len() > 1 {
+		panic!("Only one of struct's fields can have a `#[{tag}]` attribute");
 	}
 
-	let (field, attr) = filtered_fields.into_iter().nth(0).unwrap();
+	let (field, attr) = filtered_fields.into_iter().next()?;
 	let field_name = field.ident.clone().unwrap();
 
-	(field_name, field.ty.clone(), attr.clone())
+	Some((field_name, field.ty.clone(), attr.clone()))
+}
+
+/// Get size of offsets from `#[ser_offset_size(...)]` attribute, if present.
+pub fn get_offset_size(input: &DeriveInput) -> Option<TokenStream> {
+	let attrs = input
+		.attrs
+		.iter()
+		.filter(|attr| attr.path.is_ident("ser_offset_size"))
+		.collect::<Vec<_>>();
+	match attrs.len() {
+		0 => None,
+		1 => {
+			let size = attrs[0]
+				.parse_args::<Expr>()
+				.expect("`ser_offset_size` attribute must contain size e.g. `#[ser_offset_size(4)]`");
+			Some(quote! {#size})
+		}
+		_ => panic!("Found more than 1 `#[ser_offset_size]` attribute"),
+	}
 }
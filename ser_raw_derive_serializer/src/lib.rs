@@ -3,10 +3,14 @@ use quote::quote;
 use syn::{parse2, parse_macro_input, DeriveInput, Field, Ident, Type};
 
 pub(crate) mod common;
-use common::{get_fields, get_namespace, get_ser_type, get_tagged_field, SerializerType};
+use common::{
+	get_fields, get_namespace, get_optional_tagged_field, get_ser_type, get_tagged_field,
+	SerializerType,
+};
 mod ser_types;
 use ser_types::{
-	get_complete_ser_impl, get_pos_tracking_ser_impl, get_ptr_offset_ser_impl, get_pure_copy_ser_impl,
+	get_complete_ser_impl, get_portable_ser_impl, get_pos_tracking_ser_impl, get_ptr_offset_ser_impl,
+	get_pure_copy_ser_impl,
 };
 
 /// Derive macro for [`ser_raw::Serializer`]. See [`Serializer`] documentation
@@ -18,7 +22,15 @@ use ser_types::{
 /// [`Serializer`]: https://docs.rs/ser_raw/latest/ser_raw/trait.Serializer.html
 #[proc_macro_derive(
 	Serializer,
-	attributes(ser_type, ser_storage, ser_pos_mapping, ser_ptrs, __local)
+	attributes(
+		ser_type,
+		ser_storage,
+		ser_pos_mapping,
+		ser_ptrs,
+		ser_shared,
+		ser_offset_size,
+		__local
+	)
 )]
 pub fn serializer(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 	let input = parse_macro_input!(input as DeriveInput);
@@ -33,12 +45,24 @@ fn serializer_impl(input: DeriveInput) -> TokenStream {
 	let fields = get_fields(&input);
 	let (storage_field_name, storage_type, borrowed_storage_type) = get_storage_field(&fields);
 
+	// Implement `shared_allocs` method if there's a `#[ser_shared]` field
+	let shared_allocs_method =
+		get_optional_tagged_field(&fields, "ser_shared").map(|(field_name, ..)| {
+			quote! {
+				#[inline]
+				fn shared_allocs(&mut self) -> Option<&mut _ser_raw::pos::SharedAllocs> {
+					Some(&mut self.#field_name)
+				}
+			}
+		});
+
 	// Get extra methods, associated types and impls depending on serializer type
 	let (methods_and_types, impls) = match ser_type {
 		SerializerType::PureCopy => get_pure_copy_ser_impl(),
 		SerializerType::PosTracking => get_pos_tracking_ser_impl(&input, &fields),
 		SerializerType::PtrOffset => get_ptr_offset_ser_impl(&input, &fields),
 		SerializerType::Complete => get_complete_ser_impl(&input, &fields),
+		SerializerType::Portable => get_portable_ser_impl(&input),
 	};
 
 	// Implement `Serializer`
@@ -63,6 +87,8 @@ fn serializer_impl(input: DeriveInput) -> TokenStream {
 
 				#methods_and_types
 
+				#shared_allocs_method
+
 				#[inline]
 				fn storage(&self) -> &#storage_type {
 					use ::std::borrow::Borrow;
@@ -86,7 +112,7 @@ fn serializer_impl(input: DeriveInput) -> TokenStream {
 	}
 }
 
-fn get_storage_field(fields: &Vec<Field>) -> (Ident, Type, Type) {
+fn get_storage_field(fields: &[Field]) -> (Ident, Type, Type) {
 	let (field_name, borrowed_ty, attr) = get_tagged_field(fields, "ser_storage");
 
 	let ty = parse2::<Type>(attr.tokens).expect(
@@ -1,14 +1,11 @@
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{DeriveInput, Field};
+use syn::{parse_quote, DeriveInput, Field};
 
 use super::pos_tracking::impl_pos_tracking;
-use crate::common::get_tagged_field;
+use crate::common::get_optional_tagged_field;
 
-pub fn get_complete_ser_impl(
-	input: &DeriveInput,
-	fields: &Vec<Field>,
-) -> (TokenStream, TokenStream) {
+pub fn get_complete_ser_impl(input: &DeriveInput, fields: &[Field]) -> (TokenStream, TokenStream) {
 	(get_methods(), get_impls(input, fields))
 }
 
@@ -17,6 +14,8 @@ fn get_methods() -> TokenStream {
 		// Pointer-writing serializers need a functional `Addr`
 		type Addr = _ser_raw::pos::TrackingAddr;
 
+		const KIND: _ser_raw::header::SerializerKind = _ser_raw::header::SerializerKind::Complete;
+
 		fn serialize_value<T: _ser_raw::Serialize<Self>>(&mut self, value: &T) -> usize {
 			// Delegate to `PosTracking` trait's implementation
 			ser_traits::PosTracking::do_serialize_value(self, value)
@@ -39,6 +38,19 @@ fn get_methods() -> TokenStream {
 			ser_traits::PtrWriting::do_push_and_process_slice(self, slice, ptr_addr, process)
 		}
 
+		#[inline]
+		fn push_and_process_shared<T, P: FnOnce(&mut Self)>(
+			&mut self,
+			addr: usize,
+			value: &T,
+			ptr_addr: Self::Addr,
+			kind: _ser_raw::pos::RefKind,
+			process: P,
+		) -> usize {
+			// Delegate to `PtrWriting` trait's implementation
+			ser_traits::PtrWriting::do_push_and_process_shared(self, addr, value, ptr_addr, kind, process)
+		}
+
 		#[inline]
 		unsafe fn overwrite<T>(&mut self, addr: Self::Addr, value: &T) {
 			// Delegate to `Writable` trait's implementation
@@ -56,17 +68,85 @@ fn get_methods() -> TokenStream {
 			// Delegate to `Complete` trait's implementation
 			ser_traits::Complete::do_finalize(self)
 		}
+
+		#[inline]
+		fn finalize_in_place(&mut self) {
+			// Delegate to `Complete` trait's implementation
+			ser_traits::Complete::do_finalize_in_place(self);
+		}
+
+		#[inline]
+		fn reset(&mut self) {
+			// Delegate to `Complete` trait's implementation
+			ser_traits::Complete::do_reset(self);
+		}
+
+		#[inline]
+		fn checkpoint(&mut self) -> _ser_raw::pos::Checkpoint {
+			// Delegate to `Complete` trait's implementation
+			ser_traits::Complete::do_checkpoint(self)
+		}
+
+		#[inline]
+		fn rollback(
+			&mut self,
+			checkpoint: _ser_raw::pos::Checkpoint,
+		) -> ::std::result::Result<(), _ser_raw::SerializeError> {
+			// Delegate to `Complete` trait's implementation
+			ser_traits::Complete::do_rollback(self, checkpoint)
+		}
 	}
 }
 
-fn get_impls(input: &DeriveInput, fields: &Vec<Field>) -> TokenStream {
+fn get_impls(input: &DeriveInput, fields: &[Field]) -> TokenStream {
 	let pos_tracking_impl = impl_pos_tracking(input, fields);
 
-	let (ptrs, ..) = get_tagged_field(fields, "ser_ptrs");
-
 	let ser = &input.ident;
 	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
 
+	// If no `#[ser_ptrs]` field, pointers are not recorded. This is only valid if
+	// storage never moves, so bound `Complete` impl on storage being a
+	// `FixedStorage`.
+	let (ptrs_methods, complete_where_clause) = match get_optional_tagged_field(fields, "ser_ptrs") {
+		Some((ptrs, ..)) => {
+			(
+				quote! {
+					#[inline]
+					fn ptrs(&self) -> Option<&Ptrs> {
+						Some(&self.#ptrs)
+					}
+
+					#[inline]
+					fn ptrs_mut(&mut self) -> Option<&mut Ptrs> {
+						Some(&mut self.#ptrs)
+					}
+				},
+				quote! { #where_clause },
+			)
+		}
+		None => {
+			let mut generics = input.generics.clone();
+			generics.make_where_clause().predicates.push(parse_quote! {
+				<Self as Serializer>::Storage: _ser_raw::storage::FixedStorage
+			});
+			let where_clause = generics.where_clause;
+			(
+				quote! {
+					#[inline(always)]
+					fn ptrs(&self) -> Option<&Ptrs> {
+						None
+					}
+
+					#[inline(always)]
+					fn ptrs_mut(&mut self) -> Option<&mut Ptrs> {
+						None
+					}
+				},
+				quote! { #where_clause },
+			)
+		}
+	};
+
 	quote! {
 		#pos_tracking_impl
 
@@ -75,7 +155,7 @@ fn get_impls(input: &DeriveInput, fields: &Vec<Field>) -> TokenStream {
 			use ser_traits::{Complete, PtrWriting, Writable};
 
 			#[automatically_derived]
-			impl #impl_generics PtrWriting for #ser #type_generics #where_clause {
+			impl #impl_generics PtrWriting for #ser #type_generics #complete_where_clause {
 				#[inline]
 				unsafe fn overwrite_ptr(&mut self, ptr_pos: usize, target_pos: usize) {
 					// Delegate to `Complete` trait's implementation
@@ -87,16 +167,8 @@ fn get_impls(input: &DeriveInput, fields: &Vec<Field>) -> TokenStream {
 			impl #impl_generics Writable for #ser #type_generics #where_clause {}
 
 			#[automatically_derived]
-			impl #impl_generics Complete for #ser #type_generics #where_clause {
-				#[inline]
-				fn ptrs(&self) -> &Ptrs {
-					&self.#ptrs
-				}
-
-				#[inline]
-				fn ptrs_mut(&mut self) -> &mut Ptrs {
-					&mut self.#ptrs
-				}
+			impl #impl_generics Complete for #ser #type_generics #complete_where_clause {
+				#ptrs_methods
 			}
 		};
 	}
@@ -6,3 +6,5 @@ mod ptr_offset;
 pub use ptr_offset::get_ptr_offset_ser_impl;
 mod complete;
 pub use complete::get_complete_ser_impl;
+mod portable;
+pub use portable::get_portable_ser_impl;
@@ -0,0 +1,49 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+use crate::common::get_offset_size;
+
+pub fn get_portable_ser_impl(input: &DeriveInput) -> (TokenStream, TokenStream) {
+	(get_methods(input), get_impls(input))
+}
+
+fn get_methods(input: &DeriveInput) -> TokenStream {
+	let offset_size = get_offset_size(input).unwrap_or_else(|| quote! {8});
+
+	quote! {
+		// Portable serializers don't need a functional `Addr`
+		type Addr = _ser_raw::pos::NoopAddr;
+
+		const KIND: _ser_raw::header::SerializerKind = _ser_raw::header::SerializerKind::Portable;
+
+		const PORTABLE_OFFSET_SIZE: usize = #offset_size;
+
+		// Delegate all methods to `Portable` trait's implementation
+
+		#[inline]
+		fn serialize_value<T: _ser_raw::Serialize<Self>>(&mut self, value: &T) -> usize {
+			ser_traits::Portable::do_serialize_value(self, value)
+		}
+
+		#[inline]
+		fn write_portable(&mut self, pos: usize, bytes: &[u8]) {
+			ser_traits::Portable::do_write_portable(self, pos, bytes);
+		}
+
+		#[inline(always)]
+		fn value_size<T: _ser_raw::Serialize<Self>>() -> usize {
+			<Self as ser_traits::Portable>::do_value_size::<T>()
+		}
+	}
+}
+
+fn get_impls(input: &DeriveInput) -> TokenStream {
+	let ser = &input.ident;
+	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+	quote! {
+		#[automatically_derived]
+		impl #impl_generics ser_traits::Portable for #ser #type_generics #where_clause {}
+	}
+}
@@ -6,7 +6,7 @@ use crate::common::get_tagged_field;
 
 pub fn get_pos_tracking_ser_impl(
 	input: &DeriveInput,
-	fields: &Vec<Field>,
+	fields: &[Field],
 ) -> (TokenStream, TokenStream) {
 	(get_methods(), impl_pos_tracking(input, fields))
 }
@@ -37,11 +37,41 @@ fn get_methods() -> TokenStream {
 		) -> usize {
 			ser_traits::PosTracking::do_push_and_process_slice(self, slice, ptr_addr, process)
 		}
+
+		#[inline]
+		fn push_and_process_shared<T, P: FnOnce(&mut Self)>(
+			&mut self,
+			addr: usize,
+			value: &T,
+			ptr_addr: Self::Addr,
+			kind: _ser_raw::pos::RefKind,
+			process: P,
+		) -> usize {
+			ser_traits::PosTracking::do_push_and_process_shared(self, addr, value, ptr_addr, kind, process)
+		}
+
+		#[inline]
+		fn reset(&mut self) {
+			ser_traits::PosTracking::do_reset(self);
+		}
+
+		#[inline]
+		fn checkpoint(&mut self) -> _ser_raw::pos::Checkpoint {
+			ser_traits::PosTracking::do_checkpoint(self)
+		}
+
+		#[inline]
+		fn rollback(
+			&mut self,
+			checkpoint: _ser_raw::pos::Checkpoint,
+		) -> ::std::result::Result<(), _ser_raw::SerializeError> {
+			ser_traits::PosTracking::do_rollback(self, checkpoint)
+		}
 	}
 }
 
 /// Implement `PosTracking` trait
-pub fn impl_pos_tracking(input: &DeriveInput, fields: &Vec<Field>) -> TokenStream {
+pub fn impl_pos_tracking(input: &DeriveInput, fields: &[Field]) -> TokenStream {
 	let (pos_mapping, ..) = get_tagged_field(fields, "ser_pos_mapping");
 
 	let ser = &input.ident;
@@ -3,10 +3,11 @@ use quote::quote;
 use syn::{DeriveInput, Field};
 
 use super::pos_tracking::impl_pos_tracking;
+use crate::common::get_offset_size;
 
 pub fn get_ptr_offset_ser_impl(
 	input: &DeriveInput,
-	fields: &Vec<Field>,
+	fields: &[Field],
 ) -> (TokenStream, TokenStream) {
 	(get_methods(), get_impls(input, fields))
 }
@@ -16,6 +17,8 @@ fn get_methods() -> TokenStream {
 		// Pointer-writing serializers need a functional `Addr`
 		type Addr = _ser_raw::pos::TrackingAddr;
 
+		const KIND: _ser_raw::header::SerializerKind = _ser_raw::header::SerializerKind::PtrOffset;
+
 		fn serialize_value<T: _ser_raw::Serialize<Self>>(&mut self, value: &T) -> usize {
 			// Delegate to `PosTracking` trait's implementation
 			ser_traits::PosTracking::do_serialize_value(self, value)
@@ -37,12 +40,57 @@ fn get_methods() -> TokenStream {
 			// Delegate to `PtrWriting` trait's implementation
 			ser_traits::PtrWriting::do_push_and_process_slice(self, slice, ptr_addr, process)
 		}
+
+		#[inline]
+		fn push_and_process_shared<T, P: FnOnce(&mut Self)>(
+			&mut self,
+			addr: usize,
+			value: &T,
+			ptr_addr: Self::Addr,
+			kind: _ser_raw::pos::RefKind,
+			process: P,
+		) -> usize {
+			// Delegate to `PtrWriting` trait's implementation
+			ser_traits::PtrWriting::do_push_and_process_shared(self, addr, value, ptr_addr, kind, process)
+		}
+
+		#[inline]
+		unsafe fn overwrite<T>(&mut self, addr: Self::Addr, value: &T) {
+			// Delegate to `Writable` trait's implementation.
+			// `overwrite_with` is not implemented, so corrections are not applied,
+			// but values replacing skipped fields are.
+			ser_traits::Writable::do_overwrite(self, addr, value);
+		}
+
+		#[inline]
+		fn reset(&mut self) {
+			// Delegate to `PosTracking` trait's implementation
+			ser_traits::PosTracking::do_reset(self);
+		}
+
+		#[inline]
+		fn checkpoint(&mut self) -> _ser_raw::pos::Checkpoint {
+			// Delegate to `PosTracking` trait's implementation
+			ser_traits::PosTracking::do_checkpoint(self)
+		}
+
+		#[inline]
+		fn rollback(
+			&mut self,
+			checkpoint: _ser_raw::pos::Checkpoint,
+		) -> ::std::result::Result<(), _ser_raw::SerializeError> {
+			// Delegate to `PosTracking` trait's implementation
+			ser_traits::PosTracking::do_rollback(self, checkpoint)
+		}
 	}
 }
 
-fn get_impls(input: &DeriveInput, fields: &Vec<Field>) -> TokenStream {
+fn get_impls(input: &DeriveInput, fields: &[Field]) -> TokenStream {
 	let pos_tracking_impl = impl_pos_tracking(input, fields);
 
+	// Offset size from `#[ser_offset_size(...)]` attribute, or use default
+	let offset_size = get_offset_size(input).map(|size| quote! {const OFFSET_SIZE: usize = #size;});
+
 	let ser = &input.ident;
 	let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
 
@@ -50,7 +98,7 @@ fn get_impls(input: &DeriveInput, fields: &Vec<Field>) -> TokenStream {
 		#pos_tracking_impl
 
 		const _: () = {
-			use ser_traits::{PtrOffset, PtrWriting};
+			use ser_traits::{PtrOffset, PtrWriting, Writable};
 
 			#[automatically_derived]
 			impl #impl_generics PtrWriting for #ser #type_generics #where_clause {
@@ -70,7 +118,12 @@ fn get_impls(input: &DeriveInput, fields: &Vec<Field>) -> TokenStream {
 			}
 
 			#[automatically_derived]
-			impl #impl_generics PtrOffset for #ser #type_generics #where_clause {}
+			impl #impl_generics PtrOffset for #ser #type_generics #where_clause {
+				#offset_size
+			}
+
+			#[automatically_derived]
+			impl #impl_generics Writable for #ser #type_generics #where_clause {}
 		};
 	}
 }